@@ -1,18 +1,62 @@
+mod tui;
+
+use chrono::Datelike;
 use clap::{Parser, Subcommand};
-use crossterm::terminal;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, execute, queue, style, terminal};
+use directories::ProjectDirs;
 use fuzzypicker::FuzzyPicker;
-use piki_core::{DocumentStore, IndexPlugin, PluginRegistry, TodoPlugin, has_md_extension};
+use piki_core::attachment::{open_url, open_with_system_handler};
+use piki_core::dates::{self, Date};
+use piki_core::diff::{self, Change};
+use piki_core::diff_tool::DiffTool;
+use piki_core::edit_lock;
+use piki_core::edit_log::{self, Finding};
+use piki_core::export;
+use piki_core::footnotes;
+use piki_core::git;
+use piki_core::graph;
+use piki_core::images;
+use piki_core::import;
+use piki_core::json;
+use piki_core::link_check;
+use piki_core::link_index;
+use piki_core::listing;
+use piki_core::pdf;
+use piki_core::redact::RedactionRules;
+use piki_core::replace;
+use piki_core::script;
+use piki_core::section;
+use piki_core::settings_archive::{self, Outcome};
+use piki_core::spellcheck;
+use piki_core::stats::StatsPlugin;
+use piki_core::tags;
+use piki_core::template;
+use piki_core::timesheet::TimesheetPlugin;
+use piki_core::title;
+use piki_core::todo_cache;
+use piki_core::wikis;
+use piki_core::wrap;
+use piki_core::{
+    DocumentStore, IndexPlugin, PluginRegistry, RandomPlugin, TodoFilter, TodoPlugin,
+    ensure_md_extension, has_md_extension,
+};
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::env;
 use std::fs;
-use std::io::{self, Cursor, IsTerminal};
-use std::path::Path;
+use std::io::{self, Cursor, IsTerminal, Read, Write};
 use std::path::PathBuf;
+use std::path::{Component, Path};
 use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
 use tdoc::formatter::{Formatter, FormattingStyle};
-use tdoc::{Document, LinkPolicy, markdown, pager as tdoc_pager};
+use tdoc::{
+    ChecklistItem, Document, LinkPolicy, Paragraph, Span, html, markdown, pager as tdoc_pager,
+};
 use url::Url;
 
 #[derive(Parser, Debug)]
@@ -23,6 +67,19 @@ struct Args {
     #[arg(short = 'd', long = "directory", value_name = "DIRECTORY")]
     directory: Option<PathBuf>,
 
+    /// Wiki to use, by its name in the `[wikis]` section of ~/.pikirc
+    #[arg(
+        short = 'w',
+        long = "wiki",
+        value_name = "NAME",
+        conflicts_with = "directory"
+    )]
+    wiki: Option<String>,
+
+    /// Print JSON, for scripts and editors (ls, todo, log, search, backlinks)
+    #[arg(long, global = true)]
+    json: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 
@@ -32,13 +89,145 @@ struct Args {
 
 #[derive(Subcommand, Debug)]
 enum Commands {
+    /// List the lines of other notes linking to a note
+    Backlinks {
+        /// Name of the note
+        name: String,
+    },
+    /// Print a note's Markdown as it is, for piping into other tools
+    Cat {
+        /// Name of the note, or `!plugin` for a generated page
+        name: String,
+    },
+    /// Follow every link between notes and list those leading nowhere:
+    /// missing notes and files, headings that aren't there and links out of
+    /// the notes directory. Exits with an error if there are any
+    Check,
+    /// Export or import your settings, to set up piki on another machine
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Decrypt an encrypted note, storing it as plain Markdown again
+    Decrypt {
+        /// Name of the note
+        name: String,
+    },
+    /// Show the changes to a note since HEAD or another commit
+    Diff {
+        /// Name of the note
+        name: String,
+        /// Commit to compare with, HEAD if not given
+        #[arg(default_value = "HEAD")]
+        commit: String,
+        /// Compare in a diff tool: the one given (meld, kdiff3, code or a
+        /// command line), or `tool` in the `[diff]` section of ~/.pikirc
+        #[arg(long, value_name = "TOOL", num_args = 0..=1, default_missing_value = "")]
+        tool: Option<String>,
+    },
+    /// Check the notes directory, explain lost edits and find dead links
+    Doctor {
+        /// Start keeping a local log of edits (never committed or synced)
+        #[arg(long, conflicts_with = "disable_edit_log")]
+        enable_edit_log: bool,
+        /// Stop keeping the edit log and delete it
+        #[arg(long)]
+        disable_edit_log: bool,
+        /// Also check external links (http, https) and report dead ones
+        #[arg(long)]
+        check_external: bool,
+    },
     /// Edit a note
     Edit {
         /// Name of the note to edit
         name: Option<String>,
     },
+    /// Encrypt a note with a passphrase, storing it as NAME.md.age
+    Encrypt {
+        /// Name of the note
+        name: String,
+    },
+    /// Export a note to a file
+    Export {
+        /// Name of the note to export
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        name: Option<String>,
+        /// Export every note, keeping their folders
+        #[arg(long)]
+        all: bool,
+        /// Output format: `pdf`, `html`, `epub`, `txt` or `md`
+        #[arg(long, default_value = "pdf")]
+        format: String,
+        /// Hard-wrap Markdown and text output at this column (default: `wrap`
+        /// in the `[export]` section of ~/.pikirc)
+        #[arg(long, value_name = "COLUMN")]
+        wrap: Option<usize>,
+        /// File to write (default: the note's name with the format's extension)
+        #[arg(short = 'o', long = "output", value_name = "FILE")]
+        output: Option<PathBuf>,
+        /// Folder to write to (default: the current folder)
+        #[arg(long, value_name = "DIR")]
+        output_dir: Option<PathBuf>,
+        /// HTML page with `{{title}}` and `{{content}}` placeholders to put
+        /// HTML exports in (default: `html_template` in the `[export]`
+        /// section of ~/.pikirc)
+        #[arg(long, value_name = "FILE")]
+        html_template: Option<PathBuf>,
+        /// Append a "Linked from" section listing the notes linking here
+        /// (default: `backlinks` in the `[export]` section of ~/.pikirc)
+        #[arg(long)]
+        backlinks: bool,
+        /// Export private material, too: `%%private%%` regions, blocks tagged
+        /// `#private` and notes with the tags of the `[redact]` section of
+        /// ~/.pikirc are left out otherwise
+        #[arg(long)]
+        keep_private: bool,
+        /// Export encrypted notes, decrypted; they are skipped by `--all` and
+        /// refused by name otherwise
+        #[arg(long)]
+        include_encrypted: bool,
+    },
+    /// Print the graph of links between notes
+    Graph {
+        /// Output format: `dot` (Graphviz) or `json`
+        #[arg(long, default_value = "dot")]
+        format: String,
+        /// File to write (default: standard output)
+        #[arg(short = 'o', long = "output", value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// List images and other attachments with the notes linking to them
+    Images {
+        /// Scale down oversized images in place (originals are backed up)
+        #[arg(long)]
+        compress: bool,
+        /// Maximum width in pixels of compressed images
+        #[arg(long, value_name = "PIXELS", default_value_t = images::DEFAULT_MAX_WIDTH)]
+        max_width: u32,
+    },
+    /// Show the commits that changed a note
+    History {
+        /// Name of the note
+        name: String,
+        /// Number of commits to show
+        #[arg(short = 'n', default_value = "25")]
+        count: usize,
+    },
+    /// Import an Obsidian vault or a Notion export into the notes directory
+    Import {
+        /// The export: a folder or a zip file
+        path: PathBuf,
+        /// The wiki it comes from: `obsidian` or `notion`
+        #[arg(long)]
+        from: String,
+    },
     /// Generate an index of all notes
-    Index,
+    Index {
+        /// Order of the notes: grouped by `folder` (default), by `name` or
+        /// most recently changed first (`date`)
+        #[arg(long, value_parser = ["folder", "name", "date"])]
+        sort: Option<String>,
+    },
     /// Show the commit log
     Log {
         /// Number of commits to show
@@ -46,7 +235,76 @@ enum Commands {
         count: usize,
     },
     /// List all notes
-    Ls,
+    Ls {
+        /// Only list the notes matching this glob, e.g. `projects/*` or `plan*`
+        pattern: Option<String>,
+        /// Also show when each note was created and last modified, and its size
+        #[arg(long, short, conflicts_with = "tree")]
+        long: bool,
+        /// Order of the notes: by `name` (default), most recently modified
+        /// (`mtime`) or largest (`size`) first
+        #[arg(long, value_parser = ["name", "mtime", "size"])]
+        sort: Option<String>,
+        /// List the notes in the opposite order
+        #[arg(long, short)]
+        reverse: bool,
+        /// Show the notes in a tree of their folders
+        #[arg(long)]
+        tree: bool,
+    },
+    /// Create a note
+    #[command(visible_alias = "add")]
+    New {
+        /// Name of the note
+        name: String,
+        /// `-` to read the note from standard input, like --stdin
+        #[arg(value_parser = ["-"], hide_possible_values = true)]
+        source: Option<String>,
+        /// Start from a template in .piki/templates/
+        #[arg(long, short, conflicts_with_all = ["source", "stdin"])]
+        template: Option<String>,
+        /// Read the note's Markdown from standard input
+        #[arg(long)]
+        stdin: bool,
+        /// Open the new note in $EDITOR
+        #[arg(long, conflicts_with_all = ["source", "stdin"])]
+        edit: bool,
+    },
+    /// View a randomly picked note
+    Random {
+        /// Only pick from the notes in this folder
+        folder: Option<String>,
+        /// Only print the note's name
+        #[arg(long)]
+        name: bool,
+    },
+    /// Replace text in all notes
+    Replace {
+        /// Text to find
+        pattern: String,
+        /// Text to put in its place
+        replacement: String,
+        /// Take the pattern as a regular expression; `$1` in the replacement
+        /// stands for its first group
+        #[arg(long)]
+        regex: bool,
+        /// Only show the changes, without writing them
+        #[arg(long)]
+        dry_run: bool,
+        /// Ask before each replacement
+        #[arg(short, long)]
+        interactive: bool,
+    },
+    /// Put back a note's version from an earlier commit
+    Restore {
+        /// Name of the note
+        name: String,
+        /// Commit to take the note from, as listed by `piki history`
+        commit: String,
+        /// Replace the note even if it has uncommitted changes
+        #[arg(long)]
+        force: bool,
+    },
     /// Run a shell command inside the notes directory
     Run {
         /// Command to run
@@ -59,12 +317,103 @@ enum Commands {
         #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
         terms: Vec<String>,
     },
+    /// Print one section of a note: a heading and everything under it
+    Section {
+        /// Name of the note
+        name: String,
+        /// The section's heading, by its text or its `#anchor`
+        heading: String,
+        /// Print the section's Markdown instead of rendering it
+        #[arg(long)]
+        raw: bool,
+    },
+    /// Spellcheck a note, or all notes
+    Spellcheck {
+        /// Name of the note to check (default: all notes)
+        name: Option<String>,
+    },
+    /// Pull and push the notes' git repository
+    Sync {
+        /// Commit changed notes with this message first (default: `commit`
+        /// in the `[sync]` section of ~/.pikirc)
+        #[arg(short, long)]
+        message: Option<String>,
+    },
+    /// Work with tags across all notes
+    Tag {
+        #[command(subcommand)]
+        command: TagCommand,
+    },
     /// List all todos from all notes
-    Todo,
+    Todo {
+        /// Only list todos due by then: `overdue`, `today`, `this-week`,
+        /// `next-week`, `this-month` or a date
+        #[arg(long)]
+        due: Option<String>,
+        /// Only list open todos
+        #[arg(long, conflicts_with = "done")]
+        open: bool,
+        /// Only list done todos
+        #[arg(long)]
+        done: bool,
+        /// Only list the todos of the notes matching this glob, e.g. `projects/*`
+        #[arg(long)]
+        page: Option<String>,
+        /// Only list the todos carrying this tag, or in a note that does
+        #[arg(long)]
+        tag: Option<String>,
+        /// Go through the todos in the terminal, ticking them off
+        #[arg(long, short)]
+        interactive: bool,
+    },
+    /// Browse the notes in a terminal UI, with a list of them beside the page
+    Tui {
+        /// Name of the note to start at, the front page by default
+        name: Option<String>,
+    },
     /// View a note
     View {
-        /// Name of the note to view
+        /// Name of the note to view, optionally followed by `#heading` to
+        /// start at that heading
         name: Option<String>,
+        /// Output format: `ansi` (formatted for the terminal, paged on one),
+        /// `plain` (formatted text without colors), `html` (a standalone page)
+        /// or `json` (the parsed document)
+        #[arg(long, value_parser = ["ansi", "plain", "html", "json"])]
+        format: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Write ~/.pikirc, the GUI's appearance and the wiki's templates and
+    /// plugins into one file
+    Export {
+        /// File to write (default: standard output)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Install the settings from a file written by `piki config export`
+    Import {
+        /// The exported settings
+        file: PathBuf,
+        /// Replace settings that differ from the imported ones
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum TagCommand {
+    /// Rename a tag (and its subtags) in the text and frontmatter of every note
+    Rename {
+        /// Tag to rename, with or without the `#`
+        old: String,
+        /// New name of the tag
+        new: String,
+        /// Only show which notes would change
+        #[arg(long)]
+        dry_run: bool,
     },
 }
 
@@ -72,6 +421,93 @@ enum Commands {
 struct Config {
     #[serde(default)]
     aliases: HashMap<String, String>,
+    #[serde(default)]
+    export: ExportConfig,
+    #[serde(default)]
+    diff: DiffConfig,
+    #[serde(default)]
+    sync: SyncConfig,
+    /// Notes directories by name.
+    #[serde(default)]
+    wikis: BTreeMap<String, String>,
+    #[serde(default)]
+    links: LinksConfig,
+    #[serde(default)]
+    redact: RedactConfig,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct ExportConfig {
+    /// Column to hard-wrap Markdown exports at.
+    wrap: Option<usize>,
+    /// Whether exports end with a "Linked from" section.
+    #[serde(default)]
+    backlinks: bool,
+    /// HTML page to put HTML exports in.
+    html_template: Option<PathBuf>,
+}
+
+/// What `piki export` leaves out of the exported notes (see
+/// [`piki_core::redact`]).
+#[derive(Deserialize, Debug, Default)]
+struct RedactConfig {
+    /// Notes with any of these tags aren't exported at all.
+    #[serde(default)]
+    exclude_tags: Vec<String>,
+    /// Blocks with this tag are left out (default: `private`).
+    private_tag: Option<String>,
+}
+
+impl RedactConfig {
+    fn rules(&self) -> RedactionRules {
+        let defaults = RedactionRules::default();
+        RedactionRules {
+            exclude_tags: self.exclude_tags.clone(),
+            private_tag: self.private_tag.clone().unwrap_or(defaults.private_tag),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct LinksConfig {
+    /// Whether `piki view` asks before opening a web link (default: yes).
+    confirm_external: Option<bool>,
+}
+
+impl LinksConfig {
+    fn confirm_external(&self) -> bool {
+        self.confirm_external.unwrap_or(true)
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct SyncConfig {
+    /// Message to commit changed notes with before syncing.
+    commit: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct DiffConfig {
+    /// Diff tool: meld, kdiff3, code or a command line (see
+    /// `piki_core::diff_tool`).
+    tool: Option<String>,
+    /// Command line for merges, for tools given as a command line.
+    merge: Option<String>,
+}
+
+impl DiffConfig {
+    /// The tool named on the command line, or the configured one if `name`
+    /// is empty.
+    fn tool(&self, name: &str) -> Result<DiffTool, String> {
+        if !name.is_empty() && Some(name) != self.tool.as_deref() {
+            return DiffTool::new(name, None);
+        }
+        let tool = self.tool.as_deref().ok_or_else(|| {
+            "No diff tool configured: pass one to --tool or set `tool` in the [diff] section of ~/.pikirc"
+                .to_string()
+        })?;
+        DiffTool::new(tool, self.merge.as_deref())
+    }
 }
 
 impl Config {
@@ -109,17 +545,13 @@ fn get_editor() -> String {
         .unwrap_or_else(|_| "vim".to_string())
 }
 
-fn interactive_select(store: &DocumentStore) -> Result<Option<String>, String> {
-    let mut docs = store.list_all_documents()?;
-
+/// Pick a note from `docs` interactively.
+fn interactive_select(docs: &[String]) -> Result<Option<String>, String> {
     if docs.is_empty() {
         return Ok(None);
     }
 
-    // Sort alphabetically
-    docs.sort();
-
-    let mut picker = FuzzyPicker::new(&docs);
+    let mut picker = FuzzyPicker::new(docs);
     return match picker.pick() {
         Ok(res) => Ok(res),
         Err(e) => Err(format!("Failed to run fuzzy picker: {}", e)),
@@ -156,85 +588,261 @@ fn interactive_select(store: &DocumentStore) -> Result<Option<String>, String> {
 }
 
 fn cmd_edit(name: Option<String>, notes_dir: &PathBuf) -> Result<(), String> {
-    let store = DocumentStore::new(notes_dir.clone());
-
-    let note_name = if let Some(name) = name {
-        name
-    } else {
-        // Interactive selection
-        match interactive_select(&store)? {
+    let store = open_store(notes_dir);
+
+    // A name that isn't a note is a search: pick from the notes it matches,
+    // best first, or take it as the name of a new note, listed last. Without
+    // a name, pick from all notes, most recently changed first.
+    let note_name = match name {
+        Some(name) if store.path_for(&name).exists() || store.is_encrypted(&name) => name,
+        Some(name) => {
+            let mut ranked = piki_core::search::rank_notes(&store, &name)?;
+            if ranked.is_empty() {
+                name
+            } else {
+                ranked.push(name);
+                match interactive_select(&ranked)? {
+                    Some(name) => name,
+                    None => return Ok(()),
+                }
+            }
+        }
+        None => match interactive_select(&piki_core::search::rank_notes(&store, "")?)? {
             Some(name) => name,
             None => return Ok(()),
-        }
+        },
     };
 
     let doc = store.load(&note_name)?;
     let editor = get_editor();
 
+    let holders = edit_lock::holders(notes_dir, &note_name);
+    if !holders.is_empty() && !confirm_concurrent_edit(&note_name, &holders)? {
+        return Ok(());
+    }
+
     // Get the relative path from the notes directory
     let relative_path = doc.path.strip_prefix(notes_dir).unwrap_or(&doc.path);
+    // An encrypted note is edited in a decrypted copy only the user can read,
+    // which goes back into the note once the editor exits.
+    let decrypted_copy = if store.is_encrypted(&note_name) {
+        Some(private_copy(&note_name, &doc.content)?)
+    } else {
+        None
+    };
 
-    let status = Command::new(&editor)
-        .arg(relative_path)
-        .current_dir(notes_dir)
-        .status()
-        .map_err(|e| format!("Failed to open editor '{}': {}", editor, e))?;
+    // Tells the GUI to hold back saving this note until the editor exits. The
+    // claim is refreshed from a second thread while the editor runs.
+    let mut claim = edit_lock::claim(notes_dir, &note_name, edit_lock::TERMINAL).ok();
+    let (done, editor_done) = mpsc::channel::<()>();
+    let status = thread::scope(|scope| {
+        if let Some(claim) = claim.as_mut() {
+            scope.spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) =
+                    editor_done.recv_timeout(edit_lock::REFRESH_INTERVAL)
+                {
+                    let _ = claim.refresh(false);
+                }
+            });
+        }
+        let status = Command::new(&editor)
+            .arg(decrypted_copy.as_deref().unwrap_or(relative_path))
+            .current_dir(notes_dir)
+            .status();
+        drop(done);
+        status
+    });
+    drop(claim);
+
+    if let Some(copy) = decrypted_copy {
+        let edited = fs::read_to_string(&copy);
+        if let Some(dir) = copy.parent() {
+            let _ = fs::remove_dir_all(dir);
+        }
+        let edited = edited.map_err(|e| format!("Failed to read the edited note: {e}"))?;
+        if edited != doc.content {
+            store.save(&piki_core::Document {
+                content: edited,
+                ..doc.clone()
+            })?;
+        }
+    }
+    let status = status.map_err(|e| format!("Failed to open editor '{}': {}", editor, e))?;
 
     if !status.success() {
         return Err(format!("Editor exited with status: {}", status));
     }
 
+    // The editor wrote the file itself, bypassing the store.
+    if !store.is_encrypted(&note_name)
+        && let Ok(after) = fs::read_to_string(&doc.path)
+        && after != doc.content
+    {
+        let _ = edit_log::record(
+            notes_dir,
+            &note_name,
+            edit_log::Operation::Save {
+                bytes: after.len() as u64,
+            },
+        );
+    }
+
     Ok(())
 }
 
-fn cmd_view(name: Option<String>, notes_dir: &Path) -> Result<(), String> {
-    let notes_dir_buf = notes_dir.to_path_buf();
-    let canonical_notes_dir = normalize_base_path(notes_dir);
-    let store = Arc::new(DocumentStore::new(notes_dir_buf.clone()));
+/// A copy of the note `name` holding `content`, in a new folder only the
+/// user can read.
+fn private_copy(name: &str, content: &str) -> Result<PathBuf, String> {
+    let dir = env::temp_dir().join(format!("piki-{}", std::process::id()));
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder
+        .create(&dir)
+        .map_err(|e| format!("Failed to create '{}': {}", dir.display(), e))?;
+    let file = dir.join(ensure_md_extension(name.rsplit('/').next().unwrap_or(name)));
+    fs::write(&file, content)
+        .map_err(|e| format!("Failed to write '{}': {}", file.display(), e))?;
+    Ok(file)
+}
 
+/// Warn that `note` is already open elsewhere. Returns whether to edit it
+/// anyway, which is only asked when another copy has unsaved edits.
+fn confirm_concurrent_edit(note: &str, holders: &[edit_lock::Holder]) -> Result<bool, String> {
+    for holder in holders {
+        let program = if holder.program == edit_lock::GUI {
+            "the piki GUI"
+        } else {
+            holder.program.as_str()
+        };
+        eprintln!("“{note}” is also open in {program} (pid {}).", holder.pid);
+    }
+    if holders
+        .iter()
+        .any(|holder| holder.program == edit_lock::GUI)
+    {
+        eprintln!(
+            "The GUI won't save it while your editor is open, and reloads it or offers to merge afterwards."
+        );
+    }
+    if !holders.iter().any(|holder| holder.dirty) {
+        return Ok(true);
+    }
+
+    eprint!("It has unsaved edits there. Edit it here anyway? [y/N] ");
+    io::stderr().flush().map_err(|e| e.to_string())?;
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .map_err(|e| format!("Failed to read answer: {e}"))?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// The notes in `notes_dir`, asking for the passphrase of encrypted notes on
+/// the terminal.
+fn open_store(notes_dir: &Path) -> DocumentStore {
+    let mut store = DocumentStore::new(notes_dir.to_path_buf());
+    store.set_passphrase_prompt(|note| {
+        rpassword::prompt_password(format!("Passphrase for '{note}': "))
+            .ok()
+            .filter(|passphrase| !passphrase.is_empty())
+    });
+    store
+}
+
+fn plugin_registry(notes_dir: &Path) -> PluginRegistry {
     let mut plugin_registry = PluginRegistry::new();
     plugin_registry.register("index", Box::new(IndexPlugin));
     plugin_registry.register("todo", Box::new(TodoPlugin));
-    let plugin_registry = Arc::new(plugin_registry);
+    plugin_registry.register("random", Box::new(RandomPlugin));
+    plugin_registry.register("timesheet", Box::new(TimesheetPlugin));
+    plugin_registry.register("stats", Box::new(StatsPlugin));
+    script::register_scripts(&mut plugin_registry, notes_dir);
+    plugin_registry
+}
+
+/// Show the note `name`, picking one if none is given, in `format` (see
+/// [`Commands::View`]); paged if it goes to a terminal formatted for one.
+fn cmd_view(name: Option<String>, format: Option<&str>, notes_dir: &Path) -> Result<(), String> {
+    let notes_dir_buf = notes_dir.to_path_buf();
+    let canonical_notes_dir = normalize_base_path(notes_dir);
+    let store = Arc::new(open_store(notes_dir));
+
+    let plugin_registry = Arc::new(plugin_registry(notes_dir));
 
     let note_name = if let Some(name) = name {
         name
     } else {
         // Interactive selection
-        match interactive_select(store.as_ref())? {
+        match interactive_select(&piki_core::search::rank_notes(store.as_ref(), "")?)? {
             Some(name) => name,
             None => return Ok(()),
         }
     };
 
-    let initial_content = if let Some(plugin_name) = note_name.strip_prefix('!') {
+    let (note_name, heading) = match note_name.split_once('#') {
+        Some((name, heading)) if !heading.is_empty() => {
+            (name.to_string(), Some(heading.to_string()))
+        }
+        _ => (note_name, None),
+    };
+
+    let (initial_content, page_title) = if let Some(plugin_name) = note_name.strip_prefix('!') {
         let generated = plugin_registry
             .generate(plugin_name, store.as_ref())
             .map_err(|err| format!("Error generating plugin '{plugin_name}': {err}"))?;
-        let document = markdown::parse(Cursor::new(generated.into_bytes()))
+        let generated = from_heading(&generated, heading.as_deref());
+        let document = markdown::parse(Cursor::new(generated.as_bytes()))
             .map_err(|e| format!("Error parsing FTML: {}", e))?;
-        LoadedContent {
+        let content = LoadedContent {
             document,
             location: ContentLocation::Plugin,
-        }
+            heading,
+        };
+        (content, note_name.clone())
     } else {
         let doc = store.load(&note_name)?;
-        if doc.content.is_empty() {
+        if doc.content.is_empty() && matches!(format, None | Some("ansi")) {
             println!("(empty)");
             return Ok(());
         }
         let document_path = fs::canonicalize(&doc.path).unwrap_or_else(|_| doc.path.clone());
-        let document = markdown::parse(Cursor::new(doc.content.into_bytes()))
-            .map_err(|e| format!("Error parsing FTML: {}", e))?;
-        LoadedContent {
+        let document = parse_note(from_heading(&doc.content, heading.as_deref()))?;
+        let content = LoadedContent {
             document,
             location: ContentLocation::File(document_path),
-        }
+            heading,
+        };
+        (content, title::page_title(&note_name, &doc.content))
     };
 
+    match format {
+        Some("html") => {
+            let template = match Config::load().export.html_template {
+                Some(path) => fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?,
+                None => export::DEFAULT_HTML_TEMPLATE.to_string(),
+            };
+            let mut body = Vec::new();
+            html::write(&mut body, &initial_content.document)
+                .map_err(|err| format!("Error rendering HTML: {err}"))?;
+            let body = String::from_utf8_lossy(&body);
+            print!(
+                "{}",
+                export::fill_html_template(&template, &page_title, &body)
+            );
+            return Ok(());
+        }
+        Some("json") => {
+            println!("{}", document_to_json(&initial_content.document));
+            return Ok(());
+        }
+        _ => {}
+    }
+
     let stdout_is_tty = io::stdout().is_terminal();
-    let use_ansi = stdout_is_tty;
-    let use_pager = use_ansi;
+    let use_ansi = format == Some("ansi") || (format.is_none() && stdout_is_tty);
+    let use_pager = use_ansi && stdout_is_tty;
 
     if !use_pager {
         let mut formatter = if use_ansi {
@@ -251,17 +859,40 @@ fn cmd_view(name: Option<String>, notes_dir: &Path) -> Result<(), String> {
     }
 
     let shared_state = Arc::new(Mutex::new(LinkEnvironment {
+        modified_time: location_modified_time(&initial_content.location),
         document: initial_content.document.clone(),
         location: initial_content.location.clone(),
+        heading: initial_content.heading.clone(),
+        rendered: HashMap::new(),
     }));
 
-    let initial = render_document_for_terminal(&initial_content.document)?;
+    let initial = match terminal::size() {
+        Ok((width, _height)) => shared_state
+            .lock()
+            .map_err(|_| "Failed to access document".to_string())?
+            .render_for_width(width as usize)?,
+        Err(_) => render_document_for_terminal(&initial_content.document)?,
+    };
     let regen_state = shared_state.clone();
     let regenerator = move |new_width: u16, _new_height: u16| -> Result<String, String> {
-        let guard = regen_state
+        let mut guard = regen_state
             .lock()
             .map_err(|_| "Failed to access document for resize".to_string())?;
-        render_document_for_width(&guard.document, new_width as usize)
+        guard.reload_if_changed()?;
+        guard.render_for_width(new_width as usize)
+    };
+    // Polled by the pager while idle, so the page follows edits made to the
+    // viewed file elsewhere without waiting for a resize.
+    let watch_state = shared_state.clone();
+    let watcher = move |width: u16| -> Option<Result<String, String>> {
+        let Ok(mut guard) = watch_state.lock() else {
+            return Some(Err("Failed to access document".to_string()));
+        };
+        match guard.reload_if_changed() {
+            Ok(true) => Some(guard.render_for_width(width as usize)),
+            Ok(false) => None,
+            Err(err) => Some(Err(err)),
+        }
     };
 
     let link_policy = build_link_policy(
@@ -284,7 +915,34 @@ fn cmd_view(name: Option<String>, notes_dir: &Path) -> Result<(), String> {
         ..tdoc_pager::PagerOptions::default()
     };
 
-    tdoc_pager::page_output_with_options_and_regenerator(&initial, Some(regenerator), options)
+    tdoc_pager::page_output_with_options_regenerator_and_watcher(
+        &initial,
+        Some(regenerator),
+        Some(watcher),
+        options,
+    )
+}
+
+/// `content` from the line of `heading` on, for links to a section of a note.
+/// All of it if there is no such heading.
+fn from_heading<'a>(content: &'a str, heading: Option<&str>) -> &'a str {
+    let Some(line) = heading.and_then(|heading| section::heading_line(content, heading)) else {
+        return content;
+    };
+    let offset: usize = content
+        .split_inclusive('\n')
+        .take(line - 1)
+        .map(str::len)
+        .sum();
+    &content[offset..]
+}
+
+/// Parse a note for viewing, with footnote references as superscript numbers
+/// and the footnotes listed at the end.
+fn parse_note(content: &str) -> Result<Document, String> {
+    let content = footnotes::resolve(content, footnotes::superscript).to_markdown();
+    markdown::parse(Cursor::new(content.into_bytes()))
+        .map_err(|err| format!("Error parsing FTML: {}", err))
 }
 
 #[derive(Clone)]
@@ -296,16 +954,84 @@ enum ContentLocation {
 struct LoadedContent {
     document: Document,
     location: ContentLocation,
+    /// The heading the document starts at, for links to a section.
+    heading: Option<String>,
 }
 
 enum LinkTarget {
     File(PathBuf),
+    /// A non-markdown file (image, PDF, ...) inside the notes directory,
+    /// opened with the system handler instead of being paged.
+    Attachment(PathBuf),
     Plugin(String),
 }
 
 struct LinkEnvironment {
     document: Document,
     location: ContentLocation,
+    /// The heading `document` starts at, kept when reloading it.
+    heading: Option<String>,
+    /// Modification time of the viewed file when it was last parsed.
+    modified_time: Option<SystemTime>,
+    /// `document` rendered for the terminal widths seen so far. Wrapping a
+    /// page of long paragraphs is slow, and resizing a terminal back and forth
+    /// asks for the same few widths over and over.
+    rendered: HashMap<usize, String>,
+}
+
+/// How many widths [`LinkEnvironment::rendered`] keeps before starting over.
+const RENDERED_WIDTHS_KEPT: usize = 16;
+
+impl LinkEnvironment {
+    /// Re-parse the viewed file if it was changed on disk (e.g. edited in
+    /// another terminal) since it was loaded, so the next render shows the
+    /// current content. Returns whether it did.
+    fn reload_if_changed(&mut self) -> Result<bool, String> {
+        let ContentLocation::File(path) = &self.location else {
+            return Ok(false);
+        };
+        let modified_time = location_modified_time(&self.location);
+        if modified_time.is_none() || modified_time == self.modified_time {
+            return Ok(false);
+        }
+
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read {}: {}", path.display(), err))?;
+        self.document = parse_note(from_heading(&content, self.heading.as_deref()))?;
+        self.modified_time = modified_time;
+        self.rendered.clear();
+        Ok(true)
+    }
+
+    /// Show `loaded` instead of the current document.
+    fn replace(&mut self, loaded: LoadedContent) {
+        self.modified_time = location_modified_time(&loaded.location);
+        self.document = loaded.document;
+        self.location = loaded.location;
+        self.heading = loaded.heading;
+        self.rendered.clear();
+    }
+
+    /// The document rendered for a terminal `width` columns wide, from the
+    /// cache if this width was rendered before.
+    fn render_for_width(&mut self, width: usize) -> Result<String, String> {
+        if let Some(rendered) = self.rendered.get(&width) {
+            return Ok(rendered.clone());
+        }
+        let rendered = render_document_for_width(&self.document, width)?;
+        if self.rendered.len() >= RENDERED_WIDTHS_KEPT {
+            self.rendered.clear();
+        }
+        self.rendered.insert(width, rendered.clone());
+        Ok(rendered)
+    }
+}
+
+fn location_modified_time(location: &ContentLocation) -> Option<SystemTime> {
+    match location {
+        ContentLocation::File(path) => fs::metadata(path).and_then(|m| m.modified()).ok(),
+        ContentLocation::Plugin => None,
+    }
 }
 
 struct LinkCallbackState {
@@ -314,6 +1040,9 @@ struct LinkCallbackState {
     canonical_notes_dir: PathBuf,
     store: Arc<DocumentStore>,
     plugin_registry: Arc<PluginRegistry>,
+    /// The web link followed last, waiting to be followed again to confirm
+    /// opening it.
+    pending_url: Mutex<Option<String>>,
 }
 
 impl LinkCallbackState {
@@ -330,6 +1059,29 @@ impl LinkCallbackState {
             canonical_notes_dir,
             store,
             plugin_registry,
+            pending_url: Mutex::new(None),
+        }
+    }
+
+    /// Open `url` in the browser. If ~/.pikirc asks for confirmation, only
+    /// the second time in a row it is followed.
+    fn open_web_link(
+        &self,
+        url: &str,
+        context: &mut tdoc_pager::LinkCallbackContext<'_>,
+    ) -> Result<(), String> {
+        let mut pending = self
+            .pending_url
+            .lock()
+            .map_err(|_| "Unable to read the pending link".to_string())?;
+        if Config::load().links.confirm_external() && pending.as_deref() != Some(url) {
+            *pending = Some(url.to_string());
+            return context.set_status(format!("Follow the link again to open {url}"));
+        }
+        *pending = None;
+        match open_url(url) {
+            Ok(()) => context.set_status(format!("Opened {url}")),
+            Err(err) => context.set_status(format!("Error: {err}")),
         }
     }
 }
@@ -344,6 +1096,12 @@ impl tdoc_pager::LinkCallback for LinkCallbackState {
         if trimmed.is_empty() {
             return Ok(());
         }
+        if is_web_url(trimmed) {
+            return self.open_web_link(trimmed, context);
+        }
+        if let Ok(mut pending) = self.pending_url.lock() {
+            *pending = None;
+        }
 
         context.set_status(format!("Loading {trimmed} ..."))?;
 
@@ -355,6 +1113,20 @@ impl tdoc_pager::LinkCallback for LinkCallbackState {
             guard.location.clone()
         };
 
+        if let Some(LinkTarget::Attachment(path)) = resolve_link_target(
+            &self.notes_dir,
+            &self.canonical_notes_dir,
+            &current_location,
+            trimmed,
+            self.plugin_registry.as_ref(),
+        ) {
+            match open_with_system_handler(&path) {
+                Ok(()) => context.set_status(format!("Opened {trimmed}"))?,
+                Err(err) => context.set_status(format!("Error: {err}"))?,
+            }
+            return Ok(());
+        }
+
         match load_internal_content(
             self.store.as_ref(),
             self.plugin_registry.as_ref(),
@@ -364,24 +1136,19 @@ impl tdoc_pager::LinkCallback for LinkCallbackState {
             trimmed,
         ) {
             Ok(Some(loaded)) => {
-                let LoadedContent { document, location } = loaded;
                 let render_width = context.content_width().max(1);
-                let rendered = render_document_for_width(&document, render_width)?;
+                let rendered = render_document_for_width(&loaded.document, render_width)?;
                 context.replace_content(&rendered)?;
                 context.set_link_policy(build_link_policy(
                     &self.notes_dir,
                     &self.canonical_notes_dir,
-                    &location,
+                    &loaded.location,
                     &self.plugin_registry,
                 ));
-                {
-                    let mut guard = self
-                        .shared
-                        .lock()
-                        .map_err(|_| "Unable to update current document state".to_string())?;
-                    guard.document = document;
-                    guard.location = location;
-                }
+                self.shared
+                    .lock()
+                    .map_err(|_| "Unable to update current document state".to_string())?
+                    .replace(loaded);
                 context.clear_status()?;
             }
             Ok(None) => {
@@ -410,14 +1177,15 @@ fn build_link_policy(
     LinkPolicy::new(
         true,
         Arc::new(move |target: &str| {
-            resolve_link_target(
-                &notes_dir_owned,
-                &canonical_owned,
-                &location_owned,
-                target,
-                plugin_registry.as_ref(),
-            )
-            .is_some()
+            is_web_url(target.trim())
+                || resolve_link_target(
+                    &notes_dir_owned,
+                    &canonical_owned,
+                    &location_owned,
+                    target,
+                    plugin_registry.as_ref(),
+                )
+                .is_some()
         }),
     )
 }
@@ -488,13 +1256,19 @@ fn resolve_link_target(
     plugin_registry: &PluginRegistry,
 ) -> Option<LinkTarget> {
     let trimmed = target.trim();
-    if trimmed.is_empty() || trimmed.starts_with('#') || is_absolute_url(trimmed) {
+    if trimmed.is_empty() || is_absolute_url(trimmed) {
         return None;
     }
 
     let path_part = trimmed.split('#').next().unwrap_or(trimmed).trim();
     if path_part.is_empty() {
-        return None;
+        // `#heading` links to a section of the note itself.
+        return match current_location {
+            ContentLocation::File(path) if trimmed.len() > 1 => {
+                Some(LinkTarget::File(path.clone()))
+            }
+            _ => None,
+        };
     }
 
     if let Some(plugin_name) = path_part.strip_prefix('!')
@@ -503,25 +1277,10 @@ fn resolve_link_target(
         return Some(LinkTarget::Plugin(plugin_name.to_string()));
     }
 
-    let raw_path = Path::new(path_part);
-
-    let base_dir = match current_location {
-        ContentLocation::File(path) => path
-            .parent()
-            .map(PathBuf::from)
-            .unwrap_or_else(|| canonical_notes_dir.to_path_buf()),
-        ContentLocation::Plugin => canonical_notes_dir.to_path_buf(),
-    };
-
-    let resolved_base = if raw_path.is_absolute() {
-        let stripped = raw_path.strip_prefix(Path::new("/")).unwrap_or(raw_path);
-        notes_dir.join(stripped)
-    } else {
-        base_dir.join(raw_path)
-    };
+    let resolved_base = link_path(notes_dir, canonical_notes_dir, current_location, path_part);
 
     // Prefer the `.md` version of the target, falling back to the raw path
-    // (e.g. for links to assets). We append `.md` rather than using
+    // (e.g. for links to attachments like images or PDFs). We append `.md` rather than using
     // `with_extension`, which would mangle dotted note names like
     // "sprint-q2.6" into "sprint-q2.md".
     let mut candidates = Vec::new();
@@ -530,7 +1289,7 @@ fn resolve_link_target(
         with_md.push(".md");
         candidates.push(PathBuf::from(with_md));
     }
-    candidates.push(resolved_base);
+    candidates.push(resolved_base.clone());
 
     for candidate in candidates {
         if !candidate.exists() {
@@ -539,11 +1298,72 @@ fn resolve_link_target(
         if let Ok(canonical_candidate) = fs::canonicalize(&candidate)
             && canonical_candidate.starts_with(canonical_notes_dir)
         {
-            return Some(LinkTarget::File(canonical_candidate));
+            let is_note = canonical_candidate
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(has_md_extension);
+            if is_note {
+                return Some(LinkTarget::File(canonical_candidate));
+            }
+            if canonical_candidate.is_file() {
+                return Some(LinkTarget::Attachment(canonical_candidate));
+            }
         }
     }
 
-    None
+    // No file of that name: a note whose name differs only in case or in
+    // spaces versus dashes will do, e.g. `my-page.md` for `My Page`.
+    let name = note_name_in(notes_dir, canonical_notes_dir, &resolved_base)?;
+    let store = DocumentStore::new(canonical_notes_dir.to_path_buf());
+    let found = store.find_note(&name)?;
+    fs::canonicalize(store.path_for(&found))
+        .ok()
+        .filter(|path| path.starts_with(canonical_notes_dir))
+        .map(LinkTarget::File)
+}
+
+/// Where the link `path_part` (without `#heading`) from `current_location`
+/// points: relative to the linking note's folder, or to the notes directory
+/// if it starts with `/`. `.` and `..` are left in.
+fn link_path(
+    notes_dir: &Path,
+    canonical_notes_dir: &Path,
+    current_location: &ContentLocation,
+    path_part: &str,
+) -> PathBuf {
+    let raw_path = Path::new(path_part);
+    if raw_path.is_absolute() {
+        let stripped = raw_path.strip_prefix(Path::new("/")).unwrap_or(raw_path);
+        return notes_dir.join(stripped);
+    }
+    let base_dir = match current_location {
+        ContentLocation::File(path) => path
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| canonical_notes_dir.to_path_buf()),
+        ContentLocation::Plugin => canonical_notes_dir.to_path_buf(),
+    };
+    base_dir.join(raw_path)
+}
+
+/// The name of the note at `path` relative to the notes directory, with `.`
+/// and `..` resolved, or `None` if it lies outside of it.
+fn note_name_in(notes_dir: &Path, canonical_notes_dir: &Path, path: &Path) -> Option<String> {
+    let relative = path
+        .strip_prefix(canonical_notes_dir)
+        .or_else(|_| path.strip_prefix(notes_dir))
+        .ok()?;
+    let mut parts: Vec<&str> = Vec::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str()?),
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            _ => {}
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
 }
 
 fn load_internal_content(
@@ -554,6 +1374,10 @@ fn load_internal_content(
     current_location: &ContentLocation,
     target: &str,
 ) -> Result<Option<LoadedContent>, String> {
+    let heading = target
+        .split_once('#')
+        .map(|(_, heading)| heading.trim().to_string())
+        .filter(|heading| !heading.is_empty());
     match resolve_link_target(
         notes_dir,
         canonical_notes_dir,
@@ -564,26 +1388,37 @@ fn load_internal_content(
         Some(LinkTarget::File(path)) => {
             let content = fs::read_to_string(&path)
                 .map_err(|err| format!("Unable to read {}: {}", path.display(), err))?;
-            let document = markdown::parse(Cursor::new(content.into_bytes()))
-                .map_err(|err| format!("Error parsing FTML: {}", err))?;
+            let document = parse_note(from_heading(&content, heading.as_deref()))?;
             Ok(Some(LoadedContent {
                 document,
                 location: ContentLocation::File(path),
+                heading,
             }))
         }
+        Some(LinkTarget::Attachment(path)) => {
+            Err(format!("{} is an attachment, not a note", path.display()))
+        }
         Some(LinkTarget::Plugin(plugin_name)) => {
             let generated = plugin_registry.generate(&plugin_name, store)?;
-            let document = markdown::parse(Cursor::new(generated.into_bytes()))
+            let generated = from_heading(&generated, heading.as_deref());
+            let document = markdown::parse(Cursor::new(generated.as_bytes()))
                 .map_err(|err| format!("Error parsing FTML: {}", err))?;
             Ok(Some(LoadedContent {
                 document,
                 location: ContentLocation::Plugin,
+                heading,
             }))
         }
         None => Ok(None),
     }
 }
 
+/// Whether `target` links to a web page or an e-mail address, which `piki
+/// view` opens in the browser or mail program.
+fn is_web_url(target: &str) -> bool {
+    Url::parse(target).is_ok_and(|url| matches!(url.scheme(), "http" | "https" | "mailto"))
+}
+
 fn is_absolute_url(value: &str) -> bool {
     if value.starts_with("//") {
         return true;
@@ -591,13 +1426,71 @@ fn is_absolute_url(value: &str) -> bool {
     Url::parse(value).is_ok()
 }
 
-fn cmd_ls(notes_dir: &Path) -> Result<(), String> {
-    let store = DocumentStore::new(notes_dir.to_path_buf());
+/// How `piki ls` prints the notes.
+struct LsFormat {
+    /// With dates and sizes
+    long: bool,
+    /// As a tree of their folders
+    tree: bool,
+    json: bool,
+}
+
+fn cmd_ls(
+    pattern: Option<&str>,
+    format: LsFormat,
+    sort: Option<&str>,
+    reverse: bool,
+    notes_dir: &Path,
+) -> Result<(), String> {
+    let store = open_store(notes_dir);
     let mut docs = store.list_all_documents()?;
+    if let Some(pattern) = pattern {
+        docs.retain(|doc| listing::glob_matches(pattern, doc));
+    }
     docs.sort();
+    // Newest and largest first, as `ls -t` and `ls -S` do; the sort is
+    // stable, so ties stay in order of name.
+    match sort {
+        Some("mtime") => docs.sort_by_key(|doc| std::cmp::Reverse(store.modified_time(doc))),
+        Some("size") => docs.sort_by_key(|doc| std::cmp::Reverse(store.file_size(doc))),
+        _ => {}
+    }
+    if reverse {
+        docs.reverse();
+    }
+
+    if format.json {
+        print!("{}", json::notes(&store, &docs));
+        return Ok(());
+    }
+    if format.tree {
+        print!("{}", listing::tree(&docs));
+        return Ok(());
+    }
 
     for doc in docs {
-        println!("{}", doc);
+        if format.long {
+            // Created and modified like `ls -l` dates, `-` where unknown.
+            let date = |time: Option<SystemTime>| {
+                time.map_or("-".to_string(), |time| {
+                    chrono::DateTime::<chrono::Local>::from(time)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                })
+            };
+            let size = store
+                .file_size(&doc)
+                .map_or("-".to_string(), images::format_size);
+            println!(
+                "{:16}  {:16}  {:>9}  {}",
+                date(store.created_time(&doc)),
+                date(store.modified_time(&doc)),
+                size,
+                doc
+            );
+        } else {
+            println!("{}", doc);
+        }
     }
 
     Ok(())
@@ -610,6 +1503,11 @@ const C_NAME: &str = "\x1b[1;36m";
 const C_LINE: &str = "\x1b[32m";
 const C_MATCH: &str = "\x1b[1;31m";
 const C_RESET: &str = "\x1b[0m";
+/// Removed and added text in `piki diff`, coloured as by `git diff`.
+const C_REMOVED: &str = "\x1b[31m";
+const C_ADDED: &str = "\x1b[32m";
+/// Unchanged lines `piki diff` shows around each change.
+const DIFF_CONTEXT_LINES: usize = 3;
 
 /// Wrap every case-insensitive occurrence of any term in `line` with the match
 /// colour. Boundary-safe: it only does offset-based highlighting when
@@ -668,11 +1566,15 @@ fn highlight_terms(line: &str, terms: &[String], enabled: bool) -> String {
     out
 }
 
-fn cmd_search(terms: Vec<String>, notes_dir: &Path) -> Result<(), String> {
-    let store = DocumentStore::new(notes_dir.to_path_buf());
+fn cmd_search(terms: Vec<String>, json: bool, notes_dir: &Path) -> Result<(), String> {
+    let store = open_store(notes_dir);
     let query = terms.join(" ");
     let parsed = piki_core::search::parse_terms(&query);
     let results = piki_core::search::search_store(&store, &query)?;
+    if json {
+        print!("{}", json::search_results(&store, &results));
+        return Ok(());
+    }
 
     if results.is_empty() {
         eprintln!("No matches for “{}”.", query);
@@ -697,29 +1599,909 @@ fn cmd_search(terms: Vec<String>, notes_dir: &Path) -> Result<(), String> {
     Ok(())
 }
 
-fn cmd_log(count: usize, notes_dir: &PathBuf) -> Result<(), String> {
-    let output = Command::new("git")
-        .args([
-            "log",
-            &format!("-n{}", count),
-            "--pretty=format:* %ad %s",
-            "--date=short",
-        ])
-        .current_dir(notes_dir)
-        .output()
-        .map_err(|e| format!("Failed to run git log: {}", e))?;
+fn cmd_spellcheck(name: Option<String>, notes_dir: &Path) -> Result<(), String> {
+    let store = open_store(notes_dir);
+    let names = match name {
+        Some(name) => vec![name],
+        None => {
+            let mut names = store.list_all_documents()?;
+            names.sort();
+            names
+        }
+    };
+
+    let mut dictionaries = spellcheck::Dictionaries::new(spellcheck::Dictionaries::default_dirs());
+    for name in names {
+        let doc = store.load(&name)?;
+        for word in spellcheck::check(&doc.content, &mut dictionaries) {
+            println!(
+                "{}:{}:{}: {} ({})",
+                name, word.line, word.column, word.text, word.lang
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn cmd_images(compress: bool, max_width: u32, notes_dir: &Path) -> Result<(), String> {
+    let store = open_store(notes_dir);
+    let report = images::scan(&store)?;
+
+    let width = report
+        .attachments
+        .iter()
+        .map(|a| a.path.chars().count())
+        .max()
+        .unwrap_or(0);
+    for attachment in &report.attachments {
+        let pages = if attachment.referenced_by.is_empty() {
+            "(unreferenced)".to_string()
+        } else {
+            attachment.referenced_by.join(", ")
+        };
+        println!(
+            "{:width$}  {:>9}  {}",
+            attachment.path,
+            images::format_size(attachment.bytes),
+            pages
+        );
+    }
+    if !report.missing.is_empty() {
+        println!("\nMissing files:");
+        for missing in &report.missing {
+            println!("{}:{}: {}", missing.page, missing.line, missing.target);
+        }
+    }
+
+    let total: u64 = report.attachments.iter().map(|a| a.bytes).sum();
+    let unreferenced = report
+        .attachments
+        .iter()
+        .filter(|a| a.referenced_by.is_empty())
+        .count();
+    println!(
+        "\n{} attachments ({}), {} unreferenced, {} missing",
+        report.attachments.len(),
+        images::format_size(total),
+        unreferenced,
+        report.missing.len()
+    );
+
+    if !compress {
+        return Ok(());
+    }
+    let oversized: Vec<_> = report
+        .attachments
+        .iter()
+        .filter(|a| a.bytes >= images::OVERSIZED_BYTES && images::is_compressible(&a.path))
+        .collect();
+    if oversized.is_empty() {
+        println!("No oversized images to compress.");
+        return Ok(());
+    }
+    println!();
+    let mut saved = 0;
+    for attachment in oversized {
+        match images::compress(notes_dir, &attachment.path, max_width)? {
+            Some(bytes) => {
+                saved += attachment.bytes - bytes;
+                println!(
+                    "{}: {} -> {}",
+                    attachment.path,
+                    images::format_size(attachment.bytes),
+                    images::format_size(bytes)
+                );
+            }
+            None => println!("{}: kept, it would not get smaller", attachment.path),
+        }
+    }
+    println!(
+        "Saved {}; the originals are in {}",
+        images::format_size(saved),
+        images::backup_dir(notes_dir).display()
+    );
+    Ok(())
+}
+
+fn cmd_tag_rename(old: &str, new: &str, dry_run: bool, notes_dir: &Path) -> Result<(), String> {
+    let store = open_store(notes_dir);
+    let pages = tags::rename_tag_everywhere(&store, old, new, dry_run)?;
+    if pages.is_empty() {
+        eprintln!("No notes are tagged #{}.", old.trim_start_matches('#'));
+        return Ok(());
+    }
+
+    for (page, count) in &pages {
+        println!("{page}: {count}");
+    }
+    let total: usize = pages.iter().map(|(_, count)| count).sum();
+    println!(
+        "\n{} {total} tag{} in {} note{}",
+        if dry_run { "Would rename" } else { "Renamed" },
+        if total == 1 { "" } else { "s" },
+        pages.len(),
+        if pages.len() == 1 { "" } else { "s" },
+    );
+    Ok(())
+}
+
+/// Where this machine's settings are.
+fn settings_locations(notes_dir: &Path) -> settings_archive::Locations {
+    settings_archive::Locations {
+        pikirc: Config::config_path(),
+        // The file the GUI keeps its theme in (see its `window_state` module).
+        appearance: ProjectDirs::from("net.roblillack", "Piki", "piki-gui")
+            .map(|dirs| dirs.data_local_dir().join("appearance.toml")),
+        notes_dir: notes_dir.to_path_buf(),
+    }
+}
+
+fn cmd_config_export(output: Option<&Path>, notes_dir: &Path) -> Result<(), String> {
+    let entries = settings_archive::collect(&settings_locations(notes_dir))?;
+    let archive = settings_archive::write(&entries);
+    let Some(output) = output else {
+        print!("{archive}");
+        return Ok(());
+    };
+
+    fs::write(output, archive)
+        .map_err(|e| format!("Failed to write '{}': {}", output.display(), e))?;
+    for entry in &entries {
+        println!("{:<12} {}", entry.kind.as_str(), entry.name);
+    }
+    println!(
+        "\nExported {} file{} to {}",
+        entries.len(),
+        if entries.len() == 1 { "" } else { "s" },
+        output.display()
+    );
+    Ok(())
+}
+
+fn cmd_config_import(file: &Path, force: bool, notes_dir: &Path) -> Result<(), String> {
+    let archive = fs::read_to_string(file)
+        .map_err(|e| format!("Failed to read '{}': {}", file.display(), e))?;
+    let entries = settings_archive::read(&archive)?;
+    let outcomes = settings_archive::install(&entries, &settings_locations(notes_dir), force)?;
+
+    let mut kept = false;
+    for (entry, outcome) in entries.iter().zip(&outcomes) {
+        match outcome {
+            Outcome::Installed(path) => println!("Installed {}", path.display()),
+            Outcome::Unchanged(path) => println!("Unchanged {}", path.display()),
+            Outcome::Kept(path) => {
+                kept = true;
+                println!(
+                    "Kept      {} (differs from the imported one)",
+                    path.display()
+                );
+            }
+            Outcome::NoLocation => {
+                println!(
+                    "Skipped   {} {} (nowhere to put it)",
+                    entry.kind.as_str(),
+                    entry.name
+                )
+            }
+        }
+    }
+    if kept {
+        println!("\nUse --force to replace the settings that were kept.");
+    }
+    Ok(())
+}
+
+fn cmd_sync(message: Option<&str>, notes_dir: &Path) -> Result<(), String> {
+    let report = git::sync(notes_dir, message)?;
+    if report.committed {
+        println!("Committed changed notes");
+    }
+    println!("{}", report.summary());
+    Ok(())
+}
+
+fn cmd_log(count: usize, json: bool, notes_dir: &Path) -> Result<(), String> {
+    if json {
+        print!("{}", json::commits(&git::commits(notes_dir, count)?));
+        return Ok(());
+    }
+    print!("{}", git::log(notes_dir, count)?);
+    Ok(())
+}
+
+fn cmd_history(name: &str, count: usize, notes_dir: &Path) -> Result<(), String> {
+    let file = ensure_md_extension(name);
+    let commits = git::file_history(notes_dir, &file)?;
+    if commits.is_empty() {
+        eprintln!("No commits changed '{name}'.");
+        return Ok(());
+    }
+    for commit in commits.iter().take(count) {
+        let short = &commit.hash[..commit.hash.len().min(7)];
+        if commit.path == file {
+            println!("* {} {} {}", commit.date, short, commit.subject);
+        } else {
+            println!(
+                "* {} {} {} (as {})",
+                commit.date, short, commit.subject, commit.path
+            );
+        }
+    }
+    Ok(())
+}
+
+fn cmd_restore(name: &str, revision: &str, force: bool, notes_dir: &Path) -> Result<(), String> {
+    let file = ensure_md_extension(name);
+    if !force && notes_dir.join(&file).exists() && git::is_modified(notes_dir, &file)? {
+        return Err(format!(
+            "'{name}' has uncommitted changes. Commit them first, or use --force to discard them"
+        ));
+    }
+
+    // The commit is looked up in the note's history, so that versions from
+    // before a rename are found under their old path.
+    let hash = git::commit_hash(notes_dir, revision)?;
+    let history = git::file_history(notes_dir, &file)?;
+    let (content, restored) = match history.iter().find(|commit| commit.hash == hash) {
+        Some(commit) => (
+            git::file_at_commit(notes_dir, commit)?,
+            format!("{} ({} {})", &hash[..7], commit.date, commit.subject),
+        ),
+        None => (
+            git::file_at_revision(notes_dir, &hash, &file)?,
+            hash[..7].to_string(),
+        ),
+    };
+
+    let store = open_store(notes_dir);
+    store.save(&piki_core::Document {
+        name: name.to_string(),
+        path: store.path_for(name),
+        content,
+        modified_time: None,
+    })?;
+    println!("Restored {name} to {restored}");
+    Ok(())
+}
+
+fn cmd_doctor(
+    enable: bool,
+    disable: bool,
+    check_external: bool,
+    notes_dir: &Path,
+) -> Result<(), String> {
+    if enable {
+        edit_log::enable(notes_dir)?;
+        println!(
+            "Edit log enabled: {}",
+            edit_log::log_path(notes_dir).display()
+        );
+        return Ok(());
+    }
+    if disable {
+        edit_log::disable(notes_dir)?;
+        println!("Edit log disabled and removed.");
+        return Ok(());
+    }
+
+    let store = open_store(notes_dir);
+    let notes = store.list_all_documents()?;
+    println!(
+        "Notes directory: {} ({} notes)",
+        notes_dir.display(),
+        notes.len()
+    );
+
+    let in_git = git::is_repository(notes_dir);
+    if in_git {
+        println!("Git: repository found");
+    } else {
+        println!("Git: not a git repository, nothing is versioned or synced");
+    }
+
+    report_edit_log(&store, in_git, notes_dir)?;
+    if check_external {
+        report_dead_links(&store, &notes, notes_dir)?;
+    }
+    Ok(())
+}
+
+/// The edit log part of `piki doctor`.
+fn report_edit_log(store: &DocumentStore, in_git: bool, notes_dir: &Path) -> Result<(), String> {
+    if !edit_log::is_enabled(notes_dir) {
+        println!("Edit log: disabled (enable it with `piki doctor --enable-edit-log`)");
+        return Ok(());
+    }
+    let entries = edit_log::read(notes_dir)?;
+    println!("Edit log: {} entries", entries.len());
+
+    let findings = edit_log::correlate(&entries, |page| {
+        let file = piki_core::ensure_md_extension(page);
+        edit_log::PageState {
+            bytes: fs::metadata(store.path_for(page)).ok().map(|m| m.len()),
+            last_commit: in_git
+                .then(|| git::last_commit(notes_dir, &file).ok().flatten())
+                .flatten(),
+            uncommitted: in_git && git::is_modified(notes_dir, &file).unwrap_or(false),
+        }
+    });
+    if findings.is_empty() {
+        println!("\nThe last saved version of every logged note is in place.");
+        return Ok(());
+    }
+
+    println!();
+    for finding in findings {
+        match finding {
+            Finding::Replaced {
+                page,
+                edited,
+                commit: Some(commit),
+            } => println!(
+                "{page}: saved {}, then replaced by commit {} ({}, \"{}\"). \
+                 The saved version may be in an earlier commit: git log -p -- {}",
+                edit_log::format_time(edited),
+                &commit.hash[..commit.hash.len().min(7)],
+                commit.date,
+                commit.subject,
+                commit.path
+            ),
+            Finding::Replaced {
+                page,
+                edited,
+                commit: None,
+            } => println!(
+                "{page}: saved {}, then changed on disk by another program without a commit",
+                edit_log::format_time(edited)
+            ),
+            Finding::Missing {
+                page,
+                edited,
+                commit,
+            } => {
+                let last = commit
+                    .map(|c| {
+                        format!(
+                            "; last commit touching it: {} ({}, \"{}\")",
+                            &c.hash[..c.hash.len().min(7)],
+                            c.date,
+                            c.subject
+                        )
+                    })
+                    .unwrap_or_default();
+                println!(
+                    "{page}: saved {}, but the note no longer exists{last}",
+                    edit_log::format_time(edited)
+                );
+            }
+            Finding::Uncommitted { page, edited } => println!(
+                "{page}: saved {}, not committed yet (so not synced)",
+                edit_log::format_time(edited)
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+/// `piki doctor --check-external`: request every external URL (or use a
+/// recent cached result) and list the dead ones per note.
+fn report_dead_links(
+    store: &DocumentStore,
+    notes: &[String],
+    notes_dir: &Path,
+) -> Result<(), String> {
+    let mut links: Vec<(String, usize, String)> = Vec::new();
+    for note in notes {
+        let Ok(doc) = store.load(note) else {
+            continue;
+        };
+        for (line, url) in link_check::external_links(&doc.content) {
+            links.push((note.clone(), line, url));
+        }
+    }
+
+    let urls: HashSet<&String> = links.iter().map(|(_, _, url)| url).collect();
+    let mut cache = link_check::Cache::load(notes_dir);
+    let mut unchecked: Vec<String> = urls
+        .iter()
+        .filter(|url| cache.get(url, link_check::CACHE_MAX_AGE).is_none())
+        .map(|url| url.to_string())
+        .collect();
+    unchecked.sort();
+    if !unchecked.is_empty() && !link_check::curl_available() {
+        return Err(
+            "Checking external links needs curl, which could not be run; install it or leave out --check-external"
+                .to_string(),
+        );
+    }
+
+    println!(
+        "\nExternal links: {} unique URLs, {} checked recently, checking {} …",
+        urls.len(),
+        urls.len() - unchecked.len(),
+        unchecked.len()
+    );
+    let results = link_check::check_all(&unchecked, link_check::DEFAULT_CONCURRENCY, |url| {
+        link_check::check_url(url, link_check::DEFAULT_TIMEOUT)
+    });
+    for (url, status) in results {
+        cache.insert(url, status);
+    }
+    if let Err(e) = cache.save(notes_dir) {
+        eprintln!("Warning: {e}");
+    }
+
+    let mut dead = 0;
+    for (note, line, url) in &links {
+        let status = match cache.get(url, link_check::CACHE_MAX_AGE) {
+            Some(status) if !status.is_ok() => status,
+            _ => continue,
+        };
+        let reason = match status {
+            link_check::Status::Dead(code) => format!("HTTP {code}"),
+            link_check::Status::Unreachable(reason) => reason.clone(),
+            link_check::Status::Ok(_) => continue,
+        };
+        println!("{note}:{line}: {url} ({reason})");
+        dead += 1;
+    }
+    if dead == 0 {
+        println!("No dead external links.");
+    }
+    Ok(())
+}
+
+/// Follow every link between notes the way `piki view` does and print those
+/// leading nowhere, one `note:line: target (problem)` per line. Fails if there
+/// are any, for use in scripts and CI. Encrypted notes are skipped.
+fn cmd_check(notes_dir: &Path) -> Result<(), String> {
+    let store = DocumentStore::new(notes_dir.to_path_buf());
+    let canonical_notes_dir = normalize_base_path(notes_dir);
+    let plugin_registry = plugin_registry(notes_dir);
+    let notes = link_index::scan(&store)?;
+
+    let mut broken = 0;
+    for (note, links) in notes.iter().filter(|(note, _)| !store.is_encrypted(note)) {
+        let path = store.path_for(note);
+        let location = ContentLocation::File(fs::canonicalize(&path).unwrap_or(path));
+        for (line, target) in links {
+            if let Some(problem) = link_problem(
+                notes_dir,
+                &canonical_notes_dir,
+                &location,
+                target,
+                &plugin_registry,
+            ) {
+                println!("{note}:{line}: {target} ({problem})");
+                broken += 1;
+            }
+        }
+    }
+
+    match broken {
+        0 => {
+            println!("No broken links in {} notes.", notes.len());
+            Ok(())
+        }
+        1 => Err("1 broken link".to_string()),
+        broken => Err(format!("{broken} broken links")),
+    }
+}
 
-    if !output.status.success() {
+/// What is wrong with the link to `target` from `location`, if anything.
+fn link_problem(
+    notes_dir: &Path,
+    canonical_notes_dir: &Path,
+    location: &ContentLocation,
+    target: &str,
+    plugin_registry: &PluginRegistry,
+) -> Option<String> {
+    let (path_part, heading) = match target.split_once('#') {
+        Some((path_part, heading)) => (path_part.trim(), Some(heading)),
+        None => (target.trim(), None),
+    };
+    match resolve_link_target(
+        notes_dir,
+        canonical_notes_dir,
+        location,
+        target,
+        plugin_registry,
+    ) {
+        Some(LinkTarget::File(path)) => {
+            let heading = heading?;
+            // Encrypted notes can't be read without their passphrase.
+            let content = fs::read_to_string(&path).ok()?;
+            section::heading_line(&content, heading)
+                .is_none()
+                .then(|| format!("no heading '{heading}'"))
+        }
+        Some(_) => None,
+        None if path_part.starts_with('!') => Some("no such plugin".to_string()),
+        None => {
+            let path = link_path(notes_dir, canonical_notes_dir, location, path_part);
+            let mut with_md = path.clone().into_os_string();
+            with_md.push(".md");
+            let leaves = note_name_in(notes_dir, canonical_notes_dir, &path).is_none()
+                || [PathBuf::from(with_md), path].iter().any(|candidate| {
+                    fs::canonicalize(candidate)
+                        .is_ok_and(|canonical| !canonical.starts_with(canonical_notes_dir))
+                });
+            Some(if leaves {
+                "outside the notes directory".to_string()
+            } else {
+                "no such note or file".to_string()
+            })
+        }
+    }
+}
+
+/// How `piki export` writes notes.
+struct ExportOptions {
+    format: String,
+    wrap_column: Option<usize>,
+    backlinks: bool,
+    /// The page around HTML exports.
+    html_template: String,
+    /// What to leave out of the notes; `None` to export them as they are.
+    redaction: Option<RedactionRules>,
+    /// Whether to export encrypted notes, decrypted, too.
+    include_encrypted: bool,
+}
+
+fn cmd_export(
+    name: Option<String>,
+    output: Option<PathBuf>,
+    output_dir: Option<PathBuf>,
+    options: ExportOptions,
+    notes_dir: &Path,
+) -> Result<(), String> {
+    if !["pdf", "html", "epub", "txt", "md"].contains(&options.format.as_str()) {
         return Err(format!(
-            "git log failed: {}",
-            String::from_utf8_lossy(&output.stderr)
+            "Unsupported export format '{}' (supported: pdf, html, epub, txt, md)",
+            options.format
         ));
     }
+    if options.wrap_column == Some(0) {
+        return Err("The wrap column must be at least 1".to_string());
+    }
+    if name.is_none() && output.is_some() && options.format != "epub" {
+        return Err(
+            "Exporting all notes writes one file per note; use --output-dir instead of -o"
+                .to_string(),
+        );
+    }
+
+    let store = open_store(notes_dir);
+    let names: Vec<String> = match &name {
+        Some(name) => vec![name.clone()],
+        None => store.list_all_documents()?,
+    };
+    let mut pages = Vec::new();
+    let mut skipped_encrypted = 0;
+    for note in names {
+        // Exported files are plain text, wherever they end up.
+        if !options.include_encrypted && store.is_encrypted(&note) {
+            if name.is_some() {
+                return Err(format!(
+                    "'{note}' is encrypted (export it decrypted with --include-encrypted)"
+                ));
+            }
+            skipped_encrypted += 1;
+            continue;
+        }
+        match export_content(&store, &note, &options, notes_dir)? {
+            Some(content) => pages.push((note, content)),
+            None if name.is_some() => {
+                return Err(format!(
+                    "'{note}' is private: it has a tag of the [redact] section of ~/.pikirc (export it anyway with --keep-private)"
+                ));
+            }
+            None => {}
+        }
+    }
+    if skipped_encrypted > 0 {
+        eprintln!(
+            "Skipped {skipped_encrypted} encrypted note{} (add --include-encrypted to export decrypted copies)",
+            if skipped_encrypted == 1 { "" } else { "s" }
+        );
+    }
+    // Links between the exported notes lead to each other's files.
+    let exported: BTreeSet<String> = pages.iter().map(|(name, _)| name.clone()).collect();
+    let output_dir = output_dir.unwrap_or_default();
+
+    if options.format == "epub" {
+        let mut chapters = Vec::new();
+        for (name, content) in &pages {
+            chapters.push(export::Chapter {
+                name: name.trim_start_matches('!').to_string(),
+                title: title::page_title(name, content),
+                body: note_to_html(&export::link_exported(name, content, &exported, "xhtml"))?,
+            });
+        }
+        let book_title = match (&name, chapters.first()) {
+            (Some(_), Some(chapter)) => chapter.title.clone(),
+            _ => title::wiki_name(notes_dir),
+        };
+        let output = output.unwrap_or_else(|| {
+            let file_name = match &name {
+                Some(name) => name.trim_start_matches('!').replace('/', "-"),
+                None => book_title.replace('/', "-"),
+            };
+            output_dir.join(format!("{file_name}.epub"))
+        });
+        let book = export::epub(&book_title, &chapters, today());
+        fs::write(&output, book)
+            .map_err(|e| format!("Failed to write {}: {}", output.display(), e))?;
+        eprintln!("Exported {} to {}", book_title, output.display());
+        return Ok(());
+    }
+
+    if name.is_some()
+        && let Some((name, content)) = pages.first()
+    {
+        let output = output.unwrap_or_else(|| {
+            let file_name = name.trim_start_matches('!').replace('/', "-");
+            output_dir.join(format!("{file_name}.{}", options.format))
+        });
+        write_export(name, content, &exported, &options, &output)?;
+        eprintln!("Exported {} to {}", name, output.display());
+        return Ok(());
+    }
+
+    for (name, content) in &pages {
+        let output = output_dir.join(format!("{name}.{}", options.format));
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        write_export(name, content, &exported, &options, &output)?;
+    }
+    eprintln!(
+        "Exported {} note{} to {}",
+        pages.len(),
+        if pages.len() == 1 { "" } else { "s" },
+        if output_dir.as_os_str().is_empty() {
+            Path::new(".").display()
+        } else {
+            output_dir.display()
+        }
+    );
+    Ok(())
+}
+
+/// The Markdown of the note (or `!plugin` page) `name` to export, redacted
+/// and ending with its backlinks as `options` ask for; `None` if the note is
+/// private as a whole.
+fn export_content(
+    store: &DocumentStore,
+    name: &str,
+    options: &ExportOptions,
+    notes_dir: &Path,
+) -> Result<Option<String>, String> {
+    let content = if let Some(plugin_name) = name.strip_prefix('!') {
+        plugin_registry(notes_dir)
+            .generate(plugin_name, store)
+            .map_err(|err| format!("Error generating plugin '{plugin_name}': {err}"))?
+    } else {
+        store.load(name)?.content
+    };
+    export::published_content(
+        store,
+        name,
+        &content,
+        options.backlinks,
+        options.redaction.as_ref(),
+    )
+}
+
+/// Write the note `name` with `content` to `output` in the format of
+/// `options`; links to the `exported` notes lead to their files.
+fn write_export(
+    name: &str,
+    content: &str,
+    exported: &BTreeSet<String>,
+    options: &ExportOptions,
+    output: &Path,
+) -> Result<(), String> {
+    let bytes = match options.format.as_str() {
+        "pdf" => pdf::markdown_to_pdf(name, content),
+        "html" => {
+            let body = note_to_html(&export::link_exported(name, content, exported, "html"))?;
+            export::fill_html_template(
+                &options.html_template,
+                &title::page_title(name, content),
+                &body,
+            )
+            .into_bytes()
+        }
+        "txt" => {
+            let mut style = FormattingStyle::ascii();
+            if let Some(column) = options.wrap_column {
+                style.wrap_width = column;
+                style.left_padding = 0;
+            }
+            let mut buf = Vec::new();
+            Formatter::new(&mut buf, style)
+                .write_document(&parse_note(content)?)
+                .map_err(|err| format!("Error rendering FTML: {err}"))?;
+            buf
+        }
+        _ => match options.wrap_column {
+            Some(column) => wrap::hard_wrap(content, column).into_bytes(),
+            None => content.as_bytes().to_vec(),
+        },
+    };
+    fs::write(output, bytes).map_err(|e| format!("Failed to write {}: {}", output.display(), e))
+}
+
+/// The parsed `document` as JSON, for `piki view --format json`: its
+/// `paragraphs`, each with its `type` and, depending on that, the `content`
+/// spans, list `entries`, checklist `items`, quoted `children` or table
+/// `rows`. Spans have a `style`, their `text` and, if any, a `link_target`
+/// and nested `children`.
+fn document_to_json(document: &Document) -> String {
+    format!(
+        "{{\"paragraphs\":{}}}",
+        paragraphs_to_json(&document.paragraphs)
+    )
+}
+
+fn paragraphs_to_json(paragraphs: &[Paragraph]) -> String {
+    let items = paragraphs.iter().map(|paragraph| {
+        let (kind, field, value) = match paragraph {
+            Paragraph::Text { content } => ("text", "content", spans_to_json(content)),
+            Paragraph::Header1 { content } => ("header1", "content", spans_to_json(content)),
+            Paragraph::Header2 { content } => ("header2", "content", spans_to_json(content)),
+            Paragraph::Header3 { content } => ("header3", "content", spans_to_json(content)),
+            Paragraph::CodeBlock { content } => ("code_block", "content", spans_to_json(content)),
+            Paragraph::OrderedList { entries } => (
+                "ordered_list",
+                "entries",
+                json_array(entries.iter().map(|entry| paragraphs_to_json(entry))),
+            ),
+            Paragraph::UnorderedList { entries } => (
+                "unordered_list",
+                "entries",
+                json_array(entries.iter().map(|entry| paragraphs_to_json(entry))),
+            ),
+            Paragraph::Checklist { items } => ("checklist", "items", checklist_to_json(items)),
+            Paragraph::Quote { children } => ("quote", "children", paragraphs_to_json(children)),
+            Paragraph::Table { rows } => (
+                "table",
+                "rows",
+                json_array(rows.iter().map(|row| {
+                    let cells = row
+                        .cells
+                        .iter()
+                        .map(|cell| format!("{{\"content\":{}}}", spans_to_json(&cell.content)));
+                    format!("{{\"cells\":{}}}", json_array(cells))
+                })),
+            ),
+        };
+        format!("{{\"type\":\"{kind}\",\"{field}\":{value}}}")
+    });
+    json_array(items)
+}
+
+fn checklist_to_json(items: &[ChecklistItem]) -> String {
+    json_array(items.iter().map(|item| {
+        format!(
+            "{{\"checked\":{},\"content\":{},\"children\":{}}}",
+            item.checked,
+            spans_to_json(&item.content),
+            checklist_to_json(&item.children)
+        )
+    }))
+}
+
+fn spans_to_json(spans: &[Span]) -> String {
+    json_array(spans.iter().map(|span| {
+        let mut out = format!(
+            "{{\"style\":{},\"text\":{}",
+            json::quote(&format!("{:?}", span.style).to_lowercase()),
+            json::quote(&span.text)
+        );
+        if let Some(target) = &span.link_target {
+            out.push_str(&format!(",\"link_target\":{}", json::quote(target)));
+        }
+        if !span.children.is_empty() {
+            out.push_str(&format!(",\"children\":{}", spans_to_json(&span.children)));
+        }
+        out.push('}');
+        out
+    }))
+}
+
+fn json_array(items: impl Iterator<Item = String>) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(","))
+}
+
+/// A note's Markdown as an HTML fragment.
+fn note_to_html(content: &str) -> Result<String, String> {
+    let mut buf = Vec::new();
+    html::write(&mut buf, &parse_note(content)?)
+        .map_err(|err| format!("Error rendering HTML: {err}"))?;
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+fn cmd_backlinks(name: &str, json: bool, notes_dir: &Path) -> Result<(), String> {
+    let store = open_store(notes_dir);
+    let name = name.strip_suffix(".md").unwrap_or(name);
+    let backlinks = graph::backlinks(&store, name)?;
+    if json {
+        print!("{}", graph::backlinks_to_json(&backlinks));
+        return Ok(());
+    }
+
+    if backlinks.is_empty() {
+        eprintln!("No notes link to '{name}'.");
+        return Ok(());
+    }
+    for backlink in &backlinks {
+        println!("{}:{}: {}", backlink.page, backlink.line, backlink.snippet);
+    }
+    Ok(())
+}
 
-    print!("{}", String::from_utf8_lossy(&output.stdout));
+fn cmd_import(path: &Path, from: &str, notes_dir: &Path) -> Result<(), String> {
+    let source = import::Source::parse(from).ok_or_else(|| {
+        format!("Unknown wiki '{from}' to import from (supported: obsidian, notion)")
+    })?;
+    let store = open_store(notes_dir);
+    let files = import::read_export(path)?;
+    let report = import::import(&store, files, source, &html_to_markdown)?;
+
+    for name in &report.existing {
+        eprintln!("Skipped {name}: it already exists");
+    }
+    println!(
+        "Imported {} note{} and {} attachment{} into {}",
+        report.notes.len(),
+        if report.notes.len() == 1 { "" } else { "s" },
+        report.attachments.len(),
+        if report.attachments.len() == 1 {
+            ""
+        } else {
+            "s"
+        },
+        notes_dir.display()
+    );
     Ok(())
 }
 
+/// An HTML page as Markdown.
+fn html_to_markdown(text: &str) -> Result<String, String> {
+    let document = html::parse(Cursor::new(text.as_bytes()))
+        .map_err(|err| format!("Error parsing HTML: {err}"))?;
+    let mut buf = Vec::new();
+    markdown::write(&mut buf, &document).map_err(|err| format!("Error writing Markdown: {err}"))?;
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+fn cmd_graph(format: &str, output: Option<PathBuf>, notes_dir: &Path) -> Result<(), String> {
+    let store = open_store(notes_dir);
+    let graph = graph::build(&store)?;
+    let text = match format {
+        "dot" => graph.to_dot(),
+        "json" => graph.to_json(),
+        _ => {
+            return Err(format!(
+                "Unsupported graph format '{format}' (supported: dot, json)"
+            ));
+        }
+    };
+    match output {
+        Some(output) => fs::write(&output, text)
+            .map_err(|e| format!("Failed to write {}: {}", output.display(), e)),
+        None => {
+            print!("{text}");
+            Ok(())
+        }
+    }
+}
+
 fn cmd_run(command: Vec<String>, notes_dir: &PathBuf) -> Result<(), String> {
     if command.is_empty() {
         return Err("No command specified".to_string());
@@ -741,18 +2523,567 @@ fn cmd_run(command: Vec<String>, notes_dir: &PathBuf) -> Result<(), String> {
     Ok(())
 }
 
-fn cmd_index(notes_dir: &Path) -> Result<(), String> {
-    cmd_view(Some("!index".to_string()), notes_dir)
+fn cmd_encrypt(name: &str, notes_dir: &Path) -> Result<(), String> {
+    let store = open_store(notes_dir);
+    if store.is_encrypted(name) {
+        return Err(format!("'{name}' is encrypted already"));
+    }
+    if !store.path_for(name).exists() {
+        return Err(format!("No note '{name}'"));
+    }
+    let read = |prompt: &str| {
+        rpassword::prompt_password(prompt).map_err(|e| format!("Failed to read passphrase: {e}"))
+    };
+    let passphrase = read(&format!("New passphrase for '{name}': "))?;
+    if passphrase.is_empty() {
+        return Err("The passphrase must not be empty".to_string());
+    }
+    if read("Repeat the passphrase: ")? != passphrase {
+        return Err("The passphrases don't match".to_string());
+    }
+    store.encrypt_note(name, &passphrase)?;
+    println!("Encrypted {name}");
+    Ok(())
+}
+
+fn cmd_decrypt(name: &str, notes_dir: &Path) -> Result<(), String> {
+    open_store(notes_dir).decrypt_note(name)?;
+    println!("Decrypted {name}");
+    Ok(())
+}
+
+fn cmd_diff(
+    name: &str,
+    commit: &str,
+    tool: Option<DiffTool>,
+    notes_dir: &Path,
+) -> Result<(), String> {
+    let store = open_store(notes_dir);
+    let current = store.load(name)?.content;
+    let old = git::file_at_revision(notes_dir, commit, &ensure_md_extension(name))?;
+
+    if let Some(tool) = tool {
+        return tool.compare(name, (commit, &old), ("current", &current));
+    }
+    print!("{}", word_diff(&old, &current));
+    Ok(())
+}
+
+/// The changes from `old` to `new`, marked up like `git diff
+/// --word-diff=plain`, or `--word-diff=color` on a terminal.
+fn word_diff(old: &str, new: &str) -> String {
+    let changes = diff::diff_words(old, new);
+    let use_color = io::stdout().is_terminal();
+    let mut out = String::new();
+    for hunk in diff::hunks(&changes, DIFF_CONTEXT_LINES) {
+        if use_color {
+            out.push_str(&format!("{C_NAME}@@ line {} @@{C_RESET}\n", hunk.line));
+        } else {
+            out.push_str(&format!("@@ line {} @@\n", hunk.line));
+        }
+        for change in hunk.changes {
+            let (text, before, after) = match (&change, use_color) {
+                (Change::Same(text), _) => (text, "", ""),
+                (Change::Removed(text), true) => (text, C_REMOVED, C_RESET),
+                (Change::Added(text), true) => (text, C_ADDED, C_RESET),
+                (Change::Removed(text), false) => (text, "[-", "-]"),
+                (Change::Added(text), false) => (text, "{+", "+}"),
+            };
+            // Close the markup before the line break, so that it doesn't run
+            // into the next line.
+            let (text, newline) = match text.strip_suffix('\n') {
+                Some(text) => (text, "\n"),
+                None => (text.as_str(), ""),
+            };
+            if text.is_empty() {
+                out.push_str(newline);
+            } else {
+                out.push_str(&format!("{before}{text}{after}{newline}"));
+            }
+        }
+        if !out.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn cmd_replace(
+    pattern: &str,
+    replacement: &str,
+    regex: bool,
+    dry_run: bool,
+    interactive: bool,
+    notes_dir: &Path,
+) -> Result<(), String> {
+    let store = open_store(notes_dir);
+    let compiled = replace::compile(pattern, regex)?;
+    let use_color = io::stdout().is_terminal();
+    // `a` replaces this and all later matches, `q` none of them.
+    let mut answer_for_rest = None;
+    let report = replace::replace_everywhere(
+        &store,
+        &compiled,
+        replacement,
+        regex,
+        dry_run,
+        |note, found| {
+            if !interactive {
+                return true;
+            }
+            if let Some(answer) = answer_for_rest {
+                return answer;
+            }
+            let end = found.range.end.min(found.text.len());
+            let (before, matched, after) = (
+                &found.text[..found.range.start],
+                &found.text[found.range.start..end],
+                &found.text[end..],
+            );
+            if use_color {
+                println!(
+                    "{C_NAME}{note}:{}:{C_RESET} {before}{C_REMOVED}{matched}{C_RESET}{C_ADDED}{}{C_RESET}{after}",
+                    found.line, found.replacement
+                );
+            } else {
+                println!(
+                    "{note}:{}: {before}[-{matched}-]{{+{}+}}{after}",
+                    found.line, found.replacement
+                );
+            }
+            print!("Replace? [y/N/a/q] ");
+            let _ = io::stdout().flush();
+            let mut answer = String::new();
+            if io::stdin().read_line(&mut answer).is_err() {
+                answer_for_rest = Some(false);
+                return false;
+            }
+            match answer.trim() {
+                "y" | "Y" | "yes" => true,
+                "a" | "A" | "all" => {
+                    answer_for_rest = Some(true);
+                    true
+                }
+                "q" | "Q" | "quit" => {
+                    answer_for_rest = Some(false);
+                    false
+                }
+                _ => false,
+            }
+        },
+    )?;
+    for (note, err) in &report.unreadable {
+        eprintln!("Skipped '{note}': {err}");
+    }
+    let replaced = report.replaced;
+    if replaced.is_empty() {
+        eprintln!("Nothing to replace.");
+        return Ok(());
+    }
+
+    for note in &replaced {
+        if dry_run {
+            if use_color {
+                println!("{C_NAME}{}{C_RESET}", note.note);
+            } else {
+                println!("{}", note.note);
+            }
+            print!("{}", word_diff(&note.old, &note.new));
+            println!();
+        } else if !interactive {
+            println!("{}: {}", note.note, note.count);
+        }
+    }
+    let total: usize = replaced.iter().map(|note| note.count).sum();
+    println!(
+        "{}{} {total} match{} in {} note{}",
+        if interactive && !dry_run { "\n" } else { "" },
+        if dry_run { "Would replace" } else { "Replaced" },
+        if total == 1 { "" } else { "es" },
+        replaced.len(),
+        if replaced.len() == 1 { "" } else { "s" },
+    );
+    Ok(())
+}
+
+fn cmd_section(name: &str, heading: &str, raw: bool, notes_dir: &Path) -> Result<(), String> {
+    let store = open_store(notes_dir);
+    let content = if let Some(plugin_name) = name.strip_prefix('!') {
+        plugin_registry(notes_dir)
+            .generate(plugin_name, &store)
+            .map_err(|err| format!("Error generating plugin '{plugin_name}': {err}"))?
+    } else {
+        store.load(name)?.content
+    };
+    let section = section::find(&content, heading)
+        .ok_or_else(|| format!("No section '{heading}' in '{name}'"))?;
+
+    if raw {
+        println!("{section}");
+        return Ok(());
+    }
+    let document = parse_note(section)?;
+    let mut formatter = if io::stdout().is_terminal() {
+        let mut style = FormattingStyle::ansi();
+        configure_style_for_terminal(&mut style);
+        Formatter::new(io::stdout(), style)
+    } else {
+        Formatter::new_ascii(io::stdout())
+    };
+    formatter
+        .write_document(&document)
+        .map_err(|err| format!("Error rendering FTML: {err}"))
+}
+
+fn cmd_cat(name: &str, notes_dir: &Path) -> Result<(), String> {
+    let store = open_store(notes_dir);
+    let content = if let Some(plugin_name) = name.strip_prefix('!') {
+        plugin_registry(notes_dir)
+            .generate(plugin_name, &store)
+            .map_err(|err| format!("Error generating plugin '{plugin_name}': {err}"))?
+    } else {
+        let doc = store.load(name)?;
+        if !doc.path.exists() {
+            return Err(format!("No note '{name}'"));
+        }
+        doc.content
+    };
+
+    let mut stdout = io::stdout().lock();
+    match stdout
+        .write_all(content.as_bytes())
+        .and_then(|()| stdout.flush())
+    {
+        // The reader (`head`, say) has seen enough.
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(|err| format!("Failed to write the note: {err}")),
+    }
+}
+
+fn cmd_index(sort: Option<&str>, notes_dir: &Path) -> Result<(), String> {
+    let page = match sort {
+        Some(sort) => format!("!index:by-{sort}"),
+        None => "!index".to_string(),
+    };
+    cmd_view(Some(page), None, notes_dir)
+}
+
+fn cmd_new(
+    name: &str,
+    template: Option<&str>,
+    from_stdin: bool,
+    edit: bool,
+    notes_dir: &PathBuf,
+) -> Result<(), String> {
+    let name = name.trim();
+    let name = if has_md_extension(name) {
+        &name[..name.len() - 3]
+    } else {
+        name
+    };
+    let valid = !name.starts_with(['!', '/'])
+        && name
+            .split('/')
+            .all(|part| !matches!(part.trim(), "" | "." | ".."));
+    if !valid {
+        return Err(format!("'{name}' is not a valid note name"));
+    }
+
+    let store = open_store(notes_dir);
+    let path = store.path_for(name);
+    if path.exists() {
+        return Err(format!("Note '{name}' already exists"));
+    }
+    let title = name.rsplit('/').next().unwrap_or(name);
+    let content = if from_stdin {
+        let mut content = String::new();
+        io::stdin()
+            .read_to_string(&mut content)
+            .map_err(|e| format!("Failed to read standard input: {e}"))?;
+        content
+    } else {
+        match template {
+            Some(template) => {
+                template::expand(&template::load(notes_dir, template)?, name, today())
+            }
+            None => format!("# {title}\n"),
+        }
+    };
+    store.save(&piki_core::Document {
+        name: name.to_string(),
+        path,
+        content,
+        modified_time: None,
+    })?;
+
+    if edit {
+        cmd_edit(Some(name.to_string()), notes_dir)
+    } else {
+        println!("Created {name}");
+        Ok(())
+    }
+}
+
+/// Today's date in the local time zone.
+fn today() -> Date {
+    let now = chrono::Local::now().date_naive();
+    Date::from_ymd(i64::from(now.year()), now.month(), now.day())
+        .expect("chrono produced an invalid date")
+}
+
+fn cmd_random(folder: Option<String>, name_only: bool, notes_dir: &Path) -> Result<(), String> {
+    let store = open_store(notes_dir);
+    let folder = folder.unwrap_or_default();
+    let Some(name) = piki_core::random_note(&store, &folder)? else {
+        return Err(if folder.is_empty() {
+            "No notes found".to_string()
+        } else {
+            format!("No notes found in '{folder}'")
+        });
+    };
+    if name_only {
+        println!("{name}");
+        Ok(())
+    } else {
+        cmd_view(Some(name), None, notes_dir)
+    }
+}
+
+/// How `piki todo` shows the todos.
+enum TodoOutput {
+    /// The `!todo` page, in the pager
+    Page,
+    Json,
+    /// A list to go through and tick off, see [`todo_interactive`]
+    Interactive,
+}
+
+/// `piki todo`, listing the todos that are `checked` or not, if given.
+fn cmd_todo(
+    due: Option<String>,
+    checked: Option<bool>,
+    page: Option<String>,
+    tag: Option<String>,
+    output: TodoOutput,
+    notes_dir: &Path,
+) -> Result<(), String> {
+    if [&page, &tag]
+        .into_iter()
+        .flatten()
+        .any(|arg| arg.contains(','))
+    {
+        return Err("Page patterns and tags can't contain commas".to_string());
+    }
+    // Resolved here, in the local time zone, and handed on as a date.
+    let until = due
+        .map(|range| {
+            dates::due_by(&range, today())
+                .ok_or_else(|| format!("Unknown due date range '{range}'"))
+        })
+        .transpose()?;
+    // The filters, as arguments of the `todo` plugin.
+    let args = [
+        checked.map(|done| if done { "done" } else { "open" }.to_string()),
+        until.map(|until| format!("due={until}")),
+        page.map(|page| format!("page={page}")),
+        tag.map(|tag| format!("tag={tag}")),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(",");
+    match output {
+        TodoOutput::Json => {
+            let store = open_store(notes_dir);
+            let mut todos = todo_cache::scan(&store)?;
+            TodoFilter::parse(&args, Some(today()))?.apply(&store, &mut todos);
+            print!("{}", json::todos(&store, &todos));
+            Ok(())
+        }
+        TodoOutput::Interactive => {
+            todo_interactive(&TodoFilter::parse(&args, Some(today()))?, notes_dir)
+        }
+        TodoOutput::Page => {
+            let page = if args.is_empty() {
+                "!todo".to_string()
+            } else {
+                format!("!todo:{args}")
+            };
+            cmd_view(Some(page), None, notes_dir)
+        }
+    }
 }
 
-fn cmd_todo(notes_dir: &Path) -> Result<(), String> {
-    cmd_view(Some("!todo".to_string()), notes_dir)
+/// Raw mode and the alternate screen of the terminal, for as long as this
+/// lives.
+struct FullScreen;
+
+impl FullScreen {
+    fn enter() -> Result<Self, String> {
+        terminal::enable_raw_mode().map_err(|e| format!("Failed to set up the terminal: {e}"))?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)
+            .map_err(|e| format!("Failed to set up the terminal: {e}"))?;
+        Ok(FullScreen)
+    }
+}
+
+impl Drop for FullScreen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// `piki todo --interactive`: the todos `filter` lets through, one per line,
+/// to move through with the arrow keys. Space ticks a todo off, or unticks
+/// it, right in its note; Enter opens the note in the editor.
+fn todo_interactive(filter: &TodoFilter, notes_dir: &Path) -> Result<(), String> {
+    if !io::stdout().is_terminal() {
+        return Err("piki todo --interactive needs a terminal".to_string());
+    }
+    let store = open_store(notes_dir);
+    // Each todo as its note, line number and line.
+    let scan = |store: &DocumentStore| -> Result<Vec<(String, usize, String)>, String> {
+        let mut notes = todo_cache::scan(store)?;
+        filter.apply(store, &mut notes);
+        Ok(notes
+            .into_iter()
+            .flat_map(|(note, todos)| {
+                todos
+                    .into_iter()
+                    .map(move |(line, todo)| (note.clone(), line, todo))
+            })
+            .collect())
+    };
+    let mut todos = scan(&store)?;
+    let mut selected = 0;
+    let mut top = 0;
+    let mut message = String::new();
+
+    let mut screen = Some(FullScreen::enter()?);
+    loop {
+        let (width, height) = terminal::size().unwrap_or((80, 24));
+        let (width, rows) = (width as usize, (height as usize).saturating_sub(2).max(1));
+        selected = selected.min(todos.len().saturating_sub(1));
+        top = top.min(selected).max((selected + 1).saturating_sub(rows));
+        draw_todos(&todos, selected, top, rows, width, &message)
+            .map_err(|e| format!("Failed to draw the todos: {e}"))?;
+        message.clear();
+
+        let Event::Key(key) = event::read().map_err(|e| format!("Failed to read a key: {e}"))?
+        else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => break,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+            KeyCode::Up | KeyCode::Char('k') => selected = selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => selected += 1,
+            KeyCode::PageUp => selected = selected.saturating_sub(rows),
+            KeyCode::PageDown => selected += rows,
+            KeyCode::Home | KeyCode::Char('g') => selected = 0,
+            KeyCode::End | KeyCode::Char('G') => selected = todos.len(),
+            KeyCode::Char(' ') => {
+                let Some((note, line, todo)) = todos.get_mut(selected) else {
+                    continue;
+                };
+                // The list isn't scanned again, so that a todo ticked off
+                // stays in it, even when only open ones are listed.
+                let result = store.load(note).and_then(|doc| {
+                    let content = piki_core::toggle_todo(&doc.content, *line)
+                        .ok_or_else(|| format!("Line {line} of '{note}' is no todo anymore"))?;
+                    let toggled = content.lines().nth(*line - 1).unwrap_or_default();
+                    let toggled = toggled.to_string();
+                    store.save(&piki_core::Document { content, ..doc })?;
+                    Ok(toggled)
+                });
+                match result {
+                    Ok(toggled) => *todo = toggled,
+                    Err(e) => message = e,
+                }
+            }
+            KeyCode::Enter => {
+                let Some((note, _, _)) = todos.get(selected) else {
+                    continue;
+                };
+                let note = note.clone();
+                // The editor gets the terminal to itself.
+                drop(screen.take());
+                let edited = cmd_edit(Some(note), &notes_dir.to_path_buf());
+                screen = Some(FullScreen::enter()?);
+                if let Err(e) = edited {
+                    message = e;
+                }
+                todos = scan(&store)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Draw the list of `todos` for [`todo_interactive`]: `rows` of them from
+/// `top` on, with the `selected` one highlighted, below a line of help and
+/// above `message`.
+fn draw_todos(
+    todos: &[(String, usize, String)],
+    selected: usize,
+    top: usize,
+    rows: usize,
+    width: usize,
+    message: &str,
+) -> io::Result<()> {
+    let clip = |text: &str, width: usize| text.chars().take(width).collect::<String>();
+    let mut out = io::stdout();
+    queue!(
+        out,
+        terminal::Clear(terminal::ClearType::All),
+        cursor::MoveTo(0, 0)
+    )?;
+    let help = format!(
+        "{} todos — ↑↓ move, space ticks off, enter edits the note, q quits",
+        todos.len()
+    );
+    queue!(out, style::Print(clip(&help, width)))?;
+    if todos.is_empty() {
+        queue!(out, cursor::MoveTo(0, 2), style::Print("No todos found."))?;
+    }
+    for (row, (index, (note, line, todo))) in
+        todos.iter().enumerate().skip(top).take(rows).enumerate()
+    {
+        let location = format!("  {note}:{line}");
+        let text = clip(todo.trim(), width.saturating_sub(location.chars().count()));
+        let padding = width.saturating_sub(text.chars().count() + location.chars().count());
+        let line = format!("{text}{}{C_NAME}{location}{C_RESET}", " ".repeat(padding));
+        queue!(out, cursor::MoveTo(0, row as u16 + 1))?;
+        if index == selected {
+            queue!(
+                out,
+                style::Print("\x1b[7m"),
+                style::Print(line),
+                style::Print(C_RESET)
+            )?;
+        } else {
+            queue!(out, style::Print(line))?;
+        }
+    }
+    if !message.is_empty() {
+        queue!(
+            out,
+            cursor::MoveTo(0, rows as u16 + 1),
+            style::Print(format!("{C_MATCH}{}{C_RESET}", clip(message, width)))
+        )?;
+    }
+    out.flush()
 }
 
 fn print_help_with_aliases(config: &Config) {
     println!("piki - a simple personal wiki");
     println!();
-    println!("Usage: piki [-d DIRECTORY] [COMMAND]");
+    println!("Usage: piki [-d DIRECTORY | -w WIKI] [--json] [COMMAND]");
     println!();
     println!("If no command is given the note to edit can be selected interactively.");
     println!();
@@ -760,17 +3091,49 @@ fn print_help_with_aliases(config: &Config) {
     println!(
         "  -d, --directory DIRECTORY - Directory containing markdown files (default: ~/.piki)"
     );
+    println!("  -w, --wiki NAME - use the wiki of that name in the [wikis] section of ~/.pikirc");
+    println!("  --json - print JSON (ls, todo, log, search, backlinks)");
     println!();
     println!("Commands:");
+    println!("  backlinks [name] - list the lines of other notes linking to a note");
+    println!("  cat [name]  - print a note's Markdown as it is");
+    println!("  check       - list links leading to no note, file or heading");
+    println!("  config export [-o FILE] - export settings, templates and plugins");
+    println!("  config import [file] [--force] - install exported settings");
+    println!("  decrypt [name] - store an encrypted note as plain Markdown again");
+    println!("  diff [name] [commit] [--tool [TOOL]] - show a note's changes since HEAD or COMMIT");
     println!("  edit [name] - edit a note");
+    println!("  encrypt [name] - encrypt a note with a passphrase");
+    println!(
+        "  export [name|--all] [--format pdf|html|epub|txt|md] [--wrap COLUMN] [--backlinks] [-o FILE] [--output-dir DIR] [--html-template FILE] - export notes"
+    );
+    println!("  graph [--format dot|json] [-o FILE] - print the graph of links between notes");
     println!("  help        - show this help");
-    println!("  index       - generate an index of all notes");
+    println!("  history [name] [-n NUM] - show the commits that changed a note");
+    println!("  import [path] --from obsidian|notion - import another wiki's export");
+    println!("  index [--sort folder|name|date] - generate an index of all notes");
     println!("  log         - show the commit log");
-    println!("  ls          - list notes");
+    println!(
+        "  ls [pattern] [-l] [--sort name|mtime|size] [-r] [--tree] - list notes, or those matching a glob"
+    );
+    println!("  new [name] [--template NAME] [--edit] - create a note");
+    println!("  new [name] - | --stdin - create a note from standard input (alias: add)");
+    println!("  random [folder] [--name] - view a randomly picked note");
+    println!(
+        "  replace [pattern] [replacement] [--regex] [--dry-run] [--interactive] - replace text in all notes"
+    );
+    println!("  restore [name] [commit] [--force] - put back a note's version from a commit");
     println!("  run [cmd]   - run a shell command inside the notes directory");
     println!("  search [terms] - full-text search notes (all terms must match)");
-    println!("  todo        - list all todos from all notes");
-    println!("  view [name] - view a note");
+    println!("  section [name] [heading] [--raw] - print one section of a note");
+    println!("  spellcheck [name] - spellcheck a note, or all notes");
+    println!("  sync [-m MESSAGE] - pull and push the notes' git repository");
+    println!("  tag rename [old] [new] [--dry-run] - rename a tag in all notes");
+    println!(
+        "  todo [--due RANGE] [--open|--done] [--page GLOB] [--tag TAG] [-i] - list all todos from all notes, or those due by RANGE, open or done, in matching notes or tagged; -i: tick them off in a list"
+    );
+    println!("  tui [name]  - browse the notes, with a list of them beside the page");
+    println!("  view [name] [--format ansi|plain|html|json] - view a note");
 
     if !config.aliases.is_empty() {
         println!();
@@ -781,6 +3144,36 @@ fn print_help_with_aliases(config: &Config) {
             println!("  {} => {}", alias, command);
         }
     }
+
+    if !config.wikis.is_empty() {
+        println!();
+        println!("Wikis:");
+        for (name, dir) in &config.wikis {
+            println!("  {} => {}", name, dir);
+        }
+    }
+}
+
+/// The position of the alias in `raw_args`, if the first argument that isn't
+/// an option names one.
+fn alias_position(raw_args: &[String], config: &Config) -> Option<usize> {
+    // Skip program name and any -d/--directory or -w/--wiki options
+    let mut skip_next = false;
+    for (position, arg) in raw_args.iter().enumerate().skip(1) {
+        if skip_next {
+            skip_next = false;
+            continue;
+        }
+        if arg == "-d" || arg == "--directory" || arg == "-w" || arg == "--wiki" {
+            skip_next = true;
+            continue;
+        }
+        if arg.starts_with("-") {
+            continue;
+        }
+        return config.aliases.contains_key(arg).then_some(position);
+    }
+    None
 }
 
 fn main() {
@@ -797,9 +3190,21 @@ fn main() {
         }
     }
 
-    // Parse arguments to get the directory option and other args
-    let args = Args::parse();
-    let notes_dir = get_notes_dir(args.directory.clone());
+    // Parse arguments to get the directory option and other args. When an
+    // alias is run, only the options before it are piki's; the arguments
+    // after it belong to the alias.
+    let alias = alias_position(&raw_args, &config);
+    let args = match alias {
+        Some(position) => Args::parse_from(&raw_args[..position]),
+        None => Args::parse(),
+    };
+    let notes_dir = match &args.wiki {
+        Some(name) => wikis::directory(&config.wikis, name).unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }),
+        None => get_notes_dir(args.directory.clone()),
+    };
 
     // Ensure notes directory exists
     if !notes_dir.exists()
@@ -813,34 +3218,16 @@ fn main() {
         std::process::exit(1);
     }
 
-    // Check if first non-option argument is an alias
-    // Skip program name and any -d/--directory options
-    let mut first_positional = None;
-    let mut skip_next = false;
-    for arg in raw_args.iter().skip(1) {
-        if skip_next {
-            skip_next = false;
-            continue;
-        }
-        if arg == "-d" || arg == "--directory" {
-            skip_next = true;
-            continue;
-        }
-        if arg.starts_with("-d=") || arg.starts_with("--directory=") || arg.starts_with("-") {
-            continue;
-        }
-        first_positional = Some(arg.as_str());
-        break;
-    }
-
-    // Check if first positional argument is an alias
-    if let Some(potential_alias) = first_positional
-        && let Some(alias_cmd) = config.aliases.get(potential_alias)
-    {
-        // Execute the alias as a shell command in the notes directory
+    if let Some(position) = alias {
+        let potential_alias = &raw_args[position];
+        let alias_cmd = &config.aliases[potential_alias];
+        // Execute the alias as a shell command in the notes directory, with
+        // the remaining arguments as $1, $2, … and "$@"
         let status = Command::new("sh")
             .arg("-c")
             .arg(alias_cmd)
+            .arg(potential_alias)
+            .args(&raw_args[position + 1..])
             .current_dir(&notes_dir)
             .stdin(Stdio::inherit())
             .stdout(Stdio::inherit())
@@ -857,14 +3244,156 @@ fn main() {
     }
 
     let result = match args.command {
+        Some(Commands::Doctor {
+            enable_edit_log,
+            disable_edit_log,
+            check_external,
+        }) => cmd_doctor(
+            enable_edit_log,
+            disable_edit_log,
+            check_external,
+            &notes_dir,
+        ),
+        Some(Commands::Backlinks { name }) => cmd_backlinks(&name, args.json, &notes_dir),
+        Some(Commands::Cat { name }) => cmd_cat(&name, &notes_dir),
+        Some(Commands::Config {
+            command: ConfigCommand::Export { output },
+        }) => cmd_config_export(output.as_deref(), &notes_dir),
+        Some(Commands::Config {
+            command: ConfigCommand::Import { file, force },
+        }) => cmd_config_import(&file, force, &notes_dir),
+        Some(Commands::Check) => cmd_check(&notes_dir),
+        Some(Commands::Decrypt { name }) => cmd_decrypt(&name, &notes_dir),
+        Some(Commands::Diff { name, commit, tool }) => tool
+            .map(|tool| config.diff.tool(&tool))
+            .transpose()
+            .and_then(|tool| cmd_diff(&name, &commit, tool, &notes_dir)),
         Some(Commands::Edit { name }) => cmd_edit(name, &notes_dir),
-        Some(Commands::Index) => cmd_index(&notes_dir),
-        Some(Commands::View { name }) => cmd_view(name, &notes_dir),
-        Some(Commands::Ls) => cmd_ls(&notes_dir),
-        Some(Commands::Log { count }) => cmd_log(count, &notes_dir),
+        Some(Commands::Encrypt { name }) => cmd_encrypt(&name, &notes_dir),
+        Some(Commands::Export {
+            name,
+            all: _,
+            format,
+            output,
+            output_dir,
+            wrap,
+            backlinks,
+            html_template,
+            keep_private,
+            include_encrypted,
+        }) => match html_template.or(config.export.html_template.clone()) {
+            Some(path) => fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e)),
+            None => Ok(export::DEFAULT_HTML_TEMPLATE.to_string()),
+        }
+        .and_then(|html_template| {
+            let format = if format == "markdown" {
+                "md".to_string()
+            } else {
+                format
+            };
+            let options = ExportOptions {
+                format,
+                wrap_column: wrap.or(config.export.wrap),
+                backlinks: backlinks || config.export.backlinks,
+                html_template,
+                redaction: (!keep_private).then(|| config.redact.rules()),
+                include_encrypted,
+            };
+            cmd_export(name, output, output_dir, options, &notes_dir)
+        }),
+        Some(Commands::Graph { format, output }) => cmd_graph(&format, output, &notes_dir),
+        Some(Commands::Images {
+            compress,
+            max_width,
+        }) => cmd_images(compress, max_width, &notes_dir),
+        Some(Commands::History { name, count }) => cmd_history(&name, count, &notes_dir),
+        Some(Commands::Import { path, from }) => cmd_import(&path, &from, &notes_dir),
+        Some(Commands::Index { sort }) => cmd_index(sort.as_deref(), &notes_dir),
+        Some(Commands::View { name, format }) => cmd_view(name, format.as_deref(), &notes_dir),
+        Some(Commands::Tui { name }) => tui::run(name, &notes_dir),
+        Some(Commands::Ls {
+            pattern,
+            long,
+            sort,
+            reverse,
+            tree,
+        }) => cmd_ls(
+            pattern.as_deref(),
+            LsFormat {
+                long,
+                tree,
+                json: args.json,
+            },
+            sort.as_deref(),
+            reverse,
+            &notes_dir,
+        ),
+        Some(Commands::Log { count }) => cmd_log(count, args.json, &notes_dir),
+        Some(Commands::New {
+            name,
+            source,
+            template,
+            stdin,
+            edit,
+        }) => cmd_new(
+            &name,
+            template.as_deref(),
+            stdin || source.is_some(),
+            edit,
+            &notes_dir,
+        ),
+        Some(Commands::Random { folder, name }) => cmd_random(folder, name, &notes_dir),
+        Some(Commands::Replace {
+            pattern,
+            replacement,
+            regex,
+            dry_run,
+            interactive,
+        }) => cmd_replace(
+            &pattern,
+            &replacement,
+            regex,
+            dry_run,
+            interactive,
+            &notes_dir,
+        ),
+        Some(Commands::Restore {
+            name,
+            commit,
+            force,
+        }) => cmd_restore(&name, &commit, force, &notes_dir),
         Some(Commands::Run { command }) => cmd_run(command, &notes_dir),
-        Some(Commands::Search { terms }) => cmd_search(terms, &notes_dir),
-        Some(Commands::Todo) => cmd_todo(&notes_dir),
+        Some(Commands::Search { terms }) => cmd_search(terms, args.json, &notes_dir),
+        Some(Commands::Section { name, heading, raw }) => {
+            cmd_section(&name, &heading, raw, &notes_dir)
+        }
+        Some(Commands::Spellcheck { name }) => cmd_spellcheck(name, &notes_dir),
+        Some(Commands::Sync { message }) => cmd_sync(
+            message.or(config.sync.commit.clone()).as_deref(),
+            &notes_dir,
+        ),
+        Some(Commands::Tag {
+            command: TagCommand::Rename { old, new, dry_run },
+        }) => cmd_tag_rename(&old, &new, dry_run, &notes_dir),
+        Some(Commands::Todo {
+            due,
+            open,
+            done,
+            page,
+            tag,
+            interactive,
+        }) => {
+            let checked = (open || done).then_some(done);
+            let output = if interactive {
+                TodoOutput::Interactive
+            } else if args.json {
+                TodoOutput::Json
+            } else {
+                TodoOutput::Page
+            };
+            cmd_todo(due, checked, page, tag, output, &notes_dir)
+        }
         None => {
             // Default to edit command, either with provided name or interactive
             cmd_edit(args.name, &notes_dir)
@@ -0,0 +1,377 @@
+//! `piki tui`: the wiki in the terminal, between the pager of `piki view` and
+//! the GUI.
+//!
+//! The notes are listed in a sidebar on the left; the one selected there is
+//! shown rendered, as `piki view` renders it, on the right. Tab switches
+//! between the two. In the page, `n` and `p` pick its links one after another
+//! and Enter follows the one picked, `b` goes back, and `e` opens the page in
+//! the editor.
+
+use super::{
+    C_MATCH, C_RESET, ContentLocation, FullScreen, LinkTarget, cmd_edit, from_heading,
+    normalize_base_path, open_store, parse_note, plugin_registry, render_document_for_width,
+    resolve_link_target,
+};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, queue, style, terminal};
+use piki_core::attachment::open_with_system_handler;
+use piki_core::{DocumentStore, PluginRegistry, encryption, graph, has_md_extension};
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+/// Reverse video, for the selection.
+const C_SELECTED: &str = "\x1b[7m";
+const C_BOLD: &str = "\x1b[1m";
+
+#[derive(PartialEq)]
+enum Focus {
+    Sidebar,
+    Page,
+}
+
+/// The page on the right, rendered.
+struct Page {
+    /// The note's name, or `!` and the plugin's for a generated page,
+    /// followed by `#heading` if it is shown from that heading on
+    name: String,
+    location: ContentLocation,
+    /// The rendered page, line by line
+    lines: Vec<String>,
+    /// The width it was rendered for
+    width: usize,
+    /// The targets of its links to notes, their sections and files, as
+    /// written
+    links: Vec<String>,
+}
+
+struct Tui {
+    store: DocumentStore,
+    plugins: PluginRegistry,
+    notes_dir: PathBuf,
+    canonical_notes_dir: PathBuf,
+    notes: Vec<String>,
+    /// The note selected in the sidebar
+    selected: usize,
+    /// The first note the sidebar shows
+    list_top: usize,
+    page: Option<Page>,
+    /// The first line of the page shown
+    scroll: usize,
+    /// The link of the page picked with `n` and `p`
+    link: Option<usize>,
+    focus: Focus,
+    /// The pages left by following links, for going back
+    back: Vec<String>,
+    message: String,
+}
+
+/// Run `piki tui`, starting at the note `name`, or the front page.
+pub fn run(name: Option<String>, notes_dir: &Path) -> Result<(), String> {
+    if !io::stdout().is_terminal() {
+        return Err("piki tui needs a terminal".to_string());
+    }
+    let store = open_store(notes_dir);
+    let mut notes = store.list_all_documents()?;
+    notes.sort();
+    let start = name
+        .or_else(|| notes.iter().find(|note| *note == "frontpage").cloned())
+        .or_else(|| notes.first().cloned());
+    let mut tui = Tui {
+        store,
+        plugins: plugin_registry(notes_dir),
+        notes_dir: notes_dir.to_path_buf(),
+        canonical_notes_dir: normalize_base_path(notes_dir),
+        notes,
+        selected: 0,
+        list_top: 0,
+        page: None,
+        scroll: 0,
+        link: None,
+        focus: Focus::Sidebar,
+        back: Vec::new(),
+        message: String::new(),
+    };
+
+    let mut screen = Some(FullScreen::enter()?);
+    if let Some(start) = start {
+        tui.show(&start);
+    }
+    loop {
+        tui.draw()
+            .map_err(|e| format!("Failed to draw the page: {e}"))?;
+        let Event::Key(key) = event::read().map_err(|e| format!("Failed to read a key: {e}"))?
+        else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        tui.message.clear();
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => break,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+            KeyCode::Tab | KeyCode::BackTab => {
+                tui.focus = match tui.focus {
+                    Focus::Sidebar => Focus::Page,
+                    Focus::Page => Focus::Sidebar,
+                }
+            }
+            KeyCode::Char('e') => {
+                let Some(name) = tui.page.as_ref().map(|page| page.name.clone()) else {
+                    continue;
+                };
+                if name.starts_with('!') {
+                    tui.message = "Generated pages can't be edited".to_string();
+                    continue;
+                }
+                // The editor gets the terminal to itself.
+                drop(screen.take());
+                let edited = cmd_edit(Some(note_of(&name).to_string()), &tui.notes_dir);
+                screen = Some(FullScreen::enter()?);
+                if let Err(e) = edited {
+                    tui.message = e;
+                }
+                tui.notes = tui.store.list_all_documents()?;
+                tui.notes.sort();
+                tui.show(&name);
+            }
+            KeyCode::Char('b') | KeyCode::Backspace => match tui.back.pop() {
+                Some(name) => tui.show(&name),
+                None => tui.message = "Nothing to go back to".to_string(),
+            },
+            _ if tui.focus == Focus::Sidebar => tui.sidebar_key(key.code),
+            _ => tui.page_key(key.code),
+        }
+    }
+    Ok(())
+}
+
+impl Tui {
+    /// Rows for the sidebar and the page, leaving one for the status line.
+    fn rows() -> usize {
+        let (_, height) = terminal::size().unwrap_or((80, 24));
+        (height as usize).saturating_sub(1).max(1)
+    }
+
+    /// The widths of the sidebar and of the page, with a column between them.
+    fn widths() -> (usize, usize) {
+        let (width, _) = terminal::size().unwrap_or((80, 24));
+        let width = width as usize;
+        let sidebar = (width / 4).clamp(12, 40).min(width / 2);
+        (sidebar, width.saturating_sub(sidebar + 1).max(1))
+    }
+
+    fn sidebar_key(&mut self, code: KeyCode) {
+        let rows = Self::rows();
+        let selected = match code {
+            KeyCode::Up | KeyCode::Char('k') => self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.selected + 1,
+            KeyCode::PageUp => self.selected.saturating_sub(rows),
+            KeyCode::PageDown => self.selected + rows,
+            KeyCode::Home | KeyCode::Char('g') => 0,
+            KeyCode::End | KeyCode::Char('G') => self.notes.len(),
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                self.focus = Focus::Page;
+                return;
+            }
+            _ => return,
+        };
+        let selected = selected.min(self.notes.len().saturating_sub(1));
+        if selected != self.selected
+            && let Some(name) = self.notes.get(selected).cloned()
+        {
+            self.show(&name);
+        }
+    }
+
+    fn page_key(&mut self, code: KeyCode) {
+        let rows = Self::rows();
+        let links = self.page.as_ref().map_or(0, |page| page.links.len());
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll += 1,
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(rows),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.scroll += rows,
+            KeyCode::Home | KeyCode::Char('g') => self.scroll = 0,
+            KeyCode::End | KeyCode::Char('G') => self.scroll = usize::MAX,
+            KeyCode::Left | KeyCode::Char('h') => self.focus = Focus::Sidebar,
+            KeyCode::Char('n') if links > 0 => {
+                self.link = Some(self.link.map_or(0, |link| (link + 1) % links))
+            }
+            KeyCode::Char('p') if links > 0 => {
+                self.link = Some(
+                    self.link
+                        .map_or(links - 1, |link| (link + links - 1) % links),
+                )
+            }
+            KeyCode::Char('n' | 'p') => self.message = "This page has no links".to_string(),
+            KeyCode::Enter => self.follow_link(),
+            _ => {}
+        }
+    }
+
+    /// Show the page `name`, selecting it in the sidebar if it is a note.
+    fn show(&mut self, name: &str) {
+        let (_, width) = Self::widths();
+        match self.load(name, width) {
+            Ok(page) => {
+                self.page = Some(page);
+                self.scroll = 0;
+                self.link = None;
+                if let Some(index) = self.notes.iter().position(|note| note == note_of(name)) {
+                    self.selected = index;
+                }
+            }
+            Err(e) => self.message = e,
+        }
+    }
+
+    fn load(&self, name: &str, width: usize) -> Result<Page, String> {
+        let heading = name.split_once('#').map(|(_, heading)| heading);
+        let (content, location) = match note_of(name).strip_prefix('!') {
+            Some(plugin) => (
+                self.plugins
+                    .generate(plugin, &self.store)
+                    .map_err(|err| format!("Error generating plugin '{plugin}': {err}"))?,
+                ContentLocation::Plugin,
+            ),
+            None => {
+                let doc = self.store.load(note_of(name))?;
+                let path = fs::canonicalize(&doc.path).unwrap_or(doc.path);
+                (doc.content, ContentLocation::File(path))
+            }
+        };
+        let content = from_heading(&content, heading);
+        let rendered = render_document_for_width(&parse_note(content)?, width)?;
+        Ok(Page {
+            name: name.to_string(),
+            location,
+            lines: rendered.lines().map(str::to_string).collect(),
+            width,
+            links: graph::internal_links(content)
+                .into_iter()
+                .map(|(_, target)| target)
+                .collect(),
+        })
+    }
+
+    /// Go to the page the picked link points to.
+    fn follow_link(&mut self) {
+        let Some(page) = &self.page else {
+            return;
+        };
+        let Some(target) = self.link.and_then(|link| page.links.get(link)) else {
+            self.message = "Pick a link with n or p first".to_string();
+            return;
+        };
+        let name = match resolve_link_target(
+            &self.notes_dir,
+            &self.canonical_notes_dir,
+            &page.location,
+            target,
+            &self.plugins,
+        ) {
+            Some(LinkTarget::File(path)) => path
+                .strip_prefix(&self.canonical_notes_dir)
+                .ok()
+                .and_then(|path| path.to_str())
+                .map(|path| path.strip_suffix(encryption::EXTENSION).unwrap_or(path))
+                .filter(|path| has_md_extension(path))
+                .map(|path| match target.split_once('#') {
+                    Some((_, heading)) => format!("{}#{heading}", &path[..path.len() - 3]),
+                    None => path[..path.len() - 3].to_string(),
+                }),
+            Some(LinkTarget::Plugin(plugin)) => Some(format!("!{plugin}")),
+            Some(LinkTarget::Attachment(path)) => {
+                if let Err(e) = open_with_system_handler(&path) {
+                    self.message = e;
+                }
+                return;
+            }
+            None => None,
+        };
+        match name {
+            Some(name) => {
+                self.back.push(page.name.clone());
+                self.show(&name);
+            }
+            None => self.message = format!("No page '{target}'"),
+        }
+    }
+
+    fn draw(&mut self) -> io::Result<()> {
+        let rows = Self::rows();
+        let (sidebar, width) = Self::widths();
+        if let Some(name) = self
+            .page
+            .as_ref()
+            .filter(|page| page.width != width)
+            .map(|page| page.name.clone())
+        {
+            // Rendered for another width before the terminal was resized.
+            let (scroll, link) = (self.scroll, self.link);
+            self.show(&name);
+            (self.scroll, self.link) = (scroll, link);
+        }
+        let lines = self.page.as_ref().map_or(0, |page| page.lines.len());
+        self.scroll = self.scroll.min(lines.saturating_sub(rows));
+        self.selected = self.selected.min(self.notes.len().saturating_sub(1));
+        self.list_top = self
+            .list_top
+            .min(self.selected)
+            .max((self.selected + 1).saturating_sub(rows));
+
+        let clip = |text: &str, width: usize| text.chars().take(width).collect::<String>();
+        let mut out = io::stdout();
+        queue!(out, terminal::Clear(terminal::ClearType::All))?;
+        for row in 0..rows {
+            queue!(out, cursor::MoveTo(0, row as u16))?;
+            let index = self.list_top + row;
+            if let Some(note) = self.notes.get(index) {
+                let note = format!("{:sidebar$}", clip(note, sidebar));
+                let color = match (index == self.selected, &self.focus) {
+                    (true, Focus::Sidebar) => C_SELECTED,
+                    (true, Focus::Page) => C_BOLD,
+                    (false, _) => "",
+                };
+                queue!(out, style::Print(format!("{color}{note}{C_RESET}")))?;
+            }
+            queue!(
+                out,
+                cursor::MoveTo(sidebar as u16, row as u16),
+                style::Print("│")
+            )?;
+            if let Some(line) = self
+                .page
+                .as_ref()
+                .and_then(|page| page.lines.get(self.scroll + row))
+            {
+                queue!(out, style::Print(line), style::Print(C_RESET))?;
+            }
+        }
+
+        let (width, _) = terminal::size().unwrap_or((80, 24));
+        let link = self
+            .page
+            .as_ref()
+            .zip(self.link)
+            .and_then(|(page, link)| Some((link, page.links.len(), page.links.get(link)?)));
+        let status = if !self.message.is_empty() {
+            format!("{C_MATCH}{}{C_RESET}", clip(&self.message, width as usize))
+        } else if let Some((link, links, target)) = link {
+            let text = format!("Link {}/{links}: {target} — Enter follows it", link + 1);
+            clip(&text, width as usize)
+        } else {
+            let help = "↑↓ move, Tab switches sides, n/p pick a link, b back, e edit, q quit";
+            clip(help, width as usize)
+        };
+        queue!(out, cursor::MoveTo(0, rows as u16), style::Print(status))?;
+        out.flush()
+    }
+}
+
+/// The note of the page `name`, without `#heading`.
+fn note_of(name: &str) -> &str {
+    name.split_once('#').map_or(name, |(note, _)| note)
+}
@@ -0,0 +1,54 @@
+//! `!todo` on a wiki of 10,000 notes must come back within 100ms once the
+//! todo cache is warm.
+//!
+//! Run with `cargo bench -p piki-core --bench todo_scan`; exits with an error
+//! if the budget is exceeded.
+
+use std::fs;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use piki_core::{DocumentStore, Plugin, TodoPlugin};
+
+const NOTES: usize = 10_000;
+const FOLDERS: usize = 20;
+const RUNS: u32 = 10;
+const BUDGET: Duration = Duration::from_millis(100);
+
+fn main() -> ExitCode {
+    let dir = std::env::temp_dir().join(format!("piki-bench-todo-scan-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    for folder in 0..FOLDERS {
+        fs::create_dir_all(dir.join(format!("folder-{folder}"))).unwrap();
+    }
+    let paragraph = "Some prose about the project, long enough to look like a real note. ";
+    for i in 0..NOTES {
+        let mut content = format!("# Note {i}\n\n{}\n", paragraph.repeat(20));
+        if i % 10 == 0 {
+            content.push_str("\n- [ ] follow up\n- [x] drafted\n");
+        }
+        let path = dir.join(format!("folder-{}/note-{i}.md", i % FOLDERS));
+        fs::write(path, content).unwrap();
+    }
+    let store = DocumentStore::new(dir.clone());
+
+    let start = Instant::now();
+    let page = TodoPlugin.generate_content(&store, "").unwrap();
+    let cold = start.elapsed();
+    assert!(page.contains(&format!("{} notes", NOTES / 10)), "{page}");
+
+    let mut warm = Duration::ZERO;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        TodoPlugin.generate_content(&store, "").unwrap();
+        warm = warm.max(start.elapsed());
+    }
+    fs::remove_dir_all(&dir).ok();
+
+    println!("!todo on {NOTES} notes: {cold:?} cold, {warm:?} warm (slowest of {RUNS})");
+    if warm > BUDGET {
+        eprintln!("warm scan exceeds the budget of {BUDGET:?}");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,58 @@
+//! Opening non-markdown files (images, PDFs, ...) that live next to the notes.
+//!
+//! Piki never tries to render attachments itself. Links to them are resolved
+//! through [`DocumentStore::attachment_path`](crate::DocumentStore::attachment_path)
+//! and then handed to the platform's default application, the same way a file
+//! manager would open them. Web links go to the default browser the same way.
+
+use std::ffi::OsStr;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Open `path` with the system's default handler for its file type.
+///
+/// This spawns `open` on macOS, `start` on Windows and `xdg-open` everywhere
+/// else, and returns as soon as the handler has been launched — it does not
+/// wait for the viewer to exit.
+pub fn open_with_system_handler(path: &Path) -> Result<(), String> {
+    spawn_system_open(path.as_os_str())
+        .map_err(|e| format!("Failed to open '{}': {}", path.display(), e))
+}
+
+/// Open `url` in the system's default browser (or mail program, for
+/// `mailto:` links), like [`open_with_system_handler`] does for files.
+pub fn open_url(url: &str) -> Result<(), String> {
+    spawn_system_open(OsStr::new(url)).map_err(|e| format!("Failed to open {url}: {e}"))
+}
+
+fn spawn_system_open(target: &OsStr) -> std::io::Result<()> {
+    system_open_command(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+#[cfg(target_os = "macos")]
+fn system_open_command(target: &OsStr) -> Command {
+    let mut command = Command::new("open");
+    command.arg(target);
+    command
+}
+
+#[cfg(target_os = "windows")]
+fn system_open_command(target: &OsStr) -> Command {
+    // Not `cmd /C start`: cmd would parse `&`, `|` and friends in the target
+    // as commands of its own. Explorer takes it as a plain argument.
+    let mut command = Command::new("explorer");
+    command.arg(target);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn system_open_command(target: &OsStr) -> Command {
+    let mut command = Command::new("xdg-open");
+    command.arg(target);
+    command
+}
@@ -0,0 +1,411 @@
+//! Calendar dates for due dates: `YYYY-MM-DD` parsing, dates written the way
+//! people say them ("tomorrow", "next friday", "in 2 weeks", "oct 23") and
+//! relative hints ("in 3 days", "overdue").
+//!
+//! A due date is written into a note as `@due(2025-06-01)` or
+//! `📅 2025-06-01`; [`find_due_dates`] finds both.
+
+use std::fmt;
+use std::ops::Range;
+
+const DUE_PREFIX: &str = "@due(";
+const DUE_EMOJI: char = '📅';
+
+const WEEKDAYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// A day in the (proleptic Gregorian) calendar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    /// Days since 1970-01-01.
+    days: i64,
+}
+
+impl Date {
+    /// The date `year`-`month`-`day`, if there is such a day.
+    pub fn from_ymd(year: i64, month: u32, day: u32) -> Option<Date> {
+        ((1..=12).contains(&month) && day >= 1 && day <= days_in_month(year, month)).then(|| Date {
+            days: days_from_civil(year, month, day),
+        })
+    }
+
+    /// A `YYYY-MM-DD` date.
+    pub fn parse(text: &str) -> Option<Date> {
+        let bytes = text.as_bytes();
+        if bytes.len() != 10
+            || bytes[4] != b'-'
+            || bytes[7] != b'-'
+            || !text[..4].bytes().all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+        Date::from_ymd(
+            text[..4].parse().ok()?,
+            text[5..7].parse().ok()?,
+            text[8..].parse().ok()?,
+        )
+    }
+
+    /// The year, month (1–12) and day (1–31).
+    pub fn ymd(self) -> (i64, u32, u32) {
+        civil_from_days(self.days)
+    }
+
+    /// The day of the week, from 0 for Monday to 6 for Sunday.
+    pub fn weekday(self) -> u32 {
+        // 1970-01-01 was a Thursday.
+        (self.days + 3).rem_euclid(7) as u32
+    }
+
+    /// The English name of the day of the week, e.g. `Friday`.
+    pub fn weekday_name(self) -> String {
+        capitalized(WEEKDAYS[self.weekday() as usize])
+    }
+
+    /// The date `days` days later (or earlier, if negative).
+    pub fn add_days(self, days: i64) -> Date {
+        Date {
+            days: self.days + days,
+        }
+    }
+
+    /// The same day `months` months later, or the last day of that month if
+    /// it is shorter.
+    pub fn add_months(self, months: i64) -> Date {
+        let (year, month, day) = self.ymd();
+        let index = year * 12 + i64::from(month) - 1 + months;
+        let (year, month) = (index.div_euclid(12), index.rem_euclid(12) as u32 + 1);
+        let day = day.min(days_in_month(year, month));
+        Date {
+            days: days_from_civil(year, month, day),
+        }
+    }
+
+    /// Days from `self` to `other`; negative if `other` is earlier.
+    pub fn days_until(self, other: Date) -> i64 {
+        other.days - self.days
+    }
+
+    /// The date `text` describes, relative to `today`:
+    ///
+    /// - `2025-06-01`, `june 1`, `1 jun 2025` (without a year: the next one)
+    /// - `today`, `tomorrow`, `yesterday`
+    /// - `friday`, `this fri` (the next Friday after today), `next friday`
+    ///   (Friday of next week)
+    /// - `in 3 days`, `in a week`, `in 2 months`, `next week` (its Monday),
+    ///   `next month` (its first day), `end of month`
+    pub fn parse_natural(text: &str, today: Date) -> Option<Date> {
+        let text = text.trim().to_lowercase();
+        if let Some(date) = Date::parse(&text) {
+            return Some(date);
+        }
+        let words: Vec<&str> = text
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|w| !w.is_empty())
+            .collect();
+
+        match words.as_slice() {
+            ["today"] => Some(today),
+            ["tomorrow"] | ["tmrw"] => Some(today.add_days(1)),
+            ["yesterday"] => Some(today.add_days(-1)),
+            ["next", "week"] => Some(today.add_days(7 - i64::from(today.weekday()))),
+            ["next", "month"] => {
+                let (year, month, _) = today.add_months(1).ymd();
+                Date::from_ymd(year, month, 1)
+            }
+            ["next", "year"] => Date::from_ymd(today.ymd().0 + 1, 1, 1),
+            ["end", "of", "month"] => {
+                let (year, month, _) = today.ymd();
+                Date::from_ymd(year, month, days_in_month(year, month))
+            }
+            ["in", count, unit] => {
+                let count: i64 = match *count {
+                    "a" | "an" | "one" => 1,
+                    n => n.parse().ok()?,
+                };
+                match unit.trim_end_matches('s') {
+                    "day" => Some(today.add_days(count)),
+                    "week" => Some(today.add_days(7 * count)),
+                    "month" => Some(today.add_months(count)),
+                    "year" => Some(today.add_months(12 * count)),
+                    _ => None,
+                }
+            }
+            [day] => {
+                let weekday = weekday_from_name(day)?;
+                let ahead = (i64::from(weekday) - i64::from(today.weekday()) - 1).rem_euclid(7);
+                Some(today.add_days(ahead + 1))
+            }
+            ["this", day] => Date::parse_natural(day, today),
+            ["next", day] => {
+                let weekday = weekday_from_name(day)?;
+                let monday = today.add_days(7 - i64::from(today.weekday()));
+                Some(monday.add_days(i64::from(weekday)))
+            }
+            [a, b] | [a, b, _] => {
+                let (month, day) = match (month_from_name(a), month_from_name(b)) {
+                    (Some(month), None) => (month, day_number(b)?),
+                    (None, Some(month)) => (month, day_number(a)?),
+                    _ => return None,
+                };
+                match words.get(2) {
+                    Some(year) => Date::from_ymd(year.parse().ok()?, month, day),
+                    None => {
+                        let year = today.ymd().0;
+                        Date::from_ymd(year, month, day)
+                            .filter(|date| *date >= today)
+                            .or_else(|| Date::from_ymd(year + 1, month, day))
+                    }
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// How far `self` is from `today`: `today`, `tomorrow`, `in 3 days`,
+    /// `in 2 weeks`, `in 4 months`, and `yesterday`, `3 days ago`, … for past
+    /// dates.
+    pub fn relative_to(self, today: Date) -> String {
+        let days = today.days_until(self);
+        let (amount, unit) = match days.abs() {
+            0 => return "today".to_string(),
+            1 if days > 0 => return "tomorrow".to_string(),
+            1 => return "yesterday".to_string(),
+            n @ 2..=13 => (n, "day"),
+            n @ 14..=59 => (n / 7, "week"),
+            n @ 60..=729 => (n / 30, "month"),
+            n => (n / 365, "year"),
+        };
+        let plural = if amount == 1 { "" } else { "s" };
+        if days > 0 {
+            format!("in {amount} {unit}{plural}")
+        } else {
+            format!("{amount} {unit}{plural} ago")
+        }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = self.ymd();
+        write!(f, "{year:04}-{month:02}-{day:02}")
+    }
+}
+
+/// The hint shown next to a due date: `overdue` once it has passed,
+/// otherwise how far away it is ([`Date::relative_to`]).
+pub fn due_hint(due: Date, today: Date) -> String {
+    if due < today {
+        "overdue".to_string()
+    } else {
+        due.relative_to(today)
+    }
+}
+
+/// The last day a due date filter like `piki todo --due this-week` lets
+/// through, counted from `today`: `overdue` (up to yesterday), `today`,
+/// `this-week` and `next-week` (up to their Sunday), `this-month`, or any date
+/// [`Date::parse_natural`] understands. Overdue dates are always included.
+pub fn due_by(range: &str, today: Date) -> Option<Date> {
+    let range = range.trim().to_lowercase();
+    let sunday = today.add_days(6 - i64::from(today.weekday()));
+    match range.replace('-', " ").as_str() {
+        "overdue" => Some(today.add_days(-1)),
+        "this week" | "week" => Some(sunday),
+        "next week" => Some(sunday.add_days(7)),
+        "this month" | "month" => Date::parse_natural("end of month", today),
+        _ => Date::parse_natural(&range, today),
+    }
+}
+
+/// The due date annotations in `text` (`@due(YYYY-MM-DD)` or
+/// `📅 YYYY-MM-DD`): the byte range of each annotation and its date.
+pub fn find_due_dates(text: &str) -> Vec<(Range<usize>, Date)> {
+    let mut found = Vec::new();
+    for (start, _) in text.match_indices(DUE_PREFIX) {
+        let inner = start + DUE_PREFIX.len();
+        if let Some(len) = text[inner..].find(')')
+            && let Some(date) = Date::parse(text[inner..inner + len].trim())
+        {
+            found.push((start..inner + len + 1, date));
+        }
+    }
+    for (start, _) in text.match_indices(DUE_EMOJI) {
+        let after = start + DUE_EMOJI.len_utf8();
+        let date_start = after + (text[after..].len() - text[after..].trim_start().len());
+        if let Some(date) = text.get(date_start..date_start + 10).and_then(Date::parse) {
+            found.push((start..date_start + 10, date));
+        }
+    }
+    found.sort_by_key(|(range, _)| range.start);
+    found
+}
+
+fn weekday_from_name(name: &str) -> Option<u32> {
+    (name.len() >= 3)
+        .then(|| WEEKDAYS.iter().position(|day| day.starts_with(name)))
+        .flatten()
+        .map(|index| index as u32)
+}
+
+fn month_from_name(name: &str) -> Option<u32> {
+    let name = name.trim_end_matches('.');
+    (name.len() >= 3)
+        .then(|| MONTHS.iter().position(|month| month.starts_with(name)))
+        .flatten()
+        .map(|index| index as u32 + 1)
+}
+
+/// `23`, `23rd`, `1st`, …
+fn day_number(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '.');
+    digits.parse().ok().filter(|day| (1..=31).contains(day))
+}
+
+fn capitalized(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 (proleptic Gregorian calendar).
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The year, month and day of the date `days` after 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> Date {
+        Date::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parses_natural_dates() {
+        // A Thursday.
+        let today = date("2026-10-15");
+        let parse = |text| Date::parse_natural(text, today).map(|d| d.to_string());
+
+        assert_eq!(parse("2026-12-24").as_deref(), Some("2026-12-24"));
+        assert_eq!(parse(" Tomorrow ").as_deref(), Some("2026-10-16"));
+        assert_eq!(parse("friday").as_deref(), Some("2026-10-16"));
+        assert_eq!(parse("this thu").as_deref(), Some("2026-10-22"));
+        assert_eq!(parse("next friday").as_deref(), Some("2026-10-23"));
+        assert_eq!(parse("next week").as_deref(), Some("2026-10-19"));
+        assert_eq!(parse("next month").as_deref(), Some("2026-11-01"));
+        assert_eq!(parse("end of month").as_deref(), Some("2026-10-31"));
+        assert_eq!(parse("in 3 days").as_deref(), Some("2026-10-18"));
+        assert_eq!(parse("in a week").as_deref(), Some("2026-10-22"));
+        assert_eq!(
+            Date::parse_natural("in 1 month", date("2026-01-31")).map(|d| d.to_string()),
+            Some("2026-02-28".to_string())
+        );
+        assert_eq!(parse("oct 23").as_deref(), Some("2026-10-23"));
+        assert_eq!(parse("1st March").as_deref(), Some("2027-03-01"));
+        assert_eq!(parse("june 5, 2025").as_deref(), Some("2025-06-05"));
+        assert_eq!(parse("2026-02-30"), None);
+        assert_eq!(parse("someday"), None);
+        assert_eq!(date("2024-02-29").weekday_name(), "Thursday");
+    }
+
+    #[test]
+    fn relative_hints() {
+        let today = date("2026-10-15");
+        let hint = |text| due_hint(date(text), today);
+        assert_eq!(hint("2026-10-15"), "today");
+        assert_eq!(hint("2026-10-16"), "tomorrow");
+        assert_eq!(hint("2026-10-18"), "in 3 days");
+        assert_eq!(hint("2026-11-05"), "in 3 weeks");
+        assert_eq!(hint("2027-03-01"), "in 4 months");
+        assert_eq!(hint("2026-10-14"), "overdue");
+        assert_eq!(date("2026-10-12").relative_to(today), "3 days ago");
+    }
+
+    #[test]
+    fn due_date_ranges() {
+        // A Thursday.
+        let today = date("2026-10-15");
+        let by = |range| due_by(range, today).map(|d| d.to_string());
+        assert_eq!(by("overdue").as_deref(), Some("2026-10-14"));
+        assert_eq!(by("today").as_deref(), Some("2026-10-15"));
+        assert_eq!(by("this-week").as_deref(), Some("2026-10-18"));
+        assert_eq!(by("Next Week").as_deref(), Some("2026-10-25"));
+        assert_eq!(by("this-month").as_deref(), Some("2026-10-31"));
+        assert_eq!(by("2026-12-24").as_deref(), Some("2026-12-24"));
+        assert_eq!(by("friday").as_deref(), Some("2026-10-16"));
+        assert_eq!(by("someday"), None);
+    }
+
+    #[test]
+    fn finds_due_dates() {
+        let line = "- [ ] Taxes @due(2026-10-20) and 📅 2026-11-01, not @due(soon)";
+        let found = find_due_dates(line);
+        assert_eq!(found.len(), 2);
+        assert_eq!(&line[found[0].0.clone()], "@due(2026-10-20)");
+        assert_eq!(found[0].1, date("2026-10-20"));
+        assert_eq!(&line[found[1].0.clone()], "📅 2026-11-01");
+    }
+}
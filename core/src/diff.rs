@@ -0,0 +1,277 @@
+//! Word-level diff between two versions of a note.
+//!
+//! Lines are compared first; only runs of changed lines are then compared word
+//! by word, which keeps the work proportional to the size of the changes rather
+//! than the size of the note.
+//!
+//! [`hunks`] picks the changed lines out of a diff, with some lines around
+//! them, for showing just the changes of a long note.
+
+use crate::merge::lcs_matches;
+
+/// A piece of the diff. Concatenating all `Same` and `Removed` pieces gives
+/// the old text, all `Same` and `Added` pieces the new one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+/// The word-level changes turning `old` into `new`.
+pub fn diff_words(old: &str, new: &str) -> Vec<Change> {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let matches = lcs_matches(&old_lines, &new_lines);
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old_lines.len() || j < new_lines.len() {
+        if i < old_lines.len() && matches[i] == Some(j) {
+            push(&mut changes, Change::Same(old_lines[i].to_string()));
+            i += 1;
+            j += 1;
+            continue;
+        }
+
+        // A run of changed lines, up to the next line both versions share.
+        let next_i = (i..old_lines.len())
+            .find(|&n| matches[n].is_some())
+            .unwrap_or(old_lines.len());
+        let next_j = matches
+            .get(next_i)
+            .copied()
+            .flatten()
+            .unwrap_or(new_lines.len());
+        diff_hunk(
+            &mut changes,
+            &old_lines[i..next_i].concat(),
+            &new_lines[j..next_j].concat(),
+        );
+        (i, j) = (next_i, next_j);
+    }
+
+    changes
+}
+
+/// Whether the diff contains any change at all.
+pub fn has_changes(changes: &[Change]) -> bool {
+    changes.iter().any(|c| !matches!(c, Change::Same(_)))
+}
+
+/// Number of words added and removed.
+pub fn word_counts(changes: &[Change]) -> (usize, usize) {
+    changes
+        .iter()
+        .fold((0, 0), |(added, removed), change| match change {
+            Change::Added(text) => (added + text.split_whitespace().count(), removed),
+            Change::Removed(text) => (added, removed + text.split_whitespace().count()),
+            Change::Same(_) => (added, removed),
+        })
+}
+
+/// Changed lines of a diff with the unchanged lines around them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// The line of the new version the hunk starts at, counting from 1.
+    pub line: usize,
+    /// The hunk's pieces, each ending at a line break at the latest.
+    pub changes: Vec<Change>,
+}
+
+/// The lines of `changes` that have changes in them, each with up to
+/// `context` unchanged lines before and after it. Lines near each other end
+/// up in the same hunk.
+pub fn hunks(changes: &[Change], context: usize) -> Vec<Hunk> {
+    // Split the diff into lines, as it shows with the removed text in place.
+    let mut lines: Vec<(usize, Vec<Change>)> = Vec::new();
+    let mut current = Vec::new();
+    let mut line = 1;
+    let mut start = 1;
+    for change in changes {
+        let (text, kind): (&str, fn(String) -> Change) = match change {
+            Change::Same(text) => (text, Change::Same),
+            Change::Added(text) => (text, Change::Added),
+            Change::Removed(text) => (text, Change::Removed),
+        };
+        for piece in text.split_inclusive('\n') {
+            current.push(kind(piece.to_string()));
+            if !matches!(change, Change::Removed(_)) {
+                line += piece.matches('\n').count();
+            }
+            if piece.ends_with('\n') {
+                lines.push((start, std::mem::take(&mut current)));
+                start = line;
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push((start, current));
+    }
+
+    let changed: Vec<usize> = (0..lines.len())
+        .filter(|&i| has_changes(&lines[i].1))
+        .collect();
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let mut shown_until = 0;
+    for &i in &changed {
+        let from = i.saturating_sub(context).max(shown_until);
+        let to = (i + context + 1).min(lines.len());
+        if from < to {
+            let hunk = match hunks.last_mut() {
+                Some(hunk) if from == shown_until => hunk,
+                _ => {
+                    hunks.push(Hunk {
+                        line: lines[from].0,
+                        changes: Vec::new(),
+                    });
+                    hunks.last_mut().unwrap()
+                }
+            };
+            for (_, pieces) in &lines[from..to] {
+                for piece in pieces {
+                    push(&mut hunk.changes, piece.clone());
+                }
+            }
+            shown_until = to;
+        }
+    }
+    hunks
+}
+
+fn diff_hunk(changes: &mut Vec<Change>, old: &str, new: &str) {
+    let old_words = tokens(old);
+    let new_words = tokens(new);
+    let matches = lcs_matches(&old_words, &new_words);
+
+    let mut j = 0;
+    for (i, word) in old_words.iter().enumerate() {
+        match matches[i] {
+            Some(m) => {
+                for added in &new_words[j..m] {
+                    push(changes, Change::Added(added.to_string()));
+                }
+                push(changes, Change::Same(word.to_string()));
+                j = m + 1;
+            }
+            None => push(changes, Change::Removed(word.to_string())),
+        }
+    }
+    for added in &new_words[j..] {
+        push(changes, Change::Added(added.to_string()));
+    }
+}
+
+/// Split into words and the whitespace between them, so that the tokens
+/// concatenate back to `text`.
+fn tokens(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut in_space = None;
+    for (i, c) in text.char_indices() {
+        let space = c.is_whitespace();
+        if in_space.is_some_and(|s| s != space) {
+            tokens.push(&text[start..i]);
+            start = i;
+        }
+        in_space = Some(space);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+/// Append `change`, merging it into the previous piece of the same kind.
+fn push(changes: &mut Vec<Change>, change: Change) {
+    match (changes.last_mut(), &change) {
+        (Some(Change::Same(a)), Change::Same(b))
+        | (Some(Change::Added(a)), Change::Added(b))
+        | (Some(Change::Removed(a)), Change::Removed(b)) => a.push_str(b),
+        _ => changes.push(change),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rebuild(changes: &[Change]) -> (String, String) {
+        let mut old = String::new();
+        let mut new = String::new();
+        for change in changes {
+            match change {
+                Change::Same(t) => {
+                    old.push_str(t);
+                    new.push_str(t);
+                }
+                Change::Removed(t) => old.push_str(t),
+                Change::Added(t) => new.push_str(t),
+            }
+        }
+        (old, new)
+    }
+
+    #[test]
+    fn test_diff_words_within_a_line() {
+        let changes = diff_words(
+            "# Title\n\nThe quick fox jumps\n",
+            "# Title\n\nThe slow fox jumps high\n",
+        );
+        assert_eq!(
+            changes,
+            vec![
+                Change::Same("# Title\n\nThe ".into()),
+                Change::Removed("quick".into()),
+                Change::Added("slow".into()),
+                Change::Same(" fox jumps".into()),
+                Change::Added(" high".into()),
+                Change::Same("\n".into()),
+            ]
+        );
+        assert_eq!(word_counts(&changes), (2, 1));
+    }
+
+    #[test]
+    fn test_diff_words_rebuilds_both_versions() {
+        let old = "one\ntwo three\nfour\n\nfive\n";
+        let new = "zero\none\ntwo 3\n\nfive\nsix";
+        let changes = diff_words(old, new);
+        assert_eq!(rebuild(&changes), (old.to_string(), new.to_string()));
+        assert!(has_changes(&changes));
+        assert!(!has_changes(&diff_words(old, old)));
+    }
+
+    #[test]
+    fn test_hunks_show_changes_in_context() {
+        let old: String = (1..=20).map(|n| format!("line {n}\n")).collect();
+        let new = old
+            .replace("line 3\n", "line three\n")
+            .replace("line 5\n", "")
+            .replace("line 17\n", "line 17\nline 17b\n");
+        let hunks = hunks(&diff_words(&old, &new), 1);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].line, 2);
+        assert_eq!(
+            hunks[0].changes,
+            vec![
+                Change::Same("line 2\nline ".into()),
+                Change::Removed("3".into()),
+                Change::Added("three".into()),
+                Change::Same("\nline 4\n".into()),
+                Change::Removed("line 5\n".into()),
+                Change::Same("line 6\n".into()),
+            ]
+        );
+        assert_eq!(hunks[1].line, 16);
+        assert_eq!(
+            hunks[1].changes,
+            vec![
+                Change::Same("line 17\n".into()),
+                Change::Added("line 17b\n".into()),
+                Change::Same("line 18\n".into()),
+            ]
+        );
+        assert!(super::hunks(&diff_words(&old, &old), 3).is_empty());
+    }
+}
@@ -0,0 +1,319 @@
+//! Comparing versions of a note, and resolving conflicts, in an external
+//! diff/merge tool such as Meld, KDiff3 or VS Code.
+//!
+//! A tool is a command line with placeholders for the files it works on, as
+//! in `git mergetool`: `$LOCAL` and `$REMOTE` are the two versions, and for
+//! merges `$BASE` is the version both started from and `$MERGED` the file the
+//! result is written to. `meld`, `kdiff3` and `code` name ready-made command
+//! lines:
+//!
+//! ```toml
+//! [diff]
+//! tool = "meld"
+//! # or your own, with a separate command line for merges:
+//! # tool = "bcompare $LOCAL $REMOTE"
+//! # merge = "bcompare $LOCAL $REMOTE $BASE $MERGED"
+//! ```
+//!
+//! The versions are written to temporary files that are removed once the
+//! tool exits.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::merge::merge3;
+
+/// Command lines of the tools known by name: (name, diff, merge).
+const PRESETS: &[(&str, &str, &str)] = &[
+    (
+        "meld",
+        "meld $LOCAL $REMOTE",
+        "meld $LOCAL $BASE $REMOTE --output $MERGED",
+    ),
+    (
+        "kdiff3",
+        "kdiff3 $LOCAL $REMOTE",
+        "kdiff3 $BASE $LOCAL $REMOTE -o $MERGED",
+    ),
+    (
+        "code",
+        "code --wait --diff $LOCAL $REMOTE",
+        "code --wait --merge $LOCAL $REMOTE $BASE $MERGED",
+    ),
+];
+
+/// A configured diff/merge tool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffTool {
+    diff: String,
+    merge: Option<String>,
+}
+
+impl DiffTool {
+    /// The tool `tool` names: one of the presets (`vscode` works for `code`)
+    /// or a command line. `merge` overrides the command line for merges; a
+    /// tool given as a command line can only merge with one.
+    pub fn new(tool: &str, merge: Option<&str>) -> Result<DiffTool, String> {
+        let tool = tool.trim();
+        if tool.is_empty() {
+            return Err("No diff tool configured".to_string());
+        }
+        let name = if tool == "vscode" { "code" } else { tool };
+        let merge = merge.map(str::trim).filter(|m| !m.is_empty());
+        Ok(match PRESETS.iter().find(|(preset, ..)| *preset == name) {
+            Some((_, diff, preset_merge)) => DiffTool {
+                diff: diff.to_string(),
+                merge: Some(merge.unwrap_or(preset_merge).to_string()),
+            },
+            None => DiffTool {
+                diff: tool.to_string(),
+                merge: merge.map(str::to_string),
+            },
+        })
+    }
+
+    /// The tool's program, for messages.
+    pub fn name(&self) -> &str {
+        self.diff.split_whitespace().next().unwrap_or_default()
+    }
+
+    /// Whether the tool can resolve conflicts.
+    pub fn can_merge(&self) -> bool {
+        self.merge.is_some()
+    }
+
+    /// Show the changes from `old` to `new` of the note `note`, waiting until
+    /// the tool is closed. The labels end up in the temporary files' names
+    /// (`plan.HEAD.md`), which most tools show as titles.
+    pub fn compare(&self, note: &str, old: (&str, &str), new: (&str, &str)) -> Result<(), String> {
+        let (_dir, mut child) = self.start_compare(note, old, new)?;
+        // Many diff tools exit with an error status when the files differ, so
+        // it tells nothing.
+        child
+            .wait()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to wait for {}: {}", self.name(), e))
+    }
+
+    /// Like [`compare`](DiffTool::compare), but returns as soon as the tool
+    /// is running.
+    pub fn compare_in_background(
+        &self,
+        note: &str,
+        old: (&str, &str),
+        new: (&str, &str),
+    ) -> Result<(), String> {
+        let (dir, mut child) = self.start_compare(note, old, new)?;
+        thread::spawn(move || {
+            let _ = child.wait();
+            drop(dir);
+        });
+        Ok(())
+    }
+
+    fn start_compare(
+        &self,
+        note: &str,
+        (old_label, old): (&str, &str),
+        (new_label, new): (&str, &str),
+    ) -> Result<(TempDir, Child), String> {
+        let dir = TempDir::new()?;
+        let local = dir.write(&file_name(note, Some(old_label)), old)?;
+        let remote = dir.write(&file_name(note, Some(new_label)), new)?;
+        let child = spawn(&self.diff, &[("$LOCAL", &local), ("$REMOTE", &remote)])?;
+        Ok((dir, child))
+    }
+
+    /// Let the user merge `mine` and `theirs`, two versions of `note` that
+    /// both started out as `base`. The tool starts from piki's own merge,
+    /// with conflicts marked. Returns the result, or `None` if the tool exited
+    /// with an error, as most do when the merge is abandoned.
+    pub fn merge(
+        &self,
+        note: &str,
+        base: &str,
+        mine: &str,
+        theirs: &str,
+    ) -> Result<Option<String>, String> {
+        let Some(command) = &self.merge else {
+            return Err(format!("No merge command configured for '{}'", self.name()));
+        };
+        let dir = TempDir::new()?;
+        let merged = merge3(base, mine, theirs).text;
+        let base_file = dir.write(&file_name(note, Some("base")), base)?;
+        let local = dir.write(&file_name(note, Some("mine")), mine)?;
+        let remote = dir.write(&file_name(note, Some("theirs")), theirs)?;
+        let merged_file = dir.write(&file_name(note, None), &merged)?;
+
+        let ok = run(
+            command,
+            &[
+                ("$BASE", &base_file),
+                ("$LOCAL", &local),
+                ("$REMOTE", &remote),
+                ("$MERGED", &merged_file),
+            ],
+        )?;
+        if !ok {
+            return Ok(None);
+        }
+        fs::read_to_string(&merged_file)
+            .map(Some)
+            .map_err(|e| format!("Failed to read the merge result: {}", e))
+    }
+}
+
+/// The arguments of `template` with the placeholders replaced by the files'
+/// paths. Arguments are separated by whitespace, so paths may hold spaces.
+fn command_line(template: &str, files: &[(&str, &Path)]) -> Vec<String> {
+    template
+        .split_whitespace()
+        .map(|arg| {
+            files
+                .iter()
+                .fold(arg.to_string(), |arg, (placeholder, path)| {
+                    arg.replace(placeholder, &path.to_string_lossy())
+                })
+        })
+        .collect()
+}
+
+/// Start `template` on `files`.
+fn spawn(template: &str, files: &[(&str, &Path)]) -> Result<Child, String> {
+    let args = command_line(template, files);
+    let (program, args) = args
+        .split_first()
+        .ok_or_else(|| "The diff tool's command line is empty".to_string())?;
+    Command::new(program)
+        .args(args)
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))
+}
+
+/// Run `template` on `files` and wait for it. Returns whether it succeeded.
+fn run(template: &str, files: &[(&str, &Path)]) -> Result<bool, String> {
+    spawn(template, files)?
+        .wait()
+        .map(|status| status.success())
+        .map_err(|e| format!("Failed to wait for the diff tool: {}", e))
+}
+
+/// The temporary file name for `note`'s version `label`: `plan.mine.md` for
+/// `projects/plan`.
+fn file_name(note: &str, label: Option<&str>) -> String {
+    let stem = note.rsplit('/').next().unwrap_or(note);
+    match label {
+        Some(label) => {
+            let label: String = label
+                .chars()
+                .map(|c| {
+                    if c.is_alphanumeric() || c == '-' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            format!("{stem}.{label}.md")
+        }
+        None => format!("{stem}.md"),
+    }
+}
+
+/// A folder for the files handed to a tool, removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> Result<TempDir, String> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "piki-diff-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&path)
+            .map_err(|e| format!("Failed to create '{}': {}", path.display(), e))?;
+        Ok(TempDir(path))
+    }
+
+    fn write(&self, name: &str, content: &str) -> Result<PathBuf, String> {
+        let path = self.0.join(name);
+        fs::write(&path, content)
+            .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+        Ok(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_command_lines() {
+        let tool = DiffTool::new("vscode", None).unwrap();
+        assert!(tool.can_merge());
+        let local = Path::new("/tmp/my notes/plan.HEAD.md");
+        let remote = Path::new("/tmp/plan.md");
+        assert_eq!(
+            command_line(&tool.diff, &[("$LOCAL", local), ("$REMOTE", remote)]),
+            vec![
+                "code",
+                "--wait",
+                "--diff",
+                "/tmp/my notes/plan.HEAD.md",
+                "/tmp/plan.md"
+            ]
+        );
+        assert_eq!(
+            command_line("tool --out=$MERGED", &[("$MERGED", remote)]),
+            vec!["tool", "--out=/tmp/plan.md"]
+        );
+
+        let custom = DiffTool::new("bcompare $LOCAL $REMOTE", None).unwrap();
+        assert!(!custom.can_merge());
+        assert!(custom.merge("plan", "", "", "").is_err());
+        assert!(DiffTool::new(" ", None).is_err());
+        assert_eq!(
+            file_name("projects/plan", Some("a1b2 (HEAD)")),
+            "plan.a1b2__HEAD_.md"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn merges_with_the_tool() {
+        let base = "one\ntwo\n";
+        let tool = DiffTool::new("true", Some("cp $REMOTE $MERGED")).unwrap();
+        assert_eq!(
+            tool.merge("plan", base, "one\ntwo\nmine\n", "zero\none\ntwo\n")
+                .unwrap(),
+            Some("zero\none\ntwo\n".to_string())
+        );
+        // Without changing it, the result is piki's own merge.
+        let tool = DiffTool::new("true", Some("true $MERGED")).unwrap();
+        assert_eq!(
+            tool.merge("plan", base, "one\ntwo\nmine\n", "zero\none\ntwo\n")
+                .unwrap(),
+            Some("zero\none\ntwo\nmine\n".to_string())
+        );
+        let tool = DiffTool::new("true", Some("false")).unwrap();
+        assert_eq!(tool.merge("plan", base, "a", "b").unwrap(), None);
+        tool.compare("plan", ("old", "a"), ("new", "b")).unwrap();
+        let missing = DiffTool::new("piki-no-such-tool $LOCAL $REMOTE", None).unwrap();
+        assert_eq!(missing.name(), "piki-no-such-tool");
+        assert!(
+            missing
+                .compare_in_background("plan", ("old", "a"), ("new", "b"))
+                .is_err()
+        );
+    }
+}
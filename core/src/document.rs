@@ -1,7 +1,12 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+use crate::edit_log;
+use crate::encryption::{self, Keyring};
+use crate::images::join_relative;
+use crate::link_index;
+
 #[derive(Clone)]
 pub struct Document {
     pub name: String,
@@ -12,6 +17,7 @@ pub struct Document {
 
 pub struct DocumentStore {
     base_path: PathBuf,
+    keyring: Keyring,
 }
 
 /// Returns true if the name already ends with a (case-insensitive) `.md`
@@ -37,9 +43,76 @@ pub fn ensure_md_extension(name: &str) -> String {
     }
 }
 
+/// `s`, a link destination or URL path, with its `%XX` escapes decoded.
+/// Invalid escapes, such as a stray `%`, are kept as they are.
+pub fn percent_decode(s: &str) -> String {
+    let hex = |b: u8| (b as char).to_digit(16).map(|d| d as u8);
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(&[hi, lo]) = bytes.get(i + 1..i + 3)
+            && let (Some(hi), Some(lo)) = (hex(hi), hex(lo))
+        {
+            out.push(hi * 16 + lo);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// `name` lower-cased and with runs of spaces and dashes turned into a single
+/// dash, so that `My Page` and `my-page` compare equal.
+fn page_key(name: &str) -> String {
+    let mut key = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_whitespace() || c == '-' {
+            if !key.ends_with('-') {
+                key.push('-');
+            }
+        } else {
+            key.extend(c.to_lowercase());
+        }
+    }
+    key
+}
+
+/// Folder inside the notes directory that files dropped onto a note are
+/// copied to.
+pub const ATTACHMENTS_DIR: &str = "attachments";
+
+/// `stem` + `extension`, or the first of `stem-2` + `extension`, `stem-3` + …
+/// for which `taken` is false.
+pub(crate) fn unique_name(stem: &str, extension: &str, taken: impl Fn(&str) -> bool) -> String {
+    let mut name = format!("{stem}{extension}");
+    let mut n = 2;
+    while taken(&name) {
+        name = format!("{stem}-{n}{extension}");
+        n += 1;
+    }
+    name
+}
+
 impl DocumentStore {
     pub fn new(base_path: PathBuf) -> Self {
-        DocumentStore { base_path }
+        DocumentStore {
+            base_path,
+            keyring: Keyring::default(),
+        }
+    }
+
+    /// Ask `prompt` for the passphrase of encrypted notes (see
+    /// [`encryption`]). Without one, only notes whose passphrase was given
+    /// to [`encrypt_note`](Self::encrypt_note) can be opened.
+    pub fn set_passphrase_prompt(
+        &mut self,
+        prompt: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    ) {
+        self.keyring.set_prompt(Box::new(prompt));
     }
 
     /// The root directory this store reads notes from.
@@ -54,8 +127,63 @@ impl DocumentStore {
     /// We deliberately do not rely on `Path::extension`, which would treat the
     /// trailing part of a dotted note name (e.g. "sprint-q2.6") as the
     /// extension and skip adding `.md`.
+    ///
+    /// An encrypted note's path is that of its `.md.age` file, unless there
+    /// is a plain `.md` file as well.
     pub fn path_for(&self, name: &str) -> PathBuf {
-        self.base_path.join(ensure_md_extension(name))
+        let path = self.base_path.join(ensure_md_extension(name));
+        if !path.exists() {
+            let encrypted = self.encrypted_path_for(name);
+            if encrypted.exists() {
+                return encrypted;
+            }
+        }
+        path
+    }
+
+    /// The path of the note `name` if it is encrypted, whether or not it
+    /// exists.
+    pub fn encrypted_path_for(&self, name: &str) -> PathBuf {
+        self.base_path
+            .join(ensure_md_extension(name) + encryption::EXTENSION)
+    }
+
+    /// Whether the note `name` is encrypted.
+    pub fn is_encrypted(&self, name: &str) -> bool {
+        encryption::is_encrypted(&self.path_for(name))
+    }
+
+    /// Encrypt the note `name` with `passphrase`, replacing its `.md` file
+    /// by a `.md.age` one.
+    pub fn encrypt_note(&self, name: &str, passphrase: &str) -> Result<(), String> {
+        let plain = self.path_for(name);
+        if encryption::is_encrypted(&plain) {
+            return Err(format!("'{name}' is encrypted already"));
+        }
+        if !plain.exists() {
+            return Err(format!("No note '{name}'"));
+        }
+        let doc = self.load(name)?;
+        self.keyring.remember(&doc.name, passphrase);
+        self.save(&Document {
+            path: self.encrypted_path_for(name),
+            ..doc
+        })?;
+        fs::remove_file(&plain).map_err(|e| format!("Failed to remove '{}': {}", name, e))
+    }
+
+    /// Decrypt the note `name`, replacing its `.md.age` file by a `.md` one.
+    pub fn decrypt_note(&self, name: &str) -> Result<(), String> {
+        let encrypted = self.path_for(name);
+        if !encryption::is_encrypted(&encrypted) {
+            return Err(format!("'{name}' is not encrypted"));
+        }
+        let doc = self.load(name)?;
+        self.save(&Document {
+            path: self.base_path.join(ensure_md_extension(name)),
+            ..doc
+        })?;
+        fs::remove_file(&encrypted).map_err(|e| format!("Failed to remove '{}': {}", name, e))
     }
 
     /// Load a document by name (with or without .md extension)
@@ -65,8 +193,12 @@ impl DocumentStore {
 
         // Read file content and metadata if it exists, otherwise create empty document
         let (content, modified_time) = if path.exists() {
-            let content = fs::read_to_string(&path)
-                .map_err(|e| format!("Failed to read '{}': {}", name, e))?;
+            let content = if encryption::is_encrypted(&path) {
+                self.keyring.load(name, &path)?
+            } else {
+                fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read '{}': {}", name, e))?
+            };
 
             // Get modification time
             let mtime = fs::metadata(&path).ok().and_then(|m| m.modified().ok());
@@ -84,8 +216,31 @@ impl DocumentStore {
         })
     }
 
+    /// The on-disk modification time of a note, without reading its content.
+    /// Returns `None` if the note has no file (yet) or the time is unavailable.
+    pub fn modified_time(&self, name: &str) -> Option<SystemTime> {
+        fs::metadata(self.path_for(name))
+            .ok()
+            .and_then(|m| m.modified().ok())
+    }
+
+    /// The on-disk creation time of a note. Returns `None` if the note has no
+    /// file (yet) or the file system doesn't record when files were created.
+    pub fn created_time(&self, name: &str) -> Option<SystemTime> {
+        fs::metadata(self.path_for(name))
+            .ok()
+            .and_then(|m| m.created().ok())
+    }
+
+    /// The size of a note's file in bytes, `None` if it has no file (yet).
+    pub fn file_size(&self, name: &str) -> Option<u64> {
+        fs::metadata(self.path_for(name)).ok().map(|m| m.len())
+    }
+
     /// Recursively list all markdown files in the directory and subdirectories
-    /// Returns relative paths from base_path (e.g., "project-a/standup")
+    /// Returns relative paths from base_path (e.g., "project-a/standup").
+    /// Hidden directories (like `.git` and `.piki`, which holds the
+    /// templates) are skipped.
     pub fn list_all_documents(&self) -> Result<Vec<String>, String> {
         let mut docs = Vec::new();
         Self::walk_directory(&self.base_path, "", &mut docs)?;
@@ -99,9 +254,27 @@ impl DocumentStore {
 
         for entry in entries.flatten() {
             let path = entry.path();
-
-            if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("md") {
-                if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+            // The entry's type comes with the directory listing on most
+            // platforms; only symlinks need a stat to see what they point to.
+            let (is_file, is_dir) = match entry.file_type() {
+                Ok(t) if !t.is_symlink() => (t.is_file(), t.is_dir()),
+                _ => (path.is_file(), path.is_dir()),
+            };
+
+            // Encrypted notes are named after their file without `.age`.
+            let note_file = if encryption::is_encrypted(&path) {
+                path.file_name()
+                    .and_then(|s| s.to_str())
+                    .and_then(|s| s.strip_suffix(encryption::EXTENSION))
+                    .map(Path::new)
+            } else {
+                Some(path.as_path())
+            };
+            if is_file
+                && let Some(note_file) = note_file
+                && note_file.extension().and_then(|s| s.to_str()) == Some("md")
+            {
+                if let Some(name) = note_file.file_stem().and_then(|s| s.to_str()) {
                     let full_name = if prefix.is_empty() {
                         name.to_string()
                     } else {
@@ -109,9 +282,11 @@ impl DocumentStore {
                     };
                     docs.push(full_name);
                 }
-            } else if path.is_dir() {
+            } else if is_dir {
                 // Recursively walk subdirectories
-                if let Some(dir_name) = path.file_name().and_then(|s| s.to_str()) {
+                if let Some(dir_name) = path.file_name().and_then(|s| s.to_str())
+                    && !dir_name.starts_with('.')
+                {
                     let new_prefix = if prefix.is_empty() {
                         dir_name.to_string()
                     } else {
@@ -125,6 +300,180 @@ impl DocumentStore {
         Ok(())
     }
 
+    /// Recursively list all non-markdown files (images, PDFs, ...) stored
+    /// alongside the notes. Returns relative paths from base_path including the
+    /// file extension (e.g., "project-a/diagram.png"). Hidden files and
+    /// directories (like `.git`) are skipped.
+    pub fn list_attachments(&self) -> Result<Vec<String>, String> {
+        let mut attachments = Vec::new();
+        Self::walk_attachments(&self.base_path, "", &mut attachments)?;
+        Ok(attachments)
+    }
+
+    /// Helper function to recursively collect attachments
+    fn walk_attachments(dir: &Path, prefix: &str, out: &mut Vec<String>) -> Result<(), String> {
+        let entries = fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read directory '{}': {}", dir.display(), e))?;
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(file_name) = path.file_name().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if file_name.starts_with('.') {
+                continue;
+            }
+
+            let full_name = if prefix.is_empty() {
+                file_name.to_string()
+            } else {
+                format!("{}/{}", prefix, file_name)
+            };
+
+            if path.is_file() && !has_md_extension(file_name) && !encryption::is_encrypted(&path) {
+                out.push(full_name);
+            } else if path.is_dir() {
+                Self::walk_attachments(&path, &full_name, out)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolve a link target to an attachment file inside the notes directory.
+    ///
+    /// Returns `None` if the target names a note (an existing `<name>.md`
+    /// always wins, so dotted note names keep working), a markdown file, a
+    /// missing file, or anything that escapes the notes directory.
+    pub fn attachment_path(&self, name: &str) -> Option<PathBuf> {
+        let name = name.trim().trim_start_matches('/');
+        if name.is_empty() || has_md_extension(name) || self.path_for(name).exists() {
+            return None;
+        }
+
+        let candidate = self.base_path.join(name);
+        if !candidate.is_file() {
+            return None;
+        }
+
+        let base = fs::canonicalize(&self.base_path).ok()?;
+        let resolved = fs::canonicalize(&candidate).ok()?;
+        resolved.starts_with(&base).then_some(resolved)
+    }
+
+    /// The name of the note (or attachment) that a link to `target` in the
+    /// note `from` leads to.
+    ///
+    /// Like `piki view`, a link is looked up in the folder of `from` first and
+    /// in the notes directory second, so `[plan](plan)` in `projects/index`
+    /// opens `projects/plan`. Failing that, a note of almost that name is
+    /// looked for (see [`find_note`](Self::find_note)). A link to a note that
+    /// doesn't exist yet creates it in the notes directory, unless it starts
+    /// with `./` or `../`. Plugin pages and links leaving the notes directory
+    /// are returned unchanged.
+    pub fn resolve_link(&self, from: &str, target: &str) -> String {
+        let target = target.trim();
+        if target.is_empty() || target.starts_with('!') {
+            return target.to_string();
+        }
+        let target = if has_md_extension(target) {
+            &target[..target.len() - 3]
+        } else {
+            target
+        };
+        let dir = from.rsplit_once('/').map_or("", |(dir, _)| dir);
+        let relative = join_relative(dir, target);
+        let absolute = join_relative("", target);
+        let exists = |name: &String| {
+            !name.is_empty()
+                && (self.path_for(name).exists() || self.base_path.join(name).is_file())
+        };
+        if let Some(found) = [&relative, &absolute]
+            .into_iter()
+            .flatten()
+            .find(|name| exists(name))
+        {
+            return found.clone();
+        }
+        if let Some(found) = [&relative, &absolute]
+            .into_iter()
+            .flatten()
+            .find_map(|name| self.find_note(name))
+        {
+            return found;
+        }
+        let fallback = if target.starts_with("./") || target.starts_with("../") {
+            relative
+        } else {
+            absolute
+        };
+        fallback.unwrap_or_else(|| target.to_string())
+    }
+
+    /// The note meant by `name` when no note has exactly that name: one whose
+    /// name differs only in case, or else only in spaces versus dashes, so
+    /// that a link to `My Page` finds `my-page.md`.
+    pub fn find_note(&self, name: &str) -> Option<String> {
+        let name = if has_md_extension(name) {
+            &name[..name.len() - 3]
+        } else {
+            name
+        };
+        let names = self.list_all_documents().ok()?;
+        let lower = name.to_lowercase();
+        let key = page_key(name);
+        names
+            .iter()
+            .filter(|other| other.to_lowercase() == lower)
+            .min()
+            .or_else(|| names.iter().filter(|other| page_key(other) == key).min())
+            .cloned()
+    }
+
+    /// Copy the markdown file `source` into the store as a new note named
+    /// after the file. A taken name gets a `-2`, `-3`, … suffix. Returns the
+    /// new note's name.
+    pub fn import_note(&self, source: &Path) -> Result<String, String> {
+        let content = fs::read_to_string(source)
+            .map_err(|e| format!("Failed to read '{}': {}", source.display(), e))?;
+        let stem = source
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .filter(|s| !s.is_empty())
+            .unwrap_or("imported");
+        let name = unique_name(stem, "", |candidate| self.path_for(candidate).exists());
+
+        self.save(&Document {
+            name: name.clone(),
+            path: self.path_for(&name),
+            content,
+            modified_time: None,
+        })?;
+        Ok(name)
+    }
+
+    /// Copy `source` (an image, PDF, …) into the [`ATTACHMENTS_DIR`] folder,
+    /// renaming it like [`import_note`](Self::import_note) if the name is
+    /// taken. Returns the link target of the copy, e.g. `attachments/photo.jpg`.
+    pub fn import_attachment(&self, source: &Path) -> Result<String, String> {
+        let file_name = source
+            .file_name()
+            .and_then(|s| s.to_str())
+            .ok_or_else(|| format!("'{}' is not a file", source.display()))?;
+        let (stem, extension) = match file_name.rfind('.') {
+            Some(dot) if dot > 0 => (&file_name[..dot], &file_name[dot..]),
+            _ => (file_name, ""),
+        };
+
+        let dir = self.base_path.join(ATTACHMENTS_DIR);
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create '{}': {}", dir.display(), e))?;
+        let name = unique_name(stem, extension, |candidate| dir.join(candidate).exists());
+        fs::copy(source, dir.join(&name))
+            .map_err(|e| format!("Failed to copy '{}': {}", source.display(), e))?;
+        Ok(format!("{ATTACHMENTS_DIR}/{name}"))
+    }
+
     /// Save document content
     /// Creates parent directories if they don't exist
     pub fn save(&self, doc: &Document) -> Result<(), String> {
@@ -134,8 +483,27 @@ impl DocumentStore {
                 .map_err(|e| format!("Failed to create directories for '{}': {}", doc.name, e))?;
         }
 
-        fs::write(&doc.path, &doc.content)
-            .map_err(|e| format!("Failed to save '{}': {}", doc.name, e))
+        if encryption::is_encrypted(&doc.path) {
+            let data = self.keyring.encrypt(&doc.name, &doc.content)?;
+            fs::write(&doc.path, data)
+                .map_err(|e| format!("Failed to save '{}': {}", doc.name, e))?;
+            self.keyring.cache(&doc.path, &doc.content);
+        } else {
+            fs::write(&doc.path, &doc.content)
+                .map_err(|e| format!("Failed to save '{}': {}", doc.name, e))?;
+        }
+
+        link_index::note_saved(&self.base_path, &doc.name, &doc.path, &doc.content);
+        // The edit log is a diagnostic aid; failing to write it never fails
+        // the save.
+        let _ = edit_log::record(
+            &self.base_path,
+            &doc.name,
+            edit_log::Operation::Save {
+                bytes: doc.content.len() as u64,
+            },
+        );
+        Ok(())
     }
 
     /// Delete a note's file from disk.
@@ -146,10 +514,12 @@ impl DocumentStore {
     pub fn delete(&self, name: &str) -> Result<(), String> {
         let path = self.path_for(name);
         match fs::remove_file(&path) {
-            Ok(()) => Ok(()),
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
-            Err(e) => Err(format!("Failed to delete '{}': {}", name, e)),
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to delete '{}': {}", name, e)),
         }
+        let _ = edit_log::record(&self.base_path, name, edit_log::Operation::Delete);
+        Ok(())
     }
 }
 
@@ -231,6 +601,15 @@ mod tests {
         assert_eq!(ensure_md_extension("notes.MD"), "notes.MD");
     }
 
+    #[test]
+    fn test_percent_decode_tolerates_stray_percent() {
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("a%2"), "a%2");
+        assert_eq!(percent_decode("a%zz"), "a%zz");
+        assert_eq!(percent_decode("a%+1"), "a%+1");
+        assert_eq!(percent_decode("%41%42%20c"), "AB c");
+    }
+
     #[test]
     fn test_path_for_resolves_without_reading() {
         let store = DocumentStore::new("/tmp/piki-x".into());
@@ -251,6 +630,27 @@ mod tests {
         assert_eq!(store.path_for("a/b"), PathBuf::from("/tmp/piki-x/a/b.md"));
     }
 
+    #[test]
+    fn test_modified_time() {
+        let temp_dir = env::temp_dir().join("piki-test-mtime");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let store = DocumentStore::new(temp_dir.clone());
+        assert_eq!(store.modified_time("note"), None);
+        assert_eq!(store.created_time("note"), None);
+        assert_eq!(store.file_size("note"), None);
+
+        fs::write(temp_dir.join("note.md"), "hello").unwrap();
+        let mtime = store.modified_time("note");
+        assert!(mtime.is_some());
+        assert_eq!(mtime, store.load("note").unwrap().modified_time);
+        assert_eq!(store.file_size("note"), Some(5));
+
+        // Cleanup
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
     #[test]
     fn test_load_nested_path() {
         let temp_dir = env::temp_dir().join("piki-test-nested");
@@ -333,6 +733,11 @@ mod tests {
         fs::write(temp_dir.join("dir1/note1.md"), "note1").unwrap();
         fs::create_dir_all(temp_dir.join("dir1/subdir")).unwrap();
         fs::write(temp_dir.join("dir1/subdir/note2.md"), "note2").unwrap();
+        // Templates and other hidden folders hold no notes.
+        fs::create_dir_all(temp_dir.join(".piki/templates")).unwrap();
+        fs::write(temp_dir.join(".piki/templates/meeting.md"), "# {{title}}").unwrap();
+        fs::create_dir_all(temp_dir.join("dir1/.hidden")).unwrap();
+        fs::write(temp_dir.join("dir1/.hidden/note3.md"), "note3").unwrap();
 
         let docs = store.list_all_documents().unwrap();
 
@@ -344,4 +749,201 @@ mod tests {
         // Cleanup
         fs::remove_dir_all(&temp_dir).ok();
     }
+
+    #[test]
+    fn test_list_attachments_skips_notes_and_hidden_files() {
+        let temp_dir = env::temp_dir().join("piki-test-attachments");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(temp_dir.join("dir1")).unwrap();
+        fs::create_dir_all(temp_dir.join(".git")).unwrap();
+
+        fs::write(temp_dir.join("note.md"), "note").unwrap();
+        fs::write(temp_dir.join("paper.pdf"), "pdf").unwrap();
+        fs::write(temp_dir.join("dir1/diagram.png"), "png").unwrap();
+        fs::write(temp_dir.join(".DS_Store"), "").unwrap();
+        fs::write(temp_dir.join(".git/HEAD"), "ref").unwrap();
+
+        let store = DocumentStore::new(temp_dir.clone());
+        let mut attachments = store.list_attachments().unwrap();
+        attachments.sort();
+
+        assert_eq!(attachments, vec!["dir1/diagram.png", "paper.pdf"]);
+
+        // Cleanup
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn test_attachment_path_resolution() {
+        let temp_dir = env::temp_dir().join("piki-test-attachment-path");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(temp_dir.join("img")).unwrap();
+
+        fs::write(temp_dir.join("img/cat.jpg"), "jpg").unwrap();
+        fs::write(temp_dir.join("notes.md"), "notes").unwrap();
+        fs::write(temp_dir.join("sprint-q2.6"), "stray").unwrap();
+        fs::write(temp_dir.join("sprint-q2.6.md"), "note").unwrap();
+
+        let store = DocumentStore::new(temp_dir.clone());
+        let canonical = fs::canonicalize(&temp_dir).unwrap();
+
+        assert_eq!(
+            store.attachment_path("img/cat.jpg"),
+            Some(canonical.join("img/cat.jpg"))
+        );
+        assert_eq!(
+            store.attachment_path("/img/cat.jpg"),
+            Some(canonical.join("img/cat.jpg"))
+        );
+        // Notes are never attachments, even if a same-named file exists.
+        assert_eq!(store.attachment_path("notes.md"), None);
+        assert_eq!(store.attachment_path("sprint-q2.6"), None);
+        assert_eq!(store.attachment_path("missing.pdf"), None);
+        assert_eq!(store.attachment_path("../outside.pdf"), None);
+
+        // Cleanup
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn test_resolve_link_between_folders() {
+        let temp_dir = env::temp_dir().join("piki-test-resolve-link");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(temp_dir.join("projects/img")).unwrap();
+
+        fs::write(temp_dir.join("projects/index.md"), "index").unwrap();
+        fs::write(temp_dir.join("projects/plan.md"), "plan").unwrap();
+        fs::write(temp_dir.join("projects/img/chart.png"), "png").unwrap();
+        fs::write(temp_dir.join("contacts.md"), "contacts").unwrap();
+
+        let store = DocumentStore::new(temp_dir.clone());
+        let from = "projects/index";
+
+        // The linking note's folder first, then the notes directory.
+        assert_eq!(store.resolve_link(from, "plan"), "projects/plan");
+        assert_eq!(store.resolve_link(from, "plan.md"), "projects/plan");
+        assert_eq!(store.resolve_link(from, "./plan"), "projects/plan");
+        assert_eq!(store.resolve_link(from, "contacts"), "contacts");
+        assert_eq!(store.resolve_link(from, "../contacts"), "contacts");
+        assert_eq!(store.resolve_link(from, "/projects/plan"), "projects/plan");
+        assert_eq!(
+            store.resolve_link(from, "img/chart.png"),
+            "projects/img/chart.png"
+        );
+        assert_eq!(
+            store.resolve_link("contacts", "projects/plan"),
+            "projects/plan"
+        );
+
+        // New notes go to the notes directory unless the link says otherwise.
+        assert_eq!(store.resolve_link(from, "ideas"), "ideas");
+        assert_eq!(store.resolve_link(from, "./ideas"), "projects/ideas");
+        assert_eq!(store.resolve_link(from, "!index"), "!index");
+        assert_eq!(store.resolve_link("contacts", "../outside"), "../outside");
+
+        // Failing an exact match, case and spaces versus dashes are ignored.
+        fs::write(temp_dir.join("projects/Road Map.md"), "map").unwrap();
+        assert_eq!(store.resolve_link(from, "PLAN"), "projects/plan");
+        assert_eq!(store.resolve_link(from, "road-map"), "projects/Road Map");
+        assert_eq!(store.resolve_link(from, "Projects/Plan"), "projects/plan");
+        assert_eq!(store.find_note("Contacts.md").as_deref(), Some("contacts"));
+        assert_eq!(store.find_note("my  contacts"), None);
+        assert_eq!(page_key("My  Page - Draft"), "my-page-draft");
+
+        // Cleanup
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn test_import_note_and_attachment() {
+        let temp_dir = env::temp_dir().join("piki-test-import");
+        let source_dir = env::temp_dir().join("piki-test-import-source");
+        let _ = fs::remove_dir_all(&temp_dir);
+        let _ = fs::remove_dir_all(&source_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+        fs::create_dir_all(&source_dir).unwrap();
+
+        fs::write(source_dir.join("ideas.md"), "# Ideas").unwrap();
+        fs::write(source_dir.join("photo.jpg"), "jpg").unwrap();
+        fs::write(temp_dir.join("ideas.md"), "existing").unwrap();
+
+        let store = DocumentStore::new(temp_dir.clone());
+        assert_eq!(
+            store.import_note(&source_dir.join("ideas.md")).unwrap(),
+            "ideas-2"
+        );
+        assert_eq!(store.load("ideas-2").unwrap().content, "# Ideas");
+        assert_eq!(store.load("ideas").unwrap().content, "existing");
+
+        let photo = source_dir.join("photo.jpg");
+        assert_eq!(
+            store.import_attachment(&photo).unwrap(),
+            "attachments/photo.jpg"
+        );
+        assert_eq!(
+            store.import_attachment(&photo).unwrap(),
+            "attachments/photo-2.jpg"
+        );
+        assert!(store.attachment_path("attachments/photo-2.jpg").is_some());
+        assert!(store.import_note(&source_dir.join("missing.md")).is_err());
+
+        // Cleanup
+        fs::remove_dir_all(&temp_dir).ok();
+        fs::remove_dir_all(&source_dir).ok();
+    }
+
+    #[test]
+    fn test_encrypted_notes() {
+        let temp_dir = env::temp_dir().join("piki-test-encrypted");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(temp_dir.join("private")).unwrap();
+        fs::write(temp_dir.join("private/diary.md"), "# Dear diary\n").unwrap();
+
+        let store = DocumentStore::new(temp_dir.clone());
+        store.encrypt_note("private/diary", "hunter2").unwrap();
+        let encrypted = temp_dir.join("private/diary.md.age");
+        assert!(!temp_dir.join("private/diary.md").exists());
+        assert!(
+            !fs::read(&encrypted)
+                .unwrap()
+                .windows(4)
+                .any(|w| w == b"Dear")
+        );
+        assert_eq!(store.path_for("private/diary"), encrypted);
+        assert!(store.is_encrypted("private/diary"));
+        assert_eq!(store.list_all_documents().unwrap(), vec!["private/diary"]);
+        assert!(store.list_attachments().unwrap().is_empty());
+        assert!(store.encrypt_note("private/diary", "again").is_err());
+
+        // Another program without the passphrase asks for it.
+        let mut other = DocumentStore::new(temp_dir.clone());
+        assert!(other.load("private/diary").is_err());
+        other.set_passphrase_prompt(|note| {
+            assert_eq!(note, "private/diary");
+            Some("wrong".to_string())
+        });
+        assert_eq!(
+            other.load("private/diary").err().unwrap(),
+            "Wrong passphrase for 'private/diary'"
+        );
+        other.set_passphrase_prompt(|_| Some("hunter2".to_string()));
+        let mut doc = other.load("private/diary").unwrap();
+        assert_eq!(doc.content, "# Dear diary\n");
+
+        doc.content.push_str("Today I wrote tests.\n");
+        other.save(&doc).unwrap();
+        assert_eq!(
+            store.load("private/diary").unwrap().content,
+            "# Dear diary\nToday I wrote tests.\n"
+        );
+
+        store.decrypt_note("private/diary").unwrap();
+        assert!(!encrypted.exists());
+        assert_eq!(
+            fs::read_to_string(temp_dir.join("private/diary.md")).unwrap(),
+            "# Dear diary\nToday I wrote tests.\n"
+        );
+
+        fs::remove_dir_all(&temp_dir).ok();
+    }
 }
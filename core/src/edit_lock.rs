@@ -0,0 +1,210 @@
+//! Advisory locks telling piki's programs which notes are open where.
+//!
+//! While a note is open, the GUI and `piki edit` each keep a small claim file
+//! in `.piki/open/` saying who has it open and whether that copy has unsaved
+//! edits. Nothing is prevented by it: `piki edit` warns before opening a note
+//! the GUI has unsaved edits in, and the GUI holds back saving a note while
+//! `piki edit` has it open, then offers to merge its edits with the terminal's
+//! once the editor exits.
+//!
+//! A claim is kept alive by refreshing it; one that hasn't been refreshed for
+//! [`STALE_AFTER`] is left over from a program that crashed and is ignored.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::edit_log;
+
+/// `program` of claims held by the GUI.
+pub const GUI: &str = "piki-gui";
+/// `program` of claims held by `piki edit`.
+pub const TERMINAL: &str = "piki edit";
+
+/// How often a holder should [`refresh`](Claim::refresh) its claim.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(20);
+/// Age after which a claim no longer counts.
+pub const STALE_AFTER: Duration = Duration::from_secs(90);
+
+const CLAIM_DIR: &str = "open";
+
+/// Who has a note open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Holder {
+    pub pid: u32,
+    /// [`GUI`], [`TERMINAL`] or another program's name.
+    pub program: String,
+    /// Whether the note has edits there that aren't saved yet.
+    pub dirty: bool,
+    /// Seconds since the Unix epoch when the note was opened.
+    pub since: u64,
+}
+
+impl Holder {
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\n",
+            self.pid,
+            self.program,
+            u8::from(self.dirty),
+            self.since
+        )
+    }
+
+    fn parse(line: &str) -> Option<Holder> {
+        let mut fields = line.trim_end().split('\t');
+        Some(Holder {
+            pid: fields.next()?.parse().ok()?,
+            program: fields.next()?.to_string(),
+            dirty: fields.next()? == "1",
+            since: fields.next()?.parse().ok()?,
+        })
+    }
+}
+
+/// This process's claim on a note, released when dropped.
+pub struct Claim {
+    note: String,
+    path: PathBuf,
+    holder: Holder,
+    written: Instant,
+}
+
+impl Claim {
+    /// The note claimed.
+    pub fn note(&self) -> &str {
+        &self.note
+    }
+
+    /// Renew the claim, recording whether the note has unsaved edits.
+    pub fn refresh(&mut self, dirty: bool) -> Result<(), String> {
+        self.holder.dirty = dirty;
+        self.written = Instant::now();
+        fs::write(&self.path, self.holder.to_line())
+            .map_err(|e| format!("Failed to write '{}': {}", self.path.display(), e))
+    }
+
+    /// [`refresh`](Claim::refresh) the claim if `dirty` changed or it is due,
+    /// for callers that check often.
+    pub fn refresh_if_due(&mut self, dirty: bool) -> Result<(), String> {
+        if dirty != self.holder.dirty || self.written.elapsed() >= REFRESH_INTERVAL {
+            self.refresh(dirty)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// `note` as a file name prefix: `/` is escaped so all claims sit in one
+/// folder, and `#` separates the note from the holder's pid.
+fn file_prefix(note: &str) -> String {
+    let escaped = note
+        .replace('%', "%25")
+        .replace('/', "%2F")
+        .replace('#', "%23");
+    format!("{escaped}#")
+}
+
+/// Claim `note` for this process, as `program`.
+pub fn claim(notes_dir: &Path, note: &str, program: &str) -> Result<Claim, String> {
+    let dir = edit_log::local_dir(notes_dir)?.join(CLAIM_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create '{}': {}", dir.display(), e))?;
+    let pid = std::process::id();
+    let since = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut claim = Claim {
+        note: note.to_string(),
+        path: dir.join(format!("{}{pid}.lock", file_prefix(note))),
+        holder: Holder {
+            pid,
+            program: program.to_string(),
+            dirty: false,
+            since,
+        },
+        written: Instant::now(),
+    };
+    claim.refresh(false)?;
+    Ok(claim)
+}
+
+/// The other processes with a live claim on `note`, longest-held first.
+pub fn holders(notes_dir: &Path, note: &str) -> Vec<Holder> {
+    let dir = notes_dir.join(edit_log::LOCAL_DIR).join(CLAIM_DIR);
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    let prefix = file_prefix(note);
+    let mut holders: Vec<Holder> = entries
+        .flatten()
+        .filter(|entry| {
+            entry.file_name().to_str().is_some_and(|name| {
+                name.strip_prefix(&prefix)
+                    .and_then(|rest| rest.strip_suffix(".lock"))
+                    .is_some_and(|pid| pid.bytes().all(|b| b.is_ascii_digit()))
+            })
+        })
+        .filter(|entry| {
+            entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .unwrap_or_default()
+                <= STALE_AFTER
+        })
+        .filter_map(|entry| Holder::parse(&fs::read_to_string(entry.path()).ok()?))
+        .filter(|holder| holder.pid != std::process::id())
+        .collect();
+    holders.sort_by_key(|holder| holder.since);
+    holders
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claims_are_seen_by_other_processes_only() {
+        let dir = std::env::temp_dir().join("piki-test-edit-lock");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut claim = claim(&dir, "projects/plan", TERMINAL).unwrap();
+        claim.refresh_if_due(true).unwrap();
+        assert!(claim.path.ends_with(format!(
+            ".piki/open/projects%2Fplan#{}.lock",
+            std::process::id()
+        )));
+        // Our own claim doesn't count.
+        assert_eq!(holders(&dir, "projects/plan"), vec![]);
+
+        let other = Holder {
+            pid: std::process::id() + 1,
+            program: GUI.to_string(),
+            dirty: true,
+            since: 1_700_000_000,
+        };
+        let other_path = dir.join(format!(".piki/open/projects%2Fplan#{}.lock", other.pid));
+        fs::write(&other_path, other.to_line()).unwrap();
+        fs::write(
+            dir.join(".piki/open/projects%2Fplan%2Fold#7.lock"),
+            other.to_line(),
+        )
+        .unwrap();
+        assert_eq!(holders(&dir, "projects/plan"), vec![other]);
+        assert_eq!(holders(&dir, "projects"), vec![]);
+
+        drop(claim);
+        assert_eq!(fs::read_dir(dir.join(".piki/open")).unwrap().count(), 2);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,453 @@
+//! Opt-in, local-only log of edits to notes.
+//!
+//! Once enabled (`piki doctor --enable-edit-log`), every save, rename and
+//! delete of a note appends a line to `.piki/edit-log.tsv` inside the notes
+//! directory: when it happened, which note, and what was done — the size of a
+//! saved note, never its content. The `.piki/` folder ignores itself for git,
+//! so the log never leaves the machine.
+//!
+//! `piki doctor` correlates the log with the git history ([`correlate`]) to
+//! explain "where did my edit go" after a sync conflict: the last version
+//! piki saved of a note is compared to what is on disk now and to the commits
+//! that touched the note since.
+
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::git::Commit;
+
+/// Folder inside the notes directory holding piki's local, never-committed
+/// files.
+pub const LOCAL_DIR: &str = ".piki";
+const LOG_FILE: &str = "edit-log.tsv";
+
+/// What was done to a note.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// The note was written; `bytes` is its new size.
+    Save {
+        bytes: u64,
+    },
+    Rename {
+        to: String,
+    },
+    Delete,
+}
+
+/// One line of the edit log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Seconds since the Unix epoch.
+    pub time: u64,
+    pub page: String,
+    pub operation: Operation,
+}
+
+impl Entry {
+    fn to_line(&self) -> String {
+        let (op, arg) = match &self.operation {
+            Operation::Save { bytes } => ("save", bytes.to_string()),
+            Operation::Rename { to } => ("rename", to.clone()),
+            Operation::Delete => ("delete", String::new()),
+        };
+        format!("{}\t{}\t{}\t{}", self.time, self.page, op, arg)
+    }
+
+    fn parse(line: &str) -> Option<Entry> {
+        let mut fields = line.splitn(4, '\t');
+        let time = fields.next()?.parse().ok()?;
+        let page = fields.next()?.to_string();
+        let operation = match (fields.next()?, fields.next().unwrap_or("")) {
+            ("save", bytes) => Operation::Save {
+                bytes: bytes.parse().ok()?,
+            },
+            ("rename", to) if !to.is_empty() => Operation::Rename { to: to.to_string() },
+            ("delete", _) => Operation::Delete,
+            _ => return None,
+        };
+        Some(Entry {
+            time,
+            page,
+            operation,
+        })
+    }
+}
+
+/// Where the edit log of the notes in `notes_dir` lives.
+pub fn log_path(notes_dir: &Path) -> PathBuf {
+    notes_dir.join(LOCAL_DIR).join(LOG_FILE)
+}
+
+/// Whether edits in `notes_dir` are being logged.
+pub fn is_enabled(notes_dir: &Path) -> bool {
+    log_path(notes_dir).is_file()
+}
+
+/// What git leaves out of [`LOCAL_DIR`]: the log, caches and indexes, the
+/// notes open for editing and the backups of edited images. Templates and
+/// plugins are kept, to be shared with the notes.
+const GITIGNORE: &str = "*.tsv\nopen/\nimage-backups/\n";
+
+/// The [`LOCAL_DIR`] of `notes_dir`, created — with a `.gitignore` keeping
+/// what is local to this machine out of git — if it doesn't exist yet.
+pub fn local_dir(notes_dir: &Path) -> Result<PathBuf, String> {
+    let dir = notes_dir.join(LOCAL_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create '{}': {}", dir.display(), e))?;
+    // Earlier versions ignored the whole folder, templates and plugins too.
+    let gitignore = dir.join(".gitignore");
+    if fs::read_to_string(&gitignore).map_or(true, |text| text == "*\n") {
+        fs::write(&gitignore, GITIGNORE)
+            .map_err(|e| format!("Failed to write '{}': {}", gitignore.display(), e))?;
+    }
+    Ok(dir)
+}
+
+/// Replace the file at `path` in [`LOCAL_DIR`] with `contents` in one step:
+/// written to a temporary file next to it and renamed over it, so a cache
+/// being saved by a background thread and the main thread at the same time
+/// is never left half-written or interleaved.
+pub(crate) fn replace_local_file(path: &Path, contents: &str) -> std::io::Result<()> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    // Ends in `.tsv`, so git ignores it like the file it replaces.
+    let temp = path.with_extension(format!(
+        "{}-{}.tmp.tsv",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&temp, contents)
+        .and_then(|()| fs::rename(&temp, path))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&temp);
+        })
+}
+
+/// Start logging edits in `notes_dir`. Keeps an existing log.
+pub fn enable(notes_dir: &Path) -> Result<(), String> {
+    local_dir(notes_dir)?;
+    let path = log_path(notes_dir);
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to create '{}': {}", path.display(), e))
+}
+
+/// Stop logging edits in `notes_dir` and remove the log.
+pub fn disable(notes_dir: &Path) -> Result<(), String> {
+    let path = log_path(notes_dir);
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to remove '{}': {}", path.display(), e)),
+    }
+}
+
+/// Append `operation` on `page` to the log, if logging is enabled.
+pub fn record(notes_dir: &Path, page: &str, operation: Operation) -> Result<(), String> {
+    if !is_enabled(notes_dir) {
+        return Ok(());
+    }
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let entry = Entry {
+        time,
+        page: page.to_string(),
+        operation,
+    };
+    let path = log_path(notes_dir);
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open '{}': {}", path.display(), e))?;
+    writeln!(file, "{}", entry.to_line())
+        .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))
+}
+
+/// All entries of the log, oldest first. Unreadable lines are skipped.
+pub fn read(notes_dir: &Path) -> Result<Vec<Entry>, String> {
+    let path = log_path(notes_dir);
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    Ok(content.lines().filter_map(Entry::parse).collect())
+}
+
+/// A note's current state, as far as [`correlate`] needs to know it.
+#[derive(Debug, Clone, Default)]
+pub struct PageState {
+    /// Size of the note's file, `None` if it doesn't exist.
+    pub bytes: Option<u64>,
+    /// The last commit touching the note and its time (seconds since the
+    /// Unix epoch).
+    pub last_commit: Option<(u64, Commit)>,
+    /// Whether the file differs from its last committed version.
+    pub uncommitted: bool,
+}
+
+/// Something `piki doctor` found out about the last edit of a note.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finding {
+    /// The last saved version was replaced: by `commit`, or — without one — by
+    /// another program.
+    Replaced {
+        page: String,
+        edited: u64,
+        commit: Option<Commit>,
+    },
+    /// The note's file is gone; `commit` is the last one touching it.
+    Missing {
+        page: String,
+        edited: u64,
+        commit: Option<Commit>,
+    },
+    /// The last saved version is on disk but was never committed, so it
+    /// isn't synced.
+    Uncommitted { page: String, edited: u64 },
+}
+
+/// Compare the last logged save of every note with its current state, as
+/// reported by `state`. Renames are followed; deleted notes are left out.
+pub fn correlate(entries: &[Entry], mut state: impl FnMut(&str) -> PageState) -> Vec<Finding> {
+    // Note name → (time, size) of its last save.
+    let mut saved: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    for entry in entries {
+        match &entry.operation {
+            Operation::Save { bytes } => {
+                saved.insert(entry.page.clone(), (entry.time, *bytes));
+            }
+            Operation::Rename { to } => {
+                if let Some(last) = saved.remove(&entry.page) {
+                    saved.insert(to.clone(), last);
+                }
+            }
+            Operation::Delete => {
+                saved.remove(&entry.page);
+            }
+        }
+    }
+
+    let mut findings = Vec::new();
+    for (page, (edited, bytes)) in saved {
+        let current = state(&page);
+        let newer_commit = current
+            .last_commit
+            .clone()
+            .filter(|(time, _)| *time >= edited)
+            .map(|(_, commit)| commit);
+        match current.bytes {
+            None => findings.push(Finding::Missing {
+                page,
+                edited,
+                commit: current.last_commit.map(|(_, commit)| commit),
+            }),
+            Some(now) if now != bytes => findings.push(Finding::Replaced {
+                page,
+                edited,
+                commit: newer_commit,
+            }),
+            Some(_) if current.uncommitted => findings.push(Finding::Uncommitted { page, edited }),
+            Some(_) => {}
+        }
+    }
+    findings
+}
+
+/// `time` (seconds since the Unix epoch) as `YYYY-MM-DD HH:MM UTC`.
+pub fn format_time(time: u64) -> String {
+    let days = (time / 86_400) as i64;
+    let seconds = time % 86_400;
+    // Civil date from days since 1970-01-01 (proleptic Gregorian calendar).
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        seconds / 3600,
+        seconds % 3600 / 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn commit(hash: &str) -> Commit {
+        Commit {
+            hash: hash.to_string(),
+            date: "2025-03-01".to_string(),
+            subject: "sync".to_string(),
+            path: "a.md".to_string(),
+        }
+    }
+
+    fn save(time: u64, page: &str, bytes: u64) -> Entry {
+        Entry {
+            time,
+            page: page.to_string(),
+            operation: Operation::Save { bytes },
+        }
+    }
+
+    #[test]
+    fn records_only_when_enabled() {
+        let dir = env::temp_dir().join("piki-test-edit-log");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        record(&dir, "a", Operation::Save { bytes: 3 }).unwrap();
+        assert!(!is_enabled(&dir));
+
+        enable(&dir).unwrap();
+        record(&dir, "a", Operation::Save { bytes: 3 }).unwrap();
+        record(&dir, "a", Operation::Rename { to: "b".into() }).unwrap();
+        record(&dir, "b", Operation::Delete).unwrap();
+        let entries = read(&dir).unwrap();
+        let ops: Vec<_> = entries.iter().map(|e| e.operation.clone()).collect();
+        assert_eq!(
+            ops,
+            vec![
+                Operation::Save { bytes: 3 },
+                Operation::Rename { to: "b".into() },
+                Operation::Delete
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join(LOCAL_DIR).join(".gitignore")).unwrap(),
+            GITIGNORE
+        );
+
+        disable(&dir).unwrap();
+        assert!(!is_enabled(&dir));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn replaces_local_files_from_several_threads() {
+        let dir = env::temp_dir().join("piki-test-replace-local-file");
+        let _ = fs::remove_dir_all(&dir);
+        let local = local_dir(&dir).unwrap();
+        let path = local.join("cache.tsv");
+
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..20 {
+                        replace_local_file(&path, &format!("{i}\n").repeat(1000)).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // One thread's contents, whole, and no temporary files left over.
+        let text = fs::read_to_string(&path).unwrap();
+        let first = text.lines().next().unwrap();
+        assert!(text.lines().all(|line| line == first));
+        assert_eq!(text.lines().count(), 1000);
+        assert_eq!(fs::read_dir(&local).unwrap().count(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn correlates_last_saves_with_current_state() {
+        let entries = vec![
+            save(100, "replaced", 10),
+            save(100, "changed", 10),
+            save(100, "gone", 10),
+            save(100, "old", 10),
+            Entry {
+                time: 110,
+                page: "old".to_string(),
+                operation: Operation::Rename {
+                    to: "new".to_string(),
+                },
+            },
+            save(100, "deleted", 10),
+            Entry {
+                time: 120,
+                page: "deleted".to_string(),
+                operation: Operation::Delete,
+            },
+            save(100, "fine", 10),
+        ];
+        let findings = correlate(&entries, |page| match page {
+            "replaced" => PageState {
+                bytes: Some(7),
+                last_commit: Some((200, commit("abc"))),
+                uncommitted: false,
+            },
+            "changed" => PageState {
+                bytes: Some(7),
+                last_commit: Some((50, commit("def"))),
+                uncommitted: true,
+            },
+            "gone" => PageState {
+                bytes: None,
+                last_commit: Some((50, commit("ghi"))),
+                uncommitted: false,
+            },
+            "new" => PageState {
+                bytes: Some(10),
+                last_commit: None,
+                uncommitted: true,
+            },
+            _ => PageState {
+                bytes: Some(10),
+                last_commit: Some((150, commit("jkl"))),
+                uncommitted: false,
+            },
+        });
+        assert_eq!(
+            findings,
+            vec![
+                Finding::Replaced {
+                    page: "changed".into(),
+                    edited: 100,
+                    commit: None
+                },
+                Finding::Missing {
+                    page: "gone".into(),
+                    edited: 100,
+                    commit: Some(commit("ghi"))
+                },
+                Finding::Uncommitted {
+                    page: "new".into(),
+                    edited: 100
+                },
+                Finding::Replaced {
+                    page: "replaced".into(),
+                    edited: 100,
+                    commit: Some(commit("abc"))
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn formats_times_in_utc() {
+        assert_eq!(format_time(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_time(1_709_251_199), "2024-02-29 23:59 UTC");
+        assert_eq!(format_time(1_735_689_600), "2025-01-01 00:00 UTC");
+    }
+}
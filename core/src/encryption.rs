@@ -0,0 +1,225 @@
+//! Encrypted notes.
+//!
+//! A note stored as `NAME.md.age` instead of `NAME.md` is encrypted with a
+//! passphrase in the [age](https://age-encryption.org) format, so it can also
+//! be read with `age -d`. [`DocumentStore`](crate::DocumentStore) decrypts such
+//! notes when loading them and encrypts them again when saving; everything
+//! else only ever sees the Markdown. The passphrase is asked for through the
+//! function given to
+//! [`set_passphrase_prompt`](crate::DocumentStore::set_passphrase_prompt) and
+//! remembered until the program exits.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use age::secrecy::{ExposeSecret, SecretString};
+
+/// Appended to `.md` for encrypted notes.
+pub const EXTENSION: &str = ".age";
+
+/// scrypt work factor (log2) of encrypted notes. age's own default takes
+/// about a second per note, too slow to autosave with; this takes about a
+/// tenth of that.
+const WORK_FACTOR: u8 = 15;
+
+/// The highest work factor of notes encrypted elsewhere that is accepted,
+/// well above what `age -p` picks.
+const MAX_WORK_FACTOR: u8 = 22;
+
+/// How often a passphrase is asked for before loading a note fails.
+const PROMPT_ATTEMPTS: usize = 3;
+
+/// Asks for the passphrase of the note given by name; `None` if the user
+/// declined.
+pub type PassphrasePrompt = Box<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/// Whether the file at `path` holds an encrypted note.
+pub fn is_encrypted(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_suffix(EXTENSION))
+        .is_some_and(crate::has_md_extension)
+}
+
+/// `content` encrypted with `passphrase`.
+pub fn encrypt(content: &str, passphrase: &str) -> Result<Vec<u8>, String> {
+    let mut recipient = age::scrypt::Recipient::new(SecretString::from(passphrase));
+    recipient.set_work_factor(WORK_FACTOR);
+    let encryptor =
+        age::Encryptor::with_recipients(std::iter::once(&recipient as &dyn age::Recipient))
+            .map_err(|e| format!("Failed to encrypt: {e}"))?;
+    let mut data = Vec::new();
+    let mut writer = encryptor
+        .wrap_output(&mut data)
+        .map_err(|e| format!("Failed to encrypt: {e}"))?;
+    writer
+        .write_all(content.as_bytes())
+        .and_then(|_| writer.finish())
+        .map_err(|e| format!("Failed to encrypt: {e}"))?;
+    Ok(data)
+}
+
+/// `data` decrypted with `passphrase`, or `None` if it is the wrong one.
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Option<String>, String> {
+    let decryptor = age::Decryptor::new(data).map_err(|e| format!("Not an age file: {e}"))?;
+    if !decryptor.is_scrypt() {
+        return Err("Only notes encrypted with a passphrase can be opened".to_string());
+    }
+    let mut identity = age::scrypt::Identity::new(SecretString::from(passphrase));
+    identity.set_max_work_factor(MAX_WORK_FACTOR);
+    let mut reader = match decryptor.decrypt(std::iter::once(&identity as &dyn age::Identity)) {
+        Ok(reader) => reader,
+        Err(age::DecryptError::DecryptionFailed) => return Ok(None),
+        Err(e) => return Err(format!("Failed to decrypt: {e}")),
+    };
+    let mut content = String::new();
+    reader
+        .read_to_string(&mut content)
+        .map_err(|e| format!("Failed to decrypt: {e}"))?;
+    Ok(Some(content))
+}
+
+/// A file's modification time and size.
+type FileStamp = (Option<SystemTime>, u64);
+
+/// The passphrases of a store's encrypted notes and what they decrypted to.
+#[derive(Default)]
+pub(crate) struct Keyring {
+    prompt: Option<PassphrasePrompt>,
+    /// The passphrase of each note opened so far.
+    passphrases: Mutex<HashMap<String, SecretString>>,
+    /// The content of each file as last read or written, with its
+    /// modification time and size, so that loading a note again only decrypts
+    /// it if the file changed.
+    decrypted: Mutex<HashMap<PathBuf, (FileStamp, String)>>,
+}
+
+impl Keyring {
+    pub(crate) fn set_prompt(&mut self, prompt: PassphrasePrompt) {
+        self.prompt = Some(prompt);
+    }
+
+    /// Remember `passphrase` for the note `name`.
+    pub(crate) fn remember(&self, name: &str, passphrase: &str) {
+        self.passphrases
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), SecretString::from(passphrase));
+    }
+
+    /// The content of the encrypted note `name` read from `path`. The
+    /// passphrases of other notes are tried before asking for one.
+    pub(crate) fn load(&self, name: &str, path: &Path) -> Result<String, String> {
+        let stamp = file_stamp(path);
+        if let Some((cached, content)) = self.decrypted.lock().unwrap().get(path)
+            && *cached == stamp
+        {
+            return Ok(content.clone());
+        }
+
+        let data = std::fs::read(path).map_err(|e| format!("Failed to read '{}': {}", name, e))?;
+        // The note's own passphrase first, then those of the other notes.
+        let known: Vec<String> = {
+            let passphrases = self.passphrases.lock().unwrap();
+            let mut known: Vec<String> = passphrases
+                .get(name)
+                .map(|own| own.expose_secret().to_string())
+                .into_iter()
+                .collect();
+            for passphrase in passphrases.values() {
+                let passphrase = passphrase.expose_secret();
+                if !known.iter().any(|k| k == passphrase) {
+                    known.push(passphrase.to_string());
+                }
+            }
+            known
+        };
+        for passphrase in known {
+            if let Some(content) = decrypt(&data, &passphrase)? {
+                return Ok(self.opened(name, path, passphrase, content));
+            }
+        }
+
+        let Some(prompt) = &self.prompt else {
+            return Err(format!("'{name}' is encrypted and no passphrase is known"));
+        };
+        for _ in 0..PROMPT_ATTEMPTS {
+            let passphrase = prompt(name)
+                .ok_or_else(|| format!("'{name}' is encrypted; no passphrase given"))?;
+            if let Some(content) = decrypt(&data, &passphrase)? {
+                return Ok(self.opened(name, path, passphrase, content));
+            }
+        }
+        Err(format!("Wrong passphrase for '{name}'"))
+    }
+
+    fn opened(&self, name: &str, path: &Path, passphrase: String, content: String) -> String {
+        self.remember(name, &passphrase);
+        self.cache(path, &content);
+        content
+    }
+
+    /// `content` of the note `name` encrypted with its passphrase, asked for
+    /// if the note wasn't opened before.
+    pub(crate) fn encrypt(&self, name: &str, content: &str) -> Result<Vec<u8>, String> {
+        let known = self
+            .passphrases
+            .lock()
+            .unwrap()
+            .get(name)
+            .map(|passphrase| passphrase.expose_secret().to_string());
+        let passphrase = match known {
+            Some(passphrase) => passphrase,
+            None => {
+                let passphrase = self
+                    .prompt
+                    .as_ref()
+                    .and_then(|prompt| prompt(name))
+                    .ok_or_else(|| format!("No passphrase to encrypt '{name}' with"))?;
+                self.remember(name, &passphrase);
+                passphrase
+            }
+        };
+        encrypt(content, &passphrase)
+    }
+
+    /// Remember that the file at `path` now holds `content`.
+    pub(crate) fn cache(&self, path: &Path, content: &str) {
+        self.decrypted
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), (file_stamp(path), content.to_string()));
+    }
+}
+
+fn file_stamp(path: &Path) -> FileStamp {
+    let meta = std::fs::metadata(path).ok();
+    (
+        meta.as_ref().and_then(|m| m.modified().ok()),
+        meta.map_or(0, |m| m.len()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypts_with_a_passphrase() {
+        let data = encrypt("# Secret\n", "correct horse").unwrap();
+        assert!(data.starts_with(b"age-encryption.org/v1\n"));
+        assert_eq!(
+            decrypt(&data, "correct horse").unwrap().as_deref(),
+            Some("# Secret\n")
+        );
+        assert_eq!(decrypt(&data, "battery staple").unwrap(), None);
+        assert!(decrypt(b"# Secret\n", "correct horse").is_err());
+
+        assert!(is_encrypted(Path::new("notes/plan.md.age")));
+        assert!(!is_encrypted(Path::new("notes/plan.md")));
+        assert!(!is_encrypted(Path::new("notes/photo.jpg.age")));
+    }
+}
@@ -0,0 +1,394 @@
+//! The parts of `piki export` that don't depend on how a note is rendered:
+//! what of a note is published, links between exported notes, the page
+//! around an HTML export, and the EPUB container holding the notes of an
+//! e-book.
+//!
+//! An HTML export is placed in a template with `{{title}}` and `{{content}}`
+//! placeholders, [`DEFAULT_HTML_TEMPLATE`] unless one is configured:
+//!
+//! ```html
+//! <!DOCTYPE html>
+//! <html><head><title>{{title}} – My Wiki</title></head>
+//! <body>{{content}}<footer>Exported from piki</footer></body></html>
+//! ```
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write as _;
+
+use crate::DocumentStore;
+use crate::dates::Date;
+use crate::graph::{self, resolve};
+use crate::images::link_destination;
+use crate::link_target;
+use crate::redact::{RedactionRules, redact};
+use crate::tags::is_code_fence;
+use crate::zip::ZipWriter;
+
+/// The page around an HTML export.
+pub const DEFAULT_HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{title}}</title>
+<style>
+body { font-family: system-ui, sans-serif; line-height: 1.6; max-width: 760px; margin: 0 auto; padding: 24px; }
+pre, code { font-family: ui-monospace, monospace; background: #f3f3f3; }
+pre { padding: 12px; overflow-x: auto; }
+blockquote { margin-left: 0; padding-left: 1em; border-left: 3px solid #ccc; color: #555; }
+</style>
+</head>
+<body>
+{{content}}
+</body>
+</html>
+"#;
+
+/// The note `name` with `content` as it is exported: redacted by `rules`
+/// (see [`crate::redact`]), unless there are none, and, with `backlinks`,
+/// ending with a "Linked from" section listing the lines of other notes
+/// linking to it that are published, too. `None` if the note isn't published
+/// at all.
+pub fn published_content(
+    store: &DocumentStore,
+    name: &str,
+    content: &str,
+    backlinks: bool,
+    rules: Option<&RedactionRules>,
+) -> Result<Option<String>, String> {
+    let publish = |content: &str| match rules {
+        Some(rules) => redact(content, rules),
+        None => Some(content.to_string()),
+    };
+    let Some(mut content) = publish(content) else {
+        return Ok(None);
+    };
+    if backlinks && !name.starts_with('!') {
+        // The published version of each linking note, to tell whether the
+        // linking line survives its redaction.
+        let mut published: HashMap<String, Option<String>> = HashMap::new();
+        let public = graph::backlinks(store, name)?
+            .into_iter()
+            .filter(|backlink| {
+                let Ok(doc) = store.load(&backlink.page) else {
+                    return false;
+                };
+                let Some(line) = doc.content.lines().nth(backlink.line - 1) else {
+                    return false;
+                };
+                published
+                    .entry(backlink.page.clone())
+                    .or_insert_with(|| publish(&doc.content))
+                    .as_ref()
+                    .is_some_and(|published| published.lines().any(|other| other == line))
+            })
+            .collect::<Vec<_>>();
+        let section = graph::linked_from(&public);
+        if !section.is_empty() {
+            content = format!("{}\n\n{section}", content.trim_end());
+        }
+    }
+    Ok(Some(content))
+}
+
+/// `template` with `{{title}}` replaced by `title`, escaped, and
+/// `{{content}}` by the HTML `content`.
+pub fn fill_html_template(template: &str, title: &str, content: &str) -> String {
+    template
+        .replace("{{title}}", &escape_xml(title))
+        .replace("{{content}}", content)
+}
+
+/// `content` of the note `page` with the links to notes in `pages` pointing
+/// at their exported files: `[[projects/plan]]` becomes
+/// `[projects/plan](projects/plan.html)` for `extension` `html`, relative to
+/// `page`'s folder. Wiki links to notes that don't exist become their text.
+/// Links in code are left alone.
+pub fn link_exported(
+    page: &str,
+    content: &str,
+    pages: &BTreeSet<String>,
+    extension: &str,
+) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut in_fence = false;
+    for line in content.split_inclusive('\n') {
+        if is_code_fence(line) {
+            in_fence = !in_fence;
+        }
+        if in_fence || is_code_fence(line) {
+            out.push_str(line);
+            continue;
+        }
+
+        let mut in_code = false;
+        let mut rest = line;
+        while let Some(c) = rest.chars().next() {
+            if c == '`' {
+                in_code = !in_code;
+            } else if !in_code
+                && let Some(after) = rest.strip_prefix("[[")
+                && let Some(end) = after.find("]]")
+            {
+                let link = &after[..end];
+                let (target, label) = link.split_once('|').unwrap_or((link, link));
+                match exported_file(page, target, pages, extension) {
+                    Some(file) => {
+                        let _ = write!(out, "[{}]({})", label.trim(), link_target(&file));
+                    }
+                    None => out.push_str(label.trim()),
+                }
+                rest = &after[end + 2..];
+                continue;
+            } else if !in_code
+                && let Some(after) = rest.strip_prefix("](")
+                && let Some(end) = after.find(')')
+                && let Some(file) = exported_file(page, &after[..end], pages, extension)
+            {
+                let _ = write!(out, "]({})", link_target(&file));
+                rest = &after[end + 1..];
+                continue;
+            }
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+/// The exported file a link from `page` to `target` leads to, relative to
+/// `page`'s folder, keeping a `#section`.
+fn exported_file(
+    page: &str,
+    target: &str,
+    pages: &BTreeSet<String>,
+    extension: &str,
+) -> Option<String> {
+    let name = link_destination(target)?;
+    let linked = resolve(page, &name, pages)?;
+    let fragment = target
+        .trim()
+        .split_once('#')
+        .map(|(_, fragment)| format!("#{}", fragment.split_whitespace().next().unwrap_or("")))
+        .unwrap_or_default();
+
+    let from: Vec<&str> = page.split('/').collect();
+    let to: Vec<&str> = linked.split('/').collect();
+    let from_dir = &from[..from.len() - 1];
+    let common = from_dir.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut path = "../".repeat(from_dir.len() - common);
+    path.push_str(&to[common..].join("/"));
+    Some(format!("{path}.{extension}{fragment}"))
+}
+
+/// `text` with the characters special in HTML and XML escaped.
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A note in an e-book.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    /// The note's name, which places it in the book as `name.xhtml`.
+    pub name: String,
+    pub title: String,
+    /// The note as an XHTML fragment, its links made with
+    /// [`link_exported`] and extension `xhtml`.
+    pub body: String,
+}
+
+/// An EPUB 3 e-book titled `title` with `chapters` in order, with a table of
+/// contents listing them.
+pub fn epub(title: &str, chapters: &[Chapter], date: Date) -> Vec<u8> {
+    let mut zip = ZipWriter::default();
+    // The mimetype must come first, uncompressed, for readers to recognize
+    // the book; the zip only stores files anyway.
+    zip.add("mimetype", b"application/epub+zip", date);
+    zip.add(
+        "META-INF/container.xml",
+        br#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+<rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>
+"#,
+        date,
+    );
+
+    let mut manifest = String::new();
+    let mut spine = String::new();
+    let mut toc = String::new();
+    for (i, chapter) in chapters.iter().enumerate() {
+        let file = format!("{}.xhtml", chapter.name);
+        let depth = chapter.name.matches('/').count();
+        let stylesheet = format!("{}style.css", "../".repeat(depth));
+        let _ = writeln!(
+            manifest,
+            r#"<item id="c{i}" href="{}" media-type="application/xhtml+xml"/>"#,
+            escape_xml(&file)
+        );
+        let _ = writeln!(spine, r#"<itemref idref="c{i}"/>"#);
+        let _ = writeln!(
+            toc,
+            r#"<li><a href="{}">{}</a></li>"#,
+            escape_xml(&file),
+            escape_xml(&chapter.title)
+        );
+        zip.add(
+            &format!("OEBPS/{file}"),
+            xhtml(&chapter.title, &stylesheet, &chapter.body).as_bytes(),
+            date,
+        );
+    }
+
+    let nav = format!("<nav epub:type=\"toc\" id=\"toc\"><h1>Contents</h1><ol>\n{toc}</ol></nav>");
+    zip.add(
+        "OEBPS/nav.xhtml",
+        xhtml(title, "style.css", &nav).as_bytes(),
+        date,
+    );
+    zip.add(
+        "OEBPS/style.css",
+        b"body { font-family: serif; line-height: 1.5; }\npre, code { font-family: monospace; }\nblockquote { margin-left: 1em; font-style: italic; }\n",
+        date,
+    );
+    let title = escape_xml(title);
+    let opf = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="id">
+<metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+<dc:identifier id="id">urn:piki:{title}:{date}</dc:identifier>
+<dc:title>{title}</dc:title>
+<dc:language>en</dc:language>
+<meta property="dcterms:modified">{date}T00:00:00Z</meta>
+</metadata>
+<manifest>
+<item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+<item id="style" href="style.css" media-type="text/css"/>
+{manifest}</manifest>
+<spine>
+{spine}</spine>
+</package>
+"#
+    );
+    zip.add("OEBPS/content.opf", opf.as_bytes(), date);
+    zip.finish()
+}
+
+fn xhtml(title: &str, stylesheet: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head><title>{}</title><link rel="stylesheet" type="text/css" href="{}"/></head>
+<body>
+{}
+</body>
+</html>
+"#,
+        escape_xml(title),
+        escape_xml(stylesheet),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn publishes_without_private_material() {
+        let dir = std::env::temp_dir().join("piki-test-export-redact");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let plan = "# Plan\n\nPublic.\n\n%%private%%\nSalaries\n%%private%%\n\nLaunch #private\n";
+        std::fs::write(dir.join("plan.md"), plan).unwrap();
+        std::fs::write(
+            dir.join("home.md"),
+            "See [[plan]].\n\n[[plan]] again #private\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("diary.md"), "#secret\n\n[[plan]]\n").unwrap();
+        let store = DocumentStore::new(dir.clone());
+        let rules = RedactionRules {
+            exclude_tags: vec!["secret".to_string()],
+            ..RedactionRules::default()
+        };
+
+        assert_eq!(
+            published_content(&store, "plan", plan, true, Some(&rules)).unwrap(),
+            Some("# Plan\n\nPublic.\n\n## Linked from\n\n- [home](home) — See plan.\n".to_string())
+        );
+        assert_eq!(
+            published_content(
+                &store,
+                "diary",
+                "---\ntags: [secret]\n---\n",
+                false,
+                Some(&rules)
+            )
+            .unwrap(),
+            None
+        );
+
+        assert_eq!(
+            published_content(&store, "plan", plan, false, None).unwrap(),
+            Some(plan.to_string())
+        );
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn links_point_at_exported_files() {
+        let pages: BTreeSet<String> = ["home", "projects/plan", "projects/notes"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        assert_eq!(
+            link_exported(
+                "projects/notes",
+                "See [[plan|the plan]], [home](../home.md#todo) and [[gone]].\n`[[plan]]`\n```\n[[plan]]\n```\n",
+                &pages,
+                "html"
+            ),
+            "See [the plan](plan.html), [home](../home.html#todo) and gone.\n`[[plan]]`\n```\n[[plan]]\n```\n"
+        );
+        assert_eq!(
+            link_exported(
+                "home",
+                "[[projects/plan]] [x](https://example.com)",
+                &pages,
+                "xhtml"
+            ),
+            "[projects/plan](projects/plan.xhtml) [x](https://example.com)"
+        );
+        assert_eq!(
+            fill_html_template("<title>{{title}}</title>{{content}}", "R&D", "<p>x</p>"),
+            "<title>R&amp;D</title><p>x</p>"
+        );
+    }
+
+    #[test]
+    fn writes_epub_containers() {
+        let chapters = [Chapter {
+            name: "projects/plan".into(),
+            title: "Plan".into(),
+            body: "<p>Hi</p>".into(),
+        }];
+        let book = epub("Wiki", &chapters, Date::parse("2025-06-01").unwrap());
+        assert!(book.starts_with(b"PK\x03\x04"));
+        // The mimetype is the first file, readable at a fixed offset.
+        assert_eq!(&book[30..38], b"mimetype");
+        assert_eq!(&book[38..58], b"application/epub+zip");
+        let end = &book[book.len() - 22..];
+        assert!(end.starts_with(b"PK\x05\x06"));
+        assert_eq!(u16::from_le_bytes([end[10], end[11]]), 6);
+        let text = String::from_utf8_lossy(&book);
+        assert!(text.contains("OEBPS/projects/plan.xhtml"));
+        assert!(text.contains(r#"href="../style.css""#));
+        assert!(text.contains(r#"<li><a href="projects/plan.xhtml">Plan</a></li>"#));
+    }
+}
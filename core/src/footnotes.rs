@@ -0,0 +1,207 @@
+//! Footnotes: `[^label]` references and `[^label]: text` definitions.
+//!
+//! Notes keep footnotes as plain Markdown, which the editor round-trips as
+//! written. Output that lays a note out as a page — `piki view` and the PDF
+//! export — uses [`resolve`] to number the references in order of first use,
+//! replace each with a marker such as a superscript number, and move the
+//! definitions into a numbered list at the end of the page.
+
+/// One footnote, numbered for display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Footnote {
+    pub number: usize,
+    pub label: String,
+    pub text: String,
+}
+
+/// A note's Markdown with its footnotes resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolved {
+    /// The Markdown without definitions, references replaced by markers.
+    pub body: String,
+    /// Referenced footnotes in order of first use, then unreferenced ones.
+    pub notes: Vec<Footnote>,
+}
+
+impl Resolved {
+    /// The body followed by a rule and the footnotes as a numbered list, or
+    /// just the body if there are none.
+    pub fn to_markdown(&self) -> String {
+        if self.notes.is_empty() {
+            return self.body.clone();
+        }
+        let mut out = self.body.trim_end().to_string();
+        out.push_str("\n\n---\n\n");
+        for note in &self.notes {
+            out.push_str(&format!("{}. {}\n", note.number, note.text));
+        }
+        out
+    }
+}
+
+/// The label of a definition line `[^label]: text`, and the text.
+fn definition(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix("[^")?;
+    let end = rest.find("]:")?;
+    let label = &rest[..end];
+    valid_label(label).then(|| (label, rest[end + 2..].trim()))
+}
+
+fn valid_label(label: &str) -> bool {
+    !label.is_empty() && !label.contains(|c: char| c.is_whitespace() || c == '[' || c == ']')
+}
+
+fn is_fence(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+/// Resolve the footnotes of `markdown`, replacing each reference with
+/// `marker(number)`. References without a definition and anything inside
+/// code are left alone.
+pub fn resolve(markdown: &str, marker: impl Fn(usize) -> String) -> Resolved {
+    // Collect the definitions and drop them (and their indented continuation
+    // lines) from the body.
+    let mut definitions: Vec<(String, String)> = Vec::new();
+    let mut body_lines: Vec<&str> = Vec::new();
+    let mut in_fence = false;
+    let mut in_definition = false;
+    for line in markdown.lines() {
+        if is_fence(line) {
+            in_fence = !in_fence;
+            in_definition = false;
+        } else if !in_fence {
+            if let Some((label, text)) = definition(line) {
+                definitions.push((label.to_string(), text.to_string()));
+                in_definition = true;
+                continue;
+            }
+            if in_definition && (line.starts_with("    ") || line.starts_with('\t')) {
+                if let Some((_, text)) = definitions.last_mut() {
+                    text.push(' ');
+                    text.push_str(line.trim());
+                }
+                continue;
+            }
+            in_definition = false;
+        }
+        body_lines.push(line);
+    }
+
+    let defined = |label: &str| definitions.iter().any(|(l, _)| l == label);
+    let mut order: Vec<String> = Vec::new();
+    let mut body = String::with_capacity(markdown.len());
+    let mut in_fence = false;
+    for line in body_lines {
+        if is_fence(line) {
+            in_fence = !in_fence;
+        }
+        if in_fence || is_fence(line) {
+            body.push_str(line);
+            body.push('\n');
+            continue;
+        }
+
+        let mut in_code = false;
+        let mut rest = line;
+        while let Some(c) = rest.chars().next() {
+            if c == '`' {
+                in_code = !in_code;
+            } else if !in_code
+                && let Some(inner) = rest.strip_prefix("[^")
+                && let Some(end) = inner.find(']')
+                && defined(&inner[..end])
+            {
+                let label = &inner[..end];
+                let number = match order.iter().position(|l| l == label) {
+                    Some(i) => i + 1,
+                    None => {
+                        order.push(label.to_string());
+                        order.len()
+                    }
+                };
+                body.push_str(&marker(number));
+                rest = &inner[end + 1..];
+                continue;
+            }
+            body.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        body.push('\n');
+    }
+    if !markdown.ends_with('\n') {
+        body.pop();
+    }
+
+    let unreferenced = definitions
+        .iter()
+        .filter(|(label, _)| !order.contains(label))
+        .map(|(label, _)| label.clone())
+        .collect::<Vec<_>>();
+    let notes = order
+        .into_iter()
+        .chain(unreferenced)
+        .enumerate()
+        .filter_map(|(i, label)| {
+            let text = definitions.iter().find(|(l, _)| *l == label)?.1.clone();
+            Some(Footnote {
+                number: i + 1,
+                label,
+                text,
+            })
+        })
+        .collect();
+
+    Resolved { body, notes }
+}
+
+/// `n` in superscript digits, e.g. `¹²`.
+pub fn superscript(n: usize) -> String {
+    const DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+    n.to_string()
+        .bytes()
+        .map(|b| DIGITS[usize::from(b - b'0')])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_references_in_order_of_use() {
+        let md = "Second[^b] and first[^a], again[^b].\n\n[^a]: Alpha.\n[^b]: Beta\n    continued.\n[^c]: Unused.\n";
+        let resolved = resolve(md, superscript);
+        assert_eq!(resolved.body, "Second¹ and first², again¹.\n\n");
+        let notes: Vec<(usize, &str, &str)> = resolved
+            .notes
+            .iter()
+            .map(|n| (n.number, n.label.as_str(), n.text.as_str()))
+            .collect();
+        assert_eq!(
+            notes,
+            vec![
+                (1, "b", "Beta continued."),
+                (2, "a", "Alpha."),
+                (3, "c", "Unused.")
+            ]
+        );
+        assert_eq!(
+            resolved.to_markdown(),
+            "Second¹ and first², again¹.\n\n---\n\n1. Beta continued.\n2. Alpha.\n3. Unused.\n"
+        );
+    }
+
+    #[test]
+    fn code_and_undefined_references_are_kept() {
+        let md = "Use `x[^1]` or [^2].\n\n```\n[^1]: in code\n```\n\n[^1]: Real.";
+        let resolved = resolve(md, |n| format!("[{n}]"));
+        assert_eq!(
+            resolved.body,
+            "Use `x[^1]` or [^2].\n\n```\n[^1]: in code\n```\n"
+        );
+        assert_eq!(resolved.notes.len(), 1);
+        assert_eq!(resolve("No notes.", superscript).to_markdown(), "No notes.");
+        assert_eq!(superscript(10), "¹⁰");
+    }
+}
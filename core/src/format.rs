@@ -0,0 +1,241 @@
+//! Keeping saved Markdown stable, so git diffs show only what was edited.
+//!
+//! The editor works on a parsed document and serializes it back to Markdown
+//! in its own style, which can differ from how the file was written (`*`
+//! instead of `-` bullets, spacing, line breaks). Writing that serialization
+//! out would reformat the whole note on the first edit. Instead,
+//! [`preserve_formatting`] carries only the edits over into the file's own
+//! text: the lines the edit didn't touch keep their exact bytes, and a note
+//! that wasn't edited stays identical.
+//!
+//! Wikis that prefer one consistent style can opt into [`CanonicalFormat`],
+//! which rewrites each note into a normalized form on save: `-` bullets, `1.`
+//! numbers, ATX headings surrounded by blank lines, no runs of blank lines or
+//! trailing whitespace, and optionally paragraphs hard-wrapped at a column.
+
+use crate::frontmatter;
+use crate::merge::merge3;
+use crate::section;
+use crate::tags::is_code_fence;
+use crate::wrap::{hard_wrap, is_thematic_break};
+
+/// The text to write for a note whose file held `original`, which the editor
+/// serialized as `normalized` when it was loaded and as `edited` now.
+///
+/// The changes from `normalized` to `edited` are applied to `original`. Where
+/// an edit touches lines the editor had reformatted, the two can't be told
+/// apart and `edited` is used as it is.
+pub fn preserve_formatting(original: &str, normalized: &str, edited: &str) -> String {
+    if edited == normalized {
+        return original.to_string();
+    }
+    if original == normalized {
+        return edited.to_string();
+    }
+    let merged = merge3(normalized, edited, original);
+    if merged.conflicts > 0 {
+        edited.to_string()
+    } else {
+        merged.text
+    }
+}
+
+/// A normalized Markdown style applied to notes on save.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CanonicalFormat {
+    /// Hard-wrap paragraphs, list items and quotes at this column.
+    pub wrap: Option<usize>,
+}
+
+impl CanonicalFormat {
+    /// `markdown` in the canonical style. Applying it again changes nothing.
+    pub fn apply(&self, markdown: &str) -> String {
+        let normalized = normalize(markdown);
+        match self.wrap {
+            Some(column) if column > 0 => hard_wrap(&normalized, column),
+            _ => normalized,
+        }
+    }
+}
+
+fn normalize(markdown: &str) -> String {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let skip = frontmatter::line_count(markdown);
+    let mut out: Vec<String> = lines[..skip].iter().map(|l| l.to_string()).collect();
+    let mut in_code = false;
+
+    let mut index = skip;
+    while index < lines.len() {
+        let line = lines[index];
+        index += 1;
+        if is_code_fence(line) {
+            in_code = !in_code;
+            out.push(line.trim_end().to_string());
+            continue;
+        }
+        if in_code {
+            out.push(line.to_string());
+            continue;
+        }
+        if line.trim().is_empty() {
+            // One blank line between blocks, none at the start.
+            if out.len() > skip && out.last().is_some_and(|l| !l.is_empty()) {
+                out.push(String::new());
+            }
+            continue;
+        }
+
+        // Setext headings become ATX headings.
+        let underline = lines.get(index).map(|l| l.trim());
+        let setext = match underline {
+            Some(u) if !u.is_empty() && u.chars().all(|c| c == '=') => Some(1),
+            Some(u) if u.len() >= 2 && u.chars().all(|c| c == '-') => Some(2),
+            _ => None,
+        };
+        let heading = match (atx_heading(line), setext) {
+            (Some(heading), _) => Some(heading),
+            (None, Some(level)) if !line.starts_with([' ', '>', '-', '*', '+']) => {
+                index += 1;
+                Some(format!("{} {}", "#".repeat(level), line.trim()))
+            }
+            _ => None,
+        };
+        if let Some(heading) = heading {
+            if out.len() > skip && out.last().is_some_and(|l| !l.is_empty()) {
+                out.push(String::new());
+            }
+            out.push(heading);
+            out.push(String::new());
+            continue;
+        }
+
+        out.push(normalize_list_marker(&trim_trailing(line)));
+    }
+
+    while out.last().is_some_and(|l| l.is_empty()) && out.len() > skip {
+        out.pop();
+    }
+    let mut text = out.join("\n");
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text
+}
+
+/// `line` as `# Heading` if it is an ATX heading: one space after the
+/// hashes, no closing hashes.
+fn atx_heading(line: &str) -> Option<String> {
+    let (level, text) = section::atx_heading(line)?;
+    Some(if text.is_empty() {
+        "#".repeat(level)
+    } else {
+        format!("{} {text}", "#".repeat(level))
+    })
+}
+
+/// `line` without trailing whitespace, except a two-space hard line break.
+fn trim_trailing(line: &str) -> String {
+    let trimmed = line.trim_end();
+    if line.ends_with("  ") {
+        format!("{trimmed}  ")
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// `-` for bullets and `.` after numbers, inside quotes, too.
+fn normalize_list_marker(line: &str) -> String {
+    let quote_end = line.len() - line.trim_start_matches(['>', ' ']).len();
+    let (quote, rest) = line.split_at(quote_end);
+    if let Some(text) = rest.strip_prefix("* ").or_else(|| rest.strip_prefix("+ "))
+        && !is_thematic_break(rest)
+    {
+        return format!("{quote}- {text}");
+    }
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if (1..=9).contains(&digits)
+        && let Some(text) = rest[digits..].strip_prefix(") ")
+    {
+        return format!("{quote}{}. {text}", &rest[..digits]);
+    }
+    line.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_keep_the_original_formatting() {
+        let original = "Title\n=====\n\n* one\n* two\n\nText.\n";
+        let normalized = "# Title\n\n- one\n- two\n\nText.\n";
+        // Unedited: byte-identical.
+        assert_eq!(
+            preserve_formatting(original, normalized, normalized),
+            original
+        );
+        // An edit to a line the editor didn't reformat is carried over alone.
+        let edited = "# Title\n\n- one\n- two\n\nMore text.\n";
+        assert_eq!(
+            preserve_formatting(original, normalized, edited),
+            "Title\n=====\n\n* one\n* two\n\nMore text.\n"
+        );
+        // An edit to a reformatted line can't be carried over.
+        let edited = "# Title\n\n- one\n- three\n\nText.\n";
+        assert_eq!(preserve_formatting(original, normalized, edited), edited);
+    }
+
+    #[test]
+    fn canonical_format() {
+        let markdown = "\
+---
+tags: [a]
+---
+
+
+Title
+=====
+Intro \n\
+with break  \n\
+* one
++ two
+  1) nested
+> * quoted
+## Section ##
+
+
+```
+*  kept
+```
+* * *
+";
+        let canonical = CanonicalFormat::default().apply(markdown);
+        assert_eq!(
+            canonical,
+            "\
+---
+tags: [a]
+---
+# Title
+
+Intro
+with break  \n\
+- one
+- two
+  1. nested
+> - quoted
+
+## Section
+
+```
+*  kept
+```
+* * *
+"
+        );
+        assert_eq!(CanonicalFormat::default().apply(&canonical), canonical);
+
+        let wrapped = CanonicalFormat { wrap: Some(10) }.apply("Some words to wrap here\n");
+        assert_eq!(wrapped, "Some words\nto wrap\nhere\n");
+    }
+}
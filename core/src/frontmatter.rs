@@ -0,0 +1,379 @@
+//! YAML frontmatter: the block between two `---` lines a page may start with,
+//! holding properties such as its title, tags and language.
+//!
+//! The rest of piki reads single properties out of it (see [`crate::title`],
+//! [`crate::tags`], [`crate::spellcheck`]). This module finds where it ends,
+//! so that it can be kept out of what is parsed as Markdown, and reads and
+//! changes its simple `key: value` entries, the page's properties, without
+//! disturbing anything else in it.
+
+/// Properties whose values are lists, written as `[a, b]`.
+const LIST_KEYS: &[&str] = &["tags", "aliases"];
+
+/// Number of leading lines taken up by frontmatter.
+pub fn line_count(content: &str) -> usize {
+    let mut lines = content.lines();
+    if lines.next().map(str::trim_end) != Some("---") {
+        return 0;
+    }
+    match lines.position(|line| line.trim_end() == "---") {
+        Some(end) => end + 2,
+        None => 0,
+    }
+}
+
+/// `content` split into its frontmatter, with the blank lines after it, and
+/// the text of the page. The frontmatter is empty if the page has none; put
+/// back together, the two are `content` again.
+pub fn split(content: &str) -> (&str, &str) {
+    let lines = line_count(content);
+    if lines == 0 {
+        return ("", content);
+    }
+    let mut end = 0;
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        if index >= lines && !line.trim().is_empty() {
+            break;
+        }
+        end += line.len();
+    }
+    content.split_at(end)
+}
+
+/// A top-level entry of the frontmatter: lines `lines` of its inside.
+struct Entry {
+    key: String,
+    lines: std::ops::Range<usize>,
+    /// Its value, if it is a property (see [`properties`]).
+    value: Option<String>,
+    is_list: bool,
+}
+
+/// The lines between the delimiters of `frontmatter` and what follows the
+/// closing one.
+fn inside(frontmatter: &str) -> (Vec<&str>, &str) {
+    let count = line_count(frontmatter);
+    if count == 0 {
+        return (Vec::new(), "");
+    }
+    let lines = frontmatter
+        .lines()
+        .skip(1)
+        .take(count - 2)
+        .collect::<Vec<_>>();
+    let closing_end = frontmatter
+        .split_inclusive('\n')
+        .take(count)
+        .map(str::len)
+        .sum();
+    (lines, &frontmatter[closing_end..])
+}
+
+/// `value` without the quotes around it.
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    if let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        inner.replace("\\\"", "\"").replace("\\\\", "\\")
+    } else if let Some(inner) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        inner.replace("''", "'")
+    } else {
+        value.to_string()
+    }
+}
+
+/// The characters of `value` outside of quoted text, with their byte
+/// offsets. Quotes only count where a scalar starts: at the beginning, after
+/// a space, or after the `[` or `,` of a list in brackets.
+fn outside_quotes(value: &str) -> Vec<(usize, char)> {
+    let mut outside = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    let mut chars = value.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        match quote {
+            Some('"') if c == '\\' => {
+                chars.next();
+            }
+            Some('\'') if c == '\'' && chars.peek().is_some_and(|&(_, next)| next == '\'') => {
+                chars.next();
+            }
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if matches!(c, '"' | '\'')
+                && (previous.is_whitespace() || matches!(previous, '[' | ',')) =>
+            {
+                quote = Some(c)
+            }
+            None => outside.push((index, c)),
+        }
+        previous = c;
+    }
+    outside
+}
+
+/// `value` without a trailing ` # comment`.
+fn without_comment(value: &str) -> &str {
+    let comment = outside_quotes(value).into_iter().find(|&(index, c)| {
+        c == '#' && (index == 0 || value[..index].ends_with(char::is_whitespace))
+    });
+    match comment {
+        Some((index, _)) => value[..index].trim_end(),
+        None => value,
+    }
+}
+
+/// The unquoted items of `list`, the inside of a list in brackets.
+fn list_items(list: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut start = 0;
+    for (index, c) in outside_quotes(list) {
+        if c == ',' {
+            items.push(unquote(&list[start..index]));
+            start = index + 1;
+        }
+    }
+    items.push(unquote(&list[start..]));
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+/// `value` in double quotes if YAML would read it as something else than
+/// this text, or, as an item of a list in brackets, end it early.
+fn quote(value: &str, in_list: bool) -> String {
+    let special = value.starts_with([
+        '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%', '@',
+        '`',
+    ]) || value.contains(": ")
+        || value.contains(" #")
+        || value.trim() != value
+        || (in_list && value.contains([',', '[', ']']));
+    if special {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The top-level entries of `lines`, the inside of a frontmatter.
+fn entries(lines: &[&str]) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let top_level = !line.starts_with([' ', '\t', '-', '#']) && !line.trim().is_empty();
+        match line.split_once(':') {
+            Some((key, value)) if top_level => {
+                let value = without_comment(value.trim());
+                let (value, is_list) =
+                    if let Some(list) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                        (Some(list_items(list).join(", ")), true)
+                    } else if value.starts_with(['{', '|', '>', '&', '*', '!']) {
+                        (None, false)
+                    } else {
+                        (Some(unquote(value)), false)
+                    };
+                entries.push(Entry {
+                    key: key.trim().to_string(),
+                    lines: index..index + 1,
+                    value,
+                    is_list,
+                });
+            }
+            _ => {
+                // Part of the entry above: an item of its list, or something
+                // nested that makes it no property.
+                let Some(entry) = entries.last_mut().filter(|e| e.lines.end == index) else {
+                    continue;
+                };
+                if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                    continue;
+                }
+                entry.lines.end = index + 1;
+                let item = line
+                    .trim_start()
+                    .strip_prefix("- ")
+                    .map(|item| unquote(without_comment(item)));
+                entry.value = match (item, entry.value.take()) {
+                    (Some(item), Some(value)) if value.is_empty() || entry.is_list => {
+                        entry.is_list = true;
+                        Some(if value.is_empty() {
+                            item
+                        } else {
+                            format!("{value}, {item}")
+                        })
+                    }
+                    _ => None,
+                };
+            }
+        }
+    }
+    entries
+}
+
+/// The page's properties, in the order of its frontmatter: its top-level
+/// `key: value` entries whose values are text, or lists of text — in brackets
+/// or one `- item` per line — given joined by `, `. Entries holding anything
+/// else, such as nested keys, are left out.
+pub fn properties(frontmatter: &str) -> Vec<(String, String)> {
+    let (lines, _) = inside(frontmatter);
+    entries(&lines)
+        .into_iter()
+        .filter_map(|entry| Some((entry.key, entry.value?)))
+        .collect()
+}
+
+/// `frontmatter` with its properties (see [`properties`]) replaced by
+/// `properties`: changed values are rewritten in place, properties not among
+/// them are removed and new ones added at the end. Everything else in it,
+/// including the lines of properties whose values stay the same, is kept as
+/// it is; a frontmatter left empty goes away.
+pub fn with_properties(frontmatter: &str, properties: &[(String, String)]) -> String {
+    let (lines, after) = inside(frontmatter);
+    let entries = entries(&lines);
+    let format = |key: &str, value: &str, is_list: bool| {
+        if is_list || LIST_KEYS.contains(&key.to_lowercase().as_str()) {
+            let items = value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| quote(item, true))
+                .collect::<Vec<_>>();
+            format!("{key}: [{}]", items.join(", "))
+        } else {
+            format!("{key}: {}", quote(value, false))
+        }
+    };
+
+    let mut out = Vec::new();
+    let mut index = 0;
+    for entry in &entries {
+        out.extend(
+            lines[index..entry.lines.start]
+                .iter()
+                .map(|l| l.to_string()),
+        );
+        index = entry.lines.end;
+        let new_value = properties
+            .iter()
+            .find(|(key, _)| *key == entry.key)
+            .map(|(_, value)| value);
+        match (&entry.value, new_value) {
+            (Some(old), Some(new)) if old != new => {
+                out.push(format(&entry.key, new, entry.is_list))
+            }
+            (Some(_), None) => {}
+            _ => out.extend(lines[entry.lines.clone()].iter().map(|l| l.to_string())),
+        }
+    }
+    out.extend(lines[index..].iter().map(|l| l.to_string()));
+    for (key, value) in properties {
+        if !key.trim().is_empty() && !entries.iter().any(|entry| entry.key == *key) {
+            out.push(format(key.trim(), value, false));
+        }
+    }
+
+    if out.iter().all(|line| line.trim().is_empty()) {
+        return String::new();
+    }
+    let after = if frontmatter.is_empty() { "\n" } else { after };
+    format!("---\n{}\n---\n{after}", out.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_off_the_frontmatter() {
+        let page = "---\ntitle: Plan\ntags: [work]\n---\n\n# Plan\n\nText\n";
+        assert_eq!(line_count(page), 4);
+        assert_eq!(
+            split(page),
+            (
+                "---\ntitle: Plan\ntags: [work]\n---\n\n",
+                "# Plan\n\nText\n"
+            )
+        );
+        assert_eq!(split("---\r\nlang: de\r\n---\r\nHallo\r\n").1, "Hallo\r\n");
+        assert_eq!(
+            split("---\ntitle: Only\n---"),
+            ("---\ntitle: Only\n---", "")
+        );
+    }
+
+    #[test]
+    fn reads_properties() {
+        let frontmatter = "---\ntitle: \"Plan: 2025\"\ntags: [work, '#q3']\naliases:\n  - roadmap\n  - plan\nlang: de\nauthor:\n  name: Me\n# a comment\n---\n\n";
+        assert_eq!(
+            properties(frontmatter),
+            vec![
+                ("title".to_string(), "Plan: 2025".to_string()),
+                ("tags".to_string(), "work, #q3".to_string()),
+                ("aliases".to_string(), "roadmap, plan".to_string()),
+                ("lang".to_string(), "de".to_string()),
+            ]
+        );
+        assert!(properties("").is_empty());
+    }
+
+    #[test]
+    fn changes_properties_keeping_the_rest() {
+        let frontmatter = "---\ntitle: Plan\nauthor:\n  name: Me\n# a comment\ntags:\n  - work\nlang: de\n---\n\n";
+        let changed = with_properties(
+            frontmatter,
+            &[
+                ("title".to_string(), "Plan: 2025".to_string()),
+                ("tags".to_string(), "work, #q3".to_string()),
+                ("status".to_string(), "draft".to_string()),
+            ],
+        );
+        assert_eq!(
+            changed,
+            "---\ntitle: \"Plan: 2025\"\nauthor:\n  name: Me\n# a comment\ntags: [work, \"#q3\"]\nstatus: draft\n---\n\n"
+        );
+        assert_eq!(
+            properties(&changed)[1],
+            ("tags".to_string(), "work, #q3".to_string())
+        );
+
+        assert_eq!(
+            with_properties("", &[("aliases".to_string(), "x".to_string())]),
+            "---\naliases: [x]\n---\n\n"
+        );
+        assert_eq!(with_properties("---\ntitle: Plan\n---\n", &[]), "");
+    }
+
+    #[test]
+    fn reads_comments_and_quoted_commas() {
+        let frontmatter = "---\nlang: de # German\naliases: [\"a, b\", c] # two\ntags:\n  - work # mine\ntitle: 'It''s #1'\nsummary: C# and more\n---\n";
+        assert_eq!(
+            properties(frontmatter),
+            vec![
+                ("lang".to_string(), "de".to_string()),
+                ("aliases".to_string(), "a, b, c".to_string()),
+                ("tags".to_string(), "work".to_string()),
+                ("title".to_string(), "It's #1".to_string()),
+                ("summary".to_string(), "C# and more".to_string()),
+            ]
+        );
+
+        // Only the changed property is rewritten.
+        let mut changed = properties(frontmatter);
+        changed[2].1 = "work, home".to_string();
+        assert_eq!(
+            with_properties(frontmatter, &changed),
+            "---\nlang: de # German\naliases: [\"a, b\", c] # two\ntags: [work, home]\ntitle: 'It''s #1'\nsummary: C# and more\n---\n"
+        );
+    }
+
+    #[test]
+    fn pages_without_frontmatter_are_all_text() {
+        for page in [
+            "# Plan\n",
+            "---\n",
+            "---\nnever closed\n",
+            "text\n---\nmore\n---\n",
+        ] {
+            assert_eq!(line_count(page), 0);
+            assert_eq!(split(page), ("", page));
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Fuzzy matching of note names, shared by the GUI's note picker and link
+//! editor autocomplete and the CLI's ranked note search.
+
+/// Simple fuzzy match: subsequence match with light scoring. `None` when
+/// `candidate` doesn't contain the letters of `query` in order.
+pub fn score(query: &str, candidate: &str) -> Option<i32> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let mut score = 0i32;
+    let mut qi = 0usize;
+    let q = query.to_lowercase();
+    let c = candidate.to_lowercase();
+    let qb = q.as_bytes();
+    let cb = c.as_bytes();
+    for (i, &ch) in cb.iter().enumerate() {
+        if qi < qb.len() && ch == qb[qi] {
+            // Reward matches earlier and consecutive
+            score += 10 - ((i as i32).min(9));
+            // Bonus for start of word or after '/'
+            if i == 0 || cb.get(i - 1) == Some(&b'/') {
+                score += 5;
+            }
+            qi += 1;
+            if qi == qb.len() {
+                break;
+            }
+        }
+    }
+    if qi == qb.len() {
+        // Prefer prefix and exact
+        if c.starts_with(&q) {
+            score += 20;
+        }
+        if c == q {
+            score += 50;
+        }
+        Some(score)
+    } else {
+        None
+    }
+}
+
+/// The `candidates` matching `query`, best match first (ties alphabetically).
+pub fn rank<'a>(query: &str, candidates: &'a [String]) -> Vec<&'a str> {
+    let mut matches: Vec<(i32, &str)> = candidates
+        .iter()
+        .filter_map(|c| score(query, c).map(|s| (s, c.as_str())))
+        .collect();
+    matches.sort_by(|(sa, a), (sb, b)| {
+        sb.cmp(sa)
+            .then_with(|| a.to_lowercase().cmp(&b.to_lowercase()))
+    });
+    matches.into_iter().map(|(_, c)| c).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_prefixes_and_exact_matches_first() {
+        let names: Vec<String> = ["projects/roadmap", "road", "roadmap", "notes"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            rank("road", &names),
+            vec!["road", "roadmap", "projects/roadmap"]
+        );
+        assert_eq!(rank("rdmp", &names), vec!["roadmap", "projects/roadmap"]);
+        assert!(rank("xyz", &names).is_empty());
+        assert_eq!(score("", "anything"), Some(0));
+    }
+}
@@ -0,0 +1,445 @@
+//! Access to the git history of a notes directory.
+//!
+//! piki only commits when asked to: users who keep their notes in a git
+//! repository (and commit via `piki run git …` or a cron job) can browse that
+//! history, and [`sync`] exchanges it with a remote, committing changed notes
+//! first if given a message. Everything shells out to the `git` command line.
+
+use std::env;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Separates the fields of one commit line in our `--pretty` format.
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+/// One commit touching a note.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    pub hash: String,
+    /// Commit date as `YYYY-MM-DD`.
+    pub date: String,
+    pub subject: String,
+    /// The note's path at that commit, relative to the repository root (it
+    /// differs from the current one if the note was renamed since). Empty for
+    /// the commits of [`commits`], which aren't about one note.
+    pub path: String,
+}
+
+/// Run `git` with `args` inside `dir` and return its standard output.
+///
+/// Neither git nor ssh may ask for a password, which nobody would see when
+/// syncing in the background: git is told not to prompt, and ssh to run in
+/// batch mode unless `GIT_SSH_COMMAND` or `GIT_SSH` say how to run it.
+pub fn run(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let mut command = Command::new("git");
+    command.env("GIT_TERMINAL_PROMPT", "0");
+    if env::var_os("GIT_SSH_COMMAND").is_none() && env::var_os("GIT_SSH").is_none() {
+        command.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+    }
+    let output = command
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run git {}: {}", args[0], e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The last `count` commits of the repository, one `* DATE SUBJECT` line each.
+pub fn log(dir: &Path, count: usize) -> Result<String, String> {
+    run(
+        dir,
+        &[
+            "log",
+            &format!("-n{}", count),
+            "--pretty=format:* %ad %s",
+            "--date=short",
+        ],
+    )
+}
+
+/// The last `count` commits of the repository, newest first.
+pub fn commits(dir: &Path, count: usize) -> Result<Vec<Commit>, String> {
+    let output = run(
+        dir,
+        &[
+            "log",
+            &format!("-n{}", count),
+            "--date=short",
+            "--pretty=format:%H\u{1f}%ad\u{1f}%s",
+        ],
+    )?;
+    Ok(parse_file_history(&output))
+}
+
+/// All commits touching the file `file` (relative to `dir`), newest first.
+/// Renames are followed.
+pub fn file_history(dir: &Path, file: &str) -> Result<Vec<Commit>, String> {
+    let output = run(
+        dir,
+        &[
+            "log",
+            "--follow",
+            "--name-only",
+            "--date=short",
+            "--pretty=format:%H\u{1f}%ad\u{1f}%s",
+            "--",
+            file,
+        ],
+    )?;
+    Ok(parse_file_history(&output))
+}
+
+/// The content of `commit`'s version of the note.
+pub fn file_at_commit(dir: &Path, commit: &Commit) -> Result<String, String> {
+    run(dir, &["show", &format!("{}:{}", commit.hash, commit.path)])
+}
+
+/// The content of the file `file` (relative to `dir`) at `revision`, which
+/// may be anything git understands as a commit (`HEAD`, `HEAD~2`, a hash).
+pub fn file_at_revision(dir: &Path, revision: &str, file: &str) -> Result<String, String> {
+    run(dir, &["show", &format!("{revision}:./{file}")])
+}
+
+/// The full hash of the commit `revision` names (`HEAD~2`, a short hash, …).
+pub fn commit_hash(dir: &Path, revision: &str) -> Result<String, String> {
+    run(
+        dir,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{revision}^{{commit}}"),
+        ],
+    )
+    .map(|hash| hash.trim().to_string())
+    .map_err(|_| format!("No commit '{revision}'"))
+}
+
+/// The last commit touching the file `file` (relative to `dir`), with its
+/// commit time in seconds since the Unix epoch.
+pub fn last_commit(dir: &Path, file: &str) -> Result<Option<(u64, Commit)>, String> {
+    let output = run(
+        dir,
+        &[
+            "log",
+            "-1",
+            "--date=short",
+            "--pretty=format:%ct\u{1f}%H\u{1f}%ad\u{1f}%s",
+            "--",
+            file,
+        ],
+    )?;
+    let mut fields = output.trim_end().splitn(4, FIELD_SEPARATOR);
+    Ok(
+        match (fields.next(), fields.next(), fields.next(), fields.next()) {
+            (Some(time), Some(hash), Some(date), Some(subject)) => time.parse().ok().map(|time| {
+                (
+                    time,
+                    Commit {
+                        hash: hash.to_string(),
+                        date: date.to_string(),
+                        subject: subject.to_string(),
+                        path: file.to_string(),
+                    },
+                )
+            }),
+            _ => None,
+        },
+    )
+}
+
+/// Whether the file `file` (relative to `dir`) differs from its committed
+/// version, or isn't committed at all.
+pub fn is_modified(dir: &Path, file: &str) -> Result<bool, String> {
+    run(dir, &["status", "--porcelain", "--", file]).map(|out| !out.trim().is_empty())
+}
+
+/// Whether `dir` is inside a git work tree.
+pub fn is_repository(dir: &Path) -> bool {
+    run(dir, &["rev-parse", "--is-inside-work-tree"]).is_ok()
+}
+
+/// Restore the file `file` (relative to `dir`) to its last committed version,
+/// discarding all uncommitted changes to it.
+pub fn checkout_file(dir: &Path, file: &str) -> Result<(), String> {
+    run(dir, &["checkout", "HEAD", "--", file]).map(|_| ())
+}
+
+/// What [`sync`] did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Whether changed notes were committed before syncing.
+    pub committed: bool,
+    /// Commits pulled from the remote.
+    pub pulled: usize,
+    /// Commits pushed to the remote.
+    pub pushed: usize,
+}
+
+impl SyncReport {
+    /// A one-line summary, e.g. `Synced: 2 commits pulled, 1 pushed`.
+    pub fn summary(&self) -> String {
+        if self.pulled == 0 && self.pushed == 0 {
+            return "Already in sync".to_string();
+        }
+        let commits = |n: usize| if n == 1 { "commit" } else { "commits" };
+        match (self.pulled, self.pushed) {
+            (pulled, 0) => format!("Synced: {pulled} {} pulled", commits(pulled)),
+            (0, pushed) => format!("Synced: {pushed} {} pushed", commits(pushed)),
+            (pulled, pushed) => {
+                format!(
+                    "Synced: {pulled} {} pulled, {pushed} pushed",
+                    commits(pulled)
+                )
+            }
+        }
+    }
+}
+
+/// Exchange commits between the repository of `dir` and its upstream branch:
+/// pull the remote's commits, replaying local ones on top (`pull --rebase`),
+/// and push the local ones. With `commit_message`, the changes in `dir` are
+/// committed first; other uncommitted changes are kept as they are.
+///
+/// If local and remote commits change the same lines, the pull is undone,
+/// leaving everything as it was, and the error names the conflicting files.
+pub fn sync(dir: &Path, commit_message: Option<&str>) -> Result<SyncReport, String> {
+    if !is_repository(dir) {
+        return Err(format!("{} is not a git repository", dir.display()));
+    }
+    let upstream = run(
+        dir,
+        &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"],
+    )
+    .map_err(|_| {
+        "The notes repository has no upstream branch to sync with (set one with `git push -u REMOTE BRANCH`)".to_string()
+    })?;
+    let upstream = upstream.trim();
+
+    let mut report = SyncReport::default();
+    if let Some(message) = commit_message
+        && !run(dir, &["status", "--porcelain", "--", "."])?
+            .trim()
+            .is_empty()
+    {
+        run(dir, &["add", "-A", "--", "."])?;
+        run(dir, &["commit", "-q", "-m", message])?;
+        report.committed = true;
+    }
+
+    let before = run(dir, &["rev-parse", "@{u}"])?;
+    if let Err(err) = run(dir, &["pull", "-q", "--rebase", "--autostash"]) {
+        let conflicts = run(dir, &["diff", "--name-only", "--diff-filter=U"]).unwrap_or_default();
+        if conflicts.trim().is_empty() {
+            return Err(err);
+        }
+        let _ = run(dir, &["rebase", "--abort"]);
+        let files: Vec<&str> = conflicts.lines().collect();
+        return Err(format!(
+            "Sync stopped, nothing was changed: {} changed both here and in {}. Merge the changes with `git pull --rebase` in the notes directory.",
+            files.join(", "),
+            upstream
+        ));
+    }
+    report.pulled = count_commits(dir, &format!("{}..@{{u}}", before.trim()))?;
+    report.pushed = count_commits(dir, "@{u}..HEAD")?;
+    if report.pushed > 0 {
+        run(dir, &["push", "-q"])?;
+    }
+    Ok(report)
+}
+
+/// The number of commits in the range `range` (`A..B`).
+fn count_commits(dir: &Path, range: &str) -> Result<usize, String> {
+    run(dir, &["rev-list", "--count", range])?
+        .trim()
+        .parse()
+        .map_err(|e| format!("Failed to count commits: {e}"))
+}
+
+/// Parse `git log --name-only` output in the format used by [`file_history`]:
+/// a header line per commit, followed by the file name it touched.
+fn parse_file_history(output: &str) -> Vec<Commit> {
+    let mut commits: Vec<Commit> = Vec::new();
+
+    for line in output.lines() {
+        let mut fields = line.splitn(3, FIELD_SEPARATOR);
+        match (fields.next(), fields.next(), fields.next()) {
+            (Some(hash), Some(date), Some(subject)) => commits.push(Commit {
+                hash: hash.to_string(),
+                date: date.to_string(),
+                subject: subject.to_string(),
+                path: String::new(),
+            }),
+            _ if !line.is_empty() => {
+                if let Some(commit) = commits.last_mut()
+                    && commit.path.is_empty()
+                {
+                    commit.path = line.to_string();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    commits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_file_history() {
+        let output = "abc123\u{1f}2025-03-02\u{1f}Rename notes\nnotes/new.md\n\ndef456\u{1f}2025-03-01\u{1f}Add: a \u{1f} b\nold.md\n";
+        let commits = parse_file_history(output);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].hash, "abc123");
+        assert_eq!(commits[0].date, "2025-03-02");
+        assert_eq!(commits[0].path, "notes/new.md");
+        assert_eq!(commits[1].subject, "Add: a \u{1f} b");
+        assert_eq!(commits[1].path, "old.md");
+    }
+
+    #[test]
+    fn test_file_history_and_old_version() {
+        let temp_dir = std::env::temp_dir().join("piki_test_git_history");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let git = |args: &[&str]| {
+            let mut full = vec!["-c", "user.name=piki", "-c", "user.email=piki@example.com"];
+            full.extend_from_slice(args);
+            run(&temp_dir, &full)
+        };
+        if git(&["init", "-q"]).is_err() {
+            // git is not installed; nothing to test.
+            fs::remove_dir_all(&temp_dir).unwrap();
+            return;
+        }
+
+        fs::write(temp_dir.join("note.md"), "first").unwrap();
+        git(&["add", "note.md"]).unwrap();
+        git(&["commit", "-q", "-m", "First"]).unwrap();
+        fs::write(temp_dir.join("note.md"), "second").unwrap();
+        git(&["commit", "-q", "-am", "Second"]).unwrap();
+
+        let history = file_history(&temp_dir, "note.md").unwrap();
+        let subjects: Vec<&str> = history.iter().map(|c| c.subject.as_str()).collect();
+        assert_eq!(subjects, vec!["Second", "First"]);
+        let recent = commits(&temp_dir, 1).unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(
+            (recent[0].hash.as_str(), recent[0].path.as_str()),
+            (history[0].hash.as_str(), "")
+        );
+        assert_eq!(file_at_commit(&temp_dir, &history[1]).unwrap(), "first");
+        assert_eq!(
+            file_at_revision(&temp_dir, "HEAD~1", "note.md").unwrap(),
+            "first"
+        );
+        assert_eq!(commit_hash(&temp_dir, "HEAD~1").unwrap(), history[1].hash);
+        assert_eq!(
+            commit_hash(&temp_dir, &history[0].hash[..7]).unwrap(),
+            history[0].hash
+        );
+        assert!(commit_hash(&temp_dir, "HEAD~5").is_err());
+
+        let (_, last) = last_commit(&temp_dir, "note.md").unwrap().unwrap();
+        assert_eq!(last.hash, history[0].hash);
+        assert!(last_commit(&temp_dir, "missing.md").unwrap().is_none());
+        assert!(is_repository(&temp_dir));
+        assert!(!is_modified(&temp_dir, "note.md").unwrap());
+
+        fs::write(temp_dir.join("note.md"), "uncommitted").unwrap();
+        assert!(is_modified(&temp_dir, "note.md").unwrap());
+        checkout_file(&temp_dir, "note.md").unwrap();
+        assert_eq!(
+            fs::read_to_string(temp_dir.join("note.md")).unwrap(),
+            "second"
+        );
+        assert!(checkout_file(&temp_dir, "missing.md").is_err());
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_sync() {
+        let temp_dir = std::env::temp_dir().join("piki_test_git_sync");
+        let _ = fs::remove_dir_all(&temp_dir);
+        let (remote, here, there) = (
+            temp_dir.join("remote"),
+            temp_dir.join("here"),
+            temp_dir.join("there"),
+        );
+        fs::create_dir_all(&remote).unwrap();
+        let git = |dir: &Path, args: &[&str]| {
+            let mut full = vec!["-c", "user.name=piki", "-c", "user.email=piki@example.com"];
+            full.extend_from_slice(args);
+            run(dir, &full)
+        };
+        if git(&remote, &["init", "-q", "--bare"]).is_err() {
+            // git is not installed; nothing to test.
+            fs::remove_dir_all(&temp_dir).unwrap();
+            return;
+        }
+        let clone = |to: &Path| {
+            git(&temp_dir, &["clone", "-q", "remote", to.to_str().unwrap()]).unwrap();
+            git(to, &["config", "user.name", "piki"]).unwrap();
+            git(to, &["config", "user.email", "piki@example.com"]).unwrap();
+        };
+        clone(&here);
+        fs::write(here.join("note.md"), "one\ntwo\n").unwrap();
+        git(&here, &["add", "note.md"]).unwrap();
+        git(&here, &["commit", "-q", "-m", "First"]).unwrap();
+        git(&here, &["push", "-q", "-u", "origin", "HEAD"]).unwrap();
+        clone(&there);
+
+        // Without a message, changes stay uncommitted and nothing is pushed.
+        fs::write(there.join("note.md"), "one\ntwo\nthree\n").unwrap();
+        assert_eq!(sync(&there, None).unwrap(), SyncReport::default());
+        let report = sync(&there, Some("Sync")).unwrap();
+        assert_eq!(
+            (report.committed, report.pulled, report.pushed),
+            (true, 0, 1)
+        );
+        assert_eq!(report.summary(), "Synced: 1 commit pushed");
+
+        fs::write(here.join("other.md"), "other\n").unwrap();
+        let report = sync(&here, Some("Sync")).unwrap();
+        assert_eq!((report.pulled, report.pushed), (1, 1));
+        assert_eq!(
+            fs::read_to_string(here.join("note.md")).unwrap(),
+            "one\ntwo\nthree\n"
+        );
+
+        // Conflicting changes leave both sides as they were.
+        fs::write(here.join("note.md"), "one\n2\nthree\n").unwrap();
+        sync(&here, Some("Here")).unwrap();
+        fs::write(there.join("note.md"), "one\nzwei\nthree\n").unwrap();
+        let err = sync(&there, Some("There")).unwrap_err();
+        assert!(
+            err.contains("note.md changed both here and in origin/"),
+            "{err}"
+        );
+        assert_eq!(
+            fs::read_to_string(there.join("note.md")).unwrap(),
+            "one\nzwei\nthree\n"
+        );
+        assert!(git(&there, &["status", "--porcelain"]).unwrap().is_empty());
+
+        assert!(sync(&remote.join(".."), None).is_err());
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+}
@@ -0,0 +1,532 @@
+//! The graph of links between pages, for `piki graph` and the GUI's link
+//! graph view, and the backlinks of a page, which `piki backlinks` prints and
+//! `piki export --backlinks` lists at the end of the exported page.
+//!
+//! Links are Markdown links (`[text](other-page)`) and wiki links
+//! (`[[other-page]]`, `[[other-page|label]]`). Like attachments, a target is
+//! looked up relative to the linking page's folder first, then to the notes
+//! directory; links to external URLs, files and pages that don't exist are
+//! left out, as are links inside code.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::DocumentStore;
+use crate::has_md_extension;
+use crate::images::{is_file_name, join_relative, link_destination};
+use crate::json::quote;
+use crate::link_index;
+use crate::link_target;
+use crate::tags::is_code_fence;
+use crate::title::plain_text;
+
+/// Pages and the links between them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkGraph {
+    /// Page names, sorted.
+    pub pages: Vec<String>,
+    /// Links as indices into `pages`, from the linking page to its target.
+    /// Each pair appears once; links of a page to itself are left out.
+    pub links: Vec<(usize, usize)>,
+}
+
+/// The note targets linked from `content`, as written (without `#section`),
+/// in order. External URLs, files and code are skipped.
+pub fn note_links(content: &str) -> Vec<String> {
+    links_by_line(content)
+        .into_iter()
+        .map(|(_, target)| target)
+        .collect()
+}
+
+/// [`note_links`] with the (0-based) index of the line each link is on.
+fn links_by_line(content: &str) -> Vec<(usize, String)> {
+    link_destinations(content)
+        .into_iter()
+        .filter_map(|(index, destination, _)| Some((index, link_destination(destination)?)))
+        .filter(|(_, target)| !target.starts_with("mailto:") && !is_file_name(target))
+        .collect()
+}
+
+/// Every link in `content` that isn't to an external URL, with its 1-based
+/// line, as the viewer follows it: to pages and files, with `#section` if it
+/// has one, or just `#section` for a section of the page itself. Unlike
+/// [`note_links`], a wiki link's target is taken as it is, spaces included.
+pub fn internal_links(content: &str) -> Vec<(usize, String)> {
+    link_destinations(content)
+        .into_iter()
+        .filter_map(|(index, destination, is_wiki_link)| {
+            let destination = destination.trim();
+            let (path, section) = match destination.split_once('#') {
+                Some((path, section)) => (path, Some(section)),
+                None => (destination, None),
+            };
+            let path = if is_wiki_link {
+                path.trim().to_string()
+            } else if path.is_empty() {
+                String::new()
+            } else {
+                link_destination(destination)?
+            };
+            let section = section
+                .map(|section| section.split_whitespace().next().unwrap_or(""))
+                .filter(|section| !section.is_empty());
+            if path.is_empty() && section.is_none()
+                || path.contains("://")
+                || path.starts_with("mailto:")
+            {
+                return None;
+            }
+            Some(match section {
+                Some(section) => (index + 1, format!("{path}#{section}")),
+                None => (index + 1, path),
+            })
+        })
+        .collect()
+}
+
+/// The destinations of the wiki links and Markdown links (not images) in
+/// `content` as written, with the (0-based) index of their line and whether
+/// they are wiki links. Links inside code are skipped.
+fn link_destinations(content: &str) -> Vec<(usize, &str, bool)> {
+    let mut links = Vec::new();
+    let mut in_fence = false;
+
+    for (index, line) in content.lines().enumerate() {
+        if is_code_fence(line) {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut in_code = false;
+        let mut rest = line;
+        while let Some(c) = rest.chars().next() {
+            if c == '`' {
+                in_code = !in_code;
+            } else if !in_code
+                && let Some(after) = rest.strip_prefix("[[")
+                && let Some(end) = after.find("]]")
+            {
+                let target = after[..end].split('|').next().unwrap_or("");
+                links.push((index, target, true));
+                rest = &after[end + 2..];
+                continue;
+            } else if !in_code
+                && let Some(after) = rest.strip_prefix("](")
+                && let Some(end) = after.find(')')
+            {
+                let is_image = line[..line.len() - rest.len()]
+                    .rfind('[')
+                    .is_some_and(|open| line[..open].ends_with('!'));
+                if !is_image {
+                    links.push((index, &after[..end], false));
+                }
+                rest = &after[end + 1..];
+                continue;
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    links
+}
+
+/// The page a link to `target` from `page` leads to, if it is one of `pages`.
+pub(crate) fn resolve<'a>(
+    page: &str,
+    target: &str,
+    pages: &'a BTreeSet<String>,
+) -> Option<&'a String> {
+    let target = if has_md_extension(target) {
+        &target[..target.len() - 3]
+    } else {
+        target
+    };
+    let dir = page.rsplit_once('/').map_or("", |(dir, _)| dir);
+    [join_relative(dir, target), join_relative("", target)]
+        .into_iter()
+        .flatten()
+        .find_map(|name| pages.get(&name))
+}
+
+/// The page a link to `target`, as listed by [`internal_links`], names: its
+/// path without `#section`, unless it is a file's.
+fn page_target(target: &str) -> Option<&str> {
+    let path = target.split('#').next().unwrap_or(target);
+    (!path.is_empty() && !is_file_name(path)).then_some(path)
+}
+
+/// The link graph of all pages in `store`.
+pub fn build(store: &DocumentStore) -> Result<LinkGraph, String> {
+    let notes = link_index::scan(store)?;
+    let pages: BTreeSet<String> = notes.iter().map(|(page, _)| page.clone()).collect();
+    let index: HashMap<&String, usize> = pages.iter().enumerate().map(|(i, p)| (p, i)).collect();
+
+    let mut links = BTreeSet::new();
+    for (page, targets) in &notes {
+        let from = index[page];
+        for (_, target) in targets {
+            if let Some(to) = page_target(target)
+                .and_then(|target| resolve(page, target, &pages))
+                .map(|p| index[p])
+                && to != from
+            {
+                links.insert((from, to));
+            }
+        }
+    }
+
+    Ok(LinkGraph {
+        pages: pages.into_iter().collect(),
+        links: links.into_iter().collect(),
+    })
+}
+
+/// A line of another page linking to a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backlink {
+    /// The linking page.
+    pub page: String,
+    /// 1-based line of the link.
+    pub line: usize,
+    /// The line's text without markup, shortened to [`SNIPPET_CHARS`].
+    pub snippet: String,
+}
+
+/// Longest snippet of a [`Backlink`], in characters.
+pub const SNIPPET_CHARS: usize = 100;
+
+/// The lines of other pages in `store` linking to `page`, by page name and
+/// line. A line linking to the page twice is listed once. Which pages link to
+/// it comes from the [`link_index`]; only those are read, for their lines.
+pub fn backlinks(store: &DocumentStore, page: &str) -> Result<Vec<Backlink>, String> {
+    let notes = link_index::scan(store)?;
+    let pages: BTreeSet<String> = notes.iter().map(|(page, _)| page.clone()).collect();
+    let mut backlinks = Vec::new();
+    for (other, targets) in notes.iter().filter(|(other, _)| other != page) {
+        let mut linking_lines: Vec<usize> = targets
+            .iter()
+            .filter(|(_, target)| {
+                page_target(target)
+                    .and_then(|target| resolve(other, target, &pages))
+                    .is_some_and(|to| to == page)
+            })
+            .map(|(line, _)| *line)
+            .collect();
+        linking_lines.dedup();
+        if linking_lines.is_empty() {
+            continue;
+        }
+        let Ok(doc) = store.load(other) else {
+            continue;
+        };
+        let lines: Vec<&str> = doc.content.lines().collect();
+        for line in linking_lines {
+            backlinks.push(Backlink {
+                page: other.clone(),
+                line,
+                snippet: snippet(lines.get(line - 1).copied().unwrap_or("")),
+            });
+        }
+    }
+    Ok(backlinks)
+}
+
+/// `line` as plain text, without list, quote or heading markers, shortened to
+/// [`SNIPPET_CHARS`].
+fn snippet(line: &str) -> String {
+    let text = line.trim_start_matches(['-', '*', '+', '>', '#', ' ', '\t']);
+    let text = ["[ ] ", "[x] ", "[X] "]
+        .iter()
+        .find_map(|task| text.strip_prefix(task))
+        .unwrap_or(text);
+    let text = plain_text(&wiki_link_labels(text));
+    match text.char_indices().nth(SNIPPET_CHARS) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text,
+    }
+}
+
+/// `text` with each wiki link replaced by its label, or its target if it has
+/// none.
+fn wiki_link_labels(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("[[") {
+        let Some(len) = rest[start + 2..].find("]]") else {
+            break;
+        };
+        let link = &rest[start + 2..start + 2 + len];
+        out.push_str(&rest[..start]);
+        out.push_str(link.rsplit('|').next().unwrap_or(link));
+        rest = &rest[start + 4 + len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// A "Linked from" section listing `backlinks`, for appending to an exported
+/// page; empty if there are none.
+pub fn linked_from(backlinks: &[Backlink]) -> String {
+    if backlinks.is_empty() {
+        return String::new();
+    }
+    let mut section = String::from("## Linked from\n\n");
+    for backlink in backlinks {
+        section.push_str(&format!(
+            "- [{}]({}) — {}\n",
+            backlink.page,
+            link_target(&backlink.page),
+            backlink.snippet
+        ));
+    }
+    section
+}
+
+/// `backlinks` as a JSON array of `{"page": …, "line": …, "text": …}`, with
+/// the snippet as `text`.
+pub fn backlinks_to_json(backlinks: &[Backlink]) -> String {
+    let entries: Vec<String> = backlinks
+        .iter()
+        .map(|backlink| {
+            format!(
+                "{{\"page\":{},\"line\":{},\"text\":{}}}",
+                quote(&backlink.page),
+                backlink.line,
+                quote(&backlink.snippet)
+            )
+        })
+        .collect();
+    format!("[{}]\n", entries.join(","))
+}
+
+impl LinkGraph {
+    /// The graph in Graphviz's DOT language.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph piki {\n");
+        for page in &self.pages {
+            dot.push_str(&format!("  {};\n", quote(page)));
+        }
+        for &(from, to) in &self.links {
+            dot.push_str(&format!(
+                "  {} -> {};\n",
+                quote(&self.pages[from]),
+                quote(&self.pages[to])
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// The graph as JSON: `{"nodes": [{"id": …}], "links": [{"source": …,
+    /// "target": …}]}`, with pages named by their ids.
+    pub fn to_json(&self) -> String {
+        let nodes: Vec<String> = self
+            .pages
+            .iter()
+            .map(|page| format!("{{\"id\":{}}}", quote(page)))
+            .collect();
+        let links: Vec<String> = self
+            .links
+            .iter()
+            .map(|&(from, to)| {
+                format!(
+                    "{{\"source\":{},\"target\":{}}}",
+                    quote(&self.pages[from]),
+                    quote(&self.pages[to])
+                )
+            })
+            .collect();
+        format!(
+            "{{\"nodes\":[{}],\"links\":[{}]}}\n",
+            nodes.join(","),
+            links.join(",")
+        )
+    }
+
+    /// Positions for drawing the pages, in the unit square: a force-directed
+    /// layout where linked pages pull together and all pages push apart. The
+    /// same graph always gets the same layout.
+    pub fn layout(&self, iterations: usize) -> Vec<(f64, f64)> {
+        let n = self.pages.len();
+        if n == 0 {
+            return Vec::new();
+        }
+        // Start on a circle, so the layout needs no randomness.
+        let mut pos: Vec<(f64, f64)> = (0..n)
+            .map(|i| {
+                let angle = i as f64 / n as f64 * std::f64::consts::TAU;
+                (0.5 + 0.4 * angle.cos(), 0.5 + 0.4 * angle.sin())
+            })
+            .collect();
+        let k = (1.0 / n as f64).sqrt();
+        let mut temperature = 0.1;
+        let cooling = temperature / (iterations.max(1) as f64 + 1.0);
+
+        for _ in 0..iterations {
+            let mut shift = vec![(0.0, 0.0); n];
+            for i in 0..n {
+                for j in i + 1..n {
+                    let (dx, dy) = (pos[i].0 - pos[j].0, pos[i].1 - pos[j].1);
+                    let dist = (dx * dx + dy * dy).sqrt().max(1e-4);
+                    let force = k * k / dist;
+                    let (fx, fy) = (dx / dist * force, dy / dist * force);
+                    shift[i].0 += fx;
+                    shift[i].1 += fy;
+                    shift[j].0 -= fx;
+                    shift[j].1 -= fy;
+                }
+            }
+            for &(a, b) in &self.links {
+                let (dx, dy) = (pos[a].0 - pos[b].0, pos[a].1 - pos[b].1);
+                let dist = (dx * dx + dy * dy).sqrt().max(1e-4);
+                let force = dist * dist / k;
+                let (fx, fy) = (dx / dist * force, dy / dist * force);
+                shift[a].0 -= fx;
+                shift[a].1 -= fy;
+                shift[b].0 += fx;
+                shift[b].1 += fy;
+            }
+            for (p, (sx, sy)) in pos.iter_mut().zip(shift) {
+                let len = (sx * sx + sy * sy).sqrt().max(1e-9);
+                let step = len.min(temperature);
+                p.0 = (p.0 + sx / len * step).clamp(0.0, 1.0);
+                p.1 = (p.1 + sy / len * step).clamp(0.0, 1.0);
+            }
+            temperature -= cooling;
+        }
+
+        // Stretch the result to fill the square.
+        let (min_x, max_x) = bounds(pos.iter().map(|p| p.0));
+        let (min_y, max_y) = bounds(pos.iter().map(|p| p.1));
+        pos.iter()
+            .map(|&(x, y)| (stretch(x, min_x, max_x), stretch(y, min_y, max_y)))
+            .collect()
+    }
+}
+
+fn bounds(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.fold((f64::MAX, f64::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)))
+}
+
+fn stretch(v: f64, min: f64, max: f64) -> f64 {
+    if max - min < 1e-9 {
+        0.5
+    } else {
+        (v - min) / (max - min)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_note_links() {
+        let content = "See [plan](projects/plan.md#goals), [[inbox]], [[ideas|my ideas]],\n\
+                       ![photo](photo.jpg), [doc](spec.pdf), [web](https://x.org), `[[code]]`.\n\
+                       ```\n[x](fenced)\n```\n";
+        assert_eq!(
+            note_links(content),
+            vec!["projects/plan.md", "inbox", "ideas"]
+        );
+    }
+
+    #[test]
+    fn lists_internal_links_with_sections() {
+        let content = "# Plan\n\nSee [goals](projects/plan.md#goals), [[My Page]], [[ideas#later|later]],\n\
+                       [up](#plan), [doc](spec.pdf \"Spec\"), [web](https://x.org#a), [mail](mailto:a@b.c),\n\
+                       ![photo](photo.jpg), `[[code]]`, [empty](#).\n";
+        assert_eq!(
+            internal_links(content),
+            vec![
+                (3, "projects/plan.md#goals".to_string()),
+                (3, "My Page".to_string()),
+                (3, "ideas#later".to_string()),
+                (4, "#plan".to_string()),
+                (4, "spec.pdf".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_backlinks() {
+        let dir = std::env::temp_dir().join("piki-test-backlinks");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("projects")).unwrap();
+        std::fs::write(
+            dir.join("home.md"),
+            "# Home\n\n- [ ] Ask about [the plan](projects/plan) and [[projects/plan|it]]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("projects/notes.md"),
+            format!("> See [[plan]]. {}\n", "x".repeat(120)),
+        )
+        .unwrap();
+        std::fs::write(dir.join("projects/plan.md"), "[[home]]\n").unwrap();
+        let store = DocumentStore::new(dir.clone());
+
+        let backlinks = backlinks(&store, "projects/plan").unwrap();
+        assert_eq!(backlinks.len(), 2);
+        assert_eq!(
+            backlinks[0],
+            Backlink {
+                page: "home".into(),
+                line: 3,
+                snippet: "Ask about the plan and it".into(),
+            }
+        );
+        assert_eq!(backlinks[1].page, "projects/notes");
+        assert_eq!(backlinks[1].snippet.chars().count(), SNIPPET_CHARS + 1);
+        assert!(linked_from(&backlinks).starts_with(
+            "## Linked from\n\n- [home](home) — Ask about the plan and it\n- [projects/notes](projects/notes) — See plan. xxx"
+        ));
+        assert_eq!(linked_from(&[]), "");
+        assert!(backlinks_to_json(&backlinks).starts_with(
+            "[{\"page\":\"home\",\"line\":3,\"text\":\"Ask about the plan and it\"},{\"page\":\"projects/notes\",\"line\":1,"
+        ));
+        assert_eq!(backlinks_to_json(&[]), "[]\n");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn builds_the_graph() {
+        let dir = std::env::temp_dir().join("piki-test-graph");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("projects")).unwrap();
+        std::fs::write(
+            dir.join("home.md"),
+            "[plan](projects/plan) [[missing]] [[home]]\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("projects/plan.md"), "[[notes]] [back](/home.md)\n").unwrap();
+        std::fs::write(dir.join("projects/notes.md"), "[[plan]] [[plan]]\n").unwrap();
+        let graph = build(&DocumentStore::new(dir.clone())).unwrap();
+
+        assert_eq!(graph.pages, vec!["home", "projects/notes", "projects/plan"]);
+        assert_eq!(graph.links, vec![(0, 2), (1, 2), (2, 0), (2, 1)]);
+        assert!(
+            graph
+                .to_dot()
+                .contains("  \"home\" -> \"projects/plan\";\n")
+        );
+        assert!(graph.to_json().starts_with(
+            "{\"nodes\":[{\"id\":\"home\"},{\"id\":\"projects/notes\"},{\"id\":\"projects/plan\"}],\"links\":[{\"source\":\"home\",\"target\":\"projects/plan\"},"
+        ));
+
+        let layout = graph.layout(50);
+        assert_eq!(layout.len(), 3);
+        assert!(
+            layout
+                .iter()
+                .all(|&(x, y)| (0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y))
+        );
+        assert_eq!(layout, graph.layout(50));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
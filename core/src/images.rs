@@ -0,0 +1,368 @@
+//! Keeping track of images and other attachments (`piki images`).
+//!
+//! [`scan`] matches the attachments in the notes directory against the links
+//! in the notes, to find files nothing links to and links to files that don't
+//! exist. [`compress`] shrinks oversized images in place. Like the rest of the
+//! attachment handling it never decodes images itself: it shells out to
+//! ImageMagick (`magick`, or `convert` for version 6) and keeps a copy of the
+//! original in `.piki/image-backups/`, which is never committed.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::document::unique_name;
+use crate::tags::is_code_fence;
+use crate::{DocumentStore, edit_log, has_md_extension, percent_decode};
+
+const BACKUP_DIR: &str = "image-backups";
+/// Images at least this large are worth compressing.
+pub const OVERSIZED_BYTES: u64 = 512 * 1024;
+/// Compressed images are scaled down to at most this width.
+pub const DEFAULT_MAX_WIDTH: u32 = 1600;
+
+/// An attachment file and the notes linking to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    /// Path relative to the notes directory, e.g. `attachments/photo.jpg`.
+    pub path: String,
+    pub bytes: u64,
+    /// Names of the notes linking to the file, sorted.
+    pub referenced_by: Vec<String>,
+}
+
+/// A link to an attachment that doesn't exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingFile {
+    pub page: String,
+    /// 1-based line of the link.
+    pub line: usize,
+    pub target: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// All attachments, sorted by path.
+    pub attachments: Vec<Attachment>,
+    pub missing: Vec<MissingFile>,
+}
+
+/// The link targets in `content` that name files rather than notes, with
+/// their 1-based line numbers: every image, and every link whose target has a
+/// file extension other than `.md`. External URLs and code are skipped.
+pub fn file_links(content: &str) -> Vec<(usize, String)> {
+    let mut links = Vec::new();
+    let mut in_fence = false;
+
+    for (index, line) in content.lines().enumerate() {
+        if is_code_fence(line) {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut in_code = false;
+        let mut rest = line;
+        while let Some(c) = rest.chars().next() {
+            if c == '`' {
+                in_code = !in_code;
+            } else if !in_code
+                && let Some(after) = rest.strip_prefix("](")
+                && let Some(end) = after.find(')')
+            {
+                let is_image = line[..line.len() - rest.len()]
+                    .rfind('[')
+                    .is_some_and(|open| line[..open].ends_with('!'));
+                if let Some(target) = link_destination(&after[..end])
+                    && (is_image || is_file_name(&target))
+                {
+                    links.push((index + 1, target));
+                }
+                rest = &after[end + 1..];
+                continue;
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    links
+}
+
+/// The file named by a Markdown link destination (`<a b.png>`,
+/// `a%20b.png "Title"`, `a.png#page=2`), or `None` for external URLs and
+/// in-page anchors.
+pub(crate) fn link_destination(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    let target = match raw.strip_prefix('<') {
+        Some(inner) => inner.split('>').next().unwrap_or(inner),
+        None => raw.split_whitespace().next().unwrap_or(""),
+    };
+    let target = target.split('#').next().unwrap_or("");
+    if target.is_empty() || target.contains("://") || target.starts_with("mailto:") {
+        return None;
+    }
+    Some(percent_decode(target))
+}
+
+pub(crate) fn is_file_name(target: &str) -> bool {
+    let name = target.rsplit('/').next().unwrap_or(target);
+    matches!(name.rfind('.'), Some(dot) if dot > 0 && dot + 1 < name.len())
+        && !has_md_extension(name)
+}
+
+/// Join `target` onto the directory `dir` (relative to the notes directory),
+/// resolving `.` and `..`. Returns `None` if the result leaves the notes
+/// directory.
+pub(crate) fn join_relative(dir: &str, target: &str) -> Option<String> {
+    let mut parts: Vec<&str> = if target.starts_with('/') {
+        Vec::new()
+    } else {
+        dir.split('/').filter(|p| !p.is_empty()).collect()
+    };
+    for part in target.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// Match the attachments of `store` against the links in its notes. A link
+/// is resolved relative to the linking note's folder first, then to the
+/// notes directory — the same places `piki view` and the GUI look.
+pub fn scan(store: &DocumentStore) -> Result<Report, String> {
+    let mut attachments: BTreeMap<String, Vec<String>> = store
+        .list_attachments()?
+        .into_iter()
+        .map(|path| (path, Vec::new()))
+        .collect();
+    let mut missing = Vec::new();
+
+    let mut notes = store.list_all_documents()?;
+    notes.sort();
+    for page in notes {
+        let Ok(doc) = store.load(&page) else {
+            continue;
+        };
+        let dir = page.rsplit_once('/').map_or("", |(dir, _)| dir);
+        for (line, target) in file_links(&doc.content) {
+            let found = [join_relative(dir, &target), join_relative("", &target)]
+                .into_iter()
+                .flatten()
+                .find(|path| attachments.contains_key(path));
+            match found {
+                Some(path) => {
+                    let pages = attachments.get_mut(&path).expect("attachment was found");
+                    if !pages.contains(&page) {
+                        pages.push(page.clone());
+                    }
+                }
+                None => missing.push(MissingFile {
+                    page: page.clone(),
+                    line,
+                    target,
+                }),
+            }
+        }
+    }
+
+    let attachments = attachments
+        .into_iter()
+        .map(|(path, referenced_by)| Attachment {
+            bytes: fs::metadata(store.base_path().join(&path))
+                .map(|m| m.len())
+                .unwrap_or(0),
+            path,
+            referenced_by,
+        })
+        .collect();
+    Ok(Report {
+        attachments,
+        missing,
+    })
+}
+
+/// Whether `path` is an image [`compress`] can handle.
+pub fn is_compressible(path: &str) -> bool {
+    let extension = path.rsplit('.').next().unwrap_or("").to_ascii_lowercase();
+    matches!(extension.as_str(), "png" | "jpg" | "jpeg" | "webp")
+}
+
+/// Scale the image `path` (relative to `notes_dir`) down to at most
+/// `max_width` pixels wide, after copying the original to
+/// `.piki/image-backups/`. Images that don't get smaller are left as they
+/// were. Returns the new size in bytes, or `None` if the image was kept.
+pub fn compress(notes_dir: &Path, path: &str, max_width: u32) -> Result<Option<u64>, String> {
+    let file = notes_dir.join(path);
+    let before = fs::metadata(&file)
+        .map_err(|e| format!("Failed to read '{}': {}", file.display(), e))?
+        .len();
+
+    let backup = backup_path(notes_dir, path)?;
+    fs::copy(&file, &backup)
+        .map_err(|e| format!("Failed to back up '{}': {}", file.display(), e))?;
+
+    // `>` only ever shrinks; `-strip` drops metadata such as embedded previews.
+    let resize = format!("{max_width}x>");
+    let mut last_error = String::new();
+    let mut converted = false;
+    for program in ["magick", "convert"] {
+        match Command::new(program)
+            .arg(&file)
+            .args(["-resize", &resize, "-strip"])
+            .arg(&file)
+            .output()
+        {
+            Ok(output) if output.status.success() => {
+                converted = true;
+                break;
+            }
+            Ok(output) => {
+                last_error = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                break;
+            }
+            Err(e) => last_error = format!("ImageMagick not found: {e}"),
+        }
+    }
+
+    let after = fs::metadata(&file).map(|m| m.len()).unwrap_or(0);
+    if !converted || after == 0 || after >= before {
+        fs::copy(&backup, &file)
+            .map_err(|e| format!("Failed to restore '{}': {}", file.display(), e))?;
+        let _ = fs::remove_file(&backup);
+        return if converted {
+            Ok(None)
+        } else {
+            Err(format!("Failed to compress '{path}': {last_error}"))
+        };
+    }
+    Ok(Some(after))
+}
+
+/// Where [`compress`] keeps the originals.
+pub fn backup_dir(notes_dir: &Path) -> PathBuf {
+    notes_dir.join(edit_log::LOCAL_DIR).join(BACKUP_DIR)
+}
+
+/// A free path for the backup of `path`, keeping earlier backups.
+fn backup_path(notes_dir: &Path, path: &str) -> Result<PathBuf, String> {
+    edit_log::local_dir(notes_dir)?;
+    let target = backup_dir(notes_dir).join(path);
+    let dir = target.parent().unwrap_or(notes_dir);
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create '{}': {}", dir.display(), e))?;
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let (stem, extension) = match file_name.rfind('.') {
+        Some(dot) if dot > 0 => (&file_name[..dot], &file_name[dot..]),
+        _ => (file_name, ""),
+    };
+    Ok(dir.join(unique_name(stem, extension, |candidate| {
+        dir.join(candidate).exists()
+    })))
+}
+
+/// `bytes` in a human friendly unit, e.g. `1.4 MB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Document;
+
+    #[test]
+    fn finds_links_to_files() {
+        let content = "![shot](attachments/a%20b.png) and [report](docs/q1.pdf \"Q1\")\n\
+                       [note](other) [[wiki]] [md](x.md) [web](https://e.com/x.png)\n\
+                       `![code](c.png)` ![](<my pic.jpg>) [p](f.pdf#page=2)";
+        assert_eq!(
+            file_links(content),
+            vec![
+                (1, "attachments/a b.png".to_string()),
+                (1, "docs/q1.pdf".to_string()),
+                (3, "my pic.jpg".to_string()),
+                (3, "f.pdf".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unreferenced_and_missing_files() {
+        let dir = std::env::temp_dir().join("piki-test-images");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("attachments")).unwrap();
+        fs::create_dir_all(dir.join("project/img")).unwrap();
+        fs::create_dir_all(dir.join(".piki")).unwrap();
+        fs::write(dir.join("attachments/used.png"), "12345").unwrap();
+        fs::write(dir.join("attachments/unused.png"), "1").unwrap();
+        fs::write(dir.join("project/img/local.jpg"), "1").unwrap();
+        fs::write(dir.join(".piki/edit-log.tsv"), "").unwrap();
+
+        let store = DocumentStore::new(dir.clone());
+        for (name, content) in [
+            ("a", "![x](attachments/used.png)\n![y](gone.png)"),
+            (
+                "project/b",
+                "![z](img/local.jpg) ![w](../attachments/used.png)",
+            ),
+        ] {
+            store
+                .save(&Document {
+                    name: name.to_string(),
+                    path: store.path_for(name),
+                    content: content.to_string(),
+                    modified_time: None,
+                })
+                .unwrap();
+        }
+
+        let report = scan(&store).unwrap();
+        let summary: Vec<(&str, u64, Vec<&str>)> = report
+            .attachments
+            .iter()
+            .map(|a| {
+                (
+                    a.path.as_str(),
+                    a.bytes,
+                    a.referenced_by.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("attachments/unused.png", 1, vec![]),
+                ("attachments/used.png", 5, vec!["a", "project/b"]),
+                ("project/img/local.jpg", 1, vec!["project/b"]),
+            ]
+        );
+        assert_eq!(
+            report.missing,
+            vec![MissingFile {
+                page: "a".to_string(),
+                line: 2,
+                target: "gone.png".to_string()
+            }]
+        );
+        assert_eq!(format_size(1536), "1.5 KB");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
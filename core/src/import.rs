@@ -0,0 +1,463 @@
+//! Importing notes from other wikis: an Obsidian vault or a Notion export,
+//! as a folder or a zip file, becomes notes and attachments in the notes
+//! directory, keeping its folders.
+//!
+//! - **Obsidian** links by note name wherever the note is (`[[Plan]]` for
+//!   `projects/Plan.md`) and embeds with `![[photo.png]]`. Links are rewritten
+//!   to the path of what they lead to, embedded images become Markdown images
+//!   and embedded notes links. The `.obsidian` settings folder is left out.
+//! - **Notion** appends an id to every page and folder (`Plan
+//!   0123456789abcdef0123456789abcdef.md`). The ids are dropped and the links
+//!   between pages rewritten to match. HTML exports are converted to Markdown
+//!   by the caller-provided converter.
+//!
+//! Notes and attachments that already exist are never overwritten.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::document::{Document, DocumentStore, has_md_extension, unique_name};
+use crate::images::{join_relative, link_destination};
+use crate::link_target;
+use crate::tags::is_code_fence;
+use crate::zip;
+
+/// The wiki an export comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Obsidian,
+    Notion,
+}
+
+impl Source {
+    pub fn parse(name: &str) -> Option<Source> {
+        match name.to_ascii_lowercase().as_str() {
+            "obsidian" => Some(Source::Obsidian),
+            "notion" => Some(Source::Notion),
+            _ => None,
+        }
+    }
+}
+
+/// What [`import`] did.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The imported notes' names.
+    pub notes: Vec<String>,
+    /// The imported attachments' paths in the notes directory.
+    pub attachments: Vec<String>,
+    /// Notes and attachments left out as they already exist.
+    pub existing: Vec<String>,
+}
+
+/// The files of the export at `path`, a folder or a zip file, by their paths
+/// in it. Zip files in a zip file, as Notion splits large exports, are
+/// unpacked, too.
+pub fn read_export(path: &Path) -> Result<Vec<(String, Vec<u8>)>, String> {
+    if path.is_dir() {
+        let mut files = Vec::new();
+        read_folder(path, "", &mut files)?;
+        return Ok(files);
+    }
+
+    let data = fs::read(path).map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    let mut files = Vec::new();
+    for (name, data) in zip::read(&data)? {
+        if name.to_ascii_lowercase().ends_with(".zip") {
+            files.extend(zip::read(&data)?);
+        } else {
+            files.push((name, data));
+        }
+    }
+    Ok(files)
+}
+
+fn read_folder(dir: &Path, prefix: &str, files: &mut Vec<(String, Vec<u8>)>) -> Result<(), String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read '{}': {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        let path = entry.path();
+        let relative = format!("{prefix}{name}");
+        if path.is_dir() {
+            read_folder(&path, &format!("{relative}/"), files)?;
+        } else {
+            let data = fs::read(&path)
+                .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+            files.push((relative, data));
+        }
+    }
+    Ok(())
+}
+
+/// Import `files`, read with [`read_export`] from an export of `source`,
+/// into `store`. Notion's HTML pages are turned into Markdown with
+/// `html_to_markdown`.
+pub fn import(
+    store: &DocumentStore,
+    mut files: Vec<(String, Vec<u8>)>,
+    source: Source,
+    html_to_markdown: &dyn Fn(&str) -> Result<String, String>,
+) -> Result<Report, String> {
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    // Where each file of the export ends up, notes without their extension.
+    let mut placed: HashMap<String, String> = HashMap::new();
+    let mut taken: HashSet<String> = HashSet::new();
+    let mut notes = Vec::new();
+    let mut attachments = Vec::new();
+    for (path, data) in files {
+        // Hidden files and anything that would be placed outside the notes
+        // directory are left out.
+        if !zip::is_relative_path(&path)
+            || path
+                .split('/')
+                .any(|part| part.starts_with('.') || part == "__MACOSX")
+        {
+            continue;
+        }
+        let is_note = has_md_extension(&path)
+            || (source == Source::Notion && path.to_ascii_lowercase().ends_with(".html"));
+        let mut target = match source {
+            Source::Notion => path
+                .split('/')
+                .map(without_notion_id)
+                .collect::<Vec<_>>()
+                .join("/"),
+            Source::Obsidian => path.clone(),
+        };
+        if is_note {
+            target.truncate(target.rfind('.').unwrap_or(target.len()));
+        }
+        let (stem, extension) = match target.rfind('.') {
+            Some(dot) if !is_note && dot > target.rfind('/').map_or(0, |s| s + 1) => {
+                (target[..dot].to_string(), target[dot..].to_string())
+            }
+            _ => (target, String::new()),
+        };
+        let target = unique_name(&stem, &extension, |name| taken.contains(name));
+        taken.insert(target.clone());
+        placed.insert(path.clone(), target.clone());
+        if is_note {
+            notes.push((path, target, data));
+        } else {
+            attachments.push((target, data));
+        }
+    }
+
+    // Obsidian finds notes and attachments by their name alone, picking the
+    // one closest to the vault's root if several share it.
+    let mut by_name: HashMap<String, String> = HashMap::new();
+    if source == Source::Obsidian {
+        let depth = |path: &str| path.matches('/').count();
+        for (path, target) in &placed {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            for key in [name, name.strip_suffix(".md").unwrap_or(name)] {
+                let found = by_name
+                    .entry(key.to_lowercase())
+                    .or_insert_with(|| target.clone());
+                if (depth(found), found.as_str()) > (depth(target), target.as_str()) {
+                    *found = target.clone();
+                }
+            }
+        }
+    }
+
+    let mut report = Report::default();
+    for (path, name, data) in notes {
+        let text = String::from_utf8_lossy(&data);
+        let markdown = if has_md_extension(&path) {
+            text.into_owned()
+        } else {
+            html_to_markdown(&text)?
+        };
+        let links = Links {
+            from: &path,
+            to: &name,
+            placed: &placed,
+            by_name: &by_name,
+        };
+        let content = links.rewrite(&markdown);
+        let path = store.path_for(&name);
+        if path.exists() {
+            report.existing.push(name);
+            continue;
+        }
+        store.save(&Document {
+            name: name.clone(),
+            path,
+            content,
+            modified_time: None,
+        })?;
+        report.notes.push(name);
+    }
+    for (target, data) in attachments {
+        let path = store.base_path().join(&target);
+        if path.exists() {
+            report.existing.push(target);
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+        }
+        fs::write(&path, data)
+            .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+        report.attachments.push(target);
+    }
+    Ok(report)
+}
+
+/// `component` of a Notion export's path without the page id Notion appends
+/// to names: `Plan 0123456789abcdef0123456789abcdef.md` becomes `Plan.md`.
+fn without_notion_id(component: &str) -> String {
+    let (stem, extension) = match component.rfind('.') {
+        Some(dot) if dot > 0 => component.split_at(dot),
+        _ => (component, ""),
+    };
+    match stem.len().checked_sub(33).and_then(|at| stem.get(at..)) {
+        Some(tail)
+            if tail.starts_with(' ')
+                && tail[1..].chars().all(|c| c.is_ascii_hexdigit())
+                && stem.len() > 33 =>
+        {
+            format!("{}{}", &stem[..stem.len() - 33], extension)
+        }
+        _ => component.to_string(),
+    }
+}
+
+/// Rewriting the links of a note moved from `from` in the export to the note
+/// `to`.
+struct Links<'a> {
+    from: &'a str,
+    to: &'a str,
+    /// The export's files by their paths in it, see [`import`].
+    placed: &'a HashMap<String, String>,
+    /// Obsidian's lookup by name, lowercased.
+    by_name: &'a HashMap<String, String>,
+}
+
+impl Links<'_> {
+    fn rewrite(&self, content: &str) -> String {
+        let mut out = String::with_capacity(content.len());
+        let mut in_fence = false;
+        for line in content.split_inclusive('\n') {
+            if is_code_fence(line) {
+                in_fence = !in_fence;
+                out.push_str(line);
+                continue;
+            }
+            if in_fence {
+                out.push_str(line);
+                continue;
+            }
+
+            let mut in_code = false;
+            let mut rest = line;
+            while let Some(c) = rest.chars().next() {
+                if c == '`' {
+                    in_code = !in_code;
+                } else if !in_code
+                    && let Some(after) =
+                        rest.strip_prefix("![[").or_else(|| rest.strip_prefix("[["))
+                    && let Some(end) = after.find("]]")
+                {
+                    let embed = rest.starts_with('!');
+                    self.wiki_link(&after[..end], embed, &mut out);
+                    rest = &after[end + 2..];
+                    continue;
+                } else if !in_code
+                    && let Some(after) = rest.strip_prefix("](")
+                    && let Some(end) = after.find(')')
+                    && let Some(target) = self.markdown_link(&after[..end])
+                {
+                    let _ = write!(out, "]({})", link_target(&target));
+                    rest = &after[end + 1..];
+                    continue;
+                }
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        out
+    }
+
+    /// An Obsidian wiki link, `[[target#section|label]]`, or an embed if
+    /// `embed` is set.
+    fn wiki_link(&self, link: &str, embed: bool, out: &mut String) {
+        let (target, label) = match link.split_once('|') {
+            Some((target, label)) => (target.trim(), Some(label.trim())),
+            None => (link.trim(), None),
+        };
+        let (name, section) = match target.split_once('#') {
+            Some((name, section)) => (name, format!("#{section}")),
+            None => (target, String::new()),
+        };
+        let found = if name.contains('/') {
+            let name = name.trim_start_matches('/');
+            self.placed
+                .get(name)
+                .or_else(|| self.placed.get(&format!("{name}.md")))
+                .cloned()
+        } else {
+            self.by_name.get(&name.to_lowercase()).cloned()
+        };
+
+        match found {
+            // An embedded image (or other file) stays embedded.
+            Some(file) if embed && !self.is_note(&file) => {
+                let alt = label.unwrap_or(name);
+                let _ = write!(out, "![{}]({})", alt, link_target(&file));
+            }
+            Some(note) => match label {
+                Some(label) => {
+                    let _ = write!(out, "[[{note}{section}|{label}]]");
+                }
+                None => {
+                    let _ = write!(out, "[[{note}{section}]]");
+                }
+            },
+            // Keep links to notes yet to be written, as Obsidian does.
+            None => {
+                let _ = write!(out, "[[{link}]]");
+            }
+        }
+    }
+
+    fn is_note(&self, placed: &str) -> bool {
+        self.placed.iter().any(|(path, target)| {
+            target == placed && (has_md_extension(path) || path.ends_with(".html"))
+        })
+    }
+
+    /// The new destination of a Markdown link to `raw`, if it leads to a
+    /// file of the export.
+    fn markdown_link(&self, raw: &str) -> Option<String> {
+        let target = link_destination(raw)?;
+        let from_dir = self.from.rsplit_once('/').map_or("", |(dir, _)| dir);
+        let path = join_relative(from_dir, &target)?;
+        let placed = self.placed.get(&path)?;
+        let section = raw
+            .trim()
+            .split_once('#')
+            .map(|(_, section)| format!("#{}", section.split_whitespace().next().unwrap_or("")))
+            .unwrap_or_default();
+        Some(format!("{}{section}", relative(self.to, placed)))
+    }
+}
+
+/// The path from the note `from`'s folder to `to`.
+fn relative(from: &str, to: &str) -> String {
+    let from: Vec<&str> = from.split('/').collect();
+    let to_parts: Vec<&str> = to.split('/').collect();
+    let from_dir = &from[..from.len() - 1];
+    let common = from_dir
+        .iter()
+        .zip(&to_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut path = "../".repeat(from_dir.len() - common);
+    path.push_str(&to_parts[common..].join("/"));
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(files: &[(&str, &str)]) -> Vec<(String, Vec<u8>)> {
+        files
+            .iter()
+            .map(|(path, content)| (path.to_string(), content.as_bytes().to_vec()))
+            .collect()
+    }
+
+    fn no_html(_: &str) -> Result<String, String> {
+        Err("no HTML".to_string())
+    }
+
+    #[test]
+    fn imports_obsidian_vaults() {
+        let dir = std::env::temp_dir().join("piki-test-import-obsidian");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Home.md"), "mine\n").unwrap();
+        let store = DocumentStore::new(dir.clone());
+
+        let report = import(
+            &store,
+            files(&[
+                ("Home.md", "theirs\n"),
+                (
+                    "daily/Monday.md",
+                    "See [[Plan#Goals|the plan]] and [[Someday]].\n![[photo.png]] ![[Plan]]\n`[[Plan]]`\n",
+                ),
+                ("projects/Plan.md", "[back](../daily/Monday.md)\n"),
+                ("assets/photo.png", "PNG"),
+                (".obsidian/app.json", "{}"),
+                ("/tmp/piki-test-import-evil.md", "evil\n"),
+                ("../piki-test-import-evil.sh", "evil\n"),
+            ]),
+            Source::Obsidian,
+            &no_html,
+        )
+        .unwrap();
+        assert_eq!(report.notes, vec!["daily/Monday", "projects/Plan"]);
+        assert_eq!(report.attachments, vec!["assets/photo.png"]);
+        assert_eq!(report.existing, vec!["Home"]);
+        assert_eq!(
+            store.load("daily/Monday").unwrap().content,
+            "See [[projects/Plan#Goals|the plan]] and [[Someday]].\n![photo.png](assets/photo.png) [[projects/Plan]]\n`[[Plan]]`\n"
+        );
+        assert_eq!(store.load("Home").unwrap().content, "mine\n");
+        assert!(!dir.join(".obsidian").exists());
+        assert!(!Path::new("/tmp/piki-test-import-evil.md").exists());
+        assert!(!dir.join("../piki-test-import-evil.sh").exists());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn imports_notion_exports() {
+        let dir = std::env::temp_dir().join("piki-test-import-notion");
+        let _ = fs::remove_dir_all(&dir);
+        let store = DocumentStore::new(dir.clone());
+        let id = "0123456789abcdef0123456789abcdef";
+
+        let report = import(
+            &store,
+            files(&[
+                (
+                    &format!("Work {id}.md"),
+                    &format!("# Work\n\n[Plan](Work%20{id}/Plan%20{id}.html#goals)\n"),
+                ),
+                (&format!("Work {id}/Plan {id}.html"), "<h1>Plan</h1>"),
+                (&format!("Work {id}/chart.png"), "PNG"),
+            ]),
+            Source::Notion,
+            &|html: &str| {
+                Ok(html
+                    .replace("<h1>", "# ")
+                    .replace("</h1>", "\n![](chart.png)\n"))
+            },
+        )
+        .unwrap();
+        assert_eq!(report.notes, vec!["Work", "Work/Plan"]);
+        assert_eq!(report.attachments, vec!["Work/chart.png"]);
+        assert_eq!(
+            store.load("Work").unwrap().content,
+            "# Work\n\n[Plan](Work/Plan#goals)\n"
+        );
+        assert_eq!(
+            store.load("Work/Plan").unwrap().content,
+            "# Plan\n![](chart.png)\n"
+        );
+        assert_eq!(without_notion_id("Plan.md"), "Plan.md");
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
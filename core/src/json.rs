@@ -0,0 +1,189 @@
+//! The JSON printed by `piki --json`, for scripts, fzf and editors.
+//!
+//! Each command prints one JSON array. Notes are described by their `name`,
+//! the `path` of their file and `mtime`, the file's modification time in
+//! seconds since the Unix epoch (`null` if unknown), followed by what the
+//! command found in them:
+//!
+//! ```json
+//! [{"name":"projects/plan","path":"/home/me/.piki/projects/plan.md","mtime":1748772000,
+//!   "matches":[{"line":3,"text":"Budget for Q3"}]}]
+//! ```
+
+use std::time::UNIX_EPOCH;
+
+use crate::DocumentStore;
+use crate::git::Commit;
+use crate::plugin::{is_checked, todo_due_date};
+use crate::search::NoteSearchResult;
+use crate::todo_cache::NoteTodos;
+
+/// `s` as a JSON string.
+pub fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// The notes `names`, for `piki ls`.
+pub fn notes(store: &DocumentStore, names: &[String]) -> String {
+    array(
+        names
+            .iter()
+            .map(|name| format!("{{{}}}", note_fields(store, name))),
+    )
+}
+
+/// Search results with their matching lines as `matches`, for `piki search`.
+pub fn search_results(store: &DocumentStore, results: &[NoteSearchResult]) -> String {
+    array(results.iter().map(|result| {
+        format!(
+            "{{{},\"matches\":{}}}",
+            note_fields(store, &result.name),
+            lines(&result.lines, |_| String::new())
+        )
+    }))
+}
+
+/// Notes with their `todos`, each also telling whether it is `done`, for
+/// `piki todo`.
+pub fn todos(store: &DocumentStore, notes: &[NoteTodos]) -> String {
+    array(notes.iter().map(|(name, todos)| {
+        format!(
+            "{{{},\"todos\":{}}}",
+            note_fields(store, name),
+            lines(todos, |todo| {
+                let due =
+                    todo_due_date(todo).map_or("null".to_string(), |due| quote(&due.to_string()));
+                format!(",\"done\":{},\"due\":{due}", is_checked(todo))
+            })
+        )
+    }))
+}
+
+/// Commits as `hash`, `date` (`YYYY-MM-DD`) and `subject`, for `piki log`.
+pub fn commits(commits: &[Commit]) -> String {
+    array(commits.iter().map(|commit| {
+        format!(
+            "{{\"hash\":{},\"date\":{},\"subject\":{}}}",
+            quote(&commit.hash),
+            quote(&commit.date),
+            quote(&commit.subject)
+        )
+    }))
+}
+
+fn note_fields(store: &DocumentStore, name: &str) -> String {
+    let mtime = store
+        .modified_time(name)
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or("null".to_string(), |time| time.as_secs().to_string());
+    format!(
+        "\"name\":{},\"path\":{},\"mtime\":{}",
+        quote(name),
+        quote(&store.path_for(name).to_string_lossy()),
+        mtime
+    )
+}
+
+/// Numbered lines as `{"line": …, "text": …}`, with `extra` fields for each
+/// line's text.
+fn lines(lines: &[(usize, String)], extra: impl Fn(&str) -> String) -> String {
+    let entries: Vec<String> = lines
+        .iter()
+        .map(|(line, text)| {
+            format!(
+                "{{\"line\":{},\"text\":{}{}}}",
+                line,
+                quote(text.trim()),
+                extra(text)
+            )
+        })
+        .collect();
+    format!("[{}]", entries.join(","))
+}
+
+fn array(entries: impl Iterator<Item = String>) -> String {
+    format!("[{}]\n", entries.collect::<Vec<_>>().join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn quotes_control_characters_and_astral_ones() {
+        // Every character below U+0020 must be escaped; `\n` gets the short form.
+        for c in (0u8..0x20).map(char::from).filter(|&c| c != '\n') {
+            assert_eq!(quote(&c.to_string()), format!("\"\\u{:04x}\"", c as u32));
+        }
+        assert_eq!(quote("a\nb"), "\"a\\nb\"");
+        assert_eq!(quote("\0\u{1f}"), "\"\\u0000\\u001f\"");
+        assert_eq!(quote("say \"hi\" \\ bye"), "\"say \\\"hi\\\" \\\\ bye\"");
+        // Characters beyond the BMP (a surrogate pair in UTF-16) stay as they
+        // are, which JSON allows for any character but quotes, backslashes
+        // and control characters.
+        assert_eq!(quote("😀 𝄞"), "\"😀 𝄞\"");
+        assert_eq!(quote("\u{7f}é"), "\"\\u007fé\"");
+    }
+
+    #[test]
+    fn describes_notes_and_what_was_found_in_them() {
+        let dir = std::env::temp_dir().join("piki-test-json");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("plan.md"), "- [ ] Budget\n- [x] \"Hire\"\n").unwrap();
+        let store = DocumentStore::new(dir.clone());
+        let path = quote(&dir.join("plan.md").to_string_lossy());
+
+        let listed = notes(&store, &["plan".to_string(), "gone".to_string()]);
+        assert!(listed.starts_with(&format!("[{{\"name\":\"plan\",\"path\":{path},\"mtime\":1")));
+        assert!(listed.ends_with("\"mtime\":null}]\n"));
+
+        let found = search_results(
+            &store,
+            &[NoteSearchResult {
+                name: "plan".to_string(),
+                lines: vec![(1, "- [ ] Budget".to_string())],
+            }],
+        );
+        assert!(found.ends_with(",\"matches\":[{\"line\":1,\"text\":\"- [ ] Budget\"}]}]\n"));
+
+        let todo_notes = vec![(
+            "plan".to_string(),
+            vec![
+                (1, "- [ ] Budget @due(2026-06-01)".to_string()),
+                (2, "- [x] \"Hire\"".to_string()),
+            ],
+        )];
+        assert!(todos(&store, &todo_notes).ends_with(
+            ",\"todos\":[{\"line\":1,\"text\":\"- [ ] Budget @due(2026-06-01)\",\"done\":false,\"due\":\"2026-06-01\"},\
+             {\"line\":2,\"text\":\"- [x] \\\"Hire\\\"\",\"done\":true,\"due\":null}]}]\n"
+        ));
+
+        let commit = Commit {
+            hash: "3f2a".to_string(),
+            date: "2025-06-01".to_string(),
+            subject: "Plan\tQ3".to_string(),
+            path: String::new(),
+        };
+        assert_eq!(
+            commits(&[commit]),
+            "[{\"hash\":\"3f2a\",\"date\":\"2025-06-01\",\"subject\":\"Plan\\u0009Q3\"}]\n"
+        );
+        assert_eq!(commits(&[]), "[]\n");
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,7 +1,48 @@
 mod document;
 pub use crate::document::*;
 
+mod page_id;
+pub use crate::page_id::*;
+
 mod plugin;
 pub use crate::plugin::*;
 
+pub mod attachment;
+pub mod dates;
+pub mod diff;
+pub mod diff_tool;
+pub mod edit_lock;
+pub mod edit_log;
+pub mod encryption;
+pub mod export;
+pub mod footnotes;
+pub mod format;
+pub mod frontmatter;
+pub mod fuzzy;
+pub mod git;
+pub mod graph;
+pub mod images;
+pub mod import;
+pub mod json;
+pub mod link_check;
+pub mod link_index;
+pub mod listing;
+pub mod merge;
+pub mod pdf;
+pub mod redact;
+pub mod replace;
+pub mod script;
 pub mod search;
+pub mod section;
+pub mod settings_archive;
+pub mod spellcheck;
+pub mod stats;
+pub mod tags;
+pub mod template;
+pub mod timesheet;
+pub mod title;
+pub mod todo_cache;
+pub mod wikis;
+pub mod wrap;
+
+mod zip;
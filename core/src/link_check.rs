@@ -0,0 +1,344 @@
+//! Checking external (`http://`, `https://`) links for dead targets.
+//!
+//! Used by `piki doctor --check-external`. Like the git integration, requests
+//! shell out to a command line tool — `curl` — so piki needs no HTTP or TLS
+//! library. Each URL gets a `HEAD` request (retried as a one-byte `GET` for
+//! servers that don't support `HEAD`), a few at a time, each with a timeout.
+//! Results are cached for a day in `.piki/link-cache.tsv`, so re-running the
+//! check only contacts servers whose links are new or whose result is stale.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::edit_log;
+use crate::tags::is_code_fence;
+
+const CACHE_FILE: &str = "link-cache.tsv";
+/// How long a cached result is trusted.
+pub const CACHE_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+/// How many requests run at the same time.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+/// How long a single request may take.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The outcome of checking one URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// The server answered with a success or redirect status.
+    Ok(u16),
+    /// The server answered with an error status, e.g. 404.
+    Dead(u16),
+    /// No answer: unknown host, refused connection, timeout, …
+    Unreachable(String),
+}
+
+impl Status {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Status::Ok(_))
+    }
+
+    fn from_code(code: u16) -> Status {
+        // 429 (rate limited) says nothing about the link itself.
+        if code < 400 || code == 429 {
+            Status::Ok(code)
+        } else {
+            Status::Dead(code)
+        }
+    }
+
+    fn to_field(&self) -> String {
+        match self {
+            Status::Ok(code) | Status::Dead(code) => code.to_string(),
+            Status::Unreachable(reason) => format!("-{}", reason.replace(['\t', '\n'], " ")),
+        }
+    }
+
+    fn from_field(field: &str) -> Option<Status> {
+        match field.strip_prefix('-') {
+            Some(reason) => Some(Status::Unreachable(reason.to_string())),
+            None => field.parse().ok().map(Status::from_code),
+        }
+    }
+}
+
+/// The external URLs in `content` with their 1-based line numbers, in order.
+/// URLs inside code are skipped.
+pub fn external_links(content: &str) -> Vec<(usize, String)> {
+    let mut links = Vec::new();
+    let mut in_fence = false;
+
+    for (index, line) in content.lines().enumerate() {
+        if is_code_fence(line) {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut in_code = false;
+        let mut i = 0;
+        while i < line.len() {
+            let rest = &line[i..];
+            if rest.starts_with('`') {
+                in_code = !in_code;
+            } else if !in_code && (rest.starts_with("https://") || rest.starts_with("http://")) {
+                let url = url_at(rest);
+                if url.len() > "https://".len() {
+                    links.push((index + 1, url.to_string()));
+                }
+                i += url.len().max(1);
+                continue;
+            }
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    links
+}
+
+/// The URL at the start of `text`: up to whitespace or a delimiter, without
+/// trailing punctuation or a closing parenthesis that belongs to the
+/// surrounding Markdown link.
+fn url_at(text: &str) -> &str {
+    let end = text
+        .find(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"' | '\'' | '`'))
+        .unwrap_or(text.len());
+    let mut url = &text[..end];
+    loop {
+        let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '*', '_', ']']);
+        let trimmed = if trimmed.ends_with(')')
+            && trimmed.matches('(').count() < trimmed.matches(')').count()
+        {
+            &trimmed[..trimmed.len() - 1]
+        } else {
+            trimmed
+        };
+        if trimmed.len() == url.len() {
+            return url;
+        }
+        url = trimmed;
+    }
+}
+
+/// Request `url` and report whether it is alive.
+pub fn check_url(url: &str, timeout: Duration) -> Status {
+    match curl_status(url, timeout, true) {
+        // Some servers refuse or mishandle HEAD; ask for one byte instead.
+        Status::Dead(405 | 403 | 501) | Status::Unreachable(_) => curl_status(url, timeout, false),
+        status => status,
+    }
+}
+
+fn curl_status(url: &str, timeout: Duration, head: bool) -> Status {
+    let mut command = Command::new("curl");
+    command
+        .args(["--silent", "--show-error", "--location", "--output"])
+        .arg(if cfg!(windows) { "NUL" } else { "/dev/null" })
+        .args(["--write-out", "%{http_code}", "--max-time"])
+        .arg(timeout.as_secs().max(1).to_string());
+    if head {
+        command.arg("--head");
+    } else {
+        command.args(["--range", "0-0"]);
+    }
+    let output = match command.arg("--").arg(url).output() {
+        Ok(output) => output,
+        Err(e) => return Status::Unreachable(format!("failed to run curl: {e}")),
+    };
+
+    match String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<u16>()
+    {
+        Ok(code) if code > 0 => Status::from_code(code),
+        _ => Status::Unreachable(failure_reason(&String::from_utf8_lossy(&output.stderr))),
+    }
+}
+
+/// The first line of curl's error output, without its `curl: (6)` prefix.
+fn failure_reason(stderr: &str) -> String {
+    let reason = stderr
+        .trim()
+        .lines()
+        .next()
+        .unwrap_or("")
+        .trim_start_matches("curl: ")
+        .trim_start_matches(|c: char| c == '(' || c.is_ascii_digit() || c == ')')
+        .trim();
+    if reason.is_empty() {
+        "no response".to_string()
+    } else {
+        reason.to_string()
+    }
+}
+
+/// Whether `curl` can be run. Without it every link would look unreachable,
+/// so callers should check first and say so instead.
+pub fn curl_available() -> bool {
+    Command::new("curl")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Check all `urls` with up to `concurrency` requests at a time, using
+/// `check` for each one. Returns the status of every URL.
+pub fn check_all(
+    urls: &[String],
+    concurrency: usize,
+    check: impl Fn(&str) -> Status + Sync,
+) -> HashMap<String, Status> {
+    let queue = Mutex::new(urls.iter());
+    let results = Mutex::new(HashMap::new());
+    thread::scope(|scope| {
+        for _ in 0..concurrency.clamp(1, urls.len().max(1)) {
+            scope.spawn(|| {
+                while let Some(url) = queue.lock().ok().and_then(|mut q| q.next()) {
+                    let status = check(url);
+                    if let Ok(mut results) = results.lock() {
+                        results.insert(url.clone(), status);
+                    }
+                }
+            });
+        }
+    });
+    results.into_inner().unwrap_or_default()
+}
+
+/// Results of earlier checks, kept in the notes directory's `.piki/` folder.
+pub struct Cache {
+    path: PathBuf,
+    /// URL → (time checked in seconds since the Unix epoch, status).
+    entries: HashMap<String, (u64, Status)>,
+}
+
+impl Cache {
+    /// Load the cache of `notes_dir`; a missing or unreadable cache is empty.
+    pub fn load(notes_dir: &Path) -> Cache {
+        let path = notes_dir.join(edit_log::LOCAL_DIR).join(CACHE_FILE);
+        let entries = fs::read_to_string(&path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let url = fields.next()?.to_string();
+                let time = fields.next()?.parse().ok()?;
+                let status = Status::from_field(fields.next()?)?;
+                Some((url, (time, status)))
+            })
+            .collect();
+        Cache { path, entries }
+    }
+
+    /// The cached status of `url`, unless it is older than `max_age`.
+    pub fn get(&self, url: &str, max_age: Duration) -> Option<&Status> {
+        let (time, status) = self.entries.get(url)?;
+        (now().saturating_sub(*time) <= max_age.as_secs()).then_some(status)
+    }
+
+    pub fn insert(&mut self, url: String, status: Status) {
+        self.entries.insert(url, (now(), status));
+    }
+
+    /// Write the cache back, dropping expired entries.
+    pub fn save(&self, notes_dir: &Path) -> Result<(), String> {
+        edit_log::local_dir(notes_dir)?;
+        let now = now();
+        let mut lines: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, (time, _))| now.saturating_sub(*time) <= CACHE_MAX_AGE.as_secs())
+            .map(|(url, (time, status))| format!("{url}\t{time}\t{}", status.to_field()))
+            .collect();
+        lines.sort();
+        let mut content = lines.join("\n");
+        content.push('\n');
+        fs::write(&self.path, content)
+            .map_err(|e| format!("Failed to write '{}': {}", self.path.display(), e))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_external_links() {
+        let content = "See [docs](https://example.com/a_(b)).\n\
+                       <http://example.org/x> and https://example.net/y, done\n\
+                       `https://in.code` here\n\
+                       ```\nhttps://fenced.example\n```\n\
+                       [[wiki]] https://example.com/end.";
+        assert_eq!(
+            external_links(content),
+            vec![
+                (1, "https://example.com/a_(b)".to_string()),
+                (2, "http://example.org/x".to_string()),
+                (2, "https://example.net/y".to_string()),
+                (7, "https://example.com/end".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn failure_reason_is_the_first_line() {
+        assert_eq!(
+            failure_reason("curl: (6) Could not resolve host: nowhere.example\nmore details\n"),
+            "Could not resolve host: nowhere.example"
+        );
+        assert_eq!(failure_reason("\n"), "no response");
+    }
+
+    #[test]
+    fn checks_each_url_once_and_caches_results() {
+        let urls: Vec<String> = (0..20)
+            .map(|i| format!("https://example.com/{i}"))
+            .collect();
+        let results = check_all(&urls, 4, |url| {
+            if url.ends_with("/13") {
+                Status::Dead(404)
+            } else {
+                Status::Ok(200)
+            }
+        });
+        assert_eq!(results.len(), 20);
+        assert_eq!(results["https://example.com/13"], Status::Dead(404));
+
+        let dir = std::env::temp_dir().join("piki-test-link-cache");
+        let _ = fs::remove_dir_all(&dir);
+        let mut cache = Cache::load(&dir);
+        cache.insert("https://a.example".into(), Status::Dead(410));
+        cache.insert(
+            "https://b.example".into(),
+            Status::Unreachable("timed out".into()),
+        );
+        cache.save(&dir).unwrap();
+
+        let cache = Cache::load(&dir);
+        assert_eq!(
+            cache.get("https://a.example", CACHE_MAX_AGE),
+            Some(&Status::Dead(410))
+        );
+        assert_eq!(
+            cache.get("https://b.example", CACHE_MAX_AGE),
+            Some(&Status::Unreachable("timed out".into()))
+        );
+        assert_eq!(cache.get("https://c.example", CACHE_MAX_AGE), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
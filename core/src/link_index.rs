@@ -0,0 +1,240 @@
+//! The links of every note, indexed so that the link graph, backlinks and
+//! `piki check` don't read and parse every note each time.
+//!
+//! Like the todo cache ([`crate::todo_cache`]), the index lives in
+//! `.piki/link-index.tsv`: each note's internal links (see
+//! [`graph::internal_links`]) together with its modification time and size,
+//! so a scan only reads the notes that changed since the last one. Saving a
+//! note through the [`DocumentStore`] updates its entry right away.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::DocumentStore;
+use crate::edit_log::{self, LOCAL_DIR};
+use crate::encryption;
+use crate::graph;
+
+const INDEX_FILE: &str = "link-index.tsv";
+
+/// A note and its internal links, each with its 1-based line number.
+pub type NoteLinks = (String, Vec<(usize, String)>);
+
+/// What the index knows about one note.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    /// Modification time in nanoseconds since the Unix epoch.
+    mtime: u128,
+    size: u64,
+    /// 1-based line numbers and targets of the note's links.
+    links: Vec<(usize, String)>,
+}
+
+fn index_path(notes_dir: &Path) -> PathBuf {
+    notes_dir.join(LOCAL_DIR).join(INDEX_FILE)
+}
+
+/// Parse the index file: a `page` line with the note's modification time,
+/// size and name for each note, followed by a `link` line for each of its
+/// links. Names and targets come last on their lines, so they may hold tabs.
+fn parse(text: &str) -> HashMap<String, Entry> {
+    let mut entries: HashMap<String, Entry> = HashMap::new();
+    let mut current: Option<String> = None;
+    for line in text.lines() {
+        match line.split_once('\t') {
+            Some(("page", rest)) => {
+                let mut fields = rest.splitn(3, '\t');
+                current = match (fields.next(), fields.next(), fields.next()) {
+                    (Some(mtime), Some(size), Some(name)) => match (mtime.parse(), size.parse()) {
+                        (Ok(mtime), Ok(size)) => {
+                            let links = Vec::new();
+                            entries.insert(name.to_string(), Entry { mtime, size, links });
+                            Some(name.to_string())
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                };
+            }
+            Some(("link", rest)) => {
+                if let Some(entry) = current.as_ref().and_then(|name| entries.get_mut(name))
+                    && let Some((line, target)) = rest.split_once('\t')
+                    && let Ok(line) = line.parse()
+                {
+                    entry.links.push((line, target.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+    entries
+}
+
+fn serialize(entries: &HashMap<String, Entry>) -> String {
+    let mut names: Vec<&String> = entries.keys().collect();
+    names.sort();
+    let mut text = String::new();
+    for name in names {
+        let entry = &entries[name];
+        let _ = writeln!(text, "page\t{}\t{}\t{}", entry.mtime, entry.size, name);
+        for (line, target) in &entry.links {
+            let _ = writeln!(text, "link\t{line}\t{target}");
+        }
+    }
+    text
+}
+
+/// The modification time (in nanoseconds since the Unix epoch) and size of
+/// the file at `path`.
+fn stamp(path: &Path) -> Option<(u128, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    let mtime = meta
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos());
+    Some((mtime, meta.len()))
+}
+
+/// Every note in `store` with its links, sorted by note; notes that can't be
+/// read are listed without any. Reads only the notes that changed since the
+/// last scan, and updates the index.
+pub fn scan(store: &DocumentStore) -> Result<Vec<NoteLinks>, String> {
+    let notes_dir = store.base_path();
+    let indexed = fs::read_to_string(index_path(notes_dir))
+        .map(|text| parse(&text))
+        .unwrap_or_default();
+
+    let mut entries = HashMap::with_capacity(indexed.len());
+    let mut changed = false;
+    let mut notes: Vec<NoteLinks> = Vec::new();
+    for name in store.list_all_documents()? {
+        let path = store.path_for(&name);
+        if encryption::is_encrypted(&path) {
+            // The index isn't encrypted, so what encrypted notes link to
+            // stays out of it.
+            let links = store
+                .load(&name)
+                .map(|doc| graph::internal_links(&doc.content))
+                .unwrap_or_default();
+            notes.push((name, links));
+            continue;
+        }
+        let Some((mtime, size)) = stamp(&path) else {
+            continue;
+        };
+
+        let entry = match indexed.get(&name) {
+            Some(entry) if entry.mtime == mtime && entry.size == size => entry.clone(),
+            _ => {
+                changed = true;
+                let Ok(doc) = store.load(&name) else {
+                    notes.push((name, Vec::new()));
+                    continue;
+                };
+                let links = graph::internal_links(&doc.content);
+                Entry { mtime, size, links }
+            }
+        };
+        notes.push((name.clone(), entry.links.clone()));
+        entries.insert(name, entry);
+    }
+    changed |= entries.len() != indexed.len();
+
+    // The index only saves time; failing to write it is no reason to fail.
+    if changed && edit_log::local_dir(notes_dir).is_ok() {
+        let _ = edit_log::replace_local_file(&index_path(notes_dir), &serialize(&entries));
+    }
+
+    notes.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(notes)
+}
+
+/// Update the entry of the note `name`, just written to `path` with
+/// `content`. Does nothing until the index was created by a [`scan`].
+pub(crate) fn note_saved(notes_dir: &Path, name: &str, path: &Path, content: &str) {
+    let index = index_path(notes_dir);
+    let Ok(text) = fs::read_to_string(&index) else {
+        return;
+    };
+    let mut entries = parse(&text);
+    match stamp(path) {
+        Some((mtime, size)) if !encryption::is_encrypted(path) => {
+            let links = graph::internal_links(content);
+            entries.insert(name.to_string(), Entry { mtime, size, links });
+        }
+        _ => {
+            entries.remove(name);
+        }
+    }
+    let _ = edit_log::replace_local_file(&index, &serialize(&entries));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Document;
+    use std::time::{Duration, SystemTime};
+
+    fn touch(path: &Path, secs: u64) {
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn rescans_only_changed_notes() {
+        let dir = std::env::temp_dir().join("piki-test-link-index");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.md"), "# A\n[[b]] and [c](c#part)\n").unwrap();
+        fs::write(dir.join("b.md"), "no links\n").unwrap();
+        let store = DocumentStore::new(dir.clone());
+
+        assert_eq!(
+            scan(&store).unwrap(),
+            vec![
+                (
+                    "a".to_string(),
+                    vec![(2, "b".to_string()), (2, "c#part".to_string())]
+                ),
+                ("b".to_string(), vec![]),
+            ]
+        );
+        assert!(dir.join(".piki/link-index.tsv").is_file());
+
+        // An indexed note is not read again: rewriting it behind the index's
+        // back with the same size and time goes unnoticed.
+        touch(&dir.join("b.md"), 1_000);
+        scan(&store).unwrap();
+        fs::write(dir.join("b.md"), "[[zzzz]]\n").unwrap();
+        touch(&dir.join("b.md"), 1_000);
+        assert!(scan(&store).unwrap()[1].1.is_empty());
+
+        // Saving through the store updates the index right away.
+        store
+            .save(&Document {
+                content: "[[a]]\n".to_string(),
+                ..store.load("b").unwrap()
+            })
+            .unwrap();
+        let text = fs::read_to_string(dir.join(".piki/link-index.tsv")).unwrap();
+        assert!(text.ends_with("\tb\nlink\t1\ta\n"));
+        assert_eq!(scan(&store).unwrap()[1].1, vec![(1, "a".to_string())]);
+
+        // Removed notes are dropped.
+        fs::remove_file(dir.join("a.md")).unwrap();
+        assert_eq!(
+            scan(&store).unwrap(),
+            vec![("b".to_string(), vec![(1, "a".to_string())])]
+        );
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,119 @@
+//! Listing notes, for `piki ls`: picking them by a glob pattern and drawing
+//! the folders they are in as a tree.
+
+/// Whether the note `name` matches the glob `pattern`: `*` stands for any
+/// run of characters within a folder's or note's name, `**` for any across
+/// folders and `?` for a single character. A pattern without `/` is also
+/// tried against the note's name without its folder, so that `plan*` finds
+/// `projects/plan-q3` too.
+pub fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.strip_suffix(".md").unwrap_or(pattern);
+    let chars = |s: &str| s.chars().collect::<Vec<_>>();
+    let full = matches(&chars(pattern), &chars(name));
+    full || (!pattern.contains('/')
+        && name
+            .rsplit_once('/')
+            .is_some_and(|(_, file)| matches(&chars(pattern), &chars(file))))
+}
+
+fn matches(pattern: &[char], name: &[char]) -> bool {
+    match pattern {
+        [] => name.is_empty(),
+        ['*', '*', rest @ ..] => (0..=name.len()).any(|i| matches(rest, &name[i..])),
+        ['*', rest @ ..] => (0..=name.len())
+            .take_while(|&i| i == 0 || name[i - 1] != '/')
+            .any(|i| matches(rest, &name[i..])),
+        ['?', rest @ ..] => {
+            matches!(name.first(), Some(c) if *c != '/') && matches(rest, &name[1..])
+        }
+        [c, rest @ ..] => name.first() == Some(c) && matches(rest, &name[1..]),
+    }
+}
+
+/// The notes `names` drawn as a tree of their folders, one line each, in the
+/// order given: a folder goes where its first note would and lists its notes
+/// and subfolders beneath it, as `tree` does.
+pub fn tree(names: &[String]) -> String {
+    let mut root = Folder::default();
+    for name in names {
+        let mut folder = &mut root;
+        let mut parts = name.split('/').peekable();
+        while let Some(part) = parts.next() {
+            if parts.peek().is_none() {
+                folder.entries.push((part.to_string(), None));
+                break;
+            }
+            let index = match folder
+                .entries
+                .iter()
+                .position(|(entry, sub)| sub.is_some() && entry == part)
+            {
+                Some(index) => index,
+                None => {
+                    folder
+                        .entries
+                        .push((part.to_string(), Some(Folder::default())));
+                    folder.entries.len() - 1
+                }
+            };
+            folder = folder.entries[index].1.as_mut().expect("a folder");
+        }
+    }
+
+    let mut out = String::from(".\n");
+    root.draw("", &mut out);
+    out
+}
+
+#[derive(Default)]
+struct Folder {
+    /// Notes (without a folder) and subfolders, named.
+    entries: Vec<(String, Option<Folder>)>,
+}
+
+impl Folder {
+    fn draw(&self, indent: &str, out: &mut String) {
+        for (index, (name, sub)) in self.entries.iter().enumerate() {
+            let last = index + 1 == self.entries.len();
+            out.push_str(indent);
+            out.push_str(if last { "└── " } else { "├── " });
+            out.push_str(name);
+            if let Some(sub) = sub {
+                out.push_str("/\n");
+                sub.draw(
+                    &format!("{indent}{}", if last { "    " } else { "│   " }),
+                    out,
+                );
+            } else {
+                out.push('\n');
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_globs() {
+        assert!(glob_matches("plan*", "plan-q3"));
+        assert!(glob_matches("plan*", "projects/plan-q3"));
+        assert!(glob_matches("projects/*", "projects/plan"));
+        assert!(!glob_matches("projects/*", "projects/old/plan"));
+        assert!(glob_matches("projects/**", "projects/old/plan"));
+        assert!(glob_matches("**/plan", "a/b/plan"));
+        assert!(glob_matches("pl?n.md", "plan"));
+        assert!(!glob_matches("p*", "work/other"));
+        assert!(!glob_matches("plan", "planning"));
+    }
+
+    #[test]
+    fn draws_folders_as_a_tree() {
+        let names = ["a", "projects/old/x", "projects/plan", "z"].map(String::from);
+        assert_eq!(
+            tree(&names),
+            ".\n├── a\n├── projects/\n│   ├── old/\n│   │   └── x\n│   └── plan\n└── z\n"
+        );
+    }
+}
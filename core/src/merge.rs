@@ -0,0 +1,176 @@
+//! Line-based three-way merge.
+//!
+//! Used when a note was edited in piki and, at the same time, changed on disk
+//! by another program: both versions are merged against the content they
+//! started from. Changes to different lines combine cleanly; lines changed on
+//! both sides are kept in git-style conflict markers so nothing is lost.
+
+/// Marker lines written around conflicting regions.
+pub const CONFLICT_START: &str = "<<<<<<< mine";
+pub const CONFLICT_SEPARATOR: &str = "=======";
+pub const CONFLICT_END: &str = ">>>>>>> theirs";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merged {
+    pub text: String,
+    /// Number of regions wrapped in conflict markers.
+    pub conflicts: usize,
+}
+
+/// Merge `mine` and `theirs`, which were both derived from `base`.
+pub fn merge3(base: &str, mine: &str, theirs: &str) -> Merged {
+    let base: Vec<&str> = base.lines().collect();
+    let mine_lines: Vec<&str> = mine.lines().collect();
+    let theirs_lines: Vec<&str> = theirs.lines().collect();
+
+    let to_mine = lcs_matches(&base, &mine_lines);
+    let to_theirs = lcs_matches(&base, &theirs_lines);
+
+    let mut out: Vec<&str> = Vec::new();
+    let mut conflicts = 0;
+    let (mut i, mut j, mut k) = (0, 0, 0);
+
+    while i < base.len() || j < mine_lines.len() || k < theirs_lines.len() {
+        // A base line kept unchanged at the current position on both sides.
+        if i < base.len() && to_mine[i] == Some(j) && to_theirs[i] == Some(k) {
+            out.push(base[i]);
+            i += 1;
+            j += 1;
+            k += 1;
+            continue;
+        }
+
+        // Otherwise everything up to the next line both sides kept is a
+        // changed region.
+        let next = (i..base.len()).find_map(|n| Some((n, to_mine[n]?, to_theirs[n]?)));
+        let (ni, nj, nk) = next.unwrap_or((base.len(), mine_lines.len(), theirs_lines.len()));
+        let base_part = &base[i..ni];
+        let mine_part = &mine_lines[j..nj];
+        let theirs_part = &theirs_lines[k..nk];
+
+        if mine_part == base_part || mine_part == theirs_part {
+            out.extend_from_slice(theirs_part);
+        } else if theirs_part == base_part {
+            out.extend_from_slice(mine_part);
+        } else {
+            conflicts += 1;
+            out.push(CONFLICT_START);
+            out.extend_from_slice(mine_part);
+            out.push(CONFLICT_SEPARATOR);
+            out.extend_from_slice(theirs_part);
+            out.push(CONFLICT_END);
+        }
+
+        (i, j, k) = (ni, nj, nk);
+    }
+
+    let mut text = out.join("\n");
+    if !text.is_empty() && (mine.ends_with('\n') || theirs.ends_with('\n')) {
+        text.push('\n');
+    }
+    Merged { text, conflicts }
+}
+
+/// For each item of `a`, the index of the item of `b` it is paired with in a
+/// longest common subsequence of both, if any.
+pub(crate) fn lcs_matches(a: &[&str], b: &[&str]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; a.len()];
+
+    // Items shared at the start and end need no table.
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    for (n, m) in matches.iter_mut().enumerate().take(prefix) {
+        *m = Some(n);
+    }
+    for n in 0..suffix {
+        matches[a.len() - 1 - n] = Some(b.len() - 1 - n);
+    }
+
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+    if a_mid.is_empty() || b_mid.is_empty() {
+        return matches;
+    }
+
+    // lengths[x][y]: LCS length of a_mid[x..] and b_mid[y..].
+    let width = b_mid.len() + 1;
+    let mut lengths = vec![0u32; (a_mid.len() + 1) * width];
+    for x in (0..a_mid.len()).rev() {
+        for y in (0..b_mid.len()).rev() {
+            lengths[x * width + y] = if a_mid[x] == b_mid[y] {
+                lengths[(x + 1) * width + y + 1] + 1
+            } else {
+                lengths[(x + 1) * width + y].max(lengths[x * width + y + 1])
+            };
+        }
+    }
+
+    let (mut x, mut y) = (0, 0);
+    while x < a_mid.len() && y < b_mid.len() {
+        if a_mid[x] == b_mid[y] {
+            matches[prefix + x] = Some(prefix + y);
+            x += 1;
+            y += 1;
+        } else if lengths[(x + 1) * width + y] >= lengths[x * width + y + 1] {
+            x += 1;
+        } else {
+            y += 1;
+        }
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_on_different_lines_combine() {
+        let base = "one\ntwo\nthree\nfour\n";
+        let mine = "one\nTWO\nthree\nfour\n";
+        let theirs = "one\ntwo\nthree\nfour\nfive\n";
+        let merged = merge3(base, mine, theirs);
+        assert_eq!(merged.text, "one\nTWO\nthree\nfour\nfive\n");
+        assert_eq!(merged.conflicts, 0);
+    }
+
+    #[test]
+    fn test_identical_changes_are_not_conflicts() {
+        let merged = merge3("a\nb\n", "a\nc\n", "a\nc\n");
+        assert_eq!(merged.text, "a\nc\n");
+        assert_eq!(merged.conflicts, 0);
+    }
+
+    #[test]
+    fn test_overlapping_changes_are_marked() {
+        let merged = merge3("a\nb\nc\n", "a\nmine\nc\n", "a\ntheirs\nc\n");
+        assert_eq!(
+            merged.text,
+            "a\n<<<<<<< mine\nmine\n=======\ntheirs\n>>>>>>> theirs\nc\n"
+        );
+        assert_eq!(merged.conflicts, 1);
+    }
+
+    #[test]
+    fn test_deletion_and_insertion() {
+        let base = "keep\ndrop\nkeep too\n";
+        let mine = "keep\nkeep too\n";
+        let theirs = "new first\nkeep\ndrop\nkeep too\n";
+        let merged = merge3(base, mine, theirs);
+        assert_eq!(merged.text, "new first\nkeep\nkeep too\n");
+        assert_eq!(merged.conflicts, 0);
+    }
+
+    #[test]
+    fn test_lcs_matches() {
+        let a = ["x", "a", "b", "c"];
+        let b = ["a", "y", "c"];
+        assert_eq!(lcs_matches(&a, &b), vec![None, Some(0), None, Some(2)]);
+    }
+}
@@ -0,0 +1,183 @@
+//! Interned page names.
+//!
+//! The same note name ends up in many places at once — the open note, the
+//! back/forward history, remembered positions, the picker's result list — and
+//! used to be cloned into a fresh `String` for each of them. A [`PageId`] is a
+//! shared handle to a single interned copy of the name instead: cloning it is a
+//! reference-count bump, and comparing two ids is a pointer comparison.
+//!
+//! Interned names are never freed. A wiki has at most a few thousand distinct
+//! note names, so the interner stays small for the lifetime of the process.
+
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// An interned note name (or link destination).
+///
+/// Derefs to `str`, so it can be passed wherever a `&str` note name is
+/// expected, and compares equal to plain strings with the same content.
+#[derive(Clone)]
+pub struct PageId(Arc<str>);
+
+fn interner() -> &'static Mutex<HashSet<Arc<str>>> {
+    static INTERNER: OnceLock<Mutex<HashSet<Arc<str>>>> = OnceLock::new();
+    INTERNER.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+impl PageId {
+    /// Intern `name`, returning the shared id for it. Interning the same name
+    /// twice yields ids pointing at the same allocation.
+    pub fn new(name: &str) -> Self {
+        let mut set = interner().lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(existing) = set.get(name) {
+            return PageId(existing.clone());
+        }
+        let interned: Arc<str> = Arc::from(name);
+        set.insert(interned.clone());
+        PageId(interned)
+    }
+
+    /// The page name as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for PageId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for PageId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for PageId {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for PageId {
+    fn eq(&self, other: &Self) -> bool {
+        // Every id goes through the interner, so equal names share one
+        // allocation; the string comparison is only a fallback.
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl Eq for PageId {}
+
+impl PartialEq<str> for PageId {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for PageId {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for PageId {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl Hash for PageId {
+    // Must hash like `str` so that `Borrow<str>` lookups in maps work.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl PartialOrd for PageId {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PageId {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl fmt::Debug for PageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for PageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl From<&str> for PageId {
+    fn from(name: &str) -> Self {
+        PageId::new(name)
+    }
+}
+
+impl From<String> for PageId {
+    fn from(name: String) -> Self {
+        PageId::new(&name)
+    }
+}
+
+impl From<&String> for PageId {
+    fn from(name: &String) -> Self {
+        PageId::new(name)
+    }
+}
+
+impl From<PageId> for String {
+    fn from(id: PageId) -> Self {
+        id.0.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_interning_shares_allocation() {
+        let a = PageId::new("project-a/standup");
+        let b = PageId::from("project-a/standup".to_string());
+        assert!(Arc::ptr_eq(&a.0, &b.0));
+        assert_eq!(a, b);
+        assert_ne!(a, PageId::new("project-b/standup"));
+    }
+
+    #[test]
+    fn test_compares_with_strings() {
+        let id = PageId::new("frontpage");
+        assert_eq!(id, "frontpage");
+        assert_eq!(id, "frontpage".to_string());
+        assert_eq!(id.as_str(), "frontpage");
+        assert_eq!(id.to_string(), "frontpage");
+        assert!(id.starts_with("front"));
+    }
+
+    #[test]
+    fn test_map_lookup_by_str() {
+        let mut map = HashMap::new();
+        map.insert(PageId::new("todo"), 3);
+        assert_eq!(map.get("todo"), Some(&3));
+    }
+}
@@ -0,0 +1,842 @@
+//! Export of a note to a paginated PDF.
+//!
+//! The note's Markdown is laid out with the typography of piki's display —
+//! Helvetica body text, larger bold headings, indented lists, quotes with a
+//! bar on the left, and shaded Courier code blocks — onto A4 pages with a
+//! page number at the bottom. Only the PDF standard fonts are used, so no font
+//! files need to be embedded; text is encoded as WinAnsi (Latin-1 plus common
+//! typographic punctuation), other characters are replaced by `?`.
+
+use std::fmt::Write as _;
+
+use crate::footnotes;
+
+const PAGE_W: f32 = 595.0;
+const PAGE_H: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+const BODY_SIZE: f32 = 11.0;
+const CODE_SIZE: f32 = 9.5;
+const LINE_SPACING: f32 = 1.4;
+const LIST_INDENT: f32 = 18.0;
+const QUOTE_INDENT: f32 = 14.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Font {
+    Regular,
+    Bold,
+    Italic,
+    Mono,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+            Font::Italic => "F3",
+            Font::Mono => "F4",
+        }
+    }
+}
+
+/// Base font names, in the order of their `F1`…`F4` resources.
+const BASE_FONTS: [&str; 4] = [
+    "Helvetica",
+    "Helvetica-Bold",
+    "Helvetica-Oblique",
+    "Courier",
+];
+
+/// Glyph widths (1/1000 em) of Helvetica for ASCII 32–126.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// Glyph widths (1/1000 em) of Helvetica-Bold for ASCII 32–126.
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+/// Map a character to its WinAnsiEncoding byte, if it has one.
+fn win_ansi(c: char) -> Option<u8> {
+    match c {
+        ' '..='~' => Some(c as u8),
+        '\u{a0}'..='\u{ff}' => Some(c as u32 as u8),
+        '€' => Some(0x80),
+        '‚' => Some(0x82),
+        '„' => Some(0x84),
+        '…' => Some(0x85),
+        '‘' => Some(0x91),
+        '’' => Some(0x92),
+        '“' => Some(0x93),
+        '”' => Some(0x94),
+        '•' => Some(0x95),
+        '–' => Some(0x96),
+        '—' => Some(0x97),
+        '™' => Some(0x99),
+        _ => None,
+    }
+}
+
+/// Width of `c` in 1/1000 em.
+fn glyph_width(c: char, font: Font) -> u16 {
+    if font == Font::Mono {
+        return 600;
+    }
+    if c.is_ascii() && !c.is_ascii_control() {
+        let index = c as usize - 32;
+        return match font {
+            Font::Bold => HELVETICA_BOLD_WIDTHS[index],
+            _ => HELVETICA_WIDTHS[index],
+        };
+    }
+    match c {
+        '…' | '—' | '€' | '™' => 1000,
+        '•' => 350,
+        '‘' | '’' | '‚' => 222,
+        '“' | '”' | '„' => 333,
+        _ => 556,
+    }
+}
+
+fn text_width(text: &str, font: Font, size: f32) -> f32 {
+    text.chars()
+        .map(|c| f32::from(glyph_width(c, font)))
+        .sum::<f32>()
+        * size
+        / 1000.0
+}
+
+/// A piece of text in a single font.
+#[derive(Debug, Clone, PartialEq)]
+struct Run {
+    text: String,
+    font: Font,
+}
+
+/// Split inline Markdown into runs: `**bold**`, `*italic*` / `_italic_`,
+/// `` `code` ``; links are reduced to their text.
+fn inline_runs(text: &str, base: Font) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    let mut push = |s: &str, font: Font| {
+        if s.is_empty() {
+            return;
+        }
+        match runs.last_mut() {
+            Some(last) if last.font == font => last.text.push_str(s),
+            _ => runs.push(Run {
+                text: s.to_string(),
+                font,
+            }),
+        }
+    };
+
+    let mut bold = false;
+    let mut italic = false;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let font = if bold {
+            Font::Bold
+        } else if italic {
+            Font::Italic
+        } else {
+            base
+        };
+
+        if c == '`'
+            && let Some(end) = rest[1..].find('`')
+        {
+            push(&rest[1..1 + end], Font::Mono);
+            rest = &rest[end + 2..];
+        } else if rest.starts_with("**") || rest.starts_with("__") {
+            bold = !bold;
+            rest = &rest[2..];
+        } else if rest.starts_with("~~") {
+            rest = &rest[2..];
+        } else if (c == '*' || c == '_') && (italic || rest[1..].contains(c)) {
+            italic = !italic;
+            rest = &rest[1..];
+        } else if let Some(inner) = rest.strip_prefix("[[")
+            && let Some(end) = inner.find("]]")
+        {
+            let target = &inner[..end];
+            push(target.split('|').next_back().unwrap_or(target), font);
+            rest = &inner[end + 2..];
+        } else if c == '['
+            && let Some(close) = rest.find("](")
+            && let Some(end) = rest[close..].find(')')
+        {
+            for run in inline_runs(&rest[1..close], font) {
+                push(&run.text, run.font);
+            }
+            rest = &rest[close + end + 1..];
+        } else {
+            push(&rest[..c.len_utf8()], font);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    runs
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Heading(usize, String),
+    Paragraph(String),
+    ListItem {
+        depth: usize,
+        marker: String,
+        text: String,
+    },
+    Quote {
+        depth: usize,
+        text: String,
+    },
+    Code(Vec<String>),
+    Rule,
+}
+
+fn list_item(line: &str) -> Option<(usize, String, String)> {
+    let trimmed = line.trim_start();
+    let depth = (line.len() - trimmed.len()) / 2;
+    let (marker, rest) = if let Some(rest) = trimmed
+        .strip_prefix("- ")
+        .or_else(|| trimmed.strip_prefix("* "))
+        .or_else(|| trimmed.strip_prefix("+ "))
+    {
+        ("•".to_string(), rest)
+    } else {
+        let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+        let rest = trimmed[digits..].strip_prefix(". ")?;
+        if digits == 0 {
+            return None;
+        }
+        (format!("{}.", &trimmed[..digits]), rest)
+    };
+    let (marker, rest) = if let Some(rest) = rest.strip_prefix("[ ] ") {
+        ("[  ]".to_string(), rest)
+    } else if let Some(rest) = rest
+        .strip_prefix("[x] ")
+        .or_else(|| rest.strip_prefix("[X] "))
+    {
+        ("[x]".to_string(), rest)
+    } else {
+        (marker, rest)
+    };
+    Some((depth, marker, rest.to_string()))
+}
+
+/// The nesting depth of a quote line (`> > text` is 2) and its text.
+fn quote_line(line: &str) -> Option<(usize, &str)> {
+    let mut rest = line.trim().strip_prefix('>')?;
+    let mut depth = 1;
+    while let Some(inner) = rest.trim_start().strip_prefix('>') {
+        rest = inner;
+        depth += 1;
+    }
+    Some((depth, rest.trim()))
+}
+
+/// Split Markdown into the blocks laid out by [`markdown_to_pdf`].
+fn parse_blocks(markdown: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut lines = markdown.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            let mut code = Vec::new();
+            for code_line in lines.by_ref() {
+                if code_line.trim().starts_with("```") || code_line.trim().starts_with("~~~") {
+                    break;
+                }
+                code.push(code_line.replace('\t', "    "));
+            }
+            blocks.push(Block::Code(code));
+        } else if trimmed.len() >= 3
+            && (trimmed.chars().all(|c| c == '-') || trimmed.chars().all(|c| c == '*'))
+        {
+            blocks.push(Block::Rule);
+        } else if let Some(level) = Some(trimmed.chars().take_while(|&c| c == '#').count())
+            .filter(|n| (1..=6).contains(n) && trimmed[*n..].starts_with(' '))
+        {
+            blocks.push(Block::Heading(level, trimmed[level..].trim().to_string()));
+        } else if let Some((depth, marker, text)) = list_item(line) {
+            blocks.push(Block::ListItem {
+                depth,
+                marker,
+                text,
+            });
+        } else if let Some((depth, quote)) = quote_line(line) {
+            // A bare `>` separates paragraphs within the quote.
+            if quote.is_empty() {
+                continue;
+            }
+            let mut text = quote.to_string();
+            while let Some((_, next)) = lines
+                .peek()
+                .and_then(|l| quote_line(l))
+                .filter(|(d, next)| *d == depth && !next.is_empty())
+            {
+                text.push(' ');
+                text.push_str(next);
+                lines.next();
+            }
+            blocks.push(Block::Quote { depth, text });
+        } else {
+            let mut text = trimmed.to_string();
+            while let Some(next) = lines.peek() {
+                let next = next.trim();
+                if next.is_empty()
+                    || next.starts_with('#')
+                    || next.starts_with('>')
+                    || next.starts_with("```")
+                    || list_item(next).is_some()
+                {
+                    break;
+                }
+                text.push(' ');
+                text.push_str(next);
+                lines.next();
+            }
+            blocks.push(Block::Paragraph(text));
+        }
+    }
+
+    blocks
+}
+
+/// Break `runs` into lines no wider than `max_width` points.
+fn wrap_runs(runs: &[Run], size: f32, max_width: f32) -> Vec<Vec<Run>> {
+    // Split into words (with their trailing space) so breaks fall between words.
+    let mut words: Vec<Run> = Vec::new();
+    for run in runs {
+        for piece in run.text.split_inclusive(' ') {
+            words.push(Run {
+                text: piece.to_string(),
+                font: run.font,
+            });
+        }
+    }
+
+    let mut lines: Vec<Vec<Run>> = Vec::new();
+    let mut line: Vec<Run> = Vec::new();
+    let mut width = 0.0;
+
+    for mut word in words {
+        let word_width = text_width(word.text.trim_end(), word.font, size);
+        if width + word_width > max_width && !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+            width = 0.0;
+        }
+        // A single word wider than the line is broken between characters.
+        while text_width(word.text.trim_end(), word.font, size) > max_width {
+            let mut fit = 0;
+            let mut w = 0.0;
+            for (i, c) in word.text.char_indices() {
+                w += f32::from(glyph_width(c, word.font)) * size / 1000.0;
+                if w > max_width && i > 0 {
+                    break;
+                }
+                fit = i + c.len_utf8();
+            }
+            lines.push(vec![Run {
+                text: word.text[..fit].to_string(),
+                font: word.font,
+            }]);
+            word.text = word.text[fit..].to_string();
+        }
+        width += text_width(&word.text, word.font, size);
+        match line.last_mut() {
+            Some(last) if last.font == word.font => last.text.push_str(&word.text),
+            _ => line.push(word),
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Op {
+    Text {
+        x: f32,
+        y: f32,
+        font: Font,
+        size: f32,
+        text: String,
+    },
+    Fill {
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        gray: f32,
+    },
+}
+
+/// Places content top to bottom, starting new pages as needed.
+struct Layout {
+    pages: Vec<Vec<Op>>,
+    y: f32,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Layout {
+            pages: vec![Vec::new()],
+            y: PAGE_H - MARGIN,
+        }
+    }
+
+    fn ops(&mut self) -> &mut Vec<Op> {
+        self.pages.last_mut().expect("layout always has a page")
+    }
+
+    /// Reserve `height` points, moving to a new page if they don't fit.
+    /// Returns the baseline y-coordinate of the reserved line.
+    fn line(&mut self, height: f32, size: f32) -> f32 {
+        if self.y - height < MARGIN && !self.ops().is_empty() {
+            self.pages.push(Vec::new());
+            self.y = PAGE_H - MARGIN;
+        }
+        self.y -= height;
+        // Baseline sits roughly one descender above the bottom of the line.
+        self.y + (height - size) / 2.0 + size * 0.22
+    }
+
+    fn space(&mut self, points: f32) {
+        if self.y < PAGE_H - MARGIN {
+            self.y -= points;
+        }
+    }
+
+    fn text_line(&mut self, x: f32, runs: &[Run], size: f32) -> f32 {
+        let baseline = self.line(size * LINE_SPACING, size);
+        let mut x = x;
+        for run in runs {
+            let size = if run.font == Font::Mono {
+                size * 0.9
+            } else {
+                size
+            };
+            self.ops().push(Op::Text {
+                x,
+                y: baseline,
+                font: run.font,
+                size,
+                text: run.text.clone(),
+            });
+            x += text_width(&run.text, run.font, size);
+        }
+        baseline
+    }
+
+    fn paragraph(&mut self, x: f32, runs: &[Run], size: f32) {
+        for line in wrap_runs(runs, size, PAGE_W - MARGIN - x) {
+            self.text_line(x, &line, size);
+        }
+    }
+}
+
+fn heading_size(level: usize) -> f32 {
+    match level {
+        1 => 20.0,
+        2 => 16.0,
+        3 => 13.0,
+        _ => BODY_SIZE,
+    }
+}
+
+/// Render `markdown` as a PDF document titled `title`. Footnotes become
+/// `[1]`-style markers with the notes listed at the end.
+pub fn markdown_to_pdf(title: &str, markdown: &str) -> Vec<u8> {
+    let mut layout = Layout::new();
+    let text_w = PAGE_W - 2.0 * MARGIN;
+    let markdown = footnotes::resolve(markdown, |n| format!("[{n}]")).to_markdown();
+
+    for block in parse_blocks(&markdown) {
+        match block {
+            Block::Heading(level, text) => {
+                layout.space(if level == 1 { 6.0 } else { 10.0 });
+                let size = heading_size(level);
+                layout.paragraph(MARGIN, &inline_runs(&text, Font::Bold), size);
+                layout.space(4.0);
+            }
+            Block::Paragraph(text) => {
+                layout.paragraph(MARGIN, &inline_runs(&text, Font::Regular), BODY_SIZE);
+                layout.space(6.0);
+            }
+            Block::ListItem {
+                depth,
+                marker,
+                text,
+            } => {
+                let x = MARGIN + LIST_INDENT * (depth as f32 + 1.0);
+                let lines = wrap_runs(
+                    &inline_runs(&text, Font::Regular),
+                    BODY_SIZE,
+                    PAGE_W - MARGIN - x,
+                );
+                for (i, line) in lines.iter().enumerate() {
+                    let baseline = layout.text_line(x, line, BODY_SIZE);
+                    if i == 0 {
+                        let marker_x = x - 4.0 - text_width(&marker, Font::Regular, BODY_SIZE);
+                        layout.ops().push(Op::Text {
+                            x: marker_x,
+                            y: baseline,
+                            font: Font::Regular,
+                            size: BODY_SIZE,
+                            text: marker.clone(),
+                        });
+                    }
+                }
+                layout.space(2.0);
+            }
+            Block::Quote { depth, text } => {
+                // One bar per level, so nested quotes stay recognizable.
+                let x = MARGIN + QUOTE_INDENT * depth as f32;
+                for line in wrap_runs(
+                    &inline_runs(&text, Font::Italic),
+                    BODY_SIZE,
+                    PAGE_W - MARGIN - x,
+                ) {
+                    let baseline = layout.text_line(x, &line, BODY_SIZE);
+                    let height = BODY_SIZE * LINE_SPACING;
+                    for level in 0..depth {
+                        layout.ops().push(Op::Fill {
+                            x: MARGIN + QUOTE_INDENT * level as f32,
+                            y: baseline - BODY_SIZE * 0.22 - (height - BODY_SIZE) / 2.0,
+                            w: 3.0,
+                            h: height,
+                            gray: 0.75,
+                        });
+                    }
+                }
+                layout.space(6.0);
+            }
+            Block::Code(lines) => {
+                let height = CODE_SIZE * LINE_SPACING;
+                for line in if lines.is_empty() {
+                    vec![String::new()]
+                } else {
+                    lines
+                } {
+                    let baseline = layout.line(height, CODE_SIZE);
+                    let ops = layout.ops();
+                    // The shading goes first so the text is drawn on top.
+                    ops.push(Op::Fill {
+                        x: MARGIN,
+                        y: baseline - CODE_SIZE * 0.22 - (height - CODE_SIZE) / 2.0,
+                        w: text_w,
+                        h: height,
+                        gray: 0.94,
+                    });
+                    ops.push(Op::Text {
+                        x: MARGIN + 6.0,
+                        y: baseline,
+                        font: Font::Mono,
+                        size: CODE_SIZE,
+                        text: line,
+                    });
+                }
+                layout.space(8.0);
+            }
+            Block::Rule => {
+                layout.space(6.0);
+                let y = layout.line(1.0, 0.0);
+                layout.ops().push(Op::Fill {
+                    x: MARGIN,
+                    y,
+                    w: text_w,
+                    h: 0.75,
+                    gray: 0.6,
+                });
+                layout.space(8.0);
+            }
+        }
+    }
+
+    write_pdf(title, &layout.pages)
+}
+
+fn encode_text(text: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(text.len() + 2);
+    out.push(b'(');
+    for c in text.chars() {
+        let byte = win_ansi(c).unwrap_or(b'?');
+        if matches!(byte, b'(' | b')' | b'\\') {
+            out.push(b'\\');
+        }
+        out.push(byte);
+    }
+    out.push(b')');
+    out
+}
+
+fn page_content(ops: &[Op], page: usize, page_count: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    let emit_text = |out: &mut Vec<u8>, x: f32, y: f32, font: Font, size: f32, text: &str| {
+        out.extend_from_slice(
+            format!(
+                "BT /{} {:.1} Tf {:.2} {:.2} Td ",
+                font.resource(),
+                size,
+                x,
+                y
+            )
+            .as_bytes(),
+        );
+        out.extend(encode_text(text));
+        out.extend_from_slice(b" Tj ET\n");
+    };
+
+    for op in ops {
+        match op {
+            Op::Text {
+                x,
+                y,
+                font,
+                size,
+                text,
+            } => emit_text(&mut out, *x, *y, *font, *size, text),
+            Op::Fill { x, y, w, h, gray } => out.extend_from_slice(
+                format!("{gray:.2} g {x:.2} {y:.2} {w:.2} {h:.2} re f 0 g\n").as_bytes(),
+            ),
+        }
+    }
+
+    let footer = format!("{} / {}", page + 1, page_count);
+    let size = 9.0;
+    let x = (PAGE_W - text_width(&footer, Font::Regular, size)) / 2.0;
+    out.extend_from_slice(b"0.5 g\n");
+    emit_text(&mut out, x, MARGIN / 2.0, Font::Regular, size, &footer);
+    out.extend_from_slice(b"0 g\n");
+    out
+}
+
+/// Serialize the laid out pages into a complete PDF file.
+fn write_pdf(title: &str, pages: &[Vec<Op>]) -> Vec<u8> {
+    // Object numbers: 1 catalog, 2 page tree, 3 info, 4..=7 fonts, then a
+    // page object and its content stream per page.
+    let first_page_obj = 4 + BASE_FONTS.len();
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", first_page_obj + 2 * i))
+        .collect();
+    objects.push(
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+    );
+    let mut info = b"<< /Title ".to_vec();
+    info.extend(encode_text(title));
+    info.extend_from_slice(b" /Producer (piki) >>");
+    objects.push(info);
+    for font in BASE_FONTS {
+        objects.push(
+            format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /{font} /Encoding /WinAnsiEncoding >>"
+            )
+            .into_bytes(),
+        );
+    }
+
+    let mut font_resources = String::new();
+    for (i, _) in BASE_FONTS.iter().enumerate() {
+        let _ = write!(font_resources, "/F{} {} 0 R ", i + 1, 4 + i);
+    }
+    for (i, ops) in pages.iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_W} {PAGE_H}] /Resources << /Font << {font_resources}>> >> /Contents {} 0 R >>",
+                first_page_obj + 2 * i + 1
+            )
+            .into_bytes(),
+        );
+        let content = page_content(ops, i, pages.len());
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend_from_slice(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut out = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        out.extend_from_slice(object);
+        out.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref = out.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(table, "{offset:010} 00000 n ");
+    }
+    let _ = write!(
+        table,
+        "trailer\n<< /Size {} /Root 1 0 R /Info 3 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    );
+    out.extend_from_slice(table.as_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn as_text(pdf: &[u8]) -> String {
+        String::from_utf8_lossy(pdf).into_owned()
+    }
+
+    #[test]
+    fn test_inline_runs() {
+        let runs = inline_runs(
+            "Plain **bold** and `code`, [a link](x) [[note]]",
+            Font::Regular,
+        );
+        let summary: Vec<(&str, Font)> = runs.iter().map(|r| (r.text.as_str(), r.font)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Plain ", Font::Regular),
+                ("bold", Font::Bold),
+                (" and ", Font::Regular),
+                ("code", Font::Mono),
+                (", a link note", Font::Regular),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_blocks() {
+        let md = "# Title\n\nSome text\ncontinued.\n\n- item\n  - [x] done\n1. first\n\n> quote\n\n```\nlet x = 1;\n```\n\n---\n";
+        assert_eq!(
+            parse_blocks(md),
+            vec![
+                Block::Heading(1, "Title".into()),
+                Block::Paragraph("Some text continued.".into()),
+                Block::ListItem {
+                    depth: 0,
+                    marker: "•".into(),
+                    text: "item".into()
+                },
+                Block::ListItem {
+                    depth: 1,
+                    marker: "[x]".into(),
+                    text: "done".into()
+                },
+                Block::ListItem {
+                    depth: 0,
+                    marker: "1.".into(),
+                    text: "first".into()
+                },
+                Block::Quote {
+                    depth: 1,
+                    text: "quote".into()
+                },
+                Block::Code(vec!["let x = 1;".into()]),
+                Block::Rule,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_quotes() {
+        let md = "> outer\n> > inner\n>> still inner\n>\n> outer again\n";
+        assert_eq!(
+            parse_blocks(md),
+            vec![
+                Block::Quote {
+                    depth: 1,
+                    text: "outer".into()
+                },
+                Block::Quote {
+                    depth: 2,
+                    text: "inner still inner".into()
+                },
+                Block::Quote {
+                    depth: 1,
+                    text: "outer again".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrap_runs_respects_width() {
+        let runs = inline_runs(&"lorem ipsum ".repeat(40), Font::Regular);
+        let lines = wrap_runs(&runs, BODY_SIZE, 200.0);
+        assert!(lines.len() > 5);
+        for line in &lines {
+            let width: f32 = line
+                .iter()
+                .map(|r| text_width(r.text.trim_end(), r.font, BODY_SIZE))
+                .sum();
+            assert!(width <= 200.0, "line too wide: {width}");
+        }
+    }
+
+    #[test]
+    fn test_long_note_is_paginated() {
+        let md = "Paragraph of text.\n\n".repeat(200);
+        let pdf = as_text(&markdown_to_pdf("long", &md));
+        assert!(pdf.starts_with("%PDF-1.4"));
+        let count: usize = pdf
+            .split("/Count ")
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .and_then(|n| n.parse().ok())
+            .unwrap();
+        assert!(count > 1);
+        assert!(pdf.contains(&format!("(1 / {count}) Tj")));
+    }
+
+    #[test]
+    fn test_xref_points_at_objects() {
+        let pdf = markdown_to_pdf("Note (draft)", "# Hello\n\nWorld — “quoted”\n");
+        let text = as_text(&pdf);
+        assert!(text.contains("/Title (Note \\(draft\\))"));
+        let startxref: usize = text
+            .rsplit("startxref\n")
+            .next()
+            .and_then(|rest| rest.lines().next())
+            .and_then(|n| n.parse().ok())
+            .unwrap();
+        assert!(pdf[startxref..].starts_with(b"xref"));
+        // Every listed offset starts the corresponding object.
+        let table = &text[startxref..];
+        for (i, entry) in table
+            .lines()
+            .skip(3)
+            .take_while(|l| l.ends_with(" n "))
+            .enumerate()
+        {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(format!("{} 0 obj", i + 1).as_bytes()));
+        }
+        // WinAnsi encoding of typographic punctuation.
+        assert!(pdf.windows(3).any(|w| w == [0x97, b' ', 0x93]));
+    }
+}
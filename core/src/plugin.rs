@@ -1,12 +1,24 @@
 #![allow(dead_code)]
 
+use crate::dates::{Date, due_by, find_due_dates};
 use crate::document::DocumentStore;
+use crate::link_index;
+use crate::todo_cache::{self, NoteTodos};
 use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
 
 /// Trait for plugins that dynamically generate note content
 pub trait Plugin: Send + Sync {
-    /// Generate content for this plugin based on the current wiki state
-    fn generate_content(&self, store: &DocumentStore) -> Result<String, String>;
+    /// Generate content for this plugin based on the current wiki state.
+    /// `args` is whatever follows the plugin's name and a `:` in the page
+    /// name (`open` for `!todo:open`), or empty.
+    fn generate_content(&self, store: &DocumentStore, args: &str) -> Result<String, String>;
+}
+
+/// Split a plugin page name (without the `!`) into the plugin's name and its
+/// arguments: `todo:open` is `("todo", "open")`, `index` is `("index", "")`.
+pub fn split_plugin_args(page: &str) -> (&str, &str) {
+    page.split_once(':').unwrap_or((page, ""))
 }
 
 /// Registry for managing wiki plugins
@@ -27,17 +39,27 @@ impl PluginRegistry {
         self.plugins.insert(name.into(), plugin);
     }
 
-    /// Check if a plugin exists with the given name
+    /// Check if a plugin exists with the given name, which may carry
+    /// arguments (`todo:open`)
     pub fn has_plugin(&self, name: &str) -> bool {
-        self.plugins.contains_key(name)
+        self.plugins.contains_key(split_plugin_args(name).0)
+    }
+
+    /// Names of all registered plugins, sorted
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.plugins.keys().cloned().collect();
+        names.sort();
+        names
     }
 
-    /// Generate content using the named plugin
+    /// Generate content using the named plugin, passing on the arguments
+    /// after a `:` in `name` (see [`split_plugin_args`])
     pub fn generate(&self, name: &str, store: &DocumentStore) -> Result<String, String> {
+        let (name, args) = split_plugin_args(name);
         self.plugins
             .get(name)
             .ok_or_else(|| format!("Plugin '{}' not found", name))
-            .and_then(|plugin| plugin.generate_content(store))
+            .and_then(|plugin| plugin.generate_content(store, args))
     }
 }
 
@@ -47,15 +69,69 @@ impl Default for PluginRegistry {
     }
 }
 
-/// Built-in plugin that generates a sorted index of all notes
+/// Bring the caches behind the `!todo` page and the link graph (the
+/// [`todo_cache`] and the [`link_index`]) up to date, reading every note that
+/// changed since they were last written. Meant for a background thread right
+/// after startup, so that the first visit of those pages doesn't wait for it.
+pub fn warm_up(store: &DocumentStore) {
+    // Both are only caches: what fails here is tried again when needed.
+    let _ = todo_cache::scan(store);
+    let _ = link_index::scan(store);
+}
+
+/// How [`IndexPlugin`] orders the notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexOrder {
+    /// Alphabetically, under a heading for each top-level folder.
+    #[default]
+    ByFolder,
+    /// Alphabetically, in one list.
+    ByName,
+    /// Most recently modified first, with the date of the last change.
+    ByDate,
+}
+
+impl IndexOrder {
+    /// The order named `by-folder`, `by-name` or `by-date` (the `by-` may be
+    /// left out).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().trim_start_matches("by-") {
+            "folder" => Some(IndexOrder::ByFolder),
+            "name" => Some(IndexOrder::ByName),
+            "date" => Some(IndexOrder::ByDate),
+            _ => None,
+        }
+    }
+}
+
+/// Built-in plugin that generates a sorted index of all notes, grouped by
+/// folder.
+///
+/// Arguments, separated by commas, change what is listed: `by-name` lists all
+/// notes alphabetically in one list, `by-date` the most recently changed
+/// first, and anything else keeps only the notes in that folder
+/// (`!index:by-date,projects/`).
 pub struct IndexPlugin;
 
 impl Plugin for IndexPlugin {
-    fn generate_content(&self, store: &DocumentStore) -> Result<String, String> {
+    fn generate_content(&self, store: &DocumentStore, args: &str) -> Result<String, String> {
+        let mut order = IndexOrder::default();
+        let mut folder = String::new();
+        for arg in args.split(',').map(str::trim).filter(|arg| !arg.is_empty()) {
+            match IndexOrder::parse(arg) {
+                Some(by) => order = by,
+                None => folder = folder_prefix(arg),
+            }
+        }
         let mut all_docs = store.list_all_documents()?;
+        all_docs.retain(|doc| doc.starts_with(&folder));
         all_docs.sort();
 
-        let mut content = String::from("# Index\n\n");
+        let mut content = if folder.is_empty() {
+            String::from("# Index\n\n")
+        } else {
+            format!("# Index of {folder}\n\n")
+        };
         content.push_str(&format!(
             "*Dynamically generated index of all {} notes*\n\n",
             all_docs.len()
@@ -66,6 +142,31 @@ impl Plugin for IndexPlugin {
             return Ok(content);
         }
 
+        match order {
+            IndexOrder::ByFolder => {}
+            IndexOrder::ByName => {
+                for doc in &all_docs {
+                    content.push_str(&format!("- [[{}]]\n", doc));
+                }
+                return Ok(index_footer(content));
+            }
+            IndexOrder::ByDate => {
+                let mut dated: Vec<_> = all_docs
+                    .into_iter()
+                    .map(|doc| (store.modified_time(&doc), doc))
+                    .collect();
+                // Newest first; notes with the same time stay alphabetical.
+                dated.sort_by(|(a, _), (b, _)| b.cmp(a));
+                for (modified, doc) in dated {
+                    match modified.and_then(modified_date) {
+                        Some(date) => content.push_str(&format!("- [[{}]] — {}\n", doc, date)),
+                        None => content.push_str(&format!("- [[{}]]\n", doc)),
+                    }
+                }
+                return Ok(index_footer(content));
+            }
+        }
+
         // Group by top-level directory
         let mut grouped: HashMap<String, Vec<String>> = HashMap::new();
 
@@ -110,53 +211,175 @@ impl Plugin for IndexPlugin {
             }
         }
 
-        content.push_str("---\n\n");
-        content.push_str("*This note is generated by the `index` plugin*\n");
+        Ok(index_footer(content))
+    }
+}
 
-        Ok(content)
+fn index_footer(mut content: String) -> String {
+    if !content.ends_with("\n\n") {
+        content.push('\n');
     }
+    content.push_str("---\n\n");
+    content.push_str("*This note is generated by the `index` plugin*\n");
+    content
 }
 
-/// Built-in plugin that lists all todos found in notes, grouped by note
-pub struct TodoPlugin;
+/// The (UTC) day of a file modification time.
+fn modified_date(time: std::time::SystemTime) -> Option<Date> {
+    let secs = time.duration_since(std::time::UNIX_EPOCH).ok()?.as_secs();
+    Some(Date::from_ymd(1970, 1, 1)?.add_days((secs / 86_400) as i64))
+}
 
-impl Plugin for TodoPlugin {
-    fn generate_content(&self, store: &DocumentStore) -> Result<String, String> {
-        let all_docs = store.list_all_documents()?;
+/// Built-in plugin that lists all todos found in notes, grouped by note, below
+/// a count of the open and done ones. Each todo links back to its line in the
+/// note (`note#L12`). Todos with a due date come first in their note, soonest
+/// first, and open ones past their due date are flagged as overdue.
+///
+/// Arguments, separated by commas, narrow the list (see [`TodoFilter`]):
+/// `open` or `done` keep only unchecked or checked todos, `due=RANGE` only
+/// those due within a [`due_by`] range (`due=this-week`), `page=GLOB` only
+/// the notes matching a [glob](crate::listing::glob_matches), `tag=TAG` only
+/// the todos carrying that tag or in a note that does outside its todos,
+/// anything else keeps only the notes in that folder (`!todo:open,projects/`).
+pub struct TodoPlugin;
 
-        let mut content = String::from("# Todos\n\n");
-        content.push_str("*All todos found across your wiki*\n\n");
+/// Which todos the `todo` plugin lists, from its arguments.
+#[derive(Debug, Default, PartialEq)]
+pub struct TodoFilter {
+    /// Only the checked (`true`) or unchecked (`false`) ones
+    pub checked: Option<bool>,
+    /// Only those due on or before this date
+    pub due: Option<Date>,
+    /// Only those in notes in these folders (as from [`folder_prefix`])
+    pub folders: Vec<String>,
+    /// Only those in notes matching this glob
+    pub page: Option<String>,
+    /// Only those carrying this tag, or in a note that does
+    pub tag: Option<String>,
+}
 
-        let mut notes_with_todos = Vec::new();
+impl TodoFilter {
+    /// The filter the `todo` plugin's `args` ask for, with due date ranges
+    /// counted from `today`.
+    pub fn parse(args: &str, today: Option<Date>) -> Result<Self, String> {
+        let mut filter = TodoFilter::default();
+        for arg in args.split(',').map(str::trim).filter(|arg| !arg.is_empty()) {
+            match arg {
+                "open" => filter.checked = Some(false),
+                "done" => filter.checked = Some(true),
+                _ if arg.starts_with("due=") => {
+                    let range = &arg["due=".len()..];
+                    filter.due = Some(
+                        today
+                            .and_then(|today| due_by(range, today))
+                            .ok_or_else(|| format!("Unknown due date range '{range}'"))?,
+                    );
+                }
+                _ if arg.starts_with("page=") => filter.page = Some(arg[5..].to_string()),
+                _ if arg.starts_with("tag=") => {
+                    filter.tag = Some(arg[4..].trim_start_matches('#').to_string())
+                }
+                folder => filter.folders.push(folder_prefix(folder)),
+            }
+        }
+        Ok(filter)
+    }
 
-        // Scan each note for todos
-        for doc_name in &all_docs {
-            match store.load(doc_name) {
-                Ok(doc) => {
-                    let todos = extract_todos(&doc.content);
-                    if !todos.is_empty() {
-                        notes_with_todos.push((doc_name.clone(), todos));
-                    }
+    /// Keep only the todos of `notes` the filter lets through, and the notes
+    /// that still have some.
+    pub fn apply(&self, store: &DocumentStore, notes: &mut Vec<NoteTodos>) {
+        if !self.folders.is_empty() {
+            notes.retain(|(note, _)| self.folders.iter().any(|folder| note.starts_with(folder)));
+        }
+        if let Some(page) = &self.page {
+            notes.retain(|(note, _)| crate::listing::glob_matches(page, note));
+        }
+        if let Some(checked) = self.checked {
+            for (_, todos) in notes.iter_mut() {
+                todos.retain(|(_, todo)| is_checked(todo) == checked);
+            }
+        }
+        if let Some(tag) = &self.tag {
+            for (note, todos) in notes.iter_mut() {
+                // A tag on one todo doesn't make the others carry it.
+                let page_tagged = store.load(note).is_ok_and(|doc| {
+                    let lines = todos.iter().map(|(line, _)| *line).collect::<Vec<_>>();
+                    let rest = doc
+                        .content
+                        .lines()
+                        .enumerate()
+                        .filter(|(index, _)| !lines.contains(&(index + 1)))
+                        .map(|(_, line)| line)
+                        .collect::<Vec<_>>();
+                    crate::tags::page_has_tag(&rest.join("\n"), tag)
+                });
+                if !page_tagged {
+                    todos.retain(|(_, todo)| crate::tags::has_tag(todo, tag));
                 }
-                Err(_) => continue, // Skip notes that can't be loaded
             }
         }
+        notes.retain(|(_, todos)| !todos.is_empty());
+        if let Some(until) = self.due {
+            retain_due(notes, until);
+        }
+    }
+}
+
+impl Plugin for TodoPlugin {
+    fn generate_content(&self, store: &DocumentStore, args: &str) -> Result<String, String> {
+        let today = today();
+        let filter = TodoFilter::parse(args, today)?;
+        let checked = filter.checked;
+        let mut notes_with_todos = todo_cache::scan(store)?;
+        filter.apply(store, &mut notes_with_todos);
+        for (_, todos) in notes_with_todos.iter_mut() {
+            todos.sort_by_key(|(_, todo)| {
+                let due = todo_due_date(todo);
+                (due.is_none(), due)
+            });
+        }
+
+        let mut content = String::from("# Todos\n\n");
+        content.push_str(&format!(
+            "*{} found across your wiki*\n\n",
+            match checked {
+                None => "All todos",
+                Some(false) => "Open todos",
+                Some(true) => "Done todos",
+            }
+        ));
 
         if notes_with_todos.is_empty() {
             content.push_str("No todos found in any notes.\n");
             return Ok(content);
         }
 
-        // Sort notes alphabetically
-        notes_with_todos.sort_by(|a, b| a.0.cmp(&b.0));
-
         let note_count = notes_with_todos.len();
+        let done = notes_with_todos
+            .iter()
+            .flat_map(|(_, todos)| todos)
+            .filter(|(_, todo)| is_checked(todo))
+            .count();
+        let total = notes_with_todos
+            .iter()
+            .map(|(_, todos)| todos.len())
+            .sum::<usize>();
+        content.push_str(&format!("**{} open, {done} done**\n\n", total - done));
 
         // Display todos grouped by note
         for (note_name, todos) in notes_with_todos {
             content.push_str(&format!("## [[{}]]\n\n", note_name));
-            for todo in todos {
-                content.push_str(&format!("{}\n", todo));
+            for (line, todo) in todos {
+                let overdue = !is_checked(&todo)
+                    && todo_due_date(&todo)
+                        .zip(today)
+                        .is_some_and(|(due, today)| due < today);
+                content.push_str(&format!(
+                    "{}{} ([line {line}]({}))\n",
+                    todo.trim_end(),
+                    if overdue { " **overdue**" } else { "" },
+                    line_link(&note_name, line)
+                ));
             }
             content.push('\n');
         }
@@ -169,11 +392,121 @@ impl Plugin for TodoPlugin {
     }
 }
 
-/// Extract todo items from markdown content
-fn extract_todos(content: &str) -> Vec<String> {
+/// Built-in plugin that shows a randomly picked note, with a link to pick
+/// another, for stumbling upon old notes again. With a folder as argument
+/// (`!random:journal/`) the note is picked from that folder.
+pub struct RandomPlugin;
+
+impl Plugin for RandomPlugin {
+    fn generate_content(&self, store: &DocumentStore, args: &str) -> Result<String, String> {
+        let Some(name) = random_note(store, args)? else {
+            return Ok("# Random note\n\nNo notes found.\n".to_string());
+        };
+        let doc = store.load(&name)?;
+        let again = if args.is_empty() {
+            "!random".to_string()
+        } else {
+            format!("!random:{args}")
+        };
+        Ok(format!(
+            "*Picked at random: [[{name}]] · [Pick another]({})*\n\n---\n\n{}",
+            link_target(&again),
+            doc.content
+        ))
+    }
+}
+
+/// A note picked at random from those in `folder` (all notes if empty), or
+/// `None` if there are none.
+pub fn random_note(store: &DocumentStore, folder: &str) -> Result<Option<String>, String> {
+    let folder = folder_prefix(folder);
+    let mut notes = store.list_all_documents()?;
+    notes.retain(|note| note.starts_with(&folder));
+    if notes.is_empty() {
+        return Ok(None);
+    }
+    // Hashers are seeded randomly per process and per instance, which is all
+    // the randomness picking a note needs.
+    let seed = RandomState::new().hash_one(std::time::SystemTime::now());
+    let index = (seed % notes.len() as u64) as usize;
+    Ok(Some(notes.swap_remove(index)))
+}
+
+/// Today's (UTC) date.
+fn today() -> Option<Date> {
+    modified_date(std::time::SystemTime::now())
+}
+
+/// The due date of a todo line: its first `@due(…)` or `📅` annotation.
+pub fn todo_due_date(todo: &str) -> Option<Date> {
+    find_due_dates(todo).first().map(|&(_, due)| due)
+}
+
+/// Keep only the todos due on or before `until`, and the notes that still
+/// have some.
+pub fn retain_due(notes: &mut Vec<NoteTodos>, until: Date) {
+    for (_, todos) in notes.iter_mut() {
+        todos.retain(|(_, todo)| todo_due_date(todo).is_some_and(|due| due <= until));
+    }
+    notes.retain(|(_, todos)| !todos.is_empty());
+}
+
+/// A link destination for line `line` of `note`.
+fn line_link(note: &str, line: usize) -> String {
+    link_target(&format!("{note}#L{line}"))
+}
+
+/// `target` as a Markdown link destination, in angle brackets if it would end
+/// a bare destination early.
+pub(crate) fn link_target(target: &str) -> String {
+    if target.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+        format!("<{target}>")
+    } else {
+        target.to_string()
+    }
+}
+
+/// `args` as a folder to match note names against: `projects` and
+/// `projects/` both become `projects/`, empty stays empty.
+pub(crate) fn folder_prefix(args: &str) -> String {
+    let folder = args.trim().trim_matches('/');
+    if folder.is_empty() {
+        String::new()
+    } else {
+        format!("{folder}/")
+    }
+}
+
+/// Whether a todo line from [`extract_todos`] is checked.
+pub(crate) fn is_checked(todo: &str) -> bool {
+    let trimmed = todo.trim_start();
+    trimmed[1..].starts_with(" [x]") || trimmed[1..].starts_with(" [X]")
+}
+
+/// `content` with the todo on line `line` (1-based) ticked if it is open and
+/// unticked if it is done, or `None` if there is no todo on that line.
+pub fn toggle_todo(content: &str, line: usize) -> Option<String> {
+    let mut lines = content.split_inclusive('\n').collect::<Vec<_>>();
+    let todo = *lines.get(line.checked_sub(1)?)?;
+    if extract_todos(todo).is_empty() {
+        return None;
+    }
+    let mark = todo.find('[')? + 1;
+    let toggled = format!(
+        "{}{}{}",
+        &todo[..mark],
+        if is_checked(todo) { ' ' } else { 'x' },
+        &todo[mark + 1..]
+    );
+    lines[line - 1] = &toggled;
+    Some(lines.concat())
+}
+
+/// Extract todo items from markdown content, with their (1-based) line numbers
+pub(crate) fn extract_todos(content: &str) -> Vec<(usize, String)> {
     let mut todos = Vec::new();
 
-    for line in content.lines() {
+    for (index, line) in content.lines().enumerate() {
         let trimmed = line.trim();
         // Match both unchecked [ ] and checked [x] or [X] todos
         if trimmed.starts_with("- [ ]")
@@ -183,7 +516,7 @@ fn extract_todos(content: &str) -> Vec<String> {
             || trimmed.starts_with("* [x]")
             || trimmed.starts_with("* [X]")
         {
-            todos.push(line.to_string());
+            todos.push((index + 1, line.to_string()));
         }
     }
 
@@ -205,6 +538,26 @@ mod tests {
 
         assert!(registry.has_plugin("index"));
         assert!(!registry.has_plugin("nonexistent"));
+
+        registry.register("todo", Box::new(TodoPlugin));
+        assert_eq!(registry.names(), vec!["index", "todo"]);
+    }
+
+    #[test]
+    fn test_warm_up_writes_the_caches() {
+        use std::env;
+        use std::fs;
+
+        let temp_dir = env::temp_dir().join("piki-test-plugin-warm-up");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+        fs::write(temp_dir.join("plan.md"), "- [ ] Call [[bob]]\n").unwrap();
+
+        warm_up(&DocumentStore::new(temp_dir.clone()));
+        assert!(temp_dir.join(".piki/todo-cache.tsv").is_file());
+        assert!(temp_dir.join(".piki/link-index.tsv").is_file());
+
+        fs::remove_dir_all(&temp_dir).ok();
     }
 
     #[test]
@@ -220,7 +573,7 @@ mod tests {
         let plugin = IndexPlugin;
 
         // Should handle empty directory gracefully
-        let result = plugin.generate_content(&store);
+        let result = plugin.generate_content(&store, "");
         assert!(result.is_ok());
 
         let content = result.unwrap();
@@ -236,7 +589,7 @@ mod tests {
         let store = DocumentStore::new(PathBuf::from("example-wiki"));
         let plugin = IndexPlugin;
 
-        let content = plugin.generate_content(&store).unwrap();
+        let content = plugin.generate_content(&store, "").unwrap();
 
         // Should contain header
         assert!(content.contains("# Index"));
@@ -265,13 +618,31 @@ Some text here.
         let todos = extract_todos(content);
 
         assert_eq!(todos.len(), 7);
-        assert!(todos[0].contains("[ ] Unchecked todo"));
-        assert!(todos[1].contains("[x] Checked todo"));
-        assert!(todos[2].contains("[X] Checked todo uppercase"));
-        assert!(todos[3].contains("[ ] Unchecked with asterisk"));
-        assert!(todos[4].contains("[x] Checked with asterisk"));
-        assert!(todos[5].contains("[ ] Indented todo"));
-        assert!(todos[6].contains("[ ] Another todo"));
+        assert!(todos[0].1.contains("[ ] Unchecked todo"));
+        assert!(todos[1].1.contains("[x] Checked todo"));
+        assert!(todos[2].1.contains("[X] Checked todo uppercase"));
+        assert!(todos[3].1.contains("[ ] Unchecked with asterisk"));
+        assert!(todos[4].1.contains("[x] Checked with asterisk"));
+        assert!(todos[5].1.contains("[ ] Indented todo"));
+        assert!(todos[6].1.contains("[ ] Another todo"));
+        assert_eq!(todos[0].0, 4);
+        assert_eq!(todos[6].0, 14);
+    }
+
+    #[test]
+    fn test_toggle_todo() {
+        let content = "# Plan\n- [ ] Budget\n  * [X] Hire\n";
+        assert_eq!(
+            toggle_todo(content, 2).as_deref(),
+            Some("# Plan\n- [x] Budget\n  * [X] Hire\n")
+        );
+        assert_eq!(
+            toggle_todo(content, 3).as_deref(),
+            Some("# Plan\n- [ ] Budget\n  * [ ] Hire\n")
+        );
+        assert_eq!(toggle_todo(content, 1), None);
+        assert_eq!(toggle_todo(content, 0), None);
+        assert_eq!(toggle_todo(content, 4), None);
     }
 
     #[test]
@@ -286,7 +657,7 @@ Some text here.
         let store = DocumentStore::new(temp_dir.clone());
         let plugin = TodoPlugin;
 
-        let result = plugin.generate_content(&store);
+        let result = plugin.generate_content(&store, "");
         assert!(result.is_ok());
 
         let content = result.unwrap();
@@ -326,16 +697,162 @@ Some text here.
         store.save(&doc2).unwrap();
 
         let plugin = TodoPlugin;
-        let content = plugin.generate_content(&store).unwrap();
+        let content = plugin.generate_content(&store, "").unwrap();
 
         // Verify structure
         assert!(content.contains("# Todos"));
         assert!(content.contains("[[project]]"));
         assert!(content.contains("[[shopping]]"));
-        assert!(content.contains("- [ ] Buy milk"));
-        assert!(content.contains("- [x] Get eggs"));
+        assert!(content.contains("- [ ] Buy milk ([line 2](shopping#L2))\n"));
+        assert!(content.contains("- [x] Get eggs ([line 3](shopping#L3))\n"));
         assert!(content.contains("- [ ] Task 1"));
         assert!(content.contains("Found 2 notes with todos"));
+        assert!(content.contains("*\n\n**3 open, 1 done**\n\n## "));
+
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn test_plugin_args() {
+        use std::env;
+        use std::fs;
+
+        let temp_dir = env::temp_dir().join("piki-test-plugin-args");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(temp_dir.join("projects")).unwrap();
+        fs::write(temp_dir.join("inbox.md"), "- [ ] Call Sam\n").unwrap();
+        fs::write(
+            temp_dir.join("projects/plan.md"),
+            "- [ ] Draft\n- [x] Kickoff\n",
+        )
+        .unwrap();
+
+        let store = DocumentStore::new(temp_dir.clone());
+        let mut registry = PluginRegistry::new();
+        registry.register("index", Box::new(IndexPlugin));
+        registry.register("todo", Box::new(TodoPlugin));
+        assert!(registry.has_plugin("todo:open"));
+
+        let index = registry.generate("index:projects", &store).unwrap();
+        assert!(index.starts_with("# Index of projects/\n"));
+        assert!(index.contains("[[projects/plan]]"));
+        assert!(!index.contains("[[inbox]]"));
+
+        let by_name = registry.generate("index:by-name", &store).unwrap();
+        assert!(by_name.contains("notes*\n\n- [[inbox]]\n- [[projects/plan]]\n\n---"));
+        assert!(!by_name.contains("## "));
+
+        let old = std::time::SystemTime::now() - std::time::Duration::from_secs(86_400 * 3);
+        fs::File::options()
+            .write(true)
+            .open(temp_dir.join("inbox.md"))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        let by_date = registry.generate("index:by-date", &store).unwrap();
+        let plan = by_date.find("[[projects/plan]] — ").unwrap();
+        assert!(plan < by_date.find("[[inbox]] — ").unwrap());
+        let in_folder = registry.generate("index:date,projects", &store).unwrap();
+        assert!(in_folder.contains("[[projects/plan]] — "));
+        assert!(!in_folder.contains("[[inbox]]"));
+        assert_eq!(IndexOrder::parse("by-folder"), Some(IndexOrder::ByFolder));
+        assert_eq!(IndexOrder::parse("size"), None);
+
+        let open = registry.generate("todo:open", &store).unwrap();
+        assert!(open.contains("- [ ] Call Sam"));
+        assert!(open.contains("- [ ] Draft"));
+        assert!(!open.contains("Kickoff"));
+        assert!(open.contains("**2 open, 0 done**"));
+
+        fs::write(
+            temp_dir.join("taxes.md"),
+            "- [ ] Someday\n- [ ] File @due(2999-04-30)\n- [ ] Pay 📅 2000-01-15\n- [x] Sent @due(2000-01-01)\n",
+        )
+        .unwrap();
+        let due = registry.generate("todo:due=2999-05-01", &store).unwrap();
+        assert!(due.contains(
+            "## [[taxes]]\n\n- [x] Sent @due(2000-01-01) ([line 4](taxes#L4))\n- [ ] Pay 📅 2000-01-15 **overdue** ([line 3](taxes#L3))\n- [ ] File @due(2999-04-30) ([line 2](taxes#L2))\n\n"
+        ));
+        assert!(!due.contains("Someday"));
+        assert!(!due.contains("Draft"));
+        let overdue = registry.generate("todo:open,due=overdue", &store).unwrap();
+        assert!(overdue.contains("Pay"));
+        assert!(!overdue.contains("File"));
+        assert!(registry.generate("todo:due=someday", &store).is_err());
+        fs::remove_file(temp_dir.join("taxes.md")).unwrap();
+
+        let done = registry.generate("todo:done, projects/", &store).unwrap();
+        assert!(done.contains("- [x] Kickoff"));
+        assert!(!done.contains("Draft"));
+        assert!(!done.contains("Call Sam"));
+
+        fs::write(
+            temp_dir.join("projects/trip.md"),
+            "---\ntags: [travel]\n---\n- [ ] Book hotel\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.join("inbox.md"),
+            "- [ ] Call Sam\n- [ ] Pack #travel\n",
+        )
+        .unwrap();
+        let travel = registry.generate("todo:tag=#travel", &store).unwrap();
+        assert!(travel.contains("Book hotel"));
+        assert!(travel.contains("Pack #travel"));
+        assert!(!travel.contains("Call Sam"));
+        let page = registry
+            .generate("todo:open,page=projects/p*", &store)
+            .unwrap();
+        assert!(page.contains("Draft"));
+        assert!(!page.contains("Book hotel"));
+        assert!(!page.contains("Call Sam"));
+        assert_eq!(
+            TodoFilter::parse("done,page=plan*,tag=work,inbox", None),
+            Ok(TodoFilter {
+                checked: Some(true),
+                page: Some("plan*".to_string()),
+                tag: Some("work".to_string()),
+                folders: vec!["inbox/".to_string()],
+                ..TodoFilter::default()
+            })
+        );
+
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn test_random_plugin() {
+        use std::env;
+        use std::fs;
+
+        let temp_dir = env::temp_dir().join("piki-test-plugin-random");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(temp_dir.join("journal")).unwrap();
+        let store = DocumentStore::new(temp_dir.clone());
+        assert_eq!(random_note(&store, "").unwrap(), None);
+        assert!(
+            RandomPlugin
+                .generate_content(&store, "")
+                .unwrap()
+                .contains("No notes found")
+        );
+
+        fs::create_dir_all(temp_dir.join("ideas")).unwrap();
+        fs::write(temp_dir.join("ideas/garden.md"), "# Garden\n").unwrap();
+        fs::write(temp_dir.join("journal/monday.md"), "# Monday\n").unwrap();
+        fs::write(temp_dir.join("journal/tuesday.md"), "# Tuesday\n").unwrap();
+        for _ in 0..10 {
+            let note = random_note(&store, "journal").unwrap().unwrap();
+            assert!(note.starts_with("journal/"), "{note}");
+        }
+
+        let content = RandomPlugin.generate_content(&store, "").unwrap();
+        assert!(content.contains("[Pick another](!random)"));
+        let content = RandomPlugin.generate_content(&store, "ideas/").unwrap();
+        assert_eq!(
+            content,
+            "*Picked at random: [[ideas/garden]] · [Pick another](!random:ideas/)*\n\n---\n\n# Garden\n"
+        );
 
         fs::remove_dir_all(&temp_dir).ok();
     }
@@ -0,0 +1,279 @@
+//! Redaction of private material before notes are published.
+//!
+//! A wiki often mixes public and private notes. Before a note is shown to
+//! anyone else it is passed through [`redact`], which
+//!
+//! - drops the whole note if it carries one of the excluded tags, in its text
+//!   or its frontmatter,
+//! - removes everything between two `%%private%%` lines (an unclosed fence
+//!   hides the rest of the note), and
+//! - removes every block tagged with the private tag (`#private` by default):
+//!   a paragraph, a single list item together with its nested items, or — for
+//!   a tagged heading — the whole section below it.
+
+use crate::tags::{has_tag, is_code_fence, page_has_tag};
+
+/// Fence line that starts and ends a private region.
+pub const PRIVATE_FENCE: &str = "%%private%%";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactionRules {
+    /// Notes carrying any of these tags are not published at all.
+    pub exclude_tags: Vec<String>,
+    /// Blocks carrying this tag are removed from published notes.
+    pub private_tag: String,
+}
+
+impl Default for RedactionRules {
+    fn default() -> Self {
+        RedactionRules {
+            exclude_tags: Vec::new(),
+            private_tag: "private".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Blank,
+    Heading(usize),
+    ListItem(usize),
+    Other,
+}
+
+struct Block<'a> {
+    kind: BlockKind,
+    lines: Vec<&'a str>,
+    /// Fenced code is never treated as tagged.
+    code: bool,
+}
+
+/// Apply `rules` to a note's markdown. Returns `None` if the note must not be
+/// published at all.
+pub fn redact(content: &str, rules: &RedactionRules) -> Option<String> {
+    if rules
+        .exclude_tags
+        .iter()
+        .any(|tag| page_has_tag(content, tag))
+    {
+        return None;
+    }
+
+    let lines = strip_private_fences(content);
+    let blocks = split_blocks(&lines);
+
+    // Each kept line, and whether it is inside fenced code.
+    let mut kept: Vec<(&str, bool)> = Vec::new();
+    let mut skip_section: Option<usize> = None;
+    let mut skip_items_deeper_than: Option<usize> = None;
+
+    for block in &blocks {
+        if let Some(level) = skip_section {
+            match block.kind {
+                BlockKind::Heading(l) if l <= level => skip_section = None,
+                _ => continue,
+            }
+        }
+        if let Some(indent) = skip_items_deeper_than {
+            match block.kind {
+                BlockKind::ListItem(i) if i > indent => continue,
+                BlockKind::Blank => continue,
+                _ => skip_items_deeper_than = None,
+            }
+        }
+
+        let private = !block.code && has_tag(&block.lines.join("\n"), &rules.private_tag);
+        if private {
+            match block.kind {
+                BlockKind::Heading(level) => skip_section = Some(level),
+                BlockKind::ListItem(indent) => skip_items_deeper_than = Some(indent),
+                BlockKind::Blank | BlockKind::Other => {}
+            }
+            continue;
+        }
+
+        kept.extend(block.lines.iter().map(|&line| (line, block.code)));
+    }
+
+    // Removing blocks leaves runs of blank lines behind; collapse them, but
+    // leave the blank lines inside code blocks as they are.
+    let mut out = String::with_capacity(content.len());
+    let mut previous_blank = true;
+    for (line, code) in kept {
+        let blank = line.trim().is_empty();
+        if blank && previous_blank && !code {
+            continue;
+        }
+        out.push_str(line);
+        out.push('\n');
+        previous_blank = blank;
+    }
+    while out.ends_with("\n\n") {
+        out.pop();
+    }
+    if !content.ends_with('\n') && out.ends_with('\n') {
+        out.pop();
+    }
+
+    Some(out)
+}
+
+/// Remove `%%private%%` regions, including the fence lines themselves.
+fn strip_private_fences(content: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut in_code = false;
+    let mut in_private = false;
+
+    for line in content.lines() {
+        if !in_code && line.trim() == PRIVATE_FENCE {
+            in_private = !in_private;
+            continue;
+        }
+        if in_private {
+            continue;
+        }
+        if is_code_fence(line) {
+            in_code = !in_code;
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let hashes = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[hashes..];
+    ((1..=6).contains(&hashes) && (rest.is_empty() || rest.starts_with(' '))).then_some(hashes)
+}
+
+/// Indentation of `line` if it starts a list item (`-`, `*`, `+`, `1.`, `1)`).
+fn list_item_indent(line: &str) -> Option<usize> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    let after_marker = if let Some(rest) = trimmed.strip_prefix(['-', '*', '+']) {
+        rest
+    } else {
+        let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+        if digits == 0 {
+            return None;
+        }
+        trimmed[digits..].strip_prefix(['.', ')'])?
+    };
+    (after_marker.is_empty() || after_marker.starts_with(' ')).then_some(indent)
+}
+
+/// Group lines into redactable blocks: blank lines, headings, individual list
+/// items (with their continuation lines), fenced code blocks and paragraphs.
+fn split_blocks<'a>(lines: &[&'a str]) -> Vec<Block<'a>> {
+    let mut blocks: Vec<Block<'a>> = Vec::new();
+    let mut in_code = false;
+
+    for &line in lines {
+        if in_code {
+            if let Some(block) = blocks.last_mut() {
+                block.lines.push(line);
+            }
+            if is_code_fence(line) {
+                in_code = false;
+            }
+            continue;
+        }
+
+        if is_code_fence(line) {
+            in_code = true;
+            blocks.push(Block {
+                kind: BlockKind::Other,
+                lines: vec![line],
+                code: true,
+            });
+            continue;
+        }
+
+        let kind = if line.trim().is_empty() {
+            BlockKind::Blank
+        } else if let Some(level) = heading_level(line) {
+            BlockKind::Heading(level)
+        } else if let Some(indent) = list_item_indent(line) {
+            BlockKind::ListItem(indent)
+        } else {
+            // Continuation of the current paragraph or list item.
+            if let Some(block) = blocks.last_mut()
+                && !block.code
+                && matches!(block.kind, BlockKind::Other | BlockKind::ListItem(_))
+            {
+                block.lines.push(line);
+                continue;
+            }
+            BlockKind::Other
+        };
+
+        blocks.push(Block {
+            kind,
+            lines: vec![line],
+            code: false,
+        });
+    }
+
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redact_default(content: &str) -> String {
+        redact(content, &RedactionRules::default()).unwrap()
+    }
+
+    #[test]
+    fn test_excluded_tag_drops_note() {
+        let rules = RedactionRules {
+            exclude_tags: vec!["journal".to_string()],
+            ..RedactionRules::default()
+        };
+        assert_eq!(redact("Dear diary #journal\n", &rules), None);
+        assert!(redact("Public note\n", &rules).is_some());
+    }
+
+    #[test]
+    fn test_private_fence_is_removed() {
+        let content = "Intro\n\n%%private%%\nsecret\n\nmore secret\n%%private%%\n\nOutro\n";
+        assert_eq!(redact_default(content), "Intro\n\nOutro\n");
+    }
+
+    #[test]
+    fn test_unclosed_fence_hides_rest() {
+        assert_eq!(redact_default("Intro\n%%private%%\nsecret\n"), "Intro\n");
+    }
+
+    #[test]
+    fn test_tagged_paragraph_and_list_item_are_removed() {
+        let content = "Public paragraph.\n\nPrivate paragraph\nspanning lines #private\n\n- public item\n- private item #private\n  - nested private detail\n- another public item\n";
+        assert_eq!(
+            redact_default(content),
+            "Public paragraph.\n\n- public item\n- another public item\n"
+        );
+    }
+
+    #[test]
+    fn test_tagged_heading_removes_section() {
+        let content = "# Notes\n\n## Salaries #private\n\nNumbers\n\n### Details\n\nMore\n\n## Roadmap\n\nPlans\n";
+        assert_eq!(redact_default(content), "# Notes\n\n## Roadmap\n\nPlans\n");
+    }
+
+    #[test]
+    fn test_code_is_left_alone() {
+        let content = "```\n%%private%%\n#private\n```\n";
+        assert_eq!(redact_default(content), content);
+    }
+
+    #[test]
+    fn test_blank_lines_in_code_are_kept() {
+        let content = "Intro #private\n\n```\nfirst\n\n\n\nlast\n```\n\n\nOutro\n";
+        assert_eq!(
+            redact_default(content),
+            "```\nfirst\n\n\n\nlast\n```\n\nOutro\n"
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! Search and replace across all notes, for `piki replace`.
+//!
+//! The pattern is either text found as it is or a regular expression, whose
+//! replacement may refer to its groups as `$1` or `${name}`. Each match can be
+//! accepted or skipped on its own, which is how `--interactive` asks before
+//! every replacement.
+
+use std::ops::Range;
+
+use regex::Regex;
+
+use crate::DocumentStore;
+
+/// One match of the pattern, as offered for replacement.
+pub struct Match<'a> {
+    /// The 1-based line the match starts on.
+    pub line: usize,
+    /// That line, without its line break.
+    pub text: &'a str,
+    /// Where the match is in `text`; it may reach past its end.
+    pub range: Range<usize>,
+    /// What the match would be replaced by.
+    pub replacement: String,
+}
+
+/// A note with replacements, before and after.
+pub struct Replaced {
+    pub note: String,
+    pub count: usize,
+    pub old: String,
+    pub new: String,
+}
+
+/// What [`replace_everywhere`] did.
+#[derive(Default)]
+pub struct Report {
+    /// The notes with replacements, in the order of their names.
+    pub replaced: Vec<Replaced>,
+    /// The notes that couldn't be read, with why, left as they are.
+    pub unreadable: Vec<(String, String)>,
+}
+
+/// Compile `pattern`: a regular expression with `regex`, otherwise text that
+/// is found as it is.
+pub fn compile(pattern: &str, regex: bool) -> Result<Regex, String> {
+    if pattern.is_empty() {
+        return Err("The pattern must not be empty".to_string());
+    }
+    let source = if regex {
+        pattern.to_string()
+    } else {
+        regex::escape(pattern)
+    };
+    Regex::new(&source).map_err(|e| format!("Invalid pattern: {e}"))
+}
+
+/// `content` with the matches of `pattern` that `accept` agrees to replaced by
+/// `replacement`, and how many were replaced. With `expand`, `$1` and
+/// `${name}` in `replacement` stand for the match's groups; otherwise it is
+/// taken as it is. Empty matches are never replaced.
+pub fn replace(
+    content: &str,
+    pattern: &Regex,
+    replacement: &str,
+    expand: bool,
+    mut accept: impl FnMut(&Match) -> bool,
+) -> (String, usize) {
+    let mut out = String::with_capacity(content.len());
+    let mut count = 0;
+    let mut last = 0;
+    for captures in pattern.captures_iter(content) {
+        let whole = captures.get(0).expect("group 0 is the whole match");
+        if whole.is_empty() {
+            continue;
+        }
+        let mut new = String::new();
+        if expand {
+            captures.expand(replacement, &mut new);
+        } else {
+            new.push_str(replacement);
+        }
+        let line_start = content[..whole.start()].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = content[whole.start()..]
+            .find('\n')
+            .map_or(content.len(), |i| whole.start() + i);
+        let offered = Match {
+            line: content[..line_start].matches('\n').count() + 1,
+            text: &content[line_start..line_end],
+            range: whole.start() - line_start..whole.end() - line_start,
+            replacement: new,
+        };
+        if accept(&offered) {
+            out.push_str(&content[last..whole.start()]);
+            out.push_str(&offered.replacement);
+            last = whole.end();
+            count += 1;
+        }
+    }
+    out.push_str(&content[last..]);
+    (out, count)
+}
+
+/// [`replace`] in every note of `store`, in the order of their names, with
+/// `accept` also told the note's name. Notes that can't be read, such as
+/// encrypted ones, are skipped and reported; with `dry_run`, nothing is
+/// written.
+pub fn replace_everywhere(
+    store: &DocumentStore,
+    pattern: &Regex,
+    replacement: &str,
+    expand: bool,
+    dry_run: bool,
+    mut accept: impl FnMut(&str, &Match) -> bool,
+) -> Result<Report, String> {
+    let mut notes = store.list_all_documents()?;
+    notes.sort();
+    let mut report = Report::default();
+    for note in notes {
+        let mut doc = match store.load(&note) {
+            Ok(doc) => doc,
+            Err(e) => {
+                report.unreadable.push((note, e));
+                continue;
+            }
+        };
+        if !pattern.is_match(&doc.content) {
+            continue;
+        }
+        let (content, count) = replace(&doc.content, pattern, replacement, expand, |found| {
+            accept(&note, found)
+        });
+        if count == 0 {
+            continue;
+        }
+        let old = std::mem::replace(&mut doc.content, content);
+        if !dry_run && doc.content != old {
+            store.save(&doc)?;
+        }
+        report.replaced.push(Replaced {
+            note,
+            count,
+            new: doc.content,
+            old,
+        });
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn replaces_text_and_expressions() {
+        let text = compile("a.b", false).unwrap();
+        assert_eq!(
+            replace("a.b axb\na.b", &text, "$0", false, |_| true),
+            ("$0 axb\n$0".to_string(), 2)
+        );
+
+        let dates = compile(r"(\d{4})-(\d{2})", true).unwrap();
+        let mut offered = Vec::new();
+        let (out, count) = replace("x\nin 2024-06 and 2025-01\n", &dates, "$2/$1", true, |m| {
+            offered.push((
+                m.line,
+                m.text.to_string(),
+                m.range.clone(),
+                m.replacement.clone(),
+            ));
+            m.range.start > 5
+        });
+        assert_eq!(out, "x\nin 2024-06 and 01/2025\n");
+        assert_eq!(count, 1);
+        assert_eq!(
+            offered[0],
+            (
+                2,
+                "in 2024-06 and 2025-01".to_string(),
+                3..10,
+                "06/2024".to_string()
+            )
+        );
+
+        assert!(compile("", false).is_err());
+        assert!(compile("(", true).is_err());
+        let empty = compile("x*", true).unwrap();
+        assert_eq!(replace("ab", &empty, "-", true, |_| true).1, 0);
+    }
+
+    #[test]
+    fn replaces_in_every_note() {
+        let dir = std::env::temp_dir().join("piki-test-replace");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.md"), "Acme Corp and Acme Corp\n").unwrap();
+        fs::write(dir.join("b.md"), "nothing here\n").unwrap();
+        fs::write(dir.join("c.md"), "Acme Corp\n").unwrap();
+        let store = DocumentStore::new(dir.clone());
+        let pattern = compile("Acme Corp", false).unwrap();
+
+        let dry = replace_everywhere(&store, &pattern, "Acme Inc", false, true, |_, _| true)
+            .unwrap()
+            .replaced;
+        assert_eq!(dry.len(), 2);
+        assert_eq!(dry[0].new, "Acme Inc and Acme Inc\n");
+        assert_eq!(
+            fs::read_to_string(dir.join("a.md")).unwrap(),
+            "Acme Corp and Acme Corp\n"
+        );
+
+        // A note that can't be read is skipped, not the end of replacing.
+        fs::write(dir.join("a0.md"), [0xff, 0xfe]).unwrap();
+        let report = replace_everywhere(&store, &pattern, "Acme Inc", false, false, |note, _| {
+            note == "a" || note == "c"
+        })
+        .unwrap();
+        assert_eq!(report.unreadable.len(), 1);
+        assert_eq!(report.unreadable[0].0, "a0");
+        let done = report.replaced;
+        assert_eq!(
+            done.iter()
+                .map(|r| (r.note.as_str(), r.count))
+                .collect::<Vec<_>>(),
+            vec![("a", 2), ("c", 1)]
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("a.md")).unwrap(),
+            "Acme Inc and Acme Inc\n"
+        );
+        assert_eq!(fs::read_to_string(dir.join("c.md")).unwrap(), "Acme Inc\n");
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,184 @@
+//! User plugins written in [Rhai](https://rhai.rs).
+//!
+//! Every `*.rhai` file in the notes directory's `.piki/plugins/` folder is a
+//! plugin page named after the file: `.piki/plugins/recent.rhai` is `!recent`.
+//! A script sees the sorted names of all notes as `notes` and the page's
+//! arguments as `args` (`projects/` for `!recent:projects/`), can read a note
+//! with `read(name)` and ask for its last change with `modified(name)`
+//! (seconds since the Unix epoch, `()` if unknown), and evaluates to the
+//! page's Markdown:
+//!
+//! ```rhai
+//! let page = "# Projects\n\n";
+//! for name in notes {
+//!     if name.starts_with("projects/") {
+//!         page += "- [" + name + "](" + name + ")\n";
+//!     }
+//! }
+//! page
+//! ```
+//!
+//! Scripts are read again whenever their page is generated, so edits show up
+//! without a restart. A script can't take the name of a built-in plugin.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use rhai::{Array, Dynamic, Engine, Scope};
+
+use crate::edit_log::LOCAL_DIR;
+use crate::{DocumentStore, Plugin, PluginRegistry};
+
+/// Folder inside [`LOCAL_DIR`] holding the scripts.
+const PLUGIN_DIR: &str = "plugins";
+const EXTENSION: &str = "rhai";
+/// Upper bound on the operations a script may run, so a runaway loop ends in
+/// an error instead of hanging the app.
+const MAX_OPERATIONS: u64 = 50_000_000;
+
+/// A plugin page generated by a Rhai script.
+pub struct ScriptPlugin {
+    path: PathBuf,
+}
+
+impl ScriptPlugin {
+    pub fn new(path: PathBuf) -> Self {
+        ScriptPlugin { path }
+    }
+}
+
+impl Plugin for ScriptPlugin {
+    fn generate_content(&self, store: &DocumentStore, args: &str) -> Result<String, String> {
+        let source = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read '{}': {}", self.path.display(), e))?;
+
+        let mut notes = store.list_all_documents()?;
+        notes.sort();
+        let mut scope = Scope::new();
+        scope.push_constant(
+            "notes",
+            notes.into_iter().map(Dynamic::from).collect::<Array>(),
+        );
+        scope.push_constant("args", args.to_string());
+
+        let result = engine(store.base_path())
+            .eval_with_scope::<Dynamic>(&mut scope, &source)
+            .map_err(|e| format!("{}: {}", self.path.display(), e))?;
+        let type_name = result.type_name();
+        result.into_string().map_err(|_| {
+            format!(
+                "{}: the script must end with the page's text, not a value of type {}",
+                self.path.display(),
+                type_name
+            )
+        })
+    }
+}
+
+/// The engine scripts run in, with `read` and `modified` reading notes from
+/// `notes_dir`.
+fn engine(notes_dir: &Path) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    let store = DocumentStore::new(notes_dir.to_path_buf());
+    engine.register_fn(
+        "read",
+        move |name: &str| -> Result<String, Box<rhai::EvalAltResult>> {
+            store
+                .load(name)
+                .map(|doc| doc.content)
+                .map_err(|e| e.into())
+        },
+    );
+    let store = DocumentStore::new(notes_dir.to_path_buf());
+    engine.register_fn("modified", move |name: &str| -> Dynamic {
+        store
+            .modified_time(name)
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(Dynamic::UNIT, |d| Dynamic::from(d.as_secs() as i64))
+    });
+    engine
+}
+
+/// The scripts in `notes_dir`, as (plugin name, path), sorted by name.
+pub fn find_scripts(notes_dir: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = fs::read_dir(notes_dir.join(LOCAL_DIR).join(PLUGIN_DIR)) else {
+        return Vec::new();
+    };
+    let mut scripts: Vec<(String, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == EXTENSION))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            Some((name, path))
+        })
+        .collect();
+    scripts.sort();
+    scripts
+}
+
+/// Register the scripts in `notes_dir` with `registry`, skipping any whose
+/// name is already taken. Returns the names registered.
+pub fn register_scripts(registry: &mut PluginRegistry, notes_dir: &Path) -> Vec<String> {
+    let mut names = Vec::new();
+    for (name, path) in find_scripts(notes_dir) {
+        if registry.has_plugin(&name) {
+            continue;
+        }
+        registry.register(name.clone(), Box::new(ScriptPlugin::new(path)));
+        names.push(name);
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IndexPlugin;
+
+    #[test]
+    fn scripts_generate_plugin_pages() {
+        let dir = std::env::temp_dir().join("piki-test-script");
+        let _ = fs::remove_dir_all(&dir);
+        let plugins = dir.join(LOCAL_DIR).join(PLUGIN_DIR);
+        fs::create_dir_all(&plugins).unwrap();
+        fs::write(dir.join("alpha.md"), "# Alpha\n\nfirst line\n").unwrap();
+        fs::write(dir.join("beta.md"), "# Beta\n").unwrap();
+        fs::write(
+            plugins.join("sizes.rhai"),
+            "let page = \"# Sizes\\n\\n\";\n\
+             for name in notes { page += \"- \" + name + \": \" + read(name).len() + \"\\n\"; }\n\
+             page",
+        )
+        .unwrap();
+        fs::write(plugins.join("index.rhai"), "\"shadowed\"").unwrap();
+        fs::write(plugins.join("broken.rhai"), "42").unwrap();
+        fs::write(plugins.join("notes.txt"), "").unwrap();
+
+        let store = DocumentStore::new(dir.clone());
+        let mut registry = PluginRegistry::new();
+        registry.register("index", Box::new(IndexPlugin));
+        assert_eq!(
+            register_scripts(&mut registry, &dir),
+            vec!["broken", "sizes"]
+        );
+
+        assert_eq!(
+            registry.generate("sizes", &store).unwrap(),
+            "# Sizes\n\n- alpha: 20\n- beta: 7\n"
+        );
+        assert!(
+            registry
+                .generate("index", &store)
+                .unwrap()
+                .starts_with("# Index")
+        );
+        let err = registry.generate("broken", &store).unwrap_err();
+        assert!(err.contains("not a value of type i64"), "{err}");
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -12,7 +12,9 @@
 //! only concerns itself with note *content*; matching against note names is left
 //! to the caller (the GUI picker still fuzzy-matches names on top of this).
 
-use crate::DocumentStore;
+use std::time::{Duration, SystemTime};
+
+use crate::{DocumentStore, fuzzy};
 
 /// Split a query into lowercase, whitespace-separated terms, dropping empties.
 ///
@@ -115,6 +117,64 @@ pub fn search_store(store: &DocumentStore, query: &str) -> Result<Vec<NoteSearch
     Ok(results)
 }
 
+/// What a fuzzy match of a note's name is worth on top of its
+/// [`fuzzy::score`]; enough to rank it above any match of the content alone.
+const NAME_MATCH: i32 = 100;
+/// What containing all of the query's terms is worth, plus
+/// [`LINE_MATCH`] for each matching line up to [`MAX_LINE_MATCHES`].
+const CONTENT_MATCH: i32 = 40;
+const LINE_MATCH: i32 = 4;
+const MAX_LINE_MATCHES: usize = 5;
+
+/// The boost for a note changed `age` ago: recent notes are the likelier
+/// ones to be looked for.
+fn recency_bonus(age: Duration) -> i32 {
+    const DAY: u64 = 24 * 60 * 60;
+    match age.as_secs() {
+        s if s < DAY => 15,
+        s if s < 7 * DAY => 10,
+        s if s < 30 * DAY => 5,
+        _ => 0,
+    }
+}
+
+/// The notes of `store` matching a possibly vague `query`, best first, for
+/// choosing the one to open. A fuzzy match of the name counts most, then
+/// the query's terms all appearing in the note (more so on more lines), and
+/// recently changed notes get a boost; ties go to the more recent note, then
+/// by name. An empty query lists every note, most recently changed first.
+///
+/// Encrypted notes are matched by name only, so ranking never asks for a
+/// passphrase.
+pub fn rank_notes(store: &DocumentStore, query: &str) -> Result<Vec<String>, String> {
+    let query = query.trim();
+    let terms = parse_terms(query);
+    let now = SystemTime::now();
+
+    let mut ranked = Vec::new();
+    for name in store.list_all_documents()? {
+        let modified = store.modified_time(&name);
+        let mut score = fuzzy::score(query, &name).map(|s| NAME_MATCH + s);
+        if score.is_none() && !store.is_encrypted(&name) {
+            let Ok(doc) = store.load(&name) else { continue };
+            if contains_all_terms(&doc.content.to_lowercase(), &terms) {
+                let lines = matching_lines(&doc.content, &terms).len();
+                score = Some(CONTENT_MATCH + LINE_MATCH * lines.min(MAX_LINE_MATCHES) as i32);
+            }
+        }
+        let Some(score) = score else { continue };
+        let age = modified.and_then(|m| now.duration_since(m).ok());
+        let bonus = if query.is_empty() {
+            0
+        } else {
+            age.map_or(0, recency_bonus)
+        };
+        ranked.push((score + bonus, modified, name));
+    }
+    ranked.sort_by(|(sa, ma, a), (sb, mb, b)| sb.cmp(sa).then(mb.cmp(ma)).then(a.cmp(b)));
+    Ok(ranked.into_iter().map(|(_, _, name)| name).collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -178,6 +238,44 @@ mod tests {
         assert_eq!(first_snippet("nothing here", &parse_terms("zzz")), None);
     }
 
+    #[test]
+    fn rank_notes_prefers_names_then_content_then_recency() {
+        use std::fs;
+
+        let dir = std::env::temp_dir().join("piki-test-rank-notes");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let now = SystemTime::now();
+        let note = |name: &str, content: &str, days_ago: u64| {
+            let path = dir.join(format!("{name}.md"));
+            fs::write(&path, content).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(days_ago * 24 * 60 * 60))
+                .unwrap();
+        };
+        note("roadmap", "plans", 90);
+        note("standup", "talked about the roadmap", 2);
+        note("retro", "the roadmap slipped", 60);
+        note("groceries", "milk", 0);
+        let store = DocumentStore::new(dir.clone());
+
+        assert_eq!(
+            rank_notes(&store, "roadmap").unwrap(),
+            vec!["roadmap", "standup", "retro"]
+        );
+        assert_eq!(rank_notes(&store, "rdmp").unwrap(), vec!["roadmap"]);
+        assert_eq!(
+            rank_notes(&store, "").unwrap(),
+            vec!["groceries", "standup", "retro", "roadmap"]
+        );
+        assert!(rank_notes(&store, "nowhere").unwrap().is_empty());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
     #[test]
     fn search_store_finds_notes_with_all_terms() {
         use std::env;
@@ -0,0 +1,194 @@
+//! Sections of a note: a heading and everything under it, up to the next
+//! heading of the same or a higher level. `piki section` prints them, and the
+//! GUI's section links (`note#section-slug`) point at them.
+//!
+//! [`heading_slug`] is the single source of truth for a heading's slug, so a
+//! section named on the command line by its anchor is the one a section link
+//! with that anchor opens.
+
+use crate::tags::is_code_fence;
+use crate::title::plain_text;
+
+/// Turn a heading's plain text into an anchor slug.
+///
+/// Lower-cases the text, keeps (Unicode) alphanumerics, and collapses any run of
+/// whitespace, `-`, or `_` into a single `-`, dropping all other punctuation.
+/// Leading and trailing dashes are trimmed. This is deliberately simple and,
+/// crucially, *self-consistent*: the same function generates the slug written
+/// into a link and resolves it back to a heading, so exact GitHub compatibility
+/// is not required — only that generation and resolution agree.
+///
+/// Duplicate headings are disambiguated by [`heading_anchors`], not here.
+pub fn heading_slug(text: &str) -> String {
+    let mut slug = String::new();
+    let mut pending_dash = false;
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            if pending_dash && !slug.is_empty() {
+                slug.push('-');
+            }
+            pending_dash = false;
+            slug.extend(c.to_lowercase());
+        } else if c.is_whitespace() || c == '-' || c == '_' {
+            // Defer emitting the separator so trailing separators never make it
+            // into the slug and runs collapse to a single dash.
+            pending_dash = true;
+        }
+        // Any other character (punctuation, symbols) is dropped.
+    }
+    slug
+}
+
+/// Compute unique anchor slugs for a document's headings, in document order.
+///
+/// Headings that slug to the same base get a numeric suffix (`-1`, `-2`, …) in
+/// order of appearance, mirroring how GitHub disambiguates repeated headings, so
+/// a link to the second "Notes" heading resolves to that heading rather than the
+/// first. Callers pair the returned slugs positionally with the headings they
+/// passed in.
+pub fn heading_anchors<S: AsRef<str>>(heading_texts: &[S]) -> Vec<String> {
+    use std::collections::HashMap;
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut anchors = Vec::with_capacity(heading_texts.len());
+    for text in heading_texts {
+        let base = heading_slug(text.as_ref());
+        let seen = counts.entry(base.clone()).or_insert(0);
+        let anchor = if *seen == 0 {
+            base.clone()
+        } else {
+            format!("{base}-{seen}")
+        };
+        *seen += 1;
+        anchors.push(anchor);
+    }
+    anchors
+}
+
+/// An ATX heading's level and text, without closing hashes.
+pub(crate) fn atx_heading(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let level = trimmed.len() - trimmed.trim_start_matches('#').len();
+    let rest = &trimmed[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    let text = rest.trim();
+    let without_closing = text.trim_end_matches('#');
+    if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        return Some((level, without_closing.trim_end()));
+    }
+    Some((level, text))
+}
+
+/// The position among `heading_texts` (plain text, in document order) of the
+/// heading named `heading`: by its text, ignoring case and markup, or by its
+/// anchor, with or without the `#`.
+pub fn heading_index<S: AsRef<str>>(heading_texts: &[S], heading: &str) -> Option<usize> {
+    let query = heading.trim();
+    let anchor = query.strip_prefix('#').unwrap_or(query);
+    let text = plain_text(query).to_lowercase();
+    heading_anchors(heading_texts)
+        .iter()
+        .zip(heading_texts)
+        .position(|(slug, other)| slug == anchor || other.as_ref().to_lowercase() == text)
+}
+
+/// The offset, level and plain text of each ATX heading in `content` outside
+/// of code blocks.
+fn headings(content: &str) -> Vec<(usize, usize, String)> {
+    let mut headings = Vec::new();
+    let mut in_code = false;
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        if is_code_fence(line) {
+            in_code = !in_code;
+        } else if !in_code && let Some((level, text)) = atx_heading(line.trim_end()) {
+            headings.push((offset, level, plain_text(text)));
+        }
+        offset += line.len();
+    }
+    headings
+}
+
+/// The section of `content` under `heading`, named as for [`heading_index`].
+/// It runs from the heading's line to the next heading of the same or a higher
+/// level, without trailing blank lines. Only ATX (`#`) headings start
+/// sections.
+pub fn find<'a>(content: &'a str, heading: &str) -> Option<&'a str> {
+    let headings = headings(content);
+    let texts: Vec<&str> = headings.iter().map(|(_, _, text)| text.as_str()).collect();
+    let index = heading_index(&texts, heading)?;
+
+    let (start, level, _) = headings[index];
+    let end = headings[index + 1..]
+        .iter()
+        .find(|&&(_, other, _)| other <= level)
+        .map_or(content.len(), |&(offset, _, _)| offset);
+    Some(content[start..end].trim_end())
+}
+
+/// The line (1-based) of `content` that `heading` is on, named as for
+/// [`heading_index`].
+pub fn heading_line(content: &str, heading: &str) -> Option<usize> {
+    let headings = headings(content);
+    let texts: Vec<&str> = headings.iter().map(|(_, _, text)| text.as_str()).collect();
+    let (offset, _, _) = headings[heading_index(&texts, heading)?];
+    Some(content[..offset].matches('\n').count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slug_basics() {
+        assert_eq!(heading_slug("Hello World"), "hello-world");
+        assert_eq!(heading_slug("Security Model"), "security-model");
+        assert_eq!(heading_slug("  Trailing spaces  "), "trailing-spaces");
+        assert_eq!(heading_slug("Notes: Meeting!"), "notes-meeting");
+        assert_eq!(heading_slug("Q4 — Budget (2026)"), "q4-budget-2026");
+        assert_eq!(heading_slug("under_score and-dash"), "under-score-and-dash");
+        assert_eq!(heading_slug("multiple   spaces"), "multiple-spaces");
+        assert_eq!(heading_slug("---"), "");
+        assert_eq!(heading_slug(""), "");
+    }
+
+    #[test]
+    fn anchors_disambiguate_duplicates() {
+        let headings = ["Notes", "Details", "Notes", "notes"];
+        let anchors = heading_anchors(&headings);
+        assert_eq!(anchors, vec!["notes", "details", "notes-1", "notes-2"]);
+    }
+
+    #[test]
+    fn finds_sections() {
+        let content = "# Runbook\n\nIntro\n\n## On call\n\nPage **Alex**.\n\n### Escalation\n\nCall Sam.\n\n```\n# not a heading\n```\n\n## Deploys ##\n\nFriday? No.\n";
+        let on_call = "## On call\n\nPage **Alex**.\n\n### Escalation\n\nCall Sam.\n\n```\n# not a heading\n```";
+        assert_eq!(find(content, "On Call"), Some(on_call));
+        assert_eq!(find(content, "#on-call"), Some(on_call));
+        assert_eq!(
+            find(content, "escalation"),
+            Some("### Escalation\n\nCall Sam.\n\n```\n# not a heading\n```")
+        );
+        assert_eq!(
+            find(content, "deploys"),
+            Some("## Deploys ##\n\nFriday? No.")
+        );
+        assert_eq!(find(content, "not a heading"), None);
+        assert_eq!(heading_line(content, "#escalation"), Some(9));
+        assert_eq!(heading_line(content, "Deploys"), Some(17));
+        assert_eq!(heading_line(content, "not a heading"), None);
+        assert_eq!(
+            heading_index(&["Notes", "On call", "notes"], "#notes-1"),
+            Some(2)
+        );
+        assert_eq!(heading_index(&["Notes", "On call"], "ON CALL"), Some(1));
+        assert_eq!(
+            find(content, "Runbook").map(str::len),
+            Some(content.trim_end().len())
+        );
+    }
+}
@@ -0,0 +1,315 @@
+//! A user's piki setup in one file, to replicate it on another machine.
+//!
+//! An archive holds `~/.pikirc`, the GUI's appearance settings and the
+//! wiki's templates (see [`crate::template`]) and script plugins (see
+//! [`crate::script`]). It is plain text, so it can be read and diffed: a
+//! `piki-settings VERSION` line, then each file as a `--- KIND BYTES NAME`
+//! line followed by exactly that many bytes of content and a newline.
+//!
+//! `VERSION` is that of the settings schema the archive was written with.
+//! Archives of older versions are migrated when they are read; newer ones are
+//! refused, as this version of piki can't know what changed.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::edit_log::LOCAL_DIR;
+
+/// Version of the settings schema written by this version of piki.
+pub const VERSION: u32 = 1;
+
+const MAGIC: &str = "piki-settings";
+
+/// What a file in the archive is, which decides where it is installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// `~/.pikirc`
+    PikiRc,
+    /// The GUI's theme and link styling
+    Appearance,
+    /// A template in the wiki's `.piki/templates/`
+    Template,
+    /// A script plugin in the wiki's `.piki/plugins/`
+    Plugin,
+}
+
+impl Kind {
+    /// The kind's name in archives.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Kind::PikiRc => "pikirc",
+            Kind::Appearance => "appearance",
+            Kind::Template => "template",
+            Kind::Plugin => "plugin",
+        }
+    }
+
+    fn parse(text: &str) -> Option<Kind> {
+        [Kind::PikiRc, Kind::Appearance, Kind::Template, Kind::Plugin]
+            .into_iter()
+            .find(|kind| kind.as_str() == text)
+    }
+}
+
+/// One file of the archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub kind: Kind,
+    /// The file's name, without any folder.
+    pub name: String,
+    pub content: String,
+}
+
+/// Where the settings live on this machine.
+#[derive(Debug, Clone)]
+pub struct Locations {
+    pub pikirc: Option<PathBuf>,
+    pub appearance: Option<PathBuf>,
+    pub notes_dir: PathBuf,
+}
+
+impl Locations {
+    /// The path an entry of `kind` named `name` is installed at.
+    fn path(&self, kind: Kind, name: &str) -> Option<PathBuf> {
+        match kind {
+            Kind::PikiRc => self.pikirc.clone(),
+            Kind::Appearance => self.appearance.clone(),
+            Kind::Template => Some(self.notes_dir.join(LOCAL_DIR).join("templates").join(name)),
+            Kind::Plugin => Some(self.notes_dir.join(LOCAL_DIR).join("plugins").join(name)),
+        }
+    }
+}
+
+/// Collect the settings found at `locations`.
+pub fn collect(locations: &Locations) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    for (kind, path) in [
+        (Kind::PikiRc, &locations.pikirc),
+        (Kind::Appearance, &locations.appearance),
+    ] {
+        if let Some(path) = path
+            && let Some(entry) = read_entry(kind, path)?
+        {
+            entries.push(entry);
+        }
+    }
+    for (kind, folder, extension) in [
+        (Kind::Template, "templates", "md"),
+        (Kind::Plugin, "plugins", "rhai"),
+    ] {
+        let dir = locations.notes_dir.join(LOCAL_DIR).join(folder);
+        let Ok(dir_entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = dir_entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().is_some_and(|e| e == extension))
+            .collect();
+        paths.sort();
+        for path in paths {
+            if let Some(entry) = read_entry(kind, &path)? {
+                entries.push(entry);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+fn read_entry(kind: Kind, path: &Path) -> Result<Option<Entry>, String> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+        .to_string();
+    Ok(Some(Entry {
+        kind,
+        name,
+        content,
+    }))
+}
+
+/// `entries` as an archive of the current [`VERSION`].
+pub fn write(entries: &[Entry]) -> String {
+    let mut archive = format!("{MAGIC} {VERSION}\n");
+    for entry in entries {
+        let _ = writeln!(
+            archive,
+            "--- {} {} {}",
+            entry.kind.as_str(),
+            entry.content.len(),
+            entry.name
+        );
+        archive.push_str(&entry.content);
+        archive.push('\n');
+    }
+    archive
+}
+
+/// The entries of `archive`, migrated to the current [`VERSION`].
+pub fn read(archive: &str) -> Result<Vec<Entry>, String> {
+    let (header, mut rest) = archive.split_once('\n').unwrap_or((archive, ""));
+    let version: u32 = header
+        .strip_prefix(MAGIC)
+        .and_then(|version| version.trim().parse().ok())
+        .filter(|&version| version > 0)
+        .ok_or("Not a piki settings archive")?;
+    if version > VERSION {
+        return Err(format!(
+            "The archive was written by a newer piki (settings version {version}, this piki knows up to {VERSION})"
+        ));
+    }
+
+    let mut entries = Vec::new();
+    while !rest.is_empty() {
+        let (line, after) = rest.split_once('\n').ok_or("Truncated settings archive")?;
+        let mut fields = line.strip_prefix("--- ").unwrap_or_default().splitn(3, ' ');
+        let (Some(kind), Some(len), Some(name)) = (fields.next(), fields.next(), fields.next())
+        else {
+            return Err(format!("Broken settings archive entry: {line}"));
+        };
+        let kind = Kind::parse(kind).ok_or_else(|| format!("Unknown kind of setting: {kind}"))?;
+        if matches!(name, "" | "." | "..") || name.contains(['/', '\\']) {
+            return Err(format!("Invalid file name in settings archive: {name}"));
+        }
+        let len: usize = len
+            .parse()
+            .map_err(|_| format!("Broken settings archive entry: {line}"))?;
+        let content = after
+            .get(..len)
+            .ok_or("Truncated settings archive")?
+            .to_string();
+        rest = after[len..]
+            .strip_prefix('\n')
+            .ok_or("Truncated settings archive")?;
+        entries.push(Entry {
+            kind,
+            name: name.to_string(),
+            content,
+        });
+    }
+
+    Ok(migrate(version, entries))
+}
+
+/// Bring entries of settings version `version` up to the current one. Each
+/// change to the settings schema adds a step turning its previous version
+/// into the new one here.
+fn migrate(version: u32, entries: Vec<Entry>) -> Vec<Entry> {
+    debug_assert!(version <= VERSION);
+    entries
+}
+
+/// What [`install`] did with an entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Installed(PathBuf),
+    Unchanged(PathBuf),
+    /// A different file is in the way; only replaced when asked to.
+    Kept(PathBuf),
+    /// There is nowhere to put it on this machine.
+    NoLocation,
+}
+
+/// Install `entries` at `locations`. Existing files that differ are only
+/// replaced if `overwrite` is set.
+pub fn install(
+    entries: &[Entry],
+    locations: &Locations,
+    overwrite: bool,
+) -> Result<Vec<Outcome>, String> {
+    let mut outcomes = Vec::new();
+    for entry in entries {
+        let Some(path) = locations.path(entry.kind, &entry.name) else {
+            outcomes.push(Outcome::NoLocation);
+            continue;
+        };
+        match fs::read_to_string(&path) {
+            Ok(existing) if existing == entry.content => {
+                outcomes.push(Outcome::Unchanged(path));
+                continue;
+            }
+            Ok(_) if !overwrite => {
+                outcomes.push(Outcome::Kept(path));
+                continue;
+            }
+            _ => {}
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+        }
+        fs::write(&path, &entry.content)
+            .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+        outcomes.push(Outcome::Installed(path));
+    }
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archives_round_trip_between_machines() {
+        let dir = std::env::temp_dir().join("piki-test-settings-archive");
+        let _ = fs::remove_dir_all(&dir);
+        let old = Locations {
+            pikirc: Some(dir.join("old/.pikirc")),
+            appearance: Some(dir.join("old/appearance.toml")),
+            notes_dir: dir.join("old/notes"),
+        };
+        fs::create_dir_all(old.notes_dir.join(".piki/templates")).unwrap();
+        fs::write(old.pikirc.as_ref().unwrap(), "[diff]\ntool = \"meld\"\n").unwrap();
+        fs::write(
+            old.notes_dir.join(".piki/templates/meeting.md"),
+            "# {{title}}\n--- not a header\n",
+        )
+        .unwrap();
+
+        let archive = write(&collect(&old).unwrap());
+        assert!(archive.starts_with("piki-settings 1\n--- pikirc 21 .pikirc\n"));
+        let entries = read(&archive).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].content, "# {{title}}\n--- not a header\n");
+
+        let new = Locations {
+            pikirc: Some(dir.join("new/.pikirc")),
+            appearance: None,
+            notes_dir: dir.join("new/notes"),
+        };
+        fs::create_dir_all(dir.join("new")).unwrap();
+        fs::write(new.pikirc.as_ref().unwrap(), "# mine\n").unwrap();
+        let outcomes = install(&entries, &new, false).unwrap();
+        assert_eq!(outcomes[0], Outcome::Kept(dir.join("new/.pikirc")));
+        assert_eq!(
+            outcomes[1],
+            Outcome::Installed(dir.join("new/notes/.piki/templates/meeting.md"))
+        );
+        let outcomes = install(&entries, &new, true).unwrap();
+        assert_eq!(outcomes[0], Outcome::Installed(dir.join("new/.pikirc")));
+        assert!(matches!(outcomes[1], Outcome::Unchanged(_)));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn refuses_broken_and_newer_archives() {
+        assert!(read("hello\n").is_err());
+        assert!(
+            read("piki-settings 99\n")
+                .unwrap_err()
+                .contains("newer piki")
+        );
+        assert!(read("piki-settings 1\n--- template 0 ../x.md\n\n").is_err());
+        assert!(read("piki-settings 1\n--- template 10 x.md\nshort\n").is_err());
+        assert!(read("piki-settings 0\n").is_err());
+        assert_eq!(read("piki-settings 1\n").unwrap(), vec![]);
+    }
+}
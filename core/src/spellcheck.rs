@@ -0,0 +1,749 @@
+//! Spellchecking with per-page and per-block languages.
+//!
+//! A page's language comes from its frontmatter (`lang: de`), falling back to
+//! English. A single block can be switched to another language by putting
+//! `<!-- lang: fr -->` on the line before it; the override lasts until the next
+//! blank line. Code never gets checked — neither fenced blocks nor inline
+//! `code` — and neither do URLs, link targets, tags or acronyms, so technical
+//! and mixed-language notes don't light up with false positives.
+//!
+//! Dictionaries are plain word lists or Hunspell `.dic` files, whose words
+//! also take the prefixes and suffixes of the `.aff` file next to them. They
+//! are looked up by language code in `~/.config/piki/dictionaries` and
+//! `/usr/share/hunspell`. Words in a language without a dictionary are not
+//! checked at all.
+
+use crate::frontmatter;
+use crate::tags::{is_code_fence, tag_spans};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Language of pages without a `lang:` in their frontmatter.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// A word to check, or one that was found misspelled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column, in characters
+    pub column: usize,
+    pub text: String,
+    pub lang: String,
+}
+
+/// The language given in the page's frontmatter, if any.
+pub fn page_language(content: &str) -> Option<String> {
+    let mut lines = content.lines();
+    if lines.next()?.trim_end() != "---" {
+        return None;
+    }
+    for line in lines {
+        if line.trim_end() == "---" {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':')
+            && key.trim().eq_ignore_ascii_case("lang")
+        {
+            let value = value.trim().trim_matches(['"', '\'']);
+            return (!value.is_empty()).then(|| value.to_string());
+        }
+    }
+    None
+}
+
+/// A `<!-- lang: xx -->` line switching the language of the following block.
+fn block_language(line: &str) -> Option<String> {
+    let inner = line.trim().strip_prefix("<!--")?.strip_suffix("-->")?;
+    let (key, value) = inner.split_once(':')?;
+    if !key.trim().eq_ignore_ascii_case("lang") {
+        return None;
+    }
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Byte ranges of `line` that are not prose: inline code, URLs, link targets,
+/// tags and HTML comments.
+fn masked_ranges(line: &str) -> Vec<(usize, usize)> {
+    let mut masked = Vec::new();
+
+    let mut code_start = None;
+    for (i, c) in line.char_indices() {
+        if c == '`' {
+            match code_start.take() {
+                Some(start) => masked.push((start, i + 1)),
+                None => code_start = Some(i),
+            }
+        }
+    }
+
+    let mut word_start = 0;
+    for (i, c) in line.char_indices().chain([(line.len(), ' ')]) {
+        if c.is_whitespace() {
+            let token = &line[word_start..i];
+            if let Some(scheme_end) = token.find("://") {
+                // Back up over the scheme only, so `[site](https://…)` keeps
+                // its label.
+                let scheme_start = token[..scheme_end]
+                    .rfind(|c: char| !c.is_ascii_alphanumeric())
+                    .map_or(0, |p| p + 1);
+                masked.push((word_start + scheme_start, i));
+            } else if token.starts_with("www.") || token.contains('@') {
+                masked.push((word_start, i));
+            }
+            word_start = i + c.len_utf8();
+        }
+    }
+
+    let mut rest = 0;
+    while let Some(pos) = line[rest..].find("](") {
+        let start = rest + pos + 2;
+        let end = line[start..].find(')').map_or(line.len(), |e| start + e);
+        masked.push((start, end));
+        rest = end;
+    }
+
+    let mut rest = 0;
+    while let Some(pos) = line[rest..].find("[[") {
+        let start = rest + pos + 2;
+        let end = line[start..].find("]]").map_or(line.len(), |e| start + e);
+        // Only the label of `[[target|label]]` is prose.
+        let target_end = line[start..end].find('|').map_or(end, |p| start + p);
+        masked.push((start, target_end));
+        rest = end;
+    }
+
+    let mut rest = 0;
+    while let Some(pos) = line[rest..].find("<!--") {
+        let start = rest + pos;
+        let end = line[start..]
+            .find("-->")
+            .map_or(line.len(), |e| start + e + 3);
+        masked.push((start, end));
+        rest = end;
+    }
+
+    masked.extend(tag_spans(line).into_iter().map(|r| (r.start, r.end)));
+    masked
+}
+
+/// All words of `content` that should be spellchecked, each with the language
+/// it is written in.
+pub fn checkable_words(content: &str) -> Vec<Word> {
+    let page_lang = page_language(content).unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+    let mut block_lang: Option<String> = None;
+    let mut in_code = false;
+    let mut words = Vec::new();
+
+    for (index, line) in content
+        .lines()
+        .enumerate()
+        .skip(frontmatter::line_count(content))
+    {
+        if is_code_fence(line) {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        if line.trim().is_empty() {
+            block_lang = None;
+            continue;
+        }
+        if let Some(lang) = block_language(line) {
+            block_lang = Some(lang);
+            continue;
+        }
+
+        let lang = block_lang.as_deref().unwrap_or(&page_lang);
+        let masked = masked_ranges(line);
+        for (start, end, column) in word_spans(line) {
+            let text = &line[start..end];
+            let is_masked = masked.iter().any(|&(s, e)| start < e && end > s);
+            // Touching a digit or an underscore makes it an identifier.
+            let in_identifier = line[..start]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_ascii_digit() || c == '_')
+                || line[end..]
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c.is_ascii_digit() || c == '_');
+            let is_acronym = text.chars().filter(|c| c.is_uppercase()).count() > 1;
+            if is_masked || in_identifier || is_acronym || text.chars().count() < 2 {
+                continue;
+            }
+            words.push(Word {
+                line: index + 1,
+                column: column + 1,
+                text: text.to_string(),
+                lang: lang.to_string(),
+            });
+        }
+    }
+
+    words
+}
+
+/// `(start, end, column)` of each run of letters in `line`, with apostrophes
+/// inside a word (`it's`) kept. Offsets are bytes, the column is in chars.
+fn word_spans(line: &str) -> Vec<(usize, usize, usize)> {
+    let chars: Vec<(usize, char)> = line.char_indices().collect();
+    let mut spans = Vec::new();
+    let mut start: Option<(usize, usize)> = None;
+
+    for (column, &(i, c)) in chars.iter().enumerate() {
+        let next_is_letter = chars
+            .get(column + 1)
+            .is_some_and(|&(_, next)| next.is_alphabetic());
+        let in_word =
+            c.is_alphabetic() || ((c == '\'' || c == '’') && start.is_some() && next_is_letter);
+        match (in_word, start) {
+            (true, None) => start = Some((i, column)),
+            (false, Some((word_start, word_column))) => {
+                spans.push((word_start, i, word_column));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some((word_start, word_column)) = start {
+        spans.push((word_start, line.len(), word_column));
+    }
+    spans
+}
+
+/// A list of correctly spelled words, with the prefixes and suffixes of a
+/// Hunspell `.aff` file that may be added to them.
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    /// Each word with the affix flags it was listed with.
+    words: HashMap<String, Vec<Flag>>,
+    affixes: Affixes,
+}
+
+impl Dictionary {
+    pub fn from_words<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Dictionary {
+            words: words
+                .into_iter()
+                .map(|word| (word.into(), Vec::new()))
+                .collect(),
+            affixes: Affixes::default(),
+        }
+    }
+
+    /// Parse a plain word list (one word per line) or a Hunspell `.dic` file,
+    /// whose leading word count is ignored and whose `/FLAGS` suffixes take no
+    /// effect without the `.aff` file (see [`Dictionary::parse_with_affixes`]).
+    pub fn parse(text: &str) -> Self {
+        Self::parse_with_affixes(text, "")
+    }
+
+    /// Parse a Hunspell `.dic` file together with its `.aff` file, so a word
+    /// listed as `walk/DS` also accepts `walked` and `walks` if the `.aff`
+    /// file has such `SFX D` and `SFX S` rules. Only prefixes and suffixes
+    /// (`PFX`, `SFX`) and the flag format (`FLAG`) are understood.
+    pub fn parse_with_affixes(dic: &str, aff: &str) -> Self {
+        let affixes = Affixes::parse(aff);
+        let mut words: HashMap<String, Vec<Flag>> = HashMap::new();
+        for line in dic.lines() {
+            let Some(entry) = line.split('\t').next() else {
+                continue;
+            };
+            let (word, flags) = entry.split_once('/').unwrap_or((entry, ""));
+            let word = word.trim();
+            if word.is_empty() || word.chars().all(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            let flags = affixes
+                .flag_type
+                .parse(flags.split_whitespace().next().unwrap_or(""));
+            words.entry(word.to_string()).or_default().extend(flags);
+        }
+        Dictionary { words, affixes }
+    }
+
+    /// Load a word list or `.dic` file, with the `.aff` file next to it if
+    /// there is one.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = fs::read(path)
+            .map_err(|e| format!("Failed to read dictionary {}: {}", path.display(), e))?;
+        let aff = fs::read(path.with_extension("aff")).unwrap_or_default();
+        Ok(Self::parse_with_affixes(
+            &String::from_utf8_lossy(&bytes),
+            &String::from_utf8_lossy(&aff),
+        ))
+    }
+
+    /// Whether `word` is spelled correctly. A capitalized word (at the start of
+    /// a sentence) is also accepted if the dictionary has it in lowercase.
+    pub fn contains(&self, word: &str) -> bool {
+        let word = word.replace('’', "'");
+        self.accepts(&word) || self.accepts(&word.to_lowercase())
+    }
+
+    /// Whether `word` is listed, or is a listed word with affixes it allows.
+    fn accepts(&self, word: &str) -> bool {
+        if self.words.contains_key(word) {
+            return true;
+        }
+        for suffix in &self.affixes.suffixes {
+            if let Some(stem) = suffix.remove_from(word)
+                && self.has_flag(&stem, suffix.flag)
+            {
+                return true;
+            }
+        }
+        for prefix in &self.affixes.prefixes {
+            let Some(stem) = prefix.remove_from(word) else {
+                continue;
+            };
+            if self.has_flag(&stem, prefix.flag) {
+                return true;
+            }
+            // Both a prefix and a suffix, if both rules allow combining.
+            if prefix.cross_product {
+                for suffix in self.affixes.suffixes.iter().filter(|s| s.cross_product) {
+                    if let Some(root) = suffix.remove_from(&stem)
+                        && self.has_flag(&root, prefix.flag)
+                        && self.has_flag(&root, suffix.flag)
+                    {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    fn has_flag(&self, word: &str, flag: Flag) -> bool {
+        self.words
+            .get(word)
+            .is_some_and(|flags| flags.contains(&flag))
+    }
+}
+
+/// An affix flag, as a number whatever the `FLAG` format of the `.aff` file.
+type Flag = u64;
+
+/// How flags are written after a word's `/` (the `.aff` file's `FLAG`).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum FlagType {
+    /// One character per flag (the default, and `FLAG UTF-8`).
+    #[default]
+    Char,
+    /// Two characters per flag (`FLAG long`).
+    Long,
+    /// Comma-separated numbers (`FLAG num`).
+    Num,
+}
+
+impl FlagType {
+    fn parse(self, flags: &str) -> Vec<Flag> {
+        match self {
+            FlagType::Char => flags.chars().map(|c| c as Flag).collect(),
+            FlagType::Long => {
+                let chars: Vec<char> = flags.chars().collect();
+                chars
+                    .chunks(2)
+                    .map(|pair| pair.iter().fold(0, |flag, &c| (flag << 32) | c as Flag))
+                    .collect()
+            }
+            FlagType::Num => flags
+                .split(',')
+                .filter_map(|n| n.trim().parse().ok())
+                .collect(),
+        }
+    }
+}
+
+/// The prefix and suffix rules of an `.aff` file.
+#[derive(Debug, Default)]
+struct Affixes {
+    flag_type: FlagType,
+    prefixes: Vec<Affix>,
+    suffixes: Vec<Affix>,
+}
+
+impl Affixes {
+    fn parse(aff: &str) -> Self {
+        let mut affixes = Affixes::default();
+        // Whether each flag's rules combine with affixes of the other kind.
+        let mut cross_products: HashMap<(bool, Flag), bool> = HashMap::new();
+        for line in aff.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["FLAG", format, ..] => {
+                    affixes.flag_type = match *format {
+                        "long" => FlagType::Long,
+                        "num" => FlagType::Num,
+                        _ => FlagType::Char,
+                    };
+                }
+                [kind @ ("PFX" | "SFX"), flag, rest @ ..] => {
+                    let is_prefix = *kind == "PFX";
+                    let Some(&flag) = affixes.flag_type.parse(flag).first() else {
+                        continue;
+                    };
+                    match rest {
+                        // The header: `SFX D Y 4`
+                        [cross, count] if count.parse::<usize>().is_ok() => {
+                            cross_products.insert((is_prefix, flag), *cross == "Y");
+                        }
+                        // A rule: `SFX D y ied [^aeiou]y`
+                        [strip, add, condition @ ..] => {
+                            let affix = Affix {
+                                flag,
+                                cross_product: cross_products
+                                    .get(&(is_prefix, flag))
+                                    .copied()
+                                    .unwrap_or(false),
+                                is_prefix,
+                                strip: affix_text(strip),
+                                // Flags of affixes on affixes aren't supported.
+                                add: affix_text(add.split('/').next().unwrap_or("")),
+                                condition: Condition::parse(condition.first().unwrap_or(&".")),
+                            };
+                            if is_prefix {
+                                affixes.prefixes.push(affix);
+                            } else {
+                                affixes.suffixes.push(affix);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        affixes
+    }
+}
+
+/// The text of an affix rule's strip or add field, where `0` means none.
+fn affix_text(field: &str) -> String {
+    if field == "0" {
+        String::new()
+    } else {
+        field.to_string()
+    }
+}
+
+/// One `PFX` or `SFX` rule: `strip` is taken off the word's start or end,
+/// `add` put there instead, if the word matches `condition` there.
+#[derive(Debug)]
+struct Affix {
+    flag: Flag,
+    cross_product: bool,
+    is_prefix: bool,
+    strip: String,
+    add: String,
+    condition: Condition,
+}
+
+impl Affix {
+    /// The word `word` was made from with this affix, if it could have been.
+    fn remove_from(&self, word: &str) -> Option<String> {
+        let stem = if self.is_prefix {
+            let rest = word.strip_prefix(self.add.as_str())?;
+            format!("{}{rest}", self.strip)
+        } else {
+            let rest = word.strip_suffix(self.add.as_str())?;
+            format!("{rest}{}", self.strip)
+        };
+        let matches = if self.is_prefix {
+            self.condition.matches_start(&stem)
+        } else {
+            self.condition.matches_end(&stem)
+        };
+        (!stem.is_empty() && stem != word && matches).then_some(stem)
+    }
+}
+
+/// An affix rule's condition, like `[^aeiou]y`: one set of characters per
+/// character at the start (prefixes) or end (suffixes) of the word.
+#[derive(Debug)]
+struct Condition {
+    sets: Vec<CharSet>,
+}
+
+#[derive(Debug)]
+enum CharSet {
+    Any,
+    OneOf(Vec<char>),
+    NoneOf(Vec<char>),
+}
+
+impl Condition {
+    fn parse(condition: &str) -> Self {
+        let mut sets = Vec::new();
+        let mut chars = condition.chars();
+        while let Some(c) = chars.next() {
+            sets.push(match c {
+                '.' => CharSet::Any,
+                '[' => {
+                    let mut group: Vec<char> = chars.by_ref().take_while(|&c| c != ']').collect();
+                    if group.first() == Some(&'^') {
+                        group.remove(0);
+                        CharSet::NoneOf(group)
+                    } else {
+                        CharSet::OneOf(group)
+                    }
+                }
+                c => CharSet::OneOf(vec![c]),
+            });
+        }
+        Condition { sets }
+    }
+
+    fn matches_start(&self, word: &str) -> bool {
+        let mut chars = word.chars();
+        self.sets
+            .iter()
+            .all(|set| chars.next().is_some_and(|c| set.contains(c)))
+    }
+
+    fn matches_end(&self, word: &str) -> bool {
+        let mut chars = word.chars().rev();
+        self.sets
+            .iter()
+            .rev()
+            .all(|set| chars.next().is_some_and(|c| set.contains(c)))
+    }
+}
+
+impl CharSet {
+    fn contains(&self, c: char) -> bool {
+        match self {
+            CharSet::Any => true,
+            CharSet::OneOf(group) => group.contains(&c),
+            CharSet::NoneOf(group) => !group.contains(&c),
+        }
+    }
+}
+
+/// The dictionaries for all languages, loaded on first use.
+pub struct Dictionaries {
+    dirs: Vec<PathBuf>,
+    loaded: HashMap<String, Option<Dictionary>>,
+}
+
+impl Dictionaries {
+    pub fn new(dirs: Vec<PathBuf>) -> Self {
+        Dictionaries {
+            dirs,
+            loaded: HashMap::new(),
+        }
+    }
+
+    /// `~/.config/piki/dictionaries` followed by the system's Hunspell
+    /// dictionaries.
+    pub fn default_dirs() -> Vec<PathBuf> {
+        let mut dirs = Vec::new();
+        if let Some(home) = std::env::var_os("HOME") {
+            dirs.push(PathBuf::from(home).join(".config/piki/dictionaries"));
+        }
+        dirs.push(PathBuf::from("/usr/share/hunspell"));
+        dirs
+    }
+
+    /// Insert a dictionary for `lang`, replacing any loaded from disk.
+    pub fn insert(&mut self, lang: &str, dictionary: Dictionary) {
+        self.loaded.insert(normalize_lang(lang), Some(dictionary));
+    }
+
+    /// The dictionary for `lang` (e.g. `de` or `en-US`), if one is installed.
+    pub fn get(&mut self, lang: &str) -> Option<&Dictionary> {
+        let lang = normalize_lang(lang);
+        if !self.loaded.contains_key(&lang) {
+            let dictionary = self
+                .find_file(&lang)
+                .and_then(|path| Dictionary::load(&path).ok());
+            self.loaded.insert(lang.clone(), dictionary);
+        }
+        self.loaded.get(&lang)?.as_ref()
+    }
+
+    /// `de.dic`, or else the first regional variant such as `de_DE.dic`.
+    fn find_file(&self, lang: &str) -> Option<PathBuf> {
+        for dir in &self.dirs {
+            let exact = dir.join(format!("{lang}.dic"));
+            if exact.is_file() {
+                return Some(exact);
+            }
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            let prefix = format!("{lang}_");
+            let mut variants: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| {
+                    path.extension().is_some_and(|ext| ext == "dic")
+                        && path
+                            .file_name()
+                            .and_then(|n| n.to_str())
+                            .is_some_and(|n| n.starts_with(&prefix))
+                })
+                .collect();
+            variants.sort();
+            if let Some(path) = variants.into_iter().next() {
+                return Some(path);
+            }
+        }
+        None
+    }
+}
+
+/// `en-US` → `en_US`, the way dictionary files are named.
+fn normalize_lang(lang: &str) -> String {
+    lang.trim().replace('-', "_")
+}
+
+/// The misspelled words of `content`. Words in languages without an installed
+/// dictionary are skipped.
+pub fn check(content: &str, dictionaries: &mut Dictionaries) -> Vec<Word> {
+    checkable_words(content)
+        .into_iter()
+        .filter(|word| {
+            dictionaries
+                .get(&word.lang)
+                .is_some_and(|dictionary| !dictionary.contains(&word.text))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(words: &[Word]) -> Vec<&str> {
+        words.iter().map(|w| w.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_page_language() {
+        assert_eq!(
+            page_language("---\ntitle: x\nlang: de\n---\nHallo").as_deref(),
+            Some("de")
+        );
+        assert_eq!(page_language("lang: de\n"), None);
+        assert_eq!(page_language("# Title\n"), None);
+    }
+
+    #[test]
+    fn test_checkable_words_skip_code_links_and_tags() {
+        let content = "Some `inlineCode` text #tagged\n\n```\nfn main() {}\n```\n\nSee [[some-note|the label]] and [site](https://example.com/path) or https://x.org ABC v2 it's\n";
+        let words = checkable_words(content);
+        assert_eq!(
+            texts(&words),
+            vec![
+                "Some", "text", "See", "the", "label", "and", "site", "or", "it's"
+            ]
+        );
+        assert_eq!((words[1].line, words[1].column), (1, 19));
+    }
+
+    #[test]
+    fn test_checkable_words_languages() {
+        let content = "---\nlang: de\n---\nGuten Tag\n\n<!-- lang: en -->\nHello there\nstill English\n\nWieder Deutsch\n";
+        let words = checkable_words(content);
+        let langs: Vec<(&str, &str)> = words
+            .iter()
+            .map(|w| (w.text.as_str(), w.lang.as_str()))
+            .collect();
+        assert_eq!(
+            langs,
+            vec![
+                ("Guten", "de"),
+                ("Tag", "de"),
+                ("Hello", "en"),
+                ("there", "en"),
+                ("still", "en"),
+                ("English", "en"),
+                ("Wieder", "de"),
+                ("Deutsch", "de"),
+            ]
+        );
+        assert_eq!(words[0].line, 4);
+    }
+
+    #[test]
+    fn test_check_switches_dictionaries() {
+        let mut dictionaries = Dictionaries::new(Vec::new());
+        dictionaries.insert("en", Dictionary::parse("3\nhello/S\nworld\nthere\n"));
+        dictionaries.insert("de", Dictionary::from_words(["hallo", "Welt"]));
+
+        let content =
+            "Hello wrold\n\n<!-- lang: de -->\nHallo Welt welt\n\n<!-- lang: fr -->\nBonjour\n";
+        let misspelled = check(content, &mut dictionaries);
+        assert_eq!(texts(&misspelled), vec!["wrold", "welt"]);
+        assert_eq!(misspelled[1].lang, "de");
+    }
+
+    #[test]
+    fn test_dictionary_applies_affixes() {
+        let aff = "SET UTF-8\n\
+                   PFX U Y 1\n\
+                   PFX U 0 un .\n\
+                   \n\
+                   SFX D Y 3\n\
+                   SFX D 0 ed [^ey]\n\
+                   SFX D 0 d e\n\
+                   SFX D y ied [^aeiou]y\n\
+                   \n\
+                   SFX S N 1\n\
+                   SFX S 0 s .\n";
+        let dic = "4\nwalk/DS\nbake/D\ncarry/DU\nlock/U\n";
+        let dictionary = Dictionary::parse_with_affixes(dic, aff);
+
+        for word in [
+            "walk",
+            "walked",
+            "walks",
+            "baked",
+            "carried",
+            "uncarried",
+            "unlock",
+            "Walked",
+        ] {
+            assert!(dictionary.contains(word), "{word}");
+        }
+        // Suffixes the word has no flag for, or whose condition fails.
+        for word in ["bakes", "locked", "bakeed", "carryed", "unwalk", "unwalks"] {
+            assert!(!dictionary.contains(word), "{word}");
+        }
+    }
+
+    #[test]
+    fn test_dictionary_long_flags() {
+        let aff = "FLAG long\nSFX Aa Y 1\nSFX Aa 0 en .\n";
+        let dictionary = Dictionary::parse_with_affixes("1\nHaus/AaBb\n", aff);
+        assert!(dictionary.contains("Hausen"));
+        assert!(!Dictionary::parse("1\nHaus/AaBb\n").contains("Hausen"));
+    }
+
+    #[test]
+    fn test_dictionary_files_are_found_by_language() {
+        let dir = std::env::temp_dir().join("piki_test_dictionaries");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("de_DE.dic"), "1\nHaus/N\n").unwrap();
+        fs::write(dir.join("de_DE.aff"), "SFX N Y 1\nSFX N 0 es .\n").unwrap();
+
+        let mut dictionaries = Dictionaries::new(vec![dir.clone()]);
+        assert!(dictionaries.get("de").unwrap().contains("Haus"));
+        assert!(dictionaries.get("de").unwrap().contains("Hauses"));
+        assert!(dictionaries.get("de-DE").is_some());
+        assert!(dictionaries.get("fr").is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
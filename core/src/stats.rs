@@ -0,0 +1,198 @@
+//! Totals for a whole wiki: pages, words, links and todos, with its largest
+//! and most-linked pages. The `!stats` plugin ([`StatsPlugin`]) shows them.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::document::DocumentStore;
+use crate::graph::{note_links, resolve};
+use crate::plugin::{Plugin, extract_todos, folder_prefix, is_checked};
+
+/// Number of pages listed as largest and most linked.
+const TOP: usize = 10;
+
+/// Totals for the pages of a wiki.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WikiStats {
+    pub pages: usize,
+    pub words: usize,
+    /// Links to other notes, including ones to notes that don't exist.
+    pub links: usize,
+    pub open_todos: usize,
+    pub done_todos: usize,
+    /// The pages with the most words, with their word counts, largest first.
+    pub largest: Vec<(String, usize)>,
+    /// The pages linked from the most other pages, with the number of those,
+    /// most linked first.
+    pub most_linked: Vec<(String, usize)>,
+}
+
+/// The totals for the pages of `store` in `folder` (all pages if empty).
+/// Links into the folder from pages outside it count towards its most-linked
+/// pages.
+pub fn collect(store: &DocumentStore, folder: &str) -> Result<WikiStats, String> {
+    let folder = folder_prefix(folder);
+    let pages: BTreeSet<String> = store.list_all_documents()?.into_iter().collect();
+
+    let mut stats = WikiStats::default();
+    let mut words: Vec<(String, usize)> = Vec::new();
+    let mut linked_from: HashMap<&String, BTreeSet<&String>> = HashMap::new();
+    for page in &pages {
+        let Ok(doc) = store.load(page) else {
+            continue;
+        };
+        let in_folder = page.starts_with(&folder);
+        let links = note_links(&doc.content);
+        for target in &links {
+            if let Some(target) = resolve(page, target, &pages)
+                && target != page
+                && target.starts_with(&folder)
+            {
+                linked_from.entry(target).or_default().insert(page);
+            }
+        }
+        if !in_folder {
+            continue;
+        }
+
+        let count = word_count(&doc.content);
+        stats.pages += 1;
+        stats.words += count;
+        stats.links += links.len();
+        for (_, todo) in extract_todos(&doc.content) {
+            if is_checked(&todo) {
+                stats.done_todos += 1;
+            } else {
+                stats.open_todos += 1;
+            }
+        }
+        words.push((page.clone(), count));
+    }
+
+    stats.largest = top(words);
+    stats.most_linked = top(linked_from
+        .into_iter()
+        .map(|(page, from)| (page.clone(), from.len()))
+        .collect());
+    Ok(stats)
+}
+
+/// The words of `content`, leaving out Markdown markers such as `#`, `-` or
+/// a todo's `[x]`.
+fn word_count(content: &str) -> usize {
+    content
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric) && !matches!(*word, "[x]" | "[X]"))
+        .count()
+}
+
+/// The [`TOP`] entries with the highest counts, ties by name.
+fn top(mut counts: Vec<(String, usize)>) -> Vec<(String, usize)> {
+    counts.retain(|(_, count)| *count > 0);
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.truncate(TOP);
+    counts
+}
+
+/// Built-in plugin showing [`WikiStats`]. With a folder as argument
+/// (`!stats:projects/`) only the pages in it are counted.
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn generate_content(&self, store: &DocumentStore, args: &str) -> Result<String, String> {
+        let stats = collect(store, args)?;
+        let folder = folder_prefix(args);
+
+        let mut content = String::from("# Statistics\n\n");
+        if folder.is_empty() {
+            content.push_str("*Totals across your wiki*\n\n");
+        } else {
+            content.push_str(&format!("*Totals for {folder}*\n\n"));
+        }
+
+        if stats.pages == 0 {
+            content.push_str("No notes found.\n");
+            return Ok(content);
+        }
+
+        content.push_str(&format!("- **Pages:** {}\n", stats.pages));
+        content.push_str(&format!("- **Words:** {}\n", stats.words));
+        content.push_str(&format!("- **Links:** {}\n", stats.links));
+        content.push_str(&format!(
+            "- **Todos:** {} open, {} done\n\n",
+            stats.open_todos, stats.done_todos
+        ));
+
+        content.push_str("## Largest pages\n\n");
+        for (i, (page, words)) in stats.largest.iter().enumerate() {
+            content.push_str(&format!("{}. [[{}]] — {} words\n", i + 1, page, words));
+        }
+        content.push('\n');
+
+        if !stats.most_linked.is_empty() {
+            content.push_str("## Most linked pages\n\n");
+            for (i, (page, from)) in stats.most_linked.iter().enumerate() {
+                let pages = if *from == 1 { "page" } else { "pages" };
+                content.push_str(&format!(
+                    "{}. [[{}]] — linked from {} {}\n",
+                    i + 1,
+                    page,
+                    from,
+                    pages
+                ));
+            }
+            content.push('\n');
+        }
+
+        content.push_str("---\n\n");
+        content.push_str("*This note is generated by the `stats` plugin*\n");
+
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_stats() {
+        let dir = std::env::temp_dir().join("piki-test-stats");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("projects")).unwrap();
+        fs::write(
+            dir.join("home.md"),
+            "# Home\n\nSee [[projects/plan]] and [[missing]].\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("projects/plan.md"),
+            "# Plan\n\n- [ ] draft\n- [x] outline\n\nBack [home](../home.md), [[plan]].\n",
+        )
+        .unwrap();
+        fs::write(dir.join("projects/notes.md"), "Words about the [[plan]]\n").unwrap();
+        let store = DocumentStore::new(dir.clone());
+
+        let stats = collect(&store, "").unwrap();
+        assert_eq!(stats.pages, 3);
+        assert_eq!(stats.words, 5 + 6 + 4);
+        assert_eq!(stats.links, 2 + 2 + 1);
+        assert_eq!((stats.open_todos, stats.done_todos), (1, 1));
+        assert_eq!(stats.largest[0], ("projects/plan".to_string(), 6));
+        assert_eq!(
+            stats.most_linked,
+            vec![("projects/plan".to_string(), 2), ("home".to_string(), 1)]
+        );
+
+        let stats = collect(&store, "projects").unwrap();
+        assert_eq!(stats.pages, 2);
+        assert_eq!(stats.most_linked, vec![("projects/plan".to_string(), 2)]);
+
+        let page = StatsPlugin.generate_content(&store, "").unwrap();
+        assert!(page.contains("- **Todos:** 1 open, 1 done\n"));
+        assert!(page.contains("1. [[projects/plan]] — linked from 2 pages\n"));
+        assert!(page.contains("2. [[home]] — linked from 1 page\n"));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,390 @@
+//! Inline `#tags` in note text.
+//!
+//! A tag is a `#` directly followed by letters, digits, `_`, `-` or `/`, at the
+//! start of a line or after whitespace or an opening bracket — so `#work`,
+//! `#project/piki` and `(#draft)` are tags, while headings (`# Title`), URL
+//! fragments (`page#section`) and issue references (`#42`) are not. Tags inside
+//! code spans and fenced code blocks are ignored. Tags compare
+//! case-insensitively.
+//!
+//! [`rename_tag_everywhere`] renames a tag across the wiki: in the text and in
+//! the `tags:` of each page's frontmatter, together with its subtags
+//! (`#project/piki` follows a rename of `project`).
+
+use std::ops::Range;
+
+use crate::DocumentStore;
+use crate::frontmatter;
+
+/// Whether `c` may appear in a tag name.
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '/'
+}
+
+/// Whether `line` opens or closes a fenced code block.
+pub(crate) fn is_code_fence(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+/// Byte ranges of the tag *names* (without the leading `#`) in a single line
+/// of text. Callers are responsible for skipping fenced code blocks.
+pub fn tag_spans(line: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut in_code = false;
+    let mut prev: Option<char> = None;
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if c == '`' {
+            in_code = !in_code;
+        } else if c == '#'
+            && !in_code
+            && prev.is_none_or(|p| p.is_whitespace() || p == '(' || p == '[')
+        {
+            let start = i + 1;
+            let mut end = start;
+            while let Some(&(j, n)) = chars.peek() {
+                if !is_tag_char(n) {
+                    break;
+                }
+                end = j + n.len_utf8();
+                chars.next();
+            }
+            // Trailing separators are punctuation, not part of the name.
+            let name = line[start..end].trim_end_matches(['-', '/']);
+            let end = start + name.len();
+            if name.chars().any(|ch| !ch.is_ascii_digit()) {
+                spans.push(start..end);
+            }
+            prev = line[..end].chars().next_back();
+            continue;
+        }
+        prev = Some(c);
+    }
+
+    spans
+}
+
+/// All distinct tags in `content`, in order of first appearance. Tags that
+/// differ only in case are reported once, as first written.
+pub fn extract_tags(content: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    let mut in_fence = false;
+
+    for line in content.lines() {
+        if is_code_fence(line) {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        for span in tag_spans(line) {
+            let tag = &line[span];
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                tags.push(tag.to_string());
+            }
+        }
+    }
+
+    tags
+}
+
+/// Whether `content` carries `tag` (given with or without the leading `#`).
+pub fn has_tag(content: &str, tag: &str) -> bool {
+    let tag = tag.trim_start_matches('#');
+    extract_tags(content)
+        .iter()
+        .any(|t| t.eq_ignore_ascii_case(tag))
+}
+
+/// Whether the page `content` carries `tag`: in its text or among the
+/// `tags:` of its frontmatter.
+pub fn page_has_tag(content: &str, tag: &str) -> bool {
+    let tag = tag.trim_start_matches('#');
+    has_tag(content, tag)
+        || frontmatter::properties(frontmatter::split(content).0)
+            .iter()
+            .filter(|(key, _)| key == "tags")
+            .flat_map(|(_, tags)| tags.split(','))
+            .any(|t| t.trim().trim_start_matches('#').eq_ignore_ascii_case(tag))
+}
+
+/// Whether `name` (without the `#`) can be written as a tag.
+pub fn is_valid_tag(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(is_tag_char)
+        && !name.ends_with(['-', '/'])
+        && name.chars().any(|c| !c.is_ascii_digit())
+}
+
+/// `tag` with `old` — or the parent part of a subtag of `old` — replaced by
+/// `new`, or `None` if it is neither.
+fn renamed(tag: &str, old: &str, new: &str) -> Option<String> {
+    let prefix = tag.get(..old.len())?;
+    let rest = &tag[old.len()..];
+    (prefix.eq_ignore_ascii_case(old) && (rest.is_empty() || rest.starts_with('/')))
+        .then(|| format!("{new}{rest}"))
+}
+
+/// A frontmatter tag value (`work`, `"#work"`) with the tag renamed, keeping
+/// its quotes and `#`.
+fn renamed_value(value: &str, old: &str, new: &str) -> Option<String> {
+    let trimmed = value.trim();
+    let quote = trimmed
+        .chars()
+        .next()
+        .filter(|&c| (c == '"' || c == '\'') && trimmed.len() > 1 && trimmed.ends_with(c));
+    let inner = match quote {
+        Some(_) => &trimmed[1..trimmed.len() - 1],
+        None => trimmed,
+    };
+    let hash = if inner.starts_with('#') { "#" } else { "" };
+    let tag = renamed(&inner[hash.len()..], old, new)?;
+    let quote = quote.map(String::from).unwrap_or_default();
+    let start = value.len() - value.trim_start().len();
+    let end = value.trim_end().len();
+    Some(format!(
+        "{}{quote}{hash}{tag}{quote}{}",
+        &value[..start],
+        &value[end..]
+    ))
+}
+
+/// The comma-separated tag values in `list` renamed; spacing is kept.
+fn rename_values(list: &str, old: &str, new: &str, count: &mut usize) -> String {
+    list.split(',')
+        .map(|value| match renamed_value(value, old, new) {
+            Some(value) => {
+                *count += 1;
+                value
+            }
+            None => value.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// A frontmatter line with the tags in its `tags:` value renamed: a list in
+/// brackets or separated by commas, or (in `block`) an item of a list below an
+/// empty `tags:`.
+fn rename_in_frontmatter(line: &str, block: &mut bool, old: &str, new: &str) -> (String, usize) {
+    let mut count = 0;
+    if *block {
+        let indent = line.len() - line.trim_start().len();
+        if let Some(item) = line[indent..].strip_prefix('-') {
+            let item = rename_values(item, old, new, &mut count);
+            return (format!("{}-{item}", &line[..indent]), count);
+        }
+        *block = false;
+    }
+    let Some((key, value)) = line.split_once(':') else {
+        return (line.to_string(), 0);
+    };
+    if !key.trim().eq_ignore_ascii_case("tags") || key.starts_with([' ', '\t']) {
+        return (line.to_string(), 0);
+    }
+    if value.trim().is_empty() {
+        *block = true;
+        return (line.to_string(), 0);
+    }
+    let value = match value
+        .trim()
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+    {
+        Some(list) => {
+            let open = value.find('[').expect("value starts with a bracket");
+            let close = value.rfind(']').expect("value ends with a bracket");
+            format!(
+                "{}[{}]{}",
+                &value[..open],
+                rename_values(list, old, new, &mut count),
+                &value[close + 1..]
+            )
+        }
+        None => rename_values(value, old, new, &mut count),
+    };
+    (format!("{key}:{value}"), count)
+}
+
+/// `content` with the tag `old` and its subtags renamed to `new` (both given
+/// with or without the `#`), and the number of tags renamed. Line endings and
+/// everything else are kept as they are.
+pub fn rename_tag(content: &str, old: &str, new: &str) -> (String, usize) {
+    let old = old.trim().trim_start_matches('#');
+    let new = new.trim().trim_start_matches('#');
+    let frontmatter = frontmatter::line_count(content);
+    let mut out = String::with_capacity(content.len());
+    let mut count = 0;
+    let mut in_fence = false;
+    let mut in_tags_block = false;
+
+    for (index, raw) in content.split_inclusive('\n').enumerate() {
+        let body = raw.trim_end_matches(['\n', '\r']);
+        let ending = &raw[body.len()..];
+        let line = if index > 0 && index + 1 < frontmatter {
+            let (line, n) = rename_in_frontmatter(body, &mut in_tags_block, old, new);
+            count += n;
+            line
+        } else if index < frontmatter {
+            body.to_string()
+        } else if is_code_fence(body) {
+            in_fence = !in_fence;
+            body.to_string()
+        } else if in_fence {
+            body.to_string()
+        } else {
+            let mut line = String::with_capacity(body.len());
+            let mut last = 0;
+            for span in tag_spans(body) {
+                if let Some(tag) = renamed(&body[span.clone()], old, new) {
+                    line.push_str(&body[last..span.start]);
+                    line.push_str(&tag);
+                    last = span.end;
+                    count += 1;
+                }
+            }
+            line.push_str(&body[last..]);
+            line
+        };
+        out.push_str(&line);
+        out.push_str(ending);
+    }
+
+    (out, count)
+}
+
+/// Rename the tag `old` to `new` in every page of `store`, returning the pages
+/// that carry it with the number of tags renamed in each, by name. With
+/// `dry_run`, nothing is written.
+pub fn rename_tag_everywhere(
+    store: &DocumentStore,
+    old: &str,
+    new: &str,
+    dry_run: bool,
+) -> Result<Vec<(String, usize)>, String> {
+    let old = old.trim().trim_start_matches('#');
+    let new = new.trim().trim_start_matches('#');
+    for name in [old, new] {
+        if !is_valid_tag(name) {
+            return Err(format!("'{name}' is not a valid tag name"));
+        }
+    }
+
+    let mut pages = store.list_all_documents()?;
+    pages.sort();
+    let mut renamed = Vec::new();
+    for page in pages {
+        let mut doc = store.load(&page)?;
+        let (content, count) = rename_tag(&doc.content, old, new);
+        if count == 0 {
+            continue;
+        }
+        if !dry_run && content != doc.content {
+            doc.content = content;
+            store.save(&doc)?;
+        }
+        renamed.push((page, count));
+    }
+    Ok(renamed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_tags() {
+        let content = "# Heading\n\nMeeting notes #work #Project/piki.\n(#draft) and #work again\n";
+        assert_eq!(extract_tags(content), vec!["work", "Project/piki", "draft"]);
+    }
+
+    #[test]
+    fn test_non_tags_are_ignored() {
+        let content =
+            "## Title\nSee page#section and issue #42, `#code`.\n\n```\n#not-a-tag\n```\n";
+        assert!(extract_tags(content).is_empty());
+    }
+
+    #[test]
+    fn test_tag_spans_exclude_hash_and_trailing_punctuation() {
+        let line = "todo #next- and #a/b/";
+        let spans = tag_spans(line);
+        let names: Vec<&str> = spans.into_iter().map(|r| &line[r]).collect();
+        assert_eq!(names, vec!["next", "a/b"]);
+    }
+
+    #[test]
+    fn test_has_tag_is_case_insensitive() {
+        assert!(has_tag("secret stuff #Private", "private"));
+        assert!(has_tag("secret stuff #private", "#PRIVATE"));
+        assert!(!has_tag("nothing here", "private"));
+        assert!(page_has_tag(
+            "---\ntags: [work, \"#Q3\"]\n---\nText\n",
+            "#q3"
+        ));
+        assert!(page_has_tag("Text #work\n", "work"));
+        assert!(!page_has_tag("---\ntitle: work\n---\nText\n", "work"));
+    }
+
+    #[test]
+    fn test_rename_tag() {
+        let content = "\
+---\r
+title: Plan\r
+tags: [work, \"Work/q3\", workshop]\r
+---\r
+Ideas #work and #work/q3, not #workshop.\r
+`#work` and\r
+```\r
+#work\r
+```\r
+";
+        let (renamed, count) = rename_tag(content, "#WORK", "job");
+        assert_eq!(count, 4);
+        assert_eq!(
+            renamed,
+            "\
+---\r
+title: Plan\r
+tags: [job, \"job/q3\", workshop]\r
+---\r
+Ideas #job and #job/q3, not #workshop.\r
+`#work` and\r
+```\r
+#work\r
+```\r
+"
+        );
+
+        let block = "---\ntags:\n  - work\n  - '#home'\nlang: de\n---\n";
+        let (renamed, count) = rename_tag(block, "home", "house");
+        assert_eq!(count, 1);
+        assert_eq!(
+            renamed,
+            "---\ntags:\n  - work\n  - '#house'\nlang: de\n---\n"
+        );
+    }
+
+    #[test]
+    fn test_rename_tag_everywhere() {
+        let dir = std::env::temp_dir().join("piki-test-rename-tag");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.md"), "#draft #draft\n").unwrap();
+        std::fs::write(dir.join("b.md"), "no tags\n").unwrap();
+        let store = DocumentStore::new(dir.clone());
+
+        let counts = rename_tag_everywhere(&store, "draft", "wip", true).unwrap();
+        assert_eq!(counts, vec![("a".to_string(), 2)]);
+        assert_eq!(store.load("a").unwrap().content, "#draft #draft\n");
+
+        rename_tag_everywhere(&store, "draft", "wip", false).unwrap();
+        assert_eq!(store.load("a").unwrap().content, "#wip #wip\n");
+        assert!(rename_tag_everywhere(&store, "wip", "42", false).is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,104 @@
+//! Templates for new notes.
+//!
+//! Each `*.md` file in the notes directory's `.piki/templates/` folder is a
+//! template named after the file: `.piki/templates/meeting.md` is `meeting`.
+//! When a note is created from it, `{{name}}` becomes the note's name
+//! (`projects/kickoff`), `{{title}}` its last part (`kickoff`) and `{{date}}`
+//! today's date (`2025-06-01`):
+//!
+//! ```markdown
+//! # {{title}}
+//!
+//! Date: {{date}}
+//!
+//! ## Attendees
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::dates::Date;
+use crate::edit_log::LOCAL_DIR;
+
+/// Folder inside [`LOCAL_DIR`] holding the templates.
+const TEMPLATE_DIR: &str = "templates";
+const EXTENSION: &str = "md";
+
+fn template_dir(notes_dir: &Path) -> PathBuf {
+    notes_dir.join(LOCAL_DIR).join(TEMPLATE_DIR)
+}
+
+/// The names of the templates in `notes_dir`, sorted.
+pub fn names(notes_dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(template_dir(notes_dir)) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == EXTENSION))
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// The text of the template `name`.
+pub fn load(notes_dir: &Path, name: &str) -> Result<String, String> {
+    let path = template_dir(notes_dir).join(format!("{name}.{EXTENSION}"));
+    fs::read_to_string(&path).map_err(|_| {
+        let names = names(notes_dir);
+        if names.is_empty() {
+            format!(
+                "No template '{}': there are no templates in {}",
+                name,
+                template_dir(notes_dir).display()
+            )
+        } else {
+            format!("No template '{}' (templates: {})", name, names.join(", "))
+        }
+    })
+}
+
+/// `template` filled in for the note `note`, created on `today`.
+pub fn expand(template: &str, note: &str, today: Date) -> String {
+    let title = note.rsplit('/').next().unwrap_or(note);
+    template
+        .replace("{{name}}", note)
+        .replace("{{title}}", title)
+        .replace("{{date}}", &today.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_are_found_and_filled_in() {
+        let dir = std::env::temp_dir().join("piki-test-template");
+        let _ = fs::remove_dir_all(&dir);
+        assert!(load(&dir, "meeting").unwrap_err().contains("no templates"));
+
+        fs::create_dir_all(template_dir(&dir)).unwrap();
+        fs::write(
+            template_dir(&dir).join("meeting.md"),
+            "# {{title}}\n\n{{date}}, filed as {{name}}\n",
+        )
+        .unwrap();
+        fs::write(template_dir(&dir).join("daily.md"), "").unwrap();
+        fs::write(template_dir(&dir).join("notes.txt"), "").unwrap();
+        assert_eq!(names(&dir), vec!["daily", "meeting"]);
+        assert_eq!(
+            load(&dir, "weekly").unwrap_err(),
+            "No template 'weekly' (templates: daily, meeting)"
+        );
+
+        let today = Date::parse("2025-06-01").unwrap();
+        assert_eq!(
+            expand(&load(&dir, "meeting").unwrap(), "projects/kickoff", today),
+            "# kickoff\n\n2025-06-01, filed as projects/kickoff\n"
+        );
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,270 @@
+//! Time tracking with `@worked(date, duration)` entries.
+//!
+//! Time spent on a note is recorded as a line like
+//! `- @worked(2025-03-14, 25m)` in the note itself, or in a separate timesheet
+//! note as `- @worked(2025-03-14, 25m) [[project]]`, in which case it counts
+//! towards the linked note. The `!timesheet` plugin ([`TimesheetPlugin`])
+//! sums up all entries per note and per ISO week.
+
+use crate::dates::Date;
+use crate::document::DocumentStore;
+use crate::plugin::Plugin;
+use std::collections::BTreeMap;
+
+const ENTRY_PREFIX: &str = "@worked(";
+
+/// One logged block of work.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkEntry {
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub minutes: u32,
+    /// The note linked on the entry's line, if any.
+    pub link: Option<String>,
+}
+
+/// Format an entry line for `minutes` of work on `date`, optionally crediting
+/// another note.
+pub fn format_entry(date: &str, minutes: u32, link: Option<&str>) -> String {
+    match link {
+        Some(note) => format!(
+            "- {ENTRY_PREFIX}{date}, {}) [[{note}]]",
+            format_duration(minutes)
+        ),
+        None => format!("- {ENTRY_PREFIX}{date}, {})", format_duration(minutes)),
+    }
+}
+
+/// Append `entry` (from [`format_entry`]) to a note's content. Consecutive
+/// entries form a single list; anything else is separated by a blank line.
+pub fn append_entry(content: &str, entry: &str) -> String {
+    let mut out = content.trim_end_matches('\n').to_string();
+    if !out.is_empty() {
+        let last_is_entry = out
+            .lines()
+            .next_back()
+            .is_some_and(|line| line.trim_start().starts_with(&format!("- {ENTRY_PREFIX}")));
+        out.push_str(if last_is_entry { "\n" } else { "\n\n" });
+    }
+    out.push_str(entry);
+    out.push('\n');
+    out
+}
+
+/// All `@worked(...)` entries in `content`. Malformed entries are skipped.
+pub fn parse_entries(content: &str) -> Vec<WorkEntry> {
+    let mut entries = Vec::new();
+
+    for line in content.lines() {
+        let mut rest = line;
+        while let Some(start) = rest.find(ENTRY_PREFIX) {
+            let after = &rest[start + ENTRY_PREFIX.len()..];
+            let Some(end) = after.find(')') else {
+                break;
+            };
+            if let Some((date, duration)) = after[..end].split_once(',')
+                && let Some(minutes) = parse_duration(duration.trim())
+                && Date::parse(date.trim()).is_some()
+            {
+                entries.push(WorkEntry {
+                    date: date.trim().to_string(),
+                    minutes,
+                    link: first_wiki_link(line),
+                });
+            }
+            rest = &after[end..];
+        }
+    }
+
+    entries
+}
+
+/// `25m`, `1h`, `1h30m` or plain minutes (`90`).
+fn parse_duration(text: &str) -> Option<u32> {
+    if let Ok(minutes) = text.parse() {
+        return Some(minutes);
+    }
+    let (hours, rest) = match text.split_once('h') {
+        Some((h, rest)) => (h.trim().parse::<u32>().ok()?, rest.trim()),
+        None => (0, text),
+    };
+    let minutes = match rest {
+        "" => 0,
+        m => m.strip_suffix('m')?.trim().parse::<u32>().ok()?,
+    };
+    Some(hours * 60 + minutes)
+}
+
+/// `95` → `1h 35m`, `25` → `25m`, `120` → `2h`.
+pub fn format_duration(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m}m"),
+        (h, 0) => format!("{h}h"),
+        (h, m) => format!("{h}h {m}m"),
+    }
+}
+
+fn first_wiki_link(line: &str) -> Option<String> {
+    let start = line.find("[[")? + 2;
+    let end = start + line[start..].find("]]")?;
+    let target = line[start..end].split('|').next()?.trim();
+    (!target.is_empty()).then(|| target.to_string())
+}
+
+/// The ISO 8601 week of a `YYYY-MM-DD` date, e.g. `2025-W11`.
+pub fn iso_week(date: &str) -> Option<String> {
+    let date = Date::parse(date)?;
+    // Weeks belong to the year of their Thursday.
+    let thursday = date.add_days(3 - i64::from(date.weekday()));
+    let (week_year, _, _) = thursday.ymd();
+    let week = Date::from_ymd(week_year, 1, 1)?.days_until(thursday) / 7 + 1;
+    Some(format!("{week_year}-W{week:02}"))
+}
+
+/// Built-in plugin summarizing `@worked` entries per note and per week
+pub struct TimesheetPlugin;
+
+impl Plugin for TimesheetPlugin {
+    fn generate_content(&self, store: &DocumentStore, _args: &str) -> Result<String, String> {
+        let mut per_note: BTreeMap<String, u32> = BTreeMap::new();
+        let mut per_week: BTreeMap<String, BTreeMap<String, u32>> = BTreeMap::new();
+
+        for doc_name in store.list_all_documents()? {
+            let Ok(doc) = store.load(&doc_name) else {
+                continue;
+            };
+            for entry in parse_entries(&doc.content) {
+                let note = entry.link.unwrap_or_else(|| doc_name.clone());
+                let Some(week) = iso_week(&entry.date) else {
+                    continue;
+                };
+                *per_note.entry(note.clone()).or_default() += entry.minutes;
+                *per_week.entry(week).or_default().entry(note).or_default() += entry.minutes;
+            }
+        }
+
+        let mut content = String::from("# Timesheet\n\n");
+        content.push_str("*Time logged with `@worked(date, duration)` across your wiki*\n\n");
+
+        if per_note.is_empty() {
+            content.push_str("No time logged yet.\n");
+            return Ok(content);
+        }
+
+        content.push_str("## By week\n\n");
+        for (week, notes) in per_week.iter().rev() {
+            let total: u32 = notes.values().sum();
+            content.push_str(&format!("### {} — {}\n\n", week, format_duration(total)));
+            for (note, minutes) in notes {
+                content.push_str(&format!("- [[{}]] — {}\n", note, format_duration(*minutes)));
+            }
+            content.push('\n');
+        }
+
+        content.push_str("## By note\n\n");
+        for (note, minutes) in &per_note {
+            content.push_str(&format!("- [[{}]] — {}\n", note, format_duration(*minutes)));
+        }
+        content.push('\n');
+
+        let total: u32 = per_note.values().sum();
+        content.push_str("---\n\n");
+        content.push_str(&format!("*{} logged in total*\n\n", format_duration(total)));
+        content.push_str("*This note is generated by the `timesheet` plugin*\n");
+
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_entries() {
+        let content = "Notes\n\n- @worked(2025-03-14, 25m)\n- @worked(2025-03-15, 1h30m) [[project]]\n@worked(bad, 5m) @worked(2025-03-16, 90)\n";
+        let entries = parse_entries(content);
+        assert_eq!(
+            entries,
+            vec![
+                WorkEntry {
+                    date: "2025-03-14".into(),
+                    minutes: 25,
+                    link: None
+                },
+                WorkEntry {
+                    date: "2025-03-15".into(),
+                    minutes: 90,
+                    link: Some("project".into())
+                },
+                WorkEntry {
+                    date: "2025-03-16".into(),
+                    minutes: 90,
+                    link: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_append_entry() {
+        let entry = format_entry("2025-03-14", 25, None);
+        assert_eq!(entry, "- @worked(2025-03-14, 25m)");
+        let once = append_entry("Some text\n", &entry);
+        assert_eq!(once, "Some text\n\n- @worked(2025-03-14, 25m)\n");
+        let twice = append_entry(&once, &format_entry("2025-03-15", 50, Some("x")));
+        assert_eq!(
+            twice,
+            "Some text\n\n- @worked(2025-03-14, 25m)\n- @worked(2025-03-15, 50m) [[x]]\n"
+        );
+        assert_eq!(append_entry("", &entry), "- @worked(2025-03-14, 25m)\n");
+    }
+
+    #[test]
+    fn test_iso_week() {
+        assert_eq!(iso_week("2025-03-14").as_deref(), Some("2025-W11"));
+        // Belongs to the last week of the previous year ...
+        assert_eq!(iso_week("2021-01-03").as_deref(), Some("2020-W53"));
+        // ... or the first week of the next one.
+        assert_eq!(iso_week("2024-12-30").as_deref(), Some("2025-W01"));
+        assert_eq!(iso_week("nonsense"), None);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(25), "25m");
+        assert_eq!(format_duration(120), "2h");
+        assert_eq!(format_duration(95), "1h 35m");
+    }
+
+    #[test]
+    fn test_timesheet_plugin() {
+        let temp_dir = std::env::temp_dir().join("piki_test_timesheet");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+        fs::write(
+            temp_dir.join("project.md"),
+            "# Project\n\n- @worked(2025-03-14, 25m)\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.join("timesheet.md"),
+            "- @worked(2025-03-17, 1h) [[project]]\n- @worked(2025-03-17, 30m) [[other]]\n",
+        )
+        .unwrap();
+
+        let store = DocumentStore::new(temp_dir.clone());
+        let content = TimesheetPlugin.generate_content(&store, "").unwrap();
+        assert!(
+            content.contains("### 2025-W12 — 1h 30m\n\n- [[other]] — 30m\n- [[project]] — 1h\n")
+        );
+        assert!(content.contains("### 2025-W11 — 25m\n"));
+        assert!(content.contains("- [[project]] — 1h 25m\n"));
+        assert!(content.contains("*1h 55m logged in total*"));
+        // Newest week first.
+        assert!(content.find("2025-W12").unwrap() < content.find("2025-W11").unwrap());
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+}
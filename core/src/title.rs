@@ -0,0 +1,168 @@
+//! Human-readable titles for pages and wikis, as shown in window and browser
+//! tab titles.
+//!
+//! A page's title is the `title:` from its frontmatter, or else its first
+//! heading with the inline markup stripped, or else the note name. A wiki is
+//! named after its directory.
+
+use crate::frontmatter;
+use crate::tags::is_code_fence;
+use std::path::Path;
+
+/// Separates the page title from the wiki name.
+pub const SEPARATOR: &str = " — ";
+
+/// The title of the page `name` with the given content.
+pub fn page_title(name: &str, content: &str) -> String {
+    frontmatter_title(content)
+        .or_else(|| first_heading(content))
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// The name of the wiki in `dir`: its directory name, without a leading dot.
+pub fn wiki_name(dir: &Path) -> String {
+    let name = dir
+        .canonicalize()
+        .ok()
+        .and_then(|dir| dir.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .or_else(|| dir.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_default();
+    let name = name.trim_start_matches('.');
+    if name.is_empty() {
+        "Piki".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// `"Page — Wiki"`, or just the wiki name for a page without a title.
+pub fn window_title(page: &str, wiki: &str) -> String {
+    if page.is_empty() {
+        wiki.to_string()
+    } else {
+        format!("{page}{SEPARATOR}{wiki}")
+    }
+}
+
+fn frontmatter_title(content: &str) -> Option<String> {
+    let mut lines = content.lines();
+    if lines.next()?.trim_end() != "---" {
+        return None;
+    }
+    for line in lines {
+        if line.trim_end() == "---" {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':')
+            && key.trim().eq_ignore_ascii_case("title")
+        {
+            let value = value.trim().trim_matches(['"', '\'']);
+            return (!value.is_empty()).then(|| value.to_string());
+        }
+    }
+    None
+}
+
+/// The text of the first ATX or setext heading outside code blocks.
+fn first_heading(content: &str) -> Option<String> {
+    let mut in_code = false;
+    let mut previous: Option<&str> = None;
+    for line in content.lines().skip(frontmatter::line_count(content)) {
+        if is_code_fence(line) {
+            in_code = !in_code;
+            previous = None;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        let trimmed = line.trim();
+        if let Some(rest) = trimmed.strip_prefix('#') {
+            let rest = rest.trim_start_matches('#');
+            if trimmed.len() - rest.len() <= 6 && (rest.is_empty() || rest.starts_with(' ')) {
+                let text = plain_text(rest.trim().trim_end_matches('#').trim());
+                if !text.is_empty() {
+                    return Some(text);
+                }
+            }
+        }
+        if let Some(prev) = previous
+            && !trimmed.is_empty()
+            && (trimmed.chars().all(|c| c == '=') || trimmed.chars().all(|c| c == '-'))
+        {
+            let text = plain_text(prev.trim());
+            if !text.is_empty() {
+                return Some(text);
+            }
+        }
+        previous =
+            (!trimmed.is_empty() && line.starts_with(|c: char| !c.is_whitespace())).then_some(line);
+    }
+    None
+}
+
+/// `text` without emphasis, code and link markup.
+pub(crate) fn plain_text(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' | '_' | '`' | '~' | '[' => {}
+            '\\' => out.extend(chars.next()),
+            // Drop a link's target along with its brackets.
+            ']' => {
+                if chars.peek() == Some(&'(') {
+                    for c in chars.by_ref() {
+                        if c == ')' {
+                            break;
+                        }
+                    }
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_titles() {
+        assert_eq!(
+            page_title("roadmap", "# The *Big* Plan\n\nText\n"),
+            "The Big Plan"
+        );
+        assert_eq!(
+            page_title("roadmap", "Intro\n\n## [Q3](q3) goals ##\n"),
+            "Q3 goals"
+        );
+        assert_eq!(
+            page_title("roadmap", "Roadmap 2026\n============\n"),
+            "Roadmap 2026"
+        );
+        assert_eq!(
+            page_title("roadmap", "```\n# not a heading\n```\n#hashtag\n"),
+            "roadmap"
+        );
+        assert_eq!(
+            page_title("roadmap", "---\ntitle: \"Plans\"\n---\n# Heading\n"),
+            "Plans"
+        );
+        assert_eq!(
+            page_title("roadmap", "---\nlang: de\n---\nText\n"),
+            "roadmap"
+        );
+        assert_eq!(page_title("roadmap", ""), "roadmap");
+    }
+
+    #[test]
+    fn window_titles() {
+        assert_eq!(wiki_name(Path::new("/nonexistent/Work Wiki")), "Work Wiki");
+        assert_eq!(wiki_name(Path::new("/nonexistent/.notes")), "notes");
+        assert_eq!(window_title("Roadmap", "Work Wiki"), "Roadmap — Work Wiki");
+        assert_eq!(window_title("", "Work Wiki"), "Work Wiki");
+    }
+}
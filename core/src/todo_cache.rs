@@ -0,0 +1,227 @@
+//! The todos of every note, cached so the `!todo` page stays quick on large
+//! wikis.
+//!
+//! Reading and parsing every note on each visit of `!todo` gets slow once a
+//! wiki has thousands of notes. The todos found in each note are kept in
+//! `.piki/todo-cache.tsv` together with the note's modification time and size,
+//! so a scan only reads the notes that changed since the last one; notes
+//! without todos are remembered as such and never read again until they
+//! change. Notes that have to be read skip the line-by-line parse unless they
+//! contain a checkbox at all.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::DocumentStore;
+use crate::edit_log::{self, LOCAL_DIR};
+use crate::encryption;
+use crate::plugin::extract_todos;
+
+const CACHE_FILE: &str = "todo-cache.tsv";
+
+/// A note and its todos, each with its 1-based line number.
+pub type NoteTodos = (String, Vec<(usize, String)>);
+
+/// What the cache knows about one note.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    /// Modification time in nanoseconds since the Unix epoch.
+    mtime: u128,
+    size: u64,
+    /// 1-based line numbers and lines of the note's todos.
+    todos: Vec<(usize, String)>,
+}
+
+fn cache_path(notes_dir: &Path) -> PathBuf {
+    notes_dir.join(LOCAL_DIR).join(CACHE_FILE)
+}
+
+/// Parse the cache file: a `page` line with the note's modification time,
+/// size and name for each note, followed by a `todo` line for each of its
+/// todos. Names and todos come last on their lines, so they may hold tabs.
+fn parse(text: &str) -> HashMap<String, Entry> {
+    let mut entries: HashMap<String, Entry> = HashMap::new();
+    let mut current: Option<String> = None;
+    for line in text.lines() {
+        match line.split_once('\t') {
+            Some(("page", rest)) => {
+                let mut fields = rest.splitn(3, '\t');
+                current = match (fields.next(), fields.next(), fields.next()) {
+                    (Some(mtime), Some(size), Some(name)) => match (mtime.parse(), size.parse()) {
+                        (Ok(mtime), Ok(size)) => {
+                            let todos = Vec::new();
+                            entries.insert(name.to_string(), Entry { mtime, size, todos });
+                            Some(name.to_string())
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                };
+            }
+            Some(("todo", rest)) => {
+                if let Some(entry) = current.as_ref().and_then(|name| entries.get_mut(name))
+                    && let Some((line, todo)) = rest.split_once('\t')
+                    && let Ok(line) = line.parse()
+                {
+                    entry.todos.push((line, todo.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+    entries
+}
+
+fn serialize(entries: &HashMap<String, Entry>) -> String {
+    let mut names: Vec<&String> = entries.keys().collect();
+    names.sort();
+    let mut text = String::new();
+    for name in names {
+        let entry = &entries[name];
+        let _ = writeln!(text, "page\t{}\t{}\t{}", entry.mtime, entry.size, name);
+        for (line, todo) in &entry.todos {
+            let _ = writeln!(text, "todo\t{line}\t{todo}");
+        }
+    }
+    text
+}
+
+/// Whether `content` may hold a todo: a cheap byte search that saves parsing
+/// the many notes without any.
+fn may_have_todos(content: &str) -> bool {
+    content.contains("[ ]") || content.contains("[x]") || content.contains("[X]")
+}
+
+/// The todos of every note in `store` with at least one, sorted by note. Reads only the notes that
+/// changed since the last scan, and updates the cache.
+pub fn scan(store: &DocumentStore) -> Result<Vec<NoteTodos>, String> {
+    let notes_dir = store.base_path();
+    let cached = fs::read_to_string(cache_path(notes_dir))
+        .map(|text| parse(&text))
+        .unwrap_or_default();
+
+    let mut entries = HashMap::with_capacity(cached.len());
+    let mut changed = false;
+    let mut encrypted: Vec<NoteTodos> = Vec::new();
+    for name in store.list_all_documents()? {
+        let path = store.path_for(&name);
+        let Ok(meta) = fs::metadata(&path) else {
+            continue;
+        };
+        if encryption::is_encrypted(&path) {
+            // The cache file isn't encrypted, so the todos of encrypted notes
+            // stay out of it; the store keeps their decrypted content anyway.
+            if let Ok(doc) = store.load(&name) {
+                let todos = extract_todos(&doc.content);
+                if !todos.is_empty() {
+                    encrypted.push((name, todos));
+                }
+            }
+            continue;
+        }
+        let mtime = meta
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_nanos());
+        let size = meta.len();
+
+        let entry = match cached.get(&name) {
+            Some(entry) if entry.mtime == mtime && entry.size == size => entry.clone(),
+            _ => {
+                changed = true;
+                let Ok(doc) = store.load(&name) else {
+                    continue;
+                };
+                let todos = if may_have_todos(&doc.content) {
+                    extract_todos(&doc.content)
+                } else {
+                    Vec::new()
+                };
+                Entry { mtime, size, todos }
+            }
+        };
+        entries.insert(name, entry);
+    }
+    changed |= entries.len() != cached.len();
+
+    // The cache only saves time; failing to write it is no reason to fail.
+    if changed && edit_log::local_dir(notes_dir).is_ok() {
+        let _ = edit_log::replace_local_file(&cache_path(notes_dir), &serialize(&entries));
+    }
+
+    let mut notes: Vec<NoteTodos> = entries
+        .into_iter()
+        .filter(|(_, entry)| !entry.todos.is_empty())
+        .map(|(name, entry)| (name, entry.todos))
+        .chain(encrypted)
+        .collect();
+    notes.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(notes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn touch(path: &Path, secs: u64) {
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn rescans_only_changed_notes() {
+        let dir = std::env::temp_dir().join("piki-test-todo-cache");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.md"), "# A\n- [ ] one\twith a tab\n").unwrap();
+        fs::write(dir.join("b.md"), "no todos\n").unwrap();
+        fs::write(dir.join("c.md"), "- [x] done\n").unwrap();
+        let store = DocumentStore::new(dir.clone());
+
+        let first = scan(&store).unwrap();
+        assert_eq!(
+            first,
+            vec![
+                (
+                    "a".to_string(),
+                    vec![(2, "- [ ] one\twith a tab".to_string())]
+                ),
+                ("c".to_string(), vec![(1, "- [x] done".to_string())]),
+            ]
+        );
+        assert!(dir.join(".piki/todo-cache.tsv").is_file());
+
+        // A cached note is not read again: rewriting it behind the cache's back
+        // with the same size and time goes unnoticed.
+        touch(&dir.join("c.md"), 1_000);
+        scan(&store).unwrap();
+        fs::write(dir.join("c.md"), "- [x] DONE\n").unwrap();
+        touch(&dir.join("c.md"), 1_000);
+        assert_eq!(scan(&store).unwrap()[1].1[0].1, "- [x] done");
+
+        // Changed and removed notes are picked up.
+        fs::write(dir.join("b.md"), "now\n- [ ] two\n").unwrap();
+        fs::remove_file(dir.join("c.md")).unwrap();
+        assert_eq!(
+            scan(&store).unwrap(),
+            vec![
+                (
+                    "a".to_string(),
+                    vec![(2, "- [ ] one\twith a tab".to_string())]
+                ),
+                ("b".to_string(), vec![(2, "- [ ] two".to_string())]),
+            ]
+        );
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,87 @@
+//! Named wikis: the `[wikis]` table of `~/.pikirc` gives names to notes
+//! directories, so `piki -w work` and the GUI's Switch Wiki menu can open
+//! them without spelling out their paths:
+//!
+//! ```toml
+//! [wikis]
+//! personal = "~/.piki"
+//! work = "~/Documents/work-notes"
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The directory of the wiki `name`, with a leading `~` standing for the
+/// home directory.
+pub fn directory(wikis: &BTreeMap<String, String>, name: &str) -> Result<PathBuf, String> {
+    let Some(dir) = wikis.get(name) else {
+        if wikis.is_empty() {
+            return Err(format!(
+                "Unknown wiki '{name}': no wikis are set up in the [wikis] section of ~/.pikirc"
+            ));
+        }
+        let known: Vec<&str> = wikis.keys().map(String::as_str).collect();
+        return Err(format!(
+            "Unknown wiki '{name}' (known wikis: {})",
+            known.join(", ")
+        ));
+    };
+    Ok(expand_home(
+        dir,
+        std::env::var_os("HOME").as_deref().map(Path::new),
+    ))
+}
+
+/// The name of the wiki in `dir`, if it is one of `wikis`.
+pub fn name_of<'a>(wikis: &'a BTreeMap<String, String>, dir: &Path) -> Option<&'a str> {
+    let home = std::env::var_os("HOME");
+    let same = |other: &Path| match (dir.canonicalize(), other.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => dir == other,
+    };
+    wikis
+        .iter()
+        .find(|(_, path)| same(&expand_home(path, home.as_deref().map(Path::new))))
+        .map(|(name, _)| name.as_str())
+}
+
+fn expand_home(path: &str, home: Option<&Path>) -> PathBuf {
+    match (path.strip_prefix('~'), home) {
+        (Some(""), Some(home)) => home.to_path_buf(),
+        (Some(rest), Some(home)) if rest.starts_with('/') => home.join(&rest[1..]),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_wikis_by_name() {
+        let home = Some(Path::new("/home/me"));
+        assert_eq!(expand_home("~", home), PathBuf::from("/home/me"));
+        assert_eq!(
+            expand_home("~/work/notes", home),
+            PathBuf::from("/home/me/work/notes")
+        );
+        assert_eq!(expand_home("~bob/notes", home), PathBuf::from("~bob/notes"));
+        assert_eq!(expand_home("/srv/wiki", home), PathBuf::from("/srv/wiki"));
+        assert_eq!(expand_home("~/notes", None), PathBuf::from("~/notes"));
+
+        let mut wikis = BTreeMap::new();
+        assert!(directory(&wikis, "work").unwrap_err().contains("[wikis]"));
+        wikis.insert("work".to_string(), "/srv/wiki".to_string());
+        wikis.insert("home".to_string(), "/srv/home".to_string());
+        assert_eq!(
+            directory(&wikis, "work").unwrap(),
+            PathBuf::from("/srv/wiki")
+        );
+        assert_eq!(
+            directory(&wikis, "play").unwrap_err(),
+            "Unknown wiki 'play' (known wikis: home, work)"
+        );
+        assert_eq!(name_of(&wikis, Path::new("/srv/wiki")), Some("work"));
+        assert_eq!(name_of(&wikis, Path::new("/srv/other")), None);
+    }
+}
@@ -0,0 +1,358 @@
+//! Hard-wrapping Markdown at a fixed column, for reading notes in a mail
+//! client or reviewing diffs line by line.
+//!
+//! Paragraphs, list items and quotes are refilled so no line is longer than
+//! the column, unless a single word is. Continuation lines repeat the quote
+//! markers and are indented to the text of a list item, so the structure reads
+//! the same before and after wrapping. Everything whose line breaks matter is
+//! left alone: code blocks, headings, tables, HTML, link definitions and
+//! frontmatter. Hard line breaks (two trailing spaces or a backslash) are kept,
+//! and a line is never started with a word that would turn it into a list
+//! item, heading or quote.
+
+use crate::frontmatter;
+use crate::tags::is_code_fence;
+
+/// Column used when none is configured.
+pub const DEFAULT_COLUMN: usize = 72;
+
+/// A run of words up to an optional hard line break.
+struct Segment {
+    words: Vec<String>,
+    /// The trailing `"  "` or `"\\"` that ends the segment with a line break.
+    hard_break: Option<&'static str>,
+}
+
+/// A paragraph being collected.
+struct Paragraph {
+    /// Prefix of the first line: quote markers, indentation and list marker.
+    first: String,
+    /// Prefix of the continuation lines.
+    rest: String,
+    /// Number of quote markers, which continuation lines must match.
+    depth: usize,
+    segments: Vec<Segment>,
+    /// The original lines, kept in case the paragraph turns out to be a
+    /// setext heading.
+    lines: Vec<String>,
+}
+
+/// `markdown` with its paragraphs, list items and quotes wrapped at `column`.
+pub fn hard_wrap(markdown: &str, column: usize) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut paragraph: Option<Paragraph> = None;
+    let mut fence: Option<usize> = None;
+    let skip = frontmatter::line_count(markdown);
+
+    for (index, line) in markdown.lines().enumerate() {
+        if index < skip {
+            out.push(line.to_string());
+            continue;
+        }
+        let (quote, depth, body) = split_quote(line);
+
+        if let Some(depth_of_fence) = fence {
+            if depth == depth_of_fence && is_code_fence(body) {
+                fence = None;
+            }
+            out.push(line.to_string());
+            continue;
+        }
+        if is_code_fence(strip_list_marker(body).2) {
+            flush(&mut paragraph, column, &mut out);
+            fence = Some(depth);
+            out.push(line.to_string());
+            continue;
+        }
+        if body.trim().is_empty() {
+            flush(&mut paragraph, column, &mut out);
+            out.push(line.to_string());
+            continue;
+        }
+        if let Some(p) = paragraph.as_mut()
+            && depth == p.depth
+            && is_setext_underline(body)
+        {
+            // The collected lines are a heading: keep them as they were.
+            out.append(&mut p.lines);
+            paragraph = None;
+            out.push(line.to_string());
+            continue;
+        }
+        if is_verbatim(body) {
+            flush(&mut paragraph, column, &mut out);
+            out.push(line.to_string());
+            continue;
+        }
+
+        let (indent, marker, text) = strip_list_marker(body);
+        if marker.is_empty()
+            && let Some(p) = paragraph.as_mut()
+            && depth == p.depth
+        {
+            // A continuation line of the paragraph being collected.
+            p.push(text);
+            p.lines.push(line.to_string());
+            continue;
+        }
+        flush(&mut paragraph, column, &mut out);
+        if marker.is_empty() && indent.len() >= 4 {
+            // Indented code.
+            out.push(line.to_string());
+            continue;
+        }
+        let mut p = Paragraph {
+            first: format!("{quote}{indent}{marker}"),
+            rest: format!(
+                "{quote}{}",
+                " ".repeat(indent.chars().count() + marker.chars().count())
+            ),
+            depth,
+            segments: Vec::new(),
+            lines: vec![line.to_string()],
+        };
+        p.push(text);
+        paragraph = Some(p);
+    }
+    flush(&mut paragraph, column, &mut out);
+
+    let mut wrapped = out.join("\n");
+    if markdown.ends_with('\n') {
+        wrapped.push('\n');
+    }
+    wrapped
+}
+
+impl Paragraph {
+    /// Add the text of one line.
+    fn push(&mut self, text: &str) {
+        let hard_break = if text.ends_with("  ") {
+            Some("  ")
+        } else if text.ends_with('\\') && !text.ends_with("\\\\") {
+            Some("\\")
+        } else {
+            None
+        };
+        let text = match hard_break {
+            Some("\\") => &text[..text.len() - 1],
+            _ => text,
+        };
+        if self.segments.last().is_none_or(|s| s.hard_break.is_some()) {
+            self.segments.push(Segment {
+                words: Vec::new(),
+                hard_break: None,
+            });
+        }
+        let segment = self.segments.last_mut().expect("pushed above");
+        segment
+            .words
+            .extend(text.split_whitespace().map(str::to_string));
+        segment.hard_break = hard_break;
+    }
+
+    fn wrap(&self, column: usize, out: &mut Vec<String>) {
+        let mut line = self.first.clone();
+        let mut width = line.chars().count();
+        let mut empty = true;
+        for segment in &self.segments {
+            for word in &segment.words {
+                let len = word.chars().count();
+                if !empty && width + 1 + len > column && !starts_block(word) {
+                    out.push(line);
+                    line = self.rest.clone();
+                    width = line.chars().count();
+                    empty = true;
+                }
+                if !empty {
+                    line.push(' ');
+                    width += 1;
+                }
+                line.push_str(word);
+                width += len;
+                empty = false;
+            }
+            if let Some(hard_break) = segment.hard_break {
+                line.push_str(hard_break);
+                out.push(line);
+                line = self.rest.clone();
+                width = line.chars().count();
+                empty = true;
+            }
+        }
+        if !empty {
+            out.push(line);
+        }
+    }
+}
+
+fn flush(paragraph: &mut Option<Paragraph>, column: usize, out: &mut Vec<String>) {
+    if let Some(p) = paragraph.take() {
+        p.wrap(column, out);
+    }
+}
+
+/// The quote markers at the start of `line` (with the space after each), how
+/// many there are, and the rest of the line.
+fn split_quote(line: &str) -> (&str, usize, &str) {
+    let mut depth = 0;
+    let mut end = 0;
+    loop {
+        let rest = &line[end..];
+        let spaces = rest.len() - rest.trim_start_matches(' ').len();
+        if spaces > 3 || !rest[spaces..].starts_with('>') {
+            break;
+        }
+        end += spaces + 1;
+        if line[end..].starts_with(' ') {
+            end += 1;
+        }
+        depth += 1;
+    }
+    (&line[..end], depth, &line[end..])
+}
+
+/// The indentation, list marker (with a task checkbox and the space after it)
+/// and text of `body`. The marker is empty for lines that aren't list items.
+fn strip_list_marker(body: &str) -> (&str, &str, &str) {
+    let indent_len = body.len() - body.trim_start_matches(' ').len();
+    let (indent, rest) = body.split_at(indent_len);
+    let marker_len = if rest.starts_with(['-', '*', '+']) {
+        1
+    } else {
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if (1..=9).contains(&digits) && rest[digits..].starts_with(['.', ')']) {
+            digits + 1
+        } else {
+            0
+        }
+    };
+    if marker_len == 0 || !rest[marker_len..].starts_with(' ') {
+        return (indent, "", rest);
+    }
+    let mut end = marker_len + 1;
+    for checkbox in ["[ ] ", "[x] ", "[X] "] {
+        if rest[end..].starts_with(checkbox) {
+            end += checkbox.len();
+            break;
+        }
+    }
+    (indent, &rest[..end], &rest[end..])
+}
+
+/// Lines that are kept exactly as they are.
+fn is_verbatim(body: &str) -> bool {
+    let trimmed = body.trim_start();
+    trimmed.starts_with('#')
+        && trimmed
+            .trim_start_matches('#')
+            .chars()
+            .next()
+            .is_none_or(char::is_whitespace)
+        || trimmed.starts_with('|')
+        || trimmed.starts_with('<')
+        || is_link_definition(trimmed)
+        || is_thematic_break(trimmed)
+}
+
+/// `[label]: target` and footnote definitions.
+fn is_link_definition(line: &str) -> bool {
+    line.starts_with('[')
+        && line
+            .find("]:")
+            .is_some_and(|end| !line[1..end].contains(']'))
+}
+
+/// Whether `line` is a thematic break: three or more `-`, `*` or `_`.
+pub(crate) fn is_thematic_break(line: &str) -> bool {
+    let chars: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    chars.len() >= 3 && ['-', '*', '_'].iter().any(|m| chars.iter().all(|c| c == m))
+}
+
+fn is_setext_underline(body: &str) -> bool {
+    let trimmed = body.trim();
+    !trimmed.is_empty() && (trimmed.chars().all(|c| c == '=') || trimmed.chars().all(|c| c == '-'))
+}
+
+/// Whether a line starting with `word` would begin a new block.
+fn starts_block(word: &str) -> bool {
+    let digits = word.len() - word.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    matches!(word, "-" | "+" | "*")
+        || word.starts_with('>')
+        || word.chars().all(|c| c == '#')
+        || word.chars().all(|c| c == '=')
+        || word.starts_with("```")
+        || word.starts_with("~~~")
+        || (digits > 0 && matches!(&word[digits..], "." | ")"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_paragraphs_lists_and_quotes() {
+        let markdown = "\
+# A heading that is much longer than the column and stays on one line
+
+One two three four five six seven eight nine ten.
+
+- [ ] alpha beta gamma delta epsilon
+   continued zeta eta
+12. theta iota kappa lambda
+
+> > mu nu xi omicron pi rho sigma
+";
+        assert_eq!(
+            hard_wrap(markdown, 20),
+            "\
+# A heading that is much longer than the column and stays on one line
+
+One two three four
+five six seven eight
+nine ten.
+
+- [ ] alpha beta
+      gamma delta
+      epsilon
+      continued zeta
+      eta
+12. theta iota kappa
+    lambda
+
+> > mu nu xi omicron
+> > pi rho sigma
+"
+        );
+    }
+
+    #[test]
+    fn leaves_structure_alone() {
+        let markdown = "\
+---
+title: A very long frontmatter title that is not wrapped
+---
+
+```
+let code = \"is never wrapped, however long the line is\";
+```
+
+Setext heading that is long enough to wrap
+==========================================
+
+| a table | row that is long enough to wrap |
+
+[link]: https://example.com/a/long/url/that/is/kept/as/it/is
+
+First line with a hard break  
+second line - 1. keeps markers
+";
+        let wrapped = hard_wrap(markdown, 20);
+        let (kept, paragraph) = wrapped.split_at(wrapped.find("First").unwrap());
+        assert_eq!(kept, &markdown[..markdown.find("First").unwrap()]);
+        // Neither "-" nor "1." may start a line: they would begin a list.
+        assert_eq!(
+            paragraph,
+            "First line with a\nhard break  \nsecond line - 1.\nkeeps markers\n"
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Just enough of the zip format for EPUB exports, which are zip files, and
+//! for importing the zip files other wikis export to. Files are written
+//! uncompressed; reading also handles deflated files, as most zip tools
+//! write them.
+
+use crate::dates::Date;
+
+const LOCAL_HEADER: u32 = 0x04034b50;
+const DIRECTORY_ENTRY: u32 = 0x02014b50;
+const END_OF_DIRECTORY: u32 = 0x06054b50;
+
+/// A zip archive being written.
+#[derive(Default)]
+pub(crate) struct ZipWriter {
+    data: Vec<u8>,
+    directory: Vec<u8>,
+    count: u16,
+}
+
+impl ZipWriter {
+    /// Add the file `name`, dated `date`.
+    pub(crate) fn add(&mut self, name: &str, content: &[u8], date: Date) {
+        let (year, month, day) = date.ymd();
+        let dos_date =
+            (((year - 1980).clamp(0, 127) as u16) << 9) | ((month as u16) << 5) | day as u16;
+        let crc = crc32(content);
+        let offset = self.data.len() as u32;
+
+        // Fields shared by the local header and the directory entry: version
+        // needed, flags (UTF-8 names), method (stored), time, date, CRC and
+        // sizes.
+        let mut common = Vec::new();
+        common.extend_from_slice(&20u16.to_le_bytes());
+        common.extend_from_slice(&0x0800u16.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&dos_date.to_le_bytes());
+        common.extend_from_slice(&crc.to_le_bytes());
+        common.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+
+        self.data.extend_from_slice(&LOCAL_HEADER.to_le_bytes());
+        self.data.extend_from_slice(&common);
+        self.data.extend_from_slice(name.as_bytes());
+        self.data.extend_from_slice(content);
+
+        self.directory
+            .extend_from_slice(&DIRECTORY_ENTRY.to_le_bytes());
+        self.directory.extend_from_slice(&20u16.to_le_bytes());
+        self.directory.extend_from_slice(&common);
+        // Comment length, disk, internal and external attributes.
+        self.directory.extend_from_slice(&[0; 10]);
+        self.directory.extend_from_slice(&offset.to_le_bytes());
+        self.directory.extend_from_slice(name.as_bytes());
+        self.count += 1;
+    }
+
+    /// The finished archive.
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        let offset = self.data.len() as u32;
+        let size = self.directory.len() as u32;
+        self.data.append(&mut self.directory);
+        self.data.extend_from_slice(&END_OF_DIRECTORY.to_le_bytes());
+        self.data.extend_from_slice(&[0; 4]);
+        self.data.extend_from_slice(&self.count.to_le_bytes());
+        self.data.extend_from_slice(&self.count.to_le_bytes());
+        self.data.extend_from_slice(&size.to_le_bytes());
+        self.data.extend_from_slice(&offset.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        self.data
+    }
+}
+
+/// The files in the zip archive `data`, by their paths in it. Folders are
+/// left out. Archives with a path that would lead out of the folder they are
+/// unpacked into are rejected, see [`is_relative_path`].
+pub(crate) fn read(data: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let broken = || "Broken zip file".to_string();
+    // The end of directory record is the last thing in the file, followed
+    // only by a comment of up to 64 KiB.
+    let end = (0..data.len().saturating_sub(21))
+        .rev()
+        .take(22 + 0xFFFF)
+        .find(|&at| u32_at(data, at) == Some(END_OF_DIRECTORY))
+        .ok_or("Not a zip file")?;
+    let count = u16_at(data, end + 10).ok_or_else(broken)? as usize;
+    let mut at = u32_at(data, end + 16).ok_or_else(broken)? as usize;
+    if count == 0xFFFF || at == 0xFFFF_FFFF {
+        return Err("Zip files over 4 GB are not supported".to_string());
+    }
+
+    let mut files = Vec::with_capacity(count);
+    for _ in 0..count {
+        if u32_at(data, at) != Some(DIRECTORY_ENTRY) {
+            return Err(broken());
+        }
+        let field = |offset: usize| u16_at(data, at + offset).ok_or_else(broken);
+        let method = field(10)?;
+        let compressed = u32_at(data, at + 20).ok_or_else(broken)? as usize;
+        let (name_len, extra_len, comment_len) = (field(28)?, field(30)?, field(32)?);
+        let local = u32_at(data, at + 42).ok_or_else(broken)? as usize;
+        let name = data
+            .get(at + 46..at + 46 + name_len as usize)
+            .ok_or_else(broken)?;
+        let name = String::from_utf8_lossy(name).replace('\\', "/");
+        at += 46 + (name_len + extra_len + comment_len) as usize;
+        if name.ends_with('/') {
+            continue;
+        }
+        if !is_relative_path(&name) {
+            return Err(format!("Broken zip file: unsafe path '{name}'"));
+        }
+
+        if u32_at(data, local) != Some(LOCAL_HEADER) {
+            return Err(broken());
+        }
+        let start = local
+            + 30
+            + u16_at(data, local + 26).ok_or_else(broken)? as usize
+            + u16_at(data, local + 28).ok_or_else(broken)? as usize;
+        let raw = data.get(start..start + compressed).ok_or_else(broken)?;
+        let content = match method {
+            0 => raw.to_vec(),
+            8 => miniz_oxide::inflate::decompress_to_vec(raw)
+                .map_err(|_| format!("Broken zip file: can't unpack '{name}'"))?,
+            _ => return Err(format!("Can't unpack '{name}': unsupported compression")),
+        };
+        files.push((name, content));
+    }
+    Ok(files)
+}
+
+/// Whether `path`, with `/` separators, stays within the folder it is
+/// relative to: it isn't absolute, has no drive prefix (`C:`) and none of
+/// its components is empty, `.` or `..`.
+pub(crate) fn is_relative_path(path: &str) -> bool {
+    path.split('/')
+        .all(|part| !part.is_empty() && part != "." && part != "..")
+        && !path
+            .split('/')
+            .next()
+            .is_some_and(|first| first.contains(':'))
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// The CRC-32 (IEEE) of `data`, as used by zip.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_what_it_writes() {
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+        let date = Date::parse("2025-06-01").unwrap();
+        let mut zip = ZipWriter::default();
+        zip.add("mimetype", b"application/epub+zip", date);
+        zip.add("notes/plan.md", b"# Plan\n", date);
+        let data = zip.finish();
+        assert_eq!(&data[30..38], b"mimetype");
+        assert_eq!(
+            read(&data).unwrap(),
+            vec![
+                ("mimetype".to_string(), b"application/epub+zip".to_vec()),
+                ("notes/plan.md".to_string(), b"# Plan\n".to_vec()),
+            ]
+        );
+        assert!(read(b"# Plan\n").is_err());
+
+        for unsafe_path in [
+            "/tmp/evil.sh",
+            "../evil.sh",
+            "notes/../../evil.sh",
+            "C:/evil.sh",
+        ] {
+            let mut zip = ZipWriter::default();
+            zip.add("notes/plan.md", b"# Plan\n", date);
+            zip.add(unsafe_path, b"rm -rf ~\n", date);
+            assert!(read(&zip.finish()).is_err(), "{unsafe_path}");
+        }
+        assert!(!is_relative_path("notes//plan.md"));
+        assert!(!is_relative_path("./plan.md"));
+        assert!(is_relative_path("notes/plan.md"));
+        assert!(read(&data[..data.len() - 30]).is_err());
+    }
+
+    #[test]
+    fn reads_deflated_files() {
+        // A zip file holding `a.txt`, deflated, as written by Python's zipfile.
+        let data: &[u8] = &[
+            0x50, 0x4b, 0x03, 0x04, 0x14, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0xc1, 0x5a,
+            0x3b, 0x7c, 0x8a, 0xdf, 0x0b, 0x00, 0x00, 0x00, 0x12, 0x00, 0x00, 0x00, 0x05, 0x00,
+            0x00, 0x00, 0x61, 0x2e, 0x74, 0x78, 0x74, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8,
+            0x40, 0x90, 0x5c, 0x00, 0x50, 0x4b, 0x01, 0x02, 0x14, 0x03, 0x14, 0x00, 0x00, 0x00,
+            0x08, 0x00, 0x00, 0x00, 0xc1, 0x5a, 0x3b, 0x7c, 0x8a, 0xdf, 0x0b, 0x00, 0x00, 0x00,
+            0x12, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x80, 0x01, 0x00, 0x00, 0x00, 0x00, 0x61, 0x2e, 0x74, 0x78, 0x74, 0x50,
+            0x4b, 0x05, 0x06, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x33, 0x00, 0x00,
+            0x00, 0x2e, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(
+            read(data).unwrap(),
+            vec![("a.txt".to_string(), b"hello hello hello\n".to_vec())]
+        );
+    }
+}
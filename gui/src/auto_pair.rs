@@ -0,0 +1,131 @@
+//! Pairs while typing: wrapping the selection and closing brackets.
+//!
+//! With text selected, typing `*` or `_` makes it italic and `` ` `` makes it
+//! code — the editor shows what the Markdown markers would mean — while `[`
+//! and `(` put brackets around it, instead of replacing it. Without a
+//! selection, `[` and `(` get their closing bracket right away when nothing
+//! but space or punctuation follows, and typing that closing bracket in front
+//! of one that closes an open bracket steps over it. Code blocks are left
+//! alone.
+
+use rutle::editor::Editor;
+use rutle::structured_document::BlockType;
+use rutle::tree_path::DocumentPosition;
+use rutle::tree_walk::leaf_plain_text;
+
+/// The closing bracket for `open`.
+fn closing(open: char) -> Option<char> {
+    match open {
+        '[' => Some(']'),
+        '(' => Some(')'),
+        _ => None,
+    }
+}
+
+/// The opening bracket for `close`.
+fn opening(close: char) -> Option<char> {
+    match close {
+        ']' => Some('['),
+        ')' => Some('('),
+        _ => None,
+    }
+}
+
+/// Whether an opening bracket typed in front of `after` gets closed: at the
+/// end of the text, or before space or closing punctuation, not in front of
+/// a word.
+pub fn closes_before(after: &str) -> bool {
+    after
+        .chars()
+        .next()
+        .is_none_or(|c| c.is_whitespace() || ".,;:!?)]".contains(c))
+}
+
+/// Whether typing the closing bracket `close` between `before` and `after`
+/// steps over the one that follows instead: when it closes a bracket opened
+/// in `before`, as one that was closed right away does.
+pub fn steps_over(before: &str, after: &str, close: char) -> bool {
+    let Some(open) = opening(close) else {
+        return false;
+    };
+    let opened = before.chars().filter(|&c| c == open).count();
+    let closed = before.chars().filter(|&c| c == close).count();
+    after.starts_with(close) && opened > closed
+}
+
+/// Handle `typed` before it is inserted at the cursor. Returns `None` if it
+/// should be inserted as usual, otherwise whether the text changed — it
+/// doesn't when the caret just stepped over a closing bracket.
+pub fn apply(editor: &mut Editor, typed: &str) -> Option<bool> {
+    let mut chars = typed.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return None;
+    };
+    if !"*_`[]()".contains(c) || matches!(editor.current_block_type(), BlockType::CodeBlock { .. })
+    {
+        return None;
+    }
+
+    if let Some((start, end)) = editor.selection()
+        && start != end
+    {
+        return match c {
+            '*' | '_' => Some(editor.toggle_italic().is_ok()),
+            '`' => Some(editor.toggle_code().is_ok()),
+            '[' | '(' if start.path == end.path => {
+                let close = closing(c)?;
+                editor.set_cursor(end.clone());
+                editor.insert_text(&close.to_string()).ok()?;
+                editor.set_cursor(start.clone());
+                editor.insert_text(typed).ok()?;
+                // Keep the wrapped text selected.
+                editor.set_cursor(DocumentPosition::at(start.path, start.offset + 1));
+                editor.extend_selection_to(DocumentPosition::at(end.path, end.offset + 1));
+                Some(true)
+            }
+            _ => None,
+        };
+    }
+
+    let cursor = editor.cursor();
+    let text = leaf_plain_text(editor.document(), &cursor.path);
+    let (before, after) = text.split_at_checked(cursor.offset)?;
+    if let Some(close) = closing(c) {
+        if !closes_before(after) {
+            return None;
+        }
+        editor.insert_text(&format!("{c}{close}")).ok()?;
+        editor.set_cursor(DocumentPosition::at(cursor.path, cursor.offset + 1));
+        Some(true)
+    } else if steps_over(before, after, c) {
+        editor.set_cursor(DocumentPosition::at(cursor.path, cursor.offset + 1));
+        Some(false)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closes_brackets_only_in_front_of_space_and_punctuation() {
+        assert!(closes_before(""));
+        assert!(closes_before(" more"));
+        assert!(closes_before(", more"));
+        assert!(closes_before(")"));
+        assert!(!closes_before("word"));
+    }
+
+    #[test]
+    fn steps_over_brackets_that_close_an_open_one() {
+        assert!(steps_over("see (", ")", ')'));
+        assert!(steps_over("[a [b", "]]", ']'));
+        // Balanced already: the typed bracket is a new one.
+        assert!(!steps_over("(a)", ")", ')'));
+        assert!(!steps_over("(a", " )", ')'));
+        assert!(!steps_over("(a", "]", ']'));
+        assert!(!steps_over("a", "x", 'x'));
+    }
+}
@@ -1,8 +1,14 @@
 use chrono::{DateTime, Local};
 use piki_core::DocumentStore;
+use piki_core::edit_lock::{self, Claim};
+use piki_core::format::{CanonicalFormat, preserve_formatting};
 use piki_gui::content::ContentProvider;
 use std::time::SystemTime;
 
+/// Marks the status text, and the window title, while there are edits that
+/// weren't saved yet.
+pub const DIRTY_MARKER: &str = "●";
+
 /// State management for auto-save functionality
 pub struct AutoSaveState {
     /// When the content was last changed
@@ -15,8 +21,30 @@ pub struct AutoSaveState {
     pub pending_save: bool,
     /// Original content to detect changes
     pub original_content: String,
+    /// The editor's serialization of `original_content`, which differs from
+    /// it where the file isn't written in the editor's style
+    pub editor_content: String,
+    /// The style notes are rewritten to on save; `None` keeps each file's own
+    /// formatting
+    pub canonical: Option<CanonicalFormat>,
     /// Current note being edited
     pub current_note: String,
+    /// When the note's file was last modified, as of loading it; shown until
+    /// the note is saved here
+    pub modified_time: Option<SystemTime>,
+    /// When the note's file was created, if the file system knows
+    pub created_time: Option<SystemTime>,
+    /// Modification time of the note's file as of our last load or save, used
+    /// to notice when another program changes it on disk
+    pub disk_mtime: Option<SystemTime>,
+    /// Whether the user is currently being asked how to resolve a conflict
+    /// with the version on disk; saving is held off until they decide
+    pub resolving_conflict: bool,
+    /// Our claim on the open note, telling `piki edit` it is open here
+    pub claim: Option<Claim>,
+    /// Whether `piki edit` has the note open; saving is held off until the
+    /// editor exits, and the edits are then merged like any change on disk
+    pub held_by_terminal: bool,
 }
 
 impl AutoSaveState {
@@ -24,10 +52,18 @@ impl AutoSaveState {
         AutoSaveState {
             last_change_time: None,
             last_save_time: None,
+            modified_time: None,
+            created_time: None,
             is_saving: false,
             pending_save: false,
             original_content: String::new(),
+            editor_content: String::new(),
+            canonical: None,
             current_note: String::new(),
+            disk_mtime: None,
+            resolving_conflict: false,
+            claim: None,
+            held_by_terminal: false,
         }
     }
 
@@ -41,10 +77,14 @@ impl AutoSaveState {
     pub fn reset_for_note(&mut self, note_name: &str, content: &str) {
         self.current_note = note_name.to_string();
         self.original_content = content.to_string();
+        self.editor_content = content.to_string();
         self.last_change_time = None;
         self.last_save_time = None;
+        self.modified_time = None;
+        self.created_time = None;
         self.is_saving = false;
         self.pending_save = false;
+        self.disk_mtime = None;
     }
 
     /// Check if the current note should be saved (not a plugin note)
@@ -52,14 +92,79 @@ impl AutoSaveState {
         !self.current_note.starts_with('!')
     }
 
-    /// Get the status text for display
+    /// Whether `current`, the editor's content, holds edits that weren't saved.
+    pub fn has_unsaved_edits(&self, current: &str) -> bool {
+        current != self.editor_content && current != self.original_content
+    }
+
+    /// `current` with the edits carried over into the file's own formatting.
+    pub fn with_file_formatting(&self, current: &str) -> String {
+        preserve_formatting(&self.original_content, &self.editor_content, current)
+    }
+
+    /// Keep our claim on the open note current, recording whether `dirty`
+    /// edits are unsaved, and check whether `piki edit` has the note open.
+    /// Returns whether the latter changed.
+    pub fn update_claim(&mut self, store: &DocumentStore, dirty: bool) -> bool {
+        if !self.should_save() || self.current_note.is_empty() {
+            self.claim = None;
+        } else if self
+            .claim
+            .as_ref()
+            .is_none_or(|claim| claim.note() != self.current_note)
+        {
+            // Release the previous note before claiming this one.
+            self.claim = None;
+            self.claim =
+                edit_lock::claim(store.base_path(), &self.current_note, edit_lock::GUI).ok();
+        }
+        if let Some(claim) = self.claim.as_mut() {
+            let _ = claim.refresh_if_due(dirty);
+        }
+
+        let held = self.should_save()
+            && edit_lock::holders(store.base_path(), &self.current_note)
+                .iter()
+                .any(|holder| holder.program == edit_lock::TERMINAL);
+        std::mem::replace(&mut self.held_by_terminal, held) != held
+    }
+
+    /// Get the status text for display, with the [`DIRTY_MARKER`] in front
+    /// while a save is pending
     pub fn get_status_text(&self) -> String {
+        let text = self.save_status_text();
+        if !self.pending_save || self.is_saving {
+            text
+        } else if text.is_empty() {
+            DIRTY_MARKER.to_string()
+        } else {
+            format!("{DIRTY_MARKER} {text}")
+        }
+    }
+
+    /// The tooltip of the status text: when the note's file was created and
+    /// last modified, as far as known.
+    pub fn get_status_tooltip(&self) -> String {
+        let modified = self.last_save_time.or(self.modified_time);
+        [("Created", self.created_time), ("Modified", modified)]
+            .into_iter()
+            .filter_map(|(what, time)| Some(format!("{what} {}", format_date_time(time?))))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn save_status_text(&self) -> String {
+        if self.held_by_terminal {
+            return "Open in piki edit — not saving".to_string();
+        }
         if self.is_saving {
             return "Saving …".to_string();
         }
 
         if let Some(save_time) = self.last_save_time {
             format_time_since(save_time)
+        } else if let Some(modified_time) = self.modified_time {
+            describe_time_since("modified", modified_time)
         } else if self.last_change_time.is_some() {
             "not saved".to_string()
         } else {
@@ -79,8 +184,9 @@ impl AutoSaveState {
             return Ok(());
         }
 
-        // Don't save if already saving
-        if self.is_saving {
+        // Don't save if already saving, while a conflict is being resolved, or
+        // while `piki edit` has the note open
+        if self.is_saving || self.resolving_conflict || self.held_by_terminal {
             return Ok(());
         }
 
@@ -88,11 +194,33 @@ impl AutoSaveState {
         let current_content = editor.get_content();
 
         // Check if content actually changed
-        if current_content == self.original_content {
+        if !self.has_unsaved_edits(&current_content) {
             self.pending_save = false;
             return Ok(());
         }
 
+        // Only the edits show up in the file; the rest keeps its bytes unless
+        // the wiki asks for a canonical style.
+        let text = match self.canonical {
+            Some(format) => format.apply(&current_content),
+            None => self.with_file_formatting(&current_content),
+        };
+        if text == self.original_content {
+            self.editor_content = current_content;
+            self.pending_save = false;
+            return Ok(());
+        }
+
+        // Never silently overwrite a version another program wrote in the
+        // meantime; the conflict has to be resolved first (see
+        // `take_external_change`).
+        if self.changed_on_disk(store) {
+            return Err(format!(
+                "{} was changed on disk, not overwriting it",
+                self.current_note
+            ));
+        }
+
         // Mark as saving
         self.is_saving = true;
         self.pending_save = false;
@@ -103,7 +231,7 @@ impl AutoSaveState {
         let result = match doc_result {
             Ok(mut doc) => {
                 // Update content and save
-                doc.content = current_content.clone();
+                doc.content = text.clone();
                 store.save(&doc)
             }
             Err(e) => Err(e),
@@ -113,7 +241,9 @@ impl AutoSaveState {
         match result {
             Ok(()) => {
                 self.last_save_time = Some(SystemTime::now());
-                self.original_content = current_content;
+                self.original_content = text;
+                self.editor_content = current_content;
+                self.disk_mtime = store.modified_time(&self.current_note);
                 self.is_saving = false;
                 Ok(())
             }
@@ -123,6 +253,44 @@ impl AutoSaveState {
             }
         }
     }
+
+    /// Check whether another program changed the current note's file since we
+    /// last loaded or saved it. Returns the new on-disk content if it differs
+    /// from what we last loaded/saved; a mere touch of the file is ignored.
+    ///
+    /// Each change is only reported once: the new modification time is
+    /// remembered whether or not the caller decides to reload.
+    pub fn take_external_change(&mut self, store: &DocumentStore) -> Option<String> {
+        if !self.should_save() || self.is_saving || self.resolving_conflict {
+            return None;
+        }
+        // The terminal's edits are picked up once its editor exits, rather
+        // than every time it writes the file.
+        if self.held_by_terminal {
+            return None;
+        }
+
+        let mtime = store.modified_time(&self.current_note);
+        if mtime.is_none() || mtime == self.disk_mtime {
+            return None;
+        }
+        self.disk_mtime = mtime;
+
+        let content = store.load(&self.current_note).ok()?.content;
+        (content != self.original_content).then_some(content)
+    }
+
+    /// Whether the note's file was rewritten with different content since we
+    /// last loaded or saved it.
+    fn changed_on_disk(&self, store: &DocumentStore) -> bool {
+        let mtime = store.modified_time(&self.current_note);
+        if mtime.is_none() || mtime == self.disk_mtime {
+            return false;
+        }
+        store
+            .load(&self.current_note)
+            .is_ok_and(|doc| doc.content != self.original_content)
+    }
 }
 
 impl Default for AutoSaveState {
@@ -133,6 +301,12 @@ impl Default for AutoSaveState {
 
 /// Format a time duration as a human-readable string
 pub fn format_time_since(time: SystemTime) -> String {
+    describe_time_since("saved", time)
+}
+
+/// `what` happened at `time`, e.g. "modified 2 hours ago", or on which day
+/// if that was a week or more ago.
+fn describe_time_since(what: &str, time: SystemTime) -> String {
     let now = SystemTime::now();
 
     match now.duration_since(time) {
@@ -140,44 +314,46 @@ pub fn format_time_since(time: SystemTime) -> String {
             let secs = duration.as_secs();
 
             if secs < 60 {
-                "saved just now".to_string()
+                format!("{what} just now")
             } else if secs < 3600 {
                 // Less than an hour
                 let mins = secs / 60;
-                if mins == 1 {
-                    "saved 1 min ago".to_string()
-                } else {
-                    format!("saved {} min ago", mins)
-                }
+                format!("{what} {mins} min ago")
             } else if secs < 86400 {
                 // Less than a day
                 let hours = secs / 3600;
                 if hours == 1 {
-                    "saved 1 hour ago".to_string()
+                    format!("{what} 1 hour ago")
                 } else {
-                    format!("saved {} hours ago", hours)
+                    format!("{what} {hours} hours ago")
                 }
             } else if secs < 604800 {
                 // Less than a week
                 let days = secs / 86400;
                 if days == 1 {
-                    "saved 1 day ago".to_string()
+                    format!("{what} 1 day ago")
                 } else {
-                    format!("saved {} days ago", days)
+                    format!("{what} {days} days ago")
                 }
             } else {
                 // A week or more - show date
-                format_absolute_date(time)
+                format!("{what} {}", format_absolute_date(time))
             }
         }
-        Err(_) => "saved (time error)".to_string(),
+        Err(_) => format!("{what} (time error)"),
     }
 }
 
 /// Format a time as an absolute date (YYYY-MM-DD)
 fn format_absolute_date(time: SystemTime) -> String {
     let datetime: DateTime<Local> = time.into();
-    format!("saved {}", datetime.format("%Y-%m-%d"))
+    datetime.format("%Y-%m-%d").to_string()
+}
+
+/// Format a time as date and time of day (YYYY-MM-DD HH:MM)
+fn format_date_time(time: SystemTime) -> String {
+    let datetime: DateTime<Local> = time.into();
+    datetime.format("%Y-%m-%d %H:%M").to_string()
 }
 
 #[cfg(test)]
@@ -201,6 +377,33 @@ mod tests {
         assert!(state.pending_save);
     }
 
+    #[test]
+    fn test_status_text_marks_pending_save() {
+        let mut state = AutoSaveState::new();
+        state.reset_for_note("note", "");
+        assert_eq!(state.get_status_text(), "");
+        state.mark_changed();
+        assert_eq!(state.get_status_text(), "● not saved");
+        state.pending_save = false;
+        state.last_save_time = Some(SystemTime::now());
+        assert_eq!(state.get_status_text(), "saved just now");
+        state.mark_changed();
+        assert_eq!(state.get_status_text(), "● saved just now");
+    }
+
+    #[test]
+    fn test_status_text_before_saving_tells_when_modified() {
+        use std::time::Duration;
+        let mut state = AutoSaveState::new();
+        state.modified_time = Some(SystemTime::now() - Duration::from_secs(7200));
+        assert_eq!(state.get_status_text(), "modified 2 hours ago");
+        assert!(state.get_status_tooltip().starts_with("Modified "));
+        state.last_save_time = Some(SystemTime::now());
+        assert_eq!(state.get_status_text(), "saved just now");
+        state.reset_for_note("other", "");
+        assert_eq!(state.get_status_tooltip(), "");
+    }
+
     #[test]
     fn test_should_save_plugin_note() {
         let mut state = AutoSaveState::new();
@@ -208,6 +411,175 @@ mod tests {
         assert!(!state.should_save());
     }
 
+    #[test]
+    fn test_take_external_change() {
+        use std::time::Duration;
+
+        let temp_dir = std::env::temp_dir().join("piki-test-autosave-external");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let path = temp_dir.join("note.md");
+        std::fs::write(&path, "original").unwrap();
+
+        let store = DocumentStore::new(temp_dir.clone());
+        let mut state = AutoSaveState::new();
+        state.reset_for_note("note", "original");
+        state.disk_mtime = store.modified_time("note");
+
+        // Unchanged file: nothing to report.
+        assert_eq!(state.take_external_change(&store), None);
+
+        // Another program rewrites the file (bump the mtime explicitly so the
+        // test does not depend on the filesystem's timestamp granularity).
+        std::fs::write(&path, "edited elsewhere").unwrap();
+        let later = state.disk_mtime.unwrap() + Duration::from_secs(10);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert_eq!(
+            state.take_external_change(&store).as_deref(),
+            Some("edited elsewhere")
+        );
+        // Reported only once.
+        assert_eq!(state.take_external_change(&store), None);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn test_holds_saving_while_open_in_piki_edit() {
+        let temp_dir = std::env::temp_dir().join("piki-test-autosave-claim");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(temp_dir.join(".piki/open")).unwrap();
+        std::fs::write(temp_dir.join("note.md"), "original").unwrap();
+
+        let store = DocumentStore::new(temp_dir.clone());
+        let mut state = AutoSaveState::new();
+        state.reset_for_note("note", "original");
+        assert!(!state.update_claim(&store, false));
+        assert!(state.claim.is_some());
+
+        // `piki edit`, in another process, opens the note.
+        let terminal_claim = temp_dir.join(".piki/open/note#4000000000.lock");
+        std::fs::write(&terminal_claim, "4000000000\tpiki edit\t0\t0\n").unwrap();
+        assert!(state.update_claim(&store, true));
+        assert!(state.held_by_terminal);
+
+        struct Text(&'static str);
+        impl ContentProvider for Text {
+            fn get_content(&self) -> String {
+                self.0.to_string()
+            }
+        }
+        state.mark_changed();
+        state.trigger_save(&Text("edited here"), &store).unwrap();
+        assert_eq!(store.load("note").unwrap().content, "original");
+        assert!(state.pending_save);
+
+        std::fs::remove_file(&terminal_claim).unwrap();
+        assert!(state.update_claim(&store, true));
+        state.trigger_save(&Text("edited here"), &store).unwrap();
+        assert_eq!(store.load("note").unwrap().content, "edited here");
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn test_trigger_save_does_not_overwrite_external_change() {
+        use std::time::Duration;
+
+        struct Text(&'static str);
+        impl ContentProvider for Text {
+            fn get_content(&self) -> String {
+                self.0.to_string()
+            }
+        }
+
+        let temp_dir = std::env::temp_dir().join("piki-test-autosave-conflict");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let path = temp_dir.join("note.md");
+        std::fs::write(&path, "original").unwrap();
+
+        let store = DocumentStore::new(temp_dir.clone());
+        let mut state = AutoSaveState::new();
+        state.reset_for_note("note", "original");
+        state.disk_mtime = store.modified_time("note");
+
+        std::fs::write(&path, "theirs").unwrap();
+        let later = state.disk_mtime.unwrap() + Duration::from_secs(10);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+
+        assert!(state.trigger_save(&Text("mine"), &store).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "theirs");
+
+        // Once the change was taken into account, saving goes ahead.
+        assert_eq!(
+            state.take_external_change(&store).as_deref(),
+            Some("theirs")
+        );
+        state.trigger_save(&Text("mine"), &store).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "mine");
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn test_trigger_save_keeps_file_formatting() {
+        struct Text(&'static str);
+        impl ContentProvider for Text {
+            fn get_content(&self) -> String {
+                self.0.to_string()
+            }
+        }
+
+        let temp_dir = std::env::temp_dir().join("piki-test-autosave-formatting");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let path = temp_dir.join("note.md");
+        std::fs::write(&path, "* one\n* two\n\nText.\n").unwrap();
+
+        let store = DocumentStore::new(temp_dir.clone());
+        let mut state = AutoSaveState::new();
+        state.reset_for_note("note", "* one\n* two\n\nText.\n");
+        state.editor_content = "- one\n- two\n\nText.\n".to_string();
+        state.disk_mtime = store.modified_time("note");
+
+        // Unedited: the file is left alone.
+        state.mark_changed();
+        state
+            .trigger_save(&Text("- one\n- two\n\nText.\n"), &store)
+            .unwrap();
+        assert!(state.last_save_time.is_none());
+
+        state
+            .trigger_save(&Text("- one\n- two\n\nMore text.\n"), &store)
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "* one\n* two\n\nMore text.\n"
+        );
+
+        state.canonical = Some(CanonicalFormat::default());
+        state
+            .trigger_save(&Text("- one\n- two\n\nOther text.\n"), &store)
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "- one\n- two\n\nOther text.\n"
+        );
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
     #[test]
     fn test_should_save_normal_note() {
         let mut state = AutoSaveState::new();
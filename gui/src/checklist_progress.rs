@@ -0,0 +1,145 @@
+//! Checklist progress: how many items of the checklist at the caret are done,
+//! and archiving the done ones to the bottom of the list.
+//!
+//! Both act on the top-level checklist the caret is in, one contiguous run of
+//! `- [ ]` items. Nested items count towards the progress like any other, and
+//! archiving moves done items below the open ones at every level, each with
+//! the items nested under it. The edit replaces the document the same way
+//! [`crate::quote_depth`] does and keeps the caret on the same item.
+
+use std::time::Instant;
+
+use rutle::editor::{Editor, UndoKind};
+use rutle::tree_path::{DocumentPosition, PathSegment, TreePath};
+use rutle::tree_walk::enumerate_leaves;
+use tdoc::{ChecklistItem, Document, Paragraph};
+
+use crate::markdown_converter::{document_to_markdown, markdown_to_document};
+
+/// The index of the top-level checklist `path` lies in, and the index of the
+/// top-level item in it.
+fn checklist_at(doc: &Document, path: &TreePath) -> Option<(usize, usize)> {
+    let [
+        PathSegment::Paragraph(index),
+        PathSegment::ChecklistItem(item),
+        ..,
+    ] = path.segments()
+    else {
+        return None;
+    };
+    matches!(
+        doc.paragraphs.get(*index),
+        Some(Paragraph::Checklist { .. })
+    )
+    .then_some((*index, *item))
+}
+
+/// How many of `items` and the items nested in them are done, and how many
+/// there are.
+pub fn count(items: &[ChecklistItem]) -> (usize, usize) {
+    items.iter().fold((0, 0), |(done, total), item| {
+        let (nested_done, nested_total) = count(&item.children);
+        (
+            done + usize::from(item.checked) + nested_done,
+            total + 1 + nested_total,
+        )
+    })
+}
+
+/// The progress of the checklist at `path` as `(done, total)`, or `None`
+/// outside of a checklist.
+pub fn progress(doc: &Document, path: &TreePath) -> Option<(usize, usize)> {
+    let (index, _) = checklist_at(doc, path)?;
+    match &doc.paragraphs[index] {
+        Paragraph::Checklist { items } => Some(count(items)),
+        _ => None,
+    }
+}
+
+/// Move the done items below the open ones, keeping the order within both,
+/// at every level. Returns the new position of each former top-level item.
+pub fn archive(items: &mut Vec<ChecklistItem>) -> Vec<usize> {
+    for item in items.iter_mut() {
+        archive(&mut item.children);
+    }
+    let mut order = (0..items.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| items[i].checked);
+    let mut taken = std::mem::take(items)
+        .into_iter()
+        .map(Some)
+        .collect::<Vec<_>>();
+    items.extend(order.iter().filter_map(|&i| taken[i].take()));
+    let mut moved_to = vec![0; order.len()];
+    for (new, &old) in order.iter().enumerate() {
+        moved_to[old] = new;
+    }
+    moved_to
+}
+
+/// Archive the done items of the checklist at the caret (see [`archive`]), as
+/// one undoable edit. Returns whether the document changed.
+pub fn archive_done_items(editor: &mut Editor) -> bool {
+    let cursor = editor.cursor();
+    let Some((index, item)) = checklist_at(editor.document(), &cursor.path) else {
+        return false;
+    };
+
+    let mut doc = editor.document().clone();
+    let Paragraph::Checklist { items } = &mut doc.paragraphs[index] else {
+        return false;
+    };
+    let before = items.clone();
+    let moved_to = archive(items);
+    if *items == before {
+        return false;
+    }
+
+    let doc = markdown_to_document(&document_to_markdown(&doc));
+    editor.select_all();
+    let _ = editor.delete_selection();
+    if editor.insert_document(&doc).is_err() {
+        return false;
+    }
+    // Follow the caret's item; land on its first line if its nested items
+    // moved around, too.
+    let mut segments = cursor.path.segments().to_vec();
+    segments[1] = PathSegment::ChecklistItem(moved_to[item]);
+    let leaves = enumerate_leaves(editor.document());
+    let position = if leaves.iter().any(|leaf| leaf.path.segments() == segments) {
+        Some(DocumentPosition::at(TreePath(segments), cursor.offset))
+    } else {
+        leaves
+            .iter()
+            .find(|leaf| leaf.path.segments().starts_with(&segments[..2]))
+            .map(|leaf| DocumentPosition::at(leaf.path.clone(), 0))
+    };
+    if let Some(position) = position {
+        editor.set_cursor(position);
+    }
+    editor.commit_undo_step(UndoKind::Other, Instant::now());
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counting_and_archiving_done_items() {
+        let mut doc = markdown_to_document(
+            "intro\n\n- [x] one\n- [ ] two\n  - [x] two a\n  - [ ] two b\n- [x] three\n- [ ] four\n",
+        );
+        let leaves = enumerate_leaves(&doc);
+        assert_eq!(progress(&doc, &leaves[0].path), None);
+        assert_eq!(progress(&doc, &leaves[2].path), Some((3, 6)));
+
+        let Paragraph::Checklist { items } = &mut doc.paragraphs[1] else {
+            panic!("not a checklist");
+        };
+        assert_eq!(archive(items), vec![2, 0, 3, 1]);
+        let archived = markdown_to_document(
+            "intro\n\n- [ ] two\n  - [ ] two b\n  - [x] two a\n- [ ] four\n- [x] one\n- [x] three\n",
+        );
+        assert_eq!(doc.paragraphs, archived.paragraphs);
+    }
+}
@@ -8,6 +8,9 @@ use crate::rtf;
 #[derive(Debug)]
 pub enum ClipboardDocumentError {
     Empty,
+    /// Plain text without any Markdown structure, best inserted as is so its
+    /// line breaks survive.
+    PlainText,
     ClipboardUnavailable(String),
     Parse(String),
 }
@@ -113,18 +116,221 @@ fn document_from_plaintext(text: &str) -> Result<Document, ClipboardDocumentErro
     if text.trim().is_empty() {
         return Err(ClipboardDocumentError::Empty);
     }
+    if !looks_like_markdown(text) {
+        return Err(ClipboardDocumentError::PlainText);
+    }
 
     markdown::parse(Cursor::new(text.as_bytes()))
         .map_err(|err| ClipboardDocumentError::Parse(err.to_string()))
 }
 
+/// Whether `text` uses any Markdown syntax — block markers such as headings,
+/// lists, quotes, fences or tables, or inline emphasis, code and links.
+///
+/// Only such text is run through the Markdown parser on paste. Everything else
+/// is pasted verbatim: parsing would join its lines into one paragraph and
+/// might misread stray characters (`2 * 3`, `snake_case`) as formatting.
+pub fn looks_like_markdown(text: &str) -> bool {
+    text.lines()
+        .any(|line| has_block_marker(line) || has_inline_markup(line))
+}
+
+fn has_block_marker(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 && !trimmed.starts_with(['-', '*', '+']) {
+        return false;
+    }
+
+    let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+    if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+        return true;
+    }
+    if ["- ", "* ", "+ ", "> ", "```", "~~~"]
+        .iter()
+        .any(|marker| trimmed.starts_with(marker))
+    {
+        return true;
+    }
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && (trimmed[digits..].starts_with(". ") || trimmed[digits..].starts_with(") ")) {
+        return true;
+    }
+    let trimmed = trimmed.trim_end();
+    trimmed.len() > 2 && trimmed.starts_with('|') && trimmed.ends_with('|')
+}
+
+fn has_inline_markup(line: &str) -> bool {
+    if let Some(start) = line.find("[[")
+        && line[start..].contains("]]")
+    {
+        return true;
+    }
+    if let Some(close) = line.find("](")
+        && line[..close].contains('[')
+        && line[close..].contains(')')
+    {
+        return true;
+    }
+    ["**", "__", "~~", "`", "*", "_"]
+        .iter()
+        .any(|delim| has_delimited_span(line, delim))
+}
+
+/// Whether `line` has `delim`text`delim`, with the delimiters hugging the
+/// text and not sitting inside a word (so `snake_case` or `2 * 3` don't count).
+fn has_delimited_span(line: &str, delim: &str) -> bool {
+    let mut rest = line;
+    let mut before = None;
+    while let Some(open) = rest.find(delim) {
+        let prev = rest[..open].chars().next_back().or(before);
+        let inner = &rest[open + delim.len()..];
+        before = Some(delim.chars().next_back().unwrap_or(' '));
+        rest = inner;
+
+        let opens_word = inner.chars().next().is_some_and(|c| !c.is_whitespace())
+            && (delim == "`" || prev.is_none_or(|p| !p.is_alphanumeric()));
+        if !opens_word {
+            continue;
+        }
+        if let Some(close) = inner.find(delim) {
+            let last = inner[..close].chars().next_back();
+            let next = inner[close + delim.len()..].chars().next();
+            if close > 0
+                && last.is_some_and(|c| !c.is_whitespace())
+                && (delim == "`" || next.is_none_or(|n| !n.is_alphanumeric()))
+            {
+                return true;
+            }
+        }
+    }
+    false
+}
+
 fn document_from_html(html_content: &str) -> Result<Document, ClipboardDocumentError> {
     if html_content.trim().is_empty() {
         return Err(ClipboardDocumentError::Empty);
     }
 
-    html::parse(Cursor::new(html_content.as_bytes()))
-        .map_err(|err| ClipboardDocumentError::Parse(err.to_string()))
+    let doc = html::parse(Cursor::new(clean_html(html_content).as_bytes()))
+        .map_err(|err| ClipboardDocumentError::Parse(err.to_string()))?;
+    // E.g. a copied image: fall back to the plain-text alternative.
+    if doc.paragraphs.is_empty() {
+        return Err(ClipboardDocumentError::Empty);
+    }
+    Ok(doc)
+}
+
+/// Prepare HTML copied from a browser or an office suite for parsing.
+///
+/// Keeps only the copied fragment (between `<!--StartFragment-->` and
+/// `<!--EndFragment-->`, without the Windows `Version:…` header), drops
+/// `<head>`, `<style>` and `<script>`, and turns the inline styles those
+/// applications use for formatting into tags: a `<span>` with
+/// `font-weight: bold`/`700` becomes `<strong>`, one with `font-style: italic`
+/// `<em>`. The `<b style="font-weight:normal">` Google Docs wraps everything in
+/// is removed so the whole snippet doesn't turn bold.
+pub fn clean_html(html: &str) -> String {
+    let html = match (
+        html.find("<!--StartFragment-->"),
+        html.find("<!--EndFragment-->"),
+    ) {
+        (Some(start), Some(end)) if start < end => &html[start + "<!--StartFragment-->".len()..end],
+        _ => html.find('<').map_or(html, |start| &html[start..]),
+    };
+
+    let mut out = String::with_capacity(html.len());
+    // What to emit for the closing tag of each open `<span>`/`<b>`.
+    let mut closers: Vec<&'static str> = Vec::new();
+    let mut skipping: Option<String> = None;
+    let mut rest = html;
+
+    while let Some(lt) = rest.find('<') {
+        if skipping.is_none() {
+            out.push_str(&rest[..lt]);
+        }
+        let Some(len) = rest[lt..].find('>') else {
+            rest = &rest[lt..];
+            break;
+        };
+        let tag = &rest[lt..=lt + len];
+        rest = &rest[lt + len + 1..];
+
+        let closing = tag.starts_with("</");
+        let name: String = tag
+            .trim_start_matches(['<', '/'])
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        if let Some(skipped) = &skipping {
+            if closing && name == *skipped {
+                skipping = None;
+            }
+            continue;
+        }
+
+        match (name.as_str(), closing) {
+            ("head" | "style" | "script", false) => skipping = Some(name),
+            ("meta" | "link", _) => {}
+            ("span" | "b", false) => {
+                let style: String = attribute(tag, "style")
+                    .unwrap_or_default()
+                    .to_ascii_lowercase()
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect();
+                let weight = style
+                    .split(';')
+                    .find_map(|decl| decl.strip_prefix("font-weight:"));
+                let bold = match weight {
+                    Some(w) => {
+                        w == "bold" || w == "bolder" || w.parse::<u32>().is_ok_and(|n| n >= 600)
+                    }
+                    None => name == "b",
+                };
+                let italic = style.contains("font-style:italic");
+                let (open, close) = match (bold, italic) {
+                    (true, true) => ("<strong><em>", "</em></strong>"),
+                    (true, false) => ("<strong>", "</strong>"),
+                    (false, true) => ("<em>", "</em>"),
+                    (false, false) => ("", ""),
+                };
+                out.push_str(open);
+                closers.push(close);
+            }
+            ("span" | "b", true) => out.push_str(closers.pop().unwrap_or_default()),
+            _ => out.push_str(tag),
+        }
+    }
+    if skipping.is_none() && !rest.starts_with('<') {
+        out.push_str(rest);
+    }
+    out
+}
+
+/// The value of the attribute `name` in an HTML start tag.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(pos) = lower[from..].find(name) {
+        let start = from + pos;
+        from = start + name.len();
+        let preceded_by_space = lower[..start].ends_with(|c: char| c.is_whitespace());
+        let after = lower[from..].trim_start();
+        if !preceded_by_space || !after.starts_with('=') {
+            continue;
+        }
+        let value_start = tag.len() - after.len() + 1;
+        let value = tag[value_start..].trim_start();
+        let quote = value.chars().next()?;
+        return if quote == '"' || quote == '\'' {
+            value[1..].find(quote).map(|end| &value[1..=end])
+        } else {
+            value.split(|c: char| c.is_whitespace() || c == '>').next()
+        };
+    }
+    None
 }
 
 /// Copy plain text (e.g. a section link URL) to the system clipboard.
@@ -193,3 +399,53 @@ fn log_formats(formats: &[String]) {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_structure_is_detected() {
+        assert!(looks_like_markdown("# Heading\n\nSome text"));
+        assert!(looks_like_markdown("Intro\n- one\n- two"));
+        assert!(looks_like_markdown("1. first\n2. second"));
+        assert!(looks_like_markdown("> quoted"));
+        assert!(looks_like_markdown("```\ncode\n```"));
+        assert!(looks_like_markdown("| a | b |\n|---|---|"));
+        assert!(looks_like_markdown("This is **important**."));
+        assert!(looks_like_markdown("An *emphasized* word"));
+        assert!(looks_like_markdown("Run `cargo test` first"));
+        assert!(looks_like_markdown("See [the docs](https://example.com)"));
+        assert!(looks_like_markdown("Linked to [[other note]]"));
+    }
+
+    #[test]
+    fn clipboard_html_is_cleaned_up() {
+        let windows = "Version:0.9\r\nStartHTML:0000000105\r\n<html><body><!--StartFragment--><p>Hi <b>there</b></p><!--EndFragment--></body></html>";
+        assert_eq!(clean_html(windows), "<p>Hi <strong>there</strong></p>");
+
+        let docs = r#"<meta charset="utf-8"><b style="font-weight:normal;" id="docs-internal-guid-1"><p><span style="font-weight: 700;">Bold</span> and <span style="font-style:italic">italic</span> <span style="color:red">plain</span></p></b>"#;
+        assert_eq!(
+            clean_html(docs),
+            "<p><strong>Bold</strong> and <em>italic</em> plain</p>"
+        );
+
+        let page = "<html><head><title>T</title><style>p { color: red }</style></head><body><h1>Title</h1><script>x()</script><ul><li><a href='x'>link</a></li></ul></body></html>";
+        assert_eq!(
+            clean_html(page),
+            "<html><body><h1>Title</h1><ul><li><a href='x'>link</a></li></ul></body></html>"
+        );
+    }
+
+    #[test]
+    fn plain_text_is_not_markdown() {
+        assert!(!looks_like_markdown("Just a sentence."));
+        assert!(!looks_like_markdown(
+            "Jane Doe\n12 Main Street\nSpringfield"
+        ));
+        assert!(!looks_like_markdown("snake_case_name and other_thing"));
+        assert!(!looks_like_markdown("2 * 3 * 4 = 24"));
+        assert!(!looks_like_markdown("#hashtag and C# code"));
+        assert!(!looks_like_markdown("[draft] meeting (moved)"));
+    }
+}
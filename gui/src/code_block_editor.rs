@@ -0,0 +1,275 @@
+//! "Edit in External Editor" for code blocks.
+//!
+//! The code block at the cursor is written to a temporary file — with an
+//! extension guessed from its content, so the editor picks the right syntax
+//! mode — and opened in `$PIKI_EDITOR`, `$VISUAL` or `$EDITOR` (on macOS the
+//! default text editor if none is set). Every time the file is saved, the
+//! block is updated as one undoable edit; the file is removed once the editor
+//! exits. Editors that detach from the terminal need their "wait" flag, e.g.
+//! `code --wait`.
+
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::rc::Rc;
+use std::time::{Instant, SystemTime};
+
+use fltk::app;
+use rutle::editor::UndoKind;
+use rutle::renderer::Renderer;
+use rutle::tree_path::PathSegment;
+use tdoc::{Document, Paragraph};
+
+use crate::markdown_converter::{document_to_markdown, markdown_to_document};
+
+/// How often the temporary file and the editor process are checked.
+const POLL_INTERVAL_SECS: f64 = 0.5;
+
+/// Open the code block at the cursor of `display` in the external editor.
+/// `on_change` is called after each update of the block.
+pub fn edit_block_at_cursor(
+    display: Rc<RefCell<Renderer>>,
+    mut on_change: impl FnMut() + 'static,
+) -> Result<(), String> {
+    let (index, code) = {
+        let disp = display.borrow();
+        let editor = disp.editor();
+        let index = match editor.cursor().path.segments().first() {
+            Some(PathSegment::Paragraph(i)) => *i,
+            _ => return Err("The cursor is not in a code block.".to_string()),
+        };
+        let code = editor
+            .document()
+            .paragraphs
+            .get(index)
+            .and_then(code_block_text)
+            .ok_or("The cursor is not in a code block.")?;
+        (index, code)
+    };
+
+    let path = std::env::temp_dir().join(format!(
+        "piki-block-{}-{}.{}",
+        std::process::id(),
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0),
+        extension_for(&code)
+    ));
+    fs::write(&path, &code).map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    let mut child = match spawn_editor(&path) {
+        Ok(child) => child,
+        Err(e) => {
+            let _ = fs::remove_file(&path);
+            return Err(e);
+        }
+    };
+
+    let mut current = code;
+    let mut mtime = modified(&path);
+    app::add_timeout3(POLL_INTERVAL_SECS, move |handle| {
+        let exited = !matches!(child.try_wait(), Ok(None));
+
+        if modified(&path) != mtime {
+            mtime = modified(&path);
+            if let Ok(edited) = fs::read_to_string(&path) {
+                let edited = edited.strip_suffix('\n').unwrap_or(&edited).to_string();
+                if edited != current && replace_block(&display, index, &current, &edited) {
+                    current = edited;
+                    on_change();
+                    app::redraw();
+                }
+            }
+        }
+
+        if exited {
+            let _ = fs::remove_file(&path);
+        } else {
+            app::repeat_timeout3(POLL_INTERVAL_SECS, handle);
+        }
+    });
+    Ok(())
+}
+
+/// Replace the code of block `index` — if it still holds `expected` — with
+/// `code`, as one undoable edit. The cursor stays where it was.
+fn replace_block(
+    display: &Rc<RefCell<Renderer>>,
+    index: usize,
+    expected: &str,
+    code: &str,
+) -> bool {
+    let mut disp = display.borrow_mut();
+    let editor = disp.editor_mut();
+    let mut doc = editor.document().clone();
+    // The note may have been edited in the meantime; never touch another block.
+    if doc
+        .paragraphs
+        .get(index)
+        .and_then(code_block_text)
+        .as_deref()
+        != Some(expected)
+    {
+        return false;
+    }
+    let Some(block) = code_block(code) else {
+        return false;
+    };
+    doc.paragraphs[index] = block;
+
+    let cursor = editor.cursor();
+    let doc = markdown_to_document(&document_to_markdown(&doc));
+    editor.select_all();
+    let _ = editor.delete_selection();
+    if editor.insert_document(&doc).is_err() {
+        return false;
+    }
+    editor.set_cursor(cursor);
+    editor.commit_undo_step(UndoKind::Other, Instant::now());
+    true
+}
+
+/// The code of a code block, without the fences.
+pub(crate) fn code_block_text(paragraph: &Paragraph) -> Option<String> {
+    if !matches!(paragraph, Paragraph::CodeBlock { .. }) {
+        return None;
+    }
+    let mut doc = Document::new();
+    doc.add_paragraph(paragraph.clone());
+    Some(strip_fences(&document_to_markdown(&doc)))
+}
+
+/// A code block holding `code`.
+fn code_block(code: &str) -> Option<Paragraph> {
+    let fence = fence_for(code);
+    markdown_to_document(&format!("{fence}\n{code}\n{fence}\n"))
+        .paragraphs
+        .into_iter()
+        .next()
+        .filter(|p| matches!(p, Paragraph::CodeBlock { .. }))
+}
+
+/// The lines of a fenced code block's Markdown between its fences.
+pub fn strip_fences(markdown: &str) -> String {
+    let mut lines: Vec<&str> = markdown.trim_end().lines().collect();
+    let is_fence = |line: &&str| {
+        let t = line.trim_start();
+        t.starts_with("```") || t.starts_with("~~~")
+    };
+    if lines.first().is_some_and(is_fence) {
+        lines.remove(0);
+    }
+    if lines.last().is_some_and(is_fence) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// A backtick fence longer than any run of backticks inside `code`.
+pub fn fence_for(code: &str) -> String {
+    let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat((longest + 1).max(3))
+}
+
+/// A file extension for `code`, guessed from its shebang line or first
+/// characters so the editor picks a fitting syntax mode.
+pub fn extension_for(code: &str) -> &'static str {
+    let first = code.lines().next().unwrap_or("").trim();
+    if let Some(shebang) = first.strip_prefix("#!") {
+        let interpreter = shebang
+            .split_whitespace()
+            .find(|word| !word.ends_with("/env") && !word.starts_with('-'))
+            .and_then(|word| word.rsplit('/').next())
+            .unwrap_or("");
+        return match interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+            "python" => "py",
+            "node" => "js",
+            "ruby" => "rb",
+            "perl" => "pl",
+            "php" => "php",
+            "fish" => "fish",
+            _ => "sh",
+        };
+    }
+    let trimmed = code.trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        "json"
+    } else if trimmed.starts_with('<') {
+        "html"
+    } else {
+        "txt"
+    }
+}
+
+/// Start the configured editor on `path`.
+fn spawn_editor(path: &Path) -> Result<Child, String> {
+    let configured = ["PIKI_EDITOR", "VISUAL", "EDITOR"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.trim().is_empty()));
+
+    let mut command = match configured {
+        #[cfg(not(target_os = "windows"))]
+        Some(editor) => {
+            // Let the shell split e.g. `code --wait` into program and arguments.
+            let mut command = Command::new("sh");
+            command
+                .arg("-c")
+                .arg(format!("{editor} \"$1\""))
+                .arg("sh")
+                .arg(path);
+            command
+        }
+        #[cfg(target_os = "windows")]
+        Some(editor) => {
+            let mut command = Command::new("cmd");
+            command.arg("/C").arg(editor).arg(path);
+            command
+        }
+        #[cfg(target_os = "macos")]
+        None => {
+            let mut command = Command::new("open");
+            command.args(["-W", "-t"]).arg(path);
+            command
+        }
+        #[cfg(not(target_os = "macos"))]
+        None => {
+            return Err(
+                "No external editor configured. Set $VISUAL or $EDITOR (e.g. to \"code --wait\")."
+                    .to_string(),
+            );
+        }
+    };
+    command
+        .stdin(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start the external editor: {e}"))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guesses_extensions() {
+        assert_eq!(extension_for("#!/usr/bin/env python3\nprint(1)"), "py");
+        assert_eq!(extension_for("#!/bin/bash\necho hi"), "sh");
+        assert_eq!(extension_for("#!/usr/bin/env -S node --no-warnings"), "js");
+        assert_eq!(extension_for("{\"a\": 1}"), "json");
+        assert_eq!(extension_for("plain"), "txt");
+    }
+
+    #[test]
+    fn fences() {
+        assert_eq!(
+            strip_fences("```\nlet a = 1;\n\nb\n```\n"),
+            "let a = 1;\n\nb"
+        );
+        assert_eq!(fence_for("no ticks"), "```");
+        assert_eq!(fence_for("a ```` b"), "`````");
+    }
+}
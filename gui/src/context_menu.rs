@@ -10,6 +10,10 @@ pub struct MenuActions {
     pub has_selection: bool,
     /// Current block type at cursor, for radio selection state
     pub current_block: BlockType,
+    /// Whether the code block at the cursor holds a Mermaid or Graphviz diagram
+    pub in_diagram: bool,
+    /// Whether the caret is in a checklist
+    pub in_checklist: bool,
 
     // Block styles
     pub set_paragraph: Box<dyn FnMut()>,
@@ -38,12 +42,20 @@ pub struct MenuActions {
 
     // Links
     pub edit_link: Box<dyn FnMut()>,
+
+    // Checklists
+    pub archive_done_items: Box<dyn FnMut()>,
+
+    // Code blocks
+    pub edit_code_block: Box<dyn FnMut()>,
+    pub preview_diagram: Box<dyn FnMut()>,
 }
 
 /// Show a context menu at the given screen position (x, y) with standard entries.
 pub fn show_context_menu(x: i32, y: i32, mut actions: MenuActions) {
     let mut menu = MenuButton::default();
     menu.set_pos(x, y);
+    let in_code_block = matches!(actions.current_block, BlockType::CodeBlock { .. });
 
     // Paragraph Style submenu with accelerators
     #[cfg(target_os = "macos")]
@@ -280,6 +292,32 @@ pub fn show_context_menu(x: i32, y: i32, mut actions: MenuActions) {
         move |_| (actions.edit_link)(),
     );
 
+    if actions.in_checklist {
+        menu.add(
+            "Archive Done Items\t",
+            Shortcut::None,
+            MenuFlag::Normal,
+            move |_| (actions.archive_done_items)(),
+        );
+    }
+
+    if in_code_block {
+        menu.add(
+            "Edit in External Editor…\t",
+            Shortcut::None,
+            MenuFlag::Normal,
+            move |_| (actions.edit_code_block)(),
+        );
+        if actions.in_diagram {
+            menu.add(
+                "Preview Diagram…\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| (actions.preview_diagram)(),
+            );
+        }
+    }
+
     menu.add(
         "_Clear Formatting\t",
         clear_shortcut,
@@ -0,0 +1,182 @@
+//! Insert → Date …: a dialog that turns "next friday", "in 3 days" or
+//! "oct 23" into a `YYYY-MM-DD` date, inserted as a `@due(…)` annotation or
+//! as plain text.
+
+use std::rc::Rc;
+
+use chrono::Datelike;
+use fltk::{
+    button,
+    enums::{Align, CallbackTrigger, Color, Event, Key},
+    frame, input,
+    prelude::{GroupExt, InputExt, WidgetBase, WidgetExt, WindowExt},
+    window,
+};
+use piki_core::dates::{Date, due_hint};
+
+const DLG_W: i32 = 420;
+const DLG_H: i32 = 150;
+
+/// Today's date in the local time zone.
+pub fn today() -> Date {
+    let now = chrono::Local::now().date_naive();
+    Date::from_ymd(i64::from(now.year()), now.month(), now.day())
+        .expect("chrono produced an invalid date")
+}
+
+/// The text inserted for `date`: a due date annotation or the bare date.
+fn insertion(date: Date, as_due_date: bool) -> String {
+    if as_due_date {
+        format!("@due({date})")
+    } else {
+        date.to_string()
+    }
+}
+
+/// The line under the input: the date `text` stands for, or why there is none.
+fn preview(text: &str, today: Date) -> (Option<Date>, String) {
+    if text.trim().is_empty() {
+        return (
+            None,
+            "Try “tomorrow”, “next friday”, “in 2 weeks” or “oct 23”".into(),
+        );
+    }
+    match Date::parse_natural(text, today) {
+        Some(date) => (
+            Some(date),
+            format!(
+                "{}, {date} — {}",
+                date.weekday_name(),
+                date.relative_to(today)
+            ),
+        ),
+        None => (None, "Not a date I understand".into()),
+    }
+}
+
+/// Show the date dialog, centered over `center_rect` (x, y, w, h) if given.
+/// `on_insert` receives the text to insert at the cursor.
+pub fn show_date_picker(
+    center_rect: Option<(i32, i32, i32, i32)>,
+    on_insert: impl Fn(String) + 'static,
+) {
+    let today = today();
+    let mut win = window::Window::new(0, 0, DLG_W, DLG_H, Some("Insert Date"));
+
+    let mut label = frame::Frame::new(10, 10, 60, 24, Some("Date:"));
+    label.set_align(Align::Inside | Align::Left);
+    let mut date_input = input::Input::new(70, 10, 340, 24, None);
+    date_input.set_value("tomorrow");
+
+    let mut preview_line = frame::Frame::new(70, 38, 340, 20, None);
+    preview_line.set_align(Align::Inside | Align::Left);
+    preview_line.set_label_size(12);
+
+    let mut due_check = button::CheckButton::new(70, 64, 340, 22, Some("Insert as due date"));
+    due_check.set_checked(true);
+    let mut example = frame::Frame::new(70, 84, 340, 16, None);
+    example.set_align(Align::Inside | Align::Left);
+    example.set_label_size(11);
+    example.set_label_color(Color::Dark3);
+
+    let mut cancel_btn = button::Button::new(230, 110, 80, 30, Some("Cancel"));
+    let mut insert_btn = button::ReturnButton::new(320, 110, 80, 30, Some("Insert"));
+    win.end();
+
+    // Keeps the preview, example and Insert button in step with the input.
+    let refresh: Rc<dyn Fn()> = {
+        let date_input = date_input.clone();
+        let due_check = due_check.clone();
+        let preview_line = preview_line.clone();
+        let example = example.clone();
+        let insert_btn = insert_btn.clone();
+        let win = win.clone();
+        Rc::new(move || {
+            let (date, text) = preview(&date_input.value(), today);
+            preview_line.clone().set_label(&text);
+            match date {
+                Some(date) => {
+                    let hint = if due_check.is_checked() {
+                        format!("  ({})", due_hint(date, today))
+                    } else {
+                        String::new()
+                    };
+                    example.clone().set_label(&format!(
+                        "Inserts {}{hint}",
+                        insertion(date, due_check.is_checked())
+                    ));
+                    insert_btn.clone().activate();
+                }
+                None => {
+                    example.clone().set_label("");
+                    insert_btn.clone().deactivate();
+                }
+            }
+            win.clone().redraw();
+        })
+    };
+    refresh();
+
+    date_input.set_trigger(CallbackTrigger::Changed);
+    date_input.set_callback({
+        let refresh = refresh.clone();
+        move |_| refresh()
+    });
+    due_check.set_callback({
+        let refresh = refresh.clone();
+        move |_| refresh()
+    });
+
+    insert_btn.set_callback({
+        let date_input = date_input.clone();
+        let due_check = due_check.clone();
+        let mut win = win.clone();
+        move |_| {
+            if let Some(date) = Date::parse_natural(&date_input.value(), today) {
+                on_insert(insertion(date, due_check.is_checked()));
+                win.hide();
+            }
+        }
+    });
+    cancel_btn.set_callback({
+        let mut win = win.clone();
+        move |_| win.hide()
+    });
+
+    win.make_modal(true);
+    let (x, y) = match center_rect {
+        Some((px, py, pw, ph)) => (px + (pw - DLG_W) / 2, py + (ph - DLG_H) / 2),
+        None => {
+            let (sx, sy, sw, sh) = fltk::app::screen_xywh(0);
+            (sx + (sw - DLG_W) / 2, sy + (sh - DLG_H) / 2)
+        }
+    };
+    win.set_pos(x.max(0), y.max(0));
+    win.show();
+    let _ = date_input.take_focus();
+    date_input.set_position(0).ok();
+    date_input.set_mark(date_input.value().len() as i32).ok();
+
+    win.handle(move |_, e| {
+        if e == Event::KeyDown && fltk::app::event_key() == Key::Escape {
+            cancel_btn.do_callback();
+            return true;
+        }
+        false
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn previews_the_date() {
+        let today = Date::parse("2026-10-15").unwrap();
+        let (date, text) = preview("next friday", today);
+        assert_eq!(text, "Friday, 2026-10-23 — in 8 days");
+        assert_eq!(insertion(date.unwrap(), true), "@due(2026-10-23)");
+        assert_eq!(insertion(date.unwrap(), false), "2026-10-23");
+        assert_eq!(preview("whenever", today).0, None);
+    }
+}
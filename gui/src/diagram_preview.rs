@@ -0,0 +1,288 @@
+//! "Preview Diagram" for Mermaid and Graphviz code blocks.
+//!
+//! tdoc keeps a code block's text but not the language after its opening
+//! fence, so diagrams are recognized by their first keyword: `digraph`/`graph
+//! name {` for Graphviz, `flowchart`, `sequenceDiagram` and friends for
+//! Mermaid. The block is rendered to a PNG by the tool that draws it — `dot`
+//! from Graphviz or `mmdc` from mermaid-cli — and shown in a window that can
+//! be toggled between the diagram and its source. Rendering runs in the
+//! background; the window opens once the image is ready.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::time::SystemTime;
+
+use fltk::{
+    app, button, frame, group,
+    image::PngImage,
+    prelude::{DisplayExt, GroupExt, ImageExt, WidgetBase, WidgetExt},
+    text, window,
+};
+use rutle::editor::Editor;
+use rutle::tree_path::PathSegment;
+
+use crate::code_block_editor::code_block_text;
+
+/// How often the renderer process is checked.
+const POLL_INTERVAL_SECS: f64 = 0.2;
+
+/// The tool drawing a diagram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagramKind {
+    Mermaid,
+    Graphviz,
+}
+
+/// First words of the Mermaid diagram types.
+const MERMAID_KEYWORDS: &[&str] = &[
+    "graph",
+    "flowchart",
+    "sequenceDiagram",
+    "classDiagram",
+    "stateDiagram",
+    "stateDiagram-v2",
+    "erDiagram",
+    "journey",
+    "gantt",
+    "pie",
+    "quadrantChart",
+    "requirementDiagram",
+    "gitGraph",
+    "mindmap",
+    "timeline",
+];
+
+impl DiagramKind {
+    /// The kind of diagram `code` describes, if any. Blank lines and comments
+    /// (`%%` in Mermaid, `//` and `#` in Graphviz) before it are skipped.
+    pub fn detect(code: &str) -> Option<DiagramKind> {
+        let first = code.lines().map(str::trim).find(|line| {
+            !line.is_empty()
+                && !line.starts_with("%%")
+                && !line.starts_with("//")
+                && !line.starts_with('#')
+        })?;
+        let mut words = first.split_whitespace();
+        let keyword = words.next()?;
+        let keyword = match keyword {
+            "strict" => words.next().unwrap_or(""),
+            keyword => keyword,
+        };
+        match keyword {
+            "digraph" => Some(DiagramKind::Graphviz),
+            // `graph TD` is Mermaid, `graph name {` is Graphviz.
+            "graph" if first.contains('{') => Some(DiagramKind::Graphviz),
+            k if MERMAID_KEYWORDS.contains(&k.trim_end_matches(':')) => Some(DiagramKind::Mermaid),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            DiagramKind::Mermaid => "Mermaid",
+            DiagramKind::Graphviz => "Graphviz",
+        }
+    }
+
+    /// The command rendering `input` to the PNG `output`.
+    fn command(self, input: &Path, output: &Path) -> Command {
+        match self {
+            DiagramKind::Graphviz => {
+                let mut command = Command::new("dot");
+                command.arg("-Tpng").arg("-o").arg(output).arg(input);
+                command
+            }
+            DiagramKind::Mermaid => {
+                let mut command = Command::new("mmdc");
+                command
+                    .arg("--quiet")
+                    .args(["--backgroundColor", "white"])
+                    .arg("-i")
+                    .arg(input)
+                    .arg("-o")
+                    .arg(output);
+                command
+            }
+        }
+    }
+
+    fn missing_tool(self) -> &'static str {
+        match self {
+            DiagramKind::Graphviz => {
+                "Previewing Graphviz diagrams needs the `dot` command. Install Graphviz."
+            }
+            DiagramKind::Mermaid => {
+                "Previewing Mermaid diagrams needs the `mmdc` command. Install it with `npm install -g @mermaid-js/mermaid-cli`."
+            }
+        }
+    }
+}
+
+/// The diagram in the code block at the cursor, if it holds one.
+pub fn diagram_at_cursor(editor: &Editor) -> Option<(DiagramKind, String)> {
+    let index = match editor.cursor().path.segments().first() {
+        Some(PathSegment::Paragraph(i)) => *i,
+        _ => return None,
+    };
+    let code = editor
+        .document()
+        .paragraphs
+        .get(index)
+        .and_then(code_block_text)?;
+    DiagramKind::detect(&code).map(|kind| (kind, code))
+}
+
+/// Render the diagram in the code block at the cursor of `editor` and show it
+/// in a preview window.
+pub fn preview_block_at_cursor(editor: &Editor) -> Result<(), String> {
+    let (kind, code) = diagram_at_cursor(editor)
+        .ok_or("The cursor is not in a Mermaid or Graphviz code block.")?;
+
+    let stem = std::env::temp_dir().join(format!(
+        "piki-diagram-{}-{}",
+        std::process::id(),
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0),
+    ));
+    let input = stem.with_extension(match kind {
+        DiagramKind::Mermaid => "mmd",
+        DiagramKind::Graphviz => "dot",
+    });
+    let output = stem.with_extension("png");
+    fs::write(&input, &code).map_err(|e| format!("Failed to write {}: {e}", input.display()))?;
+
+    let mut child = match spawn(kind, &input, &output) {
+        Ok(child) => child,
+        Err(e) => {
+            let _ = fs::remove_file(&input);
+            return Err(e);
+        }
+    };
+
+    app::add_timeout3(POLL_INTERVAL_SECS, move |handle| {
+        let status = match child.try_wait() {
+            Ok(None) => {
+                app::repeat_timeout3(POLL_INTERVAL_SECS, handle);
+                return;
+            }
+            Ok(Some(status)) => Some(status),
+            Err(_) => None,
+        };
+        let image = match status {
+            Some(status) if status.success() => PngImage::load(&output)
+                .map_err(|e| format!("Failed to load the rendered diagram: {e}")),
+            _ => Err(render_error(kind, &mut child)),
+        };
+        let _ = fs::remove_file(&input);
+        let _ = fs::remove_file(&output);
+        match image {
+            Ok(image) => show_window(kind, image, &code),
+            Err(e) => fltk::dialog::alert_default(&e),
+        }
+    });
+    Ok(())
+}
+
+fn spawn(kind: DiagramKind, input: &Path, output: &Path) -> Result<Child, String> {
+    kind.command(input, output)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => kind.missing_tool().to_string(),
+            _ => format!("Failed to render the diagram: {e}"),
+        })
+}
+
+/// The renderer's complaint about the diagram, for the alert.
+fn render_error(kind: DiagramKind, child: &mut Child) -> String {
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        let _ = std::io::Read::read_to_string(&mut pipe, &mut stderr);
+    }
+    let stderr = stderr.trim();
+    if stderr.is_empty() {
+        format!("The {} diagram could not be rendered.", kind.label())
+    } else {
+        format!(
+            "The {} diagram could not be rendered:\n\n{stderr}",
+            kind.label()
+        )
+    }
+}
+
+/// A window showing `image`, with a button switching to the `source` and back.
+fn show_window(kind: DiagramKind, image: PngImage, source: &str) {
+    let (screen_w, screen_h) = app::screen_size();
+    let bar = 40;
+    let w = (image.w() + 20).clamp(320, (screen_w * 0.8) as i32);
+    let h = (image.h() + 20 + bar).clamp(200, (screen_h * 0.8) as i32);
+    let x = (screen_w as i32 - w) / 2;
+    let y = (screen_h as i32 - h) / 2;
+
+    let title = format!("{} Preview", kind.label());
+    let mut win = window::Window::new(x.max(0), y.max(0), w, h, None);
+    win.set_label(&title);
+
+    let mut scroll = group::Scroll::new(0, 0, w, h - bar, None);
+    let mut picture = frame::Frame::new(10, 10, image.w(), image.h(), None);
+    picture.set_image(Some(image));
+    scroll.end();
+
+    let mut source_view = text::TextDisplay::new(0, 0, w, h - bar, None);
+    let mut buffer = text::TextBuffer::default();
+    buffer.set_text(source);
+    source_view.set_buffer(buffer);
+    source_view.set_text_font(fltk::enums::Font::Courier);
+    source_view.hide();
+
+    let mut toggle = button::Button::new(w - 130, h - bar + 5, 120, 30, Some("Show Source"));
+    win.end();
+    win.make_resizable(true);
+    win.resizable(&scroll);
+
+    toggle.set_callback(move |b| {
+        if source_view.visible() {
+            source_view.hide();
+            scroll.show();
+            b.set_label("Show Source");
+        } else {
+            scroll.hide();
+            source_view.show();
+            b.set_label("Show Diagram");
+        }
+    });
+
+    win.show();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_diagrams() {
+        assert_eq!(
+            DiagramKind::detect("graph TD\n  A --> B\n"),
+            Some(DiagramKind::Mermaid)
+        );
+        assert_eq!(
+            DiagramKind::detect("%% comment\nsequenceDiagram\n  A->>B: hi\n"),
+            Some(DiagramKind::Mermaid)
+        );
+        assert_eq!(
+            DiagramKind::detect("digraph G {\n  a -> b\n}\n"),
+            Some(DiagramKind::Graphviz)
+        );
+        assert_eq!(
+            DiagramKind::detect("strict graph {\n  a -- b\n}\n"),
+            Some(DiagramKind::Graphviz)
+        );
+        assert_eq!(DiagramKind::detect("let graph = 1;\n"), None);
+        assert_eq!(DiagramKind::detect(""), None);
+    }
+}
@@ -0,0 +1,341 @@
+//! piki's note editor as a widget for other FLTK applications.
+//!
+//! [`PikiEditorWidget`] is the editor without the app around it: no window,
+//! menu bar or status bar, and no window state or session files. It is an
+//! FLTK group that sits wherever it is created, shows one note of a
+//! [`DocumentStore`] at a time, follows links between notes and saves edits
+//! with the file's own formatting kept (see [`piki_core::format`]).
+//!
+//! The host decides when to save — [`PikiEditorWidget::save`], e.g. from its
+//! own timer or menu — and is told what happens through callbacks:
+//!
+//! - [`on_load`](PikiEditorWidget::on_load): a note was opened, by
+//!   [`open`](PikiEditorWidget::open) or by following a link
+//! - [`on_save`](PikiEditorWidget::on_save): a note was written to disk
+//! - [`on_change`](PikiEditorWidget::on_change): the user edited the note
+//! - [`on_link`](PikiEditorWidget::on_link): a link was clicked; return
+//!   `true` to handle it yourself, `false` for the default (notes open in the
+//!   widget, everything else in the system browser)
+//!
+//! ```no_run
+//! use fltk::{app, prelude::*, window::Window};
+//! use piki_core::DocumentStore;
+//! use piki_gui::embed::PikiEditorWidget;
+//!
+//! let app = app::App::default();
+//! let mut win = Window::new(100, 100, 800, 600, "Dashboard");
+//! let editor = PikiEditorWidget::new(0, 0, 800, 600, DocumentStore::new("notes".into()));
+//! win.end();
+//! win.show();
+//!
+//! editor.on_load(|note| println!("opened {note}"));
+//! editor.on_save(|note| println!("saved {note}"));
+//! editor.open("frontpage").unwrap();
+//! app.run().unwrap();
+//! editor.save().unwrap();
+//! ```
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Instant;
+
+use fltk::{app, group::Group, prelude::WidgetExt};
+use piki_core::script;
+use piki_core::stats::StatsPlugin;
+use piki_core::timesheet::TimesheetPlugin;
+use piki_core::{
+    Document, DocumentStore, IndexPlugin, PluginRegistry, RandomPlugin, TodoPlugin, format,
+};
+
+use crate::content::{ContentLoader, ContentProvider};
+use crate::link_handler::is_external_link;
+use crate::note_ui::NoteUI;
+use crate::section_link;
+use crate::ui_adapters::StructuredRichUI;
+
+type NoteCallback = Option<Box<dyn FnMut(&str)>>;
+type LinkCallback = Option<Box<dyn FnMut(&str) -> bool>>;
+
+/// Interval of the caret blink timer, as in the app.
+const TICK_SECS: f64 = 0.1;
+
+/// The note editor as an embeddable widget. Cloning gives another handle to
+/// the same widget.
+#[derive(Clone)]
+pub struct PikiEditorWidget {
+    ui: Rc<RefCell<StructuredRichUI>>,
+    state: Rc<RefCell<State>>,
+}
+
+struct State {
+    store: DocumentStore,
+    plugins: PluginRegistry,
+    /// The note on screen; `!name` for a plugin page.
+    current: Option<String>,
+    /// The note's file as it was loaded or last saved.
+    original: String,
+    /// The editor's serialization of `original`.
+    normalized: String,
+    dirty: bool,
+    load_cb: NoteCallback,
+    save_cb: NoteCallback,
+    link_cb: LinkCallback,
+    change_cb: Option<Box<dyn FnMut()>>,
+}
+
+impl PikiEditorWidget {
+    /// Create the editor at the given position inside the current FLTK group,
+    /// showing notes from `store`. The `!index`, `!todo`, `!random`,
+    /// `!timesheet` and `!stats` plugin pages and the wiki's scripts (see
+    /// [`piki_core::script`]) are available, read-only.
+    pub fn new(x: i32, y: i32, w: i32, h: i32, store: DocumentStore) -> Self {
+        let mut plugins = PluginRegistry::new();
+        plugins.register("index", Box::new(IndexPlugin));
+        plugins.register("todo", Box::new(TodoPlugin));
+        plugins.register("random", Box::new(RandomPlugin));
+        plugins.register("timesheet", Box::new(TimesheetPlugin));
+        plugins.register("stats", Box::new(StatsPlugin));
+        script::register_scripts(&mut plugins, store.base_path());
+
+        let widget = PikiEditorWidget {
+            ui: Rc::new(RefCell::new(StructuredRichUI::new(x, y, w, h, true))),
+            state: Rc::new(RefCell::new(State {
+                store,
+                plugins,
+                current: None,
+                original: String::new(),
+                normalized: String::new(),
+                dirty: false,
+                load_cb: None,
+                save_cb: None,
+                link_cb: None,
+                change_cb: None,
+            })),
+        };
+        widget.ui.borrow_mut().set_readonly(true);
+
+        {
+            let state = Rc::downgrade(&widget.state);
+            widget.ui.borrow_mut().on_change(Box::new(move || {
+                let Some(state) = state.upgrade() else {
+                    return;
+                };
+                state.borrow_mut().dirty = true;
+                let cb = state.borrow_mut().change_cb.take();
+                if let Some(mut cb) = cb {
+                    cb();
+                    state.borrow_mut().change_cb.get_or_insert(cb);
+                }
+            }));
+        }
+        {
+            // Links are followed once the click has been handled, as following
+            // one replaces the document the click happened in. The editor's
+            // callbacks hold weak handles, so the widget doesn't keep itself
+            // alive.
+            let (ui, state) = (Rc::downgrade(&widget.ui), Rc::downgrade(&widget.state));
+            widget
+                .ui
+                .borrow_mut()
+                .on_link_click(Box::new(move |target: String| {
+                    if let (Some(ui), Some(state)) = (ui.upgrade(), state.upgrade()) {
+                        let handle = PikiEditorWidget { ui, state };
+                        app::awake_callback(move || handle.follow_link(&target));
+                    }
+                }));
+        }
+        {
+            // Blinks the caret and keeps due date hints current.
+            let ui = Rc::downgrade(&widget.ui);
+            let start = Instant::now();
+            app::add_timeout3(TICK_SECS, move |handle| {
+                if let Some(ui) = ui.upgrade() {
+                    if let Ok(mut ui) = ui.try_borrow_mut() {
+                        ui.tick(start.elapsed().as_millis() as u64);
+                    }
+                    app::repeat_timeout3(TICK_SECS, handle);
+                }
+            });
+        }
+
+        widget
+    }
+
+    /// The FLTK group holding the editor, e.g. to make it the window's
+    /// resizable widget.
+    pub fn group(&self) -> Group {
+        self.ui.borrow().0.group.clone()
+    }
+
+    /// Open `note` (optionally `note#section`, or `!plugin`), saving the
+    /// current note first. A note without a file starts out empty and is
+    /// created on its first save.
+    pub fn open(&self, note: &str) -> Result<(), String> {
+        let (name, section) = section_link::split_target(note);
+        self.save()?;
+
+        let content = {
+            let state = self.state.borrow();
+            match name.strip_prefix('!') {
+                Some(plugin) => state.plugins.generate(plugin, &state.store)?,
+                None => state.store.load(name)?.content,
+            }
+        };
+        let normalized = {
+            let mut ui = self.ui.borrow_mut();
+            ui.set_content_from_markdown(&content);
+            ui.set_readonly(name.starts_with('!'));
+            if let Some(section) = section {
+                ui.scroll_to_fragment(section, &content);
+            }
+            ui.get_content()
+        };
+        {
+            let mut state = self.state.borrow_mut();
+            state.current = Some(name.to_string());
+            state.original = content;
+            state.normalized = normalized;
+            state.dirty = false;
+        }
+        self.ui.borrow().0.group.clone().redraw();
+
+        let cb = self.state.borrow_mut().load_cb.take();
+        if let Some(mut cb) = cb {
+            cb(name);
+            self.state.borrow_mut().load_cb.get_or_insert(cb);
+        }
+        Ok(())
+    }
+
+    /// Write the current note's edits to its file. Returns whether anything
+    /// was written: unedited notes and plugin pages are left alone.
+    pub fn save(&self) -> Result<bool, String> {
+        let (name, text, edited) = {
+            let state = self.state.borrow();
+            let Some(name) = state.current.clone().filter(|n| !n.starts_with('!')) else {
+                return Ok(false);
+            };
+            if !state.dirty {
+                return Ok(false);
+            }
+            let edited = self.ui.borrow().get_content();
+            let text = format::preserve_formatting(&state.original, &state.normalized, &edited);
+            (name, text, edited)
+        };
+
+        {
+            let mut state = self.state.borrow_mut();
+            state.dirty = false;
+            if text == state.original {
+                return Ok(false);
+            }
+            let doc = Document {
+                name: name.clone(),
+                path: state.store.path_for(&name),
+                content: text.clone(),
+                modified_time: None,
+            };
+            if let Err(e) = state.store.save(&doc) {
+                state.dirty = true;
+                return Err(e);
+            }
+            state.original = text;
+            state.normalized = edited;
+        }
+
+        let cb = self.state.borrow_mut().save_cb.take();
+        if let Some(mut cb) = cb {
+            cb(&name);
+            self.state.borrow_mut().save_cb.get_or_insert(cb);
+        }
+        Ok(true)
+    }
+
+    /// The note on screen, if any; `!name` for a plugin page.
+    pub fn current_note(&self) -> Option<String> {
+        self.state.borrow().current.clone()
+    }
+
+    /// Whether the note has edits that haven't been saved.
+    pub fn is_dirty(&self) -> bool {
+        self.state.borrow().dirty
+    }
+
+    /// The note as the editor shows it, in Markdown.
+    pub fn content(&self) -> String {
+        self.ui.borrow().get_content()
+    }
+
+    /// Show notes without allowing edits. Plugin pages are always read-only.
+    pub fn set_readonly(&self, readonly: bool) {
+        let is_plugin = self
+            .current_note()
+            .is_some_and(|note| note.starts_with('!'));
+        self.ui.borrow_mut().set_readonly(readonly || is_plugin);
+    }
+
+    /// Called with the note's name after a note is opened.
+    pub fn on_load(&self, f: impl FnMut(&str) + 'static) {
+        self.state.borrow_mut().load_cb = Some(Box::new(f));
+    }
+
+    /// Called with the note's name after its edits are written to disk.
+    pub fn on_save(&self, f: impl FnMut(&str) + 'static) {
+        self.state.borrow_mut().save_cb = Some(Box::new(f));
+    }
+
+    /// Called after every edit.
+    pub fn on_change(&self, f: impl FnMut() + 'static) {
+        self.state.borrow_mut().change_cb = Some(Box::new(f));
+    }
+
+    /// Called with a clicked link's target (`note`, `note#section`, a URL, …)
+    /// before it is followed. Return `true` if the link was handled, `false`
+    /// to have the widget follow it.
+    pub fn on_link(&self, f: impl FnMut(&str) -> bool + 'static) {
+        self.state.borrow_mut().link_cb = Some(Box::new(f));
+    }
+
+    fn follow_link(&self, target: &str) {
+        let cb = self.state.borrow_mut().link_cb.take();
+        if let Some(mut cb) = cb {
+            let handled = cb(target);
+            self.state.borrow_mut().link_cb.get_or_insert(cb);
+            if handled {
+                return;
+            }
+        }
+
+        let normalized = section_link::normalize_link_target(target);
+        if is_external_link(&normalized) {
+            let _ = webbrowser::open(&normalized);
+            return;
+        }
+        let (note, section) = section_link::split_target(&normalized);
+        // Links written in a note are relative to its folder; `piki:` URLs
+        // name notes from the top of the wiki.
+        let note = {
+            let state = self.state.borrow();
+            match &state.current {
+                Some(current) if normalized == target => state.store.resolve_link(current, note),
+                _ => note.to_string(),
+            }
+        };
+        let attachment = self.state.borrow().store.attachment_path(&note);
+        let target = match section {
+            Some(section) => format!("{note}#{section}"),
+            None => note.clone(),
+        };
+        if let Some(path) = attachment {
+            let _ = webbrowser::open(&path.to_string_lossy());
+        } else if note.is_empty() {
+            if let Some(section) = section {
+                let content = self.content();
+                self.ui.borrow_mut().scroll_to_fragment(section, &content);
+                self.ui.borrow().0.group.clone().redraw();
+            }
+        } else if let Err(e) = self.open(&target) {
+            eprintln!("Failed to open {note}: {e}");
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! Files dropped onto the editor.
+//!
+//! FLTK delivers a drop as a paste whose text lists the dropped files, one per
+//! line: plain paths on macOS and Windows, `file://` URLs on X11 and Wayland.
+//! [`dropped_paths`] turns that text into paths; the app decides what to do
+//! with each file (markdown files become notes, anything else an attachment).
+
+use piki_core::percent_decode;
+use std::path::PathBuf;
+
+/// The files named by the text of a drop. Returns an empty list if any line
+/// is not an absolute path or `file://` URL — then text was dragged, not files.
+pub fn dropped_paths(text: &str) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let path = match line.strip_prefix("file://") {
+            // `file:///home/…` or `file://localhost/home/…`
+            Some(rest) => {
+                let Some(slash) = rest.find('/') else {
+                    return Vec::new();
+                };
+                let host = &rest[..slash];
+                if !host.is_empty() && host != "localhost" {
+                    return Vec::new();
+                }
+                PathBuf::from(percent_decode(&rest[slash..]))
+            }
+            None => PathBuf::from(line),
+        };
+        if !path.is_absolute() {
+            return Vec::new();
+        }
+        paths.push(path);
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_and_file_urls() {
+        assert_eq!(
+            dropped_paths("file:///home/me/My%20Notes/idea.md\r\nfile://localhost/tmp/a.png\n"),
+            vec![
+                PathBuf::from("/home/me/My Notes/idea.md"),
+                PathBuf::from("/tmp/a.png")
+            ]
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            dropped_paths("/Users/me/photo.jpg"),
+            vec![PathBuf::from("/Users/me/photo.jpg")]
+        );
+    }
+
+    #[test]
+    fn dragged_text_is_not_a_file() {
+        assert!(dropped_paths("some dragged words").is_empty());
+        assert!(dropped_paths("https://example.com/a.png").is_empty());
+        assert!(dropped_paths("file://server/share/a.png").is_empty());
+        assert!(dropped_paths("").is_empty());
+    }
+}
@@ -1,17 +1,67 @@
 use fltk::{draw as fltk_draw, enums::*, prelude::*};
+use piki_core::dates::{Date, due_hint, find_due_dates};
 use rutle::render_context::{CaretLean, FontStyle, FontType, RenderContext};
 
-/// FLTK implementation of rutle's [`RenderContext`].
+use crate::theme::{self, Appearance, Typography};
+
+/// FLTK implementation of rutle's [`RenderContext`]. Colors are drawn in the
+/// palette of the current [`theme`], text in its [`Typography`].
 pub struct FltkDrawContext {
     has_focus: bool,
     is_active: bool,
+    appearance: Appearance,
+    typography: Typography,
+    /// The last color set by the renderer, before the palette is applied.
+    color: u32,
+    /// Whether the renderer is drawing code, where due dates aren't hinted.
+    in_code: bool,
+    /// The day due date hints count from; `None` draws no hints.
+    today: Option<Date>,
+    /// Left edge, baseline and right edge of each text run drawn so far.
+    runs: Vec<(i32, i32, i32)>,
+    due_hints: Vec<DueHint>,
+    /// Top and bottom of the text left undimmed in focus mode.
+    focus: Option<(i32, i32)>,
+    /// Left, top, right and bottom of the areas whose text gets a highlight
+    /// background.
+    highlights: Vec<(i32, i32, i32, i32)>,
+}
+
+/// A relative hint ("in 3 days", "overdue") waiting to be drawn after the
+/// due date annotation ending at `x`.
+struct DueHint {
+    x: i32,
+    y: i32,
+    size: i32,
+    text: String,
+    overdue: bool,
 }
 
+/// Gap between a due date and its hint.
+const HINT_GAP: i32 = 6;
+const HINT_COLOR: u32 = 0x888888FF;
+const OVERDUE_COLOR: u32 = 0xCC3333FF;
+/// The background of highlighted text, such as search matches.
+const HIGHLIGHT_COLOR: u32 = 0xFFE58AFF;
+/// The editor's background, which dimmed text fades into.
+const BACKGROUND_COLOR: u32 = 0xFFFFF5FF;
+/// How far dimmed text fades into the background.
+const DIM_WEIGHT: f32 = 0.65;
+
 impl FltkDrawContext {
     pub fn new(has_focus: bool, is_active: bool) -> Self {
         FltkDrawContext {
             has_focus,
             is_active,
+            appearance: theme::current(),
+            typography: theme::typography(),
+            color: 0x000000FF,
+            in_code: false,
+            today: None,
+            runs: Vec::new(),
+            due_hints: Vec::new(),
+            focus: None,
+            highlights: Vec::new(),
         }
     }
 
@@ -21,12 +71,159 @@ impl FltkDrawContext {
 
         Self::new(has_focus, widget.active())
     }
+
+    /// Hint how far away each due date (`@due(…)`, `📅 …`) is, counted from
+    /// `today`. The hints are collected while the renderer draws and drawn
+    /// afterwards by [`Self::draw_due_hints`], once it is known which lines
+    /// have room for them.
+    pub fn with_due_hints(mut self, today: Date) -> Self {
+        self.today = Some(today);
+        self
+    }
+
+    /// Dim text and lines above `top` and below `bottom`, for focus mode.
+    pub fn with_focus(mut self, top: i32, bottom: i32) -> Self {
+        self.focus = Some((top, bottom));
+        self
+    }
+
+    /// Give the text within each of `areas` (left, top, right, bottom) a
+    /// highlight background. A text run is highlighted where it overlaps an
+    /// area its baseline lies in.
+    pub fn with_highlights(mut self, areas: Vec<(i32, i32, i32, i32)>) -> Self {
+        self.highlights = areas;
+        self
+    }
+
+    /// Draw `text` in the style of the due date hints, ending at `right` on
+    /// the line between `top` and `bottom` — unless the line's text reaches
+    /// that far. Call before [`Self::draw_due_hints`], which forgets the lines.
+    pub fn draw_margin_note(&mut self, text: &str, right: i32, top: i32, bottom: i32) {
+        let baseline = self
+            .runs
+            .iter()
+            .map(|&(_, y, _)| y)
+            .find(|y| (top..=bottom).contains(y))
+            .unwrap_or(bottom - (bottom - top) / 5);
+        fltk_draw::set_font(Font::HelveticaItalic, self.typography.scale(11));
+        let start = right - fltk_draw::width(text).ceil() as i32;
+        let blocked = self
+            .runs
+            .iter()
+            .any(|&(_, y, run_right)| y == baseline && run_right + HINT_GAP > start);
+        if blocked {
+            return;
+        }
+        self.set_color(HINT_COLOR);
+        fltk_draw::draw_text(text, start, baseline);
+    }
+
+    /// Draw the hints collected while drawing, each right after its due date
+    /// in a smaller font — unless other text follows on the same line or the
+    /// hint would reach past `right`.
+    pub fn draw_due_hints(&mut self, right: i32) {
+        for hint in std::mem::take(&mut self.due_hints) {
+            fltk_draw::set_font(Font::HelveticaItalic, hint.size);
+            let start = hint.x + HINT_GAP;
+            let end = start + fltk_draw::width(&hint.text).ceil() as i32;
+            let blocked = end > right
+                || self.runs.iter().any(|&(left, y, run_right)| {
+                    (y - hint.y).abs() <= 2 && left < end + HINT_GAP && run_right > hint.x + 1
+                });
+            if blocked {
+                continue;
+            }
+            self.set_color(if hint.overdue {
+                OVERDUE_COLOR
+            } else {
+                HINT_COLOR
+            });
+            fltk_draw::draw_text(&hint.text, start, hint.y);
+        }
+        self.runs.clear();
+    }
+}
+
+/// Make `family` the font of body text and headings. It takes over FLTK's
+/// Times fonts, which nothing else in the UI uses, with the family's bold and
+/// italic styles named the way the platform's font backend looks them up.
+pub fn load_font_family(family: &str) {
+    let styles = if cfg!(target_os = "macos") {
+        [
+            family.to_string(),
+            format!("{family}-Bold"),
+            format!("{family}-Italic"),
+            format!("{family}-BoldItalic"),
+        ]
+    } else {
+        [
+            format!(" {family}"),
+            format!("B{family}"),
+            format!("I{family}"),
+            format!("P{family}"),
+        ]
+    };
+    let slots = [
+        Font::Times,
+        Font::TimesBold,
+        Font::TimesItalic,
+        Font::TimesBoldItalic,
+    ];
+    for (slot, name) in slots.into_iter().zip(styles) {
+        Font::set_font(slot, &name);
+    }
 }
 
 impl FltkDrawContext {
+    /// In focus mode, switch to the dimmed draw color if `y` lies outside the
+    /// focused text. Returns whether it did, so the caller can switch back.
+    fn dim_at(&mut self, y: i32) -> bool {
+        let Some((top, bottom)) = self.focus else {
+            return false;
+        };
+        if (top..=bottom).contains(&y) {
+            return false;
+        }
+        let color = self.appearance.palette.map(self.color);
+        let dimmed = self.color_average(color, BACKGROUND_COLOR, DIM_WEIGHT);
+        let r = ((dimmed >> 24) & 0xFF) as u8;
+        let g = ((dimmed >> 16) & 0xFF) as u8;
+        let b = ((dimmed >> 8) & 0xFF) as u8;
+        fltk_draw::set_draw_color(Color::from_rgb(r, g, b));
+        true
+    }
+
+    /// Fill the highlight background behind the text run `text` at `x` with
+    /// baseline `y`, where it overlaps a highlighted area.
+    fn highlight_behind(&mut self, text: &str, x: i32, y: i32) {
+        let right = x + fltk_draw::width(text).round() as i32;
+        let color = self.color;
+        let mut filled = false;
+        let areas = std::mem::take(&mut self.highlights);
+        for &(left, top, area_right, bottom) in &areas {
+            let (start, end) = (left.max(x), area_right.min(right));
+            if (top..=bottom).contains(&y) && start < end {
+                self.set_color(HIGHLIGHT_COLOR);
+                fltk_draw::draw_rectf(start, top, end - start, bottom - top);
+                filled = true;
+            }
+        }
+        self.highlights = areas;
+        if filled {
+            self.set_color(color);
+        }
+    }
+
     fn inner_set_font(&self, font: FontType, style: FontStyle, size: u8) {
+        let custom = self.typography.font.is_some();
         fltk_draw::set_font(
             match font {
+                FontType::Content | FontType::Heading if custom => match style {
+                    FontStyle::Regular => Font::Times,
+                    FontStyle::Bold => Font::TimesBold,
+                    FontStyle::Italic => Font::TimesItalic,
+                    FontStyle::BoldItalic => Font::TimesBoldItalic,
+                },
                 FontType::Content => match style {
                     FontStyle::Regular => Font::Helvetica,
                     FontStyle::Bold => Font::HelveticaBold,
@@ -46,13 +243,15 @@ impl FltkDrawContext {
                     FontStyle::BoldItalic => Font::HelveticaBoldItalic,
                 },
             },
-            size as i32,
+            self.typography.scale(size),
         );
     }
 }
 
 impl RenderContext for FltkDrawContext {
     fn set_color(&mut self, color: u32) {
+        self.color = color;
+        let color = self.appearance.palette.map(color);
         let r = ((color >> 24) & 0xFF) as u8;
         let g = ((color >> 16) & 0xFF) as u8;
         let b = ((color >> 8) & 0xFF) as u8;
@@ -60,11 +259,39 @@ impl RenderContext for FltkDrawContext {
     }
 
     fn set_font(&mut self, font: FontType, style: FontStyle, size: u8) {
+        self.in_code = matches!(font, FontType::Code);
         self.inner_set_font(font, style, size);
     }
 
     fn draw_text(&mut self, text: &str, x: i32, y: i32) {
+        if !self.highlights.is_empty() {
+            self.highlight_behind(text, x, y);
+        }
+        let dimmed = self.dim_at(y);
         fltk_draw::draw_text(text, x, y);
+        if let Some(today) = self.today {
+            let width = fltk_draw::width(text).round() as i32;
+            self.runs.push((x, y, x + width));
+            if !self.in_code {
+                for (range, due) in find_due_dates(text) {
+                    self.due_hints.push(DueHint {
+                        x: x + fltk_draw::width(&text[..range.end]).round() as i32,
+                        y,
+                        size: (fltk_draw::size() * 4 / 5).max(9),
+                        text: due_hint(due, today),
+                        overdue: due < today,
+                    });
+                }
+            }
+        }
+        if self.appearance.underline_links && theme::is_link_color(self.color) {
+            let width = fltk_draw::width(text).round() as i32;
+            let y = y + (fltk_draw::descent() / 2).max(1);
+            fltk_draw::draw_line(x, y, x + width, y);
+        }
+        if dimmed {
+            self.set_color(self.color);
+        }
     }
 
     fn draw_rect_filled(&mut self, x: i32, y: i32, w: i32, h: i32) {
@@ -72,7 +299,11 @@ impl RenderContext for FltkDrawContext {
     }
 
     fn draw_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32) {
+        let dimmed = self.dim_at(y1.min(y2));
         fltk_draw::draw_line(x1, y1, x2, y2);
+        if dimmed {
+            self.set_color(self.color);
+        }
     }
 
     /// Piki's caret design: a uniform-width bracket leaning toward the affinity
@@ -121,7 +352,7 @@ impl RenderContext for FltkDrawContext {
 
     fn text_height(&self, font: FontType, style: FontStyle, size: u8) -> i32 {
         self.inner_set_font(font, style, size);
-        fltk_draw::height()
+        self.typography.line_height(fltk_draw::height())
     }
 
     fn text_descent(&self, font: FontType, style: FontStyle, size: u8) -> i32 {
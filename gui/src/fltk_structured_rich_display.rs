@@ -1,14 +1,27 @@
 // FLTK integration for rutle's Renderer
 
+use crate::auto_pair;
+use crate::checklist_progress;
 use crate::clipboard;
+use crate::date_picker;
+use crate::file_drop;
 use crate::fltk_draw_context::FltkDrawContext;
+use crate::list_drag;
+use crate::markdown_shortcuts;
 use crate::responsive_scrollbar::ResponsiveScrollbar;
+use crate::smart_punctuation;
+use crate::theme;
 use fltk::{app::MouseWheel, enums::*, prelude::*};
+use piki_core::dates::Date;
 use rutle::editor::UndoKind;
 use rutle::renderer::Renderer;
 use rutle::structured_document::{BlockType, InlineContent};
-use std::cell::RefCell;
+use rutle::tree_path::{DocumentPosition, TreePath};
+use rutle::tree_walk::{enumerate_leaves, leaf_plain_text};
+use std::cell::{Cell, RefCell};
 use std::ffi::CStr;
+use std::ops::Range;
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::time::{Duration, Instant};
 
@@ -31,6 +44,32 @@ pub struct FltkStructuredRichDisplay {
     hover_cb: Callback<Option<String>>,
     change_cb: MutCallback0,
     paragraph_cb: MutCallback<BlockType>,
+    drop_cb: MutCallback<Vec<PathBuf>>,
+    checkbox_cb: MutCallback<Vec<String>>,
+    /// The day the due date hints were last drawn for.
+    hint_day: Rc<Cell<Option<Date>>>,
+    /// Edit mode, or read mode: no caret, keys only scroll, and clicks only
+    /// follow links and tick checklist markers.
+    edit_mode: Rc<Cell<bool>>,
+    /// Whether the loaded page cannot be edited, which hides the caret even in
+    /// edit mode and hands clicked checkboxes to the checkbox callback.
+    readonly: Rc<Cell<bool>>,
+    /// Focus mode, which may dim all but the paragraph with the caret.
+    focus_mode: Rc<Cell<bool>>,
+    /// Text drawn on a highlight background until the next edit or click.
+    highlights: Rc<RefCell<Vec<Highlight>>>,
+    /// The loaded page's frontmatter, kept out of the document so it isn't
+    /// parsed as Markdown, and put back in front of it when saving.
+    frontmatter: String,
+}
+
+/// A stretch of text to draw on a highlight background, such as a search
+/// match: a byte range in the text of one block, counted in the order of
+/// [`enumerate_leaves`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Highlight {
+    pub block: usize,
+    pub range: Range<usize>,
 }
 
 const SCROLLBAR_WIDTH: i32 = 15;
@@ -40,6 +79,119 @@ const SCROLLBAR_WIDTH: i32 = 15;
 /// press only ever moves the paragraph by a single increment.
 const BLOCK_MOVE_DEBOUNCE: Duration = Duration::from_millis(120);
 
+/// Pixels between the rows probed for the caret's paragraph in focus mode.
+const FOCUS_PROBE_STEP: i32 = 3;
+
+/// The rows of the viewport, from top to bottom, showing the paragraph or list
+/// item with the caret, which focus mode leaves undimmed.
+fn caret_block_rows(disp: &Renderer) -> Option<(i32, i32)> {
+    let caret = disp.editor().cursor().path.segments().to_vec();
+    let x = disp.horizontal_padding() + 1;
+    let mut rows: Option<(i32, i32)> = None;
+    for y in (0..disp.h()).step_by(FOCUS_PROBE_STEP as usize) {
+        if disp.xy_to_position(x, y).path.segments() == caret.as_slice() {
+            let top = rows.map_or(y, |(top, _)| top);
+            rows = Some((top, y + FOCUS_PROBE_STEP));
+        }
+    }
+    rows
+}
+
+/// The areas (left, top, right, bottom) covering `highlights` on screen,
+/// between the text column's edges `left` and `right`. Highlights that no
+/// longer fit the document are skipped.
+///
+/// The renderer maps only the caret to pixels, so the caret visits both ends
+/// of each highlight and is put back, selection and all, afterwards.
+fn highlight_areas(
+    disp: &mut Renderer,
+    ctx: &mut FltkDrawContext,
+    highlights: &[Highlight],
+    left: i32,
+    right: i32,
+) -> Vec<(i32, i32, i32, i32)> {
+    let cursor = disp.editor().cursor();
+    let selection = disp.editor().selection();
+    let leaves = enumerate_leaves(disp.editor().document());
+
+    let mut areas = Vec::new();
+    for highlight in highlights {
+        let Some(leaf) = leaves.get(highlight.block) else {
+            continue;
+        };
+        let text = leaf_plain_text(disp.editor().document(), &leaf.path);
+        let Range { start, end } = highlight.range;
+        if start >= end || !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+            continue;
+        }
+        let mut at = |offset| {
+            disp.editor_mut()
+                .set_cursor(DocumentPosition::at(leaf.path.clone(), offset));
+            let (x, top) = disp.cursor_screen_position(ctx)?;
+            let (_, height) = disp.cursor_content_y(ctx)?;
+            Some((x, top, top + height))
+        };
+        let (Some((x0, top0, bottom0)), Some((x1, top1, bottom1))) = (at(start), at(end)) else {
+            continue;
+        };
+        if top0 == top1 {
+            areas.push((x0, top0, x1, bottom0));
+        } else {
+            // Wrapped: the rest of the first line, the lines in between and
+            // the start of the last.
+            areas.push((x0, top0, right, bottom0));
+            areas.push((left, bottom0, right, top1));
+            areas.push((left, top1, x1, bottom1));
+        }
+    }
+
+    let editor = disp.editor_mut();
+    match selection {
+        Some((a, b)) => {
+            editor.set_cursor(if a == cursor { b } else { a });
+            editor.extend_selection_to(cursor);
+        }
+        None => editor.set_cursor(cursor),
+    }
+    areas
+}
+
+/// Pixels the pointer must travel before pressing a list marker becomes
+/// dragging its item.
+const LIST_DRAG_THRESHOLD: i32 = 4;
+
+/// A list item pressed by its marker, which may be dragged elsewhere in its
+/// list.
+struct ListDrag {
+    /// Where the item was pressed, in window coordinates.
+    start_y: i32,
+    /// Whether the pointer has moved far enough to drag the item.
+    dragging: bool,
+    /// A pressed checkbox, ticked on release unless its item was dragged.
+    checkbox: Option<TreePath>,
+}
+
+/// Whether (`x`, `y`), local to the widget, is on a list item's bullet,
+/// number or checkbox. Puts the caret at the start of that item if so.
+fn press_list_marker(disp: &mut Renderer, w: &impl WidgetExt, x: i32, y: i32) -> bool {
+    let pos = disp.xy_to_position(x, y);
+    if list_drag::list_item(&pos.path).is_none() {
+        return false;
+    }
+    if disp.checklist_marker_hit(x, y).is_some() {
+        disp.editor_mut().set_cursor(pos);
+        return true;
+    }
+    if pos.offset != 0 {
+        return false;
+    }
+    // The item's text starts where the caret lands; the marker is left of it.
+    disp.editor_mut().set_cursor(pos);
+    let mut ctx = FltkDrawContext::from_widget_ptr(w);
+    disp.cursor_screen_position(&mut ctx)
+        .is_some_and(|(caret_x, _)| x + w.x() < caret_x)
+}
+
 impl FltkStructuredRichDisplay {
     pub fn new(x: i32, y: i32, w: i32, h: i32, edit_mode: bool) -> Self {
         let mut widget = fltk::group::Group::new(x, y, w, h, None);
@@ -59,14 +211,25 @@ impl FltkStructuredRichDisplay {
         // Track when a link click is in progress to prevent cursor repositioning
         let link_click_in_progress = Rc::new(RefCell::new(false));
 
+        // Set on a drag-and-drop release: the paste that follows carries the
+        // dropped data rather than the clipboard.
+        let dnd_pending = Rc::new(RefCell::new(false));
+
+        // The list item being dragged by its marker, if any.
+        let list_drag: Rc<RefCell<Option<ListDrag>>> = Rc::new(RefCell::new(None));
+
         // Set cursor visibility based on edit mode
         display.borrow_mut().set_cursor_visible(edit_mode);
+        let edit_mode = Rc::new(Cell::new(edit_mode));
 
         // Callbacks holders
         let link_callback: Callback<String> = Rc::new(RefCell::new(None));
         let change_callback: MutCallback0 = Rc::new(RefCell::new(None));
         let hover_callback: Callback<Option<String>> = Rc::new(RefCell::new(None));
         let paragraph_callback: MutCallback<BlockType> = Rc::new(RefCell::new(None));
+        let drop_callback: MutCallback<Vec<PathBuf>> = Rc::new(RefCell::new(None));
+        let checkbox_callback: MutCallback<Vec<String>> = Rc::new(RefCell::new(None));
+        let readonly = Rc::new(Cell::new(false));
 
         // Create vertical responsive scrollbar
         let mut vscroll = ResponsiveScrollbar::new(
@@ -94,9 +257,17 @@ impl FltkStructuredRichDisplay {
         vscroll.set_step(1.0, 10);
         vscroll.set_value(0.0);
 
+        let hint_day: Rc<Cell<Option<Date>>> = Rc::new(Cell::new(None));
+        let focus_mode = Rc::new(Cell::new(false));
+        let highlights: Rc<RefCell<Vec<Highlight>>> = Rc::new(RefCell::new(Vec::new()));
+
         widget.draw({
             let display = display.clone();
             let mut vscroll_draw = vscroll.clone();
+            let hint_day = hint_day.clone();
+            let focus_mode = focus_mode.clone();
+            let edit_mode = edit_mode.clone();
+            let highlights = highlights.clone();
             move |w| {
                 let mut disp = display.borrow_mut();
 
@@ -118,9 +289,42 @@ impl FltkStructuredRichDisplay {
                     vscroll_draw.set_value(disp.scroll_offset() as f64);
                 }
 
-                // Draw the display
-                let mut ctx = FltkDrawContext::from_widget_ptr(w);
+                // Draw the display, then the due date hints it left room for
+                let today = date_picker::today();
+                hint_day.set(Some(today));
+                let mut ctx = FltkDrawContext::from_widget_ptr(w).with_due_hints(today);
+                if !highlights.borrow().is_empty() {
+                    let left = w.x() + disp.horizontal_padding();
+                    let right = w.x() + w.w() - SCROLLBAR_WIDTH - disp.horizontal_padding();
+                    let areas =
+                        highlight_areas(&mut disp, &mut ctx, &highlights.borrow(), left, right);
+                    ctx = ctx.with_highlights(areas);
+                }
+                if focus_mode.get()
+                    && theme::typography().focus_dim
+                    && let Some((top, bottom)) = caret_block_rows(&disp)
+                {
+                    ctx = ctx.with_focus(w.y() + top, w.y() + bottom);
+                }
                 disp.draw(&mut ctx);
+                fltk::draw::push_clip(w.x(), w.y(), w.w() - SCROLLBAR_WIDTH, w.h());
+                if edit_mode.get()
+                    && let Some((done, total)) = checklist_progress::progress(
+                        disp.editor().document(),
+                        &disp.editor().cursor().path,
+                    )
+                    && let Some((_, top)) = disp.cursor_screen_position(&mut ctx)
+                {
+                    let height = disp.cursor_content_y(&mut ctx).map_or(0, |(_, h)| h);
+                    ctx.draw_margin_note(
+                        &format!("{done}/{total} done"),
+                        w.x() + w.w() - SCROLLBAR_WIDTH - disp.horizontal_padding(),
+                        top,
+                        top + height,
+                    );
+                }
+                ctx.draw_due_hints(w.x() + w.w() - SCROLLBAR_WIDTH - disp.horizontal_padding());
+                fltk::draw::pop_clip();
 
                 // Keep the macOS press-and-hold accent popup anchored to the
                 // caret. Layout is current right after `draw`, so report the
@@ -146,8 +350,27 @@ impl FltkStructuredRichDisplay {
             let link_cb = link_callback.clone();
             let hover_cb = hover_callback.clone();
             let change_cb = change_callback.clone();
+            let drop_cb = drop_callback.clone();
+            let dnd_pending = dnd_pending.clone();
             let last_block_move = last_block_move.clone();
+            let edit_mode = edit_mode.clone();
+            let readonly = readonly.clone();
+            let checkbox_cb = checkbox_callback.clone();
+            let list_drag = list_drag.clone();
+            let highlights = highlights.clone();
             move |w, event| {
+                // Highlights mark a spot to look at; editing or clicking
+                // moves on from it.
+                let dismiss = match event {
+                    Event::KeyDown | Event::Paste => edit_mode.get() && !readonly.get(),
+                    Event::Push => fltk::app::event_x() < w.x() + w.w() - SCROLLBAR_WIDTH,
+                    _ => false,
+                };
+                if dismiss && !highlights.borrow().is_empty() {
+                    highlights.borrow_mut().clear();
+                    w.redraw();
+                }
+
                 // Handle hover checking for Push, Drag, Move, and Enter
                 let check_hover = matches!(
                     event,
@@ -194,8 +417,62 @@ impl FltkStructuredRichDisplay {
 
                 match event {
                     Event::Push => {
-                        // Toggle checklist markers on left-click in edit mode
-                        if edit_mode && fltk::app::event_button() == 1 {
+                        // A read-only page can't be ticked; its checkboxes go to
+                        // the checkbox callback, with the links in their item.
+                        if readonly.get() && fltk::app::event_button() == 1 {
+                            let local_x = fltk::app::event_x() - w.x();
+                            let local_y = fltk::app::event_y() - w.y();
+                            let links = {
+                                let disp = display.borrow_mut();
+                                disp.checklist_marker_hit(local_x, local_y).map(|_| {
+                                    let pos = disp.xy_to_position(local_x, local_y);
+                                    rutle::tree_walk::leaf_inline(
+                                        disp.editor().document(),
+                                        &pos.path,
+                                    )
+                                    .iter()
+                                    .filter_map(|inline| match inline {
+                                        InlineContent::Link { link, .. } => {
+                                            Some(link.destination.clone())
+                                        }
+                                        _ => None,
+                                    })
+                                    .collect::<Vec<_>>()
+                                })
+                            };
+                            if let Some(links) = links {
+                                if let Some(cb) = &mut *checkbox_cb.borrow_mut() {
+                                    (cb)(links);
+                                }
+                                return true;
+                            }
+                        }
+
+                        // In edit mode, pressing a list marker may start dragging
+                        // its item; a checkbox is ticked on release instead.
+                        if edit_mode.get() && fltk::app::event_button() == 1 {
+                            let local_x = fltk::app::event_x() - w.x();
+                            let local_y = fltk::app::event_y() - w.y();
+                            let pressed = {
+                                let mut disp = display.borrow_mut();
+                                press_list_marker(&mut disp, &*w, local_x, local_y).then(|| {
+                                    ListDrag {
+                                        start_y: fltk::app::event_y(),
+                                        dragging: false,
+                                        checkbox: disp.checklist_marker_hit(local_x, local_y),
+                                    }
+                                })
+                            };
+                            if let Some(drag) = pressed {
+                                *list_drag.borrow_mut() = Some(drag);
+                                w.take_focus().ok();
+                                w.redraw();
+                                return true;
+                            }
+                        }
+
+                        // Toggle checklist markers on left-click, in read mode too
+                        if fltk::app::event_button() == 1 {
                             let local_x = fltk::app::event_x() - w.x();
                             let local_y = fltk::app::event_y() - w.y();
                             let toggled = {
@@ -223,7 +500,7 @@ impl FltkStructuredRichDisplay {
                         }
 
                         // Check for right-click context menu in edit mode (button 3 is right-click)
-                        if edit_mode && fltk::app::event_button() == 3 {
+                        if edit_mode.get() && fltk::app::event_button() == 3 {
                             let x = fltk::app::event_x();
                             let y = fltk::app::event_y();
 
@@ -240,9 +517,23 @@ impl FltkStructuredRichDisplay {
                             // Determine current block type based on caret position
                             let current_block = display.borrow().editor().current_block_type();
                             let w_for_actions = w.clone();
+                            let in_diagram = crate::diagram_preview::diagram_at_cursor(
+                                display.borrow().editor(),
+                            )
+                            .is_some();
+                            let in_checklist = {
+                                let d = display.borrow();
+                                checklist_progress::progress(
+                                    d.editor().document(),
+                                    &d.editor().cursor().path,
+                                )
+                                .is_some()
+                            };
                             let actions = crate::context_menu::MenuActions {
                                 has_selection,
                                 current_block,
+                                in_diagram,
+                                in_checklist,
                                 set_paragraph: Box::new({
                                     let display = display.clone();
                                     let change_cb = change_cb.clone();
@@ -511,6 +802,53 @@ impl FltkStructuredRichDisplay {
                                         fltk::app::paste(&w_r);
                                     }
                                 }),
+                                edit_code_block: Box::new({
+                                    let display = display.clone();
+                                    let change_cb = change_cb.clone();
+                                    let w_r = w_for_actions.clone();
+                                    move || {
+                                        let change_cb = change_cb.clone();
+                                        let mut w_r = w_r.clone();
+                                        let result = crate::code_block_editor::edit_block_at_cursor(
+                                            display.clone(),
+                                            move || {
+                                                if let Some(cb) = &mut *change_cb.borrow_mut() {
+                                                    (cb)();
+                                                }
+                                                w_r.redraw();
+                                            },
+                                        );
+                                        if let Err(e) = result {
+                                            fltk::dialog::alert_default(&e);
+                                        }
+                                    }
+                                }),
+                                archive_done_items: Box::new({
+                                    let display = display.clone();
+                                    let change_cb = change_cb.clone();
+                                    let mut w_r = w_for_actions.clone();
+                                    move || {
+                                        let archived = checklist_progress::archive_done_items(
+                                            display.borrow_mut().editor_mut(),
+                                        );
+                                        if archived && let Some(cb) = &mut *change_cb.borrow_mut() {
+                                            (cb)();
+                                        }
+                                        w_r.redraw();
+                                    }
+                                }),
+                                preview_diagram: Box::new({
+                                    let display = display.clone();
+                                    move || {
+                                        let result =
+                                            crate::diagram_preview::preview_block_at_cursor(
+                                                display.borrow().editor(),
+                                            );
+                                        if let Err(e) = result {
+                                            fltk::dialog::alert_default(&e);
+                                        }
+                                    }
+                                }),
                                 edit_link: Box::new({
                                     let display = display.clone();
                                     let change_cb = change_cb.clone();
@@ -689,7 +1027,7 @@ impl FltkStructuredRichDisplay {
                                 return true;
                             }
                             return false;
-                        } else if edit_mode {
+                        } else if edit_mode.get() {
                             // Not on a link - handle cursor positioning and selection in edit mode
                             let pos = {
                                 let d = display.borrow();
@@ -733,8 +1071,22 @@ impl FltkStructuredRichDisplay {
                             return true;
                         }
 
+                        // Dragging a list item by its marker
+                        if let Some(drag) = &mut *list_drag.borrow_mut() {
+                            let y = fltk::app::event_y();
+                            if (y - drag.start_y).abs() >= LIST_DRAG_THRESHOLD {
+                                drag.dragging = true;
+                            }
+                            if drag.dragging
+                                && let Some(mut win) = w.window()
+                            {
+                                win.set_cursor(Cursor::Move);
+                            }
+                            return true;
+                        }
+
                         // In edit mode, handle drag selection and auto-scroll
-                        if edit_mode {
+                        if edit_mode.get() {
                             let x = fltk::app::event_x();
                             let y = fltk::app::event_y();
 
@@ -795,6 +1147,43 @@ impl FltkStructuredRichDisplay {
                     Event::Released => {
                         // Clear link click flag on mouse release
                         *link_click_flag.borrow_mut() = false;
+
+                        // Drop a dragged list item, or tick a pressed checkbox
+                        let Some(drag) = list_drag.borrow_mut().take() else {
+                            return true;
+                        };
+                        let changed = {
+                            let mut disp = display.borrow_mut();
+                            if drag.dragging {
+                                let to = disp.xy_to_position(
+                                    fltk::app::event_x() - w.x(),
+                                    fltk::app::event_y() - w.y(),
+                                );
+                                let from = disp.editor().cursor();
+                                let offset =
+                                    list_drag::drop_offset(disp.editor().document(), &from, &to);
+                                list_drag::move_item(disp.editor_mut(), offset)
+                            } else if let Some(path) = drag.checkbox {
+                                let toggled = disp
+                                    .editor_mut()
+                                    .toggle_checkmark_at(path)
+                                    .unwrap_or_default();
+                                if toggled {
+                                    disp.editor_mut()
+                                        .commit_undo_step(UndoKind::Other, Instant::now());
+                                }
+                                toggled
+                            } else {
+                                false
+                            }
+                        };
+                        if changed && let Some(cb) = &mut *change_cb.borrow_mut() {
+                            (cb)();
+                        }
+                        if let Some(mut win) = w.window() {
+                            win.set_cursor(Cursor::Default);
+                        }
+                        w.redraw();
                         true
                     }
                     Event::Move | Event::Enter | Event::Leave => {
@@ -839,7 +1228,7 @@ impl FltkStructuredRichDisplay {
                         let state = fltk::app::event_state();
 
                         // Handle editing keys if in edit mode
-                        if edit_mode {
+                        if edit_mode.get() {
                             let mut handled = false;
                             let mut did_horizontal = false;
                             // Undo classification for whatever edit this key makes.
@@ -999,6 +1388,18 @@ impl FltkStructuredRichDisplay {
                                             .borrow()
                                             .editor()
                                             .current_block_type(),
+                                        in_diagram: crate::diagram_preview::diagram_at_cursor(
+                                            display.borrow().editor(),
+                                        )
+                                        .is_some(),
+                                        in_checklist: {
+                                            let d = display.borrow();
+                                            checklist_progress::progress(
+                                                d.editor().document(),
+                                                &d.editor().cursor().path,
+                                            )
+                                            .is_some()
+                                        },
                                         set_paragraph: Box::new({
                                             let display = display.clone();
                                             let mut w_r = w_for_actions.clone();
@@ -1228,6 +1629,59 @@ impl FltkStructuredRichDisplay {
                                                 fltk::app::paste(&w_r);
                                             }
                                         }),
+                                        edit_code_block: Box::new({
+                                            let display = display.clone();
+                                            let change_cb = change_cb.clone();
+                                            let w_r = w_for_actions.clone();
+                                            move || {
+                                                let change_cb = change_cb.clone();
+                                                let mut w_r = w_r.clone();
+                                                let result =
+                                                    crate::code_block_editor::edit_block_at_cursor(
+                                                        display.clone(),
+                                                        move || {
+                                                            if let Some(cb) =
+                                                                &mut *change_cb.borrow_mut()
+                                                            {
+                                                                (cb)();
+                                                            }
+                                                            w_r.redraw();
+                                                        },
+                                                    );
+                                                if let Err(e) = result {
+                                                    fltk::dialog::alert_default(&e);
+                                                }
+                                            }
+                                        }),
+                                        archive_done_items: Box::new({
+                                            let display = display.clone();
+                                            let change_cb = change_cb.clone();
+                                            let mut w_r = w_for_actions.clone();
+                                            move || {
+                                                let archived =
+                                                    checklist_progress::archive_done_items(
+                                                        display.borrow_mut().editor_mut(),
+                                                    );
+                                                if archived
+                                                    && let Some(cb) = &mut *change_cb.borrow_mut()
+                                                {
+                                                    (cb)();
+                                                }
+                                                w_r.redraw();
+                                            }
+                                        }),
+                                        preview_diagram: Box::new({
+                                            let display = display.clone();
+                                            move || {
+                                                let result =
+                                                    crate::diagram_preview::preview_block_at_cursor(
+                                                        display.borrow().editor(),
+                                                    );
+                                                if let Err(e) = result {
+                                                    fltk::dialog::alert_default(&e);
+                                                }
+                                            }
+                                        }),
                                         edit_link: Box::new({
                                             let display = display.clone();
                                             let w_for_dialog = w.clone();
@@ -1465,8 +1919,12 @@ impl FltkStructuredRichDisplay {
                                     }
                                     handled = true;
                                 }
-                                // Cmd/Ctrl-P (insert continuation paragraph)
-                                else if cmd_modifier && key == Key::from_char('p') {
+                                // Cmd-P (insert continuation paragraph); Ctrl-P
+                                // elsewhere opens the quick switcher instead
+                                else if cmd_modifier
+                                    && cfg!(target_os = "macos")
+                                    && key == Key::from_char('p')
+                                {
                                     let mut disp = display.borrow_mut();
                                     disp.editor_mut().insert_continuation().ok();
                                     if let Some(cb) = &mut *change_cb.borrow_mut() {
@@ -1873,11 +2331,33 @@ impl FltkStructuredRichDisplay {
                                                         }
                                                     }
 
-                                                    if !text_input.is_empty()
+                                                    let paired = if text_input.is_empty() {
+                                                        None
+                                                    } else {
+                                                        auto_pair::apply(editor, &text_input)
+                                                    };
+                                                    if let Some(changed) = paired {
+                                                        // Wrapped the selection, closed a
+                                                        // bracket or stepped over one.
+                                                        text_changed |= changed;
+                                                        did_horizontal = true;
+                                                        handled = true;
+                                                    } else if !text_input.is_empty()
                                                         && editor.insert_text(&text_input).is_ok()
                                                     {
                                                         text_changed = true;
                                                         did_horizontal = true;
+                                                        // Curly quotes, dashes and ellipses,
+                                                        // if the wiki wants them.
+                                                        smart_punctuation::apply(
+                                                            editor,
+                                                            &text_input,
+                                                        );
+                                                        // `# `, `- `, `> `, ``` … at the start
+                                                        // of a paragraph convert the block.
+                                                        if text_input == " " || text_input == "`" {
+                                                            markdown_shortcuts::apply(editor);
+                                                        }
                                                     }
                                                 }
 
@@ -1974,56 +2454,86 @@ impl FltkStructuredRichDisplay {
                             }
                         }
                     }
-                    Event::Paste => {
-                        if edit_mode {
-                            let fallback_text = fltk::app::event_text();
-                            let (platform_formats, platform_rtf) = inspect_platform_clipboard();
+                    Event::DndEnter | Event::DndDrag | Event::DndLeave => edit_mode.get(),
+                    Event::DndRelease => {
+                        if edit_mode.get() {
+                            // Drop where the mouse is, not at the old caret.
+                            let pos = display.borrow().xy_to_position(
+                                fltk::app::event_x() - w.x(),
+                                fltk::app::event_y() - w.y(),
+                            );
+                            display.borrow_mut().editor_mut().set_cursor(pos);
+                            *dnd_pending.borrow_mut() = true;
+                        }
+                        edit_mode.get()
+                    }
+                    Event::Paste if dnd_pending.replace(false) => {
+                        let text = fltk::app::event_text();
+                        let paths = file_drop::dropped_paths(&text);
+                        if !paths.is_empty() {
+                            if let Some(cb) = &mut *drop_cb.borrow_mut() {
+                                (cb)(paths);
+                            }
+                        } else if !text.is_empty() {
+                            // Dragged text is inserted as is.
+                            let mut disp = display.borrow_mut();
+                            let _ = disp.editor_mut().paste(&text);
+                            disp.editor_mut()
+                                .commit_undo_step(UndoKind::Other, Instant::now());
+                            drop(disp);
+                            if let Some(cb) = &mut *change_cb.borrow_mut() {
+                                (cb)();
+                            }
+                        }
+                        w.redraw();
+                        true
+                    }
+                    Event::Paste if edit_mode.get() => {
+                        let fallback_text = fltk::app::event_text();
+                        let (platform_formats, platform_rtf) = inspect_platform_clipboard();
+                        let fallback_ref = if fallback_text.is_empty() {
+                            None
+                        } else {
+                            Some(fallback_text.as_str())
+                        };
+
+                        let mut applied = false;
+
+                        if let Ok(doc) = clipboard::read_document_from_system(
+                            fallback_ref,
+                            &platform_formats,
+                            platform_rtf.as_deref(),
+                        ) {
+                            let mut disp = display.borrow_mut();
+                            if disp.editor_mut().insert_document(&doc).is_ok() {
+                                disp.editor_mut()
+                                    .commit_undo_step(UndoKind::Other, Instant::now());
+                                if let Some(cb) = &mut *change_cb.borrow_mut() {
+                                    (cb)();
+                                }
+                                w.redraw();
+                                applied = true;
+                            }
+                        }
+
+                        if !applied {
                             let fallback_ref = if fallback_text.is_empty() {
                                 None
                             } else {
                                 Some(fallback_text.as_str())
                             };
-
-                            let mut applied = false;
-
-                            if let Ok(doc) = clipboard::read_document_from_system(
-                                fallback_ref,
-                                &platform_formats,
-                                platform_rtf.as_deref(),
-                            ) {
+                            if let Some(text) = fallback_ref {
                                 let mut disp = display.borrow_mut();
-                                if disp.editor_mut().insert_document(&doc).is_ok() {
-                                    disp.editor_mut()
-                                        .commit_undo_step(UndoKind::Other, Instant::now());
-                                    if let Some(cb) = &mut *change_cb.borrow_mut() {
-                                        (cb)();
-                                    }
-                                    w.redraw();
-                                    applied = true;
-                                }
-                            }
-
-                            if !applied {
-                                let fallback_ref = if fallback_text.is_empty() {
-                                    None
-                                } else {
-                                    Some(fallback_text.as_str())
-                                };
-                                if let Some(text) = fallback_ref {
-                                    let mut disp = display.borrow_mut();
-                                    let _ = disp.editor_mut().paste(text);
-                                    disp.editor_mut()
-                                        .commit_undo_step(UndoKind::Other, Instant::now());
-                                    if let Some(cb) = &mut *change_cb.borrow_mut() {
-                                        (cb)();
-                                    }
-                                    w.redraw();
+                                let _ = disp.editor_mut().paste(text);
+                                disp.editor_mut()
+                                    .commit_undo_step(UndoKind::Other, Instant::now());
+                                if let Some(cb) = &mut *change_cb.borrow_mut() {
+                                    (cb)();
                                 }
+                                w.redraw();
                             }
-                            true
-                        } else {
-                            false
                         }
+                        true
                     }
                     Event::Focus => {
                         // On focus, re-evaluate hover from cursor position
@@ -2093,9 +2603,64 @@ impl FltkStructuredRichDisplay {
             hover_cb: hover_callback,
             change_cb: change_callback,
             paragraph_cb: paragraph_callback,
+            drop_cb: drop_callback,
+            checkbox_cb: checkbox_callback,
+            hint_day,
+            edit_mode,
+            readonly,
+            focus_mode,
+            highlights,
+            frontmatter: String::new(),
         }
     }
 
+    /// The loaded page's frontmatter, with the blank lines after it; empty if
+    /// it has none.
+    pub fn frontmatter(&self) -> &str {
+        &self.frontmatter
+    }
+
+    pub fn set_frontmatter(&mut self, frontmatter: &str) {
+        self.frontmatter = frontmatter.to_string();
+    }
+
+    /// Turn focus mode on or off. It dims all but the paragraph with the caret
+    /// if the typography's `focus_dim` asks for it.
+    pub fn set_focus_mode(&self, focus_mode: bool) {
+        self.focus_mode.set(focus_mode);
+    }
+
+    /// Draw the text of `highlights` on a highlight background, in place of
+    /// the previous ones, until the next edit or click.
+    pub fn set_highlights(&self, highlights: Vec<Highlight>) {
+        *self.highlights.borrow_mut() = highlights;
+    }
+
+    pub fn clear_highlights(&self) {
+        self.set_highlights(Vec::new());
+    }
+
+    /// Switch between edit mode and read mode.
+    pub fn set_edit_mode(&self, edit_mode: bool) {
+        self.edit_mode.set(edit_mode);
+        self.update_cursor_visibility();
+    }
+
+    pub fn edit_mode(&self) -> bool {
+        self.edit_mode.get()
+    }
+
+    /// Mark the loaded page as read-only (or editable again).
+    pub fn set_readonly(&self, readonly: bool) {
+        self.readonly.set(readonly);
+        self.update_cursor_visibility();
+    }
+
+    fn update_cursor_visibility(&self) {
+        let visible = self.edit_mode.get() && !self.readonly.get();
+        self.display.borrow_mut().set_cursor_visible(visible);
+    }
+
     pub fn set_link_callback(&self, cb: Option<Box<dyn Fn(String) + 'static>>) {
         *self.link_cb.borrow_mut() = cb;
     }
@@ -2104,14 +2669,30 @@ impl FltkStructuredRichDisplay {
         *self.hover_cb.borrow_mut() = cb;
     }
 
+    /// Called with the files dropped onto the editor (in edit mode).
+    pub fn set_drop_callback(&self, cb: Option<Box<dyn FnMut(Vec<PathBuf>) + 'static>>) {
+        *self.drop_cb.borrow_mut() = cb;
+    }
+
+    /// Called, instead of ticking it, with the link destinations in the item
+    /// of a checkbox clicked on a read-only page.
+    pub fn set_checkbox_callback(&self, cb: Option<Box<dyn FnMut(Vec<String>) + 'static>>) {
+        *self.checkbox_cb.borrow_mut() = cb;
+    }
+
     pub fn set_change_callback(&self, cb: Option<Box<dyn FnMut() + 'static>>) {
         *self.change_cb.borrow_mut() = cb;
     }
 
-    /// Periodic tick to update cursor blinking; triggers redraw if needed
+    /// Periodic tick to update cursor blinking, and the due date hints once
+    /// the day changes; triggers redraw if needed
     pub fn tick(&mut self, ms_since_start: u64) {
         let changed = self.display.borrow_mut().tick(ms_since_start);
-        if changed {
+        let new_day = self
+            .hint_day
+            .get()
+            .is_some_and(|day| day != date_picker::today());
+        if changed || new_day {
             self.group.redraw();
         }
     }
@@ -0,0 +1,5 @@
+//! Fuzzy matching of note names, shared by the note picker and the link
+//! editor's autocomplete. It lives in the core, which ranks notes for the CLI
+//! the same way.
+
+pub use piki_core::fuzzy::{rank, score};
@@ -1,19 +1,24 @@
 #![allow(dead_code)]
 
 use crate::position_memory::NotePosition;
+use piki_core::PageId;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
 
 const MAX_HISTORY_SIZE: usize = 100;
 
 #[derive(Debug, Clone)]
 pub struct HistoryEntry {
-    pub note_name: String,
+    pub note_name: PageId,
     /// Where the user was in this note (scroll offset + caret) when they last
     /// left it, restored when back/forward navigates here again.
     pub position: NotePosition,
 }
 
 impl HistoryEntry {
-    pub fn new(note_name: String, position: NotePosition) -> Self {
+    pub fn new(note_name: PageId, position: NotePosition) -> Self {
         HistoryEntry {
             note_name,
             position,
@@ -21,6 +26,23 @@ impl HistoryEntry {
     }
 }
 
+/// The history as it is persisted: the notes and their scroll offsets. The
+/// caret is left out, so a restored entry opens with the caret at the top.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SavedHistory {
+    #[serde(default)]
+    current: Option<usize>,
+    #[serde(default)]
+    entries: Vec<SavedEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedEntry {
+    note: String,
+    #[serde(default)]
+    scroll: i32,
+}
+
 #[derive(Debug)]
 pub struct History {
     entries: Vec<HistoryEntry>,
@@ -35,16 +57,73 @@ impl History {
         }
     }
 
+    /// Load from `path`, returning an empty history if it is missing or
+    /// corrupt.
+    pub fn load(path: &Path) -> Self {
+        let saved = fs::read_to_string(path)
+            .ok()
+            .and_then(|s| toml::from_str::<SavedHistory>(&s).ok())
+            .unwrap_or_default();
+        let mut entries = saved
+            .entries
+            .into_iter()
+            .map(|entry| {
+                HistoryEntry::new(
+                    PageId::new(&entry.note),
+                    NotePosition {
+                        scroll: entry.scroll,
+                        cursor: None,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let excess = entries.len().saturating_sub(MAX_HISTORY_SIZE);
+        entries.drain(..excess);
+        let current_index = (!entries.is_empty()).then(|| {
+            saved
+                .current
+                .unwrap_or(usize::MAX)
+                .saturating_sub(excess)
+                .min(entries.len() - 1)
+        });
+        History {
+            entries,
+            current_index,
+        }
+    }
+
+    /// Persist to `path`, creating parent directories as needed.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let saved = SavedHistory {
+            current: self.current_index,
+            entries: self
+                .entries
+                .iter()
+                .map(|entry| SavedEntry {
+                    note: entry.note_name.to_string(),
+                    scroll: entry.position.scroll,
+                })
+                .collect(),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let toml = toml::to_string_pretty(&saved)
+            .map_err(|e| io::Error::other(format!("toml serialization error: {e}")))?;
+        fs::write(path, toml)
+    }
+
     /// Add a new note to history
     /// This clears any forward history and adds the new entry
-    pub fn push(&mut self, note_name: String, position: NotePosition) {
+    pub fn push(&mut self, note_name: impl Into<PageId>, position: NotePosition) {
         // If we're in the middle of history, truncate everything after current position
         if let Some(idx) = self.current_index {
             self.entries.truncate(idx + 1);
         }
 
         // Add new entry
-        self.entries.push(HistoryEntry::new(note_name, position));
+        self.entries
+            .push(HistoryEntry::new(note_name.into(), position));
 
         // Limit history size
         if self.entries.len() > MAX_HISTORY_SIZE {
@@ -59,9 +138,10 @@ impl History {
     /// navigation follows a note that was renamed instead of resurrecting its
     /// former (now non-existent) name as an empty note.
     pub fn rename_note(&mut self, old: &str, new: &str) {
+        let new = PageId::new(new);
         for entry in &mut self.entries {
             if entry.note_name == old {
-                entry.note_name = new.to_string();
+                entry.note_name = new.clone();
             }
         }
     }
@@ -279,6 +359,39 @@ mod tests {
         assert!(!history.can_go_forward());
     }
 
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = std::env::temp_dir().join("piki-test-history");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("history.toml");
+
+        let mut history = History::new();
+        history.push("a".to_string(), scroll(0));
+        history.push("b".to_string(), scroll(10));
+        history.push("c".to_string(), scroll(20));
+        history.go_back();
+        history.update_position(NotePosition {
+            scroll: 15,
+            cursor: Some(DocumentPosition::new(1, 3)),
+        });
+        history.save(&path).unwrap();
+
+        // The notes, scroll offsets and the current entry survive; the caret
+        // does not.
+        let mut loaded = History::load(&path);
+        assert_eq!(loaded.current().unwrap().note_name, "b");
+        assert_eq!(loaded.current().unwrap().position, scroll(15));
+        assert_eq!(loaded.go_forward().unwrap().note_name, "c");
+        loaded.go_back();
+        assert_eq!(loaded.go_back().unwrap().note_name, "a");
+
+        fs::write(&path, "not a history").unwrap();
+        assert!(History::load(&path).current().is_none());
+        assert!(History::load(&dir.join("missing.toml")).current().is_none());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
     #[test]
     fn test_update_position() {
         let mut history = History::new();
@@ -1,19 +1,34 @@
 // Library exports for piki
 pub mod accents_menu;
+pub mod auto_pair;
+pub mod checklist_progress;
 pub mod clipboard;
+pub mod code_block_editor;
 pub mod content;
 pub mod context_menu;
+pub mod date_picker;
+pub mod diagram_preview;
+pub mod embed;
+pub mod file_drop;
 pub mod fltk_draw_context;
 pub mod fltk_structured_rich_display;
+pub mod fuzzy;
 pub mod link_editor;
+pub mod link_graph;
 pub mod link_handler;
+pub mod list_drag;
 pub mod live_share;
 pub mod markdown_converter;
+pub mod markdown_shortcuts;
 pub mod note_ui;
 pub mod on_air_bar;
+pub mod print;
+pub mod quote_depth;
 pub mod responsive_scrollbar;
 pub mod rtf;
 pub mod section_link;
+pub mod smart_punctuation;
+pub mod theme;
 pub mod ui_adapters;
 
 // The structured editor/layout core lives in the shared `rutle` crate; piki-gui
@@ -1,11 +1,58 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
 use fltk::{
-    button,
+    browser, button,
     enums::{Align, CallbackTrigger, Event, Key},
     input,
-    prelude::{GroupExt, InputExt, WidgetBase, WidgetExt},
+    prelude::{BrowserExt, GroupExt, InputExt, WidgetBase, WidgetExt},
     window,
 };
 
+use crate::fuzzy;
+use crate::link_handler::is_external_link;
+
+/// At most this many page names are suggested for the link target.
+const MAX_SUGGESTIONS: usize = 5;
+const SUGGESTION_ROW_H: i32 = 20;
+
+type PageNames = Box<dyn Fn() -> Vec<String>>;
+
+thread_local! {
+    /// Supplies the page names suggested as link targets. Installed once by
+    /// the app, which owns the document store.
+    static PAGE_NAMES: RefCell<Option<PageNames>> = const { RefCell::new(None) };
+}
+
+/// Provide the page names (notes and `!plugin` pages) the target field
+/// suggests while typing.
+pub fn set_page_names(source: impl Fn() -> Vec<String> + 'static) {
+    PAGE_NAMES.with(|names| *names.borrow_mut() = Some(Box::new(source)));
+}
+
+fn page_names() -> Vec<String> {
+    PAGE_NAMES.with(|names| names.borrow().as_ref().map(|f| f()).unwrap_or_default())
+}
+
+/// Page names suggested for the link `target` typed so far, best match first.
+/// External links and section links (`page#heading`) get none, and neither
+/// does a target that already names the only matching page.
+pub fn suggestions(target: &str, names: &[String]) -> Vec<String> {
+    let target = target.trim();
+    if target.is_empty() || is_external_link(target) || target.contains('#') {
+        return Vec::new();
+    }
+    let ranked = fuzzy::rank(target, names);
+    if ranked == [target] {
+        return Vec::new();
+    }
+    ranked
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(str::to_string)
+        .collect()
+}
+
 /// Options to configure the link editor dialog.
 #[derive(Default)]
 pub struct LinkEditOptions {
@@ -53,6 +100,44 @@ where
         remove_btn.deactivate();
     }
 
+    // Page-name suggestions drop down below the target field, over the rows
+    // beneath it; created last so they are drawn (and clicked) on top.
+    let mut suggestion_list = browser::HoldBrowser::new(130, 34, 280, 0, None);
+    suggestion_list.set_format_char('\0');
+    suggestion_list.hide();
+    let names = Rc::new(page_names());
+    // Set while a suggestion is taken, so the target's change callback doesn't
+    // open the list again right away.
+    let accepting = Rc::new(Cell::new(false));
+    let mut refresh_suggestions = {
+        let mut list = suggestion_list.clone();
+        let mut win = win.clone();
+        let accepting = accepting.clone();
+        move |target: &str| {
+            let matches = if accepting.get() {
+                Vec::new()
+            } else {
+                suggestions(target, &names)
+            };
+            list.clear();
+            if matches.is_empty() {
+                list.hide();
+            } else {
+                for name in &matches {
+                    list.add(name);
+                }
+                list.resize(
+                    list.x(),
+                    list.y(),
+                    list.w(),
+                    matches.len() as i32 * SUGGESTION_ROW_H + 4,
+                );
+                list.show();
+            }
+            win.redraw();
+        }
+    };
+
     // Initial validation state
     let initial_text_required = !(opts.mode_existing_link || opts.selection_mode);
     let target_ok = !target_input.value().trim().is_empty();
@@ -85,6 +170,7 @@ where
             if normalized != current {
                 i.set_value(&normalized);
             }
+            refresh_suggestions(&i.value());
             let target_ok = !i.value().trim().is_empty();
             let text_ok = if require_text {
                 !txt_v.value().trim().is_empty()
@@ -117,6 +203,64 @@ where
         });
     }
 
+    // Up/Down move through the suggestions, Enter or Tab takes the selected
+    // one, Escape closes the list (and only a second Escape the dialog).
+    let accept = {
+        let mut list = suggestion_list.clone();
+        let mut target = target_input.clone();
+        let accepting = accepting.clone();
+        move |name: &str| {
+            target.set_value(name);
+            let _ = target.set_position(name.len() as i32);
+            accepting.set(true);
+            target.do_callback();
+            accepting.set(false);
+            list.hide();
+            let _ = target.take_focus();
+        }
+    };
+    {
+        let mut list = suggestion_list.clone();
+        let mut accept = accept.clone();
+        let mut win = win.clone();
+        target_input.handle(move |_, e| {
+            if e != Event::KeyDown || !list.visible() {
+                return false;
+            }
+            let key = fltk::app::event_key();
+            if key == Key::Down || key == Key::Up {
+                let next = if key == Key::Down {
+                    (list.value() + 1).min(list.size())
+                } else {
+                    (list.value() - 1).max(1)
+                };
+                list.select(next);
+                true
+            } else if (key == Key::Enter || key == Key::KPEnter || key == Key::Tab)
+                && list.value() > 0
+            {
+                if let Some(name) = list.text(list.value()) {
+                    accept(&name);
+                }
+                true
+            } else if key == Key::Escape {
+                list.hide();
+                win.redraw();
+                true
+            } else {
+                false
+            }
+        });
+    }
+    {
+        let mut accept = accept;
+        suggestion_list.set_callback(move |list| {
+            if let Some(name) = list.selected_text() {
+                accept(&name);
+            }
+        });
+    }
+
     // Wire Save/Remove/Cancel
     let mut win_for_save = win.clone();
     let mut win_for_remove = win.clone();
@@ -193,3 +337,25 @@ where
         false
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggests_matching_pages() {
+        let names: Vec<String> = ["roadmap", "projects/roadmap", "!index", "notes"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            suggestions("rdmap", &names),
+            vec!["roadmap", "projects/roadmap"]
+        );
+        assert_eq!(suggestions("!ind", &names), vec!["!index"]);
+        assert!(suggestions("notes", &names).is_empty());
+        assert!(suggestions("https://roadmap", &names).is_empty());
+        assert!(suggestions("roadmap#goals", &names).is_empty());
+        assert!(suggestions("", &names).is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+//! The link graph window: every page as a dot, every link as a line between
+//! two dots, laid out by [`LinkGraph::layout`]. The page on screen is
+//! highlighted, hovering a dot highlights its links, and clicking one opens the
+//! page.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use fltk::{
+    app, draw,
+    enums::{Color, Cursor, Event, Font},
+    frame,
+    prelude::{GroupExt, WidgetBase, WidgetExt},
+    window,
+};
+use piki_core::graph::LinkGraph;
+
+use crate::theme;
+
+/// Radius of a page's dot, in pixels.
+const NODE_RADIUS: i32 = 6;
+/// Space kept free around the layout for labels.
+const MARGIN: i32 = 60;
+/// Iterations of the force-directed layout.
+const LAYOUT_ITERATIONS: usize = 200;
+
+const EDGE_COLOR: u32 = 0xC8C8C8FF;
+const HIGHLIGHT_COLOR: u32 = 0x3C78D8FF;
+const CURRENT_COLOR: u32 = 0xD55E00FF;
+const NODE_COLOR: u32 = 0x707070FF;
+
+fn color(rgba: u32) -> Color {
+    let rgba = theme::current().palette.map(rgba);
+    Color::from_rgb((rgba >> 24) as u8, (rgba >> 16) as u8, (rgba >> 8) as u8)
+}
+
+/// The pixel position of each page in a `w`×`h` area.
+fn node_positions(layout: &[(f64, f64)], w: i32, h: i32) -> Vec<(i32, i32)> {
+    let (w, h) = ((w - 2 * MARGIN).max(1), (h - 2 * MARGIN).max(1));
+    layout
+        .iter()
+        .map(|&(x, y)| {
+            (
+                MARGIN + (x * w as f64).round() as i32,
+                MARGIN + (y * h as f64).round() as i32,
+            )
+        })
+        .collect()
+}
+
+/// The page whose dot is at (`x`, `y`), if any.
+fn node_at(positions: &[(i32, i32)], x: i32, y: i32) -> Option<usize> {
+    let reach = (NODE_RADIUS + 3).pow(2);
+    positions
+        .iter()
+        .enumerate()
+        .map(|(i, &(nx, ny))| (i, (nx - x).pow(2) + (ny - y).pow(2)))
+        .filter(|&(_, dist)| dist <= reach)
+        .min_by_key(|&(_, dist)| dist)
+        .map(|(i, _)| i)
+}
+
+/// Show `graph` in a window; `current` is the page on screen. Clicking a page
+/// calls `open` with its name and closes the window.
+pub fn show(graph: LinkGraph, current: &str, open: impl FnOnce(String) + 'static) {
+    let (screen_w, screen_h) = app::screen_size();
+    let w = (screen_w * 0.6) as i32;
+    let h = (screen_h * 0.7) as i32;
+    let mut win = window::Window::new(
+        (screen_w as i32 - w) / 2,
+        (screen_h as i32 - h) / 2,
+        w,
+        h,
+        None,
+    );
+    win.set_label(&format!(
+        "Link Graph — {} pages, {} links",
+        graph.pages.len(),
+        graph.links.len()
+    ));
+
+    let mut canvas = frame::Frame::new(0, 0, w, h, None);
+    win.end();
+    win.make_resizable(true);
+    win.resizable(&canvas);
+
+    let layout = Rc::new(graph.layout(LAYOUT_ITERATIONS));
+    let current = graph.pages.iter().position(|p| p == current);
+    let hovered: Rc<Cell<Option<usize>>> = Rc::new(Cell::new(None));
+    let graph = Rc::new(graph);
+
+    {
+        let graph = graph.clone();
+        let layout = layout.clone();
+        let hovered = hovered.clone();
+        canvas.draw(move |f| {
+            draw::draw_rect_fill(f.x(), f.y(), f.w(), f.h(), Color::White);
+            let positions = node_positions(&layout, f.w(), f.h());
+            let at = |i: usize| (f.x() + positions[i].0, f.y() + positions[i].1);
+            let focus = hovered.get().or(current);
+
+            for &(from, to) in &graph.links {
+                let highlighted = focus.is_some_and(|n| n == from || n == to);
+                draw::set_draw_color(color(if highlighted {
+                    HIGHLIGHT_COLOR
+                } else {
+                    EDGE_COLOR
+                }));
+                draw::set_line_style(draw::LineStyle::Solid, if highlighted { 2 } else { 1 });
+                let ((x1, y1), (x2, y2)) = (at(from), at(to));
+                draw::draw_line(x1, y1, x2, y2);
+            }
+            draw::set_line_style(draw::LineStyle::Solid, 0);
+
+            for (i, page) in graph.pages.iter().enumerate() {
+                let (x, y) = at(i);
+                let node_color = if Some(i) == current {
+                    CURRENT_COLOR
+                } else if Some(i) == hovered.get() {
+                    HIGHLIGHT_COLOR
+                } else {
+                    NODE_COLOR
+                };
+                draw::set_draw_color(color(node_color));
+                draw::draw_pie(
+                    x - NODE_RADIUS,
+                    y - NODE_RADIUS,
+                    2 * NODE_RADIUS,
+                    2 * NODE_RADIUS,
+                    0.0,
+                    360.0,
+                );
+                let bold = Some(i) == current || Some(i) == hovered.get();
+                draw::set_font(
+                    if bold {
+                        Font::HelveticaBold
+                    } else {
+                        Font::Helvetica
+                    },
+                    12,
+                );
+                draw::set_draw_color(Color::from_rgb(40, 40, 40));
+                let label_w = draw::width(page) as i32;
+                draw::draw_text2(
+                    page,
+                    x - label_w / 2,
+                    y + NODE_RADIUS + 2,
+                    label_w,
+                    14,
+                    fltk::enums::Align::Center | fltk::enums::Align::Top,
+                );
+            }
+        });
+    }
+
+    let open = RefCell::new(Some(open));
+    canvas.handle(move |f, event| {
+        let positions = node_positions(&layout, f.w(), f.h());
+        let node = node_at(&positions, app::event_x() - f.x(), app::event_y() - f.y());
+        match event {
+            Event::Enter => true,
+            Event::Move => {
+                if node != hovered.get() {
+                    hovered.set(node);
+                    if let Some(mut win) = f.window() {
+                        win.set_cursor(if node.is_some() {
+                            Cursor::Hand
+                        } else {
+                            Cursor::Default
+                        });
+                    }
+                    f.redraw();
+                }
+                true
+            }
+            Event::Push => {
+                let Some(node) = node else {
+                    return false;
+                };
+                if let Some(open) = open.borrow_mut().take() {
+                    let page = graph.pages[node].clone();
+                    if let Some(mut win) = f.window() {
+                        win.hide();
+                    }
+                    open(page);
+                }
+                true
+            }
+            _ => false,
+        }
+    });
+
+    win.show();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_node_under_the_pointer() {
+        let positions = node_positions(
+            &[(0.0, 0.0), (1.0, 1.0)],
+            2 * MARGIN + 100,
+            2 * MARGIN + 100,
+        );
+        assert_eq!(
+            positions,
+            vec![(MARGIN, MARGIN), (MARGIN + 100, MARGIN + 100)]
+        );
+        assert_eq!(node_at(&positions, MARGIN + 2, MARGIN - 3), Some(0));
+        assert_eq!(node_at(&positions, MARGIN + 50, MARGIN + 50), None);
+    }
+}
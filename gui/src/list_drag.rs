@@ -0,0 +1,105 @@
+//! Reordering list items by dragging their marker (bullet, number or
+//! checkbox) up or down.
+//!
+//! The drop point is mapped to an item of the dragged item's own list, and the
+//! item is moved there with the editor's paragraph moves (the ones behind
+//! Alt-Up/Down), so nested items travel along and numbered lists number
+//! themselves afresh. Dropping above or below the list moves the item to its
+//! start or end.
+
+use std::time::Instant;
+
+use rutle::editor::{Editor, UndoKind};
+use rutle::tree_path::{DocumentPosition, PathSegment, TreePath};
+use rutle::tree_walk::enumerate_leaves;
+use tdoc::Document;
+
+/// The innermost list or checklist item `path` lies in: the path of the list
+/// holding it and the item's index in that list.
+pub fn list_item(path: &TreePath) -> Option<(&[PathSegment], usize)> {
+    let segments = path.segments();
+    let item = segments.iter().rposition(|s| item_index(s).is_some())?;
+    Some((&segments[..item], item_index(&segments[item])?))
+}
+
+fn item_index(segment: &PathSegment) -> Option<usize> {
+    match segment {
+        PathSegment::ListEntry { entry, .. } => Some(*entry),
+        PathSegment::ChecklistItem(index) => Some(*index),
+        _ => None,
+    }
+}
+
+/// How many places the item at `from` has to move to land where `to` is;
+/// negative is up. Zero if `from` isn't in a list.
+pub fn drop_offset(doc: &Document, from: &DocumentPosition, to: &DocumentPosition) -> isize {
+    let Some((list, index)) = list_item(&from.path) else {
+        return 0;
+    };
+    let index_in_list = |path: &TreePath| {
+        let segments = path.segments();
+        segments
+            .starts_with(list)
+            .then(|| segments.get(list.len()).and_then(item_index))
+            .flatten()
+    };
+    let target = match index_in_list(&to.path) {
+        Some(target) => target,
+        None => {
+            let indices = enumerate_leaves(doc)
+                .iter()
+                .filter_map(|leaf| index_in_list(&leaf.path))
+                .collect::<Vec<_>>();
+            let first = indices.iter().copied().min().unwrap_or(index);
+            let last = indices.iter().copied().max().unwrap_or(index);
+            if to < from { first } else { last }
+        }
+    };
+    target as isize - index as isize
+}
+
+/// Move the item at the caret `offset` places down (up if negative), as one
+/// undoable edit. Returns whether it moved.
+pub fn move_item(editor: &mut Editor, offset: isize) -> bool {
+    let mut moved = false;
+    for _ in 0..offset.unsigned_abs() {
+        let step = if offset < 0 {
+            editor.move_blocks_up()
+        } else {
+            editor.move_blocks_down()
+        };
+        if !step.unwrap_or(false) {
+            break;
+        }
+        moved = true;
+    }
+    if moved {
+        editor.commit_undo_step(UndoKind::Other, Instant::now());
+    }
+    moved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown_converter::markdown_to_document;
+
+    #[test]
+    fn drops_land_on_items_of_the_same_list() {
+        let doc = markdown_to_document("before\n\n- a\n- b\n- c\n\nafter\n");
+        let at = |leaf: usize| DocumentPosition::at(enumerate_leaves(&doc)[leaf].path.clone(), 0);
+        let (before, a, b, c, after) = (at(0), at(1), at(2), at(3), at(4));
+
+        assert_eq!(list_item(&b.path).map(|(_, index)| index), Some(1));
+        assert!(list_item(&before.path).is_none());
+
+        assert_eq!(drop_offset(&doc, &a, &c), 2);
+        assert_eq!(drop_offset(&doc, &c, &a), -2);
+        assert_eq!(drop_offset(&doc, &b, &b), 0);
+        // Past the ends of the list, the item goes to its start or end.
+        assert_eq!(drop_offset(&doc, &b, &before), -1);
+        assert_eq!(drop_offset(&doc, &a, &after), 2);
+        // Only list items can be dragged.
+        assert_eq!(drop_offset(&doc, &before, &c), 0);
+    }
+}
@@ -14,7 +14,9 @@
 //! ever shows the note in its own URL. A request for the *current* note is
 //! served from the in-memory Markdown (so unsaved edits show up live); any other
 //! note is loaded from disk. This lets a presenter keep a "public" note visible
-//! in a shared browser tab while taking notes in a "private" one.
+//! in a shared browser tab while taking notes in a "private" one. Every note is
+//! passed through [`piki_core::redact`] first, so `%%private%%` regions and
+//! `#private` blocks are never served.
 //!
 //! Binding is localhost-only (`127.0.0.1`) on an OS-assigned ephemeral port: the
 //! server is only reachable from the presenter's machine, so remote meeting
@@ -34,7 +36,9 @@ use tiny_http::{Header, Request, Response, Server};
 use crate::link_handler::is_external_link;
 use crate::markdown_converter::{document_to_html, markdown_to_document};
 use crate::section_link::{heading_anchors, normalize_link_target, split_target};
-use piki_core::ensure_md_extension;
+use piki_core::redact::{RedactionRules, redact};
+use piki_core::title;
+use piki_core::{ensure_md_extension, percent_decode};
 use tdoc::{ChecklistItem, Document, InlineStyle, Paragraph, Span};
 
 /// How long the serve loop blocks waiting for a request before re-checking the
@@ -64,6 +68,8 @@ pub struct HighlightTarget {
 struct ShareState {
     /// Notes directory, used to load any non-current note from disk.
     dir: PathBuf,
+    /// What to strip from every note before serving it.
+    rules: RedactionRules,
     /// The note currently open in the GUI.
     current_note: String,
     /// Live Markdown of the current note (includes not-yet-saved edits).
@@ -87,8 +93,14 @@ pub struct LiveShare {
 
 impl LiveShare {
     /// Start a sharing session bound to `127.0.0.1` on an OS-assigned port,
-    /// serving `note` (with live content `markdown`) from `dir`.
-    pub fn start(dir: PathBuf, note: String, markdown: String) -> std::io::Result<LiveShare> {
+    /// serving `note` (with live content `markdown`) from `dir`, redacted
+    /// according to `rules`.
+    pub fn start(
+        dir: PathBuf,
+        rules: RedactionRules,
+        note: String,
+        markdown: String,
+    ) -> std::io::Result<LiveShare> {
         let server =
             Server::http("127.0.0.1:0").map_err(|e| std::io::Error::other(e.to_string()))?;
         let port = server
@@ -99,6 +111,7 @@ impl LiveShare {
 
         let state = Arc::new(Mutex::new(ShareState {
             dir,
+            rules,
             current_note: note,
             current_markdown: markdown,
             highlight: Vec::new(),
@@ -196,9 +209,10 @@ fn handle_request(request: Request, state: &Arc<Mutex<ShareState>>) {
 
     // Snapshot the shared state under a short lock, then do all I/O and
     // rendering without holding it (so a slow request never blocks the GUI).
-    let (dir, current_note, current_markdown, highlight, generation) = match state.lock() {
+    let (dir, rules, current_note, current_markdown, highlight, generation) = match state.lock() {
         Ok(st) => (
             st.dir.clone(),
+            st.rules.clone(),
             st.current_note.clone(),
             st.current_markdown.clone(),
             st.highlight.clone(),
@@ -244,21 +258,39 @@ fn handle_request(request: Request, state: &Arc<Mutex<ShareState>>) {
         None
     };
 
-    let Some(markdown) = markdown else {
+    // Private material never leaves the machine: notes with an excluded tag
+    // are reported as missing, `%%private%%` regions and `#private` blocks are
+    // stripped.
+    let Some((markdown, redacted)) = markdown.and_then(|original| {
+        let markdown = redact(&original, &rules)?;
+        let redacted = markdown != original;
+        Some((markdown, redacted))
+    }) else {
         let _ = request.respond(html_response(&not_found_page(note), 404));
         return;
     };
 
     // The highlight indices are relative to the current note's document, so
-    // only ever apply them when serving the current note.
-    let highlight: &[HighlightTarget] = if is_current { &highlight } else { &[] };
+    // only ever apply them when serving the current note — and not at all once
+    // redaction has removed blocks, as they would point at the wrong ones.
+    let highlight: &[HighlightTarget] = if is_current && !redacted {
+        &highlight
+    } else {
+        &[]
+    };
 
+    let page_title =
+        title::window_title(&title::page_title(note, &markdown), &title::wiki_name(&dir));
     if query_param(query_part, "raw").is_some() {
+        // The title travels alongside the fragment, so the browser tab follows
+        // a heading that was edited or a note that was navigated to.
         let body = render_fragment(&markdown, highlight);
-        let _ = request.respond(html_response(&body, 200));
+        let response = html_response(&body, 200)
+            .with_header(ascii_header("X-Piki-Title", &encode_fragment(&page_title)));
+        let _ = request.respond(response);
     } else {
         let token = version_token(note, &current_note, generation, &dir);
-        let page = render_page(note, &markdown, &token, highlight);
+        let page = render_page(&page_title, &markdown, &token, highlight);
         let _ = request.respond(html_response(&page, 200));
     }
 }
@@ -314,7 +346,7 @@ fn load_note_markdown(dir: &Path, note: &str) -> Option<String> {
 /// stylesheet can tint it; the document-order-first one also gets `piki-lead`
 /// (the pointing arrow). The browser scrolls the lead into view after swapping.
 fn render_fragment(markdown: &str, highlight: &[HighlightTarget]) -> String {
-    let mut doc = markdown_to_document(markdown);
+    let mut doc = markdown_to_document(piki_core::frontmatter::split(markdown).1);
     rewrite_links_in_document(&mut doc);
     let anchors = collect_heading_anchors(&doc);
     let sectioned = render_sectioned_html(&doc, highlight);
@@ -462,9 +494,14 @@ fn mark_lis(html: &str, indices: &[usize], lead: Option<usize>) -> String {
     out
 }
 
-/// Render a complete, styled HTML page for `note`, embedding the current version
-/// token so the reload script starts in sync.
-fn render_page(note: &str, markdown: &str, version: &str, highlight: &[HighlightTarget]) -> String {
+/// Render a complete, styled HTML page titled `title`, embedding the current
+/// version token so the reload script starts in sync.
+fn render_page(
+    title: &str,
+    markdown: &str,
+    version: &str,
+    highlight: &[HighlightTarget],
+) -> String {
     let body = render_fragment(markdown, highlight);
     let mut page = String::with_capacity(
         body.len() + STYLESHEET.len() + RELOAD_SCRIPT.len() + COLUMN_SCRIPT.len() + 512,
@@ -477,7 +514,7 @@ fn render_page(note: &str, markdown: &str, version: &str, highlight: &[Highlight
     // then themes the content itself.
     page.push_str("<meta name=\"color-scheme\" content=\"light dark\" />\n");
     page.push_str("<title>");
-    page.push_str(&html_escape_text(note));
+    page.push_str(&html_escape_text(title));
     page.push_str("</title>\n<style>");
     page.push_str(STYLESHEET);
     page.push_str("</style>\n</head>\n<body>\n");
@@ -750,34 +787,6 @@ fn encode_with(s: &str, keep: impl Fn(u8) -> bool) -> String {
     out
 }
 
-fn percent_decode(s: &str) -> String {
-    let bytes = s.as_bytes();
-    let mut out = Vec::with_capacity(bytes.len());
-    let mut i = 0;
-    while i < bytes.len() {
-        if bytes[i] == b'%'
-            && i + 2 < bytes.len()
-            && let (Some(hi), Some(lo)) = (hex_val(bytes[i + 1]), hex_val(bytes[i + 2]))
-        {
-            out.push(hi * 16 + lo);
-            i += 3;
-            continue;
-        }
-        out.push(bytes[i]);
-        i += 1;
-    }
-    String::from_utf8_lossy(&out).into_owned()
-}
-
-fn hex_val(b: u8) -> Option<u8> {
-    match b {
-        b'0'..=b'9' => Some(b - b'0'),
-        b'a'..=b'f' => Some(b - b'a' + 10),
-        b'A'..=b'F' => Some(b - b'A' + 10),
-        _ => None,
-    }
-}
-
 fn html_escape_text(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -822,7 +831,11 @@ const RELOAD_SCRIPT: &str = r#"(function () {
         if (v === version) return;
         version = v;
         return fetch(location.pathname + "?raw=1")
-          .then(function (r) { return r.text(); })
+          .then(function (r) {
+            var title = r.headers.get("X-Piki-Title");
+            if (title) document.title = decodeURIComponent(title);
+            return r.text();
+          })
           .then(function (html) {
             var doc = document.getElementById("piki-doc");
             if (doc) {
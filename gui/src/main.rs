@@ -1,36 +1,61 @@
 mod app_icon;
 mod app_url;
 mod autosave;
-pub mod fltk_draw_context;
 mod history;
 mod link_handler;
 mod menu;
 mod note_picker;
+mod page_history;
+mod pikirc;
+mod pomodoro;
 mod position_memory;
+mod privacy_screen;
+mod properties_panel;
 mod recency;
 pub mod responsive_scrollbar;
 mod search_bar;
+mod split_view;
+mod startup_profile;
 mod statusbar;
+mod undo_memory;
+mod unsaved_changes;
+mod wiki_settings;
 mod window_state;
 
 use autosave::AutoSaveState;
 use clap::Parser;
 use fltk::{prelude::*, *};
 use history::History;
-use piki_core::{DocumentStore, IndexPlugin, PluginRegistry, TodoPlugin};
+use piki_core::attachment::open_with_system_handler;
+use piki_core::edit_log;
+use piki_core::merge::{Merged, merge3};
+use piki_core::stats::StatsPlugin;
+use piki_core::tags;
+use piki_core::timesheet::{self, TimesheetPlugin};
+use piki_core::title;
+use piki_core::{
+    DocumentStore, IndexPlugin, PluginRegistry, RandomPlugin, TodoPlugin, ensure_md_extension, git,
+    has_md_extension,
+};
 use piki_gui::live_share::LiveShare;
 use piki_gui::note_ui::NoteUI;
 use piki_gui::on_air_bar::OnAirBar;
 use piki_gui::section_link;
+use piki_gui::smart_punctuation;
+use piki_gui::theme;
 use piki_gui::ui_adapters::StructuredRichUI;
 use position_memory::{NotePosition, PositionMemory};
 use recency::RecentNotes;
 use search_bar::SearchBar;
+use startup_profile::StartupProfile;
 use statusbar::StatusBar;
 use std::cell::RefCell;
 use std::path::PathBuf;
 use std::rc::Rc;
-use std::time::Instant;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use undo_memory::UndoMemory;
 use window_state::WindowGeometry;
 
 /// Top of the content region, below the platform menu bar (0 on macOS, which
@@ -51,6 +76,10 @@ thread_local! {
     /// on the FLTK main thread. Doing this via a hook avoids threading the
     /// share handles through `load_note_helper` and its many call sites.
     static SHARE_HOOK: RefCell<Option<ShareHook>> = const { RefCell::new(None) };
+
+    /// The main window and the name of the wiki it shows, for titling the
+    /// window after the current note. Set once in `main`.
+    static TITLED_WINDOW: RefCell<Option<(window::Window, String)>> = const { RefCell::new(None) };
 }
 
 /// Notify an active sharing session that `note` is now the current note, with
@@ -63,12 +92,47 @@ fn notify_share_view(note: &str, markdown: &str) {
     });
 }
 
-// Timeout to save window state after resize/move
-const WINDOW_STATE_SAVE_TIMEOUT_SECS: f64 = 3.0;
-// Interval to autosave changes
-const AUTOSAVE_INTERVAL_SECS: f64 = 10.0;
+/// Title the main window after `note` — its first heading, or its name — and
+/// the wiki, e.g. "Roadmap — Work Wiki".
+fn update_window_title(note: &str, markdown: &str) {
+    TITLED_WINDOW.with(|titled| {
+        if let Some((wind, wiki)) = titled.borrow_mut().as_mut() {
+            let page = title::page_title(note, markdown);
+            wind.set_label(&title::window_title(&page, wiki));
+        }
+    });
+}
+
+/// Put the [`autosave::DIRTY_MARKER`] in front of the main window's title
+/// while the note has unsaved edits, or take it away.
+fn show_dirty_in_title(dirty: bool) {
+    TITLED_WINDOW.with(|titled| {
+        if let Some((wind, _)) = titled.borrow_mut().as_mut() {
+            let label = wind.label();
+            let prefix = format!("{} ", autosave::DIRTY_MARKER);
+            let clean = label.strip_prefix(&prefix).unwrap_or(&label);
+            let title = if dirty {
+                format!("{prefix}{clean}")
+            } else {
+                clean.to_string()
+            };
+            if title != label {
+                wind.set_label(&title);
+            }
+        }
+    });
+}
+
 // Interval to update "X ago" display in save status
 const SAVE_STATUS_UPDATE_INTERVAL_SECS: f64 = 30.0;
+// Interval to check whether the open note was changed on disk by another program
+const FILE_WATCH_INTERVAL_SECS: f64 = 1.0;
+// Interval to update the Pomodoro countdown in the status bar
+const POMODORO_TICK_SECS: f64 = 1.0;
+// Interval to check whether an automatic git sync is due or has finished
+const SYNC_POLL_SECS: f64 = 5.0;
+// Interval to check whether ~/.pikirc changed, to apply a new editor appearance
+const PIKIRC_WATCH_INTERVAL_SECS: f64 = 2.0;
 
 #[derive(Parser, Debug)]
 #[command(name = "piki-gui")]
@@ -78,16 +142,42 @@ struct Args {
     #[arg(short = 'd', long = "directory", value_name = "DIRECTORY")]
     directory: Option<PathBuf>,
 
-    /// Initial note to load (default: frontpage)
-    #[arg(short, long, default_value = "frontpage")]
-    note: String,
+    /// Wiki to open, by its name in the `[wikis]` section of ~/.pikirc
+    #[arg(
+        short = 'w',
+        long = "wiki",
+        value_name = "NAME",
+        conflicts_with = "directory"
+    )]
+    wiki: Option<String>,
+
+    /// Initial note to load (default: the wiki's start page)
+    #[arg(short, long)]
+    note: Option<String>,
+
+    /// Print a timing breakdown of application startup to stderr
+    #[arg(long = "profile-startup")]
+    profile_startup: bool,
+
+    /// Hide the window content after this many minutes without activity
+    #[arg(long = "lock-after", value_name = "MINUTES")]
+    lock_after: Option<u64>,
+
+    /// Log Pomodoro sessions into this note instead of the note worked on
+    #[arg(long = "timesheet", value_name = "NOTE")]
+    timesheet: Option<String>,
 }
 
 struct AppState {
     store: DocumentStore,
     plugin_registry: PluginRegistry,
     current_note: String,
+    /// The wiki's start page, opened by Note → Go Home.
+    home: String,
     history: History,
+    /// Where `history` is persisted, so back/forward still work after a
+    /// restart (None if no data dir is available).
+    history_path: Option<PathBuf>,
     /// When each note was last opened, used by the note picker to order notes
     /// and to resolve the "previous note" for a double Cmd-O/Ctrl-O.
     recent_notes: RecentNotes,
@@ -96,27 +186,42 @@ struct AppState {
     /// In-memory positions (scroll offset + caret) for recently visited notes,
     /// so returning to a note resumes where the user left off.
     note_positions: PositionMemory,
+    /// The editors, and so undo histories, of recently left notes, so
+    /// returning to a note can still undo what was typed there before.
+    undo_memory: UndoMemory,
+    /// The `[gui]` section of ~/.pikirc: autosave and window state delays.
+    gui: pikirc::GuiSettings,
 }
 
 impl AppState {
     fn new(
         store: DocumentStore,
         plugin_registry: PluginRegistry,
-        initial_note: String,
+        home: String,
         recent_notes_path: Option<PathBuf>,
+        history_path: Option<PathBuf>,
+        gui: pikirc::GuiSettings,
     ) -> Self {
         let recent_notes = recent_notes_path
             .as_deref()
             .map(RecentNotes::load)
             .unwrap_or_default();
+        let history = history_path
+            .as_deref()
+            .map(History::load)
+            .unwrap_or_else(History::new);
         AppState {
             store,
             plugin_registry,
-            current_note: initial_note,
-            history: History::new(),
+            current_note: home.clone(),
+            home,
+            history,
+            history_path,
             recent_notes,
             recent_notes_path,
             note_positions: PositionMemory::new(),
+            undo_memory: UndoMemory::new(),
+            gui,
         }
     }
 
@@ -130,10 +235,19 @@ impl AppState {
         }
     }
 
+    /// Persist the back/forward history.
+    fn save_history(&self) {
+        if let Some(path) = &self.history_path
+            && let Err(e) = self.history.save(path)
+        {
+            eprintln!("Failed to save history: {e}");
+        }
+    }
+
     /// Update all in-session state that refers to `old` to point at `new` after
     /// a note has been renamed: the current-note pointer, back/forward history,
-    /// the picker's recency ordering, and remembered positions. The on-disk file
-    /// move is handled by `rename_current_note`.
+    /// the picker's recency ordering, and remembered positions and undo
+    /// histories. The on-disk file move is handled by `rename_current_note`.
     fn rename_note(&mut self, old: &str, new: &str) {
         if self.current_note == old {
             self.current_note = new.to_string();
@@ -141,26 +255,30 @@ impl AppState {
         self.history.rename_note(old, new);
         self.recent_notes.rename(old, new);
         self.note_positions.rename(old, new);
+        self.undo_memory.rename(old, new);
         if let Some(path) = &self.recent_notes_path
             && let Err(e) = self.recent_notes.save(path)
         {
             eprintln!("Failed to save recent notes: {e}");
         }
+        self.save_history();
     }
 
     /// Drop all in-session state that refers to `note` after its file has been
     /// deleted: its back/forward history entries, the picker's recency entry,
-    /// and any remembered position. The on-disk file removal is handled
-    /// by `delete_current_note`.
+    /// and any remembered position and undo history. The on-disk file removal
+    /// is handled by `delete_current_note`.
     fn forget_note(&mut self, note: &str) {
         self.history.remove_note(note);
         self.recent_notes.remove(note);
         self.note_positions.remove(note);
+        self.undo_memory.remove(note);
         if let Some(path) = &self.recent_notes_path
             && let Err(e) = self.recent_notes.save(path)
         {
             eprintln!("Failed to save recent notes: {e}");
         }
+        self.save_history();
     }
 
     fn load_note(&mut self, note_name: &str) -> Result<String, String> {
@@ -194,6 +312,18 @@ fn save_current_note(
     active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
     statusbar: &Rc<RefCell<StatusBar>>,
 ) {
+    // Edits to the split view's source that haven't been rendered yet would
+    // not be saved.
+    split_view::flush();
+
+    // Leaving a note `piki edit` has open would drop its unsaved edits, so
+    // they are merged with the file now; the next file watch tick holds
+    // saving back again if the note is still open in the terminal.
+    if let Ok(mut as_state) = autosave_state.try_borrow_mut() {
+        as_state.held_by_terminal = false;
+    }
+    resolve_external_change(app_state, autosave_state, active_editor, statusbar);
+
     if let (Ok(ed_ptr), Ok(mut as_state), Ok(app_st)) = (
         active_editor.try_borrow(),
         autosave_state.try_borrow_mut(),
@@ -212,6 +342,7 @@ fn save_current_note(
                 }
             }
         }
+        show_dirty_in_title(as_state.pending_save);
     }
 }
 
@@ -249,11 +380,18 @@ fn rename_current_note(
     // is not lost and there is a file to move.
     save_current_note(app_state, autosave_state, active_editor, statusbar);
 
-    let (old_path, new_path) = {
+    // An encrypted note stays encrypted under its new name.
+    let (old_path, new_path, taken) = {
         let st = app_state.borrow();
-        (st.store.path_for(&old_name), st.store.path_for(new_name))
+        let old_path = st.store.path_for(&old_name);
+        let new_path = if piki_core::encryption::is_encrypted(&old_path) {
+            st.store.encrypted_path_for(new_name)
+        } else {
+            st.store.path_for(new_name)
+        };
+        (old_path, new_path, st.store.path_for(new_name).exists())
     };
-    if new_path.exists() {
+    if taken || new_path.exists() {
         return Err(format!("A note named '{new_name}' already exists."));
     }
     // A never-typed-into untitled note has no file yet; nothing to move, the new
@@ -264,6 +402,14 @@ fn rename_current_note(
                 .map_err(|e| format!("Failed to create folder for '{new_name}': {e}"))?;
         }
         std::fs::rename(&old_path, &new_path).map_err(|e| format!("Failed to rename note: {e}"))?;
+        let base = app_state.borrow().store.base_path().to_path_buf();
+        let _ = edit_log::record(
+            &base,
+            &old_name,
+            edit_log::Operation::Rename {
+                to: new_name.to_string(),
+            },
+        );
     }
 
     // Point all in-session state at the new name. The editor already holds the
@@ -280,12 +426,13 @@ fn rename_current_note(
     // link) so a note shared under its old name keeps working after a rename.
     let content = active_editor.borrow().borrow().get_content();
     notify_share_view(new_name, &content);
+    update_window_title(new_name, &content);
 
     Ok(())
 }
 
 /// Delete the currently open note: remove its file from disk, navigate away to
-/// the frontpage, and purge every trace of it from the in-session state. Backs
+/// the start page, and purge every trace of it from the in-session state. Backs
 /// the "Delete Note …" menu item (the caller shows the confirmation dialog).
 ///
 /// Read-only plugin views ("!…") have no file and cannot be deleted. Before
@@ -319,9 +466,10 @@ fn delete_current_note(
         as_state.reset_for_note(&note, &content);
     }
 
-    // Leave the now-deleted note by loading the frontpage.
+    // Leave the now-deleted note by loading the start page.
+    let home = app_state.borrow().home.clone();
     load_note_helper(
-        "frontpage",
+        &home,
         app_state,
         autosave_state,
         active_editor,
@@ -342,6 +490,20 @@ fn delete_current_note(
     Ok(())
 }
 
+/// Whether to open the web link `url`: asks first unless `[links]` in
+/// ~/.pikirc says not to.
+fn confirm_external_link(url: &str) -> bool {
+    if !pikirc::PikiRc::load().links.confirm_external() {
+        return true;
+    }
+    dialog::choice2_default(
+        &format!("Open {url} in your web browser?"),
+        "Cancel",
+        "Open",
+        "",
+    ) == Some(1)
+}
+
 fn load_note_helper(
     note_name: &str,
     app_state: &Rc<RefCell<AppState>>,
@@ -365,7 +527,7 @@ fn load_note_helper(
     // into the current back/forward history entry (only for non-history
     // navigation), and always into the recent-notes position memory so returning
     // to it later — via a link or the picker — resumes where we were.
-    {
+    let leaving_note = {
         let leaving_position = {
             let active = active_editor.borrow();
             let ed = active.borrow();
@@ -375,14 +537,21 @@ fn load_note_helper(
             }
         };
         let mut state = app_state.borrow_mut();
-        if record_history {
+        let leaving_note = state.current_note.clone();
+        // A history restored at startup points at a note that isn't open yet.
+        if record_history
+            && state
+                .history
+                .current()
+                .is_some_and(|entry| entry.note_name == leaving_note)
+        {
             state.history.update_position(leaving_position.clone());
         }
-        let leaving_note = state.current_note.clone();
         state
             .note_positions
             .remember(&leaving_note, leaving_position);
-    }
+        leaving_note
+    };
 
     // Check if this is a plugin note
     let is_plugin = note_name.starts_with('!');
@@ -404,17 +573,54 @@ fn load_note_helper(
                 None
             };
 
+            // Coming back to a note picks up its undo history where it was
+            // left, and the note being left keeps its own.
+            let kept = if is_plugin {
+                None
+            } else {
+                app_state.borrow_mut().undo_memory.take(note_name, &content)
+            };
             {
                 let active = active_editor.borrow();
                 let mut editor_mut = active.borrow_mut();
-                editor_mut.set_content_from_markdown(&content);
+                let left = match editor_mut.as_any_mut().downcast_mut::<StructuredRichUI>() {
+                    Some(structured) => Some(structured.load_note(&content, kept)),
+                    None => {
+                        editor_mut.set_content_from_markdown(&content);
+                        None
+                    }
+                };
+                if let Some(left) = left
+                    && !leaving_note.is_empty()
+                    && !leaving_note.starts_with('!')
+                {
+                    app_state
+                        .borrow_mut()
+                        .undo_memory
+                        .remember(&leaving_note, left);
+                }
 
                 // Set read-only mode for plugin notes, editable for regular notes
-                editor_mut.set_readonly(is_plugin);
+                // — unless the split view's source is where the note is edited.
+                editor_mut.set_readonly(is_plugin || split_view::is_active());
             }
+            if split_view::is_active() {
+                split_view::set_source(&content, !is_plugin);
+            }
+            // Generated plugin pages have no frontmatter to edit.
+            let properties = (!is_plugin).then(|| {
+                let active = active_editor.borrow();
+                let mut ed = active.borrow_mut();
+                ed.as_any_mut()
+                    .downcast_mut::<StructuredRichUI>()
+                    .map(|structured| structured.properties())
+                    .unwrap_or_default()
+            });
+            properties_panel::show_note(properties);
 
             // Decide where to scroll and place the caret. A section fragment
-            // (from a section link) wins and scrolls to the matching heading;
+            // (from a section link) wins and scrolls to the matching heading,
+            // or line for the `!todo` page's `#L12` links;
             // otherwise an explicit position from back/forward history wins, then
             // the remembered position for this note (if it is still one of the
             // recent ones), falling back to the top with the caret at the start.
@@ -425,7 +631,7 @@ fn load_note_helper(
                     let mut ed = active.borrow_mut();
                     ed.as_any_mut()
                         .downcast_mut::<StructuredRichUI>()
-                        .map(|structured| structured.scroll_to_anchor(frag))
+                        .map(|structured| structured.scroll_to_fragment(frag, &content))
                         .unwrap_or(false)
                 })
                 .unwrap_or(false);
@@ -461,8 +667,9 @@ fn load_note_helper(
                 app_state
                     .borrow_mut()
                     .history
-                    .push(note_name.to_string(), final_position);
+                    .push(note_name, final_position);
             }
+            app_state.borrow().save_history();
 
             // Record the open so the note picker can order notes by recency and
             // resolve the "previous note" for a double Cmd-O. Plugin notes (e.g.
@@ -474,11 +681,19 @@ fn load_note_helper(
             // Reset autosave state for the new note
             if let Ok(mut as_state) = autosave_state.try_borrow_mut() {
                 as_state.reset_for_note(note_name, &content);
-
-                // Set last_save_time to file's modification time if it exists
-                if let Some(mtime) = modified_time {
-                    as_state.last_save_time = Some(mtime);
-                }
+                // The editor may write the note in another style than the
+                // file's; that alone is no edit worth saving.
+                as_state.editor_content = active_editor.borrow().borrow().get_content();
+
+                // Until it is saved here, the status tells when the file was
+                // last modified.
+                as_state.modified_time = modified_time;
+                as_state.created_time = if is_plugin {
+                    None
+                } else {
+                    app_state.borrow().store.created_time(note_name)
+                };
+                as_state.disk_mtime = modified_time;
             }
 
             // Determine note status text based on note type
@@ -494,9 +709,9 @@ fn load_note_helper(
 
             // Set initial save status based on modification time
             if let Ok(as_state) = autosave_state.try_borrow() {
-                statusbar
-                    .borrow_mut()
-                    .set_status(&as_state.get_status_text());
+                let mut sb = statusbar.borrow_mut();
+                sb.set_status(&as_state.get_status_text());
+                sb.set_status_tooltip(&as_state.get_status_tooltip());
             } else {
                 statusbar.borrow_mut().set_status("");
             }
@@ -504,6 +719,7 @@ fn load_note_helper(
             // Keep any live-sharing session pointed at the note now on screen,
             // so the ON AIR link and the served content follow it.
             notify_share_view(note_name, &content);
+            update_window_title(note_name, &content);
 
             app::redraw();
         }
@@ -515,6 +731,528 @@ fn load_note_helper(
     }
 }
 
+/// Reconcile the editor with the open note's file when another program (a text
+/// editor, `git pull`, the CLI, ...) changed it on disk. Without unsaved edits
+/// the new version is simply shown. Otherwise the user decides whether to keep
+/// their version (overwriting the file), take the one on disk, or merge both —
+/// merging never drops either side's changes. Runs periodically and before
+/// every save, so a save never silently overwrites the other program's work.
+fn resolve_external_change(
+    app_state: &Rc<RefCell<AppState>>,
+    autosave_state: &Rc<RefCell<AutoSaveState>>,
+    active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
+    statusbar: &Rc<RefCell<StatusBar>>,
+) {
+    let theirs = match (autosave_state.try_borrow_mut(), app_state.try_borrow()) {
+        (Ok(mut as_state), Ok(st)) => as_state.take_external_change(&st.store),
+        _ => None,
+    };
+    let Some(theirs) = theirs else {
+        return;
+    };
+
+    let note = app_state.borrow().current_note.clone();
+    let mine = {
+        let active = active_editor.borrow();
+        let ed = active.borrow();
+        ed.get_content()
+    };
+    let (base, unsaved, mine) = {
+        let as_state = autosave_state.borrow();
+        (
+            as_state.original_content.clone(),
+            as_state.has_unsaved_edits(&mine),
+            // Merged line by line against the file, so in the file's style.
+            as_state.with_file_formatting(&mine),
+        )
+    };
+
+    if !unsaved {
+        replace_editor_content(&theirs, active_editor);
+        mark_disk_version_loaded(autosave_state, active_editor, &theirs);
+        notify_share_view(&note, &theirs);
+        update_window_title(&note, &theirs);
+        statusbar
+            .borrow_mut()
+            .set_status("Reloaded — changed on disk");
+        app::redraw();
+        return;
+    }
+
+    // The middle button (b1) is the Enter default, and closing the dialog
+    // also merges: the one choice that cannot lose anybody's edits. With a
+    // merge tool configured in ~/.pikirc, merging happens there.
+    let merge_tool = pikirc::PikiRc::load()
+        .diff
+        .tool()
+        .filter(|tool| tool.can_merge());
+    autosave_state.borrow_mut().resolving_conflict = true;
+    let choice = dialog::choice2_default(
+        &format!(
+            "“{note}” was changed by another program while you were editing it.\n\nKeep your version, take the one on disk, or merge both?"
+        ),
+        "Keep Mine",
+        if merge_tool.is_some() {
+            "Open in Merge Tool"
+        } else {
+            "Merge"
+        },
+        "Take Theirs",
+    );
+    // The app waits for the tool; nothing else may touch the note meanwhile.
+    let tool_merged = merge_tool.and_then(|tool| {
+        if !matches!(choice, Some(1) | None) {
+            return None;
+        }
+        statusbar
+            .borrow_mut()
+            .set_status(&format!("Waiting for {} …", tool.name()));
+        app::redraw();
+        app::flush();
+        tool.merge(&note, &base, &mine, &theirs)
+            .inspect_err(|e| {
+                dialog::alert_default(&format!("Cannot merge in the merge tool:\n\n{e}"))
+            })
+            .ok()
+            .flatten()
+    });
+    autosave_state.borrow_mut().resolving_conflict = false;
+
+    match choice {
+        Some(0) => {
+            statusbar
+                .borrow_mut()
+                .set_status("Changed on disk — keeping your version");
+        }
+        Some(2) => {
+            replace_editor_content(&theirs, active_editor);
+            mark_disk_version_loaded(autosave_state, active_editor, &theirs);
+            notify_share_view(&note, &theirs);
+            update_window_title(&note, &theirs);
+            statusbar
+                .borrow_mut()
+                .set_status("Reloaded — changed on disk");
+            app::redraw();
+            return;
+        }
+        _ => {
+            // An abandoned merge in the tool falls back to our own.
+            let merged = match tool_merged {
+                Some(text) => Merged { text, conflicts: 0 },
+                None => merge3(&base, &mine, &theirs),
+            };
+            replace_editor_content(&merged.text, active_editor);
+            // The file now holds their version; the merge result differs from
+            // it and is written below.
+            {
+                let mut as_state = autosave_state.borrow_mut();
+                as_state.original_content = theirs.clone();
+                as_state.editor_content = theirs;
+            }
+            notify_share_view(&note, &merged.text);
+            update_window_title(&note, &merged.text);
+            let status = match merged.conflicts {
+                0 => "Merged with the version on disk".to_string(),
+                n => format!("Merged with the version on disk — {n} conflict(s) marked"),
+            };
+            statusbar.borrow_mut().set_status(&status);
+        }
+    }
+
+    save_current_note(app_state, autosave_state, active_editor, statusbar);
+    app::redraw();
+}
+
+/// Replace the editor content in place, keeping the caret and scroll offset.
+fn replace_editor_content(content: &str, active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>) {
+    let active = active_editor.borrow();
+    let mut ed = active.borrow_mut();
+    let scroll = ed.scroll_pos();
+    let cursor = ed.cursor_pos();
+    ed.set_content_from_markdown(content);
+    if let Some(cursor) = cursor {
+        ed.set_cursor_pos(cursor);
+    }
+    ed.set_scroll_pos(scroll);
+}
+
+/// Record that the editor now shows exactly what is on disk.
+fn mark_disk_version_loaded(
+    autosave_state: &Rc<RefCell<AutoSaveState>>,
+    active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
+    content: &str,
+) {
+    let editor_content = active_editor.borrow().borrow().get_content();
+    let mut as_state = autosave_state.borrow_mut();
+    as_state.original_content = content.to_string();
+    as_state.editor_content = editor_content;
+    as_state.pending_save = false;
+}
+
+/// Throw away the edits to the current note and show its file again. The
+/// revert goes through the editor, so it can be undone.
+fn revert_to_saved(
+    app_state: &Rc<RefCell<AppState>>,
+    autosave_state: &Rc<RefCell<AutoSaveState>>,
+    active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
+    statusbar: &Rc<RefCell<StatusBar>>,
+) {
+    let note = app_state.borrow().current_note.clone();
+    revert_to_disk_version(app_state, autosave_state, active_editor);
+    statusbar
+        .borrow_mut()
+        .set_status(&format!("Reverted {} to the saved version", note));
+}
+
+/// Most pages listed when confirming a tag rename.
+const TAG_RENAME_LISTED_PAGES: usize = 12;
+
+/// Rename a tag in every note, after asking for the names and confirming the
+/// pages and number of tags that change. The open note is saved first and
+/// shown again afterwards, as an undoable edit.
+fn rename_tag(
+    app_state: &Rc<RefCell<AppState>>,
+    autosave_state: &Rc<RefCell<AutoSaveState>>,
+    active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
+    statusbar: &Rc<RefCell<StatusBar>>,
+) {
+    let Some(old) = dialog::input_default("Rename the tag:", "") else {
+        return;
+    };
+    let old = old.trim().trim_start_matches('#').to_string();
+    if old.is_empty() {
+        return;
+    }
+    let Some(new) = dialog::input_default(&format!("Rename #{old} to:"), &old) else {
+        return;
+    };
+    let new = new.trim().trim_start_matches('#').to_string();
+    if new.is_empty() || new == old {
+        return;
+    }
+
+    save_current_note(app_state, autosave_state, active_editor, statusbar);
+    let preview = {
+        let st = app_state.borrow();
+        tags::rename_tag_everywhere(&st.store, &old, &new, true)
+    };
+    let pages = match preview {
+        Ok(pages) if pages.is_empty() => {
+            dialog::message_default(&format!("No notes are tagged #{old}."));
+            return;
+        }
+        Ok(pages) => pages,
+        Err(e) => {
+            dialog::alert_default(&e);
+            return;
+        }
+    };
+
+    let total: usize = pages.iter().map(|(_, count)| count).sum();
+    let mut listed: Vec<String> = pages
+        .iter()
+        .take(TAG_RENAME_LISTED_PAGES)
+        .map(|(page, count)| format!("{page}: {count}"))
+        .collect();
+    if pages.len() > TAG_RENAME_LISTED_PAGES {
+        listed.push(format!(
+            "… and {} more",
+            pages.len() - TAG_RENAME_LISTED_PAGES
+        ));
+    }
+    let choice = dialog::choice2_default(
+        &format!(
+            "Rename #{old} to #{new}? This changes {total} tag(s) in {} note(s):\n\n{}",
+            pages.len(),
+            listed.join("\n")
+        ),
+        "Cancel",
+        "Rename",
+        "",
+    );
+    if choice != Some(1) {
+        return;
+    }
+
+    let result = {
+        let st = app_state.borrow();
+        tags::rename_tag_everywhere(&st.store, &old, &new, false)
+    };
+    match result {
+        Ok(pages) => {
+            let note = app_state.borrow().current_note.clone();
+            if pages.iter().any(|(page, _)| *page == note) {
+                revert_to_disk_version(app_state, autosave_state, active_editor);
+            }
+            statusbar.borrow_mut().set_status(&format!(
+                "Renamed #{old} to #{new} in {} note(s)",
+                pages.len()
+            ));
+        }
+        Err(e) => dialog::alert_default(&format!("Cannot rename #{old}:\n\n{e}")),
+    }
+}
+
+/// Restore the current note's file to its last git commit — after a
+/// confirmation — and show it in the editor, again as an undoable edit.
+fn revert_to_last_commit(
+    app_state: &Rc<RefCell<AppState>>,
+    autosave_state: &Rc<RefCell<AutoSaveState>>,
+    active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
+    statusbar: &Rc<RefCell<StatusBar>>,
+) {
+    let (note, dir) = {
+        let st = app_state.borrow();
+        (st.current_note.clone(), st.store.base_path().to_path_buf())
+    };
+    if note.starts_with('!') {
+        dialog::alert_default("Generated notes are not under version control.");
+        return;
+    }
+
+    // Like deleting, this discards work on disk, so it needs an explicit
+    // click on "Revert" (b0) rather than Enter.
+    let choice = dialog::choice2_default(
+        &format!(
+            "Revert “{note}” to its last committed version?\n\nAll changes since the last commit are discarded. You can still undo this in the editor."
+        ),
+        "Revert",
+        "Cancel",
+        "",
+    );
+    if choice != Some(0) {
+        return;
+    }
+
+    if let Err(e) = git::checkout_file(&dir, &ensure_md_extension(&note)) {
+        dialog::alert_default(&format!("Cannot revert “{note}”:\n\n{e}"));
+        return;
+    }
+    revert_to_disk_version(app_state, autosave_state, active_editor);
+    statusbar
+        .borrow_mut()
+        .set_status(&format!("Reverted {} to the last commit", note));
+}
+
+/// Replace the editor content with the current note's file as one undoable
+/// edit, and record that the editor matches the disk again.
+fn revert_to_disk_version(
+    app_state: &Rc<RefCell<AppState>>,
+    autosave_state: &Rc<RefCell<AutoSaveState>>,
+    active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
+) {
+    let (saved, mtime) = {
+        let st = app_state.borrow();
+        let note = &st.current_note;
+        let saved = st
+            .store
+            .load(note)
+            .map(|doc| doc.content)
+            .unwrap_or_default();
+        (saved, st.store.modified_time(note))
+    };
+    {
+        let active = active_editor.borrow();
+        let mut ed = active.borrow_mut();
+        match ed.as_any_mut().downcast_mut::<StructuredRichUI>() {
+            Some(structured) => {
+                structured.replace_content(&saved);
+            }
+            None => ed.set_content_from_markdown(&saved),
+        }
+    }
+    mark_disk_version_loaded(autosave_state, active_editor, &saved);
+    let mut as_state = autosave_state.borrow_mut();
+    as_state.disk_mtime = mtime;
+    as_state.last_change_time = None;
+}
+
+/// Tick (or untick) a todo listed on the `!todo` page in its note, then show
+/// the page afresh. `links` are those in the item whose checkbox was clicked;
+/// the one to the todo's line (`note#L12`) says where the todo is.
+fn toggle_listed_todo(
+    links: &[String],
+    app_state: &Rc<RefCell<AppState>>,
+    active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
+    statusbar: &Rc<RefCell<StatusBar>>,
+) {
+    let page = app_state.borrow().current_note.clone();
+    let is_todo_page = page
+        .strip_prefix('!')
+        .is_some_and(|plugin| piki_core::split_plugin_args(plugin).0 == "todo");
+    let todo = links.iter().find_map(|dest| {
+        let (note, fragment) = section_link::split_target(dest);
+        Some((note.to_string(), section_link::line_fragment(fragment?)?))
+    });
+    let (true, Some((note, line))) = (is_todo_page, todo) else {
+        return;
+    };
+
+    let result = {
+        let state = app_state.borrow();
+        state.store.load(&note).and_then(|mut doc| {
+            doc.content = piki_core::toggle_todo(&doc.content, line)
+                .ok_or_else(|| format!("The todo is no longer on line {line} of {note}"))?;
+            state.store.save(&doc)
+        })
+    };
+    let status = match result {
+        Ok(()) => format!("Updated the todo in {note}"),
+        Err(e) => e,
+    };
+    let regenerated = app_state.borrow_mut().load_note(&page);
+    match regenerated {
+        Ok(content) => replace_editor_content(&content, active_editor),
+        Err(e) => statusbar.borrow_mut().set_note(&format!("Error: {e}")),
+    }
+    statusbar.borrow_mut().set_status(&status);
+}
+
+/// Import files dropped onto the editor: markdown files become new notes (the
+/// first one is opened), anything else — images, PDFs, … — is copied into the
+/// attachments folder and linked at the drop position.
+fn import_dropped_files(
+    paths: &[PathBuf],
+    app_state: &Rc<RefCell<AppState>>,
+    autosave_state: &Rc<RefCell<AutoSaveState>>,
+    active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
+    statusbar: &Rc<RefCell<StatusBar>>,
+) {
+    let readonly = active_editor.borrow().borrow().is_readonly();
+    let mut notes = Vec::new();
+    let mut links = Vec::new();
+    let mut errors = Vec::new();
+    {
+        let st = app_state.borrow();
+        for path in paths {
+            let file_name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let is_note = has_md_extension(&file_name)
+                || file_name.to_ascii_lowercase().ends_with(".markdown");
+            let result = if path.is_dir() {
+                Err(format!("{file_name}: folders cannot be imported"))
+            } else if is_note {
+                st.store.import_note(path).map(|name| notes.push(name))
+            } else if readonly {
+                Err(format!("{file_name}: this note cannot be edited"))
+            } else {
+                st.store
+                    .import_attachment(path)
+                    .map(|target| links.push((target, file_name)))
+            };
+            if let Err(e) = result {
+                errors.push(e);
+            }
+        }
+    }
+
+    if !links.is_empty() {
+        let active = active_editor.borrow();
+        let mut ed = active.borrow_mut();
+        if let Some(structured) = ed.as_any_mut().downcast_mut::<StructuredRichUI>() {
+            structured.insert_links(&links);
+        }
+    } else if let Some(first) = notes.first() {
+        load_note_helper(
+            first,
+            app_state,
+            autosave_state,
+            active_editor,
+            statusbar,
+            None,
+            None,
+        );
+    }
+
+    let status = match (notes.len(), links.len()) {
+        (0, 0) => String::new(),
+        (n, 0) => format!("Imported {n} note(s)"),
+        (0, a) => format!("Added {a} attachment(s)"),
+        (n, a) => format!("Imported {n} note(s), added {a} attachment(s)"),
+    };
+    let status = match errors.first() {
+        Some(e) if status.is_empty() => format!("Error: {e}"),
+        Some(e) => format!("{status}; error: {e}"),
+        None => status,
+    };
+    statusbar.borrow_mut().set_status(&status);
+}
+
+/// End the running Pomodoro session early and log the time worked so far.
+fn stop_pomodoro(
+    app_state: &Rc<RefCell<AppState>>,
+    autosave_state: &Rc<RefCell<AutoSaveState>>,
+    active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
+    statusbar: &Rc<RefCell<StatusBar>>,
+) {
+    if let Some(session) = pomodoro::stop() {
+        log_work_session(
+            &session,
+            app_state,
+            autosave_state,
+            active_editor,
+            statusbar,
+        );
+    }
+    statusbar.borrow_mut().set_timer(None);
+}
+
+/// Record a finished Pomodoro session as an `@worked(date, duration)` entry,
+/// in the note worked on or in the `--timesheet` note. If that note is open,
+/// the entry is added through the editor so it is not overwritten by unsaved
+/// edits; otherwise its file is updated directly.
+fn log_work_session(
+    session: &pomodoro::FinishedSession,
+    app_state: &Rc<RefCell<AppState>>,
+    autosave_state: &Rc<RefCell<AutoSaveState>>,
+    active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
+    statusbar: &Rc<RefCell<StatusBar>>,
+) {
+    if session.minutes == 0 {
+        statusbar.borrow_mut().set_status("Pomodoro stopped");
+        return;
+    }
+
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let (target, link) = match pomodoro::timesheet_note() {
+        Some(sheet) if sheet != session.note => (sheet, Some(session.note.as_str())),
+        _ => (session.note.clone(), None),
+    };
+    let entry = timesheet::format_entry(&date, session.minutes, link);
+
+    let is_open = app_state.borrow().current_note == target;
+    let result = if is_open {
+        let content = {
+            let active = active_editor.borrow();
+            let ed = active.borrow();
+            ed.get_content()
+        };
+        replace_editor_content(&timesheet::append_entry(&content, &entry), active_editor);
+        autosave_state.borrow_mut().mark_changed();
+        save_current_note(app_state, autosave_state, active_editor, statusbar);
+        Ok(())
+    } else {
+        let state = app_state.borrow();
+        state.store.load(&target).and_then(|mut doc| {
+            doc.content = timesheet::append_entry(&doc.content, &entry);
+            state.store.save(&doc)
+        })
+    };
+
+    let status = match result {
+        Ok(()) => format!(
+            "Logged {} to {}",
+            timesheet::format_duration(session.minutes),
+            target
+        ),
+        Err(e) => format!("Error: {}", e),
+    };
+    statusbar.borrow_mut().set_status(&status);
+    app::redraw();
+}
+
 fn navigate_back(
     app_state: &Rc<RefCell<AppState>>,
     autosave_state: &Rc<RefCell<AutoSaveState>>,
@@ -593,9 +1331,71 @@ fn navigate_forward(
     }
 }
 
+/// Draw with `typography` from ~/.pikirc, loading its font if it changed.
+fn apply_typography(typography: theme::Typography) {
+    if let Some(font) = &typography.font
+        && theme::typography().font.as_ref() != Some(font)
+    {
+        piki_gui::fltk_draw_context::load_font_family(font);
+    }
+    theme::set_typography(typography);
+}
+
+/// Zoom the editor's text one step in or out, or back to actual size for
+/// `None`, and remember the zoom for the open wiki.
+fn zoom_text(
+    zoom_in: Option<bool>,
+    app_state: &Rc<RefCell<AppState>>,
+    window_geometry: &Rc<RefCell<WindowGeometry>>,
+    statusbar: &Rc<RefCell<StatusBar>>,
+) {
+    let typography = theme::typography();
+    let zoom = zoom_in.map_or(1.0, |zoom_in| theme::zoom_step(typography.zoom, zoom_in));
+    theme::set_typography(theme::Typography { zoom, ..typography });
+
+    let wiki_dir = app_state.borrow().store.base_path().to_path_buf();
+    window_geometry.borrow_mut().set_zoom_for(&wiki_dir, zoom);
+    if let Some(path) = window_state::state_file_path()
+        && let Err(err) = window_state::save_state(&path, &window_geometry.borrow())
+    {
+        eprintln!("Failed to save window state: {err}");
+    }
+    statusbar
+        .borrow_mut()
+        .set_status(&format!("Zoom: {:.0}%", zoom * 100.0));
+    app::redraw();
+}
+
+/// Pad the editor's text to the current typography's text column.
+fn update_text_column(active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>) {
+    if let Ok(ed_ptr) = active_editor.try_borrow()
+        && let Ok(mut ed) = ed_ptr.try_borrow_mut()
+        && let Some(structured) = ed.as_any_mut().downcast_mut::<StructuredRichUI>()
+    {
+        let padding = theme::typography().padding(structured.width());
+        if padding != structured.horizontal_padding() {
+            structured.set_horizontal_padding(padding);
+        }
+    }
+}
+
+/// Pad the editor's text to focus mode's column.
+fn update_focus_column(active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>) {
+    if let Ok(ed_ptr) = active_editor.try_borrow()
+        && let Ok(mut ed) = ed_ptr.try_borrow_mut()
+        && let Some(structured) = ed.as_any_mut().downcast_mut::<StructuredRichUI>()
+    {
+        let padding = theme::typography().focus_padding(structured.width());
+        if padding != structured.horizontal_padding() {
+            structured.set_horizontal_padding(padding);
+        }
+    }
+}
+
 /// Lay out the stacked content widgets for a normal (non-fullscreen) window:
-/// the ON AIR bar (if sharing), the search bar (if open) below it, then the
-/// editor filling the rest above the status bar. Fullscreen has its own layout
+/// the ON AIR bar (if sharing), the search bar (if open) below it, the
+/// properties panel (if the note has properties), then the editor filling the
+/// rest above the status bar. Focus mode has its own layout
 /// in `menu::toggle_fullscreen`.
 fn relayout_content(
     win_w: i32,
@@ -627,14 +1427,63 @@ fn relayout_content(
         search_bar.borrow_mut().resize(0, search_top, win_w);
     }
 
-    let editor_top = search_top + search_h;
+    let properties_top = search_top + search_h;
+    let properties_h = properties_panel::height();
+    properties_panel::resize(0, properties_top, win_w);
+
+    let editor_top = properties_top + properties_h;
     let editor_h = (win_h - editor_top - statusbar_h).max(0);
+    // Split, the source takes the left half and the editor the right one.
+    let editor_x = if split_view::is_active() {
+        split_view::resize(0, editor_top, win_w / 2, editor_h);
+        win_w / 2
+    } else {
+        0
+    };
     if let Ok(ed_ptr) = active_editor.try_borrow()
         && let Ok(mut ed) = ed_ptr.try_borrow_mut()
         && let Some(structured) = ed.as_any_mut().downcast_mut::<StructuredRichUI>()
     {
-        structured.resize(0, editor_top, win_w, editor_h);
+        structured.resize(editor_x, editor_top, win_w - editor_x, editor_h);
+    }
+}
+
+/// Split the window into the open note's Markdown source and its rendering,
+/// read-only while split, or join it again. Returns whether it is split now.
+fn toggle_split_view(
+    app_state: &Rc<RefCell<AppState>>,
+    active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
+    on_air: &Rc<RefCell<OnAirBar>>,
+    search_bar: &Rc<RefCell<SearchBar>>,
+    statusbar: &Rc<RefCell<StatusBar>>,
+    wind_ref: &Rc<RefCell<window::Window>>,
+) -> bool {
+    let split = !split_view::is_active();
+    let is_plugin = app_state.borrow().current_note.starts_with('!');
+    if split {
+        let markdown = active_editor.borrow().borrow().get_content();
+        split_view::show(&markdown, !is_plugin);
+    } else {
+        split_view::hide();
     }
+    active_editor
+        .borrow()
+        .borrow_mut()
+        .set_readonly(split || is_plugin);
+
+    let (w, h) = {
+        let win = wind_ref.borrow();
+        (win.width(), win.height())
+    };
+    relayout_content(w, h, on_air, search_bar, active_editor, statusbar);
+    update_text_column(active_editor);
+    statusbar.borrow_mut().set_status(if split {
+        "Split view: edit the Markdown on the left"
+    } else {
+        "Split view closed"
+    });
+    app::redraw();
+    split
 }
 
 /// Start a Live Note Sharing session for the currently open note: spin up the
@@ -660,7 +1509,8 @@ fn start_sharing(
     };
     let markdown = active_editor.borrow().borrow().get_content();
 
-    match LiveShare::start(dir, note.clone(), markdown) {
+    let rules = pikirc::PikiRc::load().redact.rules();
+    match LiveShare::start(dir, rules, note.clone(), markdown) {
         Ok(session) => {
             let url = session.url_for(&note);
             {
@@ -717,6 +1567,72 @@ fn stop_sharing(
     app::redraw();
 }
 
+/// Open another window on this wiki, showing the open note. It runs as its
+/// own process with its own history and autosave; the note is saved first so
+/// the new window starts from the latest version. Both windows pick up each
+/// other's saves the way they pick up any change on disk, and ask before one
+/// would overwrite the other's (see [`resolve_external_change`]).
+fn open_new_window(
+    app_state: &Rc<RefCell<AppState>>,
+    autosave_state: &Rc<RefCell<AutoSaveState>>,
+    active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
+    statusbar: &Rc<RefCell<StatusBar>>,
+) {
+    save_current_note(app_state, autosave_state, active_editor, statusbar);
+    let (directory, note) = {
+        let st = app_state.borrow();
+        (st.store.base_path().to_path_buf(), st.current_note.clone())
+    };
+    let launched = std::env::current_exe()
+        .map_err(|e| format!("Failed to find the Piki executable: {e}"))
+        .and_then(|exe| {
+            std::process::Command::new(exe)
+                .arg("--directory")
+                .arg(directory)
+                .arg("--note")
+                .arg(note)
+                .spawn()
+                .map_err(|e| format!("Failed to open a new window: {e}"))
+        });
+    if let Err(err) = launched {
+        statusbar.borrow_mut().set_status(&err);
+    }
+}
+
+/// Reopen Piki on the wiki `name` from ~/.pikirc, saving the open note and
+/// the window geometry first so the new window picks up where this one was.
+fn switch_wiki(
+    name: &str,
+    app_state: &Rc<RefCell<AppState>>,
+    autosave_state: &Rc<RefCell<AutoSaveState>>,
+    active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
+    statusbar: &Rc<RefCell<StatusBar>>,
+    window_geometry: &Rc<RefCell<WindowGeometry>>,
+) {
+    let launched =
+        piki_core::wikis::directory(&pikirc::PikiRc::load().wikis, name).and_then(|directory| {
+            let exe = std::env::current_exe()
+                .map_err(|e| format!("Failed to find the Piki executable: {e}"))?;
+            std::process::Command::new(exe)
+                .arg("--directory")
+                .arg(directory)
+                .spawn()
+                .map_err(|e| format!("Failed to open the wiki '{name}': {e}"))
+        });
+    if let Err(err) = launched {
+        statusbar.borrow_mut().set_status(&err);
+        return;
+    }
+    save_current_note(app_state, autosave_state, active_editor, statusbar);
+    if let Some(path) = window_state::state_file_path() {
+        let snapshot = window_geometry.borrow().clone();
+        if let Err(err) = window_state::save_state(&path, &snapshot) {
+            eprintln!("Failed to save window state: {err}");
+        }
+    }
+    app::quit();
+}
+
 fn get_directory(dir_opt: Option<PathBuf>) -> PathBuf {
     dir_opt.unwrap_or_else(|| {
         std::env::var("HOME")
@@ -727,8 +1643,18 @@ fn get_directory(dir_opt: Option<PathBuf>) -> PathBuf {
 }
 
 fn main() {
+    let started = Instant::now();
     let args = Args::parse();
-    let directory = get_directory(args.directory);
+    let mut profile = StartupProfile::new(args.profile_startup, started);
+    let directory = match &args.wiki {
+        Some(name) => piki_core::wikis::directory(&pikirc::PikiRc::load().wikis, name)
+            .unwrap_or_else(|e| {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            }),
+        None => get_directory(args.directory),
+    };
+    profile.mark("parse arguments");
 
     // Ensure directory exists
     if !directory.exists()
@@ -756,6 +1682,9 @@ fn main() {
     let mut wind = window::Window::default()
         .with_size(400, 650) // Golden ratio 1:1.618 approx
         .with_label("Piki");
+    TITLED_WINDOW.with(|titled| {
+        *titled.borrow_mut() = Some((wind.clone(), title::wiki_name(&directory)));
+    });
 
     if let Some(path) = window_state_path.as_ref()
         && let Some(saved_state) = window_state::load_state(path.as_path())
@@ -771,29 +1700,67 @@ fn main() {
     }
 
     app_icon::set_window_icon(&mut wind);
+    profile.mark("initialize FLTK");
 
     // #[cfg(target_os = "macos")]
     // wind.set_color(Color::White);
 
     wind.begin();
 
-    // Create state and register plugins
-    let store = DocumentStore::new(directory.clone());
+    // Create state and register plugins, including the wiki's own scripts.
+    // Plugins do their work when their page is opened; the caches they read
+    // are warmed up in the background once the window is up (see below).
+    let mut store = DocumentStore::new(directory.clone());
+    store.set_passphrase_prompt(|note| {
+        dialog::password_default(&format!("Passphrase for the encrypted note '{note}':"), "")
+            .filter(|passphrase| !passphrase.is_empty())
+    });
     let mut plugin_registry = PluginRegistry::new();
     plugin_registry.register("index", Box::new(IndexPlugin));
     plugin_registry.register("todo", Box::new(TodoPlugin));
+    plugin_registry.register("random", Box::new(RandomPlugin));
+    plugin_registry.register("timesheet", Box::new(TimesheetPlugin));
+    plugin_registry.register("stats", Box::new(StatsPlugin));
+    piki_core::script::register_scripts(&mut plugin_registry, &directory);
 
     let recent_notes_path = window_state::recent_notes_file(&directory);
+    let history_path = window_state::history_file(&directory);
+    let settings = wiki_settings::WikiSettings::load(&directory);
+    if let Some(path) = window_state::appearance_file() {
+        theme::set(theme::load(&path));
+    }
+    let rc = pikirc::PikiRc::load();
+    apply_typography(rc.editor);
 
     let app_state = Rc::new(RefCell::new(AppState::new(
         store,
         plugin_registry,
-        args.note.clone(),
+        settings
+            .start_page(rc.gui.start_page.as_deref())
+            .to_string(),
         recent_notes_path,
+        history_path,
+        rc.gui,
     )));
+    {
+        // Page names the link editor suggests as targets.
+        let app_state = app_state.clone();
+        piki_gui::link_editor::set_page_names(move || {
+            let Ok(st) = app_state.try_borrow() else {
+                return Vec::new();
+            };
+            let mut names = st.store.list_all_documents().unwrap_or_default();
+            names.sort();
+            names.extend(st.plugin_registry.names().iter().map(|n| format!("!{n}")));
+            names
+        });
+    }
     let autosave_state = Rc::new(RefCell::new(AutoSaveState::new()));
+    autosave_state.borrow_mut().canonical = settings.format.canonical();
+    smart_punctuation::set_enabled(settings.smart_punctuation);
     // Holds the active Live Note Sharing session, if any.
     let live_share: Rc<RefCell<Option<LiveShare>>> = Rc::new(RefCell::new(None));
+    profile.mark("load application state");
 
     #[cfg(target_os = "macos")]
     let editor_padding = 0;
@@ -819,6 +1786,33 @@ fn main() {
         editor_x, editor_y, editor_w, editor_h, true,
     )));
     let active_editor: Rc<RefCell<Rc<RefCell<dyn NoteUI>>>> = Rc::new(RefCell::new(rich_editor));
+    update_text_column(&active_editor);
+
+    // The Markdown source of View → Split View, rendered into the editor as
+    // it is edited.
+    {
+        let active_editor = active_editor.clone();
+        split_view::install(
+            editor_x,
+            editor_y,
+            editor_w / 2,
+            editor_h,
+            move |markdown| {
+                if let Ok(ed_ptr) = active_editor.try_borrow()
+                    && let Ok(mut ed) = ed_ptr.try_borrow_mut()
+                    && let Some(structured) = ed.as_any_mut().downcast_mut::<StructuredRichUI>()
+                {
+                    let scroll = structured.scroll_pos();
+                    structured.replace_content(&markdown);
+                    structured.set_scroll_pos(scroll);
+                }
+                // The source may have changed the frontmatter, too.
+                let properties =
+                    piki_core::frontmatter::properties(piki_core::frontmatter::split(&markdown).0);
+                properties_panel::show_note(Some(properties));
+            },
+        );
+    }
 
     // Create status bar at the bottom using the custom StatusBar widget
     let statusbar = Rc::new(RefCell::new(StatusBar::new(
@@ -832,18 +1826,23 @@ fn main() {
     let wind_ref = Rc::new(RefCell::new(wind.clone()));
 
     // Initialize window geometry state (with fullscreen from saved state if available)
-    let saved_fullscreen = window_state_path
+    let saved_state = window_state_path
         .as_ref()
         .and_then(|path| window_state::load_state(path.as_path()))
-        .map(|state| state.fullscreen)
-        .unwrap_or(false);
+        .unwrap_or_default();
     let window_geometry = Rc::new(RefCell::new(WindowGeometry {
         x: wind.x(),
         y: wind.y(),
         width: wind.width(),
         height: wind.height(),
-        fullscreen: saved_fullscreen,
+        fullscreen: saved_state.fullscreen,
+        zoom: saved_state.zoom,
     }));
+    // Zoom the text as far as this wiki was zoomed last time.
+    apply_typography(theme::Typography {
+        zoom: window_geometry.borrow().zoom_for(&directory),
+        ..theme::typography()
+    });
 
     // Create search bar (uses a sub-window so it floats on top)
     let search_bar = Rc::new(RefCell::new(SearchBar::new(editor_x, editor_y, editor_w)));
@@ -851,6 +1850,61 @@ fn main() {
     // Create the ON AIR bar (hidden until Live Note Sharing is enabled).
     let on_air = Rc::new(RefCell::new(OnAirBar::new(editor_x, editor_y, editor_w)));
 
+    // The properties panel above the note, editing its frontmatter.
+    {
+        let active_editor_for_change = active_editor.clone();
+        let active_editor = active_editor.clone();
+        let on_air = on_air.clone();
+        let search_bar = search_bar.clone();
+        let statusbar = statusbar.clone();
+        let wind_ref = wind_ref.clone();
+        let window_geometry = window_geometry.clone();
+        properties_panel::install(
+            editor_x,
+            editor_y,
+            editor_w,
+            move |properties| {
+                let Ok(ed_ptr) = active_editor_for_change.try_borrow() else {
+                    return;
+                };
+                if let Ok(mut ed) = ed_ptr.try_borrow_mut()
+                    && let Some(structured) = ed.as_any_mut().downcast_mut::<StructuredRichUI>()
+                {
+                    structured.set_properties(&properties);
+                }
+                if split_view::is_active() {
+                    split_view::set_source(&ed_ptr.borrow().get_content(), true);
+                }
+            },
+            move || {
+                let (w, h) = {
+                    let win = wind_ref.borrow();
+                    (win.width(), win.height())
+                };
+                if !window_geometry.borrow().fullscreen {
+                    relayout_content(w, h, &on_air, &search_bar, &active_editor, &statusbar);
+                    return;
+                }
+                // Focus mode keeps its own layout, without menu and status
+                // bar; only the panel's height changed.
+                let on_air_h = on_air.borrow().visible().then(|| on_air.borrow().height());
+                let search_h = search_bar
+                    .borrow()
+                    .visible()
+                    .then_some(search_bar::BAR_HEIGHT);
+                let top = on_air_h.unwrap_or(0) + search_h.unwrap_or(0);
+                properties_panel::resize(0, top, w);
+                let editor_top = top + properties_panel::height();
+                if let Ok(ed_ptr) = active_editor.try_borrow()
+                    && let Ok(mut ed) = ed_ptr.try_borrow_mut()
+                    && let Some(structured) = ed.as_any_mut().downcast_mut::<StructuredRichUI>()
+                {
+                    structured.resize(0, editor_top, w, h - editor_top);
+                }
+            },
+        );
+    }
+
     // Wire the ON AIR bar: Stop ends sharing; clicking the link opens it.
     {
         let live_share = live_share.clone();
@@ -917,7 +1971,7 @@ fn main() {
     );
 
     #[cfg(not(target_os = "macos"))]
-    let _menu_bar = menu::setup_menu(
+    let mut menu_bar = menu::setup_menu(
         app_state.clone(),
         autosave_state.clone(),
         active_editor.clone(),
@@ -1037,7 +2091,25 @@ fn main() {
         });
     }
 
+    // The privacy screen goes last so it covers every other widget when raised.
+    {
+        let active_editor = active_editor.clone();
+        privacy_screen::install(
+            wind.w(),
+            wind.h(),
+            args.lock_after.map(|mins| Duration::from_secs(mins * 60)),
+            move || {
+                if let Ok(ed_ptr) = active_editor.try_borrow()
+                    && let Ok(mut ed) = ed_ptr.try_borrow_mut()
+                {
+                    ed.take_focus();
+                }
+            },
+        );
+    }
+
     wind.end();
+    profile.mark("build widgets");
     let pending_save_handle = Rc::new(RefCell::new(None::<app::TimeoutHandle>));
 
     {
@@ -1048,12 +2120,64 @@ fn main() {
         let on_air_for_resize = on_air.clone();
         let active_editor_for_resize = active_editor.clone();
         let statusbar_for_resize = statusbar.clone();
-        let app_state_for_close = app_state.clone();
-        let autosave_for_close = autosave_state.clone();
-        let live_share_for_close = live_share.clone();
+        let app_state_for_resize = app_state.clone();
+
+        // FLTK hands closing the window (its close button, Escape, quitting
+        // the app on macOS) to the window's callback, not to its event
+        // handler; the callback hides the window once everything is flushed.
+        wind.set_callback({
+            let geometry = geometry.clone();
+            let pending = pending.clone();
+            let state_path = window_state_path.clone();
+            let app_state = app_state.clone();
+            let autosave_state = autosave_state.clone();
+            let active_editor = active_editor.clone();
+            let statusbar = statusbar.clone();
+            let live_share = live_share.clone();
+            move |win| {
+                // Flush the open note before the window goes away.
+                save_current_note(&app_state, &autosave_state, &active_editor, &statusbar);
+                // Keep where the user was in it for back/forward next time.
+                let position = {
+                    let active = active_editor.borrow();
+                    let ed = active.borrow();
+                    NotePosition {
+                        scroll: ed.scroll_pos(),
+                        cursor: ed.cursor_pos(),
+                    }
+                };
+                {
+                    let mut state = app_state.borrow_mut();
+                    state.history.update_position(position);
+                    state.save_history();
+                }
+                // Shut the sharing server down cleanly (joins its thread).
+                let session = live_share.borrow_mut().take();
+                drop(session);
+                if let Some(handle) = {
+                    let mut slot = pending.borrow_mut();
+                    slot.take()
+                } {
+                    app::remove_timeout3(handle);
+                }
+                if let Some(path) = state_path.as_ref() {
+                    let snapshot = geometry.borrow().clone();
+                    if let Err(err) = window_state::save_state(path.as_path(), &snapshot) {
+                        eprintln!("Failed to save window state on close: {err}");
+                    }
+                }
+                win.hide();
+            }
+        });
 
         wind.handle(move |win, event| match event {
             enums::Event::Move | enums::Event::Resize => {
+                privacy_screen::note_activity();
+                // Keep a raised privacy screen covering the whole window.
+                if matches!(event, enums::Event::Resize) && privacy_screen::is_locked() {
+                    privacy_screen::lock();
+                }
+
                 // Don't update geometry while in fullscreen mode - preserve the
                 // pre-fullscreen window position for when we exit fullscreen
                 if geometry.borrow().fullscreen {
@@ -1083,6 +2207,7 @@ fn main() {
                         &active_editor_for_resize,
                         &statusbar_for_resize,
                     );
+                    update_text_column(&active_editor_for_resize);
                 }
 
                 {
@@ -1104,7 +2229,12 @@ fn main() {
                     let geometry_for_timer = geometry.clone();
                     let pending_for_timer = pending.clone();
                     let path_for_timer = path.clone();
-                    let new_handle = app::add_timeout3(WINDOW_STATE_SAVE_TIMEOUT_SECS, move |_| {
+                    let delay = app_state_for_resize
+                        .try_borrow()
+                        .map_or(pikirc::DEFAULT_WINDOW_STATE_DELAY_SECS, |st| {
+                            st.gui.window_state_delay_secs()
+                        });
+                    let new_handle = app::add_timeout3(delay, move |_| {
                         let snapshot = geometry_for_timer.borrow().clone();
                         if let Err(err) =
                             window_state::save_state(path_for_timer.as_path(), &snapshot)
@@ -1117,29 +2247,12 @@ fn main() {
                 }
                 false
             }
-            enums::Event::Close => {
-                // Flush the open note before the window goes away.
-                save_current_note(
-                    &app_state_for_close,
-                    &autosave_for_close,
-                    &active_editor_for_resize,
-                    &statusbar_for_resize,
-                );
-                // Shut the sharing server down cleanly (joins its thread).
-                let session = live_share_for_close.borrow_mut().take();
-                drop(session);
-                if let Some(handle) = {
-                    let mut slot = pending.borrow_mut();
-                    slot.take()
-                } {
-                    app::remove_timeout3(handle);
-                }
-                if let Some(path) = state_path_for_handler.as_ref() {
-                    let snapshot = geometry.borrow().clone();
-                    if let Err(err) = window_state::save_state(path.as_path(), &snapshot) {
-                        eprintln!("Failed to save window state on close: {err}");
-                    }
-                }
+            enums::Event::Push
+            | enums::Event::Drag
+            | enums::Event::MouseWheel
+            | enums::Event::KeyDown
+            | enums::Event::Shortcut => {
+                privacy_screen::note_activity();
                 false
             }
             _ => false,
@@ -1148,9 +2261,10 @@ fn main() {
 
     active_editor.borrow().borrow().set_resizable(&mut wind);
     wind.show();
+    profile.mark("show window");
 
-    // Restore fullscreen mode if it was previously enabled
-    if saved_fullscreen {
+    // Restore focus mode if it was previously enabled
+    if saved_state.fullscreen {
         // Determine which screen the window is on using its center point
         let win_center_x = wind.x() + wind.width() / 2;
         let win_center_y = wind.y() + wind.height() / 2;
@@ -1161,12 +2275,11 @@ fn main() {
 
         // Calculate and apply padding using the correct screen dimensions
         let (_, _, screen_w, screen_h) = app::screen_xywh(screen_num);
-        let font_size = 14; // Default body text font size from theme
-        let char_width = (font_size as f32 * 0.55) as i32;
-        let target_text_width = char_width * 90; // ~90 chars
-        let scrollbar_width = 15;
-        let available_width = screen_w - scrollbar_width;
-        let padding = ((available_width - target_text_width) / 2).max(25);
+        let padding = theme::typography().focus_padding(screen_w);
+
+        // Collapse the menu bar, as `menu::toggle_fullscreen` does
+        #[cfg(not(target_os = "macos"))]
+        menu_bar.resize(0, 0, screen_w, 0);
 
         // Apply padding and resize the editor to take full height
         if let Ok(active_ptr) = active_editor.try_borrow()
@@ -1174,9 +2287,9 @@ fn main() {
             && let Some(structured) = editor.as_any_mut().downcast_mut::<StructuredRichUI>()
         {
             structured.set_horizontal_padding(padding);
+            structured.set_focus_mode(true);
             // Expand editor to full screen height (no statusbar)
-            let y = structured.y();
-            structured.resize(0, y, screen_w, screen_h - y);
+            structured.resize(0, 0, screen_w, screen_h);
         }
 
         // Hide status bar
@@ -1201,17 +2314,38 @@ fn main() {
         });
     }
 
-    // Load initial note
+    // Load initial note: the one asked for, else where the last session left
+    // off (if the wiki wants that and the note is still there), else the start
+    // page.
+    let initial_note = args.note.clone().unwrap_or_else(|| {
+        let st = app_state.borrow();
+        st.recent_notes
+            .most_recent()
+            .filter(|_| settings.reopen_last_session)
+            .filter(|note| note.starts_with('!') || st.store.path_for(note).exists())
+            .unwrap_or(&st.home)
+            .to_string()
+    });
+    // Reopening the note the last session's history was on resumes it there,
+    // keeping back/forward from it intact.
+    let restored_position = app_state
+        .borrow()
+        .history
+        .current()
+        .filter(|entry| entry.note_name == initial_note)
+        .map(|entry| entry.position.clone());
     load_note_helper(
-        &args.note,
+        &initial_note,
         &app_state,
         &autosave_state,
         &active_editor,
         &statusbar,
-        None,
+        restored_position,
         None,
     );
 
+    profile.mark("load initial note");
+
     // Wire callbacks for active editor
     wire_editor_callbacks(
         &active_editor,
@@ -1231,9 +2365,10 @@ fn main() {
             if let (Ok(as_state), Ok(mut sb)) =
                 (autosave_ref.try_borrow(), statusbar_ref.try_borrow_mut())
                 && !as_state.is_saving
-                && as_state.last_save_time.is_some()
+                && (as_state.last_save_time.is_some() || as_state.modified_time.is_some())
             {
                 sb.set_status(&as_state.get_status_text());
+                sb.set_status_tooltip(&as_state.get_status_tooltip());
                 app::redraw();
             }
 
@@ -1242,6 +2377,149 @@ fn main() {
         });
     }
 
+    // Count down a running Pomodoro session in the status bar and log it once
+    // its time is up. Clicking the countdown ends the session early.
+    pomodoro::set_timesheet_note(args.timesheet.clone());
+    {
+        let app_state = app_state.clone();
+        let autosave_state = autosave_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar_for_click = statusbar.clone();
+        statusbar.borrow_mut().on_timer_click(move || {
+            stop_pomodoro(
+                &app_state,
+                &autosave_state,
+                &active_editor,
+                &statusbar_for_click,
+            );
+        });
+    }
+    {
+        let app_state = app_state.clone();
+        let autosave_state = autosave_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        app::add_timeout3(POMODORO_TICK_SECS, move |handle| {
+            if let Some(session) = pomodoro::take_finished() {
+                log_work_session(
+                    &session,
+                    &app_state,
+                    &autosave_state,
+                    &active_editor,
+                    &statusbar,
+                );
+            }
+            if let Ok(mut sb) = statusbar.try_borrow_mut() {
+                sb.set_timer(pomodoro::status_text().as_deref());
+            }
+            app::repeat_timeout3(POMODORO_TICK_SECS, handle);
+        });
+    }
+
+    // Pick up edits made to the open note by other programs. Polling the file's
+    // modification time is cheap and needs no platform file-watching support.
+    // The same tick keeps our claim on the note current (see
+    // `piki_core::edit_lock`) and notices when `piki edit` opens it.
+    {
+        let app_state = app_state.clone();
+        let autosave_state = autosave_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        app::add_timeout3(FILE_WATCH_INTERVAL_SECS, move |handle| {
+            if let (Ok(mut as_state), Ok(st)) =
+                (autosave_state.try_borrow_mut(), app_state.try_borrow())
+            {
+                let dirty = as_state.pending_save;
+                if as_state.update_claim(&st.store, dirty)
+                    && let Ok(mut sb) = statusbar.try_borrow_mut()
+                {
+                    sb.set_status(&as_state.get_status_text());
+                }
+            }
+            resolve_external_change(&app_state, &autosave_state, &active_editor, &statusbar);
+            app::repeat_timeout3(FILE_WATCH_INTERVAL_SECS, handle);
+        });
+    }
+
+    // Apply changes to the `[editor]` and `[gui]` sections of ~/.pikirc while
+    // running.
+    {
+        let app_state = app_state.clone();
+        let active_editor = active_editor.clone();
+        let window_geometry = window_geometry.clone();
+        let mut last_modified = pikirc::PikiRc::modified();
+        app::add_timeout3(PIKIRC_WATCH_INTERVAL_SECS, move |handle| {
+            let modified = pikirc::PikiRc::modified();
+            if modified != last_modified {
+                last_modified = modified;
+                let rc = pikirc::PikiRc::load();
+                if let Ok(mut st) = app_state.try_borrow_mut() {
+                    let settings = wiki_settings::WikiSettings::load(st.store.base_path());
+                    st.home = settings
+                        .start_page(rc.gui.start_page.as_deref())
+                        .to_string();
+                    smart_punctuation::set_enabled(settings.smart_punctuation);
+                    st.gui = rc.gui;
+                }
+                let typography = theme::Typography {
+                    zoom: theme::typography().zoom,
+                    ..rc.editor
+                };
+                if typography != theme::typography() {
+                    apply_typography(typography);
+                    if window_geometry.borrow().fullscreen {
+                        update_focus_column(&active_editor);
+                    } else {
+                        update_text_column(&active_editor);
+                    }
+                    app::redraw();
+                }
+            }
+            app::repeat_timeout3(PIKIRC_WATCH_INTERVAL_SECS, handle);
+        });
+    }
+
+    // Sync the notes with their git remote every few minutes if `[sync]` in
+    // ~/.pikirc asks for it. The section is read again on every check, so
+    // turning syncing on or off takes effect without a restart. Syncing talks
+    // to the network, so it runs on its own thread; notes it pulls in are
+    // picked up by the file watch above.
+    {
+        let app_state = app_state.clone();
+        let statusbar = statusbar.clone();
+        let mut last_sync = Instant::now();
+        let mut running: Option<mpsc::Receiver<Result<git::SyncReport, String>>> = None;
+        app::add_timeout3(SYNC_POLL_SECS, move |handle| {
+            if let Some(rx) = &running
+                && let Ok(result) = rx.try_recv()
+            {
+                running = None;
+                if let Ok(mut sb) = statusbar.try_borrow_mut() {
+                    match result {
+                        Ok(report) => sb.set_status(&report.summary()),
+                        Err(e) => sb.set_status(&format!("Sync failed: {e}")),
+                    }
+                }
+            }
+            let sync = pikirc::PikiRc::load().sync;
+            if running.is_none()
+                && let Some(interval) = sync.interval()
+                && Instant::now() >= last_sync + interval
+                && let Ok(st) = app_state.try_borrow()
+            {
+                let dir = st.store.base_path().to_path_buf();
+                let commit = sync.commit.clone();
+                let (tx, rx) = mpsc::channel();
+                thread::spawn(move || {
+                    let _ = tx.send(git::sync(&dir, commit.as_deref()));
+                });
+                running = Some(rx);
+                last_sync = Instant::now();
+            }
+            app::repeat_timeout3(SYNC_POLL_SECS, handle);
+        });
+    }
+
     // Set up a lightweight tick for blinking cursor and animations
     {
         let start = Instant::now();
@@ -1274,6 +2552,7 @@ fn main() {
             if let Ok(mut bar) = on_air_ref.try_borrow_mut() {
                 bar.tick(ms);
             }
+            privacy_screen::check_idle();
             app::repeat_timeout3(0.1, handle);
         });
     }
@@ -1319,6 +2598,17 @@ fn main() {
         app_url::register();
     }
 
+    // The first timeout fires once the event loop is up and the window has
+    // been drawn, which is when startup is over from the user's perspective.
+    // Only then are the todo cache and the link index brought up to date, on
+    // their own thread, so that neither delays the window on a large wiki.
+    app::add_timeout3(0.0, move |_| {
+        profile.mark("first event loop iteration");
+        profile.report();
+        let store = DocumentStore::new(directory.clone());
+        thread::spawn(move || piki_core::warm_up(&store));
+    });
+
     app.run().unwrap();
 }
 
@@ -1336,6 +2626,10 @@ fn wire_editor_callbacks(
     let live_share_for_change = live_share.clone();
     let current_for_change = active_editor.borrow().clone();
     current_for_change.borrow_mut().on_change(Box::new(move || {
+        // Typing goes straight to the editor, bypassing the window's event
+        // handler, so count edits as activity here.
+        privacy_screen::note_activity();
+
         // Restyle if supported
         let editor_clone = editor_for_callback.clone();
         app::awake_callback(move || {
@@ -1366,7 +2660,14 @@ fn wire_editor_callbacks(
         }
 
         if let Ok(mut as_state) = autosave_for_callback.try_borrow_mut() {
+            let was_dirty = as_state.pending_save;
             as_state.mark_changed();
+            if !was_dirty && as_state.should_save() {
+                if let Ok(mut sb) = statusbar_for_callback.try_borrow_mut() {
+                    sb.set_status(&as_state.get_status_text());
+                }
+                show_dirty_in_title(true);
+            }
         }
 
         let editor_clone = editor_for_callback.clone();
@@ -1374,13 +2675,23 @@ fn wire_editor_callbacks(
         let app_state_clone = app_state_for_callback.clone();
         let statusbar_clone = statusbar_for_callback.clone();
 
-        app::add_timeout3(AUTOSAVE_INTERVAL_SECS, move |_| {
+        let delay = app_state_for_callback
+            .try_borrow()
+            .map_or(pikirc::DEFAULT_AUTOSAVE_SECS, |st| st.gui.autosave_secs());
+        app::add_timeout3(delay, move |_| {
             let should_save = autosave_clone
                 .try_borrow()
                 .map(|s| s.pending_save)
                 .unwrap_or(false);
 
             if should_save {
+                resolve_external_change(
+                    &app_state_clone,
+                    &autosave_clone,
+                    &editor_clone,
+                    &statusbar_clone,
+                );
+
                 if let Ok(mut sb) = statusbar_clone.try_borrow_mut() {
                     sb.set_status("Saving …");
                     app::redraw();
@@ -1406,6 +2717,7 @@ fn wire_editor_callbacks(
                             }
                         }
                     }
+                    show_dirty_in_title(as_state.pending_save);
                 }
             }
         });
@@ -1432,6 +2744,9 @@ fn wire_editor_callbacks(
             if link_handler::is_external_link(&normalized) {
                 let statusbar = statusbar_links.clone();
                 app::awake_callback(move || {
+                    if !confirm_external_link(&normalized) {
+                        return;
+                    }
                     if let Err(e) = webbrowser::open(&normalized) {
                         statusbar
                             .borrow_mut()
@@ -1445,7 +2760,32 @@ fn wire_editor_callbacks(
             // Internal link: split off an optional `#section` fragment so we can
             // scroll to that heading after the note loads.
             let (note, fragment) = section_link::split_target(&normalized);
-            let note = note.to_string();
+
+            // Links written in a note are relative to its folder, the way `piki
+            // view` follows them; `piki:` URLs always name notes from the top.
+            let note = match app_state_links.try_borrow() {
+                Ok(st) if normalized == link_dest => st.store.resolve_link(&st.current_note, note),
+                _ => note.to_string(),
+            };
+
+            // Links to attachments (images, PDFs, ...) stored in the notes
+            // directory open in their default application rather than being
+            // loaded into the editor as if they were markdown.
+            let attachment = app_state_links
+                .try_borrow()
+                .ok()
+                .and_then(|st| st.store.attachment_path(&note));
+            if let Some(path) = attachment {
+                let statusbar = statusbar_links.clone();
+                app::awake_callback(move || {
+                    if let Err(e) = open_with_system_handler(&path) {
+                        statusbar.borrow_mut().set_status(&e);
+                        app::redraw();
+                    }
+                });
+                return;
+            }
+
             let fragment = fragment.map(str::to_string);
 
             let app_state = app_state_links.clone();
@@ -1466,6 +2806,50 @@ fn wire_editor_callbacks(
         }));
     }
 
+    // Files dropped onto the editor are imported as notes or attachments
+    let current_for_drop = active_editor.borrow().clone();
+    {
+        let mut cur = current_for_drop.borrow_mut();
+        let app_state = app_state.clone();
+        let autosave_state = autosave_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        cur.on_files_dropped(Box::new(move |paths: Vec<PathBuf>| {
+            let app_state = app_state.clone();
+            let autosave_state = autosave_state.clone();
+            let active_editor = active_editor.clone();
+            let statusbar = statusbar.clone();
+            app::awake_callback(move || {
+                import_dropped_files(
+                    &paths,
+                    &app_state,
+                    &autosave_state,
+                    &active_editor,
+                    &statusbar,
+                );
+                app::redraw();
+            });
+        }));
+    }
+
+    // Checkboxes on the `!todo` page tick the todos in their notes
+    let current_for_checkbox = active_editor.borrow().clone();
+    {
+        let mut cur = current_for_checkbox.borrow_mut();
+        let app_state = app_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        cur.on_readonly_checkbox(Box::new(move |links: Vec<String>| {
+            let app_state = app_state.clone();
+            let active_editor = active_editor.clone();
+            let statusbar = statusbar.clone();
+            app::awake_callback(move || {
+                toggle_listed_todo(&links, &app_state, &active_editor, &statusbar);
+                app::redraw();
+            });
+        }));
+    }
+
     // Hover handler to show link destinations in the note status bar
     let current_for_hover = active_editor.borrow().clone();
     {
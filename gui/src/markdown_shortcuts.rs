@@ -0,0 +1,102 @@
+//! Markdown shortcuts while typing.
+//!
+//! Typing a Markdown block marker at the start of a plain paragraph turns the
+//! paragraph into that kind of block and removes the marker, the way other
+//! Markdown editors do: `# ` to `### ` make headings, `- `, `* ` or `+ ` a
+//! list, `1. ` a numbered list, `> ` a quote and ```` ``` ```` a code block.
+//! Inside a bullet list item, `[ ] ` turns the item into a checklist item.
+
+use rutle::editor::Editor;
+use rutle::structured_document::BlockType;
+use rutle::tree_path::DocumentPosition;
+
+/// The block a marker converts the current paragraph to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkdownShortcut {
+    Heading(u8),
+    List,
+    OrderedList,
+    Checklist,
+    Quote,
+    CodeBlock,
+}
+
+/// The shortcut for `prefix` — everything typed so far in a block of type
+/// `block` — if it is exactly a complete marker.
+pub fn shortcut_for(prefix: &str, block: &BlockType) -> Option<MarkdownShortcut> {
+    match block {
+        BlockType::Paragraph => match prefix {
+            "# " => Some(MarkdownShortcut::Heading(1)),
+            "## " => Some(MarkdownShortcut::Heading(2)),
+            "### " => Some(MarkdownShortcut::Heading(3)),
+            "- " | "* " | "+ " => Some(MarkdownShortcut::List),
+            "> " => Some(MarkdownShortcut::Quote),
+            "```" => Some(MarkdownShortcut::CodeBlock),
+            _ => {
+                let number = prefix.strip_suffix(". ")?;
+                (!number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+                    .then_some(MarkdownShortcut::OrderedList)
+            }
+        },
+        BlockType::ListItem {
+            ordered: false,
+            checkbox: None,
+            ..
+        } if prefix == "[ ] " || prefix == "[] " => Some(MarkdownShortcut::Checklist),
+        _ => None,
+    }
+}
+
+/// Apply the shortcut completed by the text just typed at the cursor, if any.
+/// Returns whether the block was converted.
+pub fn apply(editor: &mut Editor) -> bool {
+    let cursor = editor.cursor();
+    let start = DocumentPosition::at(cursor.path.clone(), 0);
+    let prefix = editor.text_in_range(start, cursor);
+    let Some(shortcut) = shortcut_for(&prefix, &editor.current_block_type()) else {
+        return false;
+    };
+
+    if !matches!(editor.delete_backward_bytes(prefix.len()), Ok(true)) {
+        return false;
+    }
+    let result = match shortcut {
+        MarkdownShortcut::Heading(level) => editor.set_block_type(BlockType::Heading { level }),
+        MarkdownShortcut::List => editor.toggle_list(),
+        MarkdownShortcut::OrderedList => editor.toggle_ordered_list(),
+        MarkdownShortcut::Checklist => editor.toggle_checklist(),
+        MarkdownShortcut::Quote => editor.toggle_quote(),
+        MarkdownShortcut::CodeBlock => editor.toggle_code_block(),
+    };
+    result.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markers_at_the_start_of_a_paragraph() {
+        let p = BlockType::Paragraph;
+        assert_eq!(shortcut_for("# ", &p), Some(MarkdownShortcut::Heading(1)));
+        assert_eq!(shortcut_for("### ", &p), Some(MarkdownShortcut::Heading(3)));
+        assert_eq!(shortcut_for("* ", &p), Some(MarkdownShortcut::List));
+        assert_eq!(
+            shortcut_for("12. ", &p),
+            Some(MarkdownShortcut::OrderedList)
+        );
+        assert_eq!(shortcut_for("> ", &p), Some(MarkdownShortcut::Quote));
+        assert_eq!(shortcut_for("```", &p), Some(MarkdownShortcut::CodeBlock));
+    }
+
+    #[test]
+    fn anything_else_is_left_alone() {
+        let p = BlockType::Paragraph;
+        assert_eq!(shortcut_for("#### ", &p), None);
+        assert_eq!(shortcut_for("text # ", &p), None);
+        assert_eq!(shortcut_for(". ", &p), None);
+        assert_eq!(shortcut_for("a. ", &p), None);
+        assert_eq!(shortcut_for("# ", &BlockType::Heading { level: 2 }), None);
+        assert_eq!(shortcut_for("- ", &BlockType::BlockQuote), None);
+    }
+}
@@ -1,12 +1,15 @@
 use super::{
     AppState, AutoSaveState, delete_current_note, load_note_helper, navigate_back,
-    navigate_forward, note_picker, rename_current_note, search_bar::SearchBar, start_sharing,
-    statusbar::StatusBar, stop_sharing, window_state::WindowGeometry,
+    navigate_forward, note_picker, open_new_window, page_history, pikirc, pomodoro,
+    properties_panel, rename_current_note, rename_tag, revert_to_last_commit, revert_to_saved,
+    save_current_note,
+    search_bar::SearchBar,
+    split_view, start_sharing,
+    statusbar::StatusBar,
+    stop_pomodoro, stop_sharing, switch_wiki, toggle_split_view, unsaved_changes,
+    window_state::{self, WindowGeometry},
+    zoom_text,
 };
-// Only the non-macOS in-app Quit item saves explicitly; on macOS the system
-// Quit routes through the window Close event, which already saves.
-#[cfg(not(target_os = "macos"))]
-use super::save_current_note;
 use chrono::Local;
 use fltk::{
     app, button, dialog,
@@ -15,10 +18,15 @@ use fltk::{
     prelude::*,
     window,
 };
+use piki_core::graph;
+use piki_gui::date_picker;
 use piki_gui::link_editor::{self, LinkEditOptions};
+use piki_gui::link_graph;
 use piki_gui::live_share::LiveShare;
 use piki_gui::note_ui::NoteUI;
 use piki_gui::on_air_bar::OnAirBar;
+use piki_gui::print;
+use piki_gui::theme::{self, Appearance, Palette};
 use piki_gui::ui_adapters::StructuredRichUI;
 use rutle::structured_document::{BlockType, InlineContent};
 use std::cell::RefCell;
@@ -33,6 +41,10 @@ const FORMAT_CODE_BLOCK: &str = "Format/Code Block";
 const FORMAT_NUMBERED_LIST: &str = "Format/Numbered List";
 const FORMAT_LIST_ITEM: &str = "Format/List Item";
 const FORMAT_CHECKLIST_ITEM: &str = "Format/_Checklist Item";
+const FORMAT_QUOTE_DEEPER: &str = "Format/Increase Quote Level";
+const FORMAT_QUOTE_SHALLOWER: &str = "Format/_Decrease Quote Level";
+const FORMAT_MOVE_UP: &str = "Format/Move Paragraph Up";
+const FORMAT_MOVE_DOWN: &str = "Format/_Move Paragraph Down";
 
 const FORMAT_INLINE_BOLD: &str = "Format/Bold";
 const FORMAT_INLINE_ITALIC: &str = "Format/Italic";
@@ -44,15 +56,15 @@ const FORMAT_EDIT_LINK: &str = "Format/Edit Link…";
 
 const FORMAT_CLEAR: &str = "Format/Clear formatting";
 
+const INSERT_DATE: &str = "Insert/Date …";
+
 const EDIT_COPY_SECTION_LINK: &str = "Edit/Copy Link to Section";
 
-const VIEW_FULLSCREEN: &str = "View/Fullscreen";
+const VIEW_READ_MODE: &str = "View/Read Mode";
+const VIEW_FOCUS_MODE: &str = "View/Focus Mode";
+const VIEW_SPLIT: &str = "View/Split View";
 const VIEW_SHARE: &str = "View/Live Note Sharing";
-
-// Default padding for normal mode
-const DEFAULT_PADDING: i32 = 25;
-// Target text width in characters for fullscreen mode
-const FULLSCREEN_TARGET_CHARS: i32 = 90;
+const VIEW_UNDERLINE_LINKS: &str = "View/Always Underline Links";
 
 const PARAGRAPH_ITEMS: &[&str] = &[
     FORMAT_PARAGRAPH,
@@ -66,6 +78,10 @@ const PARAGRAPH_ITEMS: &[&str] = &[
     FORMAT_CHECKLIST_ITEM,
 ];
 
+const QUOTE_DEPTH_ITEMS: &[&str] = &[FORMAT_QUOTE_DEEPER, FORMAT_QUOTE_SHALLOWER];
+
+const MOVE_ITEMS: &[&str] = &[FORMAT_MOVE_UP, FORMAT_MOVE_DOWN];
+
 const INLINE_ITEMS: &[&str] = &[
     FORMAT_INLINE_BOLD,
     FORMAT_INLINE_ITALIC,
@@ -154,8 +170,13 @@ fn populate_menu<M>(
         Shortcut::Ctrl
     };
     let new_shortcut = cmd | 'n';
-    let rename_shortcut = cmd | 's';
+    let save_shortcut = cmd | 's';
+    let rename_shortcut = cmd | Shortcut::Shift | 's';
     let goto_note_shortcut = cmd | 'o';
+    // Ctrl+P, as in other editors, on every platform; printing moves to
+    // Cmd/Ctrl+Shift+P to make room for it where Ctrl is the command key.
+    let quick_switcher_shortcut = Shortcut::Ctrl | 'p';
+    let print_shortcut = cmd | Shortcut::Shift | 'p';
 
     let back_shortcut = if cfg!(target_os = "macos") {
         Shortcut::Command | '['
@@ -169,7 +190,7 @@ fn populate_menu<M>(
         Shortcut::Alt | Key::Right
     };
 
-    let frontpage_shortcut = cmd | Shortcut::Alt | 'f';
+    let home_shortcut = cmd | Shortcut::Alt | 'f';
     let index_shortcut = cmd | Shortcut::Alt | 'i';
     #[cfg(not(target_os = "macos"))]
     let quit_shortcut = cmd | 'q';
@@ -185,6 +206,11 @@ fn populate_menu<M>(
     let ordered_list_shortcut = cmd | Shortcut::Shift | '7';
     let list_shortcut = cmd | Shortcut::Shift | '8';
     let checklist_shortcut = cmd | Shortcut::Shift | '9';
+    let quote_deeper_shortcut = cmd | '\'';
+    let quote_shallower_shortcut = cmd | Shortcut::Alt | '\'';
+    // Also handled by the editor itself; see its Alt-Up/Down handling.
+    let move_up_shortcut = Shortcut::Alt | Key::Up;
+    let move_down_shortcut = Shortcut::Alt | Key::Down;
     let bold_shortcut = cmd | 'b';
     let italic_shortcut = cmd | 'i';
     let underline_shortcut = cmd | 'u';
@@ -193,16 +219,17 @@ fn populate_menu<M>(
     let strike_shortcut = cmd | Shortcut::Shift | 'x';
     let edit_link_shortcut = cmd | 'k';
     let clear_shortcut = cmd | '\\';
+    let insert_date_shortcut = cmd | Shortcut::Shift | 'd';
     let undo_shortcut = cmd | 'z';
     let redo_shortcut = cmd | Shortcut::Shift | 'z';
 
-    // Write room shortcut: Ctrl/Cmd-Shift-F
+    // Focus mode shortcut: Ctrl/Cmd-Shift-F
     let fullscreen_shortcut = cmd | Shortcut::Shift | 'f';
 
     // Note menu
     // New Note creates an auto-named `untitled_…` note and opens it immediately,
     // so a quick thought can be captured without first inventing a name; the note
-    // is given a real name later with Rename Note (Cmd-S).
+    // is given a real name later with Rename Note (Cmd-Shift-S).
     {
         let app_state = app_state.clone();
         let autosave_state = autosave_state.clone();
@@ -226,6 +253,22 @@ fn populate_menu<M>(
         );
     }
 
+    // New Window opens another, independent Piki window on this wiki.
+    {
+        let app_state = app_state.clone();
+        let autosave_state = autosave_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        menu_bar.add(
+            "Note/New Window",
+            cmd | Shortcut::Shift | 'n',
+            menu::MenuFlag::Normal,
+            move |_| {
+                open_new_window(&app_state, &autosave_state, &active_editor, &statusbar);
+            },
+        );
+    }
+
     {
         let app_state = app_state.clone();
         let autosave_state = autosave_state.clone();
@@ -250,6 +293,47 @@ fn populate_menu<M>(
         );
     }
 
+    {
+        let app_state = app_state.clone();
+        let autosave_state = autosave_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        let wind_ref = wind_ref.clone();
+        menu_bar.add(
+            "Note/Quick Switcher …",
+            quick_switcher_shortcut,
+            menu::MenuFlag::Normal,
+            move |_| {
+                if let Ok(w) = wind_ref.try_borrow() {
+                    note_picker::show_note_picker(
+                        app_state.clone(),
+                        autosave_state.clone(),
+                        active_editor.clone(),
+                        statusbar.clone(),
+                        &w,
+                    );
+                }
+            },
+        );
+    }
+
+    // Save writes the note right away instead of when autosave gets to it.
+    {
+        let app_state = app_state.clone();
+        let autosave_state = autosave_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        menu_bar.add(
+            "Note/Save",
+            save_shortcut,
+            menu::MenuFlag::Normal,
+            move |_| {
+                save_current_note(&app_state, &autosave_state, &active_editor, &statusbar);
+                app::redraw();
+            },
+        );
+    }
+
     {
         let app_state = app_state.clone();
         let autosave_state = autosave_state.clone();
@@ -272,6 +356,193 @@ fn populate_menu<M>(
         );
     }
 
+    {
+        let app_state = app_state.clone();
+        let autosave_state = autosave_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        menu_bar.add(
+            "Note/Rename Tag …",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |_| {
+                rename_tag(&app_state, &autosave_state, &active_editor, &statusbar);
+            },
+        );
+    }
+
+    // Edit Properties: open the panel above the note that edits its
+    // frontmatter, even when it has none yet.
+    {
+        let statusbar = statusbar.clone();
+        menu_bar.add(
+            "Note/Edit Properties",
+            cmd | Shortcut::Alt | 'p',
+            menu::MenuFlag::Normal,
+            move |_| {
+                if !properties_panel::open() {
+                    statusbar
+                        .borrow_mut()
+                        .set_status("Generated pages have no properties.");
+                }
+            },
+        );
+    }
+
+    // Show Unsaved Changes: a word-level diff between the editor and the file
+    // on disk, with a one-click Revert to Saved.
+    {
+        let app_state = app_state.clone();
+        let autosave_state = autosave_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        let wind_ref = wind_ref.clone();
+        menu_bar.add(
+            "Note/Show Unsaved Changes …",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |_| {
+                if let Ok(w) = wind_ref.try_borrow() {
+                    unsaved_changes::show_unsaved_changes(
+                        app_state.clone(),
+                        autosave_state.clone(),
+                        active_editor.clone(),
+                        statusbar.clone(),
+                        &w,
+                    );
+                }
+            },
+        );
+    }
+
+    // Revert to Saved / Revert to Last Commit: both replace the editor
+    // content as a single edit, so Undo brings the discarded text back.
+    {
+        let app_state = app_state.clone();
+        let autosave_state = autosave_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        menu_bar.add(
+            "Note/Revert to Saved",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |_| {
+                if app_state.borrow().current_note.starts_with('!') {
+                    return;
+                }
+                revert_to_saved(&app_state, &autosave_state, &active_editor, &statusbar);
+            },
+        );
+    }
+    {
+        let app_state = app_state.clone();
+        let autosave_state = autosave_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        menu_bar.add(
+            "Note/Revert to Last Commit …",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |_| {
+                revert_to_last_commit(&app_state, &autosave_state, &active_editor, &statusbar);
+            },
+        );
+    }
+
+    // Page History: the git commits touching the current note, with a
+    // read-only preview of each version and a way to restore one.
+    {
+        let app_state = app_state.clone();
+        let autosave_state = autosave_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        let wind_ref = wind_ref.clone();
+        menu_bar.add(
+            "Note/Page History …",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |_| {
+                if let Ok(w) = wind_ref.try_borrow() {
+                    page_history::show_page_history(
+                        app_state.clone(),
+                        autosave_state.clone(),
+                        active_editor.clone(),
+                        statusbar.clone(),
+                        &w,
+                    );
+                }
+            },
+        );
+    }
+
+    // Export as PDF: the current note, including unsaved edits, laid out
+    // with the display's typography.
+    {
+        let app_state = app_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        menu_bar.add(
+            "Note/Export as PDF …",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |_| {
+                export_pdf(&app_state, &active_editor, &statusbar);
+            },
+        );
+    }
+
+    // Print: paginates the current note through the system print dialog.
+    {
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        menu_bar.add(
+            "Note/Print …",
+            print_shortcut,
+            menu::MenuFlag::Normal,
+            move |_| {
+                let markdown = active_editor.borrow().borrow().get_content();
+                match print::print_markdown(&markdown) {
+                    Ok(0) => {}
+                    Ok(pages) => statusbar.borrow_mut().set_status(&format!(
+                        "Printed {} page{}",
+                        pages,
+                        if pages == 1 { "" } else { "s" }
+                    )),
+                    Err(e) => dialog::alert_default(&format!("Failed to print: {}", e)),
+                }
+            },
+        );
+    }
+
+    // Start Pomodoro: a 25 minute session for the current note, counted down
+    // in the status bar; choosing it again while running ends it early.
+    {
+        let app_state = app_state.clone();
+        let autosave_state = autosave_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        menu_bar.add(
+            "Note/Start Pomodoro",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |_| {
+                if pomodoro::is_running() {
+                    stop_pomodoro(&app_state, &autosave_state, &active_editor, &statusbar);
+                    return;
+                }
+                let note = app_state.borrow().current_note.clone();
+                if note.starts_with('!') {
+                    dialog::alert_default("Time can only be tracked for regular notes.");
+                    return;
+                }
+                pomodoro::start(&note);
+                let mut sb = statusbar.borrow_mut();
+                sb.set_timer(pomodoro::status_text().as_deref());
+                sb.set_status("Pomodoro started");
+            },
+        );
+    }
+
     // Delete Note: removes the current note's file after a confirmation dialog.
     // Deliberately has no keyboard shortcut so a destructive action is never a
     // stray keypress away. The `_` divider closes the note-management group
@@ -332,12 +603,36 @@ fn populate_menu<M>(
         let active_editor = active_editor.clone();
         let statusbar = statusbar.clone();
         menu_bar.add(
-            "Note/Go to Frontpage",
-            frontpage_shortcut,
+            "Note/Go Home",
+            home_shortcut,
+            menu::MenuFlag::Normal,
+            move |_| {
+                let home = app_state.borrow().home.clone();
+                load_note_helper(
+                    &home,
+                    &app_state,
+                    &autosave_state,
+                    &active_editor,
+                    &statusbar,
+                    None,
+                    None,
+                );
+            },
+        );
+    }
+
+    {
+        let app_state = app_state.clone();
+        let autosave_state = autosave_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        menu_bar.add(
+            "Note/Random Note",
+            Shortcut::None,
             menu::MenuFlag::Normal,
             move |_| {
                 load_note_helper(
-                    "frontpage",
+                    "!random",
                     &app_state,
                     &autosave_state,
                     &active_editor,
@@ -349,6 +644,41 @@ fn populate_menu<M>(
         );
     }
 
+    // One item per wiki in the [wikis] section of ~/.pikirc; picking another
+    // one reopens Piki on it.
+    let wikis = pikirc::PikiRc::load().wikis;
+    let current_wiki = {
+        let st = app_state.borrow();
+        piki_core::wikis::name_of(&wikis, st.store.base_path()).map(str::to_string)
+    };
+    for name in wikis.into_keys() {
+        let label = format!(
+            "Note/Switch Wiki/{}",
+            name.replace('&', "&&").replace('/', "\\/")
+        );
+        let is_current = current_wiki.as_deref() == Some(name.as_str());
+        let app_state = app_state.clone();
+        let autosave_state = autosave_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        let window_geometry = window_geometry.clone();
+        menu_bar.add(&label, Shortcut::None, menu::MenuFlag::Radio, move |_| {
+            if !is_current {
+                switch_wiki(
+                    &name,
+                    &app_state,
+                    &autosave_state,
+                    &active_editor,
+                    &statusbar,
+                    &window_geometry,
+                );
+            }
+        });
+        if is_current && let Some(mut item) = menu_bar.find_item(&label) {
+            item.set();
+        }
+    }
+
     {
         #[cfg(not(target_os = "macos"))]
         let label = "Note/_Go to Index";
@@ -529,8 +859,44 @@ fn populate_menu<M>(
         );
     }
 
-    // Write Room mode (fullscreen with centered text)
+    // Read Mode (Cmd/Ctrl-Shift-E): hide the caret so plain clicks follow
+    // links without placing it; checklist markers stay clickable. The editor
+    // keeps the mode when other pages are opened, so the checkmark stays true.
     {
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        let menu_handle = menu_bar.clone();
+        menu_bar.add(
+            VIEW_READ_MODE,
+            cmd | Shortcut::Shift | 'e',
+            menu::MenuFlag::Toggle,
+            move |_| {
+                let edit_mode = with_structured_editor(&active_editor, false, |editor| {
+                    editor.toggle_edit_mode()
+                });
+                if let Some(mut item) = menu_handle.find_item(VIEW_READ_MODE) {
+                    if edit_mode == Some(false) {
+                        item.set();
+                    } else {
+                        item.clear();
+                    }
+                }
+                if let Some(edit_mode) = edit_mode {
+                    statusbar.borrow_mut().set_status(if edit_mode {
+                        "Edit mode"
+                    } else {
+                        "Read mode"
+                    });
+                }
+                app::redraw();
+            },
+        );
+    }
+
+    // Focus mode: fullscreen with a centered text column and no status or
+    // menu bar, optionally dimming all but the caret's paragraph
+    {
+        let app_state = app_state.clone();
         let wind_ref = wind_ref.clone();
         let window_geometry = window_geometry.clone();
         let active_editor = active_editor.clone();
@@ -539,10 +905,24 @@ fn populate_menu<M>(
         let on_air = on_air.clone();
         let menu_handle = menu_bar.clone();
         menu_bar.add(
-            VIEW_FULLSCREEN,
+            VIEW_FOCUS_MODE,
             fullscreen_shortcut,
             menu::MenuFlag::Toggle,
             move |_| {
+                // Focus mode is for writing in the editor, not beside it.
+                if split_view::is_active() {
+                    toggle_split_view(
+                        &app_state,
+                        &active_editor,
+                        &on_air,
+                        &search_bar,
+                        &statusbar,
+                        &wind_ref,
+                    );
+                    if let Some(mut item) = menu_handle.find_item(VIEW_SPLIT) {
+                        item.clear();
+                    }
+                }
                 toggle_fullscreen(
                     &wind_ref,
                     &window_geometry,
@@ -556,8 +936,8 @@ fn populate_menu<M>(
         );
     }
 
-    // Initialize write room menu state based on saved state
-    if let Some(mut item) = menu_bar.find_item(VIEW_FULLSCREEN) {
+    // Initialize focus mode menu state based on saved state
+    if let Some(mut item) = menu_bar.find_item(VIEW_FOCUS_MODE) {
         if window_geometry.borrow().fullscreen {
             item.set();
         } else {
@@ -565,6 +945,52 @@ fn populate_menu<M>(
         }
     }
 
+    // Split view: the note's Markdown source on the left, rendered on the
+    // right (see `split_view`). Leaves focus mode, which has no room for it.
+    {
+        let app_state = app_state.clone();
+        let wind_ref = wind_ref.clone();
+        let window_geometry = window_geometry.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        let search_bar = search_bar.clone();
+        let on_air = on_air.clone();
+        let menu_handle = menu_bar.clone();
+        menu_bar.add(
+            VIEW_SPLIT,
+            cmd | Shortcut::Shift | 'm',
+            menu::MenuFlag::Toggle,
+            move |_| {
+                if window_geometry.borrow().fullscreen {
+                    toggle_fullscreen(
+                        &wind_ref,
+                        &window_geometry,
+                        &active_editor,
+                        &statusbar,
+                        &search_bar,
+                        &on_air,
+                        &menu_handle,
+                    );
+                }
+                let split = toggle_split_view(
+                    &app_state,
+                    &active_editor,
+                    &on_air,
+                    &search_bar,
+                    &statusbar,
+                    &wind_ref,
+                );
+                if let Some(mut item) = menu_handle.find_item(VIEW_SPLIT) {
+                    if split {
+                        item.set();
+                    } else {
+                        item.clear();
+                    }
+                }
+            },
+        );
+    }
+
     // Live Note Sharing: start/stop a localhost webserver that shows the
     // currently visible note as a live-reloading HTML page (see
     // `piki_gui::live_share`). A toggle so its check-mark reflects whether the
@@ -616,6 +1042,101 @@ fn populate_menu<M>(
         );
     }
 
+    // Zoom: scale the editor's fonts, remembered per wiki with the window
+    // state. Cmd/Ctrl-0 goes back to the size set in ~/.pikirc.
+    for (label, shortcut, zoom_in) in [
+        ("View/Zoom In", cmd | '+', Some(true)),
+        ("View/Zoom Out", cmd | '-', Some(false)),
+        ("View/Actual Size", cmd | '0', None),
+    ] {
+        let app_state = app_state.clone();
+        let window_geometry = window_geometry.clone();
+        let statusbar = statusbar.clone();
+        menu_bar.add(label, shortcut, menu::MenuFlag::Normal, move |_| {
+            zoom_text(zoom_in, &app_state, &window_geometry, &statusbar);
+        });
+    }
+
+    // Color palette and link underlining, saved next to the window state.
+    for palette in Palette::ALL {
+        let label = format!("View/Color Palette/{}", palette.label());
+        menu_bar.add(&label, Shortcut::None, menu::MenuFlag::Radio, move |_| {
+            change_appearance(|appearance| appearance.palette = palette);
+        });
+        if theme::current().palette == palette
+            && let Some(mut item) = menu_bar.find_item(&label)
+        {
+            item.set();
+        }
+    }
+    menu_bar.add(
+        VIEW_UNDERLINE_LINKS,
+        Shortcut::None,
+        menu::MenuFlag::Toggle,
+        move |_| {
+            change_appearance(|appearance| {
+                appearance.underline_links = !appearance.underline_links;
+            });
+        },
+    );
+    if theme::current().underline_links
+        && let Some(mut item) = menu_bar.find_item(VIEW_UNDERLINE_LINKS)
+    {
+        item.set();
+    }
+
+    // Link Graph: the pages and their links; clicking a page opens it.
+    {
+        let app_state = app_state.clone();
+        let autosave_state = autosave_state.clone();
+        let active_editor = active_editor.clone();
+        let statusbar = statusbar.clone();
+        menu_bar.add(
+            "View/Link Graph …",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |_| {
+                let (graph, current) = {
+                    let st = app_state.borrow();
+                    (graph::build(&st.store), st.current_note.clone())
+                };
+                let graph = match graph {
+                    Ok(graph) => graph,
+                    Err(e) => {
+                        dialog::alert_default(&format!("Cannot build the link graph:\n\n{e}"));
+                        return;
+                    }
+                };
+                let app_state = app_state.clone();
+                let autosave_state = autosave_state.clone();
+                let active_editor = active_editor.clone();
+                let statusbar = statusbar.clone();
+                link_graph::show(graph, &current, move |page| {
+                    load_note_helper(
+                        &page,
+                        &app_state,
+                        &autosave_state,
+                        &active_editor,
+                        &statusbar,
+                        None,
+                        None,
+                    );
+                });
+            },
+        );
+    }
+
+    // Lock Screen: raise the privacy screen right away instead of waiting for
+    // the idle timeout (see `--lock-after`).
+    menu_bar.add(
+        "View/Lock Screen",
+        Shortcut::None,
+        menu::MenuFlag::Normal,
+        move |_| {
+            crate::privacy_screen::lock();
+        },
+    );
+
     // Format menu - paragraph styles
     {
         let active_editor = active_editor.clone();
@@ -749,6 +1270,31 @@ fn populate_menu<M>(
             },
         );
     }
+    for (label, shortcut, increase) in [
+        (FORMAT_QUOTE_DEEPER, quote_deeper_shortcut, true),
+        (FORMAT_QUOTE_SHALLOWER, quote_shallower_shortcut, false),
+    ] {
+        let active_editor = active_editor.clone();
+        let menu_handle = menu_bar.clone();
+        menu_bar.add(label, shortcut, menu::MenuFlag::Normal, move |_| {
+            let _ = with_structured_editor(&active_editor, true, |editor| {
+                editor.change_quote_depth(increase)
+            });
+            update_format_menu_state(&menu_handle, &active_editor);
+        });
+    }
+
+    // Move Paragraph Up/Down: swap the paragraph or list item at the caret
+    // with its neighbor, e.g. to resort a list.
+    for (label, shortcut, up) in [
+        (FORMAT_MOVE_UP, move_up_shortcut, true),
+        (FORMAT_MOVE_DOWN, move_down_shortcut, false),
+    ] {
+        let active_editor = active_editor.clone();
+        menu_bar.add(label, shortcut, menu::MenuFlag::Normal, move |_| {
+            let _ = with_structured_editor(&active_editor, true, |editor| editor.move_block(up));
+        });
+    }
 
     // Format menu - inline styles
     {
@@ -863,6 +1409,38 @@ fn populate_menu<M>(
         );
     }
 
+    // Insert menu - a date, typed the way it's said ("next friday") and
+    // inserted as `YYYY-MM-DD`, by default as a `@due(…)` annotation.
+    {
+        let active_editor = active_editor.clone();
+        let wind_ref = wind_ref.clone();
+        menu_bar.add(
+            INSERT_DATE,
+            insert_date_shortcut,
+            menu::MenuFlag::Normal,
+            move |_| {
+                if with_structured_editor_ref(&active_editor, |editor| !editor.is_readonly())
+                    != Some(true)
+                {
+                    return;
+                }
+                let center_rect = wind_ref
+                    .try_borrow()
+                    .ok()
+                    .map(|w| (w.x(), w.y(), w.w(), w.h()));
+                let active_editor = active_editor.clone();
+                date_picker::show_date_picker(center_rect, move |text| {
+                    if with_structured_editor(&active_editor, true, |editor| {
+                        editor.insert_text(&text)
+                    }) == Some(true)
+                    {
+                        app::redraw();
+                    }
+                });
+            },
+        );
+    }
+
     update_format_menu_state(menu_bar, &active_editor);
     register_paragraph_callback(menu_bar, &active_editor);
 }
@@ -1067,6 +1645,19 @@ fn perform_copy_section_link(
     }
 }
 
+/// Apply `change` to the editor's appearance, redraw and remember it.
+fn change_appearance(change: impl FnOnce(&mut Appearance)) {
+    let mut appearance = theme::current();
+    change(&mut appearance);
+    theme::set(appearance);
+    app::redraw();
+    if let Some(path) = window_state::appearance_file()
+        && let Err(err) = theme::save(&path, &appearance)
+    {
+        eprintln!("Failed to save appearance {}: {err}", path.display());
+    }
+}
+
 fn with_structured_editor<F, R>(
     active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
     require_writable: bool,
@@ -1169,7 +1760,11 @@ fn update_format_menu_state<M: MenuExt>(
         item.set();
     }
 
-    for &label in INLINE_ITEMS {
+    for &label in INLINE_ITEMS
+        .iter()
+        .chain(QUOTE_DEPTH_ITEMS)
+        .chain(MOVE_ITEMS)
+    {
         if let Some(mut item) = menu.find_item(label) {
             if !readonly {
                 item.activate();
@@ -1268,6 +1863,46 @@ fn show_delete_dialog(
     }
 }
 
+/// Ask where to save the currently open note as a PDF and write it there.
+fn export_pdf(
+    app_state: &Rc<RefCell<AppState>>,
+    active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
+    statusbar: &Rc<RefCell<StatusBar>>,
+) {
+    let note = app_state.borrow().current_note.clone();
+    // Like `piki export`, leave out what the [redact] section of ~/.pikirc
+    // marks as private.
+    let markdown = active_editor.borrow().borrow().get_content();
+    let rules = pikirc::PikiRc::load().redact.rules();
+    let Some(markdown) = piki_core::redact::redact(&markdown, &rules) else {
+        dialog::alert_default(&format!(
+            "{} is private: it has a tag of the [redact] section of ~/.pikirc.",
+            note
+        ));
+        return;
+    };
+    let file_name = format!("{}.pdf", note.trim_start_matches('!').replace('/', "-"));
+
+    let mut chooser = dialog::NativeFileChooser::new(dialog::NativeFileChooserType::BrowseSaveFile);
+    chooser.set_title("Export as PDF");
+    chooser.set_filter("PDF\t*.pdf");
+    chooser.set_preset_file(&file_name);
+    chooser.set_option(dialog::NativeFileChooserOptions::SaveAsConfirm);
+    chooser.show();
+    let path = chooser.filename();
+    if path.as_os_str().is_empty() {
+        return;
+    }
+
+    let pdf = piki_core::pdf::markdown_to_pdf(&note, &markdown);
+    match std::fs::write(&path, pdf) {
+        Ok(()) => statusbar
+            .borrow_mut()
+            .set_status(&format!("Exported to {}", path.display())),
+        Err(e) => dialog::alert_default(&format!("Failed to export {}: {}", note, e)),
+    }
+}
+
 /// Prompt for a new name for the currently open note and rename it in place
 /// (see [`rename_current_note`]). This is how a quick, auto-named note gets a
 /// real name, but it works on any note.
@@ -1385,26 +2020,10 @@ fn show_rename_dialog(
     let _ = input.take_focus();
 }
 
-/// Calculate padding for write room mode to achieve target text width
-fn calculate_fullscreen_padding(window_width: i32, font_size: i32) -> i32 {
-    // Approximate character width as 0.5 * font_size for proportional fonts
-    // This is a rough estimate; actual measurement would be more accurate
-    let char_width = (font_size as f32 * 0.55) as i32;
-    let target_text_width = char_width * FULLSCREEN_TARGET_CHARS;
-
-    // Scrollbar width (must match SCROLLBAR_WIDTH in fltk_structured_rich_display.rs)
-    let scrollbar_width = 15;
-    let available_width = window_width - scrollbar_width;
-
-    // Calculate padding to center the text
-    let padding = (available_width - target_text_width) / 2;
-
-    // Ensure minimum padding
-    padding.max(DEFAULT_PADDING)
-}
-
-/// Toggle fullscreen mode (fullscreen with centered text)
-fn toggle_fullscreen<M: MenuExt>(
+/// Toggle focus mode: fullscreen with a centered text column and neither
+/// status nor menu bar. On macOS the system hides the menu bar; elsewhere it
+/// collapses to nothing but keeps handling the shortcuts.
+fn toggle_fullscreen<M: MenuExt + Clone>(
     wind_ref: &Rc<RefCell<window::Window>>,
     window_geometry: &Rc<RefCell<WindowGeometry>>,
     active_editor: &Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
@@ -1436,6 +2055,7 @@ fn toggle_fullscreen<M: MenuExt>(
     } else {
         0
     };
+    let properties_height = properties_panel::height();
 
     // The ON AIR bar (when sharing) stays pinned to the top; everything below
     // is offset by its height.
@@ -1456,30 +2076,25 @@ fn toggle_fullscreen<M: MenuExt>(
             // Enter fullscreen mode
             win.fullscreen(true);
 
-            // Calculate padding for ~90 char text width
-            // Use the screen dimensions where the window is located
+            // Center the focus column, using the screen dimensions where the
+            // window is located
             let (_, _, screen_w, screen_h) = app::screen_xywh(screen_num);
-            let font_size = 14; // Default body text font size from theme
-            let padding = calculate_fullscreen_padding(screen_w, font_size);
+            let padding = theme::typography().focus_padding(screen_w);
+
+            // Collapse the menu bar; the content starts at the top.
+            #[cfg(not(target_os = "macos"))]
+            menu_handle.clone().resize(0, 0, screen_w, 0);
 
             // Keep the ON AIR bar pinned to the top if sharing.
             if on_air_visible && let Ok(mut bar) = on_air.try_borrow_mut() {
-                #[cfg(target_os = "macos")]
-                let editor_y = 0;
-                #[cfg(not(target_os = "macos"))]
-                let editor_y = 25;
-                bar.resize(0, editor_y, screen_w);
+                bar.resize(0, 0, screen_w);
             }
 
             // Resize search bar if visible
             if search_bar_visible && let Ok(mut sb) = search_bar.try_borrow_mut() {
-                // On macOS, editor_y is 0; otherwise it's 25 for menu bar
-                #[cfg(target_os = "macos")]
-                let editor_y = 0;
-                #[cfg(not(target_os = "macos"))]
-                let editor_y = 25;
-                sb.resize(0, editor_y + on_air_height, screen_w);
+                sb.resize(0, on_air_height, screen_w);
             }
+            properties_panel::resize(0, on_air_height + search_bar_height, screen_w);
 
             // Apply padding and resize the editor to take full height
             if let Ok(active_ptr) = active_editor.try_borrow()
@@ -1487,13 +2102,11 @@ fn toggle_fullscreen<M: MenuExt>(
                 && let Some(structured) = editor.as_any_mut().downcast_mut::<StructuredRichUI>()
             {
                 structured.set_horizontal_padding(padding);
+                structured.set_focus_mode(true);
                 // Expand editor to full screen height (no statusbar)
-                // Account for the ON AIR and search bars if visible
-                #[cfg(target_os = "macos")]
-                let editor_y = 0;
-                #[cfg(not(target_os = "macos"))]
-                let editor_y = 25;
-                let editor_top = editor_y + on_air_height + search_bar_height;
+                // Account for the ON AIR and search bars and the properties
+                // panel if visible
+                let editor_top = on_air_height + search_bar_height + properties_height;
                 structured.resize(0, editor_top, screen_w, screen_h - editor_top);
             }
 
@@ -1503,6 +2116,10 @@ fn toggle_fullscreen<M: MenuExt>(
             // Exit fullscreen mode
             win.fullscreen(false);
 
+            // Bring back the menu bar.
+            #[cfg(not(target_os = "macos"))]
+            menu_handle.clone().resize(0, 0, win.width(), 25);
+
             // Keep the ON AIR bar pinned to the top if sharing.
             if on_air_visible && let Ok(mut bar) = on_air.try_borrow_mut() {
                 #[cfg(target_os = "macos")]
@@ -1520,20 +2137,23 @@ fn toggle_fullscreen<M: MenuExt>(
                 let editor_y = 25;
                 sb.resize(0, editor_y + on_air_height, win.width());
             }
+            #[cfg(target_os = "macos")]
+            let properties_y = on_air_height + search_bar_height;
+            #[cfg(not(target_os = "macos"))]
+            let properties_y = 25 + on_air_height + search_bar_height;
+            properties_panel::resize(0, properties_y, win.width());
 
-            // Restore default padding and resize editor to make room for statusbar
+            // Restore normal padding and resize editor to make room for statusbar
             if let Ok(active_ptr) = active_editor.try_borrow()
                 && let Ok(mut editor) = active_ptr.try_borrow_mut()
                 && let Some(structured) = editor.as_any_mut().downcast_mut::<StructuredRichUI>()
             {
-                structured.set_horizontal_padding(DEFAULT_PADDING);
+                structured.set_horizontal_padding(theme::typography().padding(win.width()));
+                structured.set_focus_mode(false);
                 // Resize editor to window height minus statusbar
-                // Account for the ON AIR and search bars if visible
-                #[cfg(target_os = "macos")]
-                let editor_y = 0;
-                #[cfg(not(target_os = "macos"))]
-                let editor_y = 25;
-                let editor_top = editor_y + on_air_height + search_bar_height;
+                // Account for the ON AIR and search bars and the properties
+                // panel if visible
+                let editor_top = properties_y + properties_height;
                 structured.resize(
                     0,
                     editor_top,
@@ -1548,7 +2168,7 @@ fn toggle_fullscreen<M: MenuExt>(
     }
 
     // Update menu item
-    if let Some(mut item) = menu_handle.find_item(VIEW_FULLSCREEN) {
+    if let Some(mut item) = menu_handle.find_item(VIEW_FOCUS_MODE) {
         if entering_fullscreen {
             item.set();
         } else {
@@ -3,7 +3,10 @@ use std::rc::Rc;
 use std::time::SystemTime;
 
 use fltk::{self, draw, enums::Font, prelude::*, window};
+use piki_core::PageId;
+use piki_gui::fuzzy;
 use piki_gui::note_ui::NoteUI;
+use piki_gui::ui_adapters::StructuredRichUI;
 
 use crate::autosave::AutoSaveState;
 
@@ -15,6 +18,9 @@ thread_local! {
     static PICKER_OPEN: Cell<bool> = const { Cell::new(false) };
 }
 
+/// Lines of the highlighted note shown in the preview pane.
+const PREVIEW_LINES: usize = 12;
+
 /// Text size (points) used for the browser rows. Kept in sync with the font we
 /// measure against so ellipsis truncation lines up with what FLTK draws.
 const ROW_TEXT_SIZE: i32 = 14;
@@ -69,7 +75,7 @@ type StrCallback = Rc<RefCell<dyn FnMut(&str)>>;
 /// One entry in the picker list.
 struct Row {
     /// Note name / path used to open the note.
-    name: String,
+    name: PageId,
     /// Short plaintext preview parsed from the first paragraphs of the note.
     abbrev: String,
     /// Preformatted last-modification timestamp (right-hand column).
@@ -97,6 +103,53 @@ enum Hit {
     Content(String),
 }
 
+/// A line of the picker list.
+#[derive(Clone)]
+enum Choice {
+    /// Open the note in this row.
+    Open(usize),
+    /// Open the note in this row, which matched by content, at the first
+    /// match of these search terms.
+    OpenAtMatch(usize, Vec<String>),
+    /// Create a note with this name, which the query spelled out.
+    Create(String),
+}
+
+/// The name of the note to create for `query`, if it is a usable one:
+/// trimmed, without a `.md` extension, not a plugin page and not leaving the
+/// wiki directory.
+fn new_note_name(query: &str) -> Option<String> {
+    let name = query.trim();
+    let name = name.strip_suffix(".md").unwrap_or(name).trim();
+    let valid = !name.is_empty()
+        && !name.starts_with(['!', '/'])
+        && !name.contains('\\')
+        && !name.chars().any(char::is_control)
+        && name
+            .split('/')
+            .all(|part| !matches!(part.trim(), "" | "." | ".."));
+    valid.then(|| name.to_string())
+}
+
+/// The start of a note for the preview pane: its first `max_lines` lines,
+/// skipping frontmatter and leading blank lines.
+fn preview_text(markdown: &str, max_lines: usize) -> String {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let body = match lines.first() {
+        Some(first) if first.trim_end() == "---" => lines[1..]
+            .iter()
+            .position(|l| l.trim_end() == "---")
+            .map_or(&lines[..], |end| &lines[end + 2..]),
+        _ => &lines[..],
+    };
+    body.iter()
+        .skip_while(|l| l.trim().is_empty())
+        .take(max_lines)
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 /// Parse the first few paragraphs of a markdown note into a one-line plaintext
 /// preview: markdown syntax is stripped and whitespace collapsed. The result is
 /// capped at `max_chars`; the picker adds an ellipsis when it still overflows
@@ -238,45 +291,6 @@ fn recency_order(rows: &[Row]) -> Vec<usize> {
     order
 }
 
-// Simple fuzzy match: subsequence match with light scoring.
-fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
-    if query.is_empty() {
-        return Some(0);
-    }
-    let mut score = 0i32;
-    let mut qi = 0usize;
-    let q = query.to_lowercase();
-    let c = candidate.to_lowercase();
-    let qb = q.as_bytes();
-    let cb = c.as_bytes();
-    for (i, &ch) in cb.iter().enumerate() {
-        if qi < qb.len() && ch == qb[qi] {
-            // Reward matches earlier and consecutive
-            score += 10 - ((i as i32).min(9));
-            // Bonus for start of word or after '/'
-            if i == 0 || cb.get(i - 1) == Some(&b'/') {
-                score += 5;
-            }
-            qi += 1;
-            if qi == qb.len() {
-                break;
-            }
-        }
-    }
-    if qi == qb.len() {
-        // Prefer prefix and exact
-        if c.starts_with(&q) {
-            score += 20;
-        }
-        if c == q {
-            score += 50;
-        }
-        Some(score)
-    } else {
-        None
-    }
-}
-
 /// Next 1-based selection when stepping the quick-open cycle with the modifier
 /// held. `cur` and `sz` are 1-based; the selection wraps around both ends.
 /// Returns 0 for an empty list.
@@ -311,7 +325,7 @@ fn search_order(rows: &[Row], query: &str) -> Vec<(usize, Hit)> {
     let mut name_hits: Vec<(i32, usize)> = Vec::new();
     let mut content_hits: Vec<(usize, String)> = Vec::new();
     for (i, row) in rows.iter().enumerate() {
-        if let Some(score) = fuzzy_score(query, &row.name) {
+        if let Some(score) = fuzzy::score(query, &row.name) {
             name_hits.push((score, i));
         } else if !terms.is_empty()
             && piki_core::search::contains_all_terms(&row.content_lower, &terms)
@@ -348,8 +362,10 @@ fn search_order(rows: &[Row], query: &str) -> Vec<(usize, Hit)> {
     order
 }
 
-/// Modal "Open Note" picker: fuzzy filtering, recency ordering, previews and
-/// last-modified timestamps, with keyboard navigation.
+/// Modal "Open Note" picker, doubling as the quick-switcher: fuzzy filtering,
+/// recency ordering, previews and last-modified timestamps, with keyboard
+/// navigation. The highlighted note's first lines show below the list, and a
+/// query naming no existing note offers to create it.
 pub fn show_note_picker(
     app_state: Rc<RefCell<super::AppState>>,
     autosave_state: Rc<RefCell<AutoSaveState>>,
@@ -361,6 +377,7 @@ pub fn show_note_picker(
         browser::HoldBrowser,
         enums::{CallbackTrigger, Event, Key, Shortcut},
         input::Input,
+        text::{TextBuffer, TextDisplay},
         window::Window,
     };
 
@@ -390,7 +407,7 @@ pub fn show_note_picker(
                     modified: mtime.and_then(millis_since_epoch),
                     content_lower: content.to_lowercase(),
                     content,
-                    name,
+                    name: name.into(),
                 }
             })
             .collect();
@@ -400,7 +417,8 @@ pub fn show_note_picker(
 
     // Create a modal dialog centered on parent
     let width = 600;
-    let height = 460;
+    let height = 600;
+    let preview_h = 170;
     let px = parent.x() + (parent.w() - width) / 2;
     let py = parent.y() + (parent.h() - height) / 2;
     let mut win = Window::new(px.max(0), py.max(0), width, height, Some("Open Note"));
@@ -408,10 +426,16 @@ pub fn show_note_picker(
     win.make_modal(true);
 
     let mut input = Input::new(10, 10, width - 20, 28, None);
-    let mut list = HoldBrowser::new(10, 50, width - 20, height - 60, None);
+    let mut list = HoldBrowser::new(10, 50, width - 20, height - preview_h - 70, None);
     list.set_scrollbar_size(12);
     list.set_text_size(ROW_TEXT_SIZE);
 
+    let mut preview = TextDisplay::new(10, height - preview_h - 10, width - 20, preview_h, None);
+    preview.set_buffer(TextBuffer::default());
+    preview.set_text_size(ROW_TEXT_SIZE - 2);
+    preview.wrap_mode(fltk::text::WrapMode::AtBounds, 0);
+    preview.set_scrollbar_size(12);
+
     // Measure with the same font the browser draws in (default FLTK sans at our
     // row size) so ellipsis truncation matches on screen.
     draw::set_font(Font::Helvetica, ROW_TEXT_SIZE);
@@ -458,10 +482,34 @@ pub fn show_note_picker(
         }))
     };
 
-    // Note names in current display order, parallel to the browser lines. The
-    // browser text is formatted (columns + preview), so accepting a selection
-    // maps the 1-based line back to a name through this list.
-    let results: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(Vec::new()));
+    // What each browser line stands for, in display order. The browser text is
+    // formatted (columns + preview), so accepting a selection maps the 1-based
+    // line back to a note through this list.
+    let results: Rc<RefCell<Vec<Choice>>> = Rc::new(RefCell::new(Vec::new()));
+
+    // Show the start of the note on a (1-based) line in the preview pane.
+    let show_preview: Rc<dyn Fn(i32)> = {
+        let preview = preview.clone();
+        let rows = rows.clone();
+        let results = results.clone();
+        Rc::new(move |line: i32| {
+            let choice = usize::try_from(line - 1)
+                .ok()
+                .and_then(|i| results.borrow().get(i).cloned());
+            let text = match choice {
+                Some(Choice::Open(i) | Choice::OpenAtMatch(i, _)) => {
+                    preview_text(&rows[i].content, PREVIEW_LINES)
+                }
+                Some(Choice::Create(name)) => format!("Press Enter to create “{name}”."),
+                None => String::new(),
+            };
+            let mut preview = preview.clone();
+            if let Some(mut buffer) = preview.buffer() {
+                buffer.set_text(&text);
+            }
+            preview.scroll(0, 0);
+        })
+    };
 
     // Rebuild the list for a query: recency order when empty, fuzzy otherwise.
     // With an empty query we pre-select the *current* note (the top of the
@@ -471,48 +519,80 @@ pub fn show_note_picker(
         let rows = rows.clone();
         let results = results.clone();
         let current_note = current_note.clone();
+        let show_preview = show_preview.clone();
         Rc::new(RefCell::new(move |query: &str| {
             draw::set_font(Font::Helvetica, ROW_TEXT_SIZE);
             let q = query.trim();
 
             list.clear();
-            let mut names = Vec::new();
+            let mut choices = Vec::new();
             if q.is_empty() {
                 // Empty query: recency order with each note's generic preview
                 // (unchanged quick-open behaviour).
                 for &i in &recency_order(&rows) {
                     let row = &rows[i];
                     list.add(&browser_line(&row.name, &row.abbrev, &row.date, left_avail));
-                    names.push(row.name.clone());
+                    choices.push(Choice::Open(i));
                 }
             } else {
                 // Non-empty: name hits (generic preview) then full-text content
                 // hits (matching-line snippet).
-                for (i, hit) in search_order(&rows, q) {
+                let order = search_order(&rows, q);
+                // A query that isn't the name of a note can create one: as the
+                // first line when no name matches (so Enter creates it), else
+                // below the matches.
+                let create = new_note_name(q).filter(|name| {
+                    !rows
+                        .iter()
+                        .any(|row| row.name.as_str().eq_ignore_ascii_case(name))
+                });
+                let name_hit = order.iter().any(|(_, hit)| matches!(hit, Hit::Name));
+                let create_line = |list: &mut HoldBrowser, choices: &mut Vec<Choice>| {
+                    if let Some(name) = &create {
+                        list.add(&format!("@i{}", escape(&format!("Create “{name}”"))));
+                        choices.push(Choice::Create(name.clone()));
+                    }
+                };
+                if !name_hit {
+                    create_line(&mut list, &mut choices);
+                }
+                for (i, hit) in order {
                     let row = &rows[i];
-                    let preview = match &hit {
-                        Hit::Name => row.abbrev.as_str(),
-                        Hit::Content(snippet) => snippet.as_str(),
+                    let (preview, choice) = match &hit {
+                        Hit::Name => (row.abbrev.as_str(), Choice::Open(i)),
+                        Hit::Content(snippet) => (
+                            snippet.as_str(),
+                            Choice::OpenAtMatch(i, piki_core::search::parse_terms(q)),
+                        ),
                     };
                     list.add(&browser_line(&row.name, preview, &row.date, left_avail));
-                    names.push(row.name.clone());
+                    choices.push(choice);
+                }
+                if name_hit {
+                    create_line(&mut list, &mut choices);
                 }
             }
 
-            if !names.is_empty() {
+            let mut line = 0;
+            if !choices.is_empty() {
                 let target = if q.is_empty() {
                     Some(current_note.as_str())
                 } else {
                     None
                 };
-                let line = target
-                    .and_then(|t| names.iter().position(|n| n == t))
+                line = target
+                    .and_then(|t| {
+                        choices
+                            .iter()
+                            .position(|c| matches!(c, Choice::Open(i) if rows[*i].name == t))
+                    })
                     .map(|p| p as i32 + 1)
                     .unwrap_or(1);
                 list.select(line);
                 list.top_line(1);
             }
-            *results.borrow_mut() = names;
+            *results.borrow_mut() = choices;
+            show_preview(line);
         }))
     };
 
@@ -532,6 +612,7 @@ pub fn show_note_picker(
     // (restoring the menu), then loads the note.
     let accept_cb: Rc<RefCell<dyn FnMut()>> = {
         let list = list.clone();
+        let rows = rows.clone();
         let results = results.clone();
         let app_state = app_state.clone();
         let autosave_state = autosave_state.clone();
@@ -540,9 +621,18 @@ pub fn show_note_picker(
         let close_picker = close_picker.clone();
         Rc::new(RefCell::new(move || {
             let idx = list.value(); // 1-based
-            if idx > 0
-                && let Some(name) = results.borrow().get((idx - 1) as usize).cloned()
-            {
+            let choice = usize::try_from(idx - 1)
+                .ok()
+                .and_then(|i| results.borrow().get(i).cloned());
+            let (name, terms) = match choice {
+                Some(Choice::Open(i)) => (Some(rows[i].name.as_str().to_string()), Vec::new()),
+                Some(Choice::OpenAtMatch(i, terms)) => {
+                    (Some(rows[i].name.as_str().to_string()), terms)
+                }
+                Some(Choice::Create(name)) => (Some(name), Vec::new()),
+                None => (None, Vec::new()),
+            };
+            if let Some(name) = name {
                 (close_picker.borrow_mut())();
                 super::load_note_helper(
                     &name,
@@ -553,6 +643,15 @@ pub fn show_note_picker(
                     None,
                     None,
                 );
+                // A content hit opens where the query matched, highlighted.
+                if !terms.is_empty() {
+                    let editor = active_editor.borrow().clone();
+                    if let Ok(mut ed) = editor.try_borrow_mut()
+                        && let Some(structured) = ed.as_any_mut().downcast_mut::<StructuredRichUI>()
+                    {
+                        structured.show_first_match(&terms);
+                    }
+                }
             }
         }))
     };
@@ -570,6 +669,7 @@ pub fn show_note_picker(
         let mut list = list.clone();
         let accept_cb = accept_cb.clone();
         let close_picker = close_picker.clone();
+        let show_preview = show_preview.clone();
         // Set once the user taps the hotkey again while the modifier is held; a
         // subsequent modifier release then commits the selection. Left false in
         // the type/arrow flows so releasing the modifier does nothing there.
@@ -587,6 +687,7 @@ pub fn show_note_picker(
                         let next = cycle_index(list.value(), sz, state.contains(Shortcut::Shift));
                         list.select(next);
                         list.make_visible(next);
+                        show_preview(next);
                         navigating = true;
                     }
                     return true;
@@ -600,6 +701,7 @@ pub fn show_note_picker(
                             let next = (cur + 1).min(sz);
                             list.select(next);
                             list.top_line(next);
+                            show_preview(next);
                         }
                         true
                     }
@@ -610,6 +712,7 @@ pub fn show_note_picker(
                             let prev = (cur - 1).max(1);
                             list.select(prev);
                             list.top_line(prev);
+                            show_preview(prev);
                         }
                         true
                     }
@@ -647,6 +750,12 @@ pub fn show_note_picker(
         });
     }
 
+    // Clicking a line previews it.
+    {
+        let show_preview = show_preview.clone();
+        list.set_callback(move |list| show_preview(list.value()));
+    }
+
     // Double-click or Enter on the list accepts; Escape cancels.
     {
         let accept_cb = accept_cb.clone();
@@ -726,10 +835,27 @@ mod tests {
         assert_eq!(escape("a@b\tc"), "a@@b c");
     }
 
+    #[test]
+    fn new_note_name_rejects_unusable_names() {
+        assert_eq!(new_note_name("  ideas/next.md "), Some("ideas/next".into()));
+        assert_eq!(new_note_name("!index"), None);
+        assert_eq!(new_note_name("../outside"), None);
+        assert_eq!(new_note_name("a//b"), None);
+        assert_eq!(new_note_name("   "), None);
+    }
+
+    #[test]
+    fn preview_text_skips_frontmatter() {
+        let md = "---\ntitle: T\n---\n\n# Heading\nline 1\nline 2\n";
+        assert_eq!(preview_text(md, 2), "# Heading\nline 1");
+        // An unclosed `---` is just a thematic break.
+        assert_eq!(preview_text("---\ntext", 5), "---\ntext");
+    }
+
     /// A minimal row for exercising [`search_order`] — only name/content matter.
     fn row(name: &str, content: &str) -> Row {
         Row {
-            name: name.to_string(),
+            name: PageId::new(name),
             abbrev: String::new(),
             date: String::new(),
             last_open: None,
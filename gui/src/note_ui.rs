@@ -3,6 +3,7 @@ use fltk::{enums::Color, window};
 use rutle::structured_document::BlockType;
 use rutle::tree_path::DocumentPosition;
 use std::any::Any;
+use std::path::PathBuf;
 
 /// A minimal UI abstraction layer for a note editor/viewer.
 ///
@@ -40,6 +41,13 @@ pub trait NoteUI: ContentProvider + ContentLoader + 'static {
     // and None when not hovering any link. Default no-op.
     fn on_link_hover(&mut self, _f: Box<dyn Fn(Option<String>) + 'static>) {}
 
+    // Install handler for files dropped onto the widget. Default no-op.
+    fn on_files_dropped(&mut self, _f: Box<dyn FnMut(Vec<PathBuf>) + 'static>) {}
+
+    // Install handler for checkboxes clicked on a read-only page, called with
+    // the link destinations in the checkbox's item. Default no-op.
+    fn on_readonly_checkbox(&mut self, _f: Box<dyn FnMut(Vec<String>) + 'static>) {}
+
     // Optional restyle hook (no-op by default).
     fn restyle(&mut self) {}
 
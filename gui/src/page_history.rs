@@ -0,0 +1,203 @@
+//! "Page History" dialog: browse the git commits touching the open note, view
+//! any old version read-only, compare it with the editor's in the diff tool
+//! set in `~/.pikirc`, and restore it into the editor.
+//!
+//! Only useful when the notes directory is a git repository; otherwise the
+//! dialog is not shown and the git error is reported instead. A restored
+//! version simply replaces the editor content and is written to disk by the
+//! regular autosave.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use fltk::{
+    app, browser, button, dialog,
+    enums::{Align, Event, Font, Key},
+    frame,
+    prelude::*,
+    text, window,
+};
+use piki_core::{ensure_md_extension, git};
+use piki_gui::note_ui::NoteUI;
+
+use crate::autosave::AutoSaveState;
+use crate::pikirc::PikiRc;
+use crate::statusbar::StatusBar;
+
+const DIALOG_W: i32 = 720;
+const DIALOG_H: i32 = 460;
+const LIST_W: i32 = 260;
+
+/// Show the history of the currently open note, centered over `parent`.
+pub fn show_page_history(
+    app_state: Rc<RefCell<super::AppState>>,
+    autosave_state: Rc<RefCell<AutoSaveState>>,
+    active_editor: Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
+    statusbar: Rc<RefCell<StatusBar>>,
+    parent: &window::Window,
+) {
+    let (note, dir) = {
+        let state = app_state.borrow();
+        (
+            state.current_note.clone(),
+            state.store.base_path().to_path_buf(),
+        )
+    };
+
+    if note.starts_with('!') {
+        dialog::alert_default("This note has no history.");
+        return;
+    }
+
+    let commits = match git::file_history(&dir, &ensure_md_extension(&note)) {
+        Ok(commits) if commits.is_empty() => {
+            dialog::alert_default(&format!("“{note}” has not been committed to git yet."));
+            return;
+        }
+        Ok(commits) => Rc::new(commits),
+        Err(e) => {
+            dialog::alert_default(&format!("Cannot read the history of “{note}”:\n\n{e}"));
+            return;
+        }
+    };
+
+    let x = parent.x() + (parent.w() - DIALOG_W) / 2;
+    let y = parent.y() + (parent.h() - DIALOG_H) / 2;
+    let mut win = window::Window::new(x.max(0), y.max(0), DIALOG_W, DIALOG_H, Some("Page History"));
+    win.make_modal(true);
+    win.begin();
+
+    let mut list = browser::HoldBrowser::new(10, 10, LIST_W, DIALOG_H - 60, None);
+    for commit in commits.iter() {
+        list.add(&format!("{}  {}", commit.date, commit.subject));
+    }
+
+    let mut preview_buf = text::TextBuffer::default();
+    let mut preview =
+        text::TextDisplay::new(LIST_W + 20, 10, DIALOG_W - LIST_W - 30, DIALOG_H - 60, None);
+    preview.set_buffer(preview_buf.clone());
+    preview.set_text_font(Font::Courier);
+    preview.wrap_mode(text::WrapMode::AtBounds, 0);
+
+    let mut info = frame::Frame::new(10, DIALOG_H - 40, DIALOG_W - 290, 30, None);
+    info.set_align(Align::Inside | Align::Left);
+
+    let diff_tool = PikiRc::load().diff.tool();
+    let mut compare_btn =
+        button::Button::new(DIALOG_W - 270, DIALOG_H - 40, 80, 30, Some("Compare"));
+    let tooltip = match &diff_tool {
+        Some(tool) => format!("Compare with the editor's version in {}", tool.name()),
+        None => "Set a diff tool in the [diff] section of ~/.pikirc to compare versions".into(),
+    };
+    compare_btn.set_tooltip(&tooltip);
+    compare_btn.deactivate();
+    let mut close_btn = button::Button::new(DIALOG_W - 180, DIALOG_H - 40, 80, 30, Some("Close"));
+    let mut restore_btn =
+        button::Button::new(DIALOG_W - 90, DIALOG_H - 40, 80, 30, Some("Restore"));
+    restore_btn.deactivate();
+
+    win.end();
+
+    // The content of the selected version, once loaded.
+    let selected: Rc<RefCell<Option<(usize, String)>>> = Rc::new(RefCell::new(None));
+
+    {
+        let commits = commits.clone();
+        let selected = selected.clone();
+        let mut restore_btn = restore_btn.clone();
+        let mut compare_btn = compare_btn.clone();
+        let can_compare = diff_tool.is_some();
+        let mut info = info.clone();
+        list.set_callback(move |b| {
+            // Browser lines are 1-based; 0 means nothing is selected.
+            let Some(index) = (b.value() as usize).checked_sub(1) else {
+                return;
+            };
+            let commit = &commits[index];
+            match git::file_at_commit(&dir, commit) {
+                Ok(content) => {
+                    preview_buf.set_text(&content);
+                    info.set_label(&format!("Version of {}", commit.date));
+                    *selected.borrow_mut() = Some((index, content));
+                    restore_btn.activate();
+                    if can_compare {
+                        compare_btn.activate();
+                    }
+                }
+                Err(e) => {
+                    preview_buf.set_text("");
+                    info.set_label(&e);
+                    *selected.borrow_mut() = None;
+                    restore_btn.deactivate();
+                    compare_btn.deactivate();
+                }
+            }
+        });
+    }
+
+    if let Some(tool) = diff_tool {
+        let commits = commits.clone();
+        let selected = selected.clone();
+        let active_editor = active_editor.clone();
+        let note = note.clone();
+        let mut info = info.clone();
+        compare_btn.set_callback(move |_| {
+            let Some((index, content)) = selected.borrow().clone() else {
+                return;
+            };
+            let current = active_editor.borrow().borrow().get_content();
+            let old = (commits[index].date.as_str(), content.as_str());
+            if let Err(e) = tool.compare_in_background(&note, old, ("current", &current)) {
+                info.set_label(&e);
+            }
+        });
+    }
+
+    {
+        let mut win = win.clone();
+        restore_btn.set_callback(move |_| {
+            let Some((index, content)) = selected.borrow_mut().take() else {
+                return;
+            };
+            // The dialog is modal, but guard against the note having changed
+            // underneath it anyway.
+            if app_state.borrow().current_note != note {
+                win.hide();
+                return;
+            }
+            {
+                let active = active_editor.borrow();
+                active.borrow_mut().set_content_from_markdown(&content);
+            }
+            autosave_state.borrow_mut().mark_changed();
+            statusbar
+                .borrow_mut()
+                .set_status(&format!("Restored version of {}", commits[index].date));
+            win.hide();
+            app::redraw();
+        });
+    }
+
+    {
+        let mut win = win.clone();
+        close_btn.set_callback(move |_| win.hide());
+    }
+
+    {
+        let mut close_btn = close_btn.clone();
+        win.handle(move |_, ev| {
+            if ev == Event::KeyDown && app::event_key() == Key::Escape {
+                close_btn.do_callback();
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    win.show();
+    // Show the newest version right away.
+    list.select(1);
+    list.do_callback();
+    let _ = list.take_focus();
+}
@@ -0,0 +1,303 @@
+//! The user's settings in `~/.pikirc`, the file the GUI shares with the CLI.
+//!
+//! The GUI reads the `[diff]` section, naming the tool used to compare and
+//! merge versions of a note (see [`piki_core::diff_tool`]), and the `[sync]`
+//! section, which turns on syncing the notes with their git remote
+//! periodically (see [`piki_core::git::sync`]), the `[wikis]` section
+//! listing the wikis offered by Note → Switch Wiki (see [`piki_core::wikis`]),
+//! the `[editor]` section with the editor's fonts and text column (see
+//! [`Typography`]), the `[gui]` section with the autosave delay, the
+//! default start page, how long to wait before saving the window's place and
+//! the passphrase of the privacy screen,
+//! the `[links]` section, shared with `piki view`, saying whether to ask
+//! before opening web links, and the `[redact]` section, shared with
+//! `piki export`, saying what to leave out of shared and exported notes (see
+//! [`piki_core::redact`]); the other sections belong to the CLI and are
+//! ignored:
+//!
+//! ```toml
+//! [diff]
+//! tool = "meld"
+//!
+//! [sync]
+//! interval = 15 # minutes
+//! commit = "Sync from piki"
+//!
+//! [wikis]
+//! work = "~/Documents/work-notes"
+//!
+//! [editor]
+//! font = "Georgia"
+//! font_size = 16
+//!
+//! [gui]
+//! autosave = 5 # seconds
+//! start_page = "journal"
+//!
+//! [links]
+//! confirm_external = false
+//!
+//! [redact]
+//! exclude_tags = ["diary"]
+//! private_tag = "secret"
+//! ```
+//!
+//! The file is read whenever a setting is needed, so changes apply without a
+//! restart; the editor's appearance changes as soon as the file is saved.
+
+use piki_core::diff_tool::DiffTool;
+use piki_core::redact::RedactionRules;
+use piki_gui::theme::Typography;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PikiRc {
+    #[serde(default)]
+    pub diff: DiffSettings,
+    #[serde(default)]
+    pub sync: SyncSettings,
+    /// Notes directories by name, shared with `piki -w`.
+    #[serde(default)]
+    pub wikis: BTreeMap<String, String>,
+    #[serde(default)]
+    pub editor: Typography,
+    #[serde(default)]
+    pub gui: GuiSettings,
+    #[serde(default)]
+    pub links: LinkSettings,
+    #[serde(default)]
+    pub redact: RedactSettings,
+}
+
+/// Seconds between the last change to a note and saving it.
+pub const DEFAULT_AUTOSAVE_SECS: f64 = 10.0;
+/// Seconds after moving or resizing the window before its place is saved.
+pub const DEFAULT_WINDOW_STATE_DELAY_SECS: f64 = 3.0;
+
+/// The `[gui]` table.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct GuiSettings {
+    /// Seconds between the last change to a note and saving it.
+    #[serde(default)]
+    autosave: Option<f64>,
+    /// Start page of wikis whose `.piki.toml` doesn't name one.
+    #[serde(default)]
+    pub start_page: Option<String>,
+    /// Seconds after moving or resizing the window before its place is saved.
+    #[serde(default)]
+    window_state_delay: Option<f64>,
+    /// Passphrase that unlocks the privacy screen; any key does if unset.
+    #[serde(default)]
+    lock_passphrase: Option<String>,
+}
+
+impl GuiSettings {
+    pub fn autosave_secs(&self) -> f64 {
+        seconds(self.autosave, DEFAULT_AUTOSAVE_SECS)
+    }
+
+    pub fn window_state_delay_secs(&self) -> f64 {
+        seconds(self.window_state_delay, DEFAULT_WINDOW_STATE_DELAY_SECS)
+    }
+
+    /// The privacy screen's passphrase, if one is set and not empty.
+    pub fn lock_passphrase(&self) -> Option<String> {
+        self.lock_passphrase.clone().filter(|p| !p.is_empty())
+    }
+}
+
+/// A delay from the settings, at least half a second; `default` if unset or
+/// not a positive number.
+fn seconds(setting: Option<f64>, default: f64) -> f64 {
+    setting
+        .filter(|secs| secs.is_finite() && *secs > 0.0)
+        .map_or(default, |secs| secs.max(0.5))
+}
+
+/// The `[links]` table, shared with `piki view`.
+#[derive(Debug, Default, Deserialize)]
+pub struct LinkSettings {
+    /// Whether to ask before opening a web link (default: yes).
+    #[serde(default)]
+    confirm_external: Option<bool>,
+}
+
+impl LinkSettings {
+    pub fn confirm_external(&self) -> bool {
+        self.confirm_external.unwrap_or(true)
+    }
+}
+
+/// The `[redact]` table, shared with `piki export`.
+#[derive(Debug, Default, Deserialize)]
+pub struct RedactSettings {
+    /// Notes with any of these tags are neither shared nor exported.
+    #[serde(default)]
+    exclude_tags: Vec<String>,
+    /// Blocks with this tag are left out (default: `private`).
+    #[serde(default)]
+    private_tag: Option<String>,
+}
+
+impl RedactSettings {
+    pub fn rules(&self) -> RedactionRules {
+        let defaults = RedactionRules::default();
+        RedactionRules {
+            exclude_tags: self.exclude_tags.clone(),
+            private_tag: self.private_tag.clone().unwrap_or(defaults.private_tag),
+        }
+    }
+}
+
+/// The `[diff]` table.
+#[derive(Debug, Default, Deserialize)]
+pub struct DiffSettings {
+    /// meld, kdiff3, code or a command line.
+    #[serde(default)]
+    tool: Option<String>,
+    /// Command line for merges, for tools given as a command line.
+    #[serde(default)]
+    merge: Option<String>,
+}
+
+impl DiffSettings {
+    /// The configured tool, if any. A broken setting is reported and ignored.
+    pub fn tool(&self) -> Option<DiffTool> {
+        let tool = self.tool.as_deref()?;
+        DiffTool::new(tool, self.merge.as_deref())
+            .inspect_err(|err| eprintln!("Ignoring the diff tool in ~/.pikirc: {err}"))
+            .ok()
+    }
+}
+
+/// The `[sync]` table, shared with `piki sync`.
+#[derive(Debug, Default, Deserialize)]
+pub struct SyncSettings {
+    /// Minutes between automatic syncs; none if unset or zero.
+    #[serde(default)]
+    interval: Option<u64>,
+    /// Message to commit changed notes with before syncing.
+    #[serde(default)]
+    pub commit: Option<String>,
+}
+
+impl SyncSettings {
+    /// The time between automatic syncs, if they are turned on.
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval
+            .filter(|&minutes| minutes > 0)
+            .map(|minutes| Duration::from_secs(minutes * 60))
+    }
+}
+
+impl PikiRc {
+    /// Load `~/.pikirc`. A missing file gives the defaults; a broken one is
+    /// reported and gives the defaults, too.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        Self::parse(&contents).unwrap_or_else(|err| {
+            eprintln!("Failed to parse {}: {err}", path.display());
+            Self::default()
+        })
+    }
+
+    /// When `~/.pikirc` was last changed, if it exists.
+    pub fn modified() -> Option<SystemTime> {
+        fs::metadata(Self::path()?).ok()?.modified().ok()
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join(".pikirc"))
+    }
+
+    fn parse(contents: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_diff_tool_and_ignores_cli_settings() {
+        let rc = PikiRc::parse(
+            "[aliases]\nt = \"todo\"\n\n[export]\nwrap = 72\n\n[diff]\ntool = \"kdiff3\"\n",
+        )
+        .unwrap();
+        let tool = rc.diff.tool().unwrap();
+        assert_eq!(tool.name(), "kdiff3");
+        assert!(tool.can_merge());
+
+        assert!(PikiRc::parse("").unwrap().diff.tool().is_none());
+        let custom = PikiRc::parse("[diff]\ntool = \"opendiff $LOCAL $REMOTE\"\n").unwrap();
+        assert!(!custom.diff.tool().unwrap().can_merge());
+    }
+
+    #[test]
+    fn reads_the_sync_interval() {
+        let rc = PikiRc::parse("[sync]\ninterval = 15\ncommit = \"Sync\"\n").unwrap();
+        assert_eq!(rc.sync.interval(), Some(Duration::from_secs(900)));
+        assert_eq!(rc.sync.commit.as_deref(), Some("Sync"));
+        assert!(
+            PikiRc::parse("[sync]\ninterval = 0\n")
+                .unwrap()
+                .sync
+                .interval()
+                .is_none()
+        );
+        assert!(PikiRc::parse("").unwrap().sync.interval().is_none());
+    }
+
+    #[test]
+    fn reads_the_wikis() {
+        let rc = PikiRc::parse("[wikis]\nwork = \"~/work\"\nhome = \"/srv/home\"\n").unwrap();
+        let names: Vec<&str> = rc.wikis.keys().map(String::as_str).collect();
+        assert_eq!(names, ["home", "work"]);
+        assert!(PikiRc::parse("").unwrap().wikis.is_empty());
+    }
+
+    #[test]
+    fn asks_before_opening_web_links_unless_told_not_to() {
+        assert!(PikiRc::parse("").unwrap().links.confirm_external());
+        let rc = PikiRc::parse("[links]\nconfirm_external = false\n").unwrap();
+        assert!(!rc.links.confirm_external());
+    }
+
+    #[test]
+    fn reads_the_editor_appearance() {
+        let rc = PikiRc::parse("[editor]\nfont = \"Georgia\"\nmax_width = 700\n").unwrap();
+        assert_eq!(rc.editor.font.as_deref(), Some("Georgia"));
+        assert_eq!(rc.editor.max_width, Some(700));
+        assert_eq!(rc.editor.font_size, Typography::default().font_size);
+        assert_eq!(PikiRc::parse("").unwrap().editor, Typography::default());
+    }
+
+    #[test]
+    fn reads_the_gui_timing_and_start_page() {
+        let rc = PikiRc::parse(
+            "[gui]\nautosave = 2.5\nstart_page = \"journal\"\nwindow_state_delay = 0.1\n",
+        )
+        .unwrap();
+        assert_eq!(rc.gui.autosave_secs(), 2.5);
+        assert_eq!(rc.gui.start_page.as_deref(), Some("journal"));
+        assert_eq!(rc.gui.window_state_delay_secs(), 0.5);
+
+        let defaults = PikiRc::parse("[gui]\nautosave = 0\n").unwrap().gui;
+        assert_eq!(defaults.autosave_secs(), DEFAULT_AUTOSAVE_SECS);
+        assert_eq!(
+            defaults.window_state_delay_secs(),
+            DEFAULT_WINDOW_STATE_DELAY_SECS
+        );
+    }
+}
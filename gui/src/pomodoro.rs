@@ -0,0 +1,119 @@
+//! Pomodoro timer for tracking time spent on a note.
+//!
+//! A session is started against the open note (Note → Start Pomodoro) and
+//! counts down in the status bar. When it runs out — or is stopped early — the
+//! time worked is logged as an `@worked(date, duration)` entry (see
+//! [`piki_core::timesheet`]), either into the note itself or into the note
+//! given with `--timesheet`.
+//!
+//! Like the privacy screen, the running session lives in a thread-local so the
+//! menu, the status bar and the periodic timer can reach it without threading
+//! a handle through all of them. Everything here runs on the FLTK main thread.
+
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+/// Length of one session.
+pub const SESSION_LENGTH: Duration = Duration::from_secs(25 * 60);
+
+thread_local! {
+    static SESSION: RefCell<Option<Session>> = const { RefCell::new(None) };
+    /// Note that collects all entries (`--timesheet`); `None` logs into the
+    /// note worked on.
+    static TIMESHEET_NOTE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+struct Session {
+    note: String,
+    started: Instant,
+}
+
+/// A session that ended, to be logged.
+pub struct FinishedSession {
+    pub note: String,
+    pub minutes: u32,
+}
+
+/// Log all sessions into `note` instead of the note worked on.
+pub fn set_timesheet_note(note: Option<String>) {
+    TIMESHEET_NOTE.with(|t| *t.borrow_mut() = note);
+}
+
+pub fn timesheet_note() -> Option<String> {
+    TIMESHEET_NOTE.with(|t| t.borrow().clone())
+}
+
+/// Start a session for `note`, replacing any running one.
+pub fn start(note: &str) {
+    SESSION.with(|s| {
+        *s.borrow_mut() = Some(Session {
+            note: note.to_string(),
+            started: Instant::now(),
+        })
+    });
+}
+
+pub fn is_running() -> bool {
+    SESSION.with(|s| s.borrow().is_some())
+}
+
+/// End the running session early.
+pub fn stop() -> Option<FinishedSession> {
+    SESSION
+        .with(|s| s.borrow_mut().take())
+        .map(|session| FinishedSession {
+            minutes: worked_minutes(session.started.elapsed()),
+            note: session.note,
+        })
+}
+
+/// End the running session if its time is up.
+pub fn take_finished() -> Option<FinishedSession> {
+    let done = SESSION.with(|s| {
+        s.borrow()
+            .as_ref()
+            .is_some_and(|session| session.started.elapsed() >= SESSION_LENGTH)
+    });
+    if done { stop() } else { None }
+}
+
+/// Status bar text for the running session, e.g. `🍅 12:34 · project`.
+pub fn status_text() -> Option<String> {
+    SESSION.with(|s| {
+        s.borrow().as_ref().map(|session| {
+            let remaining = SESSION_LENGTH.saturating_sub(session.started.elapsed());
+            format!("🍅 {} · {}", format_remaining(remaining), session.note)
+        })
+    })
+}
+
+/// Whole minutes worked, rounded to the nearest minute and capped at the
+/// session length.
+fn worked_minutes(elapsed: Duration) -> u32 {
+    let elapsed = elapsed.min(SESSION_LENGTH);
+    ((elapsed.as_secs() + 30) / 60) as u32
+}
+
+/// `mm:ss`
+fn format_remaining(remaining: Duration) -> String {
+    let secs = remaining.as_secs();
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worked_minutes_rounds_and_caps() {
+        assert_eq!(worked_minutes(Duration::from_secs(29)), 0);
+        assert_eq!(worked_minutes(Duration::from_secs(12 * 60 + 40)), 13);
+        assert_eq!(worked_minutes(Duration::from_secs(3 * 3600)), 25);
+    }
+
+    #[test]
+    fn remaining_time_is_formatted_as_minutes_and_seconds() {
+        assert_eq!(format_remaining(SESSION_LENGTH), "25:00");
+        assert_eq!(format_remaining(Duration::from_secs(61)), "01:01");
+    }
+}
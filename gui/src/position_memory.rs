@@ -6,6 +6,7 @@
 //! were instead of jumping to the top with the caret reset. This is
 //! deliberately not persisted: it only needs to survive within a session.
 
+use piki_core::PageId;
 use rutle::tree_path::DocumentPosition;
 
 /// How many notes' positions are retained.
@@ -24,7 +25,7 @@ pub struct NotePosition {
 #[derive(Default)]
 pub struct PositionMemory {
     /// (note name, position), most-recently-remembered first.
-    entries: Vec<(String, NotePosition)>,
+    entries: Vec<(PageId, NotePosition)>,
 }
 
 impl PositionMemory {
@@ -36,7 +37,7 @@ impl PositionMemory {
     /// least-recently-remembered note once more than [`CAPACITY`] are tracked.
     pub fn remember(&mut self, note: &str, pos: NotePosition) {
         self.entries.retain(|(name, _)| name != note);
-        self.entries.insert(0, (note.to_string(), pos));
+        self.entries.insert(0, (PageId::new(note), pos));
         self.entries.truncate(CAPACITY);
     }
 
@@ -52,7 +53,7 @@ impl PositionMemory {
     /// its remembered position and recency. No-op if `old` is not tracked.
     pub fn rename(&mut self, old: &str, new: &str) {
         if let Some((name, _)) = self.entries.iter_mut().find(|(name, _)| name == old) {
-            *name = new.to_string();
+            *name = PageId::new(new);
         }
     }
 
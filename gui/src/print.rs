@@ -0,0 +1,200 @@
+//! Printing the current note.
+//!
+//! The note is laid out by a separate, off-screen rutle [`Renderer`] as wide
+//! as the printable area, then drawn page by page onto FLTK's [`Printer`] by
+//! scrolling that renderer one page at a time. [`PrintDrawContext`] wraps the
+//! regular [`FltkDrawContext`] with what paper needs: no caret or focus
+//! decorations, and page breaks that never cut a line of text in half. Each
+//! page is drawn twice — first a measuring pass that finds the top of the
+//! first line crossing the bottom edge, then the real pass clipped to it — and
+//! the next page starts at that line.
+
+use crate::fltk_draw_context::FltkDrawContext;
+use crate::markdown_converter::markdown_to_document;
+use fltk::printer::Printer;
+use rutle::render_context::{CaretLean, FontStyle, FontType, RenderContext};
+use rutle::renderer::Renderer;
+
+/// [`RenderContext`] for one printed page of a scrolled [`Renderer`].
+pub struct PrintDrawContext {
+    inner: FltkDrawContext,
+    /// Only record where the page has to break, don't draw anything.
+    measuring: bool,
+    page_height: i32,
+    /// Added to every y-coordinate, for when the renderer couldn't scroll as
+    /// far as asked (at the end of the note).
+    dy: i32,
+    font: (FontType, FontStyle, u8),
+    /// Top of the first line of text that doesn't fit on the page.
+    break_at: Option<i32>,
+}
+
+impl PrintDrawContext {
+    pub fn new(page_height: i32, dy: i32, measuring: bool) -> Self {
+        PrintDrawContext {
+            inner: FltkDrawContext::new(false, true),
+            measuring,
+            page_height,
+            dy,
+            font: (FontType::Content, FontStyle::Regular, 14),
+            break_at: None,
+        }
+    }
+
+    /// Height of the part of the page that can be printed without splitting
+    /// a line, as found by a measuring pass.
+    pub fn printable_height(&self) -> i32 {
+        match self.break_at {
+            // A line taller than a whole page has to be split after all.
+            Some(y) if y > 0 => y.min(self.page_height),
+            _ => self.page_height,
+        }
+    }
+}
+
+impl RenderContext for PrintDrawContext {
+    fn set_color(&mut self, color: u32) {
+        if !self.measuring {
+            self.inner.set_color(color);
+        }
+    }
+
+    fn set_font(&mut self, font: FontType, style: FontStyle, size: u8) {
+        self.font = (font, style, size);
+        self.inner.set_font(font, style, size);
+    }
+
+    fn draw_text(&mut self, text: &str, x: i32, y: i32) {
+        let y = y + self.dy;
+        if self.measuring {
+            let (font, style, size) = self.font;
+            let descent = self.inner.text_descent(font, style, size);
+            let top = y + descent - self.inner.text_height(font, style, size);
+            if top < self.page_height && y + descent > self.page_height {
+                self.break_at = Some(self.break_at.map_or(top, |b| b.min(top)));
+            }
+            return;
+        }
+        self.inner.draw_text(text, x, y);
+    }
+
+    fn draw_rect_filled(&mut self, x: i32, y: i32, w: i32, h: i32) {
+        if !self.measuring {
+            self.inner.draw_rect_filled(x, y + self.dy, w, h);
+        }
+    }
+
+    fn draw_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32) {
+        if !self.measuring {
+            self.inner.draw_line(x1, y1 + self.dy, x2, y2 + self.dy);
+        }
+    }
+
+    fn draw_caret(&mut self, _x: i32, _y: i32, _height: i32, _lean: CaretLean) {}
+
+    fn text_width(&mut self, text: &str, font: FontType, style: FontStyle, size: u8) -> f64 {
+        self.inner.text_width(text, font, style, size)
+    }
+
+    fn text_height(&self, font: FontType, style: FontStyle, size: u8) -> i32 {
+        self.inner.text_height(font, style, size)
+    }
+
+    fn text_descent(&self, font: FontType, style: FontStyle, size: u8) -> i32 {
+        self.inner.text_descent(font, style, size)
+    }
+
+    fn push_clip(&mut self, x: i32, y: i32, w: i32, h: i32) {
+        if !self.measuring {
+            self.inner.push_clip(x, y + self.dy, w, h);
+        }
+    }
+
+    fn pop_clip(&mut self) {
+        if !self.measuring {
+            self.inner.pop_clip();
+        }
+    }
+
+    fn color_average(&self, c1: u32, c2: u32, weight: f32) -> u32 {
+        self.inner.color_average(c1, c2, weight)
+    }
+
+    fn color_contrast(&self, fg: u32, bg: u32) -> u32 {
+        self.inner.color_contrast(fg, bg)
+    }
+
+    fn color_inactive(&self, c: u32) -> u32 {
+        self.inner.color_inactive(c)
+    }
+
+    fn has_focus(&self) -> bool {
+        false
+    }
+
+    fn is_active(&self) -> bool {
+        true
+    }
+}
+
+/// Show the system print dialog and print `markdown`. Returns the number of
+/// pages printed, or `Ok(0)` if the dialog was cancelled.
+pub fn print_markdown(markdown: &str) -> Result<usize, String> {
+    let mut printer = Printer::default();
+    // The page count isn't known before laying out on the chosen paper.
+    let (from, to) = match printer.begin_job(0) {
+        Ok(range) => range,
+        Err(_) => return Ok(0),
+    };
+    let from = from.unwrap_or(1).max(1) as usize;
+    let to = to.map_or(usize::MAX, |to| to.max(1) as usize);
+
+    let (width, height) = printer.printable_rect();
+    if width <= 0 || height <= 0 {
+        printer.end_job();
+        return Err("The printer reported no printable area".to_string());
+    }
+
+    let mut renderer = Renderer::new(0, 0, width, height);
+    renderer.set_cursor_visible(false);
+    renderer.editor_mut().set_document(markdown_to_document(
+        piki_core::frontmatter::split(markdown).1,
+    ));
+
+    let mut printed = 0;
+    let mut page = 1;
+    let mut offset = 0;
+    loop {
+        renderer.set_scroll(offset);
+        let dy = renderer.scroll_offset() - offset;
+
+        let mut measure = PrintDrawContext::new(height, dy, true);
+        renderer.draw(&mut measure);
+        let page_height = measure.printable_height();
+
+        if (from..=to).contains(&page) {
+            if let Err(e) = printer.begin_page() {
+                printer.end_job();
+                return Err(format!("Failed to start page {page}: {e:?}"));
+            }
+            let mut ctx = PrintDrawContext::new(height, dy, false);
+            ctx.push_clip(0, -dy, width, page_height);
+            renderer.draw(&mut ctx);
+            ctx.pop_clip();
+            if let Err(e) = printer.end_page() {
+                printer.end_job();
+                return Err(format!("Failed to finish page {page}: {e:?}"));
+            }
+            printed += 1;
+        }
+
+        offset += page_height;
+        page += 1;
+        if offset >= renderer.content_height() || page > to {
+            break;
+        }
+    }
+
+    printer.end_job();
+    Ok(printed)
+}
@@ -0,0 +1,245 @@
+//! Privacy screen that hides the window content after a period of inactivity.
+//!
+//! When enabled with `--lock-after <MINUTES>` (or triggered via View → Lock
+//! Screen), an opaque cover is raised over the whole window so sensitive notes
+//! are not left readable on an unattended or shared screen. Clicking the cover
+//! or pressing any key lowers it again, unless `lock_passphrase` is set in the
+//! `[gui]` section of `~/.pikirc`: then it only lowers once that passphrase is
+//! typed into the field on the cover.
+//!
+//! Activity is tracked in a thread-local rather than by threading a handle
+//! through every input path: the window's event handler and the editor's change
+//! callback simply call [`note_activity`]. Everything here runs on the FLTK
+//! main thread.
+
+use crate::pikirc;
+use fltk::{enums, frame, group, input, prelude::*};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+const COVER_LABEL: &str = "Piki is locked\n\nClick or press any key to continue";
+const PASSPHRASE_LABEL: &str = "Piki is locked\n\nEnter your passphrase to continue";
+const WRONG_PASSPHRASE_LABEL: &str = "Piki is locked\n\nWrong passphrase, try again";
+/// Size of the passphrase field, centered below the label.
+const INPUT_W: i32 = 240;
+const INPUT_H: i32 = 28;
+
+thread_local! {
+    /// When the user last interacted with the window.
+    static LAST_ACTIVITY: Cell<Option<Instant>> = const { Cell::new(None) };
+    /// The installed screen (see [`install`]); `None` until then.
+    static SCREEN: RefCell<Option<PrivacyScreen>> = const { RefCell::new(None) };
+}
+
+struct PrivacyScreen {
+    cover: group::Group,
+    label: frame::Frame,
+    input: input::SecretInput,
+    /// Idle time after which the screen locks itself; `None` disables auto-lock
+    /// (locking manually from the menu still works).
+    lock_after: Option<Duration>,
+    /// The passphrase that unlocks the screen, read from the settings when it
+    /// is locked; `None` if any key unlocks it.
+    passphrase: Option<String>,
+}
+
+/// Create the (hidden) cover. Must be called while the main window is the
+/// current group and after all other content widgets were added, so that the
+/// cover is drawn on top of them. `on_unlock` runs after the cover is lowered,
+/// e.g. to return keyboard focus to the editor.
+pub fn install(w: i32, h: i32, lock_after: Option<Duration>, on_unlock: impl Fn() + 'static) {
+    let mut cover = group::Group::new(0, 0, w, h, None);
+    cover.set_frame(enums::FrameType::FlatBox);
+    cover.set_color(enums::Color::from_rgb(48, 48, 52));
+    let mut label = frame::Frame::new(0, 0, w, h, None);
+    label.set_label_color(enums::Color::from_rgb(220, 220, 220));
+    label.set_label_size(16);
+    let mut input = input::SecretInput::new(0, 0, INPUT_W, INPUT_H, None);
+    input.set_trigger(enums::CallbackTrigger::EnterKeyAlways);
+    input.hide();
+    cover.end();
+    cover.hide();
+
+    let on_unlock = Rc::new(on_unlock);
+    {
+        let on_unlock = on_unlock.clone();
+        input.set_callback(move |i| {
+            let correct = SCREEN.with(|screen| {
+                screen
+                    .borrow()
+                    .as_ref()
+                    .is_some_and(|s| s.passphrase.as_deref() == Some(i.value().as_str()))
+            });
+            i.set_value("");
+            if correct {
+                unlock(&*on_unlock);
+            } else {
+                SCREEN.with(|screen| {
+                    if let Some(s) = screen.borrow_mut().as_mut() {
+                        s.label.set_label(WRONG_PASSPHRASE_LABEL);
+                        s.cover.redraw();
+                    }
+                });
+            }
+        });
+    }
+
+    cover.handle(move |_, ev| match ev {
+        // Accept focus so keystrokes go to the cover (and never reach the
+        // hidden editor) while locked.
+        enums::Event::Focus | enums::Event::Unfocus => true,
+        enums::Event::Push | enums::Event::KeyDown | enums::Event::Shortcut => {
+            if needs_passphrase() {
+                // The passphrase field has had its go at these already.
+                focus_input();
+            } else {
+                unlock(&*on_unlock);
+            }
+            true
+        }
+        _ => false,
+    });
+
+    note_activity();
+    SCREEN.with(|screen| {
+        *screen.borrow_mut() = Some(PrivacyScreen {
+            cover,
+            label,
+            input,
+            lock_after,
+            passphrase: None,
+        })
+    });
+}
+
+/// Lower the cover and hand the window back.
+fn unlock(on_unlock: &dyn Fn()) {
+    let win = SCREEN.with(|screen| {
+        let mut screen = screen.borrow_mut();
+        let s = screen.as_mut()?;
+        s.cover.hide();
+        s.cover.window()
+    });
+    note_activity();
+    on_unlock();
+    if let Some(mut win) = win {
+        win.redraw();
+    }
+}
+
+/// Whether the raised cover waits for the passphrase.
+fn needs_passphrase() -> bool {
+    SCREEN.with(|screen| {
+        screen
+            .try_borrow()
+            .is_ok_and(|s| s.as_ref().is_some_and(|s| s.passphrase.is_some()))
+    })
+}
+
+/// Put the keyboard focus into the passphrase field.
+fn focus_input() {
+    SCREEN.with(|screen| {
+        if let Some(s) = screen.borrow_mut().as_mut() {
+            s.input.take_focus().ok();
+        }
+    });
+}
+
+/// Record user activity, postponing the next auto-lock.
+pub fn note_activity() {
+    LAST_ACTIVITY.with(|last| last.set(Some(Instant::now())));
+}
+
+/// Whether the cover is currently raised.
+pub fn is_locked() -> bool {
+    SCREEN.with(|screen| screen.borrow().as_ref().is_some_and(|s| s.cover.visible()))
+}
+
+/// Raise the cover immediately.
+pub fn lock() {
+    SCREEN.with(|screen| {
+        if let Some(s) = screen.borrow_mut().as_mut() {
+            // Follow the window's current size, which may have changed since
+            // the cover was created.
+            if let Some(win) = s.cover.window() {
+                let (w, h) = (win.w(), win.h());
+                s.cover.resize(0, 0, w, h);
+                s.label.resize(0, 0, w, h);
+                s.input
+                    .resize((w - INPUT_W) / 2, h / 2 + 40, INPUT_W, INPUT_H);
+            }
+            // Only read the passphrase when locking, so a changed setting
+            // applies the next time without a restart.
+            if !s.cover.visible() {
+                s.passphrase = pikirc::PikiRc::load().gui.lock_passphrase();
+            }
+            if s.passphrase.is_some() {
+                s.label.set_label(PASSPHRASE_LABEL);
+                s.input.set_value("");
+                s.input.show();
+                s.cover.show();
+                s.input.take_focus().ok();
+            } else {
+                s.label.set_label(COVER_LABEL);
+                s.input.hide();
+                s.cover.show();
+                s.cover.take_focus().ok();
+            }
+            s.cover.redraw();
+        }
+    });
+}
+
+/// Lock the screen if auto-lock is enabled and the user has been idle for
+/// longer than the configured time. Called periodically from a timer.
+pub fn check_idle() {
+    let lock_after = SCREEN.with(|screen| screen.borrow().as_ref().and_then(|s| s.lock_after));
+    let last = LAST_ACTIVITY.with(Cell::get);
+    if !is_locked() && idle_expired(last, Instant::now(), lock_after) {
+        lock();
+    }
+}
+
+/// True when more than `lock_after` has passed between `last_activity` and
+/// `now`. Never true when auto-lock is disabled or no activity was recorded.
+fn idle_expired(
+    last_activity: Option<Instant>,
+    now: Instant,
+    lock_after: Option<Duration>,
+) -> bool {
+    match (last_activity, lock_after) {
+        (Some(last), Some(limit)) => now.saturating_duration_since(last) >= limit,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idle_expires_after_limit() {
+        let start = Instant::now();
+        let limit = Some(Duration::from_secs(300));
+        assert!(!idle_expired(Some(start), start, limit));
+        assert!(!idle_expired(
+            Some(start),
+            start + Duration::from_secs(299),
+            limit
+        ));
+        assert!(idle_expired(
+            Some(start),
+            start + Duration::from_secs(300),
+            limit
+        ));
+    }
+
+    #[test]
+    fn idle_never_expires_when_disabled() {
+        let start = Instant::now();
+        let much_later = start + Duration::from_secs(86_400);
+        assert!(!idle_expired(Some(start), much_later, None));
+        assert!(!idle_expired(None, much_later, Some(Duration::ZERO)));
+    }
+}
@@ -0,0 +1,307 @@
+//! Properties panel: the open note's frontmatter as rows of key and value.
+//!
+//! A note with properties (see [`piki_core::frontmatter::properties`]) gets a
+//! "Properties" bar above its text. Opened, it lists them one per row, with
+//! tags and aliases separated by commas, and a button to add another. Edits
+//! are written back into the frontmatter as they are typed, leaving anything
+//! the panel doesn't show as it was. Note → Properties opens the panel on any
+//! note, to add the first ones.
+//!
+//! Like the split view's source pane, the panel lives in a thread-local, as
+//! loading a note has to update it; the app lays it out with [`resize`] below
+//! the search bar.
+
+use fltk::{app, button, enums::*, group, input, prelude::*};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const HEADER_HEIGHT: i32 = 26;
+const ROW_HEIGHT: i32 = 28;
+const KEY_WIDTH: i32 = 140;
+const BUTTON_WIDTH: i32 = 26;
+const PADDING: i32 = 4;
+
+/// Called with the properties after they were edited.
+type OnChange = Rc<dyn Fn(Vec<(String, String)>)>;
+
+thread_local! {
+    /// The installed panel (see [`install`]); `None` until then.
+    static PANEL: RefCell<Option<Panel>> = const { RefCell::new(None) };
+}
+
+struct Panel {
+    group: group::Group,
+    header: button::Button,
+    add: button::Button,
+    /// One key and value input, and a remove button, per property.
+    rows: Vec<(input::Input, input::Input, button::Button)>,
+    properties: Vec<(String, String)>,
+    /// Whether the open note can have properties at all.
+    enabled: bool,
+    expanded: bool,
+    on_change: OnChange,
+    /// Called when the panel's height changed, to lay the window out again.
+    on_resize: Rc<dyn Fn()>,
+}
+
+/// Create the (hidden) panel. Must be called while the main window is the
+/// current group.
+pub fn install(
+    x: i32,
+    y: i32,
+    w: i32,
+    on_change: impl Fn(Vec<(String, String)>) + 'static,
+    on_resize: impl Fn() + 'static,
+) {
+    let mut group = group::Group::new(x, y, w, HEADER_HEIGHT, None);
+    let mut header = button::Button::new(x, y, w, HEADER_HEIGHT, None);
+    header.set_frame(FrameType::FlatBox);
+    header.set_align(Align::Inside | Align::Left);
+    header.set_label_size(13);
+    header.clear_visible_focus();
+    header.set_callback(|_| {
+        let expanded = PANEL.with(|panel| panel.borrow().as_ref().is_some_and(|p| p.expanded));
+        set_expanded(!expanded);
+    });
+    let mut add = button::Button::new(x, y, 120, ROW_HEIGHT - 6, "Add Property");
+    add.set_label_size(12);
+    add.set_callback(|_| add_row());
+    group.end();
+    group.hide();
+
+    PANEL.with(|panel| {
+        *panel.borrow_mut() = Some(Panel {
+            group,
+            header,
+            add,
+            rows: Vec::new(),
+            properties: Vec::new(),
+            enabled: false,
+            expanded: false,
+            on_change: Rc::new(on_change),
+            on_resize: Rc::new(on_resize),
+        })
+    });
+}
+
+/// The panel's height, 0 while it is hidden.
+pub fn height() -> i32 {
+    PANEL.with(|panel| {
+        panel
+            .borrow()
+            .as_ref()
+            .filter(|p| p.group.visible())
+            .map_or(0, |p| p.group.h())
+    })
+}
+
+/// Place the panel at (`x`, `y`), `w` wide.
+pub fn resize(x: i32, y: i32, w: i32) {
+    PANEL.with(|panel| {
+        if let Some(p) = panel.borrow_mut().as_mut() {
+            let h = p.group.h();
+            p.group.resize(x, y, w, h);
+            p.layout();
+        }
+    });
+}
+
+/// Show the properties of the note just loaded, collapsed; `None` for a page
+/// that can't have any, such as a plugin page.
+pub fn show_note(properties: Option<Vec<(String, String)>>) {
+    update(|p| {
+        p.enabled = properties.is_some();
+        p.properties = properties.unwrap_or_default();
+        p.expanded = false;
+        p.rebuild();
+    });
+}
+
+/// Open the panel for editing, with an empty row to start with if the note
+/// has no properties yet. Returns `false` if the note can't have any.
+pub fn open() -> bool {
+    let enabled = PANEL.with(|panel| panel.borrow().as_ref().is_some_and(|p| p.enabled));
+    if enabled {
+        update(|p| {
+            p.expanded = true;
+            if p.properties.is_empty() {
+                p.properties.push(Default::default());
+            }
+            p.rebuild();
+        });
+        focus_last_row();
+    }
+    enabled
+}
+
+fn set_expanded(expanded: bool) {
+    update(|p| {
+        p.expanded = expanded;
+        p.rebuild();
+    });
+}
+
+fn add_row() {
+    update(|p| {
+        p.properties.push(Default::default());
+        p.rebuild();
+    });
+    focus_last_row();
+}
+
+/// Rebuilding the rows deletes the button that was pressed, so this waits
+/// until its callback has returned.
+fn remove_row(index: usize) {
+    app::awake_callback(move || {
+        let changed = update(|p| {
+            (index < p.properties.len()).then(|| {
+                p.properties.remove(index);
+                p.rebuild();
+                p.properties.clone()
+            })
+        });
+        if let Some((on_change, Some(properties))) = changed {
+            on_change(properties);
+        }
+    });
+}
+
+/// Put keyboard focus into the key input of the last row, e.g. one just
+/// added.
+fn focus_last_row() {
+    PANEL.with(|panel| {
+        if let Some(p) = panel.borrow_mut().as_mut()
+            && let Some((key, _, _)) = p.rows.last_mut()
+        {
+            let _ = key.take_focus();
+        }
+    });
+}
+
+/// The values of the rows' inputs were edited.
+fn rows_edited() {
+    let changed = update(|p| {
+        p.properties = p
+            .rows
+            .iter()
+            .map(|(key, value, _)| (key.value().trim().to_string(), value.value()))
+            .collect();
+        p.update_header();
+        p.properties.clone()
+    });
+    if let Some((on_change, properties)) = changed {
+        on_change(properties);
+    }
+}
+
+/// Change the panel with `change`, then lay the window out again if its
+/// height changed. Returns what `change` returns, with the change callback
+/// to tell about it, unless the panel isn't installed.
+fn update<R>(change: impl FnOnce(&mut Panel) -> R) -> Option<(OnChange, R)> {
+    let (result, resized) = PANEL.with(|panel| {
+        let mut panel = panel.borrow_mut();
+        let p = panel.as_mut()?;
+        let height = (p.group.visible(), p.group.h());
+        let result = change(p);
+        let resized = (height != (p.group.visible(), p.group.h())).then(|| p.on_resize.clone());
+        Some(((p.on_change.clone(), result), resized))
+    })?;
+    if let Some(on_resize) = resized {
+        on_resize();
+    }
+    Some(result)
+}
+
+impl Panel {
+    /// Recreate the rows for `properties` and show or hide the panel.
+    fn rebuild(&mut self) {
+        for (key, value, remove) in self.rows.drain(..) {
+            app::delete_widget(key);
+            app::delete_widget(value);
+            app::delete_widget(remove);
+        }
+
+        if self.expanded {
+            self.group.begin();
+            for (index, (key_text, value_text)) in self.properties.iter().enumerate() {
+                let mut key = input::Input::default();
+                key.set_value(key_text);
+                key.set_text_size(13);
+                key.set_tooltip("Name, e.g. title, tags or aliases");
+                let mut value = input::Input::default();
+                value.set_value(value_text);
+                value.set_text_size(13);
+                value.set_tooltip("Value; separate tags and aliases with commas");
+                // Values are saved as they are typed; a name only once it is
+                // complete, as a property renamed moves to the end.
+                key.set_trigger(CallbackTrigger::Release | CallbackTrigger::EnterKey);
+                value.set_trigger(CallbackTrigger::Changed);
+                for input in [&mut key, &mut value] {
+                    input.set_callback(|_| rows_edited());
+                }
+                let mut remove = button::Button::default().with_label("×");
+                remove.set_tooltip("Remove this property");
+                remove.set_callback(move |_| remove_row(index));
+                self.rows.push((key, value, remove));
+            }
+            self.group.end();
+        }
+
+        let rows = if self.expanded {
+            self.properties.len() as i32 + 1
+        } else {
+            0
+        };
+        let (x, y, w) = (self.group.x(), self.group.y(), self.group.w());
+        self.group
+            .resize(x, y, w, HEADER_HEIGHT + rows * ROW_HEIGHT);
+        if self.enabled && (self.expanded || !self.properties.is_empty()) {
+            self.group.show();
+        } else {
+            self.group.hide();
+        }
+        self.update_header();
+        self.layout();
+    }
+
+    /// Label the header with whether the panel is open and, closed, the
+    /// names of the properties.
+    fn update_header(&mut self) {
+        let label = if self.expanded {
+            "▾ Properties".to_string()
+        } else {
+            let names = self
+                .properties
+                .iter()
+                .map(|(key, _)| key.as_str())
+                .filter(|key| !key.is_empty())
+                .collect::<Vec<_>>();
+            format!("▸ Properties: {}", names.join(", "))
+        };
+        // `@` starts a symbol in FLTK labels.
+        self.header.set_label(&label.replace('@', "@@"));
+        self.header.redraw();
+    }
+
+    /// Place the header, the rows and the add button inside the group.
+    fn layout(&mut self) {
+        let (x, y, w) = (self.group.x(), self.group.y(), self.group.w());
+        self.header.resize(x, y, w, HEADER_HEIGHT);
+        let value_w = (w - KEY_WIDTH - BUTTON_WIDTH - 4 * PADDING).max(0);
+        let mut top = y + HEADER_HEIGHT;
+        for (key, value, remove) in &mut self.rows {
+            let h = ROW_HEIGHT - 4;
+            key.resize(x + PADDING, top, KEY_WIDTH, h);
+            value.resize(x + 2 * PADDING + KEY_WIDTH, top, value_w, h);
+            remove.resize(x + 3 * PADDING + KEY_WIDTH + value_w, top, BUTTON_WIDTH, h);
+            top += ROW_HEIGHT;
+        }
+        if self.expanded {
+            self.add.resize(x + PADDING, top, 120, ROW_HEIGHT - 6);
+            self.add.show();
+        } else {
+            self.add.hide();
+        }
+        self.group.redraw();
+    }
+}
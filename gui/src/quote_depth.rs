@@ -0,0 +1,118 @@
+//! Quote levels: nesting the block at the cursor deeper into (or out of) a
+//! quote, like Increase/Decrease Quote Level in a mail client.
+//!
+//! tdoc represents `> > text` as a quote whose only child is another quote, so
+//! changing the level wraps the top-level block at the cursor in one more
+//! quote, or unwraps its outermost one. The edit replaces the document the same
+//! way the external code block editor does and keeps the caret in the same
+//! text leaf.
+
+use std::time::Instant;
+
+use rutle::editor::{Editor, UndoKind};
+use rutle::tree_path::{DocumentPosition, PathSegment};
+use rutle::tree_walk::enumerate_leaves;
+use tdoc::Paragraph;
+
+use crate::markdown_converter::{document_to_markdown, markdown_to_document};
+
+/// Quotes are nested at most this deep.
+pub const MAX_DEPTH: usize = 6;
+
+/// How many quotes `paragraph` is nested in, following single-child quotes.
+pub fn depth(paragraph: &Paragraph) -> usize {
+    match paragraph {
+        Paragraph::Quote { children } => match children.as_slice() {
+            [only] => 1 + depth(only),
+            _ => 1,
+        },
+        _ => 0,
+    }
+}
+
+/// `paragraph` wrapped in one more quote, unless it is nested too deep.
+pub fn deeper(paragraph: Paragraph) -> Option<Vec<Paragraph>> {
+    (depth(&paragraph) < MAX_DEPTH).then(|| {
+        vec![Paragraph::Quote {
+            children: vec![paragraph],
+        }]
+    })
+}
+
+/// The blocks inside `paragraph`'s outermost quote, or `None` for a block
+/// that isn't quoted.
+pub fn shallower(paragraph: Paragraph) -> Option<Vec<Paragraph>> {
+    match paragraph {
+        Paragraph::Quote { children } => Some(children),
+        _ => None,
+    }
+}
+
+/// Increase (or decrease) the quote level of the top-level block at the
+/// cursor, as one undoable edit. Returns whether the document changed.
+pub fn change_depth(editor: &mut Editor, increase: bool) -> bool {
+    let cursor = editor.cursor();
+    let Some(PathSegment::Paragraph(index)) = cursor.path.segments().first().cloned() else {
+        return false;
+    };
+    let Some(leaf) = enumerate_leaves(editor.document())
+        .iter()
+        .position(|leaf| leaf.path == cursor.path)
+    else {
+        return false;
+    };
+
+    let mut doc = editor.document().clone();
+    if index >= doc.paragraphs.len() {
+        return false;
+    }
+    let block = doc.paragraphs[index].clone();
+    let replacement = if increase {
+        deeper(block)
+    } else {
+        shallower(block)
+    };
+    let Some(replacement) = replacement else {
+        return false;
+    };
+    doc.paragraphs.splice(index..=index, replacement);
+
+    let doc = markdown_to_document(&document_to_markdown(&doc));
+    editor.select_all();
+    let _ = editor.delete_selection();
+    if editor.insert_document(&doc).is_err() {
+        return false;
+    }
+    // Nesting changes the paths, not the order of the text leaves.
+    if let Some(leaf) = enumerate_leaves(editor.document()).get(leaf) {
+        editor.set_cursor(DocumentPosition::at(leaf.path.clone(), cursor.offset));
+    }
+    editor.commit_undo_step(UndoKind::Other, Instant::now());
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nesting_and_unnesting_quotes() {
+        let doc = markdown_to_document("> > inner\n");
+        let quote = doc.paragraphs[0].clone();
+        assert_eq!(depth(&quote), 2);
+
+        let nested = deeper(quote.clone()).unwrap();
+        assert_eq!(depth(&nested[0]), 3);
+        let unnested = shallower(quote).unwrap();
+        assert_eq!(depth(&unnested[0]), 1);
+
+        let text = markdown_to_document("plain\n").paragraphs[0].clone();
+        assert_eq!(depth(&text), 0);
+        assert!(shallower(text.clone()).is_none());
+        let mut quoted = tdoc::Document::new();
+        for paragraph in deeper(text).unwrap() {
+            quoted.add_paragraph(paragraph);
+        }
+        assert_eq!(document_to_markdown(&quoted).trim(), "> plain");
+    }
+}
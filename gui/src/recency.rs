@@ -2,7 +2,7 @@
 //!
 //! The note picker lists notes most-recently-opened first and lets a double
 //! `Cmd-O`/`Ctrl-O` jump straight back to the previous note. Both need to know
-//! *when* notes were last opened, which the in-session navigation [`History`]
+//! *when* notes were last opened, which the navigation [`History`]
 //! does not record. This is persisted as TOML next to the window-state file so
 //! the ordering survives restarts — otherwise "sort by last open date" would be
 //! empty on every launch. The file is scoped per wiki directory (see
@@ -70,6 +70,14 @@ impl RecentNotes {
         }
     }
 
+    /// The note opened most recently — where the last session left off.
+    pub fn most_recent(&self) -> Option<&str> {
+        self.opened
+            .iter()
+            .max_by(|(a_name, a), (b_name, b)| a.cmp(b).then_with(|| b_name.cmp(a_name)))
+            .map(|(name, _)| name.as_str())
+    }
+
     /// Forget `note`'s recency entry (used when a note is deleted) so it no
     /// longer appears in the picker's ordering. No-op if it was never opened.
     pub fn remove(&mut self, note: &str) {
@@ -121,6 +129,15 @@ mod tests {
         r.remove("never");
     }
 
+    #[test]
+    fn most_recent_is_the_latest_opened() {
+        let mut r = RecentNotes::default();
+        assert_eq!(r.most_recent(), None);
+        r.opened.insert("older".into(), 1);
+        r.opened.insert("newer".into(), 2);
+        assert_eq!(r.most_recent(), Some("newer"));
+    }
+
     #[test]
     fn roundtrips_names_with_slashes() {
         let mut r = RecentNotes::default();
@@ -8,69 +8,19 @@
 //!   `piki://path/to/note#section-slug`.
 //!
 //! "Copy Link to Section" (Cmd-Shift-K) puts the URL form on the clipboard, so
-//! it is clickable everywhere; pasting such a URL into the link editor strips it
-//! back to the internal form via [`normalize_link_target`]. Both forms share the
-//! same `#section-slug` fragment, and [`heading_slug`] is the single source of
-//! truth for turning a heading's text into that slug — used both when a link is
-//! generated and when one is resolved back to a heading, so the two always agree.
+//! it is clickable everywhere; pasting such a URL into the link editor strips
+//! it back to the internal form via [`normalize_link_target`]. Both forms share
+//! the same `#section-slug` fragment, and [`heading_slug`] (shared with `piki
+//! section` through piki-core) is the single source of truth for turning a
+//! heading's text into that slug — used both when a link is generated and when
+//! one is resolved back to a heading, so the two always agree.
 
 /// The custom URL scheme Piki registers with the operating system.
 pub const URL_SCHEME: &str = "piki";
 
-/// Turn a heading's plain text into an anchor slug.
-///
-/// Lower-cases the text, keeps (Unicode) alphanumerics, and collapses any run of
-/// whitespace, `-`, or `_` into a single `-`, dropping all other punctuation.
-/// Leading and trailing dashes are trimmed. This is deliberately simple and,
-/// crucially, *self-consistent*: the same function generates the slug written
-/// into a link and resolves it back to a heading, so exact GitHub compatibility
-/// is not required — only that generation and resolution agree.
-///
-/// Duplicate headings are disambiguated by [`heading_anchors`], not here.
-pub fn heading_slug(text: &str) -> String {
-    let mut slug = String::new();
-    let mut pending_dash = false;
-    for c in text.chars() {
-        if c.is_alphanumeric() {
-            if pending_dash && !slug.is_empty() {
-                slug.push('-');
-            }
-            pending_dash = false;
-            slug.extend(c.to_lowercase());
-        } else if c.is_whitespace() || c == '-' || c == '_' {
-            // Defer emitting the separator so trailing separators never make it
-            // into the slug and runs collapse to a single dash.
-            pending_dash = true;
-        }
-        // Any other character (punctuation, symbols) is dropped.
-    }
-    slug
-}
+pub use piki_core::section::{heading_anchors, heading_index, heading_slug};
 
-/// Compute unique anchor slugs for a document's headings, in document order.
-///
-/// Headings that slug to the same base get a numeric suffix (`-1`, `-2`, …) in
-/// order of appearance, mirroring how GitHub disambiguates repeated headings, so
-/// a link to the second "Notes" heading resolves to that heading rather than the
-/// first. Callers pair the returned slugs positionally with the headings they
-/// passed in.
-pub fn heading_anchors<S: AsRef<str>>(heading_texts: &[S]) -> Vec<String> {
-    use std::collections::HashMap;
-    let mut counts: HashMap<String, usize> = HashMap::new();
-    let mut anchors = Vec::with_capacity(heading_texts.len());
-    for text in heading_texts {
-        let base = heading_slug(text.as_ref());
-        let seen = counts.entry(base.clone()).or_insert(0);
-        let anchor = if *seen == 0 {
-            base.clone()
-        } else {
-            format!("{base}-{seen}")
-        };
-        *seen += 1;
-        anchors.push(anchor);
-    }
-    anchors
-}
+use piki_core::percent_decode;
 
 /// Split a link destination into its note part and optional `#fragment`.
 ///
@@ -84,6 +34,17 @@ pub fn split_target(dest: &str) -> (&str, Option<&str>) {
     }
 }
 
+/// The line number in a line fragment (`L12`), which the `!todo` page uses to
+/// link each todo to its line. Heading slugs are lower-case, so the two never
+/// collide.
+pub fn line_fragment(fragment: &str) -> Option<usize> {
+    fragment
+        .strip_prefix('L')?
+        .parse()
+        .ok()
+        .filter(|&line| line > 0)
+}
+
 /// Build the `piki://` URL form of a link to `note`, optionally at `anchor`.
 ///
 /// The note path and the fragment are percent-encoded so the result is a valid,
@@ -153,56 +114,16 @@ fn encode_with(s: &str, keep: impl Fn(u8) -> bool) -> String {
     out
 }
 
-fn percent_decode(s: &str) -> String {
-    let bytes = s.as_bytes();
-    let mut out = Vec::with_capacity(bytes.len());
-    let mut i = 0;
-    while i < bytes.len() {
-        if bytes[i] == b'%'
-            && i + 2 < bytes.len()
-            && let (Some(hi), Some(lo)) = (hex_val(bytes[i + 1]), hex_val(bytes[i + 2]))
-        {
-            out.push(hi * 16 + lo);
-            i += 3;
-            continue;
-        }
-        out.push(bytes[i]);
-        i += 1;
-    }
-    String::from_utf8_lossy(&out).into_owned()
-}
-
-fn hex_val(b: u8) -> Option<u8> {
-    match b {
-        b'0'..=b'9' => Some(b - b'0'),
-        b'a'..=b'f' => Some(b - b'a' + 10),
-        b'A'..=b'F' => Some(b - b'A' + 10),
-        _ => None,
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn slug_basics() {
-        assert_eq!(heading_slug("Hello World"), "hello-world");
-        assert_eq!(heading_slug("Security Model"), "security-model");
-        assert_eq!(heading_slug("  Trailing spaces  "), "trailing-spaces");
-        assert_eq!(heading_slug("Notes: Meeting!"), "notes-meeting");
-        assert_eq!(heading_slug("Q4 — Budget (2026)"), "q4-budget-2026");
-        assert_eq!(heading_slug("under_score and-dash"), "under-score-and-dash");
-        assert_eq!(heading_slug("multiple   spaces"), "multiple-spaces");
-        assert_eq!(heading_slug("---"), "");
-        assert_eq!(heading_slug(""), "");
-    }
-
-    #[test]
-    fn anchors_disambiguate_duplicates() {
-        let headings = ["Notes", "Details", "Notes", "notes"];
-        let anchors = heading_anchors(&headings);
-        assert_eq!(anchors, vec!["notes", "details", "notes-1", "notes-2"]);
+    fn line_fragments() {
+        assert_eq!(line_fragment("L12"), Some(12));
+        assert_eq!(line_fragment("L0"), None);
+        assert_eq!(line_fragment("l12"), None);
+        assert_eq!(line_fragment("lists"), None);
     }
 
     #[test]
@@ -261,12 +182,4 @@ mod tests {
         assert_eq!(normalize_link_target("PIKI://frontpage"), "frontpage");
         assert_eq!(normalize_link_target("piki:frontpage#top"), "frontpage#top");
     }
-
-    #[test]
-    fn percent_decode_tolerates_stray_percent() {
-        assert_eq!(percent_decode("100%"), "100%");
-        assert_eq!(percent_decode("a%2"), "a%2");
-        assert_eq!(percent_decode("a%zz"), "a%zz");
-        assert_eq!(percent_decode("%41%42"), "AB");
-    }
 }
@@ -0,0 +1,94 @@
+//! Smart punctuation while typing.
+//!
+//! With `smart_punctuation = true` in a wiki's `.piki.toml`, straight quotes
+//! typed in the editor become curly ones — opening after a space or bracket,
+//! closing (and apostrophes) anywhere else — `--` becomes an en dash, a third
+//! `-` turns that into an em dash, and `...` becomes an ellipsis. Code blocks
+//! are left alone.
+
+use std::cell::Cell;
+
+use rutle::editor::Editor;
+use rutle::structured_document::BlockType;
+use rutle::tree_path::DocumentPosition;
+
+thread_local! {
+    /// Whether the open wiki asks for smart punctuation. Set by the app when
+    /// it loads the wiki's settings.
+    static ENABLED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Turn smart punctuation on or off for the editor.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.with(|cell| cell.set(enabled));
+}
+
+pub fn enabled() -> bool {
+    ENABLED.with(Cell::get)
+}
+
+/// Whether a quote after `previous` opens rather than closes.
+fn opens_after(previous: Option<char>) -> bool {
+    previous.is_none_or(|c| c.is_whitespace() || "([{<“‘–—-/".contains(c))
+}
+
+/// The replacement for what was just typed, the end of `prefix` — everything
+/// in the block up to the cursor: how many bytes to take back and what to
+/// put in their place.
+pub fn replacement_for(prefix: &str) -> Option<(usize, &'static str)> {
+    let mut chars = prefix.chars().rev();
+    let typed = chars.next()?;
+    let previous = chars.next();
+    match typed {
+        '"' => Some((1, if opens_after(previous) { "“" } else { "”" })),
+        '\'' => Some((1, if opens_after(previous) { "‘" } else { "’" })),
+        '-' if prefix.ends_with("–-") => Some(("–-".len(), "—")),
+        '-' if prefix.ends_with("--") => Some((2, "–")),
+        '.' if prefix.ends_with("...") => Some((3, "…")),
+        _ => None,
+    }
+}
+
+/// Replace the punctuation completed by `typed`, just inserted at the cursor,
+/// if smart punctuation is on. Returns whether the text changed.
+pub fn apply(editor: &mut Editor, typed: &str) -> bool {
+    if !enabled()
+        || !matches!(typed, "\"" | "'" | "-" | ".")
+        || matches!(editor.current_block_type(), BlockType::CodeBlock { .. })
+    {
+        return false;
+    }
+    let cursor = editor.cursor();
+    let start = DocumentPosition::at(cursor.path.clone(), 0);
+    let Some((taken, replacement)) = replacement_for(&editor.text_in_range(start, cursor)) else {
+        return false;
+    };
+    matches!(editor.delete_backward_bytes(taken), Ok(true))
+        && editor.insert_text(replacement).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curls_quotes_by_what_precedes_them() {
+        assert_eq!(replacement_for("\""), Some((1, "“")));
+        assert_eq!(replacement_for("say \""), Some((1, "“")));
+        assert_eq!(replacement_for("(\""), Some((1, "“")));
+        assert_eq!(replacement_for("“hi\""), Some((1, "”")));
+        assert_eq!(replacement_for("don'"), Some((1, "’")));
+        assert_eq!(replacement_for("a '"), Some((1, "‘")));
+        assert_eq!(replacement_for("‘quoted'"), Some((1, "’")));
+    }
+
+    #[test]
+    fn dashes_and_ellipsis() {
+        assert_eq!(replacement_for("-"), None);
+        assert_eq!(replacement_for("1--"), Some((2, "–")));
+        assert_eq!(replacement_for("1–-"), Some(("–-".len(), "—")));
+        assert_eq!(replacement_for("wait.."), None);
+        assert_eq!(replacement_for("wait..."), Some((3, "…")));
+        assert_eq!(replacement_for("plain"), None);
+    }
+}
@@ -0,0 +1,158 @@
+//! Split view: the open note's Markdown source beside its rendering.
+//!
+//! View → Split View puts a plain-text editor with the note's Markdown on the
+//! left of the window and the rich editor, read-only while split, on the
+//! right. Edits to the source are rendered into the preview once typing
+//! pauses for a moment, keeping its scroll position, and the preview's
+//! change callback takes care of saving them like any other edit.
+//!
+//! Like the privacy screen, the source pane lives in a thread-local instead of
+//! being threaded through every function that loads or lays out a note; the
+//! app calls [`set_source`] after loading one and lays the pane out with
+//! [`resize`].
+
+use fltk::{app, enums, prelude::*, text};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+/// How long typing in the source must pause before the preview follows.
+const RENDER_DELAY_SECS: f64 = 0.3;
+
+thread_local! {
+    /// The installed pane (see [`install`]); `None` until then.
+    static PANE: RefCell<Option<SourcePane>> = const { RefCell::new(None) };
+}
+
+struct SourcePane {
+    editor: text::TextEditor,
+    buffer: text::TextBuffer,
+    /// Set while the source is replaced by [`set_source`] rather than edited.
+    loading: Rc<Cell<bool>>,
+    /// The pending render of the source into the preview.
+    pending: Rc<Cell<Option<app::TimeoutHandle>>>,
+    /// Renders the source into the preview.
+    on_edit: Rc<dyn Fn(String)>,
+}
+
+/// Create the (hidden) source pane. Must be called while the main window is
+/// the current group. `on_edit` is called with the source after it was
+/// edited, to render it into the preview.
+pub fn install(x: i32, y: i32, w: i32, h: i32, on_edit: impl Fn(String) + 'static) {
+    let buffer = text::TextBuffer::default();
+    let mut editor = text::TextEditor::new(x, y, w, h, None);
+    editor.set_buffer(buffer.clone());
+    editor.set_text_font(enums::Font::Courier);
+    editor.set_text_size(crate::theme::typography().font_size as i32);
+    editor.wrap_mode(text::WrapMode::AtBounds, 0);
+    editor.set_linenumber_width(0);
+    editor.hide();
+
+    let loading = Rc::new(Cell::new(false));
+    let pending: Rc<Cell<Option<app::TimeoutHandle>>> = Rc::new(Cell::new(None));
+    let on_edit: Rc<dyn Fn(String)> = Rc::new(on_edit);
+    {
+        let loading = loading.clone();
+        let pending = pending.clone();
+        let on_edit = on_edit.clone();
+        let source = buffer.clone();
+        let mut buffer = buffer.clone();
+        buffer.add_modify_callback(move |_, _, _, _, _| {
+            if loading.get() {
+                return;
+            }
+            if let Some(handle) = pending.take() {
+                app::remove_timeout3(handle);
+            }
+            let pending_for_timer = pending.clone();
+            let on_edit = on_edit.clone();
+            let source = source.clone();
+            pending.set(Some(app::add_timeout3(RENDER_DELAY_SECS, move |_| {
+                pending_for_timer.set(None);
+                on_edit(source.text());
+            })));
+        });
+    }
+
+    PANE.with(|pane| {
+        *pane.borrow_mut() = Some(SourcePane {
+            editor,
+            buffer,
+            loading,
+            pending,
+            on_edit,
+        })
+    });
+}
+
+/// Whether the window is split.
+pub fn is_active() -> bool {
+    PANE.with(|pane| pane.borrow().as_ref().is_some_and(|p| p.editor.visible()))
+}
+
+/// Split the window, showing `markdown` as the source (see [`set_source`]).
+pub fn show(markdown: &str, editable: bool) {
+    set_source(markdown, editable);
+    PANE.with(|pane| {
+        if let Some(p) = pane.borrow_mut().as_mut() {
+            p.editor.show();
+            let _ = p.editor.take_focus();
+        }
+    });
+}
+
+/// Close the split, rendering what was still waiting to be rendered first.
+pub fn hide() {
+    flush();
+    PANE.with(|pane| {
+        if let Some(p) = pane.borrow_mut().as_mut() {
+            p.editor.hide();
+        }
+    });
+}
+
+/// Show `markdown` as the source of the note just loaded; only `editable`
+/// sources (not those of generated plugin pages) can be changed. Does not
+/// render it into the preview.
+pub fn set_source(markdown: &str, editable: bool) {
+    PANE.with(|pane| {
+        if let Some(p) = pane.borrow_mut().as_mut() {
+            if let Some(handle) = p.pending.take() {
+                app::remove_timeout3(handle);
+            }
+            p.loading.set(true);
+            p.buffer.set_text(markdown);
+            p.loading.set(false);
+            if editable {
+                p.editor.activate();
+            } else {
+                p.editor.deactivate();
+            }
+            p.editor.scroll(0, 0);
+        }
+    });
+}
+
+/// Render edits to the source that are still waiting for typing to pause,
+/// e.g. before the note is saved.
+pub fn flush() {
+    let waiting = PANE.with(|pane| {
+        let pane = pane.borrow();
+        let p = pane.as_ref()?;
+        let handle = p.pending.take()?;
+        app::remove_timeout3(handle);
+        Some((p.on_edit.clone(), p.buffer.text()))
+    });
+    if let Some((on_edit, markdown)) = waiting {
+        on_edit(markdown);
+    }
+}
+
+/// Place the source pane.
+pub fn resize(x: i32, y: i32, w: i32, h: i32) {
+    PANE.with(|pane| {
+        if let Some(p) = pane.borrow_mut().as_mut() {
+            p.editor.resize(x, y, w, h);
+            p.editor.redraw();
+        }
+    });
+}
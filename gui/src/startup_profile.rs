@@ -0,0 +1,86 @@
+//! Opt-in startup timing, enabled with `--profile-startup`.
+//!
+//! Records named checkpoints while `main` sets the application up and prints a
+//! breakdown to stderr once the event loop is running, so slow phases (e.g.
+//! loading a huge initial note) are easy to spot. When disabled, marks are
+//! ignored and nothing is printed.
+
+use std::time::{Duration, Instant};
+
+pub struct StartupProfile {
+    enabled: bool,
+    started: Instant,
+    /// (phase label, time since `started` when the phase finished)
+    marks: Vec<(&'static str, Duration)>,
+}
+
+impl StartupProfile {
+    /// Start profiling from `started` (taken as early as possible in `main`).
+    pub fn new(enabled: bool, started: Instant) -> Self {
+        StartupProfile {
+            enabled,
+            started,
+            marks: Vec::new(),
+        }
+    }
+
+    /// Record that the phase `label` has just finished.
+    pub fn mark(&mut self, label: &'static str) {
+        if self.enabled {
+            self.marks.push((label, self.started.elapsed()));
+        }
+    }
+
+    /// Print the timing breakdown to stderr (no-op when disabled).
+    pub fn report(&self) {
+        if self.enabled {
+            eprint!("{}", format_report(&self.marks));
+        }
+    }
+}
+
+/// Render one line per phase with its own duration and the running total.
+fn format_report(marks: &[(&'static str, Duration)]) -> String {
+    let width = marks
+        .iter()
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::from("Startup profile:\n");
+    let mut previous = Duration::ZERO;
+    for (label, at) in marks {
+        let phase = at.saturating_sub(previous);
+        out.push_str(&format!(
+            "  {label:<width$}  {:>8.1} ms  (total {:.1} ms)\n",
+            phase.as_secs_f64() * 1000.0,
+            at.as_secs_f64() * 1000.0,
+        ));
+        previous = *at;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_lists_phase_and_total_durations() {
+        let marks = [
+            ("parse arguments", Duration::from_millis(2)),
+            ("show window", Duration::from_millis(12)),
+        ];
+        let report = format_report(&marks);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines[0], "Startup profile:");
+        assert_eq!(lines[1], "  parse arguments       2.0 ms  (total 2.0 ms)");
+        assert_eq!(lines[2], "  show window          10.0 ms  (total 12.0 ms)");
+    }
+
+    #[test]
+    fn disabled_profile_records_nothing() {
+        let mut profile = StartupProfile::new(false, Instant::now());
+        profile.mark("anything");
+        assert!(profile.marks.is_empty());
+    }
+}
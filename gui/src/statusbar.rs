@@ -12,6 +12,12 @@ fn brighten_color(color: enums::Color, factor: f32) -> enums::Color {
     enums::Color::from_rgb(new_r, new_g, new_b)
 }
 
+/// Horizontal position and width of the centered timer display
+fn timer_geometry(x: i32, w: i32) -> (i32, i32) {
+    let tw = 180.min(w / 3);
+    (x + (w - tw) / 2, tw)
+}
+
 /// Custom status bar widget that manages two child widgets (note status and save status)
 /// and automatically handles layout and rendering
 pub struct StatusBar {
@@ -21,6 +27,8 @@ pub struct StatusBar {
     note_status: button::Button,
     // Right side: save status (frame for display)
     save_status: frame::Frame,
+    // Center: running Pomodoro timer (hidden when idle)
+    timer_status: frame::Frame,
     // Colors
     bg_color: enums::Color,
     text_color: enums::Color,
@@ -78,10 +86,21 @@ impl StatusBar {
         save_status.set_color(bg_color);
         save_status.set_label_color(text_color);
 
+        // Create timer frame (center), hidden until a timer runs
+        let (tx, tw) = timer_geometry(x, w);
+        let mut timer_status = frame::Frame::new(tx, y, tw, h, None);
+        timer_status.set_frame(enums::FrameType::FlatBox);
+        timer_status.set_align(enums::Align::Center | enums::Align::Inside);
+        timer_status.set_label_size(app::font_size() - 1);
+        timer_status.set_color(bg_color);
+        timer_status.set_label_color(text_color);
+        timer_status.hide();
+
         StatusBar {
             background,
             note_status,
             save_status,
+            timer_status,
             bg_color,
             text_color,
             hover_color,
@@ -96,6 +115,7 @@ impl StatusBar {
         self.background.set_color(color);
         self.note_status.set_color(color);
         self.save_status.set_color(color);
+        self.timer_status.set_color(color);
 
         // Update the hover handler with the new colors
         let mut but2 = self.note_status.clone();
@@ -121,6 +141,7 @@ impl StatusBar {
         self.text_color = color;
         self.note_status.set_label_color(color);
         self.save_status.set_label_color(color);
+        self.timer_status.set_label_color(color);
     }
 
     /// Set the note status text (left side)
@@ -133,6 +154,23 @@ impl StatusBar {
         self.save_status.set_label(text);
     }
 
+    /// Show the timer text (center), or hide it with `None`
+    pub fn set_timer(&mut self, text: Option<&str>) {
+        match text {
+            Some(text) => {
+                self.timer_status.set_label(text);
+                if self.background.visible() {
+                    self.timer_status.show();
+                }
+            }
+            None => {
+                self.timer_status.set_label("");
+                self.timer_status.hide();
+            }
+        }
+        self.timer_status.redraw();
+    }
+
     /// Set the tooltip for the note status (left side)
     pub fn set_note_tooltip(&mut self, tooltip: &str) {
         self.note_status.set_tooltip(tooltip);
@@ -186,6 +224,18 @@ impl StatusBar {
         });
     }
 
+    /// Register a callback for when the timer is clicked
+    pub fn on_timer_click<F: FnMut() + 'static>(&mut self, mut cb: F) {
+        self.timer_status.handle(move |_, evt| {
+            if evt == enums::Event::Push {
+                cb();
+                true
+            } else {
+                false
+            }
+        });
+    }
+
     /// Get a reference to the note status widget (for external manipulation)
     pub fn note_status_widget(&self) -> button::Button {
         self.note_status.clone()
@@ -201,6 +251,8 @@ impl StatusBar {
         self.background.resize(x, y, w, h);
         self.note_status.resize(x + 5, y, w / 2 - 10, h);
         self.save_status.resize(x + 5 + w / 2, y, w / 2 - 10, h);
+        let (tx, tw) = timer_geometry(x, w);
+        self.timer_status.resize(tx, y, tw, h);
     }
 
     /// Get the height of the status bar
@@ -228,6 +280,7 @@ impl StatusBar {
         self.background.hide();
         self.note_status.hide();
         self.save_status.hide();
+        self.timer_status.hide();
     }
 
     /// Show the status bar
@@ -235,6 +288,9 @@ impl StatusBar {
         self.background.show();
         self.note_status.show();
         self.save_status.show();
+        if !self.timer_status.label().is_empty() {
+            self.timer_status.show();
+        }
     }
 
     /// Check if the status bar is visible
@@ -0,0 +1,393 @@
+//! Color palettes and link styling for the editor.
+//!
+//! rutle's renderer picks the colors of links, highlights, quotes and so on;
+//! every color passes through [`FltkDrawContext`](crate::fltk_draw_context::FltkDrawContext)
+//! on its way to the screen, which maps it through the current [`Palette`].
+//! Palettes keep each color's lightness and only move its hue and saturation,
+//! so contrast against the background stays what the renderer intended.
+//!
+//! Links are recognized by their color: with [`Appearance::underline_links`]
+//! set, text drawn in the link color is always underlined, so color is never
+//! the only thing telling a link apart from the text around it.
+//!
+//! The appearance lives in a thread-local (everything runs on the FLTK main
+//! thread) and is persisted next to the window state.
+//!
+//! Fonts and the width of the text column are [`Typography`], set in the
+//! `[editor]` section of `~/.pikirc` rather than from the menus.
+
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Palette {
+    /// The renderer's own colors.
+    #[default]
+    Standard,
+    /// The Okabe–Ito colors, which stay distinguishable with red-green and
+    /// blue-yellow color blindness.
+    ColorBlind,
+    /// Darker text and accents for more contrast.
+    HighContrast,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 3] = [
+        Palette::Standard,
+        Palette::ColorBlind,
+        Palette::HighContrast,
+    ];
+
+    /// The palette's name in menus.
+    pub fn label(self) -> &'static str {
+        match self {
+            Palette::Standard => "Standard",
+            Palette::ColorBlind => "Color-Blind Safe",
+            Palette::HighContrast => "High Contrast",
+        }
+    }
+
+    /// `color` (`0xRRGGBBAA`, like rutle's) in this palette.
+    pub fn map(self, color: u32) -> u32 {
+        let (h, s, l) = to_hsl(color);
+        match self {
+            Palette::Standard => color,
+            // Grays and near-whites have no hue to confuse.
+            Palette::ColorBlind if chroma(s, l) < 0.1 => color,
+            Palette::ColorBlind => {
+                let (h, s, _) = to_hsl(okabe_ito(h));
+                from_hsl(h, s, l, color & 0xFF)
+            }
+            // Text and accents get darker; light backgrounds stay as they are.
+            Palette::HighContrast if l < 0.6 => from_hsl(h, s, l * 0.6, color & 0xFF),
+            Palette::HighContrast => color,
+        }
+    }
+}
+
+/// The Okabe–Ito color closest in hue to `hue` (degrees).
+fn okabe_ito(hue: f32) -> u32 {
+    match hue {
+        h if !(15.0..345.0).contains(&h) => 0xD55E00FF, // vermillion
+        h if h < 45.0 => 0xE69F00FF,                    // orange
+        h if h < 70.0 => 0xF0E442FF,                    // yellow
+        h if h < 170.0 => 0x009E73FF,                   // bluish green
+        h if h < 200.0 => 0x56B4E9FF,                   // sky blue
+        h if h < 260.0 => 0x0072B2FF,                   // blue
+        _ => 0xCC79A7FF,                                // reddish purple
+    }
+}
+
+/// How the editor draws notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Appearance {
+    #[serde(default)]
+    pub palette: Palette,
+    /// Underline links all the time, not only while hovered.
+    #[serde(default)]
+    pub underline_links: bool,
+}
+
+thread_local! {
+    static CURRENT: Cell<Appearance> = const {
+        Cell::new(Appearance {
+            palette: Palette::Standard,
+            underline_links: false,
+        })
+    };
+}
+
+/// Body text size the renderer's own sizes are made for, in points.
+pub const DEFAULT_FONT_SIZE: u8 = 14;
+
+/// Space to the left and right of the text, in pixels, unless a
+/// [`Typography::max_width`] asks for more.
+pub const DEFAULT_PADDING: i32 = 25;
+
+/// Characters per line of the text column in View → Focus Mode, unless
+/// [`Typography::focus_width`] asks for another measure.
+pub const DEFAULT_FOCUS_WIDTH: u16 = 90;
+
+/// Width of the editor's scrollbar (must match `SCROLLBAR_WIDTH` in
+/// `fltk_structured_rich_display.rs`).
+const SCROLLBAR_WIDTH: i32 = 15;
+
+/// The editor's fonts and text column, from the `[editor]` section of
+/// `~/.pikirc`:
+///
+/// ```toml
+/// [editor]
+/// font = "Georgia"   # body text and headings
+/// font_size = 16     # body text, in points; headings and code scale along
+/// line_spacing = 1.3 # line height as a multiple of the font's
+/// max_width = 720    # widest the text column gets, in pixels
+/// focus_width = 72   # characters per line in focus mode
+/// focus_dim = true   # fade all but the caret's paragraph in focus mode
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Typography {
+    /// Font family of body text and headings; Helvetica if unset.
+    pub font: Option<String>,
+    pub font_size: u8,
+    pub line_spacing: f32,
+    /// Unset, the text fills the window.
+    pub max_width: Option<i32>,
+    /// Unset, focus mode's text column is [`DEFAULT_FOCUS_WIDTH`] characters.
+    pub focus_width: Option<u16>,
+    pub focus_dim: bool,
+    /// View → Zoom on top of `font_size`, remembered per wiki with the
+    /// window state rather than set in `~/.pikirc`.
+    #[serde(skip)]
+    pub zoom: f32,
+}
+
+impl Default for Typography {
+    fn default() -> Self {
+        Typography {
+            font: None,
+            font_size: DEFAULT_FONT_SIZE,
+            line_spacing: 1.0,
+            max_width: None,
+            focus_width: None,
+            focus_dim: false,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl Typography {
+    /// `size`, as chosen by the renderer for the default font size, scaled to
+    /// the configured one and zoomed.
+    pub fn scale(&self, size: u8) -> i32 {
+        let size = size as f32 * self.font_size.max(1) as f32 / DEFAULT_FONT_SIZE as f32
+            * self.zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        (size.round() as i32).max(1)
+    }
+
+    /// The height of a line of text `height` pixels high.
+    pub fn line_height(&self, height: i32) -> i32 {
+        (height as f32 * self.line_spacing.clamp(0.5, 4.0)).round() as i32
+    }
+
+    /// Horizontal padding of an editor `width` pixels wide, centering the
+    /// text column if it would be wider than [`Self::max_width`].
+    pub fn padding(&self, width: i32) -> i32 {
+        match self.max_width {
+            Some(max_width) => ((width - SCROLLBAR_WIDTH - max_width) / 2).max(DEFAULT_PADDING),
+            None => DEFAULT_PADDING,
+        }
+    }
+
+    /// Horizontal padding of an editor `width` pixels wide in focus mode,
+    /// centering a column of [`Self::focus_width`] characters.
+    pub fn focus_padding(&self, width: i32) -> i32 {
+        // A rough average character width for proportional fonts.
+        let char_width = (self.scale(DEFAULT_FONT_SIZE) as f32 * 0.55) as i32;
+        let chars = self.focus_width.unwrap_or(DEFAULT_FOCUS_WIDTH).max(1) as i32;
+        ((width - SCROLLBAR_WIDTH - char_width * chars) / 2).max(DEFAULT_PADDING)
+    }
+}
+
+/// How far View → Zoom goes out and in.
+pub const MIN_ZOOM: f32 = 0.5;
+pub const MAX_ZOOM: f32 = 3.0;
+
+/// Each View → Zoom In enlarges the text by this factor.
+const ZOOM_STEP: f32 = 1.1;
+
+/// `zoom` one step further in or out. Rounded to whole percents, so that
+/// zooming in and out again ends at exactly 100%.
+pub fn zoom_step(zoom: f32, zoom_in: bool) -> f32 {
+    let zoom = if zoom_in {
+        zoom * ZOOM_STEP
+    } else {
+        zoom / ZOOM_STEP
+    };
+    ((zoom * 100.0).round() / 100.0).clamp(MIN_ZOOM, MAX_ZOOM)
+}
+
+thread_local! {
+    static TYPOGRAPHY: RefCell<Typography> = RefCell::new(Typography::default());
+}
+
+/// The typography the editor currently draws with.
+pub fn typography() -> Typography {
+    TYPOGRAPHY.with(|current| current.borrow().clone())
+}
+
+/// Draw with `typography` from the next redraw on. The font family must
+/// have been loaded with
+/// [`load_font_family`](crate::fltk_draw_context::load_font_family) first.
+pub fn set_typography(typography: Typography) {
+    TYPOGRAPHY.with(|current| *current.borrow_mut() = typography);
+}
+
+/// The appearance the editor currently draws with.
+pub fn current() -> Appearance {
+    CURRENT.with(Cell::get)
+}
+
+/// Draw with `appearance` from the next redraw on.
+pub fn set(appearance: Appearance) {
+    CURRENT.with(|current| current.set(appearance));
+}
+
+/// Load the appearance saved at `path`; a missing or broken file gives the
+/// defaults.
+pub fn load(path: &Path) -> Appearance {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| toml::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(path: &Path, appearance: &Appearance) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let toml = toml::to_string_pretty(appearance)
+        .map_err(|err| io::Error::other(format!("toml serialization error: {err}")))?;
+    fs::write(path, toml)
+}
+
+/// Whether text in `color` is a link: a clearly blue, neither very dark nor
+/// very light color, as links are drawn in every palette.
+pub fn is_link_color(color: u32) -> bool {
+    let (h, s, l) = to_hsl(color);
+    (190.0..265.0).contains(&h) && s >= 0.35 && (0.15..0.75).contains(&l)
+}
+
+/// Hue (degrees), saturation and lightness (0–1) of an `0xRRGGBBAA` color.
+fn to_hsl(color: u32) -> (f32, f32, f32) {
+    let r = ((color >> 24) & 0xFF) as f32 / 255.0;
+    let g = ((color >> 16) & 0xFF) as f32 / 255.0;
+    let b = ((color >> 8) & 0xFF) as f32 / 255.0;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let d = max - min;
+    if d == 0.0 {
+        return (0.0, 0.0, l);
+    }
+    let s = d / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+        60.0 * ((g - b) / d).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / d + 2.0)
+    } else {
+        60.0 * ((r - g) / d + 4.0)
+    };
+    (h, s, l)
+}
+
+/// How colorful a color is, from 0 (gray) to 1.
+fn chroma(s: f32, l: f32) -> f32 {
+    (1.0 - (2.0 * l - 1.0).abs()) * s
+}
+
+fn from_hsl(h: f32, s: f32, l: f32, alpha: u32) -> u32 {
+    let c = chroma(s, l);
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = l - c / 2.0;
+    let (r, g, b) = match h {
+        h if h < 60.0 => (c, x, 0.0),
+        h if h < 120.0 => (x, c, 0.0),
+        h if h < 180.0 => (0.0, c, x),
+        h if h < 240.0 => (0.0, x, c),
+        h if h < 300.0 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let channel = |v: f32| (((v + m) * 255.0).round().clamp(0.0, 255.0)) as u32;
+    (channel(r) << 24) | (channel(g) << 16) | (channel(b) << 8) | alpha
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palettes_keep_lightness_and_grays() {
+        for color in [0x000000FF, 0x777777FF, 0xFFFFF5FF] {
+            assert_eq!(Palette::ColorBlind.map(color), color);
+        }
+        assert_eq!(Palette::Standard.map(0x3366CCFF), 0x3366CCFF);
+
+        // A red and a green accent end up as vermillion and bluish green.
+        let red = Palette::ColorBlind.map(0xCC3333FF);
+        let green = Palette::ColorBlind.map(0x33CC33FF);
+        assert!((to_hsl(red).0 - to_hsl(0xD55E00FF).0).abs() < 1.0);
+        assert!((to_hsl(green).0 - to_hsl(0x009E73FF).0).abs() < 1.0);
+        assert!((to_hsl(red).2 - to_hsl(0xCC3333FF).2).abs() < 0.01);
+
+        let dark = Palette::HighContrast.map(0x3366CCFF);
+        assert!(to_hsl(dark).2 < to_hsl(0x3366CCFF).2);
+        assert_eq!(Palette::HighContrast.map(0xFFF3A0FF), 0xFFF3A0FF);
+    }
+
+    #[test]
+    fn typography_scales_sizes_and_centers_the_text() {
+        let standard = Typography::default();
+        assert_eq!(standard.scale(14), 14);
+        assert_eq!(standard.line_height(17), 17);
+        assert_eq!(standard.padding(2000), DEFAULT_PADDING);
+
+        let typography: Typography =
+            toml::from_str("font_size = 21\nline_spacing = 1.5\nmax_width = 600\n").unwrap();
+        assert_eq!(typography.font, None);
+        assert_eq!(typography.scale(14), 21);
+        assert_eq!(typography.scale(20), 30);
+        assert_eq!(typography.line_height(20), 30);
+        assert_eq!(typography.padding(1015), 200);
+        assert_eq!(typography.padding(500), DEFAULT_PADDING);
+
+        let zoomed = Typography {
+            zoom: 1.5,
+            ..typography
+        };
+        assert_eq!(zoomed.scale(14), 32);
+        assert_eq!(zoomed.line_height(20), 30);
+    }
+
+    #[test]
+    fn focus_mode_centers_a_column_of_characters() {
+        let standard = Typography::default();
+        assert_eq!(standard.focus_padding(1275), 315);
+        assert_eq!(standard.focus_padding(500), DEFAULT_PADDING);
+
+        let typography: Typography =
+            toml::from_str("font_size = 20\nfocus_width = 60\nfocus_dim = true\n").unwrap();
+        assert!(typography.focus_dim);
+        assert_eq!(typography.focus_padding(1275), 300);
+    }
+
+    #[test]
+    fn zooming_in_and_out_returns_to_actual_size() {
+        let mut zoom = 1.0;
+        for _ in 0..5 {
+            zoom = zoom_step(zoom, true);
+        }
+        assert_eq!(zoom, 1.61);
+        for _ in 0..5 {
+            zoom = zoom_step(zoom, false);
+        }
+        assert_eq!(zoom, 1.0);
+        assert_eq!(zoom_step(MAX_ZOOM, true), MAX_ZOOM);
+        assert_eq!(zoom_step(MIN_ZOOM, false), MIN_ZOOM);
+    }
+
+    #[test]
+    fn links_are_recognized_in_every_palette() {
+        let link = 0x1A5FB4FF;
+        for palette in Palette::ALL {
+            assert!(is_link_color(palette.map(link)), "{palette:?}");
+        }
+        assert!(!is_link_color(0x000000FF));
+        assert!(!is_link_color(0xCCE0FFFF)); // light blue selection background
+        assert!(!is_link_color(0xCC3333FF));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::content::{ContentLoader, ContentProvider};
 use crate::fltk_draw_context::FltkDrawContext;
-use crate::fltk_structured_rich_display::FltkStructuredRichDisplay;
+use crate::fltk_structured_rich_display::{FltkStructuredRichDisplay, Highlight};
 use crate::live_share::HighlightTarget;
 use crate::markdown_converter::document_to_markdown;
 use crate::note_ui::NoteUI;
@@ -12,6 +12,7 @@ use rutle::tree_path::{DocumentPosition, PathSegment, TreePath};
 use rutle::tree_walk::LeafInfo;
 use std::any::Any;
 use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use tdoc::Document;
 
 /// Vertical breathing room, in pixels, kept above a heading when scrolling to a
@@ -80,6 +81,90 @@ impl StructuredRichUI {
         app::paste(&group);
     }
 
+    /// Load a note's `markdown`, as [`ContentLoader::set_content_from_markdown`]
+    /// does, or put back `kept`, the editor the note was last left in, which
+    /// brings back its undo history, caret and selection. Returns the editor
+    /// being replaced, with the undo history of the note being left.
+    pub fn load_note(&mut self, markdown: &str, kept: Option<Editor>) -> Editor {
+        let restored = kept.is_some();
+        self.0
+            .set_frontmatter(piki_core::frontmatter::split(markdown).0);
+        let editor = kept.unwrap_or_else(|| Editor::with_tdoc(Document::new()));
+        let left = std::mem::replace(self.0.display.borrow_mut().editor_mut(), editor);
+        if restored {
+            self.0.display.borrow_mut().set_scroll(0);
+            self.0.clear_highlights();
+            self.0.emit_paragraph_state();
+        } else {
+            self.set_content_from_markdown(markdown);
+        }
+        left
+    }
+
+    /// Replace the whole document with `markdown` as a single undoable edit.
+    /// Unlike [`ContentLoader::set_content_from_markdown`], which starts a
+    /// fresh undo history, the replacement itself can be undone — used for
+    /// reverting a note.
+    pub fn replace_content(&mut self, markdown: &str) -> bool {
+        let (frontmatter, body) = piki_core::frontmatter::split(markdown);
+        self.0.set_frontmatter(frontmatter);
+        let doc = crate::markdown_converter::markdown_to_document(body);
+        let inserted = {
+            let mut disp = self.0.display.borrow_mut();
+            let editor = disp.editor_mut();
+            editor.select_all();
+            let _ = editor.delete_selection();
+            doc.paragraphs.is_empty() || editor.insert_document(&doc).is_ok()
+        };
+        self.0.notify_change();
+        self.0.emit_paragraph_state();
+        inserted
+    }
+
+    /// The note's properties, from its frontmatter (see
+    /// [`piki_core::frontmatter::properties`]).
+    pub fn properties(&self) -> Vec<(String, String)> {
+        piki_core::frontmatter::properties(self.0.frontmatter())
+    }
+
+    /// Write `properties` into the note's frontmatter, as edited in the
+    /// properties panel, and report the change like an edit of the text.
+    pub fn set_properties(&mut self, properties: &[(String, String)]) {
+        let frontmatter = piki_core::frontmatter::with_properties(self.0.frontmatter(), properties);
+        self.0.set_frontmatter(&frontmatter);
+        self.0.notify_change();
+    }
+
+    /// Insert plain `text` at the cursor, in place of the selection if there
+    /// is one.
+    pub fn insert_text(&mut self, text: &str) -> bool {
+        let inserted = {
+            let mut disp = self.0.display.borrow_mut();
+            let editor = disp.editor_mut();
+            if editor.selection().is_some() {
+                let _ = editor.delete_selection();
+            }
+            editor.insert_text(text).is_ok()
+        };
+        self.0.notify_change();
+        inserted
+    }
+
+    /// Insert links to `(destination, text)` at the cursor, separated by
+    /// spaces, as one undoable edit.
+    pub fn insert_links(&mut self, links: &[(String, String)]) -> bool {
+        let inserted = {
+            let mut disp = self.0.display.borrow_mut();
+            let editor = disp.editor_mut();
+            links.iter().enumerate().all(|(i, (dest, text))| {
+                (i == 0 || editor.insert_text(" ").is_ok())
+                    && editor.insert_link_at_cursor(dest, text).is_ok()
+            })
+        };
+        self.0.notify_change();
+        inserted
+    }
+
     pub fn undo(&mut self) -> bool {
         let changed = {
             let mut disp = self.0.display.borrow_mut();
@@ -116,6 +201,38 @@ impl StructuredRichUI {
         self.apply_edit(|editor| editor.toggle_quote())
     }
 
+    /// Nest the block at the cursor one quote level deeper (or shallower).
+    pub fn change_quote_depth(&mut self, increase: bool) -> bool {
+        let changed = {
+            let mut disp = self.0.display.borrow_mut();
+            crate::quote_depth::change_depth(disp.editor_mut(), increase)
+        };
+        if changed {
+            self.0.notify_change();
+            self.0.emit_paragraph_state();
+        }
+        changed
+    }
+
+    /// Swap the paragraph or list item at the cursor with the one above (or
+    /// below), keeping the caret in it.
+    pub fn move_block(&mut self, up: bool) -> bool {
+        let moved = {
+            let mut disp = self.0.display.borrow_mut();
+            let editor = disp.editor_mut();
+            if up {
+                editor.move_blocks_up()
+            } else {
+                editor.move_blocks_down()
+            }
+        };
+        let moved = moved.unwrap_or(false);
+        if moved {
+            self.0.notify_change();
+        }
+        moved
+    }
+
     pub fn toggle_code_block(&mut self) -> bool {
         self.apply_edit(|editor| editor.toggle_code_block())
     }
@@ -180,15 +297,14 @@ impl StructuredRichUI {
             .map(|(_, anchor)| anchor)
     }
 
-    /// Scroll so the heading whose anchor slug equals `anchor` sits near the top
-    /// of the viewport. Returns `false` if no heading matches.
+    /// Scroll so the heading named `anchor`, by its anchor slug or its text,
+    /// sits near the top of the viewport. Returns `false` if no heading
+    /// matches.
     pub fn scroll_to_anchor(&mut self, anchor: &str) -> bool {
         let target = {
             let disp = self.0.display.borrow();
-            heading_anchor_map(disp.editor().document())
-                .into_iter()
-                .find(|(_, a)| a == anchor)
-                .map(|(idx, _)| idx)
+            let (indices, texts) = heading_blocks(disp.editor().document());
+            crate::section_link::heading_index(&texts, anchor).map(|i| indices[i])
         };
         match target {
             Some(idx) => self.scroll_to_block(idx),
@@ -196,20 +312,50 @@ impl StructuredRichUI {
         }
     }
 
+    /// Scroll to a link's `#fragment`: a heading's anchor slug, or `L12` for
+    /// line 12 of `markdown`, the note's source. Returns `false` if nothing
+    /// matches.
+    pub fn scroll_to_fragment(&mut self, fragment: &str, markdown: &str) -> bool {
+        match crate::section_link::line_fragment(fragment) {
+            Some(line) => self.scroll_to_line(markdown, line),
+            None => self.scroll_to_anchor(fragment),
+        }
+    }
+
+    /// Scroll to line `line` (1-based) of `markdown`, the note's source, with
+    /// the caret at the start of the paragraph or list item the line is part
+    /// of. Returns `false` if there is no such line.
+    pub fn scroll_to_line(&mut self, markdown: &str, line: usize) -> bool {
+        let path = leaf_for_line(markdown, line).and_then(|index| {
+            let disp = self.0.display.borrow();
+            rutle::tree_walk::enumerate_leaves(disp.editor().document())
+                .get(index)
+                .map(|leaf| leaf.path.clone())
+        });
+        match path {
+            Some(path) => self.scroll_to_position(DocumentPosition::at(path, 0)),
+            None => false,
+        }
+    }
+
     /// Scroll so top-level block `block_index` sits near the top of the viewport.
-    ///
-    /// The renderer exposes no public block→pixel mapping, so this moves the
-    /// caret into the target block and reuses the caret→pixel bridge
-    /// (`cursor_content_y`) after a layout pass. Returns `false` if the index is
-    /// out of range.
+    /// Returns `false` if the index is out of range.
     pub fn scroll_to_block(&mut self, block_index: usize) -> bool {
-        let mut ctx = FltkDrawContext::new(true, true);
-        let mut disp = self.0.display.borrow_mut();
-        if block_index >= disp.editor().document().paragraphs.len() {
+        if block_index >= self.0.display.borrow().editor().document().paragraphs.len() {
             return false;
         }
-        disp.editor_mut()
-            .set_cursor(DocumentPosition::new(block_index, 0));
+        self.scroll_to_position(DocumentPosition::new(block_index, 0))
+    }
+
+    /// Move the caret to `position` and scroll so its line sits near the top of
+    /// the viewport.
+    ///
+    /// The renderer exposes no public block→pixel mapping, so this reuses the
+    /// caret→pixel bridge (`cursor_content_y`) after a layout pass.
+    fn scroll_to_position(&mut self, position: DocumentPosition) -> bool {
+        let mut ctx = FltkDrawContext::new(true, true);
+        let mut disp = self.0.display.borrow_mut();
+        disp.editor_mut().set_cursor(position);
         // Lay out with real font metrics so `cursor_content_y` is populated.
         disp.ensure_cursor_visible(&mut ctx);
         if let Some((content_y, _line_h)) = disp.cursor_content_y(&mut ctx) {
@@ -228,6 +374,12 @@ impl StructuredRichUI {
         self.0.group.redraw();
     }
 
+    /// Turn focus mode's dimming of the other paragraphs on or off.
+    pub fn set_focus_mode(&mut self, focus_mode: bool) {
+        self.0.set_focus_mode(focus_mode);
+        self.0.group.redraw();
+    }
+
     /// Get current horizontal padding
     pub fn horizontal_padding(&self) -> i32 {
         self.0.display.borrow().horizontal_padding()
@@ -249,6 +401,16 @@ impl StructuredRichUI {
         new_state
     }
 
+    /// Toggle between edit mode and read mode, returning whether edit mode is
+    /// now on. Read mode hides the caret, so plain clicks follow links; checklist
+    /// markers can still be ticked.
+    pub fn toggle_edit_mode(&mut self) -> bool {
+        let edit_mode = !self.0.edit_mode();
+        self.0.set_edit_mode(edit_mode);
+        self.0.group.redraw();
+        edit_mode
+    }
+
     /// Resize the editor widget
     pub fn resize(&mut self, x: i32, y: i32, w: i32, h: i32) {
         self.0.group.resize(x, y, w, h);
@@ -277,6 +439,40 @@ impl StructuredRichUI {
 
     // ==================== Search Methods ====================
 
+    /// Draw the text of `highlights` on a highlight background until the next
+    /// edit or click.
+    pub fn set_highlights(&mut self, highlights: Vec<Highlight>) {
+        self.0.set_highlights(highlights);
+        self.0.group.redraw();
+    }
+
+    pub fn clear_highlights(&mut self) {
+        self.0.clear_highlights();
+        self.0.group.redraw();
+    }
+
+    /// Highlight the search `terms` (lowercase, see
+    /// [`piki_core::search::parse_terms`]) in the first block containing any
+    /// of them, and move the caret there. Returns `false` if none occurs.
+    pub fn show_first_match(&mut self, terms: &[String]) -> bool {
+        let highlights = {
+            let disp = self.0.display.borrow();
+            first_match_highlights(disp.editor().document(), terms)
+        };
+        let Some(first) = highlights.first() else {
+            return false;
+        };
+        let path = {
+            let disp = self.0.display.borrow();
+            rutle::tree_walk::enumerate_leaves(disp.editor().document())[first.block]
+                .path
+                .clone()
+        };
+        let start = first.range.start;
+        self.set_highlights(highlights);
+        self.scroll_to_position(DocumentPosition::at(path, start))
+    }
+
     /// Perform a case-insensitive search for the given term
     pub fn search(&mut self, term: &str) -> usize {
         self.0.display.borrow_mut().search(term)
@@ -439,6 +635,15 @@ fn enclosing_li_path(path: &TreePath) -> Option<TreePath> {
 /// editor classifies blocks, and delegates slug/duplicate handling to
 /// [`crate::section_link`] so generation and resolution share one algorithm.
 fn heading_anchor_map(doc: &tdoc::Document) -> Vec<(usize, String)> {
+    let (indices, texts) = heading_blocks(doc);
+    indices
+        .into_iter()
+        .zip(crate::section_link::heading_anchors(&texts))
+        .collect()
+}
+
+/// The indices of the top-level heading blocks of `doc` and their plain text.
+fn heading_blocks(doc: &tdoc::Document) -> (Vec<usize>, Vec<String>) {
     let mut indices = Vec::new();
     let mut texts = Vec::new();
     for i in 0..doc.paragraphs.len() {
@@ -451,24 +656,75 @@ fn heading_anchor_map(doc: &tdoc::Document) -> Vec<(usize, String)> {
             indices.push(i);
         }
     }
-    indices
-        .into_iter()
-        .zip(crate::section_link::heading_anchors(&texts))
-        .collect()
+    (indices, texts)
+}
+
+/// The occurrences of `terms` in the first block of `doc` that contains any,
+/// in order and without overlaps. Blocks whose text changes length when
+/// lowercased are passed over, as their matches can't be mapped back.
+fn first_match_highlights(doc: &Document, terms: &[String]) -> Vec<Highlight> {
+    for (block, leaf) in rutle::tree_walk::enumerate_leaves(doc).iter().enumerate() {
+        let text = rutle::tree_walk::leaf_plain_text(doc, &leaf.path);
+        let lower = text.to_lowercase();
+        let mut found = terms
+            .iter()
+            .filter(|term| !term.is_empty())
+            .flat_map(|term| lower.match_indices(term.as_str()))
+            .map(|(start, term)| start..start + term.len())
+            .collect::<Vec<_>>();
+        if found.is_empty() || lower.len() != text.len() {
+            continue;
+        }
+        found.sort_by_key(|range| (range.start, std::cmp::Reverse(range.end)));
+        let mut highlights: Vec<Highlight> = Vec::new();
+        for range in found {
+            if highlights
+                .last()
+                .is_none_or(|last| range.start >= last.range.end)
+            {
+                highlights.push(Highlight { block, range });
+            }
+        }
+        return highlights;
+    }
+    Vec::new()
+}
+
+/// The index, in [`enumerate_leaves`](rutle::tree_walk::enumerate_leaves)
+/// order, of the leaf that line `line` (1-based) of `markdown` ends up in:
+/// the last leaf of the document made of the lines up to it.
+fn leaf_for_line(markdown: &str, line: usize) -> Option<usize> {
+    if line == 0 || line > markdown.lines().count() {
+        return None;
+    }
+    // The frontmatter isn't part of the document; its lines go to the first
+    // leaf.
+    let (frontmatter, body) = piki_core::frontmatter::split(markdown);
+    let line = line.saturating_sub(frontmatter.lines().count()).max(1);
+    let prefix: String = body.split_inclusive('\n').take(line).collect();
+    let doc = crate::markdown_converter::markdown_to_document(&prefix);
+    rutle::tree_walk::enumerate_leaves(&doc)
+        .len()
+        .checked_sub(1)
 }
 
 impl ContentProvider for StructuredRichUI {
     fn get_content(&self) -> String {
         let disp = self.0.display.borrow();
-        document_to_markdown(disp.editor().document())
+        let body = document_to_markdown(disp.editor().document());
+        format!("{}{body}", self.0.frontmatter())
     }
 }
 
 impl ContentLoader for StructuredRichUI {
     fn set_content_from_markdown(&mut self, markdown: &str) {
+        // Frontmatter would be mangled into a rule and a heading; it is kept
+        // aside and only the rest of the page is edited.
+        let (frontmatter, body) = piki_core::frontmatter::split(markdown);
+        self.0.set_frontmatter(frontmatter);
         let mut disp = self.0.display.borrow_mut();
         // Loading a different note starts a fresh undo history (set_document resets it).
-        let mut doc = crate::markdown_converter::markdown_to_document(markdown);
+        let mut doc = crate::markdown_converter::markdown_to_document(body);
         // A brand-new note loads as an empty document (no paragraphs). rutle's
         // block-level commands (headings, lists, quotes) act on existing leaves,
         // so with nothing to convert the first Cmd-Alt-1 would silently no-op
@@ -482,6 +738,7 @@ impl ContentLoader for StructuredRichUI {
         disp.editor_mut().set_document(doc);
         disp.set_scroll(0);
         drop(disp);
+        self.0.clear_highlights();
         self.0.emit_paragraph_state();
     }
 }
@@ -492,7 +749,7 @@ impl NoteUI for StructuredRichUI {
     }
 
     fn set_readonly(&mut self, readonly: bool) {
-        self.0.display.borrow_mut().set_cursor_visible(!readonly);
+        self.0.set_readonly(readonly);
     }
 
     fn is_readonly(&self) -> bool {
@@ -542,6 +799,14 @@ impl NoteUI for StructuredRichUI {
         self.0.set_paragraph_callback(Some(f));
     }
 
+    fn on_files_dropped(&mut self, f: Box<dyn FnMut(Vec<PathBuf>) + 'static>) {
+        self.0.set_drop_callback(Some(f));
+    }
+
+    fn on_readonly_checkbox(&mut self, f: Box<dyn FnMut(Vec<String>) + 'static>) {
+        self.0.set_checkbox_callback(Some(f));
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -779,6 +1044,34 @@ mod tests {
         assert_eq!(editor.current_block_type(), BlockType::Paragraph);
     }
 
+    #[test]
+    fn highlights_the_terms_in_the_first_block_with_any() {
+        let doc = crate::markdown_converter::markdown_to_document(
+            "Nothing here\n\nThe Budget and budgets\n\nbudget again\n",
+        );
+        let terms = piki_core::search::parse_terms("budget gets");
+        let ranges = first_match_highlights(&doc, &terms)
+            .into_iter()
+            .map(|h| (h.block, h.range))
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![(1, 4..10), (1, 15..21)]);
+        assert!(first_match_highlights(&doc, &["absent".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn finds_the_leaf_of_a_source_line() {
+        let md = "# Title\n\nIntro\nwrapped\n\n- [ ] one\n- [ ] two\n";
+        assert_eq!(leaf_for_line(md, 1), Some(0));
+        assert_eq!(leaf_for_line(md, 4), Some(1));
+        assert_eq!(leaf_for_line(md, 7), Some(3));
+        assert_eq!(leaf_for_line(md, 8), None);
+
+        let with_frontmatter = format!("---\ntitle: T\n---\n\n{md}");
+        assert_eq!(leaf_for_line(&with_frontmatter, 2), Some(0));
+        assert_eq!(leaf_for_line(&with_frontmatter, 8), Some(1));
+        assert_eq!(leaf_for_line(&with_frontmatter, 11), Some(3));
+    }
+
     /// With the seeded empty paragraph a fresh note carries a leaf, so the very
     /// first Cmd-Alt-1 converts it to a heading without needing a keystroke.
     #[test]
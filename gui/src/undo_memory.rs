@@ -0,0 +1,111 @@
+//! In-memory undo histories for recently left notes.
+//!
+//! Loading a note gives the editor a fresh undo history, so leaving a note and
+//! coming back to it — via a link, the picker or back/forward — would lose the
+//! chance to undo what was typed there before. Instead, the editor a note was
+//! edited in is kept when leaving it and put back on return, undo history,
+//! caret and selection included. Like [`crate::position_memory`], this only
+//! lives for the session.
+
+use piki_core::PageId;
+use piki_gui::markdown_converter::{document_to_markdown, markdown_to_document};
+use rutle::editor::Editor;
+
+/// How many notes' editors are retained.
+const CAPACITY: usize = 10;
+
+#[derive(Default)]
+pub struct UndoMemory {
+    /// (note name, the editor it was left in), most-recently-left first.
+    entries: Vec<(PageId, Editor)>,
+}
+
+impl UndoMemory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep `editor`, which `note` was just left in, promoting the note to
+    /// most-recent and evicting the least-recently-left note once more than
+    /// [`CAPACITY`] are kept.
+    pub fn remember(&mut self, note: &str, editor: Editor) {
+        self.entries.retain(|(name, _)| name != note);
+        self.entries.insert(0, (PageId::new(note), editor));
+        self.entries.truncate(CAPACITY);
+    }
+
+    /// The editor `note` was left in, no longer kept afterwards — if it still
+    /// holds `markdown`, the note as it is being loaded. A note changed in the
+    /// meantime (on disk, by another program) starts over instead, as the
+    /// old history wouldn't fit it.
+    pub fn take(&mut self, note: &str, markdown: &str) -> Option<Editor> {
+        let index = self.entries.iter().position(|(name, _)| name == note)?;
+        let (_, editor) = self.entries.remove(index);
+        let body = piki_core::frontmatter::split(markdown).1;
+        let loaded = document_to_markdown(&markdown_to_document(body));
+        (document_to_markdown(editor.document()) == loaded).then_some(editor)
+    }
+
+    /// Rename a kept note in place (used when a note is renamed). No-op if
+    /// `old` is not kept.
+    pub fn rename(&mut self, old: &str, new: &str) {
+        if let Some((name, _)) = self.entries.iter_mut().find(|(name, _)| name == old) {
+            *name = PageId::new(new);
+        }
+    }
+
+    /// Stop keeping `note`'s editor (used when a note is deleted). No-op if it
+    /// is not kept.
+    pub fn remove(&mut self, note: &str) {
+        self.entries.retain(|(name, _)| name != note);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(markdown: &str) -> Editor {
+        Editor::with_tdoc(markdown_to_document(markdown))
+    }
+
+    #[test]
+    fn hands_back_the_editor_of_an_unchanged_note() {
+        let mut m = UndoMemory::new();
+        m.remember("a", editor("Hello\n"));
+        assert!(m.take("b", "Hello\n").is_none());
+        assert!(m.take("a", "Hello\n").is_some());
+        // Taken once, it is gone.
+        assert!(m.take("a", "Hello\n").is_none());
+    }
+
+    #[test]
+    fn drops_the_editor_of_a_changed_note() {
+        let mut m = UndoMemory::new();
+        m.remember("a", editor("Hello\n"));
+        assert!(m.take("a", "Changed on disk\n").is_none());
+        assert!(m.take("a", "Hello\n").is_none());
+    }
+
+    #[test]
+    fn evicts_least_recently_left_beyond_capacity() {
+        let mut m = UndoMemory::new();
+        for i in 0..=CAPACITY {
+            m.remember(&format!("p{i}"), editor("x\n"));
+        }
+        assert!(m.take("p0", "x\n").is_none());
+        assert!(m.take("p1", "x\n").is_some());
+    }
+
+    #[test]
+    fn follows_renames_and_deletions() {
+        let mut m = UndoMemory::new();
+        m.remember("old", editor("x\n"));
+        m.remember("gone", editor("y\n"));
+        m.rename("old", "new");
+        m.remove("gone");
+        assert!(m.take("old", "x\n").is_none());
+        assert!(m.take("new", "x\n").is_some());
+        assert!(m.take("gone", "y\n").is_none());
+    }
+}
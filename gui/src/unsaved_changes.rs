@@ -0,0 +1,157 @@
+//! "Show Unsaved Changes" dialog: a word-level diff between the editor and the
+//! note's file on disk, with a way to throw the edits away.
+//!
+//! Removed words are shown in red, added words in bold green, everything else
+//! as is. "Revert to Saved" replaces the editor content with the file.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use fltk::{
+    app, button, dialog,
+    enums::{Align, Color, Event, Font, Key},
+    frame,
+    prelude::*,
+    text, window,
+};
+use piki_core::diff::{self, Change};
+use piki_gui::note_ui::NoteUI;
+
+use crate::autosave::AutoSaveState;
+use crate::statusbar::StatusBar;
+
+const DIALOG_W: i32 = 640;
+const DIALOG_H: i32 = 460;
+
+/// Show what changed in the open note since it was last saved, centered over
+/// `parent`.
+pub fn show_unsaved_changes(
+    app_state: Rc<RefCell<super::AppState>>,
+    autosave_state: Rc<RefCell<AutoSaveState>>,
+    active_editor: Rc<RefCell<Rc<RefCell<dyn NoteUI>>>>,
+    statusbar: Rc<RefCell<StatusBar>>,
+    parent: &window::Window,
+) {
+    let note = app_state.borrow().current_note.clone();
+    if note.starts_with('!') {
+        dialog::alert_default("Generated notes are never saved.");
+        return;
+    }
+
+    // A note that doesn't exist yet compares against an empty file.
+    let saved = app_state
+        .borrow()
+        .store
+        .load(&note)
+        .map(|doc| doc.content)
+        .unwrap_or_default();
+    let current = active_editor.borrow().borrow().get_content();
+    let changes = diff::diff_words(&saved, &current);
+    if !diff::has_changes(&changes) {
+        dialog::message_default("There are no unsaved changes.");
+        return;
+    }
+
+    let x = parent.x() + (parent.w() - DIALOG_W) / 2;
+    let y = parent.y() + (parent.h() - DIALOG_H) / 2;
+    let mut win = window::Window::new(
+        x.max(0),
+        y.max(0),
+        DIALOG_W,
+        DIALOG_H,
+        Some("Unsaved Changes"),
+    );
+    win.make_modal(true);
+    win.begin();
+
+    let (diff_text, styles) = styled_diff(&changes);
+    let mut buf = text::TextBuffer::default();
+    buf.set_text(&diff_text);
+    let mut style_buf = text::TextBuffer::default();
+    style_buf.set_text(&styles);
+    let mut display = text::TextDisplay::new(10, 10, DIALOG_W - 20, DIALOG_H - 60, None);
+    display.set_buffer(buf);
+    display.set_highlight_data(
+        style_buf,
+        vec![
+            text::StyleTableEntry {
+                color: Color::Foreground,
+                font: Font::Helvetica,
+                size: 14,
+            },
+            text::StyleTableEntry {
+                color: Color::from_rgb(0xc0, 0x20, 0x20),
+                font: Font::HelveticaItalic,
+                size: 14,
+            },
+            text::StyleTableEntry {
+                color: Color::from_rgb(0x10, 0x80, 0x30),
+                font: Font::HelveticaBold,
+                size: 14,
+            },
+        ],
+    );
+    display.wrap_mode(text::WrapMode::AtBounds, 0);
+
+    let (added, removed) = diff::word_counts(&changes);
+    let mut info = frame::Frame::new(10, DIALOG_H - 40, DIALOG_W - 280, 30, None);
+    info.set_align(Align::Inside | Align::Left);
+    info.set_label(&format!("{added} words added, {removed} removed"));
+
+    let mut revert_btn = button::Button::new(
+        DIALOG_W - 260,
+        DIALOG_H - 40,
+        160,
+        30,
+        Some("Revert to Saved"),
+    );
+    let mut close_btn = button::Button::new(DIALOG_W - 90, DIALOG_H - 40, 80, 30, Some("Close"));
+
+    win.end();
+
+    {
+        let mut win = win.clone();
+        revert_btn.set_callback(move |_| {
+            super::revert_to_saved(&app_state, &autosave_state, &active_editor, &statusbar);
+            win.hide();
+            app::redraw();
+        });
+    }
+
+    {
+        let mut win = win.clone();
+        close_btn.set_callback(move |_| win.hide());
+    }
+
+    {
+        let mut close_btn = close_btn.clone();
+        win.handle(move |_, ev| {
+            if ev == Event::KeyDown && app::event_key() == Key::Escape {
+                close_btn.do_callback();
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    win.show();
+    let _ = close_btn.take_focus();
+}
+
+/// The diff as one text plus its style buffer: `A` unchanged, `B` removed,
+/// `C` added — one style byte per text byte.
+fn styled_diff(changes: &[Change]) -> (String, String) {
+    let mut text = String::new();
+    let mut styles = String::new();
+    for change in changes {
+        let (piece, style) = match change {
+            Change::Same(t) => (t, 'A'),
+            Change::Removed(t) => (t, 'B'),
+            Change::Added(t) => (t, 'C'),
+        };
+        text.push_str(piece);
+        styles.extend(std::iter::repeat_n(style, piece.len()));
+    }
+    (text, styles)
+}
@@ -0,0 +1,147 @@
+//! Settings that belong to a wiki rather than to the app: which page to start
+//! on, whether to pick up where the last session left off, whether to type
+//! smart punctuation, and how notes are formatted on save.
+//!
+//! They live in `.piki.toml` at the top of the wiki directory, so they travel
+//! with the notes (and their git repository) from machine to machine:
+//!
+//! ```toml
+//! start_page = "!index"
+//! reopen_last_session = true
+//! smart_punctuation = true
+//!
+//! [format]
+//! canonical = true
+//! wrap = 72
+//! ```
+
+use piki_core::format::CanonicalFormat;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const FILE_NAME: &str = ".piki.toml";
+
+/// The start page of wikis that don't configure one.
+pub const DEFAULT_START_PAGE: &str = "frontpage";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct WikiSettings {
+    /// The home page: opened on startup and by Note → Go Home. A note name or
+    /// a plugin page such as `!index`.
+    #[serde(default)]
+    start_page: Option<String>,
+    /// Start on the note that was open last instead of the start page.
+    #[serde(default)]
+    pub reopen_last_session: bool,
+    /// Turn straight quotes, `--` and `...` into typographic ones while
+    /// typing (see `piki_gui::smart_punctuation`).
+    #[serde(default)]
+    pub smart_punctuation: bool,
+    #[serde(default)]
+    pub format: FormatSettings,
+}
+
+/// The `[format]` table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct FormatSettings {
+    /// Rewrite notes in the canonical style when saving them, instead of
+    /// keeping each file's own formatting.
+    #[serde(default)]
+    canonical: bool,
+    /// Hard-wrap paragraphs at this column in the canonical style.
+    #[serde(default)]
+    wrap: Option<usize>,
+}
+
+impl FormatSettings {
+    /// The style to save notes in, if the wiki asks for one.
+    pub fn canonical(&self) -> Option<CanonicalFormat> {
+        self.canonical.then_some(CanonicalFormat {
+            wrap: self.wrap.filter(|&column| column > 0),
+        })
+    }
+}
+
+impl WikiSettings {
+    /// Load the settings of the wiki in `dir`. A missing file gives the
+    /// defaults; a broken one is reported and gives the defaults, too.
+    pub fn load(dir: &Path) -> Self {
+        let path = Self::path(dir);
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        Self::parse(&contents).unwrap_or_else(|err| {
+            eprintln!("Failed to parse {}: {err}", path.display());
+            Self::default()
+        })
+    }
+
+    pub fn path(dir: &Path) -> PathBuf {
+        dir.join(FILE_NAME)
+    }
+
+    fn parse(contents: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    /// The note to open on startup and when going home; `default` (from
+    /// `~/.pikirc`) if the wiki doesn't name one.
+    pub fn start_page<'a>(&'a self, default: Option<&'a str>) -> &'a str {
+        [self.start_page.as_deref(), default]
+            .into_iter()
+            .flatten()
+            .map(str::trim)
+            .find(|page| !page.is_empty())
+            .map_or(DEFAULT_START_PAGE, |page| {
+                page.strip_suffix(".md").unwrap_or(page)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_page_defaults_to_frontpage() {
+        assert_eq!(WikiSettings::default().start_page(None), "frontpage");
+        let empty = WikiSettings::parse("start_page = \"\"\n").unwrap();
+        assert_eq!(empty.start_page(None), "frontpage");
+        assert!(!empty.reopen_last_session);
+        assert!(!empty.smart_punctuation);
+        assert_eq!(empty.format.canonical(), None);
+    }
+
+    #[test]
+    fn parses_format() {
+        let settings = WikiSettings::parse("[format]\ncanonical = true\nwrap = 72\n").unwrap();
+        assert_eq!(
+            settings.format.canonical(),
+            Some(CanonicalFormat { wrap: Some(72) })
+        );
+        let wrap_only = WikiSettings::parse("[format]\nwrap = 72\n").unwrap();
+        assert_eq!(wrap_only.format.canonical(), None);
+    }
+
+    #[test]
+    fn parses_start_page_and_session() {
+        let settings =
+            WikiSettings::parse("start_page = \"!index\"\nreopen_last_session = true\n").unwrap();
+        assert_eq!(settings.start_page(None), "!index");
+        assert!(settings.reopen_last_session);
+        assert!(
+            WikiSettings::parse("smart_punctuation = true\n")
+                .unwrap()
+                .smart_punctuation
+        );
+
+        let file = WikiSettings::parse("start_page = \"projects/home.md\"\n").unwrap();
+        assert_eq!(file.start_page(None), "projects/home");
+        assert_eq!(file.start_page(Some("journal")), "projects/home");
+        assert_eq!(
+            WikiSettings::default().start_page(Some(" journal.md ")),
+            "journal"
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     fs,
     io::{self},
     path::{Path, PathBuf},
@@ -10,6 +11,7 @@ const QUALIFIER: &str = "net.roblillack";
 const ORGANIZATION: &str = "Piki";
 const APPLICATION: &str = "piki-gui";
 const STATE_FILE_NAME: &str = "window_state.toml";
+const APPEARANCE_FILE_NAME: &str = "appearance.toml";
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct WindowGeometry {
@@ -20,6 +22,34 @@ pub struct WindowGeometry {
     /// Whether fullscreen mode is active
     #[serde(default)]
     pub fullscreen: bool,
+    /// View → Zoom of each wiki that isn't at 100%, by its directory.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub zoom: BTreeMap<String, f32>,
+}
+
+impl WindowGeometry {
+    /// The zoom of the wiki in `wiki_dir`.
+    pub fn zoom_for(&self, wiki_dir: &Path) -> f32 {
+        self.zoom.get(&wiki_key(wiki_dir)).copied().unwrap_or(1.0)
+    }
+
+    /// Remember `zoom` for the wiki in `wiki_dir`.
+    pub fn set_zoom_for(&mut self, wiki_dir: &Path, zoom: f32) {
+        if zoom == 1.0 {
+            self.zoom.remove(&wiki_key(wiki_dir));
+        } else {
+            self.zoom.insert(wiki_key(wiki_dir), zoom);
+        }
+    }
+}
+
+/// The (canonical) directory of a wiki, to remember its settings by.
+fn wiki_key(wiki_dir: &Path) -> String {
+    wiki_dir
+        .canonicalize()
+        .unwrap_or_else(|_| wiki_dir.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
 }
 
 /// Path to a file named `name` inside the application's local data directory.
@@ -34,11 +64,28 @@ pub fn state_file_path() -> Option<PathBuf> {
     data_file(STATE_FILE_NAME)
 }
 
+/// Path to the saved palette and link styling (see `piki_gui::theme`).
+pub fn appearance_file() -> Option<PathBuf> {
+    data_file(APPEARANCE_FILE_NAME)
+}
+
 /// Path to the note-picker recency store for a specific wiki directory.
 ///
 /// Recency is scoped per wiki: the filename embeds a hash of the (canonical)
 /// wiki path so opening notes in one wiki never reorders another wiki's picker.
 pub fn recent_notes_file(wiki_dir: &Path) -> Option<PathBuf> {
+    wiki_data_file("recent_notes", wiki_dir)
+}
+
+/// Path to the back/forward history of a specific wiki directory, scoped per
+/// wiki like [`recent_notes_file`].
+pub fn history_file(wiki_dir: &Path) -> Option<PathBuf> {
+    wiki_data_file("history", wiki_dir)
+}
+
+/// Path to the data file `prefix_<hash>.toml` of the wiki in `wiki_dir`, the
+/// hash being that of its (canonical) path.
+fn wiki_data_file(prefix: &str, wiki_dir: &Path) -> Option<PathBuf> {
     use std::hash::{Hash, Hasher};
 
     let canonical = wiki_dir
@@ -46,7 +93,7 @@ pub fn recent_notes_file(wiki_dir: &Path) -> Option<PathBuf> {
         .unwrap_or_else(|_| wiki_dir.to_path_buf());
     let mut hasher = std::collections::hash_map::DefaultHasher::new();
     canonical.hash(&mut hasher);
-    data_file(&format!("recent_notes_{:016x}.toml", hasher.finish()))
+    data_file(&format!("{prefix}_{:016x}.toml", hasher.finish()))
 }
 
 pub fn load_state(path: &Path) -> Option<WindowGeometry> {
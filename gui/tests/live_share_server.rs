@@ -7,6 +7,7 @@ use std::io::{Read, Write};
 use std::net::TcpStream;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use piki_core::redact::RedactionRules;
 use piki_gui::live_share::LiveShare;
 
 /// Minimal HTTP/1.0 GET. Returns `(head, body)` split on the blank line. Using
@@ -40,7 +41,13 @@ fn serves_current_note_with_rewritten_links_and_reload_script() {
     fs::write(dir.join("other.md"), "# Other\n\nBody.\n").unwrap();
 
     let markdown = "# Frontpage\n\nSee [other](other) and [site](https://example.com).\n";
-    let share = LiveShare::start(dir.clone(), "frontpage".into(), markdown.into()).unwrap();
+    let share = LiveShare::start(
+        dir.clone(),
+        RedactionRules::default(),
+        "frontpage".into(),
+        markdown.into(),
+    )
+    .unwrap();
     let port = share.port();
 
     let (head, body) = http_get(port, "/frontpage");
@@ -66,7 +73,13 @@ fn serves_current_note_with_rewritten_links_and_reload_script() {
 #[test]
 fn root_redirects_to_current_note() {
     let dir = unique_dir("root");
-    let share = LiveShare::start(dir.clone(), "my/shared/note".into(), "hi".into()).unwrap();
+    let share = LiveShare::start(
+        dir.clone(),
+        RedactionRules::default(),
+        "my/shared/note".into(),
+        "hi".into(),
+    )
+    .unwrap();
     let (head, _) = http_get(share.port(), "/");
     assert!(head.contains(" 302"), "{head}");
     assert!(head.contains("Location: /my/shared/note"), "{head}");
@@ -77,13 +90,23 @@ fn root_redirects_to_current_note() {
 fn serves_other_notes_from_disk_and_404s_unknown() {
     let dir = unique_dir("disk");
     fs::write(dir.join("other.md"), "# Other note\n").unwrap();
-    let share = LiveShare::start(dir.clone(), "frontpage".into(), "hi".into()).unwrap();
+    fs::write(dir.join("diary.md"), "# Dear diary #diary\n").unwrap();
+    let rules = RedactionRules {
+        exclude_tags: vec!["diary".into()],
+        ..RedactionRules::default()
+    };
+    let share = LiveShare::start(dir.clone(), rules, "frontpage".into(), "hi".into()).unwrap();
     let port = share.port();
 
     let (head, body) = http_get(port, "/other");
     assert!(head.contains(" 200"), "{head}");
     assert!(body.contains("Other note"), "{body}");
 
+    // Notes with an excluded tag are never served.
+    let (head, body) = http_get(port, "/diary");
+    assert!(head.contains(" 404"), "{head}");
+    assert!(!body.contains("Dear diary"), "{body}");
+
     let (head, _) = http_get(port, "/does-not-exist");
     assert!(head.contains(" 404"), "{head}");
 
@@ -97,7 +120,13 @@ fn rejects_path_traversal() {
     let parent = dir.parent().unwrap();
     let secret = parent.join("piki-secret.md");
     fs::write(&secret, "TOP SECRET").unwrap();
-    let share = LiveShare::start(dir.clone(), "frontpage".into(), "hi".into()).unwrap();
+    let share = LiveShare::start(
+        dir.clone(),
+        RedactionRules::default(),
+        "frontpage".into(),
+        "hi".into(),
+    )
+    .unwrap();
 
     let (head, body) = http_get(share.port(), "/..%2Fpiki-secret");
     assert!(head.contains(" 404"), "{head}");
@@ -110,7 +139,13 @@ fn rejects_path_traversal() {
 #[test]
 fn version_endpoint_tracks_live_updates() {
     let dir = unique_dir("version");
-    let share = LiveShare::start(dir.clone(), "frontpage".into(), "v1".into()).unwrap();
+    let share = LiveShare::start(
+        dir.clone(),
+        RedactionRules::default(),
+        "frontpage".into(),
+        "v1".into(),
+    )
+    .unwrap();
     let port = share.port();
 
     let (_, body) = http_get(port, "/__piki/version?note=frontpage");
@@ -140,6 +175,7 @@ fn selection_highlight_is_served_and_cleared() {
     fs::write(dir.join("other.md"), "para 0\n\npara 1\n").unwrap();
     let share = LiveShare::start(
         dir.clone(),
+        RedactionRules::default(),
         "frontpage".into(),
         "para 0\n\npara 1\n\npara 2\n".into(),
     )